@@ -4,8 +4,8 @@ use std::time::Duration;
 
 use anyhow::anyhow;
 use aws_lambda_events::apigw::{ApiGatewayProxyRequest, ApiGatewayProxyResponse};
-use aws_sdk_s3::presigning::PresigningConfig;
 use lambda_runtime::LambdaEvent;
+use s3_code_examples::presign::{PresignPolicy, Presigner};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
@@ -27,16 +27,20 @@ impl std::fmt::Display for Url {
     }
 }
 
+/// Upload URLs last long enough for the browser to send one photo.
+const UPLOAD_EXPIRY: Duration = Duration::from_secs(5 * 60);
+
 async fn make_put_url(common: &Common, file_name: String) -> Result<Url, anyhow::Error> {
     let uuid = uuid::Uuid::new_v4();
     let key_name = format!("{uuid}/{file_name}");
-    let put_object = common
-        .s3_client()
-        .put_object()
-        .bucket(common.storage_bucket())
-        .key(key_name)
-        .content_type("image/jpeg")
-        .presigned(PresigningConfig::expires_in(Duration::from_secs(5 * 60))?)
+    let presigner = Presigner::new(common.s3_client().clone(), common.storage_bucket())
+        .with_policy(
+            PresignPolicy::new()
+                .with_max_expiry(UPLOAD_EXPIRY)
+                .with_content_type("image/jpeg"),
+        );
+    let put_object = presigner
+        .presign_put(&key_name, UPLOAD_EXPIRY, Some("image/jpeg"), None)
         .await?;
     Ok(Url {
         url: put_object.uri().to_string(),
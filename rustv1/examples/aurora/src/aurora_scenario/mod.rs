@@ -10,6 +10,8 @@ use aws_sdk_rds::{
     operation::create_db_cluster_parameter_group::CreateDbClusterParameterGroupOutput,
     types::{DbCluster, DbClusterParameterGroup, DbClusterSnapshot, DbInstance, Parameter},
 };
+use sdk_examples_resource_ledger::{Ledger, Resource, ResourceKind};
+pub use sdk_examples_scenario_error::ScenarioError;
 use sdk_examples_test_utils::waiter::Waiter;
 use tracing::{info, trace, warn};
 
@@ -25,65 +27,6 @@ static FILTER_PARAMETER_NAMES: Set<&'static str> = phf_set! {
     "auto_increment_increment",
 };
 
-#[derive(Debug, PartialEq, Eq)]
-struct MetadataError {
-    message: Option<String>,
-    code: Option<String>,
-}
-
-impl MetadataError {
-    fn from(err: &dyn ProvideErrorMetadata) -> Self {
-        MetadataError {
-            message: err.message().map(String::from),
-            code: err.code().map(String::from),
-        }
-    }
-}
-
-impl Display for MetadataError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let display = match (&self.message, &self.code) {
-            (None, None) => "Unknown".to_string(),
-            (None, Some(code)) => format!("({code})"),
-            (Some(message), None) => message.to_string(),
-            (Some(message), Some(code)) => format!("{message} ({code})"),
-        };
-        write!(f, "{display}")
-    }
-}
-
-#[derive(Debug, PartialEq, Eq)]
-pub struct ScenarioError {
-    message: String,
-    context: Option<MetadataError>,
-}
-
-impl ScenarioError {
-    pub fn with(message: impl Into<String>) -> Self {
-        ScenarioError {
-            message: message.into(),
-            context: None,
-        }
-    }
-
-    pub fn new(message: impl Into<String>, err: &dyn ProvideErrorMetadata) -> Self {
-        ScenarioError {
-            message: message.into(),
-            context: Some(MetadataError::from(err)),
-        }
-    }
-}
-
-impl std::error::Error for ScenarioError {}
-impl Display for ScenarioError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self.context {
-            Some(c) => write!(f, "{}: {}", self.message, c),
-            None => write!(f, "{}", self.message),
-        }
-    }
-}
-
 // Parse the ParameterName, Description, and AllowedValues values and display them.
 #[derive(Debug)]
 pub struct AuroraScenarioParameter {
@@ -120,8 +63,10 @@ pub struct AuroraScenario {
     db_cluster_parameter_group: Option<DbClusterParameterGroup>,
     db_cluster_identifier: Option<String>,
     db_instance_identifier: Option<String>,
+    db_cluster_snapshot_identifier: Option<String>,
     username: Option<String>,
     password: Option<SecretString>,
+    ledger: Ledger,
 }
 
 impl AuroraScenario {
@@ -134,11 +79,19 @@ impl AuroraScenario {
             db_cluster_parameter_group: None,
             db_cluster_identifier: None,
             db_instance_identifier: None,
+            db_cluster_snapshot_identifier: None,
             username: None,
             password: None,
+            ledger: Ledger::disabled(),
         }
     }
 
+    /// Record the resources the scenario creates, so an interrupted run can be cleaned up.
+    pub fn with_ledger(mut self, ledger: Ledger) -> Self {
+        self.ledger = ledger;
+        self
+    }
+
     // snippet-start:[rust.aurora.get_engines.usage]
     // Get available engine families for Aurora MySql. rds.DescribeDbEngineVersions(Engine='aurora-mysql') and build a set of the 'DBParameterGroupFamily' field values. I get {aurora-mysql8.0, aurora-mysql5.7}.
     pub async fn get_engines(&self) -> Result<HashMap<String, Vec<String>>, ScenarioError> {
@@ -146,10 +99,10 @@ impl AuroraScenario {
         trace!(versions=?describe_db_engine_versions, "full list of versions");
 
         if let Err(err) = describe_db_engine_versions {
-            return Err(ScenarioError::new(
-                "Failed to retrieve DB Engine Versions",
-                &err,
-            ));
+            return Err(
+                ScenarioError::from_sdk("Failed to retrieve DB Engine Versions", &err)
+                    .operation("DescribeDBEngineVersions"),
+            );
         };
 
         let version_count = describe_db_engine_versions
@@ -197,7 +150,10 @@ impl AuroraScenario {
                     .map(|o| o.db_instance_class().unwrap_or_default().to_string())
                     .collect::<Vec<String>>()
             })
-            .map_err(|err| ScenarioError::new("Could not get available instance classes", &err))
+            .map_err(|err| {
+                ScenarioError::from_sdk("Could not get available instance classes", &err)
+                    .operation("DescribeOrderableDBInstanceOptions")
+            })
     }
     // snippet-end:[rust.aurora.get_instance_classes.usage]
 
@@ -228,14 +184,19 @@ impl AuroraScenario {
                 if error.code() == Some("DBParameterGroupAlreadyExists") {
                     info!("Cluster Parameter Group already exists, nothing to do");
                 } else {
-                    return Err(ScenarioError::new(
+                    return Err(ScenarioError::from_sdk(
                         "Could not create Cluster Parameter Group",
                         &error,
-                    ));
+                    )
+                    .operation("CreateDBClusterParameterGroup"));
                 }
             }
             _ => {
                 info!("Created Cluster Parameter Group");
+                self.ledger.created(Resource::new(
+                    ResourceKind::RdsDbClusterParameterGroup,
+                    DB_CLUSTER_PARAMETER_GROUP_NAME,
+                ));
             }
         }
 
@@ -272,7 +233,8 @@ impl AuroraScenario {
             )
             .await;
         if let Err(err) = describe_db_clusters_output {
-            return Err(ScenarioError::new("Failed to get cluster", &err));
+            return Err(ScenarioError::from_sdk("Failed to get cluster", &err)
+                .operation("DescribeDBClusters"));
         }
 
         let db_cluster = describe_db_clusters_output
@@ -295,10 +257,11 @@ impl AuroraScenario {
             .await;
 
         if let Err(err) = parameters_output {
-            return Err(ScenarioError::new(
+            return Err(ScenarioError::from_sdk(
                 format!("Failed to retrieve parameters for {DB_CLUSTER_PARAMETER_GROUP_NAME}"),
                 &err,
-            ));
+            )
+            .operation("DescribeDBClusterParameters"));
         }
 
         let parameters = parameters_output
@@ -340,10 +303,11 @@ impl AuroraScenario {
             .await;
 
         if let Err(error) = modify_db_cluster_parameter_group {
-            return Err(ScenarioError::new(
+            return Err(ScenarioError::from_sdk(
                 "Failed to modify cluster parameter group",
                 &error,
-            ));
+            )
+            .operation("ModifyDBClusterParameterGroup"));
         }
 
         Ok(())
@@ -378,10 +342,11 @@ impl AuroraScenario {
             )
             .await;
         if let Err(err) = create_db_cluster {
-            return Err(ScenarioError::new(
+            return Err(ScenarioError::from_sdk(
                 "Failed to create DB Cluster with cluster group",
                 &err,
-            ));
+            )
+            .operation("CreateDBCluster"));
         }
 
         self.db_cluster_identifier = create_db_cluster
@@ -389,9 +354,15 @@ impl AuroraScenario {
             .db_cluster
             .and_then(|c| c.db_cluster_identifier);
 
-        if self.db_cluster_identifier.is_none() {
+        let Some(db_cluster_identifier) = self.db_cluster_identifier.as_deref() else {
             return Err(ScenarioError::with("Created DB Cluster missing Identifier"));
-        }
+        };
+        self.ledger.created(
+            Resource::new(ResourceKind::RdsDbCluster, db_cluster_identifier).depends_on(
+                ResourceKind::RdsDbClusterParameterGroup,
+                DB_CLUSTER_PARAMETER_GROUP_NAME,
+            ),
+        );
 
         info!(
             "Started a db cluster: {}",
@@ -410,16 +381,25 @@ impl AuroraScenario {
             )
             .await;
         if let Err(err) = create_db_instance {
-            return Err(ScenarioError::new(
-                "Failed to create Instance in DB Cluster",
-                &err,
-            ));
+            return Err(
+                ScenarioError::from_sdk("Failed to create Instance in DB Cluster", &err)
+                    .operation("CreateDBInstance"),
+            );
         }
 
         self.db_instance_identifier = create_db_instance
             .unwrap()
             .db_instance
             .and_then(|i| i.db_instance_identifier);
+        if let (Some(instance), Some(cluster)) = (
+            self.db_instance_identifier.as_deref(),
+            self.db_cluster_identifier.as_deref(),
+        ) {
+            self.ledger.created(
+                Resource::new(ResourceKind::RdsDbInstance, instance)
+                    .depends_on(ResourceKind::RdsDbCluster, cluster),
+            );
+        }
 
         // Cluster creation can take up to 20 minutes to become available
         let cluster_max_wait = Duration::from_secs(20 * 60);
@@ -448,10 +428,10 @@ impl AuroraScenario {
                 )
                 .await;
             if let Err(err) = instance {
-                return Err(ScenarioError::new(
-                    "Failed to find instance for cluster",
-                    &err,
-                ));
+                return Err(
+                    ScenarioError::from_sdk("Failed to find instance for cluster", &err)
+                        .operation("DescribeDBInstances"),
+                );
             }
 
             let instances_available = instance
@@ -470,10 +450,10 @@ impl AuroraScenario {
                 .await;
 
             if let Err(err) = endpoints {
-                return Err(ScenarioError::new(
-                    "Failed to find endpoint for cluster",
-                    &err,
-                ));
+                return Err(
+                    ScenarioError::from_sdk("Failed to find endpoint for cluster", &err)
+                        .operation("DescribeDBClusterEndpoints"),
+                );
             }
 
             let endpoints_available = endpoints
@@ -494,7 +474,7 @@ impl AuroraScenario {
     // snippet-start:[rust.aurora.snapshot.usage]
     // Create a snapshot of the DB cluster. rds.CreateDbClusterSnapshot.
     // Wait for the snapshot to create. rds.DescribeDbClusterSnapshots until Status == 'available'.
-    pub async fn snapshot(&self, name: &str) -> Result<DbClusterSnapshot, ScenarioError> {
+    pub async fn snapshot(&mut self, name: &str) -> Result<DbClusterSnapshot, ScenarioError> {
         let id = self.db_cluster_identifier.as_deref().unwrap_or_default();
         let snapshot = self
             .rds
@@ -502,10 +482,20 @@ impl AuroraScenario {
             .await;
         match snapshot {
             Ok(output) => match output.db_cluster_snapshot {
-                Some(snapshot) => Ok(snapshot),
+                Some(snapshot) => {
+                    if let Some(snapshot_id) = snapshot.db_cluster_snapshot_identifier() {
+                        self.db_cluster_snapshot_identifier = Some(snapshot_id.to_string());
+                        self.ledger.created(Resource::new(
+                            ResourceKind::RdsDbClusterSnapshot,
+                            snapshot_id,
+                        ));
+                    }
+                    Ok(snapshot)
+                }
                 None => Err(ScenarioError::with("Missing Snapshot")),
             },
-            Err(err) => Err(ScenarioError::new("Failed to create snapshot", &err)),
+            Err(err) => Err(ScenarioError::from_sdk("Failed to create snapshot", &err)
+                .operation("CreateDBClusterSnapshot")),
         }
     }
     // snippet-end:[rust.aurora.snapshot.usage]
@@ -529,17 +519,21 @@ impl AuroraScenario {
                 .as_deref()
                 .unwrap_or("Missing Instance Identifier");
             let message = format!("failed to delete db instance {identifier}");
-            clean_up_errors.push(ScenarioError::new(message, &err));
+            clean_up_errors
+                .push(ScenarioError::from_sdk(message, &err).operation("DeleteDBInstance"));
         } else {
             // Wait for the instance to delete
             let waiter = Waiter::default();
             while waiter.sleep().await.is_ok() {
                 let describe_db_instances = self.rds.describe_db_instances().await;
                 if let Err(err) = describe_db_instances {
-                    clean_up_errors.push(ScenarioError::new(
-                        "Failed to check instance state during deletion",
-                        &err,
-                    ));
+                    clean_up_errors.push(
+                        ScenarioError::from_sdk(
+                            "Failed to check instance state during deletion",
+                            &err,
+                        )
+                        .operation("DescribeDBInstances"),
+                    );
                     break;
                 }
                 let db_instances = describe_db_instances
@@ -552,6 +546,9 @@ impl AuroraScenario {
 
                 if db_instances.is_empty() {
                     trace!("Delete Instance waited and no instances were found");
+                    if let Some(instance) = self.db_instance_identifier.as_deref() {
+                        self.ledger.deleted(ResourceKind::RdsDbInstance, instance);
+                    }
                     break;
                 }
                 match db_instances.first().unwrap().db_instance_status() {
@@ -584,7 +581,8 @@ impl AuroraScenario {
                 .as_deref()
                 .unwrap_or("Missing DB Cluster Identifier");
             let message = format!("failed to delete db cluster {identifier}");
-            clean_up_errors.push(ScenarioError::new(message, &err));
+            clean_up_errors
+                .push(ScenarioError::from_sdk(message, &err).operation("DeleteDBCluster"));
         } else {
             // Wait for the instance and cluster to fully delete. rds.DescribeDbInstances and rds.DescribeDbClusters until both are not found.
             let waiter = Waiter::default();
@@ -598,16 +596,22 @@ impl AuroraScenario {
                     )
                     .await;
                 if let Err(err) = describe_db_clusters {
-                    clean_up_errors.push(ScenarioError::new(
-                        "Failed to check cluster state during deletion",
-                        &err,
-                    ));
+                    clean_up_errors.push(
+                        ScenarioError::from_sdk(
+                            "Failed to check cluster state during deletion",
+                            &err,
+                        )
+                        .operation("DescribeDBClusters"),
+                    );
                     break;
                 }
                 let describe_db_clusters = describe_db_clusters.unwrap();
                 let db_clusters = describe_db_clusters.db_clusters();
                 if db_clusters.is_empty() {
                     trace!("Delete cluster waited and no clusters were found");
+                    if let Some(cluster) = self.db_cluster_identifier.as_deref() {
+                        self.ledger.deleted(ResourceKind::RdsDbCluster, cluster);
+                    }
                     break;
                 }
                 match db_clusters.first().unwrap().status() {
@@ -624,6 +628,20 @@ impl AuroraScenario {
             }
         }
 
+        // Delete the DB cluster snapshot. rds.DeleteDbClusterSnapshot.
+        if let Some(snapshot) = self.db_cluster_snapshot_identifier.as_deref() {
+            let delete_db_cluster_snapshot = self.rds.delete_db_cluster_snapshot(snapshot).await;
+            if let Err(err) = delete_db_cluster_snapshot {
+                let message = format!("failed to delete db cluster snapshot {snapshot}");
+                clean_up_errors.push(
+                    ScenarioError::from_sdk(message, &err).operation("DeleteDBClusterSnapshot"),
+                );
+            } else {
+                self.ledger
+                    .deleted(ResourceKind::RdsDbClusterSnapshot, snapshot);
+            }
+        }
+
         // Delete the DB cluster parameter group. rds.DeleteDbClusterParameterGroup.
         let delete_db_cluster_parameter_group = self
             .rds
@@ -638,10 +656,15 @@ impl AuroraScenario {
             )
             .await;
         if let Err(error) = delete_db_cluster_parameter_group {
-            clean_up_errors.push(ScenarioError::new(
-                "Failed to delete the db cluster parameter group",
-                &error,
-            ))
+            clean_up_errors.push(
+                ScenarioError::from_sdk("Failed to delete the db cluster parameter group", &error)
+                    .operation("DeleteDBClusterParameterGroup"),
+            )
+        } else {
+            self.ledger.deleted(
+                ResourceKind::RdsDbClusterParameterGroup,
+                DB_CLUSTER_PARAMETER_GROUP_NAME,
+            );
         }
 
         if clean_up_errors.is_empty() {
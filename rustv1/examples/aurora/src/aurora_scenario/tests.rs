@@ -17,6 +17,7 @@ use aws_sdk_rds::{
         create_db_instance::{CreateDBInstanceError, CreateDbInstanceOutput},
         delete_db_cluster::DeleteDbClusterOutput,
         delete_db_cluster_parameter_group::DeleteDbClusterParameterGroupOutput,
+        delete_db_cluster_snapshot::DeleteDbClusterSnapshotOutput,
         delete_db_instance::DeleteDbInstanceOutput,
         describe_db_cluster_endpoints::DescribeDbClusterEndpointsOutput,
         describe_db_cluster_parameters::{
@@ -180,7 +181,7 @@ async fn test_scenario_get_engines_failed() {
     let versions_map = scenario.get_engines().await;
     assert_matches!(
         versions_map,
-        Err(ScenarioError { message, context: _ }) if message == "Failed to retrieve DB Engine Versions"
+        Err(err) if err.message() == "Failed to retrieve DB Engine Versions"
     );
 }
 // snippet-end:[rust.aurora.get_engines.test]
@@ -261,7 +262,7 @@ async fn test_scenario_get_instance_classes_error() {
 
     assert_matches!(
         instance_classes,
-        Err(ScenarioError {message, context: _}) if message == "Could not get available instance classes"
+        Err(err) if err.message() == "Could not get available instance classes"
     );
 }
 // snippet-end:[rust.aurora.get_instance_classes.test]
@@ -308,7 +309,7 @@ async fn test_scenario_get_cluster_missing_cluster() {
     scenario.db_cluster_identifier = Some("RustSDKCodeExamplesDBCluster".into());
     let cluster = scenario.get_cluster().await;
 
-    assert_matches!(cluster, Err(ScenarioError { message, context: _ }) if message == "Did not find the cluster");
+    assert_matches!(cluster, Err(err) if err.message() == "Did not find the cluster");
 }
 
 #[tokio::test]
@@ -340,7 +341,7 @@ async fn test_scenario_get_cluster_error() {
     scenario.db_cluster_identifier = Some("RustSDKCodeExamplesDBCluster".into());
     let cluster = scenario.get_cluster().await;
 
-    assert_matches!(cluster, Err(ScenarioError { message, context: _ }) if message == "Failed to get cluster");
+    assert_matches!(cluster, Err(err) if err.message() == "Failed to get cluster");
 }
 // snippet-end:[rust.aurora.get_cluster.test]
 
@@ -431,7 +432,7 @@ async fn test_scenario_cluster_parameters_error() {
     let mut scenario = AuroraScenario::new(mock_rds);
     scenario.db_cluster_identifier = Some("RustSDKCodeExamplesDBCluster".into());
     let params = scenario.cluster_parameters().await;
-    assert_matches!(params, Err(ScenarioError { message, context: _ }) if message == "Failed to retrieve parameters for RustSDKCodeExamplesDBParameterGroup");
+    assert_matches!(params, Err(err) if err.message() == "Failed to retrieve parameters for RustSDKCodeExamplesDBParameterGroup");
 }
 // snippet-end:[rust.aurora.cluster_parameters.test]
 
@@ -490,7 +491,7 @@ async fn test_scenario_update_auto_increment_error() {
     let scenario = AuroraScenario::new(mock_rds);
 
     let update = scenario.update_auto_increment(10, 20).await;
-    assert_matches!(update, Err(ScenarioError { message, context: _}) if message == "Failed to modify cluster parameter group");
+    assert_matches!(update, Err(err) if err.message() == "Failed to modify cluster parameter group");
 }
 // snippet-end:[rust.aurora.update_auto_increment.test]
 
@@ -618,7 +619,7 @@ async fn test_start_cluster_and_instance_cluster_create_error() {
     scenario.password = Some(SecretString::new("test password".into()));
 
     let create = scenario.start_cluster_and_instance().await;
-    assert_matches!(create, Err(ScenarioError { message, context: _}) if message == "Failed to create DB Cluster with cluster group")
+    assert_matches!(create, Err(err) if err.message() == "Failed to create DB Cluster with cluster group")
 }
 
 #[tokio::test]
@@ -640,7 +641,7 @@ async fn test_start_cluster_and_instance_cluster_create_missing_id() {
     scenario.password = Some(SecretString::new("test password".into()));
 
     let create = scenario.start_cluster_and_instance().await;
-    assert_matches!(create, Err(ScenarioError { message, context:_ }) if message == "Created DB Cluster missing Identifier");
+    assert_matches!(create, Err(err) if err.message() == "Created DB Cluster missing Identifier");
 }
 
 #[tokio::test]
@@ -683,7 +684,7 @@ async fn test_start_cluster_and_instance_instance_create_error() {
     scenario.password = Some(SecretString::new("test password".into()));
 
     let create = scenario.start_cluster_and_instance().await;
-    assert_matches!(create, Err(ScenarioError { message, context: _ }) if message == "Failed to create Instance in DB Cluster")
+    assert_matches!(create, Err(err) if err.message() == "Failed to create Instance in DB Cluster")
 }
 
 #[tokio::test]
@@ -843,9 +844,15 @@ async fn test_scenario_clean_up() {
         .with(eq("MockParamGroup"))
         .return_once(|_| Ok(DeleteDbClusterParameterGroupOutput::builder().build()));
 
+    mock_rds
+        .expect_delete_db_cluster_snapshot()
+        .with(eq("MockCluster_MockSnapshot"))
+        .return_once(|_| Ok(DeleteDbClusterSnapshotOutput::builder().build()));
+
     let mut scenario = AuroraScenario::new(mock_rds);
     scenario.db_cluster_identifier = Some(String::from("MockCluster"));
     scenario.db_instance_identifier = Some(String::from("MockInstance"));
+    scenario.db_cluster_snapshot_identifier = Some(String::from("MockCluster_MockSnapshot"));
     scenario.db_cluster_parameter_group = Some(
         DbClusterParameterGroup::builder()
             .db_cluster_parameter_group_name("MockParamGroup")
@@ -952,8 +959,8 @@ async fn test_scenario_clean_up_errors() {
         assert!(clean_up.is_err());
         let errs = clean_up.unwrap_err();
         assert_eq!(errs.len(), 2);
-        assert_matches!(errs.first(), Some(ScenarioError {message, context: _}) if message == "Failed to check instance state during deletion");
-        assert_matches!(errs.get(1), Some(ScenarioError {message, context: _}) if message == "Failed to check cluster state during deletion");
+        assert_matches!(errs.first(), Some(err) if err.message() == "Failed to check instance state during deletion");
+        assert_matches!(errs.get(1), Some(err) if err.message() == "Failed to check cluster state during deletion");
     });
 
     tokio::time::advance(Duration::from_secs(1)).await; // Wait for first Describe Instances
@@ -1012,7 +1019,7 @@ async fn test_scenario_snapshot_error() {
     let mut scenario = AuroraScenario::new(mock_rds);
     scenario.db_cluster_identifier = Some("MockCluster".into());
     let create_snapshot = scenario.snapshot("MockSnapshot").await;
-    assert_matches!(create_snapshot, Err(ScenarioError { message, context: _}) if message == "Failed to create snapshot");
+    assert_matches!(create_snapshot, Err(err) if err.message() == "Failed to create snapshot");
 }
 
 #[tokio::test]
@@ -1028,6 +1035,6 @@ async fn test_scenario_snapshot_invalid() {
     let mut scenario = AuroraScenario::new(mock_rds);
     scenario.db_cluster_identifier = Some("MockCluster".into());
     let create_snapshot = scenario.snapshot("MockSnapshot").await;
-    assert_matches!(create_snapshot, Err(ScenarioError { message, context: _}) if message == "Missing Snapshot");
+    assert_matches!(create_snapshot, Err(err) if err.message() == "Missing Snapshot");
 }
 // snippet-end:[rust.aurora.snapshot.test]
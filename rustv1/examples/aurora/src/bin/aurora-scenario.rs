@@ -10,6 +10,7 @@ use aurora_code_examples::{
 };
 use aws_sdk_rds::Client;
 use inquire::{validator::StringValidator, CustomUserError};
+use sdk_examples_resource_ledger::{Ledger, DEFAULT_JOURNAL};
 use secrecy::SecretString;
 use tracing::warn;
 
@@ -54,8 +55,8 @@ fn select(
 
 // Prepare the Aurora Scenario. Prompt for several settings that are optional to the Scenario, but that the user should choose for the demo.
 // This includes the engine, engine version, and instance class.
-async fn prepare_scenario(rds: RdsClient) -> Result<AuroraScenario, anyhow::Error> {
-    let mut scenario = AuroraScenario::new(rds);
+async fn prepare_scenario(rds: RdsClient, ledger: Ledger) -> Result<AuroraScenario, anyhow::Error> {
+    let mut scenario = AuroraScenario::new(rds).with_ledger(ledger);
 
     // Get available engine families for Aurora MySql. rds.DescribeDbEngineVersions(Engine='aurora-mysql') and build a set of the 'DBParameterGroupFamily' field values. I get {aurora-mysql8.0, aurora-mysql5.7}.
     let available_engines = scenario.get_engines().await;
@@ -191,7 +192,10 @@ async fn main() -> Result<(), anyhow::Error> {
     let sdk_config = aws_config::from_env().load().await;
     let client = Client::new(&sdk_config);
     let rds = RdsClient::new(client);
-    let mut scenario = prepare_scenario(rds).await?;
+    // Record what the scenario creates, so `cleanup --from-journal` can remove it if this run is interrupted.
+    let ledger =
+        Ledger::open(DEFAULT_JOURNAL).with_region(sdk_config.region().map(|r| r.to_string()));
+    let mut scenario = prepare_scenario(rds, ledger).await?;
 
     // At this point, the scenario has things in AWS and needs to get cleaned up.
     let mut warnings = Warnings::new();
@@ -203,7 +207,7 @@ async fn main() -> Result<(), anyhow::Error> {
         }
     }
 
-    // Clean up the instance, cluster, and parameter group, waiting for the instance and cluster to delete before moving on.
+    // Clean up the instance, cluster, snapshot, and parameter group, waiting for the instance and cluster to delete before moving on.
     let clean_up = scenario.clean_up().await;
     if let Err(errors) = clean_up {
         for error in errors {
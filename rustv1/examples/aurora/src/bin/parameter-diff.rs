@@ -0,0 +1,149 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::anyhow;
+use aurora_code_examples::{
+    parameters::{DesiredParameters, ParameterManager},
+    rds::Rds as RdsClient,
+};
+use aws_config::meta::region::RegionProviderChain;
+use aws_sdk_rds::{config::Region, Client};
+use clap::Parser;
+use sdk_examples_resource_ledger::{Ledger, DEFAULT_JOURNAL};
+
+#[derive(Debug, Parser)]
+struct Opt {
+    /// The AWS Region.
+    #[structopt(short, long)]
+    region: Option<String>,
+
+    /// The DB cluster parameter group to compare.
+    #[structopt(short, long)]
+    group: Option<String>,
+
+    /// A JSON file of the parameters the group should have.
+    #[structopt(short, long)]
+    file: String,
+
+    /// Apply the differences, instead of only showing them.
+    #[structopt(short, long)]
+    apply: bool,
+
+    /// A cluster that uses the group. After applying, list its instances waiting on a reboot.
+    #[structopt(short, long)]
+    cluster: Option<String>,
+
+    /// Try the changes on a clone of this cluster, restored from a snapshot, instead of on a group.
+    #[structopt(long)]
+    clone_from: Option<String>,
+
+    /// The instance class for the clone's instance.
+    #[structopt(long, default_value = "db.r5.large")]
+    instance_class: String,
+
+    /// Leave the clone running after the changes are applied.
+    #[structopt(long)]
+    keep_clone: bool,
+}
+
+/// Compares an Aurora DB cluster parameter group with a file of desired parameters, and applies
+/// the difference.
+/// # Arguments
+///
+/// * `-f FILE` - The JSON file of desired parameters.
+/// * `[-g GROUP]` - The DB cluster parameter group to compare.
+/// * `[-a]` - Apply the differences.
+/// * `[-c CLUSTER]` - A cluster using the group, to check for instances waiting on a reboot.
+/// * `[--clone-from CLUSTER]` - Clone this cluster and try the changes on the clone's group.
+/// * `[--instance-class CLASS]` - The clone's instance class. Defaults to db.r5.large.
+/// * `[--keep-clone]` - Don't delete the clone afterwards.
+/// * `[-r REGION]` - The Region in which the client is created.
+///    If not supplied, uses the value of the **AWS_REGION** environment variable.
+///    If the environment variable is not set, defaults to **us-west-2**.
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    tracing_subscriber::fmt::init();
+
+    let Opt {
+        region,
+        group,
+        file,
+        apply,
+        cluster,
+        clone_from,
+        instance_class,
+        keep_clone,
+    } = Opt::parse();
+
+    let desired = DesiredParameters::load(&file)?;
+
+    let region_provider = RegionProviderChain::first_try(region.map(Region::new))
+        .or_default_provider()
+        .or_else(Region::new("us-west-2"));
+    let sdk_config = aws_config::from_env().region(region_provider).load().await;
+    let ledger =
+        Ledger::open(DEFAULT_JOURNAL).with_region(sdk_config.region().map(|r| r.to_string()));
+    let manager =
+        ParameterManager::new(RdsClient::new(Client::new(&sdk_config))).with_ledger(ledger);
+
+    let clone = match &clone_from {
+        Some(source) => {
+            let name = format!("{source}-clone");
+            println!("Cloning {source} as {name}. This can take 20 minutes or more.");
+            Some(
+                manager
+                    .clone_cluster(source, &name, &instance_class)
+                    .await?,
+            )
+        }
+        None => None,
+    };
+
+    let (group, cluster) = match &clone {
+        Some(clone) => (
+            clone.parameter_group_name.clone(),
+            Some(clone.cluster_identifier.clone()),
+        ),
+        None => (
+            group.ok_or_else(|| anyhow!("Either --group or --clone-from is required"))?,
+            cluster,
+        ),
+    };
+
+    // Run the comparison on its own, so the clone is deleted even when it fails.
+    let result: Result<(), anyhow::Error> = async {
+        let diff = manager.diff(&group, &desired).await?;
+        print!("{diff}");
+
+        if (apply || clone.is_some()) && !diff.is_empty() {
+            let applied = manager.apply(&diff).await?;
+            println!("Applied {applied} parameters to {group}.");
+            if let Some(cluster) = &cluster {
+                let pending = manager.pending_reboot(cluster).await?;
+                if !pending.is_empty() {
+                    println!("Waiting on a reboot: {}", pending.join(", "));
+                }
+            }
+        }
+        Ok(())
+    }
+    .await;
+
+    if let Some(clone) = clone {
+        if keep_clone {
+            println!("Kept {}.", clone.cluster_identifier);
+        } else if let Err(errors) = manager.delete_clone(clone).await {
+            for error in &errors {
+                tracing::warn!("{error}");
+            }
+            if let Err(err) = &result {
+                tracing::warn!("{err}");
+            }
+            return Err(anyhow!(
+                "Failed to delete the clone; see {DEFAULT_JOURNAL} to clean up"
+            ));
+        }
+    }
+
+    result
+}
@@ -0,0 +1,957 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compare a cluster parameter group against the parameters it should have, apply the difference,
+//! and try changes out first on a clone of the cluster restored from a snapshot.
+
+use std::{collections::BTreeMap, fmt::Display, future::Future, path::Path, time::Duration};
+
+use aws_sdk_rds::types::{ApplyMethod, Parameter};
+use sdk_examples_resource_ledger::{Ledger, Resource, ResourceKind};
+use sdk_examples_scenario_error::{ErrorKind, ScenarioError};
+use sdk_examples_test_utils::waiter::Waiter;
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::rds::Rds;
+
+/// ModifyDBClusterParameterGroup accepts at most 20 parameters per call.
+pub const MAX_PARAMETERS_PER_MODIFY: usize = 20;
+
+// Restoring a cluster takes about as long as creating one.
+const CLONE_MAX_WAIT: Duration = Duration::from_secs(30 * 60);
+const CLONE_POLL: Duration = Duration::from_secs(15);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DesiredParameter {
+    pub value: String,
+    /// When to apply the change. When not set, dynamic parameters apply immediately and static
+    /// parameters after the next reboot.
+    pub apply_method: Option<ApplyMethod>,
+}
+
+/// The values some parameters in a group should have. Parameters not listed are left alone.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DesiredParameters(BTreeMap<String, DesiredParameter>);
+
+impl DesiredParameters {
+    pub fn new() -> Self {
+        DesiredParameters::default()
+    }
+
+    pub fn set(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set_with(name, value, None)
+    }
+
+    pub fn set_with(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+        apply_method: Option<ApplyMethod>,
+    ) -> Self {
+        self.0.insert(
+            name.into(),
+            DesiredParameter {
+                value: value.into(),
+                apply_method,
+            },
+        );
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&DesiredParameter> {
+        self.0.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Read a parameter file: a JSON object from parameter name to either its value, or an
+    /// object with `value` and `apply_method` (`immediate` or `pending-reboot`).
+    ///
+    /// ```json
+    /// {
+    ///     "max_connections": 500,
+    ///     "innodb_lock_wait_timeout": { "value": "120", "apply_method": "pending-reboot" }
+    /// }
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, ScenarioError> {
+        let value: Value = serde_json::from_str(json)
+            .map_err(|err| ScenarioError::with(format!("Invalid parameter file: {err}")))?;
+        let Value::Object(entries) = value else {
+            return Err(ScenarioError::with(
+                "Invalid parameter file: expected an object of parameter names",
+            ));
+        };
+
+        let mut desired = DesiredParameters::new();
+        for (name, entry) in entries {
+            let (value, apply_method) = match &entry {
+                Value::Object(detail) => (
+                    detail.get("value").and_then(scalar),
+                    match detail.get("apply_method").and_then(Value::as_str) {
+                        None => None,
+                        Some(method) => Some(parse_apply_method(&name, method)?),
+                    },
+                ),
+                other => (scalar(other), None),
+            };
+            let value = value.ok_or_else(|| {
+                ScenarioError::with(format!("Invalid parameter file: {name} has no value"))
+            })?;
+            desired = desired.set_with(name, value, apply_method);
+        }
+        Ok(desired)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|err| {
+            ScenarioError::with(format!("Failed to read parameter file {path:?}: {err}"))
+        })?;
+        DesiredParameters::from_json(&json)
+    }
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(if *b { "1".into() } else { "0".into() }),
+        _ => None,
+    }
+}
+
+fn parse_apply_method(name: &str, method: &str) -> Result<ApplyMethod, ScenarioError> {
+    match method {
+        "immediate" => Ok(ApplyMethod::Immediate),
+        "pending-reboot" => Ok(ApplyMethod::PendingReboot),
+        other => Err(ScenarioError::with(format!(
+            "Invalid parameter file: {name} has unknown apply_method {other}"
+        ))),
+    }
+}
+
+/// One parameter whose value needs to change.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParameterChange {
+    pub name: String,
+    pub current: Option<String>,
+    pub desired: String,
+    /// `static` or `dynamic`, as the engine reports it.
+    pub apply_type: Option<String>,
+    pub apply_method: ApplyMethod,
+    /// Set when the change was asked to apply immediately, but the parameter is static, so it
+    /// can only apply at the next reboot.
+    pub deferred: bool,
+}
+
+impl ParameterChange {
+    pub fn requires_reboot(&self) -> bool {
+        self.apply_method == ApplyMethod::PendingReboot
+    }
+
+    fn to_parameter(&self) -> Parameter {
+        Parameter::builder()
+            .parameter_name(self.name.clone())
+            .parameter_value(self.desired.clone())
+            .apply_method(self.apply_method.clone())
+            .build()
+    }
+}
+
+impl Display for ParameterChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} -> {} ({})",
+            self.name,
+            self.current.as_deref().unwrap_or("<engine default>"),
+            self.desired,
+            self.apply_method.as_str()
+        )?;
+        if self.deferred {
+            write!(f, " static parameter, deferred to reboot")?;
+        }
+        Ok(())
+    }
+}
+
+/// How a parameter group differs from the desired parameters.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParameterDiff {
+    pub group: String,
+    pub changes: Vec<ParameterChange>,
+    /// Desired parameters that already have their value.
+    pub unchanged: Vec<String>,
+    /// Desired parameters the group doesn't have.
+    pub unknown: Vec<String>,
+    /// Desired parameters the engine doesn't allow changing.
+    pub not_modifiable: Vec<String>,
+}
+
+impl ParameterDiff {
+    pub fn compare(group: &str, current: &[Parameter], desired: &DesiredParameters) -> Self {
+        let mut diff = ParameterDiff {
+            group: group.to_string(),
+            ..Default::default()
+        };
+
+        for (name, want) in &desired.0 {
+            let Some(parameter) = current
+                .iter()
+                .find(|p| p.parameter_name() == Some(name.as_str()))
+            else {
+                diff.unknown.push(name.clone());
+                continue;
+            };
+            if parameter.parameter_value() == Some(want.value.as_str()) {
+                diff.unchanged.push(name.clone());
+                continue;
+            }
+            if parameter.is_modifiable() == Some(false) {
+                diff.not_modifiable.push(name.clone());
+                continue;
+            }
+
+            let is_static = parameter.apply_type() == Some("static");
+            let (apply_method, deferred) = match &want.apply_method {
+                // RDS rejects immediate changes to static parameters.
+                Some(ApplyMethod::Immediate) if is_static => (ApplyMethod::PendingReboot, true),
+                Some(method) => (method.clone(), false),
+                None if is_static => (ApplyMethod::PendingReboot, false),
+                None => (ApplyMethod::Immediate, false),
+            };
+            diff.changes.push(ParameterChange {
+                name: name.clone(),
+                current: parameter.parameter_value().map(String::from),
+                desired: want.value.clone(),
+                apply_type: parameter.apply_type().map(String::from),
+                apply_method,
+                deferred,
+            });
+        }
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn requires_reboot(&self) -> bool {
+        self.changes.iter().any(ParameterChange::requires_reboot)
+    }
+
+    /// The changes, split into calls that each fit ModifyDBClusterParameterGroup's limit.
+    pub fn batches(&self) -> Vec<Vec<Parameter>> {
+        self.changes
+            .chunks(MAX_PARAMETERS_PER_MODIFY)
+            .map(|chunk| chunk.iter().map(ParameterChange::to_parameter).collect())
+            .collect()
+    }
+}
+
+impl Display for ParameterDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}: {} to change, {} unchanged",
+            self.group,
+            self.changes.len(),
+            self.unchanged.len()
+        )?;
+        for change in &self.changes {
+            writeln!(f, "  ~ {change}")?;
+        }
+        for name in &self.unknown {
+            writeln!(f, "  ! {name}: not a parameter in this group")?;
+        }
+        for name in &self.not_modifiable {
+            writeln!(f, "  ! {name}: not modifiable")?;
+        }
+        if self.requires_reboot() {
+            writeln!(
+                f,
+                "  Some changes take effect only after the instances reboot."
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The resources a clone is made of, which `delete_clone` removes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClusterClone {
+    pub cluster_identifier: String,
+    pub instance_identifier: String,
+    pub parameter_group_name: String,
+    pub snapshot_identifier: String,
+}
+
+pub struct ParameterManager {
+    rds: Rds,
+    ledger: Ledger,
+}
+
+impl ParameterManager {
+    pub fn new(rds: Rds) -> Self {
+        ParameterManager {
+            rds,
+            ledger: Ledger::disabled(),
+        }
+    }
+
+    /// Record the clones this creates, so an interrupted run can be cleaned up.
+    pub fn with_ledger(mut self, ledger: Ledger) -> Self {
+        self.ledger = ledger;
+        self
+    }
+
+    // snippet-start:[rust.aurora.parameter_diff.usage]
+    pub async fn diff(
+        &self,
+        group: &str,
+        desired: &DesiredParameters,
+    ) -> Result<ParameterDiff, ScenarioError> {
+        let current = self
+            .rds
+            .describe_db_cluster_parameters(group)
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to retrieve parameters for {group}"), &err)
+                    .operation("DescribeDBClusterParameters")
+            })?
+            .into_iter()
+            .flat_map(|page| page.parameters.unwrap_or_default())
+            .collect::<Vec<_>>();
+
+        Ok(ParameterDiff::compare(group, &current, desired))
+    }
+    // snippet-end:[rust.aurora.parameter_diff.usage]
+
+    // snippet-start:[rust.aurora.parameter_apply.usage]
+    /// Apply the diff in batches. If a batch fails, the batches before it stay applied, and the
+    /// error says how many parameters were changed. Returns the number of parameters changed.
+    pub async fn apply(&self, diff: &ParameterDiff) -> Result<usize, ScenarioError> {
+        let batches = diff.batches();
+        let total = batches.len();
+        let mut applied = 0;
+        for (number, batch) in batches.into_iter().enumerate() {
+            let size = batch.len();
+            self.rds
+                .modify_db_cluster_parameter_group(&diff.group, batch)
+                .await
+                .map_err(|err| {
+                    let message = format!(
+                        "Failed to apply batch {} of {total} to {} \
+                         after changing {applied} parameters",
+                        number + 1,
+                        diff.group
+                    );
+                    ScenarioError::from_sdk(message, &err)
+                        .operation("ModifyDBClusterParameterGroup")
+                })?;
+            applied += size;
+            info!(group = %diff.group, applied, "Applied parameter batch");
+        }
+        Ok(applied)
+    }
+    // snippet-end:[rust.aurora.parameter_apply.usage]
+
+    /// The instances in the cluster whose parameters are waiting on a reboot.
+    pub async fn pending_reboot(&self, cluster: &str) -> Result<Vec<String>, ScenarioError> {
+        let clusters = self
+            .rds
+            .describe_db_clusters(cluster)
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to describe {cluster}"), &err)
+                    .operation("DescribeDBClusters")
+            })?;
+        Ok(clusters
+            .db_clusters()
+            .iter()
+            .flat_map(|c| c.db_cluster_members())
+            .filter(|m| m.db_cluster_parameter_group_status() == Some("pending-reboot"))
+            .filter_map(|m| m.db_instance_identifier().map(String::from))
+            .collect())
+    }
+
+    // snippet-start:[rust.aurora.clone_cluster.usage]
+    /// Restore a snapshot of `source` as a new cluster named `clone`, with its own copy of the
+    /// source's parameter group, so parameter changes can be tried without touching the source.
+    pub async fn clone_cluster(
+        &self,
+        source: &str,
+        clone: &str,
+        instance_class: &str,
+    ) -> Result<ClusterClone, ScenarioError> {
+        let clusters = self.rds.describe_db_clusters(source).await.map_err(|err| {
+            ScenarioError::from_sdk(format!("Failed to describe {source}"), &err)
+                .operation("DescribeDBClusters")
+        })?;
+        let source_cluster = clusters
+            .db_clusters()
+            .first()
+            .ok_or_else(|| ScenarioError::with(format!("Did not find the cluster {source}")))?;
+        let engine = source_cluster.engine().unwrap_or_default().to_string();
+        let source_group = source_cluster
+            .db_cluster_parameter_group()
+            .unwrap_or_default()
+            .to_string();
+
+        let snapshot_identifier = format!("{clone}-source");
+        self.rds
+            .snapshot_cluster(source, &snapshot_identifier)
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to snapshot {source}"), &err)
+                    .operation("CreateDBClusterSnapshot")
+            })?;
+        self.ledger.created(Resource::new(
+            ResourceKind::RdsDbClusterSnapshot,
+            snapshot_identifier.as_str(),
+        ));
+
+        let parameter_group_name = format!("{clone}-parameters");
+        self.rds
+            .copy_db_cluster_parameter_group(
+                &source_group,
+                &parameter_group_name,
+                &format!("Parameters for {clone}, copied from {source_group}"),
+            )
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(
+                    format!("Failed to copy parameter group {source_group}"),
+                    &err,
+                )
+                .operation("CopyDBClusterParameterGroup")
+            })?;
+        self.ledger.created(Resource::new(
+            ResourceKind::RdsDbClusterParameterGroup,
+            parameter_group_name.as_str(),
+        ));
+
+        wait_until("snapshot to be available", || {
+            self.snapshot_available(&snapshot_identifier)
+        })
+        .await?;
+
+        self.rds
+            .restore_db_cluster_from_snapshot(
+                clone,
+                &snapshot_identifier,
+                &engine,
+                &parameter_group_name,
+            )
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to restore {snapshot_identifier}"), &err)
+                    .operation("RestoreDBClusterFromSnapshot")
+            })?;
+        self.ledger
+            .created(Resource::new(ResourceKind::RdsDbCluster, clone).depends_on(
+                ResourceKind::RdsDbClusterParameterGroup,
+                parameter_group_name.as_str(),
+            ));
+
+        let instance_identifier = format!("{clone}-instance");
+        self.rds
+            .create_db_instance(clone, &instance_identifier, instance_class, &engine)
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to create instance in {clone}"), &err)
+                    .operation("CreateDBInstance")
+            })?;
+        self.ledger.created(
+            Resource::new(ResourceKind::RdsDbInstance, instance_identifier.as_str())
+                .depends_on(ResourceKind::RdsDbCluster, clone),
+        );
+
+        wait_until("clone to be available", || {
+            self.clone_available(clone, &instance_identifier)
+        })
+        .await?;
+
+        Ok(ClusterClone {
+            cluster_identifier: clone.to_string(),
+            instance_identifier,
+            parameter_group_name,
+            snapshot_identifier,
+        })
+    }
+    // snippet-end:[rust.aurora.clone_cluster.usage]
+
+    /// Delete everything the clone is made of, continuing past failures.
+    pub async fn delete_clone(&self, clone: ClusterClone) -> Result<(), Vec<ScenarioError>> {
+        let mut errors = vec![];
+
+        match self
+            .rds
+            .delete_db_instance(&clone.instance_identifier)
+            .await
+        {
+            Ok(_) => match wait_until("clone instance to delete", || {
+                self.instance_gone(&clone.instance_identifier)
+            })
+            .await
+            {
+                Ok(()) => self.ledger.deleted(
+                    ResourceKind::RdsDbInstance,
+                    clone.instance_identifier.as_str(),
+                ),
+                Err(err) => errors.push(err),
+            },
+            Err(err) => errors.push(
+                ScenarioError::from_sdk(
+                    format!("Failed to delete {}", clone.instance_identifier),
+                    &err,
+                )
+                .operation("DeleteDBInstance"),
+            ),
+        }
+
+        match self.rds.delete_db_cluster(&clone.cluster_identifier).await {
+            Ok(_) => match wait_until("clone cluster to delete", || {
+                self.cluster_gone(&clone.cluster_identifier)
+            })
+            .await
+            {
+                Ok(()) => self.ledger.deleted(
+                    ResourceKind::RdsDbCluster,
+                    clone.cluster_identifier.as_str(),
+                ),
+                Err(err) => errors.push(err),
+            },
+            Err(err) => errors.push(
+                ScenarioError::from_sdk(
+                    format!("Failed to delete {}", clone.cluster_identifier),
+                    &err,
+                )
+                .operation("DeleteDBCluster"),
+            ),
+        }
+
+        match self
+            .rds
+            .delete_db_cluster_parameter_group(&clone.parameter_group_name)
+            .await
+        {
+            Ok(_) => self.ledger.deleted(
+                ResourceKind::RdsDbClusterParameterGroup,
+                clone.parameter_group_name.as_str(),
+            ),
+            Err(err) => errors.push(
+                ScenarioError::from_sdk(
+                    format!("Failed to delete {}", clone.parameter_group_name),
+                    &err,
+                )
+                .operation("DeleteDBClusterParameterGroup"),
+            ),
+        }
+
+        match self
+            .rds
+            .delete_db_cluster_snapshot(&clone.snapshot_identifier)
+            .await
+        {
+            Ok(_) => self.ledger.deleted(
+                ResourceKind::RdsDbClusterSnapshot,
+                clone.snapshot_identifier.as_str(),
+            ),
+            Err(err) => errors.push(
+                ScenarioError::from_sdk(
+                    format!("Failed to delete {}", clone.snapshot_identifier),
+                    &err,
+                )
+                .operation("DeleteDBClusterSnapshot"),
+            ),
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    async fn snapshot_available(&self, snapshot: &str) -> Result<bool, ScenarioError> {
+        let snapshots = self
+            .rds
+            .describe_db_cluster_snapshots(snapshot)
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to describe {snapshot}"), &err)
+                    .operation("DescribeDBClusterSnapshots")
+            })?;
+        Ok(snapshots
+            .db_cluster_snapshots()
+            .first()
+            .and_then(|s| s.status())
+            == Some("available"))
+    }
+
+    async fn clone_available(&self, cluster: &str, instance: &str) -> Result<bool, ScenarioError> {
+        let clusters = self
+            .rds
+            .describe_db_clusters(cluster)
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to describe {cluster}"), &err)
+                    .operation("DescribeDBClusters")
+            })?;
+        if clusters.db_clusters().first().and_then(|c| c.status()) != Some("available") {
+            return Ok(false);
+        }
+        let instances = self
+            .rds
+            .describe_db_instance(instance)
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to describe {instance}"), &err)
+                    .operation("DescribeDBInstances")
+            })?;
+        Ok(instances
+            .db_instances()
+            .first()
+            .and_then(|i| i.db_instance_status())
+            == Some("available"))
+    }
+
+    async fn instance_gone(&self, instance: &str) -> Result<bool, ScenarioError> {
+        match self.rds.describe_db_instance(instance).await {
+            Ok(output) => Ok(output.db_instances().is_empty()),
+            Err(err)
+                if err
+                    .as_service_error()
+                    .map(|e| e.is_db_instance_not_found_fault())
+                    == Some(true) =>
+            {
+                Ok(true)
+            }
+            Err(err) => Err(ScenarioError::from_sdk(
+                format!("Failed to describe {instance}"),
+                &err,
+            )
+            .operation("DescribeDBInstances")),
+        }
+    }
+
+    async fn cluster_gone(&self, cluster: &str) -> Result<bool, ScenarioError> {
+        match self.rds.describe_db_clusters(cluster).await {
+            Ok(output) => Ok(output.db_clusters().is_empty()),
+            Err(err)
+                if err
+                    .as_service_error()
+                    .map(|e| e.is_db_cluster_not_found_fault())
+                    == Some(true) =>
+            {
+                Ok(true)
+            }
+            Err(err) => Err(
+                ScenarioError::from_sdk(format!("Failed to describe {cluster}"), &err)
+                    .operation("DescribeDBClusters"),
+            ),
+        }
+    }
+}
+
+/// Poll `check` until it reports true, or CLONE_MAX_WAIT passes. Failed checks are retried,
+/// unless the request was invalid or not allowed, which won't change by waiting.
+async fn wait_until<F, Fut>(what: &str, mut check: F) -> Result<(), ScenarioError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<bool, ScenarioError>>,
+{
+    let waiter = Waiter::builder()
+        .max(CLONE_MAX_WAIT)
+        .poll(CLONE_POLL)
+        .build();
+    loop {
+        match check().await {
+            Ok(true) => return Ok(()),
+            Ok(false) => {}
+            Err(err) if err.error_kind() == ErrorKind::Client => {
+                return Err(err.context(format!("Stopped waiting for {what}")))
+            }
+            Err(err) => warn!(%err, "Failed to check while waiting for {what}"),
+        }
+        waiter
+            .sleep()
+            .await
+            .map_err(|err| ScenarioError::with(format!("Timed out waiting for {what}: {err}")))?;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aws_sdk_rds::{
+        error::SdkError,
+        operation::{
+            copy_db_cluster_parameter_group::CopyDbClusterParameterGroupOutput,
+            create_db_cluster_snapshot::CreateDbClusterSnapshotOutput,
+            create_db_instance::CreateDbInstanceOutput,
+            describe_db_cluster_parameters::DescribeDbClusterParametersOutput,
+            describe_db_cluster_snapshots::{
+                DescribeDBClusterSnapshotsError, DescribeDbClusterSnapshotsOutput,
+            },
+            describe_db_clusters::DescribeDbClustersOutput,
+            describe_db_instances::DescribeDbInstancesOutput,
+            modify_db_cluster_parameter_group::ModifyDbClusterParameterGroupOutput,
+            restore_db_cluster_from_snapshot::RestoreDbClusterFromSnapshotOutput,
+        },
+        types::{error::DbClusterSnapshotNotFoundFault, DbCluster, DbClusterSnapshot, DbInstance},
+    };
+    use aws_smithy_runtime_api::http::{Response, StatusCode};
+    use aws_smithy_types::body::SdkBody;
+    use mockall::predicate::eq;
+
+    use super::*;
+    use crate::rds::MockRdsImpl;
+
+    fn parameter(name: &str, value: &str, apply_type: &str) -> Parameter {
+        Parameter::builder()
+            .parameter_name(name)
+            .parameter_value(value)
+            .apply_type(apply_type)
+            .is_modifiable(true)
+            .build()
+    }
+
+    #[test]
+    fn test_compare_chooses_apply_method() {
+        let current = vec![
+            parameter("max_connections", "100", "dynamic"),
+            parameter("innodb_buffer_pool_size", "1024", "static"),
+            parameter("binlog_format", "ROW", "static"),
+            Parameter::builder()
+                .parameter_name("character_set_database")
+                .parameter_value("latin1")
+                .is_modifiable(false)
+                .build(),
+        ];
+        let desired = DesiredParameters::from_json(
+            r#"{
+                "max_connections": 500,
+                "innodb_buffer_pool_size": { "value": "2048", "apply_method": "immediate" },
+                "binlog_format": "ROW",
+                "character_set_database": "utf8mb4",
+                "not_a_parameter": "1"
+            }"#,
+        )
+        .unwrap();
+
+        let diff = ParameterDiff::compare("group", &current, &desired);
+
+        assert_eq!(
+            diff.changes,
+            vec![
+                ParameterChange {
+                    name: "innodb_buffer_pool_size".into(),
+                    current: Some("1024".into()),
+                    desired: "2048".into(),
+                    apply_type: Some("static".into()),
+                    apply_method: ApplyMethod::PendingReboot,
+                    deferred: true,
+                },
+                ParameterChange {
+                    name: "max_connections".into(),
+                    current: Some("100".into()),
+                    desired: "500".into(),
+                    apply_type: Some("dynamic".into()),
+                    apply_method: ApplyMethod::Immediate,
+                    deferred: false,
+                },
+            ]
+        );
+        assert_eq!(diff.unchanged, vec!["binlog_format"]);
+        assert_eq!(diff.unknown, vec!["not_a_parameter"]);
+        assert_eq!(diff.not_modifiable, vec!["character_set_database"]);
+        assert!(diff.requires_reboot());
+    }
+
+    #[tokio::test]
+    async fn test_apply_batches_twenty_at_a_time() {
+        let current: Vec<Parameter> = (0..45)
+            .map(|i| parameter(&format!("p{i:02}"), "0", "dynamic"))
+            .collect();
+        let desired = (0..45).fold(DesiredParameters::new(), |d, i| {
+            d.set(format!("p{i:02}"), "1")
+        });
+
+        let mut mock_rds = MockRdsImpl::default();
+        mock_rds
+            .expect_describe_db_cluster_parameters()
+            .with(eq("group"))
+            .return_once(move |_| {
+                Ok(vec![DescribeDbClusterParametersOutput::builder()
+                    .set_parameters(Some(current))
+                    .build()])
+            });
+        let mut sizes = vec![20, 20, 5].into_iter();
+        mock_rds
+            .expect_modify_db_cluster_parameter_group()
+            .with(eq("group"), mockall::predicate::always())
+            .times(3)
+            .returning(move |_, params| {
+                assert_eq!(Some(params.len()), sizes.next());
+                Ok(ModifyDbClusterParameterGroupOutput::builder().build())
+            });
+
+        let manager = ParameterManager::new(mock_rds);
+        let diff = manager.diff("group", &desired).await.unwrap();
+        assert_eq!(diff.changes.len(), 45);
+        assert_eq!(manager.apply(&diff).await.unwrap(), 45);
+    }
+
+    #[tokio::test]
+    async fn test_clone_cluster() {
+        tokio::time::pause();
+        let mut mock_rds = MockRdsImpl::default();
+
+        mock_rds
+            .expect_describe_db_clusters()
+            .with(eq("source"))
+            .return_once(|_| {
+                Ok(DescribeDbClustersOutput::builder()
+                    .db_clusters(
+                        DbCluster::builder()
+                            .db_cluster_identifier("source")
+                            .engine("aurora-mysql")
+                            .db_cluster_parameter_group("source-group")
+                            .build(),
+                    )
+                    .build())
+            });
+        mock_rds
+            .expect_snapshot_cluster()
+            .with(eq("source"), eq("clone-source"))
+            .return_once(|_, _| Ok(CreateDbClusterSnapshotOutput::builder().build()));
+        mock_rds
+            .expect_copy_db_cluster_parameter_group()
+            .withf(|source, target, _| source == "source-group" && target == "clone-parameters")
+            .return_once(|_, _, _| Ok(CopyDbClusterParameterGroupOutput::builder().build()));
+        let mut snapshot_status = vec!["creating", "available"].into_iter();
+        mock_rds
+            .expect_describe_db_cluster_snapshots()
+            .with(eq("clone-source"))
+            .times(2)
+            .returning(move |_| {
+                Ok(DescribeDbClusterSnapshotsOutput::builder()
+                    .db_cluster_snapshots(
+                        DbClusterSnapshot::builder()
+                            .status(snapshot_status.next().unwrap())
+                            .build(),
+                    )
+                    .build())
+            });
+        mock_rds
+            .expect_restore_db_cluster_from_snapshot()
+            .with(
+                eq("clone"),
+                eq("clone-source"),
+                eq("aurora-mysql"),
+                eq("clone-parameters"),
+            )
+            .return_once(|_, _, _, _| Ok(RestoreDbClusterFromSnapshotOutput::builder().build()));
+        mock_rds
+            .expect_create_db_instance()
+            .with(
+                eq("clone"),
+                eq("clone-instance"),
+                eq("db.r5.large"),
+                eq("aurora-mysql"),
+            )
+            .return_once(|_, _, _, _| Ok(CreateDbInstanceOutput::builder().build()));
+        mock_rds
+            .expect_describe_db_clusters()
+            .with(eq("clone"))
+            .return_once(|_| {
+                Ok(DescribeDbClustersOutput::builder()
+                    .db_clusters(DbCluster::builder().status("available").build())
+                    .build())
+            });
+        mock_rds
+            .expect_describe_db_instance()
+            .with(eq("clone-instance"))
+            .return_once(|_| {
+                Ok(DescribeDbInstancesOutput::builder()
+                    .db_instances(
+                        DbInstance::builder()
+                            .db_instance_status("available")
+                            .build(),
+                    )
+                    .build())
+            });
+
+        let manager = ParameterManager::new(mock_rds);
+        let clone = manager
+            .clone_cluster("source", "clone", "db.r5.large")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            clone,
+            ClusterClone {
+                cluster_identifier: "clone".into(),
+                instance_identifier: "clone-instance".into(),
+                parameter_group_name: "clone-parameters".into(),
+                snapshot_identifier: "clone-source".into(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clone_cluster_stops_waiting_on_client_error() {
+        tokio::time::pause();
+        let mut mock_rds = MockRdsImpl::default();
+
+        mock_rds
+            .expect_describe_db_clusters()
+            .with(eq("source"))
+            .return_once(|_| {
+                Ok(DescribeDbClustersOutput::builder()
+                    .db_clusters(
+                        DbCluster::builder()
+                            .db_cluster_identifier("source")
+                            .engine("aurora-mysql")
+                            .db_cluster_parameter_group("source-group")
+                            .build(),
+                    )
+                    .build())
+            });
+        mock_rds
+            .expect_snapshot_cluster()
+            .return_once(|_, _| Ok(CreateDbClusterSnapshotOutput::builder().build()));
+        mock_rds
+            .expect_copy_db_cluster_parameter_group()
+            .return_once(|_, _, _| Ok(CopyDbClusterParameterGroupOutput::builder().build()));
+        mock_rds
+            .expect_describe_db_cluster_snapshots()
+            .with(eq("clone-source"))
+            .times(1)
+            .returning(|_| {
+                Err(SdkError::service_error(
+                    DescribeDBClusterSnapshotsError::DbClusterSnapshotNotFoundFault(
+                        DbClusterSnapshotNotFoundFault::builder().build(),
+                    ),
+                    Response::new(StatusCode::try_from(404).unwrap(), SdkBody::empty()),
+                ))
+            });
+        mock_rds.expect_restore_db_cluster_from_snapshot().never();
+
+        let manager = ParameterManager::new(mock_rds);
+        let clone = manager
+            .clone_cluster("source", "clone", "db.r5.large")
+            .await;
+
+        assert!(clone.is_err());
+    }
+}
@@ -4,6 +4,9 @@
 use aws_sdk_rds::{
     error::SdkError,
     operation::{
+        copy_db_cluster_parameter_group::{
+            CopyDBClusterParameterGroupError, CopyDbClusterParameterGroupOutput,
+        },
         create_db_cluster::{CreateDBClusterError, CreateDbClusterOutput},
         create_db_cluster_parameter_group::CreateDBClusterParameterGroupError,
         create_db_cluster_parameter_group::CreateDbClusterParameterGroupOutput,
@@ -13,6 +16,7 @@ use aws_sdk_rds::{
         delete_db_cluster_parameter_group::{
             DeleteDBClusterParameterGroupError, DeleteDbClusterParameterGroupOutput,
         },
+        delete_db_cluster_snapshot::{DeleteDBClusterSnapshotError, DeleteDbClusterSnapshotOutput},
         delete_db_instance::{DeleteDBInstanceError, DeleteDbInstanceOutput},
         describe_db_cluster_endpoints::{
             DescribeDBClusterEndpointsError, DescribeDbClusterEndpointsOutput,
@@ -20,6 +24,9 @@ use aws_sdk_rds::{
         describe_db_cluster_parameters::{
             DescribeDBClusterParametersError, DescribeDbClusterParametersOutput,
         },
+        describe_db_cluster_snapshots::{
+            DescribeDBClusterSnapshotsError, DescribeDbClusterSnapshotsOutput,
+        },
         describe_db_clusters::{DescribeDBClustersError, DescribeDbClustersOutput},
         describe_db_engine_versions::{
             DescribeDBEngineVersionsError, DescribeDbEngineVersionsOutput,
@@ -29,6 +36,9 @@ use aws_sdk_rds::{
         modify_db_cluster_parameter_group::{
             ModifyDBClusterParameterGroupError, ModifyDbClusterParameterGroupOutput,
         },
+        restore_db_cluster_from_snapshot::{
+            RestoreDBClusterFromSnapshotError, RestoreDbClusterFromSnapshotOutput,
+        },
     },
     types::{OrderableDbInstanceOption, Parameter},
     Client as RdsClient,
@@ -280,4 +290,67 @@ impl RdsImpl {
             .await
     }
     // snippet-end:[rust.aurora.delete_db_cluster_parameter_group.wrapper]
+
+    // snippet-start:[rust.aurora.copy_db_cluster_parameter_group.wrapper]
+    pub async fn copy_db_cluster_parameter_group(
+        &self,
+        source: &str,
+        target: &str,
+        description: &str,
+    ) -> Result<CopyDbClusterParameterGroupOutput, SdkError<CopyDBClusterParameterGroupError>> {
+        self.inner
+            .copy_db_cluster_parameter_group()
+            .source_db_cluster_parameter_group_identifier(source)
+            .target_db_cluster_parameter_group_identifier(target)
+            .target_db_cluster_parameter_group_description(description)
+            .send()
+            .await
+    }
+    // snippet-end:[rust.aurora.copy_db_cluster_parameter_group.wrapper]
+
+    // snippet-start:[rust.aurora.describe_db_cluster_snapshots.wrapper]
+    pub async fn describe_db_cluster_snapshots(
+        &self,
+        snapshot_identifier: &str,
+    ) -> Result<DescribeDbClusterSnapshotsOutput, SdkError<DescribeDBClusterSnapshotsError>> {
+        self.inner
+            .describe_db_cluster_snapshots()
+            .db_cluster_snapshot_identifier(snapshot_identifier)
+            .send()
+            .await
+    }
+    // snippet-end:[rust.aurora.describe_db_cluster_snapshots.wrapper]
+
+    // snippet-start:[rust.aurora.delete_db_cluster_snapshot.wrapper]
+    pub async fn delete_db_cluster_snapshot(
+        &self,
+        snapshot_identifier: &str,
+    ) -> Result<DeleteDbClusterSnapshotOutput, SdkError<DeleteDBClusterSnapshotError>> {
+        self.inner
+            .delete_db_cluster_snapshot()
+            .db_cluster_snapshot_identifier(snapshot_identifier)
+            .send()
+            .await
+    }
+    // snippet-end:[rust.aurora.delete_db_cluster_snapshot.wrapper]
+
+    // snippet-start:[rust.aurora.restore_db_cluster_from_snapshot.wrapper]
+    pub async fn restore_db_cluster_from_snapshot(
+        &self,
+        cluster_identifier: &str,
+        snapshot_identifier: &str,
+        engine: &str,
+        parameter_group: &str,
+    ) -> Result<RestoreDbClusterFromSnapshotOutput, SdkError<RestoreDBClusterFromSnapshotError>>
+    {
+        self.inner
+            .restore_db_cluster_from_snapshot()
+            .db_cluster_identifier(cluster_identifier)
+            .snapshot_identifier(snapshot_identifier)
+            .engine(engine)
+            .db_cluster_parameter_group_name(parameter_group)
+            .send()
+            .await
+    }
+    // snippet-end:[rust.aurora.restore_db_cluster_from_snapshot.wrapper]
 }
@@ -0,0 +1,136 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use anyhow::anyhow;
+use autoscaling_code_examples::scenario::{AutoScalingScenario, OutputFormat, ScenarioChange};
+use aws_config::meta::region::RegionProviderChain;
+use aws_sdk_autoscaling::config::Region;
+use clap::Parser;
+use serde::Serialize;
+
+#[derive(Debug, Parser)]
+struct Opt {
+    /// The AWS Region.
+    #[structopt(short, long)]
+    region: Option<String>,
+
+    /// The name of the Auto Scaling group.
+    #[structopt(short, long)]
+    group_name: String,
+
+    /// How to write the group's state, and in watch mode, each change.
+    #[structopt(short, long, value_enum, default_value = "text")]
+    output: OutputFormat,
+
+    /// Keep describing the group, and write what changes between descriptions.
+    #[structopt(short, long)]
+    watch: bool,
+
+    /// How many seconds to wait between descriptions in watch mode.
+    #[structopt(short, long, default_value = "10")]
+    interval: u64,
+}
+
+/// A change, with when it was seen, as one line of JSON or one YAML document.
+#[derive(Serialize)]
+struct TimedChange<'a> {
+    at: u64,
+    #[serde(flatten)]
+    change: &'a ScenarioChange,
+}
+
+fn write_change(format: OutputFormat, at: u64, change: &ScenarioChange) -> anyhow::Result<()> {
+    let change = TimedChange { at, change };
+    match format {
+        OutputFormat::Text => println!("{} {}", change.at, change.change),
+        OutputFormat::Json => println!("{}", serde_json::to_string(&change)?),
+        OutputFormat::Yaml => print!("---\n{}", serde_yaml::to_string(&change)?),
+    }
+    Ok(())
+}
+
+/// Describes an Auto Scaling group, as text, JSON, or YAML, and optionally watches it for changes.
+/// # Arguments
+///
+/// * `-g GROUP-NAME` - The name of the Auto Scaling group.
+/// * `[-o text|json|yaml]` - The output format. Defaults to text.
+/// * `[-w]` - Watch the group until interrupted, writing each change as it is seen.
+/// * `[-i INTERVAL]` - Seconds between descriptions in watch mode. Defaults to 10.
+/// * `[-r REGION]` - The Region in which the client is created.
+///    If not supplied, uses the value of the **AWS_REGION** environment variable.
+///    If the environment variable is not set, defaults to **us-west-2**.
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    tracing_subscriber::fmt::init();
+
+    let Opt {
+        region,
+        group_name,
+        output,
+        watch,
+        interval,
+    } = Opt::parse();
+
+    let region_provider = RegionProviderChain::first_try(region.map(Region::new))
+        .or_default_provider()
+        .or_else(Region::new("us-west-2"));
+    let shared_config = aws_config::from_env().region(region_provider).load().await;
+
+    let scenario = AutoScalingScenario::existing(&shared_config, &group_name)
+        .await
+        .map_err(|err| anyhow!("Failed to find group: {err}"))?;
+
+    if !watch {
+        let description = scenario.describe_scenario().await;
+        println!("{}", description.render(&group_name, output)?);
+        return Ok(());
+    }
+
+    // Write the starting state in full, then only what changes.
+    let mut first = true;
+    let mut result = Ok(());
+    scenario
+        .watch(Duration::from_secs(interval), |snapshot, changes| {
+            for error in &snapshot.errors {
+                tracing::warn!("{error}");
+            }
+            if first {
+                first = false;
+                let initial = match output {
+                    OutputFormat::Text => {
+                        println!(
+                            "{} Watching {}: {}, {} instances",
+                            snapshot.taken_at,
+                            snapshot.name,
+                            snapshot.capacity,
+                            snapshot.instances.len()
+                        );
+                        Ok(())
+                    }
+                    // One line, like the changes that follow, so the output is JSON Lines.
+                    OutputFormat::Json => serde_json::to_string(snapshot)
+                        .map(|json| println!("{json}"))
+                        .map_err(anyhow::Error::from),
+                    OutputFormat::Yaml => snapshot
+                        .to_yaml()
+                        .map(|yaml| print!("---\n{yaml}"))
+                        .map_err(anyhow::Error::from),
+                };
+                if let Err(err) = initial {
+                    result = Err(err);
+                    return false;
+                }
+            }
+            for change in changes {
+                if let Err(err) = write_change(output, snapshot.taken_at, change) {
+                    result = Err(err);
+                    return false;
+                }
+            }
+            true
+        })
+        .await;
+    result
+}
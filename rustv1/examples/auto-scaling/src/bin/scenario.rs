@@ -1,12 +1,26 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::BTreeSet, fmt::Display};
+use std::{
+    collections::BTreeSet,
+    fmt::Display,
+    time::{Duration, SystemTime},
+};
 
 use anyhow::anyhow;
-use autoscaling_code_examples::scenario::{AutoScalingScenario, ScenarioError};
+use autoscaling_code_examples::scenario::{
+    AutoScalingScenario, LifecycleResult, LifecycleTransition, ScenarioError, Schedule, Step,
+};
+use aws_sdk_autoscaling::types::MetricType;
+use aws_sdk_ec2::types::InstanceType;
+use sdk_examples_resource_ledger::{Ledger, DEFAULT_JOURNAL};
 use tracing::{info, warn};
 
+const TARGET_TRACKING_POLICY_NAME: &str = "SDK_Code_Examples_Target_Tracking_Policy";
+const STEP_POLICY_NAME: &str = "SDK_Code_Examples_Step_Policy";
+const SCHEDULED_ACTION_NAME: &str = "SDK_Code_Examples_Scheduled_Action";
+const LIFECYCLE_HOOK_NAME: &str = "SDK_Code_Examples_Launch_Hook";
+
 async fn show_scenario_description(scenario: &AutoScalingScenario, event: &str) {
     let description = scenario.describe_scenario().await;
     info!("DescribeAutoScalingInstances: {event}\n{description}");
@@ -48,7 +62,10 @@ async fn main() -> Result<(), anyhow::Error> {
     // 1. Create an EC2 launch template that you'll use to create an auto scaling group. Bonus: use SDK with EC2.CreateLaunchTemplate to create the launch template.
     // 2. CreateAutoScalingGroup: pass it the launch template you created in step 0. Give it min/max of 1 instance.
     // 4. EnableMetricsCollection: enable all metrics or a subset.
-    let scenario = match AutoScalingScenario::prepare_scenario(&shared_config).await {
+    // Record what the scenario creates, so `cleanup --from-journal` can remove it if this run is interrupted.
+    let ledger =
+        Ledger::open(DEFAULT_JOURNAL).with_region(shared_config.region().map(|r| r.to_string()));
+    let scenario = match AutoScalingScenario::prepare_scenario(&shared_config, ledger).await {
         Ok(scenario) => scenario,
         Err(errs) => {
             let err_str = errs
@@ -161,6 +178,114 @@ async fn main() -> Result<(), anyhow::Error> {
     )
     .await;
 
+    // Scale with policies instead of setting capacity directly. The target tracking policy only
+    // scales out, so it doesn't undo the step policy while the group is idle.
+    if let Err(err) = scenario
+        .put_target_tracking_policy(
+            TARGET_TRACKING_POLICY_NAME,
+            MetricType::AsgAverageCpuUtilization,
+            50.0,
+            true,
+        )
+        .await
+    {
+        warnings.push("There was a problem adding a target tracking policy", err);
+    }
+    let steps = [
+        Step {
+            lower_bound: Some(0.0),
+            upper_bound: Some(20.0),
+            adjustment: 1,
+        },
+        Step {
+            lower_bound: Some(20.0),
+            upper_bound: None,
+            adjustment: 2,
+        },
+    ];
+    if let Err(err) = scenario
+        .put_step_scaling_policy(STEP_POLICY_NAME, &steps)
+        .await
+    {
+        warnings.push("There was a problem adding a step scaling policy", err);
+    }
+
+    // Without an alarm, run the step policy as if CPU had gone 10 points over a 50% threshold.
+    if let Err(err) = scenario.execute_policy(STEP_POLICY_NAME, 60.0, 50.0).await {
+        warnings.push("There was a problem executing the step scaling policy", err);
+    }
+    if let Err(err) = scenario.wait_for_stable(3).await {
+        warnings.push(
+            "There was a problem waiting for the step policy to scale out",
+            err,
+        );
+    }
+
+    // A scheduled action an hour from now, which the scenario deletes with the group before it runs.
+    let schedule = Schedule {
+        name: SCHEDULED_ACTION_NAME.into(),
+        start_time: Some(SystemTime::now() + Duration::from_secs(60 * 60)),
+        desired_capacity: Some(1),
+        ..Default::default()
+    };
+    if let Err(err) = scenario.put_scheduled_action(&schedule).await {
+        warnings.push("There was a problem adding a scheduled action", err);
+    }
+
+    show_scenario_description(&scenario, "show the group's scaling policies and schedule").await;
+
+    // Roll out a new launch template version. Each replacement instance waits on the launch hook
+    // until the handler completes it, as a real deployment would run health checks there.
+    if let Err(err) = scenario
+        .put_lifecycle_hook(
+            LIFECYCLE_HOOK_NAME,
+            LifecycleTransition::Launching,
+            Duration::from_secs(300),
+            LifecycleResult::Abandon,
+        )
+        .await
+    {
+        warnings.push("There was a problem adding a lifecycle hook", err);
+    }
+
+    let refresh = match scenario
+        .new_launch_template_version(InstanceType::T2Micro)
+        .await
+    {
+        Ok(version) => {
+            info!("Created launch template version {version}");
+            scenario
+                .start_instance_refresh(66, Duration::from_secs(60))
+                .await
+        }
+        Err(err) => Err(err),
+    };
+    match refresh {
+        Ok(id) => {
+            let finished = scenario
+                .wait_for_instance_refresh(
+                    &id,
+                    |action| {
+                        info!("Lifecycle action: {action}");
+                        LifecycleResult::Continue
+                    },
+                    |progress| info!("Instance refresh: {progress}"),
+                )
+                .await;
+            match finished {
+                Ok(progress) if progress.is_successful() => {}
+                Ok(progress) => warnings.push(
+                    "The instance refresh did not succeed",
+                    ScenarioError::with(progress.to_string()),
+                ),
+                Err(err) => warnings.push("There was a problem during the instance refresh", err),
+            }
+        }
+        Err(err) => warnings.push("There was a problem starting an instance refresh", err),
+    }
+
+    show_scenario_description(&scenario, "show the group after the instance refresh").await;
+
     // 11. DisableMetricsCollection
     let scale_group = scenario.scale_group_to_zero().await;
     if let Err(err) = scale_group {
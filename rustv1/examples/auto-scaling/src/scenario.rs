@@ -2,7 +2,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-    error::Error,
     fmt::{Debug, Display},
     time::{Duration, SystemTime},
 };
@@ -10,11 +9,29 @@ use std::{
 use anyhow::anyhow;
 use aws_config::SdkConfig;
 use aws_sdk_autoscaling::{
-    error::{DisplayErrorContext, ProvideErrorMetadata},
-    types::{Activity, AutoScalingGroup, LaunchTemplateSpecification},
+    error::DisplayErrorContext,
+    types::{
+        Activity, AutoScalingGroup, LaunchTemplateSpecification, LifecycleHook, ScalingPolicy,
+        ScheduledUpdateGroupAction,
+    },
 };
 use aws_sdk_ec2::types::RequestLaunchTemplateData;
-use tracing::trace;
+use sdk_examples_resource_ledger::{Ledger, Resource, ResourceKind};
+pub use sdk_examples_scenario_error::ScenarioError;
+use tracing::{trace, warn};
+
+mod lifecycle;
+mod policies;
+mod refresh;
+mod snapshot;
+
+pub use lifecycle::{LifecycleAction, LifecycleResult, LifecycleTransition};
+pub use policies::{Schedule, Step};
+pub use refresh::RefreshProgress;
+pub use snapshot::{
+    ActivitySnapshot, Capacity, GroupSnapshot, HookSnapshot, InstanceSnapshot, OutputFormat,
+    PolicySnapshot, RefreshSnapshot, ScenarioChange, ScheduledActionSnapshot, Section,
+};
 
 const LAUNCH_TEMPLATE_NAME: &str = "SDK_Code_Examples_EC2_Autoscaling_template_from_Rust_SDK";
 const AUTOSCALING_GROUP_NAME: &str = "SDK_Code_Examples_EC2_Autoscaling_Group_from_Rust_SDK";
@@ -24,13 +41,19 @@ const WAIT_TIME: Duration = Duration::from_millis(500); // Wait half a second at
 struct Waiter {
     start: SystemTime,
     max: Duration,
+    poll: Duration,
 }
 
 impl Waiter {
     fn new() -> Self {
+        Waiter::with(MAX_WAIT, WAIT_TIME)
+    }
+
+    fn with(max: Duration, poll: Duration) -> Self {
         Waiter {
             start: SystemTime::now(),
-            max: MAX_WAIT,
+            max,
+            poll,
         }
     }
 
@@ -44,7 +67,7 @@ impl Waiter {
                 "Exceeded maximum wait duration for stable group",
             ))
         } else {
-            tokio::time::sleep(WAIT_TIME).await;
+            tokio::time::sleep(self.poll).await;
             Ok(())
         }
     }
@@ -55,6 +78,7 @@ pub struct AutoScalingScenario {
     autoscaling: aws_sdk_autoscaling::Client,
     launch_template_arn: String,
     auto_scaling_group_name: String,
+    ledger: Ledger,
 }
 
 impl Display for AutoScalingScenario {
@@ -73,9 +97,13 @@ impl Display for AutoScalingScenario {
 }
 
 pub struct AutoScalingScenarioDescription {
-    group: Result<Vec<String>, ScenarioError>,
+    group: Result<Vec<AutoScalingGroup>, ScenarioError>,
     instances: Result<Vec<String>, anyhow::Error>,
     activities: Result<Vec<Activity>, anyhow::Error>,
+    policies: Result<Vec<ScalingPolicy>, ScenarioError>,
+    scheduled_actions: Result<Vec<ScheduledUpdateGroupAction>, ScenarioError>,
+    lifecycle_hooks: Result<Vec<LifecycleHook>, ScenarioError>,
+    instance_refreshes: Result<Vec<RefreshProgress>, ScenarioError>,
 }
 
 impl Display for AutoScalingScenarioDescription {
@@ -83,8 +111,13 @@ impl Display for AutoScalingScenarioDescription {
         writeln!(f, "\t      Group status:")?;
         match &self.group {
             Ok(groups) => {
-                for status in groups {
-                    writeln!(f, "\t\t- {status}")?;
+                for group in groups {
+                    writeln!(
+                        f,
+                        "\t\t- {}: {}",
+                        group.auto_scaling_group_name().unwrap_or("Unknown"),
+                        group.status().unwrap_or("Unknown")
+                    )?;
                 }
             }
             Err(e) => writeln!(f, "\t\t! - {e}")?,
@@ -117,76 +150,100 @@ impl Display for AutoScalingScenarioDescription {
             Err(e) => writeln!(f, "\t\t! {e}")?,
         }
 
-        Ok(())
-    }
-}
-
-#[derive(Debug)]
-struct MetadataError {
-    message: Option<String>,
-    code: Option<String>,
-}
-
-impl MetadataError {
-    fn from(err: &dyn ProvideErrorMetadata) -> Self {
-        MetadataError {
-            message: err.message().map(|s| s.to_string()),
-            code: err.code().map(|s| s.to_string()),
+        writeln!(f, "\t  Scaling policies:")?;
+        match &self.policies {
+            Ok(policies) => {
+                for policy in policies {
+                    write!(
+                        f,
+                        "\t\t- {} ({})",
+                        policy.policy_name().unwrap_or("Unknown"),
+                        policy.policy_type().unwrap_or("Unknown"),
+                    )?;
+                    if let Some(target) = policy.target_tracking_configuration() {
+                        write!(
+                            f,
+                            " Target: {} {}",
+                            target.target_value(),
+                            target
+                                .predefined_metric_specification()
+                                .map(|m| m.predefined_metric_type().as_str())
+                                .unwrap_or("custom metric"),
+                        )?;
+                    }
+                    for step in policy.step_adjustments() {
+                        write!(
+                            f,
+                            " Step: [{}, {}) {:+}",
+                            step.metric_interval_lower_bound()
+                                .map_or("-inf".to_string(), |b| b.to_string()),
+                            step.metric_interval_upper_bound()
+                                .map_or("inf".to_string(), |b| b.to_string()),
+                            step.scaling_adjustment(),
+                        )?;
+                    }
+                    writeln!(f)?;
+                }
+            }
+            Err(e) => writeln!(f, "\t\t! {e}")?,
         }
-    }
-}
 
-impl Display for MetadataError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let display = match (&self.message, &self.code) {
-            (None, None) => "Unknown".to_string(),
-            (None, Some(code)) => format!("({code})"),
-            (Some(message), None) => message.to_string(),
-            (Some(message), Some(code)) => format!("{message} ({code})"),
-        };
-        write!(f, "{display}")
-    }
-}
-
-#[derive(Debug)]
-pub struct ScenarioError {
-    message: String,
-    context: Option<MetadataError>,
-}
-
-impl ScenarioError {
-    pub fn with(message: impl Into<String>) -> Self {
-        ScenarioError {
-            message: message.into(),
-            context: None,
+        writeln!(f, "\t Scheduled actions:")?;
+        match &self.scheduled_actions {
+            Ok(actions) => {
+                for action in actions {
+                    writeln!(
+                        f,
+                        "\t\t- {} Start: {:?} Recurrence: {} Min: {:?} Max: {:?} Desired: {:?}",
+                        action.scheduled_action_name().unwrap_or("Unknown"),
+                        action.start_time(),
+                        action.recurrence().unwrap_or("none"),
+                        action.min_size(),
+                        action.max_size(),
+                        action.desired_capacity(),
+                    )?;
+                }
+            }
+            Err(e) => writeln!(f, "\t\t! {e}")?,
         }
-    }
 
-    pub fn new(message: impl Into<String>, err: &dyn ProvideErrorMetadata) -> Self {
-        ScenarioError {
-            message: message.into(),
-            context: Some(MetadataError::from(err)),
+        writeln!(f, "\t   Lifecycle hooks:")?;
+        match &self.lifecycle_hooks {
+            Ok(hooks) => {
+                for hook in hooks {
+                    writeln!(
+                        f,
+                        "\t\t- {} {} Heartbeat: {}s Default: {}",
+                        hook.lifecycle_hook_name().unwrap_or("Unknown"),
+                        hook.lifecycle_transition().unwrap_or("Unknown"),
+                        hook.heartbeat_timeout().unwrap_or(-1),
+                        hook.default_result().unwrap_or("Unknown"),
+                    )?;
+                }
+            }
+            Err(e) => writeln!(f, "\t\t! {e}")?,
         }
-    }
-}
 
-impl Error for ScenarioError {
-    // While `Error` can capture `source` information about the underlying error, for this example
-    // the ScenarioError captures the underlying information in MetadataError and treats it as a
-    // single Error from this Crate. In other contexts, it may be appropriate to model the error
-    // as including the SdkError as its source.
-}
-impl Display for ScenarioError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self.context {
-            Some(c) => write!(f, "{}: {}", self.message, c),
-            None => write!(f, "{}", self.message),
+        writeln!(f, "\tInstance refreshes:")?;
+        match &self.instance_refreshes {
+            Ok(refreshes) => {
+                for refresh in refreshes {
+                    writeln!(f, "\t\t- {refresh}")?;
+                }
+            }
+            Err(e) => writeln!(f, "\t\t! {e}")?,
         }
+
+        Ok(())
     }
 }
 
 impl AutoScalingScenario {
-    pub async fn prepare_scenario(sdk_config: &SdkConfig) -> Result<Self, Vec<ScenarioError>> {
+    /// Create the launch template and group, recording each in the ledger as it is created.
+    pub async fn prepare_scenario(
+        sdk_config: &SdkConfig,
+        ledger: Ledger,
+    ) -> Result<Self, Vec<ScenarioError>> {
         let ec2 = aws_sdk_ec2::Client::new(sdk_config);
         let autoscaling = aws_sdk_autoscaling::Client::new(sdk_config);
 
@@ -195,7 +252,8 @@ impl AutoScalingScenario {
         // Before creating any resources, prepare the list of AZs
         let availablity_zones = ec2.describe_availability_zones().send().await;
         if let Err(err) = availablity_zones {
-            return Err(vec![ScenarioError::new("Failed to find AZs", &err)]);
+            return Err(vec![ScenarioError::from_sdk("Failed to find AZs", &err)
+                .operation("DescribeAvailabilityZones")]);
         }
 
         let availability_zones: Vec<String> = availablity_zones
@@ -220,7 +278,12 @@ impl AutoScalingScenario {
             )
             .send()
             .await
-            .map_err(|err| vec![ScenarioError::new("Failed to create launch template", &err)])?;
+            .map_err(|err| {
+                vec![
+                    ScenarioError::from_sdk("Failed to create launch template", &err)
+                        .operation("CreateLaunchTemplate"),
+                ]
+            })?;
 
         let launch_template_arn = match create_launch_template.launch_template {
             Some(launch_template) => launch_template.launch_template_id.unwrap_or_default(),
@@ -234,6 +297,10 @@ impl AutoScalingScenario {
                 return Err(vec![ScenarioError::with("Failed to load launch template")]);
             }
         };
+        ledger.created(Resource::new(
+            ResourceKind::Ec2LaunchTemplate,
+            launch_template_arn.as_str(),
+        ));
 
         // 2. CreateAutoScalingGroup: pass it the launch template you created in step 0. Give it min/max of 1 instance.
         //   You can use EC2.describe_availability_zones() to get a list of AZs (you have to specify an AZ when you create the group).
@@ -253,10 +320,11 @@ impl AutoScalingScenario {
             .send()
             .await
         {
-            let mut errs = vec![ScenarioError::new(
-                "Failed to create autoscaling group",
-                &err,
-            )];
+            let mut errs =
+                vec![
+                    ScenarioError::from_sdk("Failed to create autoscaling group", &err)
+                        .operation("CreateAutoScalingGroup"),
+                ];
 
             if let Err(err) = autoscaling
                 .delete_auto_scaling_group()
@@ -264,10 +332,10 @@ impl AutoScalingScenario {
                 .send()
                 .await
             {
-                errs.push(ScenarioError::new(
-                    "Failed to clean up autoscaling group",
-                    &err,
-                ));
+                errs.push(
+                    ScenarioError::from_sdk("Failed to clean up autoscaling group", &err)
+                        .operation("DeleteAutoScalingGroup"),
+                );
             }
 
             if let Err(err) = ec2
@@ -276,19 +344,35 @@ impl AutoScalingScenario {
                 .send()
                 .await
             {
-                errs.push(ScenarioError::new(
-                    "Failed to clean up launch template",
-                    &err,
-                ));
+                errs.push(
+                    ScenarioError::from_sdk("Failed to clean up launch template", &err)
+                        .operation("DeleteLaunchTemplate"),
+                );
+            } else {
+                ledger.deleted(
+                    ResourceKind::Ec2LaunchTemplate,
+                    launch_template_arn.as_str(),
+                );
             }
             return Err(errs);
         }
+        ledger.created(
+            Resource::new(
+                ResourceKind::AutoScalingGroup,
+                auto_scaling_group_name.as_str(),
+            )
+            .depends_on(
+                ResourceKind::Ec2LaunchTemplate,
+                launch_template_arn.as_str(),
+            ),
+        );
 
         let scenario = AutoScalingScenario {
             ec2,
             autoscaling: autoscaling.clone(), // Clients are cheap so cloning here to prevent a move is ok.
             auto_scaling_group_name: auto_scaling_group_name.clone(),
             launch_template_arn,
+            ledger,
         };
 
         // snippet-start:[rust.auto-scaling.scenario.enable_metrics_collection]
@@ -311,14 +395,41 @@ impl AutoScalingScenario {
             Ok(_) => Ok(scenario),
             Err(err) => {
                 scenario.clean_scenario().await?;
-                Err(vec![ScenarioError::new(
+                Err(vec![ScenarioError::from_sdk(
                     "Failed to enable metrics collections for group",
                     &err,
-                )])
+                )
+                .operation("EnableMetricsCollection")])
             }
         }
     }
 
+    /// Describe and watch a group the scenario didn't create. Don't clean up a scenario made this
+    /// way, unless the group and its launch template should be deleted.
+    pub async fn existing(
+        sdk_config: &SdkConfig,
+        auto_scaling_group_name: &str,
+    ) -> Result<Self, ScenarioError> {
+        let mut scenario = AutoScalingScenario {
+            ec2: aws_sdk_ec2::Client::new(sdk_config),
+            autoscaling: aws_sdk_autoscaling::Client::new(sdk_config),
+            launch_template_arn: String::new(),
+            auto_scaling_group_name: auto_scaling_group_name.to_string(),
+            ledger: Ledger::disabled(),
+        };
+        let group = scenario.get_group().await?;
+        scenario.launch_template_arn = group
+            .launch_template()
+            .and_then(|t| t.launch_template_id())
+            .unwrap_or_default()
+            .to_string();
+        Ok(scenario)
+    }
+
+    pub fn auto_scaling_group_name(&self) -> &str {
+        &self.auto_scaling_group_name
+    }
+
     pub async fn clean_scenario(self) -> Result<(), Vec<ScenarioError>> {
         let _ = self.wait_for_no_scaling().await;
         let delete_group = self
@@ -336,19 +447,33 @@ impl AutoScalingScenario {
             .send()
             .await;
 
+        if delete_launch_template.is_ok() {
+            self.ledger.deleted(
+                ResourceKind::Ec2LaunchTemplate,
+                self.launch_template_arn.as_str(),
+            );
+        }
+
         let early_exit = match (delete_group, delete_launch_template) {
             (Ok(_), Ok(_)) => Ok(()),
-            (Ok(_), Err(e)) => Err(vec![ScenarioError::new(
+            (Ok(_), Err(e)) => Err(vec![ScenarioError::from_sdk(
                 "There was an error cleaning the launch template",
                 &e,
-            )]),
-            (Err(e), Ok(_)) => Err(vec![ScenarioError::new(
+            )
+            .operation("DeleteLaunchTemplate")]),
+            (Err(e), Ok(_)) => Err(vec![ScenarioError::from_sdk(
                 "There was an error cleaning the scale group",
                 &e,
-            )]),
+            )
+            .operation("DeleteAutoScalingGroup")]),
             (Err(e1), Err(e2)) => Err(vec![
-                ScenarioError::new("Multiple error cleaning the scenario Scale Group", &e1),
-                ScenarioError::new("Multiple error cleaning the scenario Launch Template", &e2),
+                ScenarioError::from_sdk("Multiple error cleaning the scenario Scale Group", &e1)
+                    .operation("DeleteAutoScalingGroup"),
+                ScenarioError::from_sdk(
+                    "Multiple error cleaning the scenario Launch Template",
+                    &e2,
+                )
+                .operation("DeleteLaunchTemplate"),
             ]),
         };
 
@@ -380,10 +505,22 @@ impl AutoScalingScenario {
                                 return Err(errors);
                             }
                         }
-                        None => return Ok(()),
+                        None => {
+                            self.ledger.deleted(
+                                ResourceKind::AutoScalingGroup,
+                                self.auto_scaling_group_name.as_str(),
+                            );
+                            return Ok(());
+                        }
                     },
                     Err(err) => {
-                        errors.push(ScenarioError::new("Failed to describe autoscaling group during cleanup 3 times, last error", &err));
+                        errors.push(
+                            ScenarioError::from_sdk(
+                                "Failed to describe autoscaling group during cleanup 3 times, last error",
+                                &err,
+                            )
+                            .operation("DescribeAutoScalingGroups"),
+                        );
                     }
                 }
                 if errors.len() > 3 {
@@ -404,20 +541,10 @@ impl AutoScalingScenario {
             .auto_scaling_group_names(self.auto_scaling_group_name.clone())
             .send()
             .await
-            .map(|s| {
-                s.auto_scaling_groups()
-                    .iter()
-                    .map(|s| {
-                        format!(
-                            "{}: {}",
-                            s.auto_scaling_group_name().unwrap_or("Unknown"),
-                            s.status().unwrap_or("Unknown")
-                        )
-                    })
-                    .collect::<Vec<String>>()
-            })
+            .map(|s| s.auto_scaling_groups().to_vec())
             .map_err(|e| {
-                ScenarioError::new("Failed to describe auto scaling groups for scenario", &e)
+                ScenarioError::from_sdk("Failed to describe auto scaling groups for scenario", &e)
+                    .operation("DescribeAutoScalingGroups")
             });
 
         let instances = self
@@ -449,6 +576,10 @@ impl AutoScalingScenario {
             group,
             instances,
             activities,
+            policies: self.list_policies().await,
+            scheduled_actions: self.list_scheduled_actions().await,
+            lifecycle_hooks: self.list_lifecycle_hooks().await,
+            instance_refreshes: self.list_instance_refreshes().await,
         }
     }
     // snippet-end:[rust.auto-scaling.scenario.describe_scenario]
@@ -463,14 +594,15 @@ impl AutoScalingScenario {
             .await;
 
         if let Err(err) = describe_auto_scaling_groups {
-            return Err(ScenarioError::new(
+            return Err(ScenarioError::from_sdk(
                 format!(
                     "Failed to get status of autoscaling group {}",
                     self.auto_scaling_group_name.clone()
                 )
                 .as_str(),
                 &err,
-            ));
+            )
+            .operation("DescribeAutoScalingGroups"));
         }
 
         let describe_auto_scaling_groups_output = describe_auto_scaling_groups.unwrap();
@@ -500,7 +632,8 @@ impl AutoScalingScenario {
                 .send()
                 .await
                 .map_err(|e| {
-                    ScenarioError::new("Failed to get autoscaling activities for group", &e)
+                    ScenarioError::from_sdk("Failed to get autoscaling activities for group", &e)
+                        .operation("DescribeScalingActivities")
                 })?;
             let activities = describe_activities.activities();
             trace!(
@@ -553,7 +686,10 @@ impl AutoScalingScenario {
                     .filter(|id| !id.is_empty())
                     .collect::<Vec<String>>()
             })
-            .map_err(|err| ScenarioError::new("Failed to get list of auto scaling instances", &err))
+            .map_err(|err| {
+                ScenarioError::from_sdk("Failed to get list of auto scaling instances", &err)
+                    .operation("DescribeAutoScalingInstances")
+            })
     }
     // snippet-end:[rust.auto-scaling.scenario.list_instances]
 
@@ -566,10 +702,11 @@ impl AutoScalingScenario {
             .send()
             .await;
         if let Err(err) = update_group {
-            return Err(ScenarioError::new(
+            return Err(ScenarioError::from_sdk(
                 format!("Failer to update group to min size ({size}))").as_str(),
                 &err,
-            ));
+            )
+            .operation("UpdateAutoScalingGroup"));
         }
         Ok(())
     }
@@ -584,10 +721,11 @@ impl AutoScalingScenario {
             .send()
             .await;
         if let Err(err) = update_group {
-            return Err(ScenarioError::new(
+            return Err(ScenarioError::from_sdk(
                 format!("Failed to update group to max size ({size})").as_str(),
                 &err,
-            ));
+            )
+            .operation("UpdateAutoScalingGroup"));
         }
         Ok(())
     }
@@ -604,10 +742,11 @@ impl AutoScalingScenario {
             .send()
             .await;
         if let Err(err) = update_group {
-            return Err(ScenarioError::new(
+            return Err(ScenarioError::from_sdk(
                 format!("Failed to update group to desired capacity ({capacity}))").as_str(),
                 &err,
-            ));
+            )
+            .operation("SetDesiredCapacity"));
         }
         Ok(())
     }
@@ -624,6 +763,15 @@ impl AutoScalingScenario {
             .await;
         // snippet-end:[rust.auto-scaling.scenario.disable_metrics_collection]
 
+        // Instances would otherwise wait out each terminating hook's heartbeat, and a refresh
+        // in progress would keep replacing them. Either only slows the scale down, so carry on.
+        if let Err(err) = self.remove_lifecycle_hooks().await {
+            warn!("Failed to remove lifecycle hooks before scaling down: {err}");
+        }
+        if let Err(err) = self.cancel_instance_refresh().await {
+            warn!("Failed to cancel instance refresh before scaling down: {err}");
+        }
+
         // 12. DeleteAutoScalingGroup (to delete the group you must stop all instances):
         //   UpdateAutoScalingGroup with MinSize=0
         let update_group = self
@@ -635,10 +783,10 @@ impl AutoScalingScenario {
             .send()
             .await;
         if let Err(err) = update_group {
-            return Err(ScenarioError::new(
-                "Failed to update group for scaling down&",
-                &err,
-            ));
+            return Err(
+                ScenarioError::from_sdk("Failed to update group for scaling down&", &err)
+                    .operation("UpdateAutoScalingGroup"),
+            );
         }
 
         let stable = self.wait_for_stable(0).await;
@@ -671,10 +819,10 @@ impl AutoScalingScenario {
                 .send()
                 .await;
             if let Err(err) = termination {
-                Err(ScenarioError::new(
-                    "There was a problem terminating an instance",
-                    &err,
-                ))
+                Err(
+                    ScenarioError::from_sdk("There was a problem terminating an instance", &err)
+                        .operation("TerminateInstances"),
+                )
             } else {
                 Ok(())
             }
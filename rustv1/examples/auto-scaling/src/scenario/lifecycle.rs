@@ -0,0 +1,296 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Lifecycle hooks pause instances as they launch or terminate, until something completes the
+//! lifecycle action or the hook's heartbeat times out.
+
+use std::{fmt::Display, time::Duration};
+
+use aws_sdk_autoscaling::types::{Instance, LifecycleHook, LifecycleState};
+use tracing::info;
+
+use super::{AutoScalingScenario, ScenarioError, Waiter};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LifecycleTransition {
+    Launching,
+    Terminating,
+}
+
+impl LifecycleTransition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LifecycleTransition::Launching => "autoscaling:EC2_INSTANCE_LAUNCHING",
+            LifecycleTransition::Terminating => "autoscaling:EC2_INSTANCE_TERMINATING",
+        }
+    }
+
+    /// The state an instance waits in while a hook for this transition is active.
+    fn wait_state(&self) -> LifecycleState {
+        match self {
+            LifecycleTransition::Launching => LifecycleState::PendingWait,
+            LifecycleTransition::Terminating => LifecycleState::TerminatingWait,
+        }
+    }
+}
+
+/// How to finish a lifecycle action. Abandoning a launch terminates the instance; abandoning a
+/// termination still terminates it, but skips any remaining hooks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LifecycleResult {
+    Continue,
+    Abandon,
+}
+
+impl LifecycleResult {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LifecycleResult::Continue => "CONTINUE",
+            LifecycleResult::Abandon => "ABANDON",
+        }
+    }
+}
+
+/// An instance waiting on a lifecycle hook.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LifecycleAction {
+    pub hook_name: String,
+    pub instance_id: String,
+    pub transition: LifecycleTransition,
+}
+
+impl Display for LifecycleAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} waiting on {} ({})",
+            self.instance_id,
+            self.hook_name,
+            self.transition.as_str()
+        )
+    }
+}
+
+/// The actions `instances` are waiting on, one for each of `hooks` that matches the transition
+/// an instance is waiting in.
+fn lifecycle_actions(hooks: &[LifecycleHook], instances: &[Instance]) -> Vec<LifecycleAction> {
+    let mut actions = vec![];
+    for transition in [
+        LifecycleTransition::Launching,
+        LifecycleTransition::Terminating,
+    ] {
+        let waiting = instances
+            .iter()
+            .filter(|i| i.lifecycle_state() == Some(&transition.wait_state()))
+            .filter_map(|i| i.instance_id());
+        for instance_id in waiting {
+            // Every hook for the transition is active at once, and each must be completed.
+            for hook in hooks
+                .iter()
+                .filter(|h| h.lifecycle_transition() == Some(transition.as_str()))
+            {
+                actions.push(LifecycleAction {
+                    hook_name: hook.lifecycle_hook_name().unwrap_or_default().to_string(),
+                    instance_id: instance_id.to_string(),
+                    transition,
+                });
+            }
+        }
+    }
+    actions
+}
+
+impl AutoScalingScenario {
+    // snippet-start:[rust.auto-scaling.scenario.put_lifecycle_hook]
+    /// Pause instances in `transition` until their lifecycle action is completed, or until
+    /// `heartbeat_timeout` passes and the hook finishes with `default_result`.
+    pub async fn put_lifecycle_hook(
+        &self,
+        name: &str,
+        transition: LifecycleTransition,
+        heartbeat_timeout: Duration,
+        default_result: LifecycleResult,
+    ) -> Result<(), ScenarioError> {
+        self.autoscaling
+            .put_lifecycle_hook()
+            .auto_scaling_group_name(self.auto_scaling_group_name.clone())
+            .lifecycle_hook_name(name)
+            .lifecycle_transition(transition.as_str())
+            .heartbeat_timeout(heartbeat_timeout.as_secs() as i32)
+            .default_result(default_result.as_str())
+            .send()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to put lifecycle hook {name}"), &err)
+                    .operation("PutLifecycleHook")
+            })?;
+        Ok(())
+    }
+    // snippet-end:[rust.auto-scaling.scenario.put_lifecycle_hook]
+
+    pub async fn list_lifecycle_hooks(&self) -> Result<Vec<LifecycleHook>, ScenarioError> {
+        self.autoscaling
+            .describe_lifecycle_hooks()
+            .auto_scaling_group_name(self.auto_scaling_group_name.clone())
+            .send()
+            .await
+            .map(|output| output.lifecycle_hooks.unwrap_or_default())
+            .map_err(|err| {
+                ScenarioError::from_sdk("Failed to describe lifecycle hooks", &err)
+                    .operation("DescribeLifecycleHooks")
+            })
+    }
+
+    pub async fn delete_lifecycle_hook(&self, name: &str) -> Result<(), ScenarioError> {
+        self.autoscaling
+            .delete_lifecycle_hook()
+            .auto_scaling_group_name(self.auto_scaling_group_name.clone())
+            .lifecycle_hook_name(name)
+            .send()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to delete lifecycle hook {name}"), &err)
+                    .operation("DeleteLifecycleHook")
+            })?;
+        Ok(())
+    }
+
+    /// Delete every lifecycle hook, so scaling no longer pauses for them.
+    pub async fn remove_lifecycle_hooks(&self) -> Result<(), ScenarioError> {
+        for hook in self.list_lifecycle_hooks().await? {
+            if let Some(name) = hook.lifecycle_hook_name() {
+                self.delete_lifecycle_hook(name).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The lifecycle actions instances in the group are currently waiting on.
+    pub async fn pending_lifecycle_actions(&self) -> Result<Vec<LifecycleAction>, ScenarioError> {
+        let hooks = self.list_lifecycle_hooks().await?;
+        let group = self.get_group().await?;
+        Ok(lifecycle_actions(&hooks, group.instances()))
+    }
+
+    // snippet-start:[rust.auto-scaling.scenario.complete_lifecycle_action]
+    /// Ask `handler` how to finish each pending lifecycle action, and complete it. Returns the
+    /// actions completed, with how each was finished.
+    pub async fn complete_lifecycle_actions(
+        &self,
+        handler: impl Fn(&LifecycleAction) -> LifecycleResult,
+    ) -> Result<Vec<(LifecycleAction, LifecycleResult)>, ScenarioError> {
+        let mut completed = vec![];
+        for action in self.pending_lifecycle_actions().await? {
+            let result = handler(&action);
+            self.autoscaling
+                .complete_lifecycle_action()
+                .auto_scaling_group_name(self.auto_scaling_group_name.clone())
+                .lifecycle_hook_name(action.hook_name.clone())
+                .instance_id(action.instance_id.clone())
+                .lifecycle_action_result(result.as_str())
+                .send()
+                .await
+                .map_err(|err| {
+                    ScenarioError::from_sdk(
+                        format!("Failed to complete lifecycle action {action}"),
+                        &err,
+                    )
+                    .operation("CompleteLifecycleAction")
+                })?;
+            info!(
+                "Completed lifecycle action {action} with {}",
+                result.as_str()
+            );
+            completed.push((action, result));
+        }
+        Ok(completed)
+    }
+    // snippet-end:[rust.auto-scaling.scenario.complete_lifecycle_action]
+
+    /// Keep completing lifecycle actions with `handler` until the group has `size` instances and
+    /// none are waiting on a hook.
+    pub async fn wait_for_stable_with_hooks(
+        &self,
+        size: usize,
+        handler: impl Fn(&LifecycleAction) -> LifecycleResult,
+    ) -> Result<Vec<(LifecycleAction, LifecycleResult)>, ScenarioError> {
+        let waiter = Waiter::new();
+        let mut completed = vec![];
+        loop {
+            completed.extend(self.complete_lifecycle_actions(&handler).await?);
+            let group = self.get_group().await?;
+            let in_service = group
+                .instances()
+                .iter()
+                .filter(|i| i.lifecycle_state() == Some(&LifecycleState::InService))
+                .count();
+            if in_service == size && super::count_group_instances(&group) == size {
+                return Ok(completed);
+            }
+            waiter.sleep().await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hook(name: &str, transition: LifecycleTransition) -> LifecycleHook {
+        LifecycleHook::builder()
+            .lifecycle_hook_name(name)
+            .lifecycle_transition(transition.as_str())
+            .build()
+    }
+
+    fn instance(id: &str, state: LifecycleState) -> Instance {
+        Instance::builder()
+            .instance_id(id)
+            .lifecycle_state(state)
+            .build()
+    }
+
+    #[test]
+    fn test_lifecycle_actions_match_hooks_to_waiting_instances() {
+        let hooks = [
+            hook("launch-check", LifecycleTransition::Launching),
+            hook("launch-config", LifecycleTransition::Launching),
+            hook("drain", LifecycleTransition::Terminating),
+        ];
+        let instances = [
+            instance("i-1", LifecycleState::PendingWait),
+            instance("i-2", LifecycleState::InService),
+            instance("i-3", LifecycleState::TerminatingWait),
+            instance("i-4", LifecycleState::Pending),
+        ];
+        let action = |hook: &str, instance: &str, transition| LifecycleAction {
+            hook_name: hook.into(),
+            instance_id: instance.into(),
+            transition,
+        };
+
+        assert_eq!(
+            lifecycle_actions(&hooks, &instances),
+            vec![
+                action("launch-check", "i-1", LifecycleTransition::Launching),
+                action("launch-config", "i-1", LifecycleTransition::Launching),
+                action("drain", "i-3", LifecycleTransition::Terminating),
+            ]
+        );
+        assert!(lifecycle_actions(&[], &instances).is_empty());
+        assert!(lifecycle_actions(&hooks[..2], &instances[2..]).is_empty());
+    }
+
+    #[test]
+    fn test_display_lifecycle_action() {
+        let action = LifecycleAction {
+            hook_name: "drain".into(),
+            instance_id: "i-1".into(),
+            transition: LifecycleTransition::Terminating,
+        };
+        assert_eq!(
+            action.to_string(),
+            "i-1 waiting on drain (autoscaling:EC2_INSTANCE_TERMINATING)"
+        );
+    }
+}
@@ -0,0 +1,266 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Scaling policies and scheduled actions, which change the group's capacity without a direct call
+//! to SetDesiredCapacity.
+
+use std::time::SystemTime;
+
+use aws_sdk_autoscaling::{
+    primitives::DateTime,
+    types::{
+        MetricType, PredefinedMetricSpecification, ScalingPolicy, ScheduledUpdateGroupAction,
+        StepAdjustment, TargetTrackingConfiguration,
+    },
+};
+
+use super::{AutoScalingScenario, ScenarioError};
+
+/// One step of a step scaling policy. The bounds are relative to the alarm's breach threshold, and
+/// an open bound extends to infinity.
+#[derive(Clone, Debug)]
+pub struct Step {
+    pub lower_bound: Option<f64>,
+    pub upper_bound: Option<f64>,
+    /// The number of instances to add, or remove when negative.
+    pub adjustment: i32,
+}
+
+/// A scheduled change to the group's size. Only the sizes that are set are changed.
+#[derive(Clone, Debug, Default)]
+pub struct Schedule {
+    pub name: String,
+    pub start_time: Option<SystemTime>,
+    /// A cron expression, in UTC, for actions that repeat.
+    pub recurrence: Option<String>,
+    pub min_size: Option<i32>,
+    pub max_size: Option<i32>,
+    pub desired_capacity: Option<i32>,
+}
+
+fn step_adjustments(steps: &[Step]) -> Result<Vec<StepAdjustment>, ScenarioError> {
+    steps
+        .iter()
+        .map(|step| {
+            StepAdjustment::builder()
+                .set_metric_interval_lower_bound(step.lower_bound)
+                .set_metric_interval_upper_bound(step.upper_bound)
+                .scaling_adjustment(step.adjustment)
+                .build()
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| ScenarioError::with(format!("Failed to build step scaling policy: {err}")))
+}
+
+impl AutoScalingScenario {
+    // snippet-start:[rust.auto-scaling.scenario.put_target_tracking_policy]
+    /// Keep a predefined metric, like average CPU, near a target by adding and removing instances.
+    /// Returns the policy's ARN.
+    pub async fn put_target_tracking_policy(
+        &self,
+        name: &str,
+        metric: MetricType,
+        target_value: f64,
+        disable_scale_in: bool,
+    ) -> Result<String, ScenarioError> {
+        let configuration = PredefinedMetricSpecification::builder()
+            .predefined_metric_type(metric)
+            .build()
+            .and_then(|metric| {
+                TargetTrackingConfiguration::builder()
+                    .predefined_metric_specification(metric)
+                    .target_value(target_value)
+                    .disable_scale_in(disable_scale_in)
+                    .build()
+            })
+            .map_err(|err| {
+                ScenarioError::with(format!("Failed to build target tracking policy: {err}"))
+            })?;
+
+        let policy = self
+            .autoscaling
+            .put_scaling_policy()
+            .auto_scaling_group_name(self.auto_scaling_group_name.clone())
+            .policy_name(name)
+            .policy_type("TargetTrackingScaling")
+            .target_tracking_configuration(configuration)
+            .send()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(
+                    format!("Failed to put target tracking policy {name}"),
+                    &err,
+                )
+                .operation("PutScalingPolicy")
+            })?;
+
+        Ok(policy.policy_arn.unwrap_or_default())
+    }
+    // snippet-end:[rust.auto-scaling.scenario.put_target_tracking_policy]
+
+    // snippet-start:[rust.auto-scaling.scenario.put_step_scaling_policy]
+    /// Change capacity by a different amount depending on how far an alarm's metric is past its
+    /// threshold. Returns the policy's ARN.
+    pub async fn put_step_scaling_policy(
+        &self,
+        name: &str,
+        steps: &[Step],
+    ) -> Result<String, ScenarioError> {
+        let step_adjustments = step_adjustments(steps)?;
+        let policy = self
+            .autoscaling
+            .put_scaling_policy()
+            .auto_scaling_group_name(self.auto_scaling_group_name.clone())
+            .policy_name(name)
+            .policy_type("StepScaling")
+            .adjustment_type("ChangeInCapacity")
+            .metric_aggregation_type("Average")
+            .set_step_adjustments(Some(step_adjustments))
+            .send()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to put step scaling policy {name}"), &err)
+                    .operation("PutScalingPolicy")
+            })?;
+
+        Ok(policy.policy_arn.unwrap_or_default())
+    }
+    // snippet-end:[rust.auto-scaling.scenario.put_step_scaling_policy]
+
+    /// Run a step scaling policy as if its alarm had reported `metric_value` against `breach_threshold`.
+    pub async fn execute_policy(
+        &self,
+        name: &str,
+        metric_value: f64,
+        breach_threshold: f64,
+    ) -> Result<(), ScenarioError> {
+        self.autoscaling
+            .execute_policy()
+            .auto_scaling_group_name(self.auto_scaling_group_name.clone())
+            .policy_name(name)
+            .metric_value(metric_value)
+            .breach_threshold(breach_threshold)
+            .honor_cooldown(false)
+            .send()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to execute policy {name}"), &err)
+                    .operation("ExecutePolicy")
+            })?;
+        Ok(())
+    }
+
+    pub async fn list_policies(&self) -> Result<Vec<ScalingPolicy>, ScenarioError> {
+        self.autoscaling
+            .describe_policies()
+            .auto_scaling_group_name(self.auto_scaling_group_name.clone())
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk("Failed to describe scaling policies", &err)
+                    .operation("DescribePolicies")
+            })
+    }
+
+    pub async fn delete_policy(&self, name: &str) -> Result<(), ScenarioError> {
+        self.autoscaling
+            .delete_policy()
+            .auto_scaling_group_name(self.auto_scaling_group_name.clone())
+            .policy_name(name)
+            .send()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to delete policy {name}"), &err)
+                    .operation("DeletePolicy")
+            })?;
+        Ok(())
+    }
+
+    // snippet-start:[rust.auto-scaling.scenario.put_scheduled_update_group_action]
+    pub async fn put_scheduled_action(&self, schedule: &Schedule) -> Result<(), ScenarioError> {
+        self.autoscaling
+            .put_scheduled_update_group_action()
+            .auto_scaling_group_name(self.auto_scaling_group_name.clone())
+            .scheduled_action_name(schedule.name.clone())
+            .set_start_time(schedule.start_time.map(DateTime::from))
+            .set_recurrence(schedule.recurrence.clone())
+            .set_min_size(schedule.min_size)
+            .set_max_size(schedule.max_size)
+            .set_desired_capacity(schedule.desired_capacity)
+            .send()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(
+                    format!("Failed to put scheduled action {}", schedule.name),
+                    &err,
+                )
+                .operation("PutScheduledUpdateGroupAction")
+            })?;
+        Ok(())
+    }
+    // snippet-end:[rust.auto-scaling.scenario.put_scheduled_update_group_action]
+
+    pub async fn list_scheduled_actions(
+        &self,
+    ) -> Result<Vec<ScheduledUpdateGroupAction>, ScenarioError> {
+        self.autoscaling
+            .describe_scheduled_actions()
+            .auto_scaling_group_name(self.auto_scaling_group_name.clone())
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk("Failed to describe scheduled actions", &err)
+                    .operation("DescribeScheduledActions")
+            })
+    }
+
+    pub async fn delete_scheduled_action(&self, name: &str) -> Result<(), ScenarioError> {
+        self.autoscaling
+            .delete_scheduled_action()
+            .auto_scaling_group_name(self.auto_scaling_group_name.clone())
+            .scheduled_action_name(name)
+            .send()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to delete scheduled action {name}"), &err)
+                    .operation("DeleteScheduledAction")
+            })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_step_adjustments() {
+        let steps = [
+            Step {
+                lower_bound: Some(0.0),
+                upper_bound: Some(20.0),
+                adjustment: 1,
+            },
+            Step {
+                lower_bound: Some(20.0),
+                upper_bound: None,
+                adjustment: 2,
+            },
+        ];
+
+        let adjustments = step_adjustments(&steps).unwrap();
+
+        assert_eq!(adjustments.len(), 2);
+        assert_eq!(adjustments[0].metric_interval_lower_bound(), Some(0.0));
+        assert_eq!(adjustments[0].metric_interval_upper_bound(), Some(20.0));
+        assert_eq!(adjustments[0].scaling_adjustment(), 1);
+        assert_eq!(adjustments[1].metric_interval_upper_bound(), None);
+        assert_eq!(adjustments[1].scaling_adjustment(), 2);
+    }
+}
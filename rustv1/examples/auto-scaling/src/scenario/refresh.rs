@@ -0,0 +1,299 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Roll a new launch template version out to the group with an instance refresh.
+
+use std::{fmt::Display, time::Duration};
+
+use aws_sdk_autoscaling::types::{
+    InstanceRefresh, InstanceRefreshStatus, RefreshPreferences, RefreshStrategy,
+};
+use aws_sdk_ec2::types::{InstanceType, RequestLaunchTemplateData};
+
+use super::{AutoScalingScenario, LifecycleAction, LifecycleResult, ScenarioError, Waiter};
+
+// Each instance replaced waits out its warmup, so a refresh takes several minutes per instance.
+const REFRESH_MAX_WAIT: Duration = Duration::from_secs(30 * 60);
+const REFRESH_POLL: Duration = Duration::from_secs(15);
+
+/// Where an instance refresh is up to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RefreshProgress {
+    pub id: String,
+    pub status: Option<InstanceRefreshStatus>,
+    pub status_reason: Option<String>,
+    pub percentage_complete: i32,
+    pub instances_to_update: i32,
+}
+
+impl RefreshProgress {
+    /// Whether the refresh has stopped, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.status,
+            Some(
+                InstanceRefreshStatus::Successful
+                    | InstanceRefreshStatus::Failed
+                    | InstanceRefreshStatus::Cancelled
+                    | InstanceRefreshStatus::RollbackSuccessful
+                    | InstanceRefreshStatus::RollbackFailed
+            )
+        )
+    }
+
+    pub fn is_successful(&self) -> bool {
+        self.status == Some(InstanceRefreshStatus::Successful)
+    }
+}
+
+impl From<&InstanceRefresh> for RefreshProgress {
+    fn from(refresh: &InstanceRefresh) -> Self {
+        RefreshProgress {
+            id: refresh
+                .instance_refresh_id()
+                .unwrap_or_default()
+                .to_string(),
+            status: refresh.status().cloned(),
+            status_reason: refresh.status_reason().map(String::from),
+            percentage_complete: refresh.percentage_complete().unwrap_or(0),
+            instances_to_update: refresh.instances_to_update().unwrap_or(0),
+        }
+    }
+}
+
+impl Display for RefreshProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}: {}% complete, {} instances to update",
+            self.id,
+            self.status
+                .as_ref()
+                .map(|s| s.as_str())
+                .unwrap_or("Unknown"),
+            self.percentage_complete,
+            self.instances_to_update
+        )?;
+        if let Some(reason) = &self.status_reason {
+            write!(f, " ({reason})")?;
+        }
+        Ok(())
+    }
+}
+
+impl AutoScalingScenario {
+    /// Add a launch template version that uses `instance_type`. The group launches from the
+    /// latest version, so new instances, and an instance refresh, pick it up. Returns the version.
+    pub async fn new_launch_template_version(
+        &self,
+        instance_type: InstanceType,
+    ) -> Result<i64, ScenarioError> {
+        let version = self
+            .ec2
+            .create_launch_template_version()
+            .launch_template_id(self.launch_template_arn.clone())
+            .source_version("$Latest")
+            .launch_template_data(
+                RequestLaunchTemplateData::builder()
+                    .instance_type(instance_type)
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk("Failed to create launch template version", &err)
+                    .operation("CreateLaunchTemplateVersion")
+            })?;
+
+        version
+            .launch_template_version()
+            .and_then(|v| v.version_number())
+            .ok_or_else(|| ScenarioError::with("Created launch template version has no number"))
+    }
+
+    // snippet-start:[rust.auto-scaling.scenario.start_instance_refresh]
+    /// Replace the group's instances a few at a time, keeping at least `min_healthy_percentage`
+    /// of capacity in service. Returns the refresh's ID.
+    pub async fn start_instance_refresh(
+        &self,
+        min_healthy_percentage: i32,
+        instance_warmup: Duration,
+    ) -> Result<String, ScenarioError> {
+        let refresh = self
+            .autoscaling
+            .start_instance_refresh()
+            .auto_scaling_group_name(self.auto_scaling_group_name.clone())
+            .strategy(RefreshStrategy::Rolling)
+            .preferences(
+                RefreshPreferences::builder()
+                    .min_healthy_percentage(min_healthy_percentage)
+                    .instance_warmup(instance_warmup.as_secs() as i32)
+                    .skip_matching(true)
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk("Failed to start instance refresh", &err)
+                    .operation("StartInstanceRefresh")
+            })?;
+
+        refresh
+            .instance_refresh_id
+            .ok_or_else(|| ScenarioError::with("Started instance refresh has no ID"))
+    }
+    // snippet-end:[rust.auto-scaling.scenario.start_instance_refresh]
+
+    /// The group's instance refreshes, newest first.
+    pub async fn list_instance_refreshes(&self) -> Result<Vec<RefreshProgress>, ScenarioError> {
+        self.autoscaling
+            .describe_instance_refreshes()
+            .auto_scaling_group_name(self.auto_scaling_group_name.clone())
+            .send()
+            .await
+            .map(|output| {
+                output
+                    .instance_refreshes()
+                    .iter()
+                    .map(RefreshProgress::from)
+                    .collect()
+            })
+            .map_err(|err| {
+                ScenarioError::from_sdk("Failed to describe instance refreshes", &err)
+                    .operation("DescribeInstanceRefreshes")
+            })
+    }
+
+    pub async fn instance_refresh_progress(
+        &self,
+        id: &str,
+    ) -> Result<RefreshProgress, ScenarioError> {
+        let refreshes = self
+            .autoscaling
+            .describe_instance_refreshes()
+            .auto_scaling_group_name(self.auto_scaling_group_name.clone())
+            .instance_refresh_ids(id)
+            .send()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to describe instance refresh {id}"), &err)
+                    .operation("DescribeInstanceRefreshes")
+            })?;
+
+        refreshes
+            .instance_refreshes()
+            .first()
+            .map(RefreshProgress::from)
+            .ok_or_else(|| ScenarioError::with(format!("Could not find instance refresh {id}")))
+    }
+
+    // snippet-start:[rust.auto-scaling.scenario.wait_for_instance_refresh]
+    /// Poll the refresh until it finishes, reporting each change in progress to `on_progress`.
+    /// Instances the refresh launches still wait on lifecycle hooks, so `handler` completes
+    /// their actions along the way.
+    pub async fn wait_for_instance_refresh(
+        &self,
+        id: &str,
+        handler: impl Fn(&LifecycleAction) -> LifecycleResult,
+        mut on_progress: impl FnMut(&RefreshProgress),
+    ) -> Result<RefreshProgress, ScenarioError> {
+        let waiter = Waiter::with(REFRESH_MAX_WAIT, REFRESH_POLL);
+        let mut last: Option<RefreshProgress> = None;
+        loop {
+            self.complete_lifecycle_actions(&handler).await?;
+            let progress = self.instance_refresh_progress(id).await?;
+            if last.as_ref() != Some(&progress) {
+                on_progress(&progress);
+            }
+            if progress.is_finished() {
+                return Ok(progress);
+            }
+            last = Some(progress);
+            waiter.sleep().await?;
+        }
+    }
+    // snippet-end:[rust.auto-scaling.scenario.wait_for_instance_refresh]
+
+    /// Cancel the group's active instance refresh, if there is one.
+    pub async fn cancel_instance_refresh(&self) -> Result<(), ScenarioError> {
+        match self
+            .autoscaling
+            .cancel_instance_refresh()
+            .auto_scaling_group_name(self.auto_scaling_group_name.clone())
+            .send()
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err)
+                if err
+                    .as_service_error()
+                    .map(|e| e.is_active_instance_refresh_not_found_fault())
+                    == Some(true) =>
+            {
+                Ok(())
+            }
+            Err(err) => Err(
+                ScenarioError::from_sdk("Failed to cancel instance refresh", &err)
+                    .operation("CancelInstanceRefresh"),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn progress(status: Option<InstanceRefreshStatus>) -> RefreshProgress {
+        RefreshProgress {
+            id: "refresh-1".into(),
+            status,
+            status_reason: None,
+            percentage_complete: 50,
+            instances_to_update: 1,
+        }
+    }
+
+    #[test]
+    fn test_refresh_progress_status() {
+        for status in [
+            InstanceRefreshStatus::Pending,
+            InstanceRefreshStatus::InProgress,
+            InstanceRefreshStatus::Cancelling,
+            InstanceRefreshStatus::RollbackInProgress,
+        ] {
+            let progress = progress(Some(status.clone()));
+            assert!(!progress.is_finished(), "{status:?}");
+            assert!(!progress.is_successful(), "{status:?}");
+        }
+        for status in [
+            InstanceRefreshStatus::Failed,
+            InstanceRefreshStatus::Cancelled,
+            InstanceRefreshStatus::RollbackSuccessful,
+            InstanceRefreshStatus::RollbackFailed,
+        ] {
+            let progress = progress(Some(status.clone()));
+            assert!(progress.is_finished(), "{status:?}");
+            assert!(!progress.is_successful(), "{status:?}");
+        }
+        let successful = progress(Some(InstanceRefreshStatus::Successful));
+        assert!(successful.is_finished());
+        assert!(successful.is_successful());
+        assert!(!progress(None).is_finished());
+    }
+
+    #[test]
+    fn test_display_refresh_progress() {
+        let mut progress = progress(Some(InstanceRefreshStatus::InProgress));
+        assert_eq!(
+            progress.to_string(),
+            "refresh-1 InProgress: 50% complete, 1 instances to update"
+        );
+        progress.status = None;
+        progress.status_reason = Some("Waiting for warmup".into());
+        assert_eq!(
+            progress.to_string(),
+            "refresh-1 Unknown: 50% complete, 1 instances to update (Waiting for warmup)"
+        );
+    }
+}
@@ -0,0 +1,859 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A serializable model of the group's state, and the changes between two of them, for dashboards
+//! and assertions that can't parse the text description.
+
+use std::{
+    fmt::Display,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use aws_sdk_autoscaling::{
+    primitives::{DateTime, DateTimeFormat},
+    types::{
+        Activity, AutoScalingGroup, Instance, LifecycleHook, ScalingPolicy,
+        ScheduledUpdateGroupAction,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use super::{AutoScalingScenario, AutoScalingScenarioDescription, RefreshProgress, ScenarioError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Yaml,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capacity {
+    pub min_size: i32,
+    pub max_size: i32,
+    pub desired_capacity: i32,
+}
+
+impl Display for Capacity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "min {} max {} desired {}",
+            self.min_size, self.max_size, self.desired_capacity
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstanceSnapshot {
+    pub instance_id: String,
+    pub availability_zone: Option<String>,
+    pub lifecycle_state: Option<String>,
+    pub health_status: Option<String>,
+    pub instance_type: Option<String>,
+    pub launch_template_id: Option<String>,
+    pub launch_template_version: Option<String>,
+    pub protected_from_scale_in: bool,
+}
+
+impl From<&Instance> for InstanceSnapshot {
+    fn from(instance: &Instance) -> Self {
+        let template = instance.launch_template();
+        InstanceSnapshot {
+            instance_id: instance.instance_id().unwrap_or_default().to_string(),
+            availability_zone: instance.availability_zone().map(String::from),
+            lifecycle_state: instance.lifecycle_state().map(|s| s.as_str().to_string()),
+            health_status: instance.health_status().map(String::from),
+            instance_type: instance.instance_type().map(String::from),
+            launch_template_id: template
+                .and_then(|t| t.launch_template_id())
+                .map(String::from),
+            launch_template_version: template.and_then(|t| t.version()).map(String::from),
+            protected_from_scale_in: instance.protected_from_scale_in().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivitySnapshot {
+    pub activity_id: String,
+    pub cause: Option<String>,
+    pub status: Option<String>,
+    pub progress: Option<i32>,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+}
+
+fn format_time(time: Option<&DateTime>) -> Option<String> {
+    time.and_then(|t| t.fmt(DateTimeFormat::DateTime).ok())
+}
+
+impl From<&Activity> for ActivitySnapshot {
+    fn from(activity: &Activity) -> Self {
+        ActivitySnapshot {
+            activity_id: activity.activity_id().unwrap_or_default().to_string(),
+            cause: activity.cause().map(String::from),
+            status: activity.status_code().map(|s| s.as_str().to_string()),
+            progress: activity.progress(),
+            start_time: format_time(activity.start_time()),
+            end_time: format_time(activity.end_time()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicySnapshot {
+    pub name: String,
+    pub policy_type: Option<String>,
+}
+
+impl From<&ScalingPolicy> for PolicySnapshot {
+    fn from(policy: &ScalingPolicy) -> Self {
+        PolicySnapshot {
+            name: policy.policy_name().unwrap_or_default().to_string(),
+            policy_type: policy.policy_type().map(String::from),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledActionSnapshot {
+    pub name: String,
+    pub start_time: Option<String>,
+    pub recurrence: Option<String>,
+    pub min_size: Option<i32>,
+    pub max_size: Option<i32>,
+    pub desired_capacity: Option<i32>,
+}
+
+impl From<&ScheduledUpdateGroupAction> for ScheduledActionSnapshot {
+    fn from(action: &ScheduledUpdateGroupAction) -> Self {
+        ScheduledActionSnapshot {
+            name: action
+                .scheduled_action_name()
+                .unwrap_or_default()
+                .to_string(),
+            start_time: format_time(action.start_time()),
+            recurrence: action.recurrence().map(String::from),
+            min_size: action.min_size(),
+            max_size: action.max_size(),
+            desired_capacity: action.desired_capacity(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HookSnapshot {
+    pub name: String,
+    pub transition: Option<String>,
+}
+
+impl From<&LifecycleHook> for HookSnapshot {
+    fn from(hook: &LifecycleHook) -> Self {
+        HookSnapshot {
+            name: hook.lifecycle_hook_name().unwrap_or_default().to_string(),
+            transition: hook.lifecycle_transition().map(String::from),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefreshSnapshot {
+    pub id: String,
+    pub status: Option<String>,
+    pub percentage_complete: i32,
+    pub instances_to_update: i32,
+}
+
+impl From<&RefreshProgress> for RefreshSnapshot {
+    fn from(progress: &RefreshProgress) -> Self {
+        RefreshSnapshot {
+            id: progress.id.clone(),
+            status: progress.status.as_ref().map(|s| s.as_str().to_string()),
+            percentage_complete: progress.percentage_complete,
+            instances_to_update: progress.instances_to_update,
+        }
+    }
+}
+
+/// A part of the group's state that is described by its own call, and so can fail on its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Section {
+    /// The group's status, capacity, and instances.
+    Group,
+    Activities,
+    Policies,
+    ScheduledActions,
+    LifecycleHooks,
+    InstanceRefreshes,
+}
+
+/// The group's state at one moment. Parts that could not be described are left empty, listed in
+/// `unavailable`, and the reason is in `errors`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupSnapshot {
+    pub name: String,
+    /// Seconds since the Unix epoch.
+    pub taken_at: u64,
+    /// Only set while the group is being deleted.
+    pub status: Option<String>,
+    pub capacity: Capacity,
+    pub instances: Vec<InstanceSnapshot>,
+    pub activities: Vec<ActivitySnapshot>,
+    pub policies: Vec<PolicySnapshot>,
+    pub scheduled_actions: Vec<ScheduledActionSnapshot>,
+    pub lifecycle_hooks: Vec<HookSnapshot>,
+    pub instance_refreshes: Vec<RefreshSnapshot>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unavailable: Vec<Section>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+/// One difference between two snapshots of the group.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum ScenarioChange {
+    GroupStatus {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    Capacity {
+        from: Capacity,
+        to: Capacity,
+    },
+    InstanceAdded {
+        instance: InstanceSnapshot,
+    },
+    InstanceRemoved {
+        instance_id: String,
+    },
+    /// One of an instance's lifecycle state, health status, or launch template version changed.
+    InstanceChanged {
+        instance_id: String,
+        field: String,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    ActivityStarted {
+        activity: ActivitySnapshot,
+    },
+    ActivityUpdated {
+        activity_id: String,
+        status: Option<String>,
+        progress: Option<i32>,
+    },
+    PolicyAdded {
+        name: String,
+    },
+    PolicyRemoved {
+        name: String,
+    },
+    ScheduledActionAdded {
+        action: ScheduledActionSnapshot,
+    },
+    ScheduledActionRemoved {
+        name: String,
+    },
+    LifecycleHookAdded {
+        name: String,
+    },
+    LifecycleHookRemoved {
+        name: String,
+    },
+    InstanceRefresh {
+        refresh: RefreshSnapshot,
+    },
+}
+
+impl Display for ScenarioChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let or_none = |value: &Option<String>| value.clone().unwrap_or_else(|| "none".into());
+        match self {
+            ScenarioChange::GroupStatus { from, to } => {
+                write!(f, "Group status {} -> {}", or_none(from), or_none(to))
+            }
+            ScenarioChange::Capacity { from, to } => write!(f, "Capacity {from} -> {to}"),
+            ScenarioChange::InstanceAdded { instance } => write!(
+                f,
+                "Instance {} added in {}",
+                instance.instance_id,
+                or_none(&instance.availability_zone)
+            ),
+            ScenarioChange::InstanceRemoved { instance_id } => {
+                write!(f, "Instance {instance_id} removed")
+            }
+            ScenarioChange::InstanceChanged {
+                instance_id,
+                field,
+                from,
+                to,
+            } => write!(
+                f,
+                "Instance {instance_id} {field} {} -> {}",
+                or_none(from),
+                or_none(to)
+            ),
+            ScenarioChange::ActivityStarted { activity } => write!(
+                f,
+                "Activity started: {}",
+                activity.cause.as_deref().unwrap_or("Unknown")
+            ),
+            ScenarioChange::ActivityUpdated {
+                activity_id,
+                status,
+                progress,
+            } => write!(
+                f,
+                "Activity {activity_id} {} {}%",
+                or_none(status),
+                progress.unwrap_or(-1)
+            ),
+            ScenarioChange::PolicyAdded { name } => write!(f, "Policy {name} added"),
+            ScenarioChange::PolicyRemoved { name } => write!(f, "Policy {name} removed"),
+            ScenarioChange::ScheduledActionAdded { action } => write!(
+                f,
+                "Scheduled action {} added, starting {}",
+                action.name,
+                or_none(&action.start_time)
+            ),
+            ScenarioChange::ScheduledActionRemoved { name } => {
+                write!(f, "Scheduled action {name} removed")
+            }
+            ScenarioChange::LifecycleHookAdded { name } => {
+                write!(f, "Lifecycle hook {name} added")
+            }
+            ScenarioChange::LifecycleHookRemoved { name } => {
+                write!(f, "Lifecycle hook {name} removed")
+            }
+            ScenarioChange::InstanceRefresh { refresh } => write!(
+                f,
+                "Instance refresh {} {} {}%",
+                refresh.id,
+                or_none(&refresh.status),
+                refresh.percentage_complete
+            ),
+        }
+    }
+}
+
+impl GroupSnapshot {
+    pub fn from_description(name: &str, description: &AutoScalingScenarioDescription) -> Self {
+        let mut errors = vec![];
+        let mut unavailable = vec![];
+        let mut snapshot = GroupSnapshot {
+            name: name.to_string(),
+            taken_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+
+        match &description.group {
+            Ok(groups) => match groups.first() {
+                Some(group) => snapshot.fill_group(group),
+                None => {
+                    unavailable.push(Section::Group);
+                    errors.push(format!("Could not find autoscaling group {name}"));
+                }
+            },
+            Err(err) => {
+                unavailable.push(Section::Group);
+                errors.push(err.to_string());
+            }
+        }
+        match &description.activities {
+            Ok(activities) => snapshot.activities = activities.iter().map(Into::into).collect(),
+            Err(err) => {
+                unavailable.push(Section::Activities);
+                errors.push(err.to_string());
+            }
+        }
+        match &description.policies {
+            Ok(policies) => snapshot.policies = policies.iter().map(Into::into).collect(),
+            Err(err) => {
+                unavailable.push(Section::Policies);
+                errors.push(err.to_string());
+            }
+        }
+        match &description.scheduled_actions {
+            Ok(actions) => snapshot.scheduled_actions = actions.iter().map(Into::into).collect(),
+            Err(err) => {
+                unavailable.push(Section::ScheduledActions);
+                errors.push(err.to_string());
+            }
+        }
+        match &description.lifecycle_hooks {
+            Ok(hooks) => snapshot.lifecycle_hooks = hooks.iter().map(Into::into).collect(),
+            Err(err) => {
+                unavailable.push(Section::LifecycleHooks);
+                errors.push(err.to_string());
+            }
+        }
+        match &description.instance_refreshes {
+            Ok(refreshes) => {
+                snapshot.instance_refreshes = refreshes.iter().map(Into::into).collect()
+            }
+            Err(err) => {
+                unavailable.push(Section::InstanceRefreshes);
+                errors.push(err.to_string());
+            }
+        }
+
+        snapshot.unavailable = unavailable;
+        snapshot.errors = errors;
+        snapshot
+    }
+
+    fn is_available(&self, section: Section) -> bool {
+        !self.unavailable.contains(&section)
+    }
+
+    /// Fill the sections that could not be described with their values from `last`, so the
+    /// snapshot holds the last known state of each section. A section is only available again
+    /// if it was in `last`.
+    pub fn carry_forward(&mut self, last: &GroupSnapshot) {
+        for section in std::mem::take(&mut self.unavailable) {
+            match section {
+                Section::Group => {
+                    self.status = last.status.clone();
+                    self.capacity = last.capacity;
+                    self.instances = last.instances.clone();
+                }
+                Section::Activities => self.activities = last.activities.clone(),
+                Section::Policies => self.policies = last.policies.clone(),
+                Section::ScheduledActions => {
+                    self.scheduled_actions = last.scheduled_actions.clone()
+                }
+                Section::LifecycleHooks => self.lifecycle_hooks = last.lifecycle_hooks.clone(),
+                Section::InstanceRefreshes => {
+                    self.instance_refreshes = last.instance_refreshes.clone()
+                }
+            }
+            if !last.is_available(section) {
+                self.unavailable.push(section);
+            }
+        }
+    }
+
+    fn fill_group(&mut self, group: &AutoScalingGroup) {
+        self.status = group.status().map(String::from);
+        self.capacity = Capacity {
+            min_size: group.min_size().unwrap_or_default(),
+            max_size: group.max_size().unwrap_or_default(),
+            desired_capacity: group.desired_capacity().unwrap_or_default(),
+        };
+        self.instances = group.instances().iter().map(Into::into).collect();
+        self.instances
+            .sort_by(|a, b| a.instance_id.cmp(&b.instance_id));
+    }
+
+    pub fn to_json(&self) -> Result<String, ScenarioError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|err| ScenarioError::with(format!("Failed to write snapshot as JSON: {err}")))
+    }
+
+    pub fn to_yaml(&self) -> Result<String, ScenarioError> {
+        serde_yaml::to_string(self)
+            .map_err(|err| ScenarioError::with(format!("Failed to write snapshot as YAML: {err}")))
+    }
+
+    /// The changes that turn this snapshot into `next`. Sections that either snapshot could not
+    /// describe are skipped, rather than reported as everything in them being added or removed.
+    pub fn diff(&self, next: &GroupSnapshot) -> Vec<ScenarioChange> {
+        let mut changes = vec![];
+        let compare = |section| self.is_available(section) && next.is_available(section);
+
+        if compare(Section::Group) {
+            self.diff_group(next, &mut changes);
+        }
+        if compare(Section::Activities) {
+            self.diff_activities(next, &mut changes);
+        }
+        if compare(Section::Policies) {
+            self.diff_policies(next, &mut changes);
+        }
+        if compare(Section::ScheduledActions) {
+            self.diff_scheduled_actions(next, &mut changes);
+        }
+        if compare(Section::LifecycleHooks) {
+            self.diff_lifecycle_hooks(next, &mut changes);
+        }
+        if compare(Section::InstanceRefreshes) {
+            changes.extend(
+                next.instance_refreshes
+                    .iter()
+                    .filter(|refresh| !self.instance_refreshes.contains(refresh))
+                    .map(|refresh| ScenarioChange::InstanceRefresh {
+                        refresh: refresh.clone(),
+                    }),
+            );
+        }
+
+        changes
+    }
+
+    fn diff_group(&self, next: &GroupSnapshot, changes: &mut Vec<ScenarioChange>) {
+        if self.status != next.status {
+            changes.push(ScenarioChange::GroupStatus {
+                from: self.status.clone(),
+                to: next.status.clone(),
+            });
+        }
+        if self.capacity != next.capacity {
+            changes.push(ScenarioChange::Capacity {
+                from: self.capacity,
+                to: next.capacity,
+            });
+        }
+
+        for instance in &next.instances {
+            match self
+                .instances
+                .iter()
+                .find(|i| i.instance_id == instance.instance_id)
+            {
+                None => changes.push(ScenarioChange::InstanceAdded {
+                    instance: instance.clone(),
+                }),
+                Some(before) => {
+                    let fields = [
+                        (
+                            "lifecycle-state",
+                            &before.lifecycle_state,
+                            &instance.lifecycle_state,
+                        ),
+                        (
+                            "health-status",
+                            &before.health_status,
+                            &instance.health_status,
+                        ),
+                        (
+                            "launch-template-version",
+                            &before.launch_template_version,
+                            &instance.launch_template_version,
+                        ),
+                    ];
+                    for (field, from, to) in fields {
+                        if from != to {
+                            changes.push(ScenarioChange::InstanceChanged {
+                                instance_id: instance.instance_id.clone(),
+                                field: field.into(),
+                                from: from.clone(),
+                                to: to.clone(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        for instance in &self.instances {
+            if !next
+                .instances
+                .iter()
+                .any(|i| i.instance_id == instance.instance_id)
+            {
+                changes.push(ScenarioChange::InstanceRemoved {
+                    instance_id: instance.instance_id.clone(),
+                });
+            }
+        }
+    }
+
+    fn diff_activities(&self, next: &GroupSnapshot, changes: &mut Vec<ScenarioChange>) {
+        for activity in &next.activities {
+            match self
+                .activities
+                .iter()
+                .find(|a| a.activity_id == activity.activity_id)
+            {
+                None => changes.push(ScenarioChange::ActivityStarted {
+                    activity: activity.clone(),
+                }),
+                Some(before)
+                    if before.status != activity.status || before.progress != activity.progress =>
+                {
+                    changes.push(ScenarioChange::ActivityUpdated {
+                        activity_id: activity.activity_id.clone(),
+                        status: activity.status.clone(),
+                        progress: activity.progress,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn diff_policies(&self, next: &GroupSnapshot, changes: &mut Vec<ScenarioChange>) {
+        let names = |policies: &Vec<PolicySnapshot>| -> Vec<String> {
+            policies.iter().map(|p| p.name.clone()).collect()
+        };
+        let (before, after) = (names(&self.policies), names(&next.policies));
+        changes.extend(
+            after
+                .iter()
+                .filter(|name| !before.contains(name))
+                .map(|name| ScenarioChange::PolicyAdded { name: name.clone() }),
+        );
+        changes.extend(
+            before
+                .iter()
+                .filter(|name| !after.contains(name))
+                .map(|name| ScenarioChange::PolicyRemoved { name: name.clone() }),
+        );
+    }
+
+    fn diff_scheduled_actions(&self, next: &GroupSnapshot, changes: &mut Vec<ScenarioChange>) {
+        let has = |actions: &Vec<ScheduledActionSnapshot>, name: &str| {
+            actions.iter().any(|a| a.name == name)
+        };
+        changes.extend(
+            next.scheduled_actions
+                .iter()
+                .filter(|action| !has(&self.scheduled_actions, &action.name))
+                .map(|action| ScenarioChange::ScheduledActionAdded {
+                    action: action.clone(),
+                }),
+        );
+        changes.extend(
+            self.scheduled_actions
+                .iter()
+                .filter(|action| !has(&next.scheduled_actions, &action.name))
+                .map(|action| ScenarioChange::ScheduledActionRemoved {
+                    name: action.name.clone(),
+                }),
+        );
+    }
+
+    fn diff_lifecycle_hooks(&self, next: &GroupSnapshot, changes: &mut Vec<ScenarioChange>) {
+        let names = |hooks: &Vec<HookSnapshot>| -> Vec<String> {
+            hooks.iter().map(|h| h.name.clone()).collect()
+        };
+        let (before, after) = (names(&self.lifecycle_hooks), names(&next.lifecycle_hooks));
+        changes.extend(
+            after
+                .iter()
+                .filter(|name| !before.contains(name))
+                .map(|name| ScenarioChange::LifecycleHookAdded { name: name.clone() }),
+        );
+        changes.extend(
+            before
+                .iter()
+                .filter(|name| !after.contains(name))
+                .map(|name| ScenarioChange::LifecycleHookRemoved { name: name.clone() }),
+        );
+    }
+}
+
+impl AutoScalingScenarioDescription {
+    pub fn render(&self, name: &str, format: OutputFormat) -> Result<String, ScenarioError> {
+        match format {
+            OutputFormat::Text => Ok(self.to_string()),
+            OutputFormat::Json => GroupSnapshot::from_description(name, self).to_json(),
+            OutputFormat::Yaml => GroupSnapshot::from_description(name, self).to_yaml(),
+        }
+    }
+}
+
+impl AutoScalingScenario {
+    pub async fn snapshot(&self) -> GroupSnapshot {
+        let description = self.describe_scenario().await;
+        GroupSnapshot::from_description(&self.auto_scaling_group_name, &description)
+    }
+
+    // snippet-start:[rust.auto-scaling.scenario.watch]
+    /// Describe the group every `interval`, and pass each snapshot to `on_change` with what changed
+    /// since the last one. The first call has no changes. Stops when `on_change` returns false.
+    /// A section that fails to describe keeps its last known state, so the failure doesn't show
+    /// up as changes; the snapshot passed to `on_change` has the error.
+    pub async fn watch(
+        &self,
+        interval: Duration,
+        mut on_change: impl FnMut(&GroupSnapshot, &[ScenarioChange]) -> bool,
+    ) -> GroupSnapshot {
+        let mut last = self.snapshot().await;
+        if !on_change(&last, &[]) {
+            return last;
+        }
+        loop {
+            tokio::time::sleep(interval).await;
+            let next = self.snapshot().await;
+            let changes = last.diff(&next);
+            let keep_watching = on_change(&next, &changes);
+            let mut next = next;
+            next.carry_forward(&last);
+            last = next;
+            if !keep_watching {
+                return last;
+            }
+        }
+    }
+    // snippet-end:[rust.auto-scaling.scenario.watch]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn instance(id: &str, state: &str) -> InstanceSnapshot {
+        InstanceSnapshot {
+            instance_id: id.into(),
+            availability_zone: Some("us-west-2a".into()),
+            lifecycle_state: Some(state.into()),
+            health_status: Some("Healthy".into()),
+            launch_template_version: Some("1".into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_reports_instance_and_capacity_changes() {
+        let before = GroupSnapshot {
+            name: "group".into(),
+            capacity: Capacity {
+                min_size: 1,
+                max_size: 3,
+                desired_capacity: 2,
+            },
+            instances: vec![instance("i-1", "InService"), instance("i-2", "InService")],
+            ..Default::default()
+        };
+        let mut after = before.clone();
+        after.capacity.desired_capacity = 3;
+        after.instances = vec![instance("i-2", "Terminating"), instance("i-3", "Pending")];
+        after.policies = vec![PolicySnapshot {
+            name: "step".into(),
+            policy_type: Some("StepScaling".into()),
+        }];
+
+        let changes = before.diff(&after);
+
+        assert_eq!(
+            changes,
+            vec![
+                ScenarioChange::Capacity {
+                    from: before.capacity,
+                    to: after.capacity
+                },
+                ScenarioChange::InstanceChanged {
+                    instance_id: "i-2".into(),
+                    field: "lifecycle-state".into(),
+                    from: Some("InService".into()),
+                    to: Some("Terminating".into()),
+                },
+                ScenarioChange::InstanceAdded {
+                    instance: instance("i-3", "Pending")
+                },
+                ScenarioChange::InstanceRemoved {
+                    instance_id: "i-1".into()
+                },
+                ScenarioChange::PolicyAdded {
+                    name: "step".into()
+                },
+            ]
+        );
+        assert!(after.diff(&after).is_empty());
+    }
+
+    #[test]
+    fn test_changes_serialize_with_event_tag() {
+        let change = ScenarioChange::InstanceRemoved {
+            instance_id: "i-1".into(),
+        };
+        assert_eq!(
+            serde_json::to_string(&change).unwrap(),
+            r#"{"event":"instance-removed","instance_id":"i-1"}"#
+        );
+        let snapshot = GroupSnapshot {
+            name: "group".into(),
+            instances: vec![instance("i-1", "InService")],
+            ..Default::default()
+        };
+        let json = snapshot.to_json().unwrap();
+        assert_eq!(
+            serde_json::from_str::<GroupSnapshot>(&json).unwrap(),
+            snapshot
+        );
+        let yaml = snapshot.to_yaml().unwrap();
+        assert_eq!(
+            serde_yaml::from_str::<GroupSnapshot>(&yaml).unwrap(),
+            snapshot
+        );
+    }
+
+    #[test]
+    fn test_diff_reports_scheduled_actions() {
+        let action = |name: &str| ScheduledActionSnapshot {
+            name: name.into(),
+            start_time: Some("2024-01-01T00:00:00Z".into()),
+            desired_capacity: Some(2),
+            ..Default::default()
+        };
+        let before = GroupSnapshot {
+            name: "group".into(),
+            scheduled_actions: vec![action("nightly")],
+            ..Default::default()
+        };
+        let mut after = before.clone();
+        after.scheduled_actions = vec![action("weekend")];
+
+        assert_eq!(
+            before.diff(&after),
+            vec![
+                ScenarioChange::ScheduledActionAdded {
+                    action: action("weekend")
+                },
+                ScenarioChange::ScheduledActionRemoved {
+                    name: "nightly".into()
+                },
+            ]
+        );
+
+        let mut failed = GroupSnapshot {
+            name: "group".into(),
+            unavailable: vec![Section::ScheduledActions],
+            ..Default::default()
+        };
+        assert!(before.diff(&failed).is_empty());
+        failed.carry_forward(&before);
+        assert_eq!(failed.scheduled_actions, before.scheduled_actions);
+    }
+
+    #[test]
+    fn test_failed_sections_are_not_changes() {
+        let good = GroupSnapshot {
+            name: "group".into(),
+            instances: vec![instance("i-1", "InService"), instance("i-2", "InService")],
+            policies: vec![PolicySnapshot {
+                name: "step".into(),
+                policy_type: Some("StepScaling".into()),
+            }],
+            ..Default::default()
+        };
+        // The group and its policies could not be described this time.
+        let mut failed = GroupSnapshot {
+            name: "group".into(),
+            unavailable: vec![Section::Group, Section::Policies],
+            errors: vec!["Throttling".into()],
+            ..Default::default()
+        };
+
+        assert!(good.diff(&failed).is_empty());
+
+        failed.carry_forward(&good);
+        assert_eq!(failed.instances, good.instances);
+        assert_eq!(failed.policies, good.policies);
+        assert!(failed.unavailable.is_empty());
+
+        let mut recovered = good.clone();
+        recovered.instances = vec![instance("i-2", "InService")];
+        assert_eq!(
+            failed.diff(&recovered),
+            vec![ScenarioChange::InstanceRemoved {
+                instance_id: "i-1".into()
+            }]
+        );
+    }
+}
@@ -0,0 +1,470 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Typed values for the events in an InvokeAgent `ResponseStream`.
+
+use std::collections::HashMap;
+
+use aws_sdk_bedrockagentruntime::{
+    self as bedrockagentruntime,
+    types::{
+        error::ResponseStreamError, Citation, InvocationInputMember, OrchestrationTrace,
+        ResponseStream, ReturnControlPayload, Trace, TracePart,
+    },
+};
+#[allow(unused_imports)]
+use mockall::automock;
+use tracing::warn;
+
+#[cfg(not(test))]
+pub use EventReceiverImpl as EventReceiver;
+#[cfg(test)]
+pub use MockEventReceiverImpl as EventReceiver;
+
+pub struct EventReceiverImpl {
+    inner: aws_sdk_bedrockagentruntime::primitives::event_stream::EventReceiver<
+        ResponseStream,
+        ResponseStreamError,
+    >,
+}
+
+#[cfg_attr(test, automock)]
+impl EventReceiverImpl {
+    #[allow(dead_code)]
+    pub fn new(
+        inner: aws_sdk_bedrockagentruntime::primitives::event_stream::EventReceiver<
+            ResponseStream,
+            ResponseStreamError,
+        >,
+    ) -> Self {
+        Self { inner }
+    }
+
+    pub async fn recv(
+        &mut self,
+    ) -> Result<
+        Option<ResponseStream>,
+        aws_sdk_bedrockagentruntime::error::SdkError<
+            ResponseStreamError,
+            aws_smithy_types::event_stream::RawMessage,
+        >,
+    > {
+        self.inner.recv().await
+    }
+}
+
+/// A source the agent used for part of its answer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AgentSource {
+    pub uri: Option<String>,
+    pub excerpt: Option<String>,
+}
+
+/// Part of the agent's answer, and the knowledge base sources it came from.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AgentCitation {
+    /// The cited part of the generated text.
+    pub text: Option<String>,
+    /// The cited part's start and end offsets in the generated text.
+    pub span: Option<(i32, i32)>,
+    pub sources: Vec<AgentSource>,
+}
+
+impl From<&Citation> for AgentCitation {
+    fn from(citation: &Citation) -> Self {
+        let text_part = citation
+            .generated_response_part()
+            .and_then(|part| part.text_response_part());
+        Self {
+            text: text_part.and_then(|part| part.text()).map(String::from),
+            span: text_part
+                .and_then(|part| part.span())
+                .and_then(|span| Some((span.start()?, span.end()?))),
+            sources: citation
+                .retrieved_references()
+                .iter()
+                .map(|reference| AgentSource {
+                    uri: reference.location().and_then(|location| {
+                        location
+                            .s3_location()
+                            .and_then(|s3| s3.uri())
+                            .or_else(|| location.web_location().and_then(|web| web.url()))
+                            .or_else(|| {
+                                location
+                                    .confluence_location()
+                                    .and_then(|confluence| confluence.url())
+                            })
+                            .map(String::from)
+                    }),
+                    excerpt: reference
+                        .content()
+                        .and_then(|content| content.text())
+                        .map(String::from),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Which step of the agent's processing a trace describes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceKind {
+    PreProcessing,
+    Orchestration,
+    PostProcessing,
+    Guardrail,
+    Failure,
+    Other,
+}
+
+/// A trace event, with the parts most useful for following the agent's reasoning pulled out.
+#[derive(Clone, Debug)]
+pub struct AgentTrace {
+    pub kind: TraceKind,
+    /// The agent's explanation of its next step, from an orchestration trace.
+    pub rationale: Option<String>,
+    pub failure_reason: Option<String>,
+    /// The full trace part, for anything not pulled out above.
+    pub raw: TracePart,
+}
+
+impl From<TracePart> for AgentTrace {
+    fn from(part: TracePart) -> Self {
+        let (kind, rationale, failure_reason) = match part.trace() {
+            Some(Trace::PreProcessingTrace(_)) => (TraceKind::PreProcessing, None, None),
+            Some(Trace::OrchestrationTrace(OrchestrationTrace::Rationale(rationale))) => (
+                TraceKind::Orchestration,
+                rationale.text().map(String::from),
+                None,
+            ),
+            Some(Trace::OrchestrationTrace(_)) => (TraceKind::Orchestration, None, None),
+            Some(Trace::PostProcessingTrace(_)) => (TraceKind::PostProcessing, None, None),
+            Some(Trace::GuardrailTrace(_)) => (TraceKind::Guardrail, None, None),
+            Some(Trace::FailureTrace(failure)) => (
+                TraceKind::Failure,
+                None,
+                failure.failure_reason().map(String::from),
+            ),
+            _ => (TraceKind::Other, None, None),
+        };
+        Self {
+            kind,
+            rationale,
+            failure_reason,
+            raw: part,
+        }
+    }
+}
+
+/// A function in one of the agent's action groups that the agent wants the caller to run.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FunctionCall {
+    pub action_group: String,
+    /// The function name, or the API path for action groups defined with an OpenAPI schema.
+    pub function: String,
+    pub parameters: HashMap<String, String>,
+    /// For action groups defined with an OpenAPI schema, the operation to answer. The result
+    /// has to name the same path and method.
+    pub api: Option<ApiOperation>,
+}
+
+/// An operation in an action group's OpenAPI schema.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ApiOperation {
+    pub api_path: String,
+    pub http_method: String,
+}
+
+/// The agent has handed control back to the caller to run one or more functions.
+/// Send the results back with `AgentClient::return_results`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReturnControl {
+    pub invocation_id: String,
+    pub calls: Vec<FunctionCall>,
+}
+
+impl From<&ReturnControlPayload> for ReturnControl {
+    fn from(payload: &ReturnControlPayload) -> Self {
+        Self {
+            invocation_id: payload.invocation_id().unwrap_or_default().to_string(),
+            calls: payload
+                .invocation_inputs()
+                .iter()
+                .filter_map(|input| match input {
+                    InvocationInputMember::FunctionInvocationInput(input) => Some(FunctionCall {
+                        action_group: input.action_group().to_string(),
+                        function: input.function().unwrap_or_default().to_string(),
+                        parameters: input
+                            .parameters()
+                            .iter()
+                            .filter_map(|p| Some((p.name()?.to_string(), p.value()?.to_string())))
+                            .collect(),
+                        api: None,
+                    }),
+                    InvocationInputMember::ApiInvocationInput(input) => Some(FunctionCall {
+                        action_group: input.action_group().to_string(),
+                        function: input.api_path().unwrap_or_default().to_string(),
+                        parameters: input
+                            .parameters()
+                            .iter()
+                            .filter_map(|p| Some((p.name()?.to_string(), p.value()?.to_string())))
+                            .collect(),
+                        api: Some(ApiOperation {
+                            api_path: input.api_path().unwrap_or_default().to_string(),
+                            http_method: input.http_method().unwrap_or_default().to_string(),
+                        }),
+                    }),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// One event from the agent, as a typed value.
+#[derive(Clone, Debug)]
+pub enum AgentEvent {
+    Text(String),
+    Citation(AgentCitation),
+    Trace(AgentTrace),
+    ReturnControl(ReturnControl),
+}
+
+/// Everything the agent sent in response to one turn.
+#[derive(Clone, Debug, Default)]
+pub struct AgentResponse {
+    pub text: String,
+    pub citations: Vec<AgentCitation>,
+    pub traces: Vec<AgentTrace>,
+    pub return_control: Option<ReturnControl>,
+}
+
+// snippet-start:[rust.bedrock-agent-runtime.InvokeAgent.process_stream]
+/// Read the response stream to the end, calling `on_event` as each event arrives.
+pub async fn process_agent_response_stream(
+    mut event_receiver: EventReceiver,
+    mut on_event: impl FnMut(&AgentEvent),
+) -> Result<AgentResponse, bedrockagentruntime::Error> {
+    let mut response = AgentResponse::default();
+
+    while let Some(event_result) = event_receiver.recv().await? {
+        let events = match event_result {
+            ResponseStream::Chunk(chunk) => {
+                let mut events = vec![];
+                if let Some(bytes) = chunk.bytes {
+                    match String::from_utf8(bytes.into_inner()) {
+                        Ok(text_chunk) => events.push(AgentEvent::Text(text_chunk)),
+                        Err(e) => {
+                            warn!("UTF-8 decoding error for chunk: {}", e);
+                        }
+                    }
+                }
+                if let Some(attribution) = chunk.attribution {
+                    events.extend(
+                        attribution
+                            .citations()
+                            .iter()
+                            .map(|citation| AgentEvent::Citation(citation.into())),
+                    );
+                }
+                events
+            }
+            ResponseStream::Trace(trace) => vec![AgentEvent::Trace(trace.into())],
+            ResponseStream::ReturnControl(payload) => {
+                vec![AgentEvent::ReturnControl((&payload).into())]
+            }
+            other => {
+                warn!("Ignoring unhandled event type from Bedrock stream: {other:?}");
+                vec![]
+            }
+        };
+
+        for event in events {
+            on_event(&event);
+            match event {
+                AgentEvent::Text(text) => response.text.push_str(&text),
+                AgentEvent::Citation(citation) => response.citations.push(citation),
+                AgentEvent::Trace(trace) => response.traces.push(trace),
+                AgentEvent::ReturnControl(return_control) => {
+                    response.return_control = Some(return_control)
+                }
+            }
+        }
+    }
+
+    Ok(response)
+}
+// snippet-end:[rust.bedrock-agent-runtime.InvokeAgent.process_stream]
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use aws_sdk_bedrockagentruntime::types::{
+        Attribution, FunctionInvocationInput, FunctionParameter, GeneratedResponsePart,
+        PayloadPart, Rationale, RetrievalResultContent, RetrievalResultLocation,
+        RetrievalResultLocationType, RetrievalResultS3Location, RetrievedReference, Span,
+        TextResponsePart,
+    };
+
+    #[tokio::test]
+    async fn test_process_agent_response_stream() {
+        let mut mock = MockEventReceiverImpl::default();
+        mock.expect_recv().times(1).returning(|| {
+            Ok(Some(
+                aws_sdk_bedrockagentruntime::types::ResponseStream::Chunk(
+                    aws_sdk_bedrockagentruntime::types::PayloadPart::builder()
+                        .set_bytes(Some(aws_smithy_types::Blob::new(vec![
+                            116, 101, 115, 116, 32, 99, 111, 109, 112, 108, 101, 116, 105, 111, 110,
+                        ])))
+                        .build(),
+                ),
+            ))
+        });
+
+        // end the stream
+        mock.expect_recv().times(1).returning(|| Ok(None));
+
+        let response = process_agent_response_stream(mock, |_| {}).await.unwrap();
+
+        assert_eq!("test completion", response.text);
+    }
+
+    #[tokio::test]
+    async fn test_process_agent_response_stream_trace() {
+        let mut mock = MockEventReceiverImpl::default();
+        mock.expect_recv().times(1).returning(|| {
+            Ok(Some(ResponseStream::Trace(
+                TracePart::builder()
+                    .trace(Trace::OrchestrationTrace(OrchestrationTrace::Rationale(
+                        Rationale::builder()
+                            .text("The user needs help, so I will ask what with.")
+                            .build(),
+                    )))
+                    .build(),
+            )))
+        });
+        mock.expect_recv().times(1).returning(|| Ok(None));
+
+        let mut seen = 0;
+        let response = process_agent_response_stream(mock, |_| seen += 1)
+            .await
+            .unwrap();
+
+        assert_eq!(seen, 1);
+        assert_eq!(response.traces.len(), 1);
+        assert_eq!(response.traces[0].kind, TraceKind::Orchestration);
+        assert_eq!(
+            response.traces[0].rationale.as_deref(),
+            Some("The user needs help, so I will ask what with.")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_process_agent_response_stream_citations() {
+        let mut mock = MockEventReceiverImpl::default();
+        mock.expect_recv().times(1).returning(|| {
+            Ok(Some(ResponseStream::Chunk(
+                PayloadPart::builder()
+                    .bytes(aws_smithy_types::Blob::new("Returns take 5 days."))
+                    .attribution(
+                        Attribution::builder()
+                            .citations(
+                                Citation::builder()
+                                    .generated_response_part(
+                                        GeneratedResponsePart::builder()
+                                            .text_response_part(
+                                                TextResponsePart::builder()
+                                                    .text("Returns take 5 days.")
+                                                    .span(Span::builder().start(0).end(19).build())
+                                                    .build(),
+                                            )
+                                            .build(),
+                                    )
+                                    .retrieved_references(
+                                        RetrievedReference::builder()
+                                            .content(
+                                                RetrievalResultContent::builder()
+                                                    .text("Refunds are processed within 5 days.")
+                                                    .build(),
+                                            )
+                                            .location(
+                                                RetrievalResultLocation::builder()
+                                                    .r#type(RetrievalResultLocationType::S3)
+                                                    .s3_location(
+                                                        RetrievalResultS3Location::builder()
+                                                            .uri("s3://docs/returns.pdf")
+                                                            .build(),
+                                                    )
+                                                    .build()
+                                                    .unwrap(),
+                                            )
+                                            .build(),
+                                    )
+                                    .build(),
+                            )
+                            .build(),
+                    )
+                    .build(),
+            )))
+        });
+        mock.expect_recv().times(1).returning(|| Ok(None));
+
+        let response = process_agent_response_stream(mock, |_| {}).await.unwrap();
+
+        assert_eq!(response.text, "Returns take 5 days.");
+        assert_eq!(
+            response.citations,
+            vec![AgentCitation {
+                text: Some("Returns take 5 days.".into()),
+                span: Some((0, 19)),
+                sources: vec![AgentSource {
+                    uri: Some("s3://docs/returns.pdf".into()),
+                    excerpt: Some("Refunds are processed within 5 days.".into()),
+                }],
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_process_agent_response_stream_return_control() {
+        let mut mock = MockEventReceiverImpl::default();
+        mock.expect_recv().times(1).returning(|| {
+            Ok(Some(ResponseStream::ReturnControl(
+                ReturnControlPayload::builder()
+                    .invocation_id("inv-1")
+                    .invocation_inputs(InvocationInputMember::FunctionInvocationInput(
+                        FunctionInvocationInput::builder()
+                            .action_group("orders")
+                            .function("get_order_status")
+                            .parameters(
+                                FunctionParameter::builder()
+                                    .name("order_id")
+                                    .r#type("string")
+                                    .value("1234")
+                                    .build(),
+                            )
+                            .build()
+                            .unwrap(),
+                    ))
+                    .build(),
+            )))
+        });
+        mock.expect_recv().times(1).returning(|| Ok(None));
+
+        let response = process_agent_response_stream(mock, |_| {}).await.unwrap();
+
+        assert_eq!(
+            response.return_control,
+            Some(ReturnControl {
+                invocation_id: "inv-1".into(),
+                calls: vec![FunctionCall {
+                    action_group: "orders".into(),
+                    function: "get_order_status".into(),
+                    parameters: HashMap::from([("order_id".into(), "1234".into())]),
+                    api: None,
+                }],
+            })
+        );
+    }
+}
@@ -0,0 +1,504 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A session-aware client for invoking a Bedrock agent across several turns.
+
+use std::collections::HashMap;
+
+use aws_config::{BehaviorVersion, Region};
+use aws_sdk_bedrockagentruntime::{
+    self as bedrockagentruntime,
+    types::{ApiResult, ContentBody, FunctionResult, InvocationResultMember, SessionState},
+};
+
+pub mod events;
+
+use events::{process_agent_response_stream, EventReceiver};
+pub use events::{
+    AgentCitation, AgentEvent, AgentResponse, AgentSource, AgentTrace, ApiOperation, FunctionCall,
+    ReturnControl, TraceKind,
+};
+
+pub const AGENT_ID_VAR: &str = "BEDROCK_AGENT_ID";
+pub const AGENT_ALIAS_ID_VAR: &str = "BEDROCK_AGENT_ALIAS_ID";
+pub const AGENT_REGION_VAR: &str = "BEDROCK_AGENT_REGION";
+
+#[derive(Debug)]
+pub enum AgentError {
+    /// The agent configuration is missing or invalid.
+    Config(String),
+    /// Reading input or writing output failed.
+    Io(std::io::Error),
+    Sdk(Box<bedrockagentruntime::Error>),
+}
+
+impl std::fmt::Display for AgentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AgentError::Config(message) => write!(f, "Agent configuration error: {message}"),
+            AgentError::Io(err) => write!(f, "Agent IO error: {err}"),
+            AgentError::Sdk(err) => write!(f, "Bedrock agent error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for AgentError {}
+
+impl From<std::io::Error> for AgentError {
+    fn from(value: std::io::Error) -> Self {
+        AgentError::Io(value)
+    }
+}
+
+impl From<bedrockagentruntime::Error> for AgentError {
+    fn from(value: bedrockagentruntime::Error) -> Self {
+        AgentError::Sdk(Box::new(value))
+    }
+}
+
+/// Which agent to talk to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgentConfig {
+    pub agent_id: String,
+    pub agent_alias_id: String,
+    /// When None, the region comes from the default provider chain.
+    pub region: Option<String>,
+    /// Ask the agent to send trace events along with its answer.
+    pub enable_trace: bool,
+}
+
+impl AgentConfig {
+    /// Read the configuration from BEDROCK_AGENT_ID, BEDROCK_AGENT_ALIAS_ID, and
+    /// optionally BEDROCK_AGENT_REGION.
+    pub fn from_env() -> Result<Self, AgentError> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, AgentError> {
+        let required = |name: &str| {
+            var(name)
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| AgentError::Config(format!("{name} is not set")))
+        };
+        Ok(Self {
+            agent_id: required(AGENT_ID_VAR)?,
+            agent_alias_id: required(AGENT_ALIAS_ID_VAR)?,
+            region: var(AGENT_REGION_VAR).filter(|value| !value.is_empty()),
+            enable_trace: false,
+        })
+    }
+
+    pub fn with_trace(mut self, enable_trace: bool) -> Self {
+        self.enable_trace = enable_trace;
+        self
+    }
+}
+
+/// The output of a function the agent asked the caller to run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionCallResult {
+    pub call: FunctionCall,
+    pub body: String,
+}
+
+/// The state of one conversation with the agent.
+///
+/// Session attributes are sent with every turn; prompt session attributes are sent
+/// with the next turn only.
+#[derive(Clone, Debug)]
+pub struct AgentSession {
+    pub session_id: String,
+    pub session_attributes: HashMap<String, String>,
+    pub prompt_session_attributes: HashMap<String, String>,
+    /// Set while the agent is waiting for function results.
+    pub pending_return_control: Option<ReturnControl>,
+    turns: usize,
+}
+
+impl Default for AgentSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AgentSession {
+    pub fn new() -> Self {
+        Self::with_id(uuid::Uuid::new_v4().to_string())
+    }
+
+    /// Resume an existing session. The agent keeps its history for the session id.
+    pub fn with_id(session_id: String) -> Self {
+        Self {
+            session_id,
+            session_attributes: HashMap::new(),
+            prompt_session_attributes: HashMap::new(),
+            pending_return_control: None,
+            turns: 0,
+        }
+    }
+
+    pub fn set_attribute(&mut self, key: &str, value: &str) {
+        self.session_attributes.insert(key.into(), value.into());
+    }
+
+    pub fn set_prompt_attribute(&mut self, key: &str, value: &str) {
+        self.prompt_session_attributes
+            .insert(key.into(), value.into());
+    }
+
+    /// How many responses the agent has sent in this session.
+    pub fn turns(&self) -> usize {
+        self.turns
+    }
+
+    /// Build the session state for the next request, taking the prompt session attributes.
+    /// While the agent is waiting for function results, the next request must carry them.
+    fn next_state(&mut self, results: Vec<FunctionCallResult>) -> Result<SessionState, AgentError> {
+        if self.pending_return_control.is_some() && results.is_empty() {
+            return Err(AgentError::Config(
+                "The agent is waiting for function results; send them with return_results".into(),
+            ));
+        }
+        let mut state = SessionState::builder()
+            .set_session_attributes(
+                (!self.session_attributes.is_empty()).then(|| self.session_attributes.clone()),
+            )
+            .set_prompt_session_attributes(
+                (!self.prompt_session_attributes.is_empty())
+                    .then(|| std::mem::take(&mut self.prompt_session_attributes)),
+            );
+        if let Some(return_control) = self.pending_return_control.take() {
+            let results = results
+                .into_iter()
+                .map(|result| match result.call.api {
+                    // Calls from an OpenAPI action group are answered as the API's response.
+                    Some(api) => InvocationResultMember::ApiResult(
+                        ApiResult::builder()
+                            .action_group(result.call.action_group)
+                            .api_path(api.api_path)
+                            .http_method(api.http_method)
+                            .response_body(
+                                "application/json",
+                                ContentBody::builder().body(result.body).build(),
+                            )
+                            .build()
+                            .expect("action group is set"),
+                    ),
+                    None => InvocationResultMember::FunctionResult(
+                        FunctionResult::builder()
+                            .action_group(result.call.action_group)
+                            .function(result.call.function)
+                            .response_body("TEXT", ContentBody::builder().body(result.body).build())
+                            .build()
+                            .expect("action group is set"),
+                    ),
+                })
+                .collect();
+            state = state
+                .invocation_id(return_control.invocation_id)
+                .set_return_control_invocation_results(Some(results));
+        }
+        Ok(state.build())
+    }
+
+    fn record(&mut self, response: &AgentResponse) {
+        self.turns += 1;
+        self.pending_return_control = response.return_control.clone();
+    }
+}
+
+/// Sends turns to one agent and keeps each session's state up to date.
+pub struct AgentClient {
+    client: bedrockagentruntime::Client,
+    config: AgentConfig,
+}
+
+impl AgentClient {
+    pub fn new(client: bedrockagentruntime::Client, config: AgentConfig) -> Self {
+        Self { client, config }
+    }
+
+    /// Load the agent configuration from the environment, and an SDK client for its region.
+    pub async fn from_env() -> Result<Self, AgentError> {
+        let config = AgentConfig::from_env()?;
+        let mut loader = aws_config::defaults(BehaviorVersion::latest());
+        if let Some(region) = &config.region {
+            loader = loader.region(Region::new(region.clone()));
+        }
+        let sdk_config = loader.load().await;
+        Ok(Self::new(
+            bedrockagentruntime::Client::new(&sdk_config),
+            config,
+        ))
+    }
+
+    pub fn config(&self) -> &AgentConfig {
+        &self.config
+    }
+
+    pub fn config_mut(&mut self) -> &mut AgentConfig {
+        &mut self.config
+    }
+
+    /// Send the user's text, calling `on_event` as the agent's events arrive.
+    /// If the agent returns control, the session holds the calls until `return_results`.
+    pub async fn send(
+        &self,
+        session: &mut AgentSession,
+        text: &str,
+        on_event: impl FnMut(&AgentEvent),
+    ) -> Result<AgentResponse, AgentError> {
+        self.send_state(session, Some(text), vec![], false, on_event)
+            .await
+    }
+
+    /// Send the results of the functions the agent asked for, and continue the turn.
+    pub async fn return_results(
+        &self,
+        session: &mut AgentSession,
+        results: Vec<FunctionCallResult>,
+        on_event: impl FnMut(&AgentEvent),
+    ) -> Result<AgentResponse, AgentError> {
+        if session.pending_return_control.is_none() {
+            return Err(AgentError::Config(
+                "The agent is not waiting for function results".into(),
+            ));
+        }
+        self.send_state(session, None, results, false, on_event)
+            .await
+    }
+
+    /// Tell the agent the session is over, so it can discard the session's history.
+    pub async fn end_session(&self, session: &mut AgentSession) -> Result<(), AgentError> {
+        self.send_state(session, Some("Goodbye"), vec![], true, |_| {})
+            .await
+            .map(|_| ())
+    }
+
+    /// Invoke the agent with the session's next state. If the request fails, the session is put
+    /// back as it was, so the same turn or results can be sent again.
+    async fn send_state(
+        &self,
+        session: &mut AgentSession,
+        text: Option<&str>,
+        results: Vec<FunctionCallResult>,
+        end_session: bool,
+        on_event: impl FnMut(&AgentEvent),
+    ) -> Result<AgentResponse, AgentError> {
+        let pending_return_control = session.pending_return_control.clone();
+        let prompt_session_attributes = session.prompt_session_attributes.clone();
+        let state = session.next_state(results)?;
+        let response = self
+            .invoke(session, text, state, end_session, on_event)
+            .await;
+        if response.is_err() {
+            session.pending_return_control = pending_return_control;
+            session.prompt_session_attributes = prompt_session_attributes;
+        }
+        response
+    }
+
+    // snippet-start:[rust.bedrock-agent-runtime.InvokeAgent]
+    async fn invoke(
+        &self,
+        session: &mut AgentSession,
+        text: Option<&str>,
+        state: SessionState,
+        end_session: bool,
+        on_event: impl FnMut(&AgentEvent),
+    ) -> Result<AgentResponse, AgentError> {
+        let output = self
+            .client
+            .invoke_agent()
+            .agent_id(&self.config.agent_id)
+            .agent_alias_id(&self.config.agent_alias_id)
+            .session_id(&session.session_id)
+            .set_input_text(text.map(String::from))
+            .session_state(state)
+            .enable_trace(self.config.enable_trace)
+            .end_session(end_session)
+            .send()
+            .await
+            .map_err(bedrockagentruntime::Error::from)?;
+
+        let response =
+            process_agent_response_stream(EventReceiver::new(output.completion), on_event).await?;
+        session.record(&response);
+        Ok(response)
+    }
+    // snippet-end:[rust.bedrock-agent-runtime.InvokeAgent]
+}
+
+#[cfg(test)]
+mod test {
+    use aws_smithy_runtime::client::http::test_util::StaticReplayClient;
+    use sdk_examples_test_utils::{client_config, test_event};
+
+    use super::*;
+
+    fn order_status_call() -> FunctionCall {
+        FunctionCall {
+            action_group: "orders".into(),
+            function: "get_order_status".into(),
+            parameters: HashMap::from([("order_id".into(), "1234".into())]),
+            api: None,
+        }
+    }
+
+    fn waiting_session(call: FunctionCall) -> AgentSession {
+        let mut session = AgentSession::with_id("session".into());
+        session.record(&AgentResponse {
+            return_control: Some(ReturnControl {
+                invocation_id: "inv-1".into(),
+                calls: vec![call],
+            }),
+            ..Default::default()
+        });
+        session
+    }
+
+    #[test]
+    fn test_config_from_vars() {
+        let vars = HashMap::from([
+            (AGENT_ID_VAR, "AGENT"),
+            (AGENT_ALIAS_ID_VAR, "ALIAS"),
+            (AGENT_REGION_VAR, ""),
+        ]);
+        let config = AgentConfig::from_vars(|name| vars.get(name).map(|v| v.to_string())).unwrap();
+        assert_eq!(config.agent_id, "AGENT");
+        assert_eq!(config.agent_alias_id, "ALIAS");
+        assert_eq!(config.region, None);
+
+        let err =
+            AgentConfig::from_vars(|name| (name == AGENT_ID_VAR).then(|| "AGENT".to_string()))
+                .unwrap_err();
+        assert!(err.to_string().contains(AGENT_ALIAS_ID_VAR));
+    }
+
+    #[test]
+    fn test_prompt_attributes_last_one_turn() {
+        let mut session = AgentSession::with_id("session".into());
+        session.set_attribute("user", "alice");
+        session.set_prompt_attribute("today", "Monday");
+
+        let state = session.next_state(vec![]).unwrap();
+        assert_eq!(state.session_attributes().unwrap()["user"], "alice");
+        assert_eq!(
+            state.prompt_session_attributes().unwrap()["today"],
+            "Monday"
+        );
+
+        let state = session.next_state(vec![]).unwrap();
+        assert_eq!(state.session_attributes().unwrap()["user"], "alice");
+        assert!(state.prompt_session_attributes().is_none());
+    }
+
+    #[test]
+    fn test_function_results_answer_pending_return_control() {
+        let call = order_status_call();
+        let mut session = waiting_session(call.clone());
+        assert_eq!(session.turns(), 1);
+
+        // The agent can't take a new prompt until it has the results.
+        assert!(matches!(
+            session.next_state(vec![]),
+            Err(AgentError::Config(_))
+        ));
+
+        let state = session
+            .next_state(vec![FunctionCallResult {
+                call,
+                body: "shipped".into(),
+            }])
+            .unwrap();
+        assert_eq!(state.invocation_id(), Some("inv-1"));
+        match &state.return_control_invocation_results()[0] {
+            InvocationResultMember::FunctionResult(result) => {
+                assert_eq!(result.function(), Some("get_order_status"));
+                assert_eq!(
+                    result.response_body().unwrap()["TEXT"].body(),
+                    Some("shipped")
+                );
+            }
+            other => panic!("Unexpected result {other:?}"),
+        }
+        assert!(session.pending_return_control.is_none());
+    }
+
+    #[test]
+    fn test_api_results_name_the_operation() {
+        let call = FunctionCall {
+            action_group: "orders".into(),
+            function: "/orders/{orderId}".into(),
+            parameters: HashMap::from([("orderId".into(), "1234".into())]),
+            api: Some(ApiOperation {
+                api_path: "/orders/{orderId}".into(),
+                http_method: "GET".into(),
+            }),
+        };
+        let mut session = waiting_session(call.clone());
+
+        let state = session
+            .next_state(vec![FunctionCallResult {
+                call,
+                body: r#"{"status": "shipped"}"#.into(),
+            }])
+            .unwrap();
+        match &state.return_control_invocation_results()[0] {
+            InvocationResultMember::ApiResult(result) => {
+                assert_eq!(result.action_group(), "orders");
+                assert_eq!(result.api_path(), Some("/orders/{orderId}"));
+                assert_eq!(result.http_method(), Some("GET"));
+                assert_eq!(
+                    result.response_body().unwrap()["application/json"].body(),
+                    Some(r#"{"status": "shipped"}"#)
+                );
+            }
+            other => panic!("Unexpected result {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_invoke_keeps_pending_return_control() {
+        let client = bedrockagentruntime::Client::from_conf(
+            client_config!(aws_sdk_bedrockagentruntime)
+                .http_client(StaticReplayClient::new(vec![test_event!(
+                    r#""#,
+                    (
+                        400,
+                        vec![("x-amzn-errortype", "ValidationException")],
+                        r#"{"message": "Invalid invocation results."}"#
+                    )
+                )]))
+                .build(),
+        );
+        let agent = AgentClient::new(
+            client,
+            AgentConfig {
+                agent_id: "AGENT".into(),
+                agent_alias_id: "ALIAS".into(),
+                region: None,
+                enable_trace: false,
+            },
+        );
+        let call = order_status_call();
+        let mut session = waiting_session(call.clone());
+
+        let result = agent
+            .return_results(
+                &mut session,
+                vec![FunctionCallResult {
+                    call,
+                    body: "shipped".into(),
+                }],
+                |_| {},
+            )
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            session.pending_return_control.map(|r| r.invocation_id),
+            Some("inv-1".to_string())
+        );
+    }
+}
@@ -0,0 +1,146 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An interactive chat with the agent named by BEDROCK_AGENT_ID and BEDROCK_AGENT_ALIAS_ID.
+//! The session id and session attributes carry over between turns. When the agent returns
+//! control to run one of its action group functions, you type in the function's result.
+//!
+//! Lines starting with `/` are commands:
+//! `/attr key=value` sets a session attribute, `/prompt key=value` sets a prompt session
+//! attribute for the next turn, `/new` starts a new session, and `/quit` ends the session.
+
+use std::io::{stdin, stdout, Write};
+
+use bedrock_agent_runtime::agent::{
+    AgentClient, AgentError, AgentEvent, AgentResponse, AgentSession, FunctionCallResult, TraceKind,
+};
+use clap::Parser;
+
+#[derive(Debug, Parser)]
+struct Opt {
+    /// Resume the session with this id.
+    #[structopt(short, long)]
+    session_id: Option<String>,
+
+    /// Print the agent's trace events as they arrive.
+    #[structopt(short, long)]
+    trace: bool,
+}
+
+fn read_line(prompt: &str) -> Result<Option<String>, std::io::Error> {
+    print!("{prompt}");
+    stdout().flush()?;
+    let mut line = String::new();
+    if stdin().read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+fn print_event(event: &AgentEvent, trace: bool) {
+    match event {
+        AgentEvent::Text(text) => {
+            print!("{text}");
+            let _ = stdout().flush();
+        }
+        AgentEvent::Trace(agent_trace) if trace => match agent_trace.kind {
+            TraceKind::Failure => eprintln!(
+                "\n[trace] failure: {}",
+                agent_trace.failure_reason.as_deref().unwrap_or("unknown")
+            ),
+            kind => match &agent_trace.rationale {
+                Some(rationale) => eprintln!("\n[trace] {kind:?}: {rationale}"),
+                None => eprintln!("\n[trace] {kind:?}"),
+            },
+        },
+        _ => {}
+    }
+}
+
+fn print_citations(response: &AgentResponse) {
+    for (i, citation) in response.citations.iter().enumerate() {
+        for source in &citation.sources {
+            println!(
+                "  [{}] {}",
+                i + 1,
+                source.uri.as_deref().unwrap_or("(unknown source)")
+            );
+        }
+    }
+}
+
+fn parse_pair(arg: &str) -> Option<(&str, &str)> {
+    arg.split_once('=').map(|(k, v)| (k.trim(), v.trim()))
+}
+
+/// Ask the user to play the part of each function the agent wants to run.
+fn collect_results(response: &AgentResponse) -> Result<Vec<FunctionCallResult>, std::io::Error> {
+    let mut results = vec![];
+    for call in response
+        .return_control
+        .iter()
+        .flat_map(|return_control| &return_control.calls)
+    {
+        println!(
+            "\nThe agent wants to call {}::{} with {:?}",
+            call.action_group, call.function, call.parameters
+        );
+        let body = read_line("result> ")?.unwrap_or_default();
+        results.push(FunctionCallResult {
+            call: call.clone(),
+            body,
+        });
+    }
+    Ok(results)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), AgentError> {
+    let Opt { session_id, trace } = Opt::parse();
+
+    let mut client = AgentClient::from_env().await?;
+    client.config_mut().enable_trace = trace;
+    let mut session = session_id.map_or_else(AgentSession::new, AgentSession::with_id);
+    println!("Session {}. Type /quit to exit.", session.session_id);
+
+    while let Some(line) = read_line("> ")? {
+        match line.split_once(' ').unwrap_or((line.as_str(), "")) {
+            ("", _) => continue,
+            ("/quit", _) => break,
+            ("/new", _) => {
+                session = AgentSession::new();
+                println!("Session {}.", session.session_id);
+                continue;
+            }
+            ("/attr", arg) => {
+                match parse_pair(arg) {
+                    Some((key, value)) => session.set_attribute(key, value),
+                    None => println!("Usage: /attr key=value"),
+                }
+                continue;
+            }
+            ("/prompt", arg) => {
+                match parse_pair(arg) {
+                    Some((key, value)) => session.set_prompt_attribute(key, value),
+                    None => println!("Usage: /prompt key=value"),
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        let mut response = client
+            .send(&mut session, &line, |event| print_event(event, trace))
+            .await?;
+        while response.return_control.is_some() {
+            let results = collect_results(&response)?;
+            response = client
+                .return_results(&mut session, results, |event| print_event(event, trace))
+                .await?;
+        }
+        println!();
+        print_citations(&response);
+    }
+
+    client.end_session(&mut session).await
+}
@@ -0,0 +1,217 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A command line chat with a model on Amazon Bedrock that remembers previous sessions.
+//! Each conversation is saved as JSON after every turn, and can be resumed later with `--resume <id>`.
+//! Long conversations are kept within a token budget by dropping or summarizing the oldest turns.
+
+use std::io::{stdin, stdout, Write};
+
+use aws_config::BehaviorVersion;
+use aws_sdk_bedrockruntime::Client;
+use bedrock_runtime::{
+    conversation::{
+        ContextStrategy, Conversation, ConversationError, ConversationManager, ConversationStore,
+        InferenceProfile,
+    },
+    stream::consume_stream,
+};
+use clap::Parser;
+use tokio::sync::broadcast;
+
+const CLAUDE_REGION: &str = "us-east-1";
+
+const SYSTEM_PROMPT: &str =
+    "You are a helpful assistant. Keep answers short unless asked for detail.";
+
+#[derive(Debug, Parser)]
+struct Opt {
+    /// The directory where conversations are saved.
+    #[structopt(long, default_value = ".conversations")]
+    dir: String,
+
+    /// Resume the conversation with this id.
+    #[structopt(short, long)]
+    resume: Option<String>,
+
+    /// List saved conversations and exit.
+    #[structopt(short, long)]
+    list: bool,
+
+    /// Stream responses with ConverseStream instead of waiting for the full reply.
+    #[structopt(short, long)]
+    stream: bool,
+
+    /// The inference profile: default, precise, or creative. New conversations use default
+    /// unless told otherwise, and resumed ones keep their own.
+    #[structopt(short, long)]
+    profile: Option<String>,
+
+    /// The model ID, overriding the profile's.
+    #[structopt(short, long)]
+    model_id: Option<String>,
+
+    /// The approximate number of tokens of history to send with each turn.
+    #[structopt(long, default_value = "4000")]
+    token_budget: usize,
+
+    /// Summarize old turns instead of dropping them when over the budget.
+    #[structopt(long)]
+    summarize: bool,
+}
+
+fn get_input() -> Result<Option<String>, ConversationError> {
+    print!("> ");
+    stdout().flush()?;
+    let mut line = String::new();
+    stdin().read_line(&mut line)?;
+    let line = line.trim();
+    if line.is_empty() || line == "x" {
+        Ok(None)
+    } else {
+        Ok(Some(line.to_string()))
+    }
+}
+
+/// Once a Ctrl-C handler is installed, Ctrl-C no longer exits, so there is one for the whole
+/// chat. It stops the reply being streamed, if there is one, and otherwise exits.
+fn handle_ctrl_c() -> broadcast::Sender<()> {
+    let (interrupts, _) = broadcast::channel(1);
+    let sender = interrupts.clone();
+    tokio::spawn(async move {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if sender.send(()).is_err() {
+                println!();
+                std::process::exit(130);
+            }
+        }
+    });
+    interrupts
+}
+
+async fn send_streaming(
+    manager: &ConversationManager,
+    conversation: &mut Conversation,
+    text: String,
+    interrupts: &broadcast::Sender<()>,
+) -> Result<(), ConversationError> {
+    // Ctrl-C stops the reply early; whatever arrived is kept in the conversation.
+    let mut interrupted = interrupts.subscribe();
+
+    // Keep the turn only once the reply has arrived, so a failed request, or a stream that
+    // breaks partway, doesn't leave the user's message unanswered in the history.
+    let mut next = manager.next_turn(conversation, text).await?;
+
+    let mut stream = next
+        .converse_stream(manager.client())?
+        .send()
+        .await
+        .map_err(|e| ConversationError(format!("ConverseStream failed: {e}")))?
+        .stream;
+
+    let cancel = async move {
+        let _ = interrupted.recv().await;
+    };
+    let response = consume_stream(&mut stream, cancel, |text| {
+        print!("{text}");
+        let _ = stdout().flush();
+    })
+    .await
+    .map_err(|e| ConversationError(e.0))?;
+    println!();
+
+    if response.cancelled {
+        println!("(cancelled)");
+    }
+    tracing::info!(
+        stop_reason = ?response.stop_reason,
+        input_tokens = response.metrics.input_tokens,
+        output_tokens = response.metrics.output_tokens,
+        latency_ms = response.metrics.latency_ms,
+        "Streamed reply"
+    );
+
+    // A reply cancelled before anything arrived would be an empty turn.
+    if response.message.content().is_empty() {
+        return Ok(());
+    }
+    manager.record(&mut next, &response.message)?;
+    *conversation = next;
+    Ok(())
+}
+
+async fn send(
+    manager: &ConversationManager,
+    conversation: &mut Conversation,
+    text: String,
+) -> Result<(), ConversationError> {
+    let message = manager.send(conversation, text).await?;
+    for block in message.content() {
+        if let Ok(text) = block.as_text() {
+            println!("{text}");
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), ConversationError> {
+    tracing_subscriber::fmt::init();
+    let opt = Opt::parse();
+
+    let store = ConversationStore::new(&opt.dir)?;
+    if opt.list {
+        for id in store.list()? {
+            println!("{id}");
+        }
+        return Ok(());
+    }
+
+    let profile = opt
+        .profile
+        .as_deref()
+        .map(|name| {
+            InferenceProfile::named(name)
+                .ok_or_else(|| ConversationError(format!("Unknown profile {name}")))
+        })
+        .transpose()?;
+
+    let sdk_config = aws_config::defaults(BehaviorVersion::latest())
+        .region(CLAUDE_REGION)
+        .load()
+        .await;
+    let manager = ConversationManager::new(
+        Client::new(&sdk_config),
+        store,
+        opt.token_budget,
+        if opt.summarize {
+            ContextStrategy::Summarize
+        } else {
+            ContextStrategy::Drop
+        },
+    );
+
+    let mut conversation = manager.resume_or_start(
+        opt.resume.as_deref(),
+        profile,
+        opt.model_id.as_deref(),
+        Some(SYSTEM_PROMPT),
+    )?;
+    println!(
+        "Conversation {} ({} messages). Enter an empty line or x to exit.",
+        conversation.id,
+        conversation.messages.len()
+    );
+
+    let interrupts = opt.stream.then(handle_ctrl_c);
+    while let Some(text) = get_input()? {
+        if let Some(interrupts) = &interrupts {
+            send_streaming(&manager, &mut conversation, text, interrupts).await?;
+        } else {
+            send(&manager, &mut conversation, text).await?;
+        }
+    }
+
+    println!("Resume this conversation with --resume {}", conversation.id);
+    Ok(())
+}
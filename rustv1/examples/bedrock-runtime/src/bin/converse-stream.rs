@@ -6,12 +6,10 @@ use aws_config::BehaviorVersion;
 use aws_sdk_bedrockruntime::{
     error::ProvideErrorMetadata,
     operation::converse_stream::ConverseStreamError,
-    types::{
-        error::ConverseStreamOutputError, ContentBlock, ConversationRole,
-        ConverseStreamOutput as ConverseStreamOutputType, Message,
-    },
+    types::{error::ConverseStreamOutputError, ContentBlock, ConversationRole, Message},
     Client,
 };
+use bedrock_runtime::stream::StreamAggregator;
 
 // Set the model ID, e.g., Claude 3 Haiku.
 const MODEL_ID: &str = "anthropic.claude-3-haiku-20240307-v1:0";
@@ -97,12 +95,19 @@ async fn main() -> Result<(), BedrockConverseStreamError> {
         )),
     }?;
 
+    // The aggregator reassembles the full message, and keeps the stop reason and token usage
+    // that arrive after the last text delta.
+    let mut aggregator = StreamAggregator::new();
     loop {
         let token = stream.recv().await;
         match token {
-            Ok(Some(text)) => {
-                let next = get_converse_output_text(text)?;
-                print!("{}", next);
+            Ok(Some(event)) => {
+                if let Some(text) = aggregator
+                    .push(event)
+                    .map_err(|e| BedrockConverseStreamError(e.0))?
+                {
+                    print!("{}", text);
+                }
                 Ok(())
             }
             Ok(None) => break,
@@ -117,19 +122,18 @@ async fn main() -> Result<(), BedrockConverseStreamError> {
 
     println!();
 
-    Ok(())
-}
+    let response = aggregator
+        .finish(false)
+        .map_err(|e| BedrockConverseStreamError(e.0))?;
+    println!(
+        "Stop reason: {:?}, tokens: {} in / {} out, latency: {} ms",
+        response.stop_reason,
+        response.metrics.input_tokens.unwrap_or_default(),
+        response.metrics.output_tokens.unwrap_or_default(),
+        response.metrics.latency_ms.unwrap_or_default(),
+    );
 
-fn get_converse_output_text(
-    output: ConverseStreamOutputType,
-) -> Result<String, BedrockConverseStreamError> {
-    Ok(match output {
-        ConverseStreamOutputType::ContentBlockDelta(event) => match event.delta() {
-            Some(delta) => delta.as_text().cloned().unwrap_or_else(|_| "".into()),
-            None => "".into(),
-        },
-        _ => "".into(),
-    })
+    Ok(())
 }
 
 // snippet-end:[rust.bedrock-runtime.ConverseStream_AnthropicClaude]
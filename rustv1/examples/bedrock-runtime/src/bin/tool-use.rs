@@ -6,21 +6,17 @@
 //! input. It uses the Open-Meteo API (https://open-meteo.com) to retrieve current weather data for a given location.
 
 // snippet-start:[rust.bedrock-runtime.Converse_AnthropicClaude.tool-use.supporting]
-use std::{collections::HashMap, io::stdin};
+use std::io::stdin;
 
+use async_trait::async_trait;
 use aws_config::BehaviorVersion;
-use aws_sdk_bedrockruntime::{
-    error::{BuildError, SdkError},
-    operation::converse::{ConverseError, ConverseOutput},
-    types::{
-        ContentBlock, ConversationRole::User, Message, StopReason, SystemContentBlock, Tool,
-        ToolConfiguration, ToolInputSchema, ToolResultBlock, ToolResultContentBlock,
-        ToolSpecification, ToolUseBlock,
-    },
-    Client,
+use aws_sdk_bedrockruntime::{error::BuildError, types::ContentBlock, Client};
+use bedrock_runtime::{
+    conversation::{Conversation, ConversationError, InferenceProfile},
+    tools::{converse_with_tools, ModelTurn, Tool, ToolError, ToolOutput, ToolRegistry},
 };
-use aws_smithy_runtime_api::http::Response;
-use aws_smithy_types::Document;
+use schemars::JsonSchema;
+use serde::Deserialize;
 use tracing::debug;
 
 // Set the model ID, e.g., Claude 3 Haiku.
@@ -45,49 +41,19 @@ To use the tool, you strictly apply the provided tool specification.
 
 // The maximum number of recursive calls allowed in the tool_use_demo function.
 // This helps prevent infinite loops and potential performance issues.
-const MAX_RECURSIONS: i8 = 5;
+const MAX_RECURSIONS: usize = 5;
 
 const TOOL_NAME: &str = "Weather_Tool";
 const TOOL_DESCRIPTION: &str =
     "Get the current weather for a given location, based on its WGS84 coordinates.";
-fn make_tool_schema() -> Document {
-    Document::Object(HashMap::<String, Document>::from([
-        ("type".into(), Document::String("object".into())),
-        (
-            "properties".into(),
-            Document::Object(HashMap::from([
-                (
-                    "latitude".into(),
-                    Document::Object(HashMap::from([
-                        ("type".into(), Document::String("string".into())),
-                        (
-                            "description".into(),
-                            Document::String("Geographical WGS84 latitude of the location.".into()),
-                        ),
-                    ])),
-                ),
-                (
-                    "longitude".into(),
-                    Document::Object(HashMap::from([
-                        ("type".into(), Document::String("string".into())),
-                        (
-                            "description".into(),
-                            Document::String(
-                                "Geographical WGS84 longitude of the location.".into(),
-                            ),
-                        ),
-                    ])),
-                ),
-            ])),
-        ),
-        (
-            "required".into(),
-            Document::Array(vec![
-                Document::String("latitude".into()),
-                Document::String("longitude".into()),
-            ]),
-        ),
-    ]))
+
+// The tool's input schema is generated from this struct, including the field descriptions.
+#[derive(Debug, Deserialize, JsonSchema)]
+struct WeatherInput {
+    /// Geographical WGS84 latitude of the location.
+    latitude: String,
+    /// Geographical WGS84 longitude of the location.
+    longitude: String,
 }
 
 #[derive(Debug)]
@@ -107,12 +73,9 @@ impl From<BuildError> for ToolUseScenarioError {
         ToolUseScenarioError(value.to_string().clone())
     }
 }
-impl From<SdkError<ConverseError, Response>> for ToolUseScenarioError {
-    fn from(value: SdkError<ConverseError, Response>) -> Self {
-        ToolUseScenarioError(match value.as_service_error() {
-            Some(value) => value.meta().message().unwrap_or("Unknown").into(),
-            None => "Unknown".into(),
-        })
+impl From<ConversationError> for ToolUseScenarioError {
+    fn from(value: ConversationError) -> Self {
+        ToolUseScenarioError(value.0)
     }
 }
 // snippet-end:[rust.bedrock-runtime.Converse_AnthropicClaude.tool-use.supporting]
@@ -187,89 +150,77 @@ https://docs.aws.amazon.com/bedrock/latest/userguide/service_code_examples.html
 
 // snippet-start:[rust.bedrock-runtime.Converse_AnthropicClaude.tool-use.weather-tool]
 const ENDPOINT: &str = "https://api.open-meteo.com/v1/forecast";
-async fn fetch_weather_data(
-    tool_use: &ToolUseBlock,
-) -> Result<ToolResultBlock, ToolUseScenarioError> {
-    let input = tool_use.input();
-    let latitude = input
-        .as_object()
-        .unwrap()
-        .get("latitude")
-        .unwrap()
-        .as_string()
-        .unwrap();
-    let longitude = input
-        .as_object()
-        .unwrap()
-        .get("longitude")
-        .unwrap()
-        .as_string()
-        .unwrap();
-    let params = [
-        ("latitude", latitude),
-        ("longitude", longitude),
-        ("current_weather", "true"),
-    ];
-
-    debug!("Calling {ENDPOINT} with {params:?}");
-
-    let response = reqwest::Client::new()
-        .get(ENDPOINT)
-        .query(&params)
-        .send()
-        .await
-        .map_err(|e| ToolUseScenarioError(format!("Error requesting weather: {e:?}")))?
-        .error_for_status()
-        .map_err(|e| ToolUseScenarioError(format!("Failed to request weather: {e:?}")))?;
-
-    debug!("Response: {response:?}");
-
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| ToolUseScenarioError(format!("Error reading response: {e:?}")))?;
-
-    let result = String::from_utf8(bytes.to_vec())
-        .map_err(|_| ToolUseScenarioError("Response was not utf8".into()))?;
-
-    Ok(ToolResultBlock::builder()
-        .tool_use_id(tool_use.tool_use_id())
-        .content(ToolResultContentBlock::Text(result))
-        .build()?)
+struct WeatherTool {
+    http: reqwest::Client,
+}
+
+#[async_trait]
+impl Tool for WeatherTool {
+    type Input = WeatherInput;
+
+    fn name(&self) -> &str {
+        TOOL_NAME
+    }
+
+    fn description(&self) -> &str {
+        TOOL_DESCRIPTION
+    }
+
+    async fn call(&self, input: WeatherInput) -> Result<ToolOutput, ToolError> {
+        println!("\x1b[0;90mExecuting tool: {TOOL_NAME} with input: {input:?}...\x1b[0m");
+        let params = [
+            ("latitude", input.latitude.as_str()),
+            ("longitude", input.longitude.as_str()),
+            ("current_weather", "true"),
+        ];
+
+        debug!("Calling {ENDPOINT} with {params:?}");
+
+        let response = self
+            .http
+            .get(ENDPOINT)
+            .query(&params)
+            .send()
+            .await
+            .map_err(|e| ToolError(format!("Error requesting weather: {e:?}")))?
+            .error_for_status()
+            .map_err(|e| ToolError(format!("Failed to request weather: {e:?}")))?;
+
+        debug!("Response: {response:?}");
+
+        let result = response
+            .text()
+            .await
+            .map_err(|e| ToolError(format!("Error reading response: {e:?}")))?;
+
+        println!("\x1b[0;90mTool responded with {result}\x1b[0m");
+        Ok(ToolOutput::Text(result))
+    }
 }
 // snippet-end:[rust.bedrock-runtime.Converse_AnthropicClaude.tool-use.weather-tool]
 
 // snippet-start:[rust.bedrock-runtime.Converse_AnthropicClaude.tool-use]
-#[derive(Debug)]
-#[allow(dead_code)]
-struct InvokeToolResult(String, ToolResultBlock);
 struct ToolUseScenario {
     client: Client,
-    conversation: Vec<Message>,
-    system_prompt: SystemContentBlock,
-    tool_config: ToolConfiguration,
+    conversation: Conversation,
+    tools: ToolRegistry,
 }
 
 impl ToolUseScenario {
     fn new(client: Client) -> Self {
-        let system_prompt = SystemContentBlock::Text(SYSTEM_PROMPT.into());
-        let tool_config = ToolConfiguration::builder()
-            .tools(Tool::ToolSpec(
-                ToolSpecification::builder()
-                    .name(TOOL_NAME)
-                    .description(TOOL_DESCRIPTION)
-                    .input_schema(ToolInputSchema::Json(make_tool_schema()))
-                    .build()
-                    .unwrap(),
-            ))
-            .build()
-            .unwrap();
+        let conversation = Conversation::new(InferenceProfile {
+            model_id: MODEL_ID.into(),
+            ..Default::default()
+        })
+        .with_system_prompt(SYSTEM_PROMPT);
+        let tools = ToolRegistry::new().register(WeatherTool {
+            http: reqwest::Client::new(),
+        });
 
         ToolUseScenario {
             client,
-            conversation: vec![],
-            system_prompt,
-            tool_config,
+            conversation,
+            tools,
         }
     }
 
@@ -280,120 +231,29 @@ impl ToolUseScenario {
                 break;
             }
 
-            let message = Message::builder()
-                .role(User)
-                .content(ContentBlock::Text(input.unwrap()))
-                .build()
-                .map_err(ToolUseScenarioError::from)?;
-            self.conversation.push(message);
-
-            let response = self.send_to_bedrock().await?;
-
-            self.process_model_response(response).await?;
+            self.conversation.push_user_text(input.unwrap());
+
+            // The registry runs every tool the model asks for, concurrently, and sends back the results,
+            // until the model ends its turn.
+            converse_with_tools(
+                &self.client,
+                &self.tools,
+                &mut self.conversation,
+                MAX_RECURSIONS,
+                print_turn,
+            )
+            .await?;
         }
 
         Ok(())
     }
+}
 
-    async fn send_to_bedrock(&mut self) -> Result<ConverseOutput, ToolUseScenarioError> {
-        debug!("Sending conversation to bedrock");
-        self.client
-            .converse()
-            .model_id(MODEL_ID)
-            .set_messages(Some(self.conversation.clone()))
-            .system(self.system_prompt.clone())
-            .tool_config(self.tool_config.clone())
-            .send()
-            .await
-            .map_err(ToolUseScenarioError::from)
-    }
-
-    async fn process_model_response(
-        &mut self,
-        mut response: ConverseOutput,
-    ) -> Result<(), ToolUseScenarioError> {
-        let mut iteration = 0;
-
-        while iteration < MAX_RECURSIONS {
-            iteration += 1;
-            let message = if let Some(ref output) = response.output {
-                if output.is_message() {
-                    Ok(output.as_message().unwrap().clone())
-                } else {
-                    Err(ToolUseScenarioError(
-                        "Converse Output is not a message".into(),
-                    ))
-                }
-            } else {
-                Err(ToolUseScenarioError("Missing Converse Output".into()))
-            }?;
-
-            self.conversation.push(message.clone());
-
-            match response.stop_reason {
-                StopReason::ToolUse => {
-                    response = self.handle_tool_use(&message).await?;
-                }
-                StopReason::EndTurn => {
-                    print_model_response(&message.content[0])?;
-                    return Ok(());
-                }
-                _ => (),
-            }
-        }
-
-        Err(ToolUseScenarioError(
-            "Exceeded MAX_ITERATIONS when calling tools".into(),
-        ))
-    }
-
-    async fn handle_tool_use(
-        &mut self,
-        message: &Message,
-    ) -> Result<ConverseOutput, ToolUseScenarioError> {
-        let mut tool_results: Vec<ContentBlock> = vec![];
-
-        for block in &message.content {
-            match block {
-                ContentBlock::Text(_) => print_model_response(block)?,
-                ContentBlock::ToolUse(tool) => {
-                    let tool_response = self.invoke_tool(tool).await?;
-                    tool_results.push(ContentBlock::ToolResult(tool_response.1));
-                }
-                _ => (),
-            };
-        }
-
-        let message = Message::builder()
-            .role(User)
-            .set_content(Some(tool_results))
-            .build()?;
-        self.conversation.push(message);
-
-        self.send_to_bedrock().await
-    }
-
-    async fn invoke_tool(
-        &mut self,
-        tool: &ToolUseBlock,
-    ) -> Result<InvokeToolResult, ToolUseScenarioError> {
-        match tool.name() {
-            TOOL_NAME => {
-                println!(
-                    "\x1b[0;90mExecuting tool: {TOOL_NAME} with input: {:?}...\x1b[0m",
-                    tool.input()
-                );
-                let content = fetch_weather_data(tool).await?;
-                println!(
-                    "\x1b[0;90mTool responded with {:?}\x1b[0m",
-                    content.content()
-                );
-                Ok(InvokeToolResult(tool.tool_use_id.clone(), content))
-            }
-            _ => Err(ToolUseScenarioError(format!(
-                "The requested tool with name {} does not exist",
-                tool.name()
-            ))),
+fn print_turn(turn: &ModelTurn) {
+    for block in turn.message.content() {
+        if block.is_text() {
+            // Every block is text, so printing can't fail.
+            let _ = print_model_response(block);
         }
     }
 }
@@ -0,0 +1,909 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A multi-turn conversation with a model, persisted to disk between sessions.
+//!
+//! `Conversation` holds the history, system prompt, and inference profile for one conversation,
+//! and builds `Converse` and `ConverseStream` requests from them. `ConversationStore` saves
+//! conversations as JSON files so they can be resumed by id. `ConversationManager` keeps the
+//! history within a token budget, either by dropping the oldest turns or by asking the model
+//! to summarize them.
+
+use std::{fs, path::PathBuf};
+
+use aws_sdk_bedrockruntime::{
+    error::BuildError,
+    operation::{
+        converse::builders::ConverseFluentBuilder,
+        converse_stream::builders::ConverseStreamFluentBuilder,
+    },
+    types::{
+        ContentBlock, ConversationRole, InferenceConfiguration, Message, SystemContentBlock,
+        ToolResultBlock, ToolResultContentBlock, ToolResultStatus, ToolUseBlock,
+    },
+    Client,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug, info};
+
+use crate::document;
+
+// Claude 3 Haiku, as in the other examples in this crate.
+pub const DEFAULT_MODEL_ID: &str = "anthropic.claude-3-haiku-20240307-v1:0";
+
+// A rough average for English text with Claude's tokenizer. Good enough to decide when to trim.
+const CHARS_PER_TOKEN: usize = 4;
+
+const SUMMARY_PROMPT: &str =
+    "Summarize the following conversation between a user and an assistant. \
+Keep names, numbers, decisions, and open questions. Respond with only the summary.";
+
+#[derive(Debug)]
+pub struct ConversationError(pub String);
+impl std::fmt::Display for ConversationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Conversation error: {}", self.0)
+    }
+}
+impl std::error::Error for ConversationError {}
+impl From<&str> for ConversationError {
+    fn from(value: &str) -> Self {
+        ConversationError(value.into())
+    }
+}
+impl From<BuildError> for ConversationError {
+    fn from(value: BuildError) -> Self {
+        ConversationError(value.to_string())
+    }
+}
+impl From<std::io::Error> for ConversationError {
+    fn from(value: std::io::Error) -> Self {
+        ConversationError(format!("I/O error: {value}"))
+    }
+}
+impl From<serde_json::Error> for ConversationError {
+    fn from(value: serde_json::Error) -> Self {
+        ConversationError(format!("JSON error: {value}"))
+    }
+}
+
+/// The model and inference parameters used for every turn of a conversation.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InferenceProfile {
+    pub model_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+}
+
+impl Default for InferenceProfile {
+    fn default() -> Self {
+        Self {
+            model_id: DEFAULT_MODEL_ID.into(),
+            max_tokens: None,
+            temperature: None,
+            top_p: None,
+            stop_sequences: vec![],
+        }
+    }
+}
+
+impl InferenceProfile {
+    /// Low temperature, for factual answers and tool use.
+    pub fn precise() -> Self {
+        Self {
+            temperature: Some(0.0),
+            max_tokens: Some(1024),
+            ..Default::default()
+        }
+    }
+
+    /// High temperature, for brainstorming and writing.
+    pub fn creative() -> Self {
+        Self {
+            temperature: Some(1.0),
+            top_p: Some(0.9),
+            max_tokens: Some(2048),
+            ..Default::default()
+        }
+    }
+
+    /// Look up a profile by the name used on the command line.
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::default()),
+            "precise" => Some(Self::precise()),
+            "creative" => Some(Self::creative()),
+            _ => None,
+        }
+    }
+
+    pub fn inference_configuration(&self) -> InferenceConfiguration {
+        InferenceConfiguration::builder()
+            .set_max_tokens(self.max_tokens)
+            .set_temperature(self.temperature)
+            .set_top_p(self.top_p)
+            .set_stop_sequences(if self.stop_sequences.is_empty() {
+                None
+            } else {
+                Some(self.stop_sequences.clone())
+            })
+            .build()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+/// A serializable mirror of the Converse API's content blocks.
+/// Only text and tool blocks are kept; images and documents are not persisted.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Content {
+    Text {
+        text: String,
+    },
+    ToolUse {
+        tool_use_id: String,
+        name: String,
+        input: Value,
+    },
+    ToolResult {
+        tool_use_id: String,
+        content: Vec<ToolResultContent>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        status: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolResultContent {
+    Text { text: String },
+    Json { json: Value },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StoredMessage {
+    pub role: Role,
+    pub content: Vec<Content>,
+}
+
+impl StoredMessage {
+    pub fn user_text(text: impl Into<String>) -> Self {
+        Self {
+            role: Role::User,
+            content: vec![Content::Text { text: text.into() }],
+        }
+    }
+
+    /// A user turn that starts an exchange, as opposed to one carrying tool results.
+    fn is_user_text(&self) -> bool {
+        self.role == Role::User
+            && self
+                .content
+                .iter()
+                .all(|content| matches!(content, Content::Text { .. }))
+    }
+
+    fn estimated_tokens(&self) -> usize {
+        self.content
+            .iter()
+            .map(|content| match content {
+                Content::Text { text } => text.len(),
+                Content::ToolUse { name, input, .. } => name.len() + input.to_string().len(),
+                Content::ToolResult { content, .. } => content
+                    .iter()
+                    .map(|c| match c {
+                        ToolResultContent::Text { text } => text.len(),
+                        ToolResultContent::Json { json } => json.to_string().len(),
+                    })
+                    .sum(),
+            })
+            .sum::<usize>()
+            .div_ceil(CHARS_PER_TOKEN)
+    }
+
+    /// The text of this message, for summaries and display.
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .map(|content| match content {
+                Content::Text { text } => text.clone(),
+                Content::ToolUse { name, input, .. } => format!("[called {name} with {input}]"),
+                Content::ToolResult { content, .. } => content
+                    .iter()
+                    .map(|c| match c {
+                        ToolResultContent::Text { text } => text.clone(),
+                        ToolResultContent::Json { json } => json.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl TryFrom<&Message> for StoredMessage {
+    type Error = ConversationError;
+
+    fn try_from(message: &Message) -> Result<Self, Self::Error> {
+        let role = match message.role() {
+            ConversationRole::User => Role::User,
+            ConversationRole::Assistant => Role::Assistant,
+            role => return Err(ConversationError(format!("Unknown role {role:?}"))),
+        };
+        let content = message
+            .content()
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text(text) => Some(Ok(Content::Text { text: text.clone() })),
+                ContentBlock::ToolUse(tool_use) => Some(Ok(Content::ToolUse {
+                    tool_use_id: tool_use.tool_use_id().into(),
+                    name: tool_use.name().into(),
+                    input: document::to_json(tool_use.input()),
+                })),
+                ContentBlock::ToolResult(result) => Some(
+                    result
+                        .content()
+                        .iter()
+                        .map(|c| match c {
+                            ToolResultContentBlock::Text(text) => {
+                                Ok(ToolResultContent::Text { text: text.clone() })
+                            }
+                            ToolResultContentBlock::Json(json) => Ok(ToolResultContent::Json {
+                                json: document::to_json(json),
+                            }),
+                            other => Err(ConversationError(format!(
+                                "Cannot store tool result content {other:?}"
+                            ))),
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                        .map(|content| Content::ToolResult {
+                            tool_use_id: result.tool_use_id().into(),
+                            content,
+                            status: result.status().map(|s| s.as_str().to_string()),
+                        }),
+                ),
+                other => {
+                    debug!(?other, "Not storing content block");
+                    None
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { role, content })
+    }
+}
+
+impl TryFrom<&StoredMessage> for Message {
+    type Error = ConversationError;
+
+    fn try_from(message: &StoredMessage) -> Result<Self, Self::Error> {
+        let content = message
+            .content
+            .iter()
+            .map(|content| {
+                Ok(match content {
+                    Content::Text { text } => ContentBlock::Text(text.clone()),
+                    Content::ToolUse {
+                        tool_use_id,
+                        name,
+                        input,
+                    } => ContentBlock::ToolUse(
+                        ToolUseBlock::builder()
+                            .tool_use_id(tool_use_id)
+                            .name(name)
+                            .input(document::from_json(input.clone()))
+                            .build()?,
+                    ),
+                    Content::ToolResult {
+                        tool_use_id,
+                        content,
+                        status,
+                    } => ContentBlock::ToolResult(
+                        ToolResultBlock::builder()
+                            .tool_use_id(tool_use_id)
+                            .set_content(Some(
+                                content
+                                    .iter()
+                                    .map(|c| match c {
+                                        ToolResultContent::Text { text } => {
+                                            ToolResultContentBlock::Text(text.clone())
+                                        }
+                                        ToolResultContent::Json { json } => {
+                                            ToolResultContentBlock::Json(document::from_json(
+                                                json.clone(),
+                                            ))
+                                        }
+                                    })
+                                    .collect(),
+                            ))
+                            .set_status(status.as_deref().map(ToolResultStatus::from))
+                            .build()?,
+                    ),
+                })
+            })
+            .collect::<Result<Vec<_>, ConversationError>>()?;
+
+        Ok(Message::builder()
+            .role(match message.role {
+                Role::User => ConversationRole::User,
+                Role::Assistant => ConversationRole::Assistant,
+            })
+            .set_content(Some(content))
+            .build()?)
+    }
+}
+
+/// One conversation: its id, system prompt, inference profile, and history.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: String,
+    #[serde(default)]
+    pub system: Vec<String>,
+    #[serde(default)]
+    pub profile: InferenceProfile,
+    /// A summary of turns that were trimmed from `messages`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub messages: Vec<StoredMessage>,
+}
+
+impl Conversation {
+    pub fn new(profile: InferenceProfile) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            system: vec![],
+            profile,
+            summary: None,
+            messages: vec![],
+        }
+    }
+
+    pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system.push(prompt.into());
+        self
+    }
+
+    pub fn push_user_text(&mut self, text: impl Into<String>) {
+        self.messages.push(StoredMessage::user_text(text));
+    }
+
+    /// Record a message from the model, or tool results from the caller.
+    pub fn push(&mut self, message: &Message) -> Result<(), ConversationError> {
+        self.messages.push(StoredMessage::try_from(message)?);
+        Ok(())
+    }
+
+    pub fn messages(&self) -> Result<Vec<Message>, ConversationError> {
+        self.messages.iter().map(Message::try_from).collect()
+    }
+
+    /// The system prompt, followed by the summary of any trimmed turns.
+    pub fn system_blocks(&self) -> Vec<SystemContentBlock> {
+        self.system
+            .iter()
+            .cloned()
+            .chain(
+                self.summary
+                    .iter()
+                    .map(|summary| format!("Summary of the earlier conversation:\n{summary}")),
+            )
+            .map(SystemContentBlock::Text)
+            .collect()
+    }
+
+    pub fn estimated_tokens(&self) -> usize {
+        self.system_blocks()
+            .iter()
+            .filter_map(|block| block.as_text().ok())
+            .map(|text| text.len().div_ceil(CHARS_PER_TOKEN))
+            .sum::<usize>()
+            + self
+                .messages
+                .iter()
+                .map(StoredMessage::estimated_tokens)
+                .sum::<usize>()
+    }
+
+    /// A Converse request for the next turn. Add a tool configuration before sending, if needed.
+    pub fn converse(&self, client: &Client) -> Result<ConverseFluentBuilder, ConversationError> {
+        Ok(client
+            .converse()
+            .model_id(&self.profile.model_id)
+            .set_system(Some(self.system_blocks()))
+            .set_messages(Some(self.messages()?))
+            .inference_config(self.profile.inference_configuration()))
+    }
+
+    /// A ConverseStream request for the next turn.
+    pub fn converse_stream(
+        &self,
+        client: &Client,
+    ) -> Result<ConverseStreamFluentBuilder, ConversationError> {
+        Ok(client
+            .converse_stream()
+            .model_id(&self.profile.model_id)
+            .set_system(Some(self.system_blocks()))
+            .set_messages(Some(self.messages()?))
+            .inference_config(self.profile.inference_configuration()))
+    }
+
+    /// Split off the oldest exchanges until the rest fits in `budget`.
+    /// An exchange starts at a user text message, so tool uses are never separated from their results.
+    /// The most recent exchange is always kept.
+    fn split_to_budget(&mut self, budget: usize) -> Vec<StoredMessage> {
+        let mut dropped = vec![];
+        while self.estimated_tokens() > budget {
+            let next_exchange = self
+                .messages
+                .iter()
+                .skip(1)
+                .position(StoredMessage::is_user_text)
+                .map(|i| i + 1);
+            match next_exchange {
+                Some(end) => dropped.extend(self.messages.drain(..end)),
+                None => break,
+            }
+        }
+        dropped
+    }
+}
+
+/// How to bring a conversation back under its token budget.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ContextStrategy {
+    /// Forget the oldest exchanges.
+    #[default]
+    Drop,
+    /// Replace the oldest exchanges with a model-written summary.
+    Summarize,
+}
+
+/// Saves conversations as `<id>.json` files in a directory.
+#[derive(Clone, Debug)]
+pub struct ConversationStore {
+    dir: PathBuf,
+}
+
+impl ConversationStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, ConversationError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, id: &str) -> Result<PathBuf, ConversationError> {
+        if id.is_empty() || id.contains(['/', '\\', '.']) {
+            return Err(ConversationError(format!("Invalid conversation id {id:?}")));
+        }
+        Ok(self.dir.join(format!("{id}.json")))
+    }
+
+    /// Write the conversation to a temporary file, then move it into place, so a crash never leaves a partial file.
+    pub fn save(&self, conversation: &Conversation) -> Result<(), ConversationError> {
+        let path = self.path(&conversation.id)?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(conversation)?)?;
+        fs::rename(&tmp, &path)?;
+        debug!(?path, "Saved conversation");
+        Ok(())
+    }
+
+    pub fn load(&self, id: &str) -> Result<Conversation, ConversationError> {
+        let path = self.path(id)?;
+        let bytes = fs::read(&path)
+            .map_err(|e| ConversationError(format!("Cannot read conversation {id}: {e}")))?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// The ids of all saved conversations.
+    pub fn list(&self) -> Result<Vec<String>, ConversationError> {
+        let mut ids = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                path.file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .map(String::from)
+            })
+            .collect::<Vec<_>>();
+        ids.sort();
+        Ok(ids)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), ConversationError> {
+        fs::remove_file(self.path(id)?)?;
+        Ok(())
+    }
+}
+
+/// Sends conversation turns to Bedrock, keeping each conversation within a token budget and saved to a store.
+pub struct ConversationManager {
+    client: Client,
+    store: ConversationStore,
+    token_budget: usize,
+    strategy: ContextStrategy,
+}
+
+impl ConversationManager {
+    pub fn new(
+        client: Client,
+        store: ConversationStore,
+        token_budget: usize,
+        strategy: ContextStrategy,
+    ) -> Self {
+        Self {
+            client,
+            store,
+            token_budget,
+            strategy,
+        }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub fn store(&self) -> &ConversationStore {
+        &self.store
+    }
+
+    /// Load a saved conversation, or start a new one when `id` is None. A saved conversation
+    /// keeps its own profile unless `profile` is set, and `model_id` replaces the model of
+    /// whichever profile is used.
+    pub fn resume_or_start(
+        &self,
+        id: Option<&str>,
+        profile: Option<InferenceProfile>,
+        model_id: Option<&str>,
+        system_prompt: Option<&str>,
+    ) -> Result<Conversation, ConversationError> {
+        let mut conversation = match id {
+            Some(id) => {
+                info!(id, "Resuming conversation");
+                let mut conversation = self.store.load(id)?;
+                if let Some(profile) = profile {
+                    conversation.profile = profile;
+                }
+                conversation
+            }
+            None => {
+                let mut conversation = Conversation::new(profile.unwrap_or_default());
+                if let Some(prompt) = system_prompt {
+                    conversation = conversation.with_system_prompt(prompt);
+                }
+                info!(id = conversation.id, "Starting conversation");
+                conversation
+            }
+        };
+        if let Some(model_id) = model_id {
+            conversation.profile.model_id = model_id.to_string();
+        }
+        Ok(conversation)
+    }
+
+    /// Trim the conversation to the budget, using the configured strategy.
+    pub async fn fit_to_budget(
+        &self,
+        conversation: &mut Conversation,
+    ) -> Result<(), ConversationError> {
+        let dropped = conversation.split_to_budget(self.token_budget);
+        if dropped.is_empty() {
+            return Ok(());
+        }
+        info!(
+            dropped = dropped.len(),
+            strategy = ?self.strategy,
+            "Conversation over token budget"
+        );
+        if self.strategy == ContextStrategy::Summarize {
+            conversation.summary = Some(self.summarize(conversation, &dropped).await?);
+        }
+        Ok(())
+    }
+
+    async fn summarize(
+        &self,
+        conversation: &Conversation,
+        dropped: &[StoredMessage],
+    ) -> Result<String, ConversationError> {
+        let transcript = conversation
+            .summary
+            .iter()
+            .map(|summary| format!("(Earlier summary) {summary}"))
+            .chain(dropped.iter().map(|message| {
+                let speaker = match message.role {
+                    Role::User => "User",
+                    Role::Assistant => "Assistant",
+                };
+                format!("{speaker}: {}", message.text())
+            }))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let response = self
+            .client
+            .converse()
+            .model_id(&conversation.profile.model_id)
+            .system(SystemContentBlock::Text(SUMMARY_PROMPT.into()))
+            .messages(
+                Message::builder()
+                    .role(ConversationRole::User)
+                    .content(ContentBlock::Text(transcript))
+                    .build()?,
+            )
+            .send()
+            .await
+            .map_err(|e| ConversationError(format!("Failed to summarize conversation: {e}")))?;
+
+        response
+            .output()
+            .and_then(|output| output.as_message().ok())
+            .and_then(|message| message.content().first())
+            .and_then(|content| content.as_text().ok())
+            .cloned()
+            .ok_or_else(|| ConversationError("Summary response had no text".into()))
+    }
+
+    /// A copy of the conversation with a user message added, trimmed to the budget.
+    /// Record the reply in the copy and keep it only once the turn succeeds, so a failed
+    /// request doesn't leave an unanswered user message in the history.
+    pub async fn next_turn(
+        &self,
+        conversation: &Conversation,
+        text: impl Into<String>,
+    ) -> Result<Conversation, ConversationError> {
+        let mut next = conversation.clone();
+        next.push_user_text(text);
+        self.fit_to_budget(&mut next).await?;
+        Ok(next)
+    }
+
+    /// Add a user message, send the conversation with Converse, record and save the reply.
+    /// If any step fails, the conversation is left as it was.
+    pub async fn send(
+        &self,
+        conversation: &mut Conversation,
+        text: impl Into<String>,
+    ) -> Result<Message, ConversationError> {
+        let mut next = self.next_turn(conversation, text).await?;
+
+        let response = next
+            .converse(&self.client)?
+            .send()
+            .await
+            .map_err(|e| ConversationError(format!("Converse failed: {e}")))?;
+
+        let message = response
+            .output()
+            .and_then(|output| output.as_message().ok())
+            .cloned()
+            .ok_or_else(|| ConversationError("Converse output is not a message".into()))?;
+
+        self.record(&mut next, &message)?;
+        *conversation = next;
+        Ok(message)
+    }
+
+    /// Record a reply that was received some other way, such as assembled from a ConverseStream, and save.
+    pub fn record(
+        &self,
+        conversation: &mut Conversation,
+        message: &Message,
+    ) -> Result<(), ConversationError> {
+        conversation.push(message)?;
+        self.store.save(conversation)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        Content, ContextStrategy, Conversation, ConversationManager, ConversationStore,
+        InferenceProfile, Role, StoredMessage, ToolResultContent,
+    };
+    use aws_sdk_bedrockruntime::{types::Message, Client};
+    use aws_smithy_runtime::client::http::test_util::StaticReplayClient;
+    use sdk_examples_test_utils::{client_config, test_event};
+    use serde_json::json;
+
+    fn tool_exchange() -> Vec<StoredMessage> {
+        vec![
+            StoredMessage::user_text("What's the weather in Rome?"),
+            StoredMessage {
+                role: Role::Assistant,
+                content: vec![Content::ToolUse {
+                    tool_use_id: "t1".into(),
+                    name: "Weather_Tool".into(),
+                    input: json!({"latitude": "41.9", "longitude": "12.5"}),
+                }],
+            },
+            StoredMessage {
+                role: Role::User,
+                content: vec![Content::ToolResult {
+                    tool_use_id: "t1".into(),
+                    content: vec![ToolResultContent::Json {
+                        json: json!({"temperature": 21.5}),
+                    }],
+                    status: Some("success".into()),
+                }],
+            },
+            StoredMessage {
+                role: Role::Assistant,
+                content: vec![Content::Text {
+                    text: "It is 21.5°C in Rome.".into(),
+                }],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_message_round_trip() {
+        for stored in tool_exchange() {
+            let message = Message::try_from(&stored).unwrap();
+            assert_eq!(StoredMessage::try_from(&message).unwrap(), stored);
+        }
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ConversationStore::new(dir.path()).unwrap();
+
+        let mut conversation =
+            Conversation::new(InferenceProfile::precise()).with_system_prompt("Be brief.");
+        conversation.messages = tool_exchange();
+        store.save(&conversation).unwrap();
+
+        assert_eq!(store.list().unwrap(), vec![conversation.id.clone()]);
+        assert_eq!(store.load(&conversation.id).unwrap(), conversation);
+
+        store.delete(&conversation.id).unwrap();
+        assert!(store.list().unwrap().is_empty());
+        assert!(store.load("../etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_resume_with_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::new(
+            Client::from_conf(client_config!(aws_sdk_bedrockruntime).build()),
+            ConversationStore::new(dir.path()).unwrap(),
+            4000,
+            ContextStrategy::Drop,
+        );
+        let saved = Conversation::new(InferenceProfile::precise());
+        manager.store().save(&saved).unwrap();
+
+        let resumed = manager
+            .resume_or_start(Some(&saved.id), None, None, None)
+            .unwrap();
+        assert_eq!(resumed.profile, InferenceProfile::precise());
+
+        let resumed = manager
+            .resume_or_start(
+                Some(&saved.id),
+                Some(InferenceProfile::creative()),
+                Some("test-model"),
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            resumed.profile.temperature,
+            InferenceProfile::creative().temperature
+        );
+        assert_eq!(resumed.profile.model_id, "test-model");
+
+        let started = manager
+            .resume_or_start(None, None, Some("test-model"), Some("Be brief."))
+            .unwrap();
+        assert_eq!(started.profile.model_id, "test-model");
+        assert_eq!(started.system, vec!["Be brief.".to_string()]);
+    }
+
+    #[test]
+    fn test_split_keeps_exchanges_whole() {
+        let mut conversation = Conversation::new(InferenceProfile::default());
+        conversation.messages = tool_exchange();
+        conversation.messages.extend(tool_exchange());
+        conversation.messages.extend(tool_exchange());
+
+        let total = conversation.estimated_tokens();
+        let dropped = conversation.split_to_budget(total - 1);
+
+        assert_eq!(dropped.len(), 4);
+        assert_eq!(conversation.messages.len(), 8);
+        assert!(conversation.messages[0].is_user_text());
+    }
+
+    #[test]
+    fn test_split_keeps_latest_exchange() {
+        let mut conversation = Conversation::new(InferenceProfile::default());
+        conversation.messages = tool_exchange();
+
+        let dropped = conversation.split_to_budget(0);
+
+        assert!(dropped.is_empty());
+        assert_eq!(conversation.messages.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_failed_send_keeps_history() {
+        let client = Client::from_conf(
+            client_config!(aws_sdk_bedrockruntime)
+                .http_client(StaticReplayClient::new(vec![
+                    test_event!(
+                        r#""#,
+                        (
+                            400,
+                            vec![("x-amzn-errortype", "ValidationException")],
+                            r#"{"message": "The model is not available."}"#
+                        )
+                    ),
+                    test_event!(
+                        r#""#,
+                        (
+                            200,
+                            r#"{"output": {"message": {"role": "assistant", "content": [{"text": "Hello!"}]}},
+                            "stopReason": "end_turn",
+                            "usage": {"inputTokens": 4, "outputTokens": 2, "totalTokens": 6},
+                            "metrics": {"latencyMs": 100}}"#
+                        )
+                    ),
+                ]))
+                .build(),
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::new(
+            client,
+            ConversationStore::new(dir.path()).unwrap(),
+            4000,
+            ContextStrategy::Drop,
+        );
+        let mut conversation = Conversation::new(InferenceProfile::default());
+
+        assert!(manager.send(&mut conversation, "Hi?").await.is_err());
+        assert!(conversation.messages.is_empty());
+
+        manager.send(&mut conversation, "Hi").await.unwrap();
+        assert_eq!(
+            conversation.messages,
+            vec![
+                StoredMessage::user_text("Hi"),
+                StoredMessage {
+                    role: Role::Assistant,
+                    content: vec![Content::Text {
+                        text: "Hello!".into()
+                    }],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_summary_in_system_prompt() {
+        let mut conversation =
+            Conversation::new(InferenceProfile::default()).with_system_prompt("Be brief.");
+        conversation.summary = Some("The user asked about Rome.".into());
+        let system = conversation.system_blocks();
+        assert_eq!(system.len(), 2);
+        assert!(system[1]
+            .as_text()
+            .unwrap()
+            .ends_with("The user asked about Rome."));
+    }
+}
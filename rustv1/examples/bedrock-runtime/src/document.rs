@@ -0,0 +1,62 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Conversions between `aws_smithy_types::Document`, which the Converse API uses
+//! for tool inputs and schemas, and `serde_json::Value`, which the rest of Rust uses.
+
+use aws_smithy_types::{Document, Number};
+use serde_json::Value;
+
+/// Convert a JSON value to a Document.
+pub fn from_json(value: Value) -> Document {
+    match value {
+        Value::Null => Document::Null,
+        Value::Bool(b) => Document::Bool(b),
+        Value::Number(n) => Document::Number(if let Some(u) = n.as_u64() {
+            Number::PosInt(u)
+        } else if let Some(i) = n.as_i64() {
+            Number::NegInt(i)
+        } else {
+            Number::Float(n.as_f64().unwrap_or_default())
+        }),
+        Value::String(s) => Document::String(s),
+        Value::Array(a) => Document::Array(a.into_iter().map(from_json).collect()),
+        Value::Object(o) => {
+            Document::Object(o.into_iter().map(|(k, v)| (k, from_json(v))).collect())
+        }
+    }
+}
+
+/// Convert a Document to a JSON value. Floats that JSON can't represent, such as NaN, become null.
+pub fn to_json(document: &Document) -> Value {
+    match document {
+        Document::Null => Value::Null,
+        Document::Bool(b) => Value::Bool(*b),
+        Document::Number(Number::PosInt(u)) => Value::from(*u),
+        Document::Number(Number::NegInt(i)) => Value::from(*i),
+        Document::Number(Number::Float(f)) => Value::from(*f),
+        Document::String(s) => Value::String(s.clone()),
+        Document::Array(a) => Value::Array(a.iter().map(to_json).collect()),
+        Document::Object(o) => {
+            Value::Object(o.iter().map(|(k, v)| (k.clone(), to_json(v))).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{from_json, to_json};
+    use serde_json::json;
+
+    #[test]
+    fn test_round_trip() {
+        let value = json!({
+            "latitude": "40.7",
+            "count": 3,
+            "offset": -2,
+            "ratio": 0.5,
+            "tags": ["a", null, true],
+        });
+        assert_eq!(to_json(&from_json(value.clone())), value);
+    }
+}
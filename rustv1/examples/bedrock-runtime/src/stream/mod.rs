@@ -0,0 +1,413 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reassemble a ConverseStream into the same `Message` that Converse would have returned.
+//!
+//! `StreamAggregator` collects text deltas and tool use JSON fragments into complete
+//! `ContentBlock`s, and keeps the stop reason, token usage, and latency that arrive at the end
+//! of the stream. `consume_stream` drives an aggregator from any `EventSource`, either the SDK's
+//! event receiver or a `recorded::RecordedStream`, and stops early if cancelled.
+
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use aws_sdk_bedrockruntime::{
+    error::ProvideErrorMetadata,
+    primitives::event_stream::EventReceiver,
+    types::{
+        error::ConverseStreamOutputError, ContentBlock, ContentBlockDelta, ContentBlockStart,
+        ConversationRole, ConverseStreamOutput, Message, StopReason, TokenUsage, ToolUseBlock,
+    },
+};
+use tracing::{debug, warn};
+
+use crate::document;
+
+pub mod recorded;
+
+#[derive(Debug)]
+pub struct StreamError(pub String);
+impl std::fmt::Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Stream error: {}", self.0)
+    }
+}
+impl std::error::Error for StreamError {}
+impl From<&str> for StreamError {
+    fn from(value: &str) -> Self {
+        StreamError(value.into())
+    }
+}
+
+/// Where stream events come from.
+#[async_trait]
+pub trait EventSource: Send {
+    /// The next event, or None when the stream has ended.
+    async fn recv(&mut self) -> Result<Option<ConverseStreamOutput>, StreamError>;
+}
+
+#[async_trait]
+impl EventSource for EventReceiver<ConverseStreamOutput, ConverseStreamOutputError> {
+    async fn recv(&mut self) -> Result<Option<ConverseStreamOutput>, StreamError> {
+        EventReceiver::recv(self).await.map_err(|e| {
+            StreamError(match e.as_service_error() {
+                Some(e) => e
+                    .message()
+                    .unwrap_or("Unknown StreamOutput exception")
+                    .into(),
+                None => format!("Error receiving stream: {e}"),
+            })
+        })
+    }
+}
+
+/// A content block still being streamed.
+#[derive(Debug)]
+enum PartialBlock {
+    Text(String),
+    ToolUse {
+        tool_use_id: String,
+        name: String,
+        input: String,
+    },
+}
+
+/// Timing and usage for a streamed response.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StreamMetrics {
+    pub input_tokens: Option<i32>,
+    pub output_tokens: Option<i32>,
+    pub total_tokens: Option<i32>,
+    /// Latency reported by Bedrock.
+    pub latency_ms: Option<i64>,
+    /// Time from the first event to the first text or tool use delta, measured locally.
+    pub time_to_first_token: Option<Duration>,
+    /// Time from the first event to the last, measured locally.
+    pub elapsed: Duration,
+}
+
+/// The reassembled response.
+#[derive(Clone, Debug)]
+pub struct StreamedResponse {
+    pub message: Message,
+    /// None when the stream was cancelled or ended before MessageStop.
+    pub stop_reason: Option<StopReason>,
+    pub metrics: StreamMetrics,
+    pub cancelled: bool,
+}
+
+impl StreamedResponse {
+    /// All text in the message, concatenated.
+    pub fn text(&self) -> String {
+        self.message
+            .content()
+            .iter()
+            .filter_map(|block| block.as_text().ok())
+            .cloned()
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+pub struct StreamAggregator {
+    role: ConversationRole,
+    blocks: BTreeMap<i32, PartialBlock>,
+    stop_reason: Option<StopReason>,
+    usage: Option<TokenUsage>,
+    latency_ms: Option<i64>,
+    started: Option<Instant>,
+    first_token: Option<Duration>,
+    last_event: Option<Instant>,
+}
+
+impl Default for StreamAggregator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamAggregator {
+    pub fn new() -> Self {
+        Self {
+            role: ConversationRole::Assistant,
+            blocks: BTreeMap::new(),
+            stop_reason: None,
+            usage: None,
+            latency_ms: None,
+            started: None,
+            first_token: None,
+            last_event: None,
+        }
+    }
+
+    /// Add an event to the response. Returns the text it added, if any, for display.
+    pub fn push(&mut self, event: ConverseStreamOutput) -> Result<Option<String>, StreamError> {
+        let now = Instant::now();
+        let started = *self.started.get_or_insert(now);
+        self.last_event = Some(now);
+
+        match event {
+            ConverseStreamOutput::MessageStart(event) => {
+                self.role = event.role;
+            }
+            ConverseStreamOutput::ContentBlockStart(event) => {
+                match event.start {
+                    Some(ContentBlockStart::ToolUse(start)) => {
+                        self.blocks.insert(
+                            event.content_block_index,
+                            PartialBlock::ToolUse {
+                                tool_use_id: start.tool_use_id,
+                                name: start.name,
+                                input: String::new(),
+                            },
+                        );
+                    }
+                    other => debug!(?other, "Ignoring content block start"),
+                };
+            }
+            ConverseStreamOutput::ContentBlockDelta(event) => {
+                self.first_token.get_or_insert(now - started);
+                let index = event.content_block_index;
+                match event.delta {
+                    Some(ContentBlockDelta::Text(text)) => {
+                        // Text blocks have no start event; the first delta opens them.
+                        match self
+                            .blocks
+                            .entry(index)
+                            .or_insert_with(|| PartialBlock::Text(String::new()))
+                        {
+                            PartialBlock::Text(block) => block.push_str(&text),
+                            PartialBlock::ToolUse { .. } => {
+                                return Err(StreamError(format!(
+                                    "Text delta for tool use block {index}"
+                                )))
+                            }
+                        }
+                        return Ok(Some(text));
+                    }
+                    Some(ContentBlockDelta::ToolUse(delta)) => match self.blocks.get_mut(&index) {
+                        Some(PartialBlock::ToolUse { input, .. }) => input.push_str(&delta.input),
+                        _ => {
+                            return Err(StreamError(format!(
+                                "Tool use delta for block {index}, which is not a tool use"
+                            )))
+                        }
+                    },
+                    other => debug!(?other, "Ignoring content block delta"),
+                }
+            }
+            ConverseStreamOutput::ContentBlockStop(event) => {
+                debug!(index = event.content_block_index, "Content block complete");
+            }
+            ConverseStreamOutput::MessageStop(event) => {
+                self.stop_reason = Some(event.stop_reason);
+            }
+            ConverseStreamOutput::Metadata(event) => {
+                self.usage = event.usage;
+                self.latency_ms = event.metrics.map(|metrics| metrics.latency_ms);
+            }
+            other => warn!(?other, "Unknown stream event"),
+        }
+        Ok(None)
+    }
+
+    /// The response so far. Tool use input that never finished streaming is an error, since it
+    /// can't be parsed, unless the stream was cancelled. A cancelled response keeps only its
+    /// text, as a tool use that was cut off can't be run.
+    pub fn finish(self, cancelled: bool) -> Result<StreamedResponse, StreamError> {
+        let content = self
+            .blocks
+            .into_values()
+            .filter(|block| match block {
+                PartialBlock::ToolUse { name, .. } if cancelled => {
+                    debug!(%name, "Dropping tool use from cancelled stream");
+                    false
+                }
+                _ => true,
+            })
+            .map(|block| match block {
+                PartialBlock::Text(text) => Ok(ContentBlock::Text(text)),
+                PartialBlock::ToolUse {
+                    tool_use_id,
+                    name,
+                    input,
+                } => {
+                    // A tool with no parameters streams no input at all.
+                    let input = if input.is_empty() { "{}" } else { &input };
+                    let input = serde_json::from_str(input).map_err(|e| {
+                        StreamError(format!("Incomplete input for tool {name}: {e}"))
+                    })?;
+                    ToolUseBlock::builder()
+                        .tool_use_id(tool_use_id)
+                        .name(name)
+                        .input(document::from_json(input))
+                        .build()
+                        .map(ContentBlock::ToolUse)
+                        .map_err(|e| StreamError(e.to_string()))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let message = Message::builder()
+            .role(self.role)
+            .set_content(Some(content))
+            .build()
+            .map_err(|e| StreamError(e.to_string()))?;
+
+        Ok(StreamedResponse {
+            message,
+            stop_reason: self.stop_reason,
+            metrics: StreamMetrics {
+                input_tokens: self.usage.as_ref().map(|usage| usage.input_tokens),
+                output_tokens: self.usage.as_ref().map(|usage| usage.output_tokens),
+                total_tokens: self.usage.as_ref().map(|usage| usage.total_tokens),
+                latency_ms: self.latency_ms,
+                time_to_first_token: self.first_token,
+                elapsed: match (self.started, self.last_event) {
+                    (Some(started), Some(last)) => last - started,
+                    _ => Duration::ZERO,
+                },
+            },
+            cancelled,
+        })
+    }
+}
+
+/// Read every event from `source` into a response, calling `on_text` with each text delta.
+/// If `cancel` completes first, stop reading and return what has arrived, marked as cancelled.
+pub async fn consume_stream(
+    source: &mut impl EventSource,
+    cancel: impl Future<Output = ()>,
+    mut on_text: impl FnMut(&str),
+) -> Result<StreamedResponse, StreamError> {
+    let mut aggregator = StreamAggregator::new();
+    tokio::pin!(cancel);
+
+    loop {
+        tokio::select! {
+            _ = &mut cancel => {
+                debug!("Stream cancelled");
+                return aggregator.finish(true);
+            }
+            event = source.recv() => match event? {
+                Some(event) => {
+                    if let Some(text) = aggregator.push(event)? {
+                        on_text(&text);
+                    }
+                }
+                None => return aggregator.finish(false),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{consume_stream, recorded::RecordedStream, EventSource, StreamAggregator};
+    use crate::document;
+    use aws_sdk_bedrockruntime::types::StopReason;
+    use serde_json::json;
+
+    const TOOL_USE: &str = include_str!("./fixtures/tool-use.jsonl");
+    const TEXT: &str = include_str!("./fixtures/text.jsonl");
+
+    #[tokio::test]
+    async fn test_text_stream() {
+        let mut source = RecordedStream::parse(TEXT).unwrap();
+        let mut printed = String::new();
+        let response = consume_stream(&mut source, std::future::pending(), |text| {
+            printed.push_str(text)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            response.text(),
+            "A 'hello world' program shows the minimum code needed to print output."
+        );
+        assert_eq!(printed, response.text());
+        assert_eq!(response.stop_reason, Some(StopReason::EndTurn));
+        assert_eq!(response.metrics.output_tokens, Some(16));
+        assert_eq!(response.metrics.total_tokens, Some(37));
+        assert_eq!(response.metrics.latency_ms, Some(402));
+        assert!(response.metrics.time_to_first_token.is_some());
+        assert!(!response.cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_tool_use_stream() {
+        let mut source = RecordedStream::parse(TOOL_USE).unwrap();
+        let response = consume_stream(&mut source, std::future::pending(), |_| {})
+            .await
+            .unwrap();
+
+        assert_eq!(response.stop_reason, Some(StopReason::ToolUse));
+        let content = response.message.content();
+        assert_eq!(content.len(), 2);
+        assert_eq!(
+            content[0].as_text().unwrap(),
+            "Let me check the weather in Rome."
+        );
+        let tool_use = content[1].as_tool_use().unwrap();
+        assert_eq!(tool_use.tool_use_id(), "tooluse_1");
+        assert_eq!(tool_use.name(), "Weather_Tool");
+        assert_eq!(
+            document::to_json(tool_use.input()),
+            json!({"latitude": "41.9", "longitude": "12.5"})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_stream() {
+        let mut source = RecordedStream::parse(TEXT).unwrap();
+        let response = consume_stream(&mut source, std::future::ready(()), |_| {})
+            .await
+            .unwrap();
+
+        assert!(response.cancelled);
+        assert_eq!(response.stop_reason, None);
+    }
+
+    #[tokio::test]
+    async fn test_stream_error() {
+        let mut source = RecordedStream::parse(TEXT)
+            .unwrap()
+            .fail_after(2, "ThrottlingException");
+        let result = consume_stream(&mut source, std::future::pending(), |_| {}).await;
+        assert_eq!(result.unwrap_err().0, "ThrottlingException");
+    }
+
+    /// A stream cut off in the middle of tool input can't produce a valid tool use.
+    #[tokio::test]
+    async fn test_incomplete_tool_input() {
+        let mut source = RecordedStream::parse(TOOL_USE)
+            .unwrap()
+            .fail_after(8, "Connection reset");
+        let mut aggregator = StreamAggregator::new();
+        while let Ok(Some(event)) = source.recv().await {
+            aggregator.push(event).unwrap();
+        }
+        assert!(aggregator.finish(false).is_err());
+    }
+
+    /// Cancelling in the middle of tool input keeps the text, and drops the tool use.
+    #[tokio::test]
+    async fn test_cancelled_tool_input() {
+        let mut source = RecordedStream::parse(TOOL_USE)
+            .unwrap()
+            .fail_after(8, "Connection reset");
+        let mut aggregator = StreamAggregator::new();
+        while let Ok(Some(event)) = source.recv().await {
+            aggregator.push(event).unwrap();
+        }
+        let response = aggregator.finish(true).unwrap();
+
+        assert!(response.cancelled);
+        assert_eq!(response.message.content().len(), 1);
+        assert_eq!(response.text(), "Let me check the weather in Rome.");
+    }
+}
@@ -0,0 +1,200 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replay a ConverseStream from a recorded fixture, for testing stream consumers offline.
+//!
+//! A fixture has one event per line, in the JSON shape Bedrock sends over the wire:
+//! `{"messageStart":{"role":"assistant"}}`, `{"contentBlockDelta":{...}}`, and so on.
+
+use std::collections::VecDeque;
+
+use async_trait::async_trait;
+use aws_sdk_bedrockruntime::{
+    error::BuildError,
+    types::{
+        ContentBlockDelta, ContentBlockDeltaEvent, ContentBlockStart, ContentBlockStartEvent,
+        ContentBlockStopEvent, ConversationRole, ConverseStreamMetadataEvent,
+        ConverseStreamMetrics, ConverseStreamOutput, MessageStartEvent, MessageStopEvent,
+        StopReason, TokenUsage, ToolUseBlockDelta, ToolUseBlockStart,
+    },
+};
+use serde::Deserialize;
+
+use super::{EventSource, StreamError};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum RecordedEvent {
+    MessageStart {
+        role: String,
+    },
+    ContentBlockStart {
+        #[serde(rename = "contentBlockIndex")]
+        index: i32,
+        start: RecordedStart,
+    },
+    ContentBlockDelta {
+        #[serde(rename = "contentBlockIndex")]
+        index: i32,
+        delta: RecordedDelta,
+    },
+    ContentBlockStop {
+        #[serde(rename = "contentBlockIndex")]
+        index: i32,
+    },
+    MessageStop {
+        #[serde(rename = "stopReason")]
+        stop_reason: String,
+    },
+    Metadata {
+        usage: RecordedUsage,
+        metrics: RecordedMetrics,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum RecordedStart {
+    ToolUse {
+        #[serde(rename = "toolUseId")]
+        tool_use_id: String,
+        name: String,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum RecordedDelta {
+    Text(String),
+    ToolUse { input: String },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordedUsage {
+    input_tokens: i32,
+    output_tokens: i32,
+    total_tokens: i32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordedMetrics {
+    latency_ms: i64,
+}
+
+impl TryFrom<RecordedEvent> for ConverseStreamOutput {
+    type Error = BuildError;
+
+    fn try_from(event: RecordedEvent) -> Result<Self, Self::Error> {
+        Ok(match event {
+            RecordedEvent::MessageStart { role } => ConverseStreamOutput::MessageStart(
+                MessageStartEvent::builder()
+                    .role(ConversationRole::from(role.as_str()))
+                    .build()?,
+            ),
+            RecordedEvent::ContentBlockStart { index, start } => {
+                let start = match start {
+                    RecordedStart::ToolUse { tool_use_id, name } => ContentBlockStart::ToolUse(
+                        ToolUseBlockStart::builder()
+                            .tool_use_id(tool_use_id)
+                            .name(name)
+                            .build()?,
+                    ),
+                };
+                ConverseStreamOutput::ContentBlockStart(
+                    ContentBlockStartEvent::builder()
+                        .content_block_index(index)
+                        .start(start)
+                        .build()?,
+                )
+            }
+            RecordedEvent::ContentBlockDelta { index, delta } => {
+                let delta = match delta {
+                    RecordedDelta::Text(text) => ContentBlockDelta::Text(text),
+                    RecordedDelta::ToolUse { input } => ContentBlockDelta::ToolUse(
+                        ToolUseBlockDelta::builder().input(input).build()?,
+                    ),
+                };
+                ConverseStreamOutput::ContentBlockDelta(
+                    ContentBlockDeltaEvent::builder()
+                        .content_block_index(index)
+                        .delta(delta)
+                        .build()?,
+                )
+            }
+            RecordedEvent::ContentBlockStop { index } => ConverseStreamOutput::ContentBlockStop(
+                ContentBlockStopEvent::builder()
+                    .content_block_index(index)
+                    .build()?,
+            ),
+            RecordedEvent::MessageStop { stop_reason } => ConverseStreamOutput::MessageStop(
+                MessageStopEvent::builder()
+                    .stop_reason(StopReason::from(stop_reason.as_str()))
+                    .build()?,
+            ),
+            RecordedEvent::Metadata { usage, metrics } => ConverseStreamOutput::Metadata(
+                ConverseStreamMetadataEvent::builder()
+                    .usage(
+                        TokenUsage::builder()
+                            .input_tokens(usage.input_tokens)
+                            .output_tokens(usage.output_tokens)
+                            .total_tokens(usage.total_tokens)
+                            .build()?,
+                    )
+                    .metrics(
+                        ConverseStreamMetrics::builder()
+                            .latency_ms(metrics.latency_ms)
+                            .build()?,
+                    )
+                    .build(),
+            ),
+        })
+    }
+}
+
+/// An EventSource that replays recorded events.
+pub struct RecordedStream {
+    events: VecDeque<ConverseStreamOutput>,
+    /// When set, replaying fails with this error after the recorded events run out.
+    error: Option<String>,
+}
+
+impl RecordedStream {
+    /// Parse a fixture with one JSON event per line. Blank lines are skipped.
+    pub fn parse(fixture: &str) -> Result<Self, StreamError> {
+        let events = fixture
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let event: RecordedEvent = serde_json::from_str(line)
+                    .map_err(|e| StreamError(format!("Bad fixture line {line}: {e}")))?;
+                ConverseStreamOutput::try_from(event).map_err(|e| StreamError(e.to_string()))
+            })
+            .collect::<Result<VecDeque<_>, _>>()?;
+        Ok(Self {
+            events,
+            error: None,
+        })
+    }
+
+    /// Keep only the first `count` events, then fail, as if the connection dropped.
+    pub fn fail_after(mut self, count: usize, error: &str) -> Self {
+        self.events.truncate(count);
+        self.error = Some(error.into());
+        self
+    }
+}
+
+#[async_trait]
+impl EventSource for RecordedStream {
+    async fn recv(&mut self) -> Result<Option<ConverseStreamOutput>, StreamError> {
+        match self.events.pop_front() {
+            Some(event) => Ok(Some(event)),
+            None => match self.error.take() {
+                Some(error) => Err(StreamError(error)),
+                None => Ok(None),
+            },
+        }
+    }
+}
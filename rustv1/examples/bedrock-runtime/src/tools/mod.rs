@@ -0,0 +1,461 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tools a model can call through the Converse API.
+//!
+//! Implement `Tool` for each capability, with an input type that derives `Deserialize` and
+//! `JsonSchema`. A `ToolRegistry` builds the `ToolConfiguration` from the registered tools,
+//! runs every `ToolUse` block in a turn concurrently, and turns failures and timeouts into
+//! error `ToolResultBlock`s the model can read. `converse_with_tools` drives a conversation
+//! until the model stops asking for tools.
+
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use aws_sdk_bedrockruntime::{
+    error::BuildError,
+    types::{
+        ContentBlock, ConversationRole, Message, StopReason, Tool as SdkTool, ToolConfiguration,
+        ToolInputSchema, ToolResultBlock, ToolResultContentBlock, ToolResultStatus,
+        ToolSpecification, ToolUseBlock,
+    },
+};
+use schemars::{gen::SchemaSettings, JsonSchema};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use crate::{
+    conversation::{Conversation, ConversationError},
+    document,
+};
+
+mod model;
+pub mod scripted;
+
+pub use model::{ConverseModel, ModelTurn};
+
+// Long enough for a typical HTTP call, short enough that a hung tool doesn't stall the conversation.
+const DEFAULT_TOOL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What a tool returns to the model.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ToolOutput {
+    Text(String),
+    Json(Value),
+}
+
+#[derive(Debug)]
+pub struct ToolError(pub String);
+impl std::fmt::Display for ToolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::error::Error for ToolError {}
+impl From<&str> for ToolError {
+    fn from(value: &str) -> Self {
+        ToolError(value.into())
+    }
+}
+
+/// A capability the model can call. The input schema sent to the model is generated from `Input`,
+/// including descriptions from its doc comments.
+#[async_trait]
+pub trait Tool: Send + Sync + 'static {
+    type Input: DeserializeOwned + JsonSchema + Send;
+
+    fn name(&self) -> &str;
+
+    fn description(&self) -> &str;
+
+    async fn call(&self, input: Self::Input) -> Result<ToolOutput, ToolError>;
+}
+
+/// The JSON schema for a tool input type, with all subschemas inlined as the Converse API expects.
+pub fn input_schema<T: JsonSchema>() -> Value {
+    let generator = SchemaSettings::draft07()
+        .with(|settings| {
+            settings.inline_subschemas = true;
+            settings.meta_schema = None;
+        })
+        .into_generator();
+    let mut schema = serde_json::to_value(generator.into_root_schema_for::<T>())
+        .unwrap_or_else(|_| Value::Object(Default::default()));
+    if let Some(schema) = schema.as_object_mut() {
+        // The title is the Rust type name, which means nothing to the model.
+        schema.remove("title");
+    }
+    schema
+}
+
+/// Tool with its input type erased, so tools with different inputs can share a registry.
+#[async_trait]
+trait RegisteredTool: Send + Sync {
+    fn specification(&self) -> Result<ToolSpecification, BuildError>;
+
+    async fn call_json(&self, input: Value) -> Result<ToolOutput, ToolError>;
+}
+
+struct Registered<T>(T);
+
+#[async_trait]
+impl<T: Tool> RegisteredTool for Registered<T> {
+    fn specification(&self) -> Result<ToolSpecification, BuildError> {
+        ToolSpecification::builder()
+            .name(self.0.name())
+            .description(self.0.description())
+            .input_schema(ToolInputSchema::Json(document::from_json(input_schema::<
+                T::Input,
+            >())))
+            .build()
+    }
+
+    async fn call_json(&self, input: Value) -> Result<ToolOutput, ToolError> {
+        let input = serde_json::from_value(input)
+            .map_err(|e| ToolError(format!("Invalid input for {}: {e}", self.0.name())))?;
+        self.0.call(input).await
+    }
+}
+
+/// The set of tools offered to the model.
+pub struct ToolRegistry {
+    tools: BTreeMap<String, Arc<dyn RegisteredTool>>,
+    timeout: Duration,
+}
+
+impl Default for ToolRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self {
+            tools: BTreeMap::new(),
+            timeout: DEFAULT_TOOL_TIMEOUT,
+        }
+    }
+
+    /// How long a single tool call may run before it's reported to the model as failed.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Add a tool. A tool with the same name replaces the earlier one.
+    pub fn register(mut self, tool: impl Tool) -> Self {
+        self.tools
+            .insert(tool.name().to_string(), Arc::new(Registered(tool)));
+        self
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.tools.keys().map(String::as_str)
+    }
+
+    /// The ToolConfiguration for a Converse request, listing every registered tool.
+    pub fn tool_configuration(&self) -> Result<ToolConfiguration, BuildError> {
+        let tools = self
+            .tools
+            .values()
+            .map(|tool| tool.specification().map(SdkTool::ToolSpec))
+            .collect::<Result<Vec<_>, _>>()?;
+        ToolConfiguration::builder().set_tools(Some(tools)).build()
+    }
+
+    /// Run one tool use. Unknown tools, bad input, tool errors, and timeouts all become error results.
+    pub async fn invoke(&self, tool_use: &ToolUseBlock) -> Result<ToolResultBlock, BuildError> {
+        let name = tool_use.name();
+        let result = match self.tools.get(name) {
+            Some(tool) => {
+                debug!(name, input = ?tool_use.input(), "Calling tool");
+                let call = tool.call_json(document::to_json(tool_use.input()));
+                match tokio::time::timeout(self.timeout, call).await {
+                    Ok(result) => result,
+                    Err(_) => Err(ToolError(format!(
+                        "{name} did not finish within {} seconds",
+                        self.timeout.as_secs_f32()
+                    ))),
+                }
+            }
+            None => Err(ToolError(format!("The tool {name} does not exist"))),
+        };
+
+        let (content, status) = match result {
+            Ok(ToolOutput::Text(text)) => (
+                ToolResultContentBlock::Text(text),
+                ToolResultStatus::Success,
+            ),
+            Ok(ToolOutput::Json(json)) => (
+                ToolResultContentBlock::Json(document::from_json(json)),
+                ToolResultStatus::Success,
+            ),
+            Err(err) => {
+                warn!(name, %err, "Tool failed");
+                (ToolResultContentBlock::Text(err.0), ToolResultStatus::Error)
+            }
+        };
+
+        ToolResultBlock::builder()
+            .tool_use_id(tool_use.tool_use_id())
+            .content(content)
+            .status(status)
+            .build()
+    }
+
+    /// Run all tool uses concurrently. Results are in the same order as the tool uses.
+    pub async fn invoke_all(
+        &self,
+        tool_uses: &[&ToolUseBlock],
+    ) -> Result<Vec<ToolResultBlock>, BuildError> {
+        futures::future::join_all(tool_uses.iter().map(|tool_use| self.invoke(tool_use)))
+            .await
+            .into_iter()
+            .collect()
+    }
+}
+
+// snippet-start:[rust.bedrock-runtime.Converse_AnthropicClaude.tool-use.round-trip]
+/// Send the conversation to the model, running tools until it ends its turn.
+/// `on_turn` sees every model turn, including the ones that only request tools.
+pub async fn converse_with_tools(
+    model: &impl ConverseModel,
+    registry: &ToolRegistry,
+    conversation: &mut Conversation,
+    max_iterations: usize,
+    mut on_turn: impl FnMut(&ModelTurn),
+) -> Result<Message, ConversationError> {
+    let tool_config = registry.tool_configuration()?;
+
+    for _ in 0..max_iterations {
+        let turn = model.converse(conversation, &tool_config).await?;
+        conversation.push(&turn.message)?;
+        on_turn(&turn);
+
+        if turn.stop_reason != StopReason::ToolUse {
+            return Ok(turn.message);
+        }
+
+        let tool_uses = turn
+            .message
+            .content()
+            .iter()
+            .filter_map(|block| block.as_tool_use().ok())
+            .collect::<Vec<_>>();
+        let results = registry.invoke_all(&tool_uses).await?;
+
+        let message = Message::builder()
+            .role(ConversationRole::User)
+            .set_content(Some(
+                results.into_iter().map(ContentBlock::ToolResult).collect(),
+            ))
+            .build()?;
+        conversation.push(&message)?;
+    }
+
+    Err(ConversationError(format!(
+        "Model still requested tools after {max_iterations} turns"
+    )))
+}
+// snippet-end:[rust.bedrock-runtime.Converse_AnthropicClaude.tool-use.round-trip]
+
+#[cfg(test)]
+mod test {
+    use super::{
+        converse_with_tools, input_schema, scripted::ScriptedModel, Tool, ToolError, ToolOutput,
+        ToolRegistry,
+    };
+    use crate::conversation::{Content, Conversation, InferenceProfile, ToolResultContent};
+    use async_trait::async_trait;
+    use schemars::JsonSchema;
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[derive(Deserialize, JsonSchema)]
+    struct AddInput {
+        /// The first number.
+        a: i64,
+        /// The second number.
+        b: i64,
+    }
+
+    struct Add;
+
+    #[async_trait]
+    impl Tool for Add {
+        type Input = AddInput;
+
+        fn name(&self) -> &str {
+            "add"
+        }
+
+        fn description(&self) -> &str {
+            "Add two numbers."
+        }
+
+        async fn call(&self, input: AddInput) -> Result<ToolOutput, ToolError> {
+            Ok(ToolOutput::Json(json!({ "sum": input.a + input.b })))
+        }
+    }
+
+    #[derive(Deserialize, JsonSchema)]
+    struct SleepInput {
+        seconds: u64,
+    }
+
+    struct Sleep;
+
+    #[async_trait]
+    impl Tool for Sleep {
+        type Input = SleepInput;
+
+        fn name(&self) -> &str {
+            "sleep"
+        }
+
+        fn description(&self) -> &str {
+            "Wait."
+        }
+
+        async fn call(&self, input: SleepInput) -> Result<ToolOutput, ToolError> {
+            tokio::time::sleep(Duration::from_secs(input.seconds)).await;
+            Ok(ToolOutput::Text("done".into()))
+        }
+    }
+
+    fn tool_results(conversation: &Conversation) -> Vec<(String, ToolResultContent, String)> {
+        conversation
+            .messages
+            .iter()
+            .flat_map(|message| message.content.iter())
+            .filter_map(|content| match content {
+                Content::ToolResult {
+                    tool_use_id,
+                    content,
+                    status,
+                } => Some((
+                    tool_use_id.clone(),
+                    content[0].clone(),
+                    status.clone().unwrap_or_default(),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_input_schema() {
+        let schema = input_schema::<AddInput>();
+        assert_eq!(schema["type"], "object");
+        assert_eq!(
+            schema["properties"]["a"]["description"],
+            "The first number."
+        );
+        assert_eq!(schema["required"], json!(["a", "b"]));
+        assert!(schema.get("title").is_none());
+    }
+
+    #[test]
+    fn test_tool_configuration() {
+        let registry = ToolRegistry::new().register(Sleep).register(Add);
+        let config = registry.tool_configuration().unwrap();
+        let names = config
+            .tools()
+            .iter()
+            .map(|tool| tool.as_tool_spec().unwrap().name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["add", "sleep"]);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_tool_uses() {
+        let model = ScriptedModel::new()
+            .then_tool_uses(vec![
+                ("t1", "add", json!({"a": 1, "b": 2})),
+                ("t2", "add", json!({"a": 3, "b": 4})),
+            ])
+            .then_text("The sums are 3 and 7.");
+        let registry = ToolRegistry::new().register(Add);
+        let mut conversation = Conversation::new(InferenceProfile::default());
+        conversation.push_user_text("Add 1 and 2, and 3 and 4.");
+
+        let mut turns = 0;
+        let reply = converse_with_tools(&model, &registry, &mut conversation, 5, |_| turns += 1)
+            .await
+            .unwrap();
+
+        assert_eq!(turns, 2);
+        assert_eq!(
+            reply.content()[0].as_text().unwrap(),
+            "The sums are 3 and 7."
+        );
+        assert_eq!(
+            tool_results(&conversation),
+            vec![
+                (
+                    "t1".into(),
+                    ToolResultContent::Json {
+                        json: json!({"sum": 3})
+                    },
+                    "success".into()
+                ),
+                (
+                    "t2".into(),
+                    ToolResultContent::Json {
+                        json: json!({"sum": 7})
+                    },
+                    "success".into()
+                ),
+            ]
+        );
+        // The second request must have carried the tool results back to the model.
+        assert_eq!(model.requests()[1].messages.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_tool_failures_become_error_results() {
+        let model = ScriptedModel::new()
+            .then_tool_uses(vec![
+                ("t1", "sleep", json!({"seconds": 60})),
+                ("t2", "subtract", json!({})),
+                ("t3", "add", json!({"a": "one"})),
+            ])
+            .then_text("Sorry, that didn't work.");
+        let registry = ToolRegistry::new()
+            .with_timeout(Duration::from_millis(10))
+            .register(Add)
+            .register(Sleep);
+        let mut conversation = Conversation::new(InferenceProfile::default());
+        conversation.push_user_text("Do things.");
+
+        converse_with_tools(&model, &registry, &mut conversation, 5, |_| {})
+            .await
+            .unwrap();
+
+        let results = tool_results(&conversation);
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|(_, _, status)| status == "error"));
+        assert_eq!(
+            results[1].1,
+            ToolResultContent::Text {
+                text: "The tool subtract does not exist".into()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_max_iterations() {
+        let model = ScriptedModel::new()
+            .then_tool_uses(vec![("t1", "add", json!({"a": 1, "b": 1}))])
+            .then_tool_uses(vec![("t2", "add", json!({"a": 2, "b": 2}))]);
+        let registry = ToolRegistry::new().register(Add);
+        let mut conversation = Conversation::new(InferenceProfile::default());
+        conversation.push_user_text("Keep adding.");
+
+        let result = converse_with_tools(&model, &registry, &mut conversation, 2, |_| {}).await;
+        assert!(result.is_err());
+    }
+}
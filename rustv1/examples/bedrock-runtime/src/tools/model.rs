@@ -0,0 +1,60 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use async_trait::async_trait;
+use aws_sdk_bedrockruntime::{
+    types::{Message, StopReason, ToolConfiguration},
+    Client,
+};
+
+use crate::conversation::{Conversation, ConversationError};
+
+/// One response from the model: its message, and why it stopped.
+#[derive(Clone, Debug)]
+pub struct ModelTurn {
+    pub message: Message,
+    pub stop_reason: StopReason,
+}
+
+/// Something that can take the next turn in a conversation.
+/// Implemented for the Bedrock Runtime `Client`, and by `ScriptedModel` for offline tests.
+#[async_trait]
+pub trait ConverseModel: Send + Sync {
+    async fn converse(
+        &self,
+        conversation: &Conversation,
+        tool_config: &ToolConfiguration,
+    ) -> Result<ModelTurn, ConversationError>;
+}
+
+// snippet-start:[rust.bedrock-runtime.Converse_AnthropicClaude.tool-use.converse]
+#[async_trait]
+impl ConverseModel for Client {
+    async fn converse(
+        &self,
+        conversation: &Conversation,
+        tool_config: &ToolConfiguration,
+    ) -> Result<ModelTurn, ConversationError> {
+        let response = conversation
+            .converse(self)?
+            .tool_config(tool_config.clone())
+            .send()
+            .await
+            .map_err(|e| match e.as_service_error() {
+                Some(e) => ConversationError(e.meta().message().unwrap_or("Unknown").into()),
+                None => ConversationError(format!("Converse failed: {e}")),
+            })?;
+
+        let message = response
+            .output()
+            .and_then(|output| output.as_message().ok())
+            .cloned()
+            .ok_or_else(|| ConversationError("Converse output is not a message".into()))?;
+
+        Ok(ModelTurn {
+            message,
+            stop_reason: response.stop_reason,
+        })
+    }
+}
+// snippet-end:[rust.bedrock-runtime.Converse_AnthropicClaude.tool-use.converse]
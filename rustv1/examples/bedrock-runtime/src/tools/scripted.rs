@@ -0,0 +1,89 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A fake model that replays scripted turns, for testing tool loops without calling Bedrock.
+
+use std::{collections::VecDeque, sync::Mutex};
+
+use async_trait::async_trait;
+use aws_sdk_bedrockruntime::types::{
+    ContentBlock, ConversationRole, Message, StopReason, ToolConfiguration, ToolUseBlock,
+};
+use serde_json::Value;
+
+use super::{ConverseModel, ModelTurn};
+use crate::{
+    conversation::{Conversation, ConversationError},
+    document,
+};
+
+/// Replays turns in order, and records every conversation it was sent.
+#[derive(Default)]
+pub struct ScriptedModel {
+    turns: Mutex<VecDeque<ModelTurn>>,
+    requests: Mutex<Vec<Conversation>>,
+}
+
+impl ScriptedModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Next, end the turn with a text reply.
+    pub fn then_text(self, text: &str) -> Self {
+        self.then(vec![ContentBlock::Text(text.into())], StopReason::EndTurn)
+    }
+
+    /// Next, request these tools, given as (tool use id, tool name, input).
+    pub fn then_tool_uses(self, tool_uses: Vec<(&str, &str, Value)>) -> Self {
+        let content = tool_uses
+            .into_iter()
+            .map(|(id, name, input)| {
+                ContentBlock::ToolUse(
+                    ToolUseBlock::builder()
+                        .tool_use_id(id)
+                        .name(name)
+                        .input(document::from_json(input))
+                        .build()
+                        .expect("scripted tool use has all required fields"),
+                )
+            })
+            .collect();
+        self.then(content, StopReason::ToolUse)
+    }
+
+    /// Next, reply with these content blocks and stop reason.
+    pub fn then(self, content: Vec<ContentBlock>, stop_reason: StopReason) -> Self {
+        let message = Message::builder()
+            .role(ConversationRole::Assistant)
+            .set_content(Some(content))
+            .build()
+            .expect("scripted message has all required fields");
+        self.turns.lock().unwrap().push_back(ModelTurn {
+            message,
+            stop_reason,
+        });
+        self
+    }
+
+    /// Every conversation sent to the model so far, as it was at the time.
+    pub fn requests(&self) -> Vec<Conversation> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl ConverseModel for ScriptedModel {
+    async fn converse(
+        &self,
+        conversation: &Conversation,
+        _tool_config: &ToolConfiguration,
+    ) -> Result<ModelTurn, ConversationError> {
+        self.requests.lock().unwrap().push(conversation.clone());
+        self.turns
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or_else(|| ConversationError("The scripted model has no more turns".into()))
+    }
+}
@@ -15,7 +15,7 @@ use aws_sdk_dynamodb::{config::Region, meta::PKG_VERSION, Client, Error};
 use clap::Parser;
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
-use std::io::{stdin, Read};
+use sdk_examples_prompter::{DefaultPrompter, Prompter, TerminalPrompter};
 use std::time::Duration;
 use std::{iter, process};
 
@@ -178,10 +178,11 @@ async fn remove_table(client: &Client, table: &str) -> Result<(), Error> {
 }
 // snippet-end:[dynamodb.rust.partiql-remove_table]
 
-/// Wait for the user to press Enter.
-fn pause() {
-    println!("Press Enter to continue.");
-    stdin().read_exact(&mut [0]).unwrap();
+/// Wait for the user to press Enter. Only the terminal prompter waits.
+fn pause(prompter: &dyn Prompter) {
+    if let Err(e) = prompter.pause("Press Enter to continue.") {
+        println!("{}", e);
+    }
 }
 
 /// Performs CRUD (create, read, update, delete) operations on a DynamoDB table and table item.
@@ -204,6 +205,12 @@ async fn main() -> Result<(), Error> {
         verbose,
     } = Opt::parse();
 
+    let prompter: Box<dyn Prompter> = if interactive {
+        Box::new(TerminalPrompter)
+    } else {
+        Box::new(DefaultPrompter)
+    };
+
     let region_provider = RegionProviderChain::first_try(region.map(Region::new))
         .or_default_provider()
         .or_else(Region::new("us-west-2"));
@@ -264,9 +271,7 @@ async fn main() -> Result<(), Error> {
 
     println!("Table is now ready to use.");
 
-    if interactive {
-        pause();
-    }
+    pause(prompter.as_ref());
 
     println!("Adding item to table.");
 
@@ -283,9 +288,7 @@ async fn main() -> Result<(), Error> {
     add_item(&client, item.clone()).await?;
     println!("Added item to table.");
 
-    if interactive {
-        pause();
-    }
+    pause(prompter.as_ref());
 
     item.age = "44".to_string();
 
@@ -296,26 +299,20 @@ async fn main() -> Result<(), Error> {
 
     println!("Modified table item.");
 
-    if interactive {
-        pause();
-    }
+    pause(prompter.as_ref());
 
     /* Get item and compare it with the one we added */
     println!("Comparing table item to original value.");
 
     query_item(&client, item).await;
 
-    if interactive {
-        pause();
-    }
+    pause(prompter.as_ref());
 
     /* Delete the item. */
     println!("Deleting item.");
     remove_item(&client, &table, &key, value.clone()).await?;
 
-    if interactive {
-        pause();
-    }
+    pause(prompter.as_ref());
 
     /* Delete the table. */
     println!("Deleting table.");
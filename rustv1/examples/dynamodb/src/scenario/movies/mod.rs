@@ -1,8 +1,10 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
-use aws_sdk_dynamodb::error::SdkError;
+use aws_sdk_dynamodb::config::http::HttpResponse;
+use aws_sdk_dynamodb::error::{ProvideErrorMetadata, SdkError};
 use aws_sdk_dynamodb::types::{AttributeValue, PutRequest};
 use aws_smithy_types::error::operation::BuildError;
+use sdk_examples_scenario_error::ScenarioError;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -23,7 +25,7 @@ pub enum MovieError {
     FromSerde(serde_dynamo::Error),
 
     #[error("aws_sdk_dynamodb error: {0}")]
-    Dynamo(aws_sdk_dynamodb::Error),
+    Dynamo(ScenarioError),
 
     #[error("unknown DynamoDB movies error: {0}")]
     Unknown(String),
@@ -31,7 +33,7 @@ pub enum MovieError {
 
 impl From<aws_sdk_dynamodb::Error> for MovieError {
     fn from(err: aws_sdk_dynamodb::Error) -> Self {
-        MovieError::Dynamo(err)
+        MovieError::Dynamo(ScenarioError::new("", &err))
     }
 }
 
@@ -41,13 +43,18 @@ impl From<serde_dynamo::Error> for MovieError {
     }
 }
 
-impl<E, R> From<SdkError<E, R>> for MovieError
-where
-    E: std::fmt::Debug,
-    R: std::fmt::Debug,
-{
-    fn from(err: SdkError<E, R>) -> Self {
-        MovieError::Unknown(format!("{err:?}"))
+impl From<MovieError> for ScenarioError {
+    fn from(err: MovieError) -> Self {
+        match err {
+            MovieError::Dynamo(sdk) => sdk.context("aws_sdk_dynamodb error"),
+            _ => ScenarioError::with(err.to_string()),
+        }
+    }
+}
+
+impl<E: ProvideErrorMetadata> From<SdkError<E, HttpResponse>> for MovieError {
+    fn from(err: SdkError<E, HttpResponse>) -> Self {
+        MovieError::Dynamo(ScenarioError::from_sdk("", &err))
     }
 }
 
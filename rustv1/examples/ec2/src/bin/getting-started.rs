@@ -1,22 +1,94 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::path::PathBuf;
+
+use clap::Parser;
 use ec2_code_examples::{
     ec2::EC2,
     getting_started::{
         scenario::{run, Ec2InstanceScenario},
+        state::{StateFile, DEFAULT_STATE_FILE},
         util::UtilImpl,
     },
     ssm::SSM,
 };
+use sdk_examples_prompter::PrompterArgs;
+use sdk_examples_resource_ledger::{Ledger, DEFAULT_JOURNAL};
+
+#[derive(Debug, Parser)]
+struct Opt {
+    #[command(flatten)]
+    prompts: PrompterArgs,
+
+    /// Where to save the scenario's progress after each step.
+    #[structopt(long, default_value = DEFAULT_STATE_FILE)]
+    state_file: PathBuf,
+
+    /// Continue the run saved in the state file.
+    #[structopt(long, conflicts_with = "teardown")]
+    resume: bool,
+
+    /// Delete the resources saved in the state file, without running the scenario.
+    #[structopt(long)]
+    teardown: bool,
+}
 
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
+    let Opt {
+        prompts,
+        state_file,
+        resume,
+        teardown,
+    } = Opt::parse();
+
+    let util = match prompts.prompter() {
+        Ok(prompter) => UtilImpl::new(prompter),
+        Err(err) => {
+            eprintln!("{err}");
+            return;
+        }
+    };
+    let state_file = StateFile::new(state_file);
+    let saved = match state_file.load() {
+        Ok(saved) => saved,
+        Err(err) => {
+            eprintln!("{err}");
+            return;
+        }
+    };
+
     let sdk_config = aws_config::load_from_env().await;
     let ec2 = EC2::new(aws_sdk_ec2::Client::new(&sdk_config));
     let ssm = SSM::new(aws_sdk_ssm::Client::new(&sdk_config));
-    let util = UtilImpl {};
-    let scenario = Ec2InstanceScenario::new(ec2, ssm, util);
-    run(scenario).await;
+    // Record what the scenario creates, so `cleanup --from-journal` can remove it if this run is interrupted.
+    let ledger =
+        Ledger::open(DEFAULT_JOURNAL).with_region(sdk_config.region().map(|r| r.to_string()));
+    let mut scenario = Ec2InstanceScenario::new(ec2, ssm, util)
+        .with_ledger(ledger)
+        .with_state_file(state_file.clone());
+
+    match saved {
+        Some(state) if resume || teardown => scenario.resume_from(state).await,
+        None if resume || teardown => {
+            eprintln!("There is no saved run in {:?}.", state_file.path());
+            return;
+        }
+        Some(state) if state.has_resources() => {
+            eprintln!(
+                "{:?} has a run that hasn't been cleaned up. Use --resume to continue it, or --teardown to delete its resources.",
+                state_file.path()
+            );
+            return;
+        }
+        _ => {}
+    }
+
+    if teardown {
+        scenario.tear_down().await;
+    } else {
+        run(scenario).await;
+    }
 }
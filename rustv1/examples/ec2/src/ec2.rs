@@ -16,7 +16,10 @@ use aws_sdk_ec2::{
     Client as EC2Client,
 };
 use aws_sdk_ssm::types::Parameter;
-use aws_smithy_runtime_api::client::waiters::error::WaiterError;
+use aws_smithy_runtime_api::client::{
+    orchestrator::HttpResponse, result::SdkError, waiters::error::WaiterError,
+};
+use sdk_examples_scenario_error::ScenarioError;
 
 #[cfg(test)]
 use mockall::automock;
@@ -304,7 +307,7 @@ impl EC2Impl {
             .wait(duration.unwrap_or(Duration::from_secs(60)))
             .await
             .map_err(|err| match err {
-                WaiterError::ExceededMaxWait(exceeded) => EC2Error(format!(
+                WaiterError::ExceededMaxWait(exceeded) => EC2Error::new(format!(
                     "Exceeded max time ({}s) waiting for instance to start.",
                     exceeded.max_wait().as_secs()
                 )),
@@ -397,7 +400,7 @@ impl EC2Impl {
             .wait(duration.unwrap_or(Duration::from_secs(60)))
             .await
             .map_err(|err| match err {
-                WaiterError::ExceededMaxWait(exceeded) => EC2Error(format!(
+                WaiterError::ExceededMaxWait(exceeded) => EC2Error::new(format!(
                     "Exceeded max time ({}s) waiting for instance to stop.",
                     exceeded.max_wait().as_secs(),
                 )),
@@ -430,7 +433,7 @@ impl EC2Impl {
             .wait(Duration::from_secs(60))
             .await
             .map_err(|err| match err {
-                WaiterError::ExceededMaxWait(exceeded) => EC2Error(format!(
+                WaiterError::ExceededMaxWait(exceeded) => EC2Error::new(format!(
                     "Exceeded max time ({}s) waiting for instance to terminate.",
                     exceeded.max_wait().as_secs(),
                 )),
@@ -493,30 +496,40 @@ impl EC2Impl {
 
 // snippet-start:[ec2.rust.ec2error.impl]
 #[derive(Debug)]
-pub struct EC2Error(String);
+pub struct EC2Error(ScenarioError);
 impl EC2Error {
     pub fn new(value: impl Into<String>) -> Self {
-        EC2Error(value.into())
+        EC2Error(ScenarioError::with(value))
     }
 
     pub fn add_message(self, message: impl Into<String>) -> Self {
-        EC2Error(format!("{}: {}", message.into(), self.0))
+        EC2Error(self.0.context(message))
+    }
+
+    /// Whether the call that failed is worth trying again, for example after throttling.
+    pub fn is_retryable(&self) -> bool {
+        self.0.is_retryable()
+    }
+}
+
+impl<E: ProvideErrorMetadata> From<SdkError<E, HttpResponse>> for EC2Error {
+    fn from(value: SdkError<E, HttpResponse>) -> Self {
+        EC2Error(ScenarioError::from_sdk("", &value))
+    }
+}
+
+impl<O, E> From<WaiterError<O, E>> for EC2Error
+where
+    WaiterError<O, E>: ProvideErrorMetadata,
+{
+    fn from(value: WaiterError<O, E>) -> Self {
+        EC2Error(ScenarioError::from_metadata(&value))
     }
 }
 
-impl<T: ProvideErrorMetadata> From<T> for EC2Error {
-    fn from(value: T) -> Self {
-        EC2Error(format!(
-            "{}: {}",
-            value
-                .code()
-                .map(String::from)
-                .unwrap_or("unknown code".into()),
-            value
-                .message()
-                .map(String::from)
-                .unwrap_or("missing reason".into()),
-        ))
+impl From<EC2Error> for ScenarioError {
+    fn from(value: EC2Error) -> Self {
+        value.0
     }
 }
 
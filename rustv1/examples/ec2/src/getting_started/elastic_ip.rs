@@ -16,10 +16,33 @@ pub struct ElasticIpManager {
 }
 
 impl ElasticIpManager {
+    /// Manage an address allocated, and maybe associated, by an earlier run.
+    pub fn restore(
+        allocation: AllocateAddressOutput,
+        association: Option<AssociateAddressOutput>,
+    ) -> Self {
+        ElasticIpManager {
+            elastic_ip: Some(allocation),
+            association,
+        }
+    }
+
     pub fn has_allocation(&self) -> bool {
         self.elastic_ip.is_some()
     }
 
+    pub fn allocation_id(&self) -> Option<&str> {
+        self.elastic_ip
+            .as_ref()
+            .and_then(|allocation| allocation.allocation_id())
+    }
+
+    pub fn association_id(&self) -> Option<&str> {
+        self.association
+            .as_ref()
+            .and_then(|association| association.association_id())
+    }
+
     pub fn public_ip(&self) -> &str {
         if let Some(allocation) = &self.elastic_ip {
             if let Some(addr) = allocation.public_ip() {
@@ -14,6 +14,17 @@ pub struct InstanceManager {
 }
 
 impl InstanceManager {
+    /// Manage an instance created by an earlier run.
+    pub fn restore(instance: Instance) -> Self {
+        InstanceManager {
+            instance: Some(instance),
+        }
+    }
+
+    pub fn instance(&self) -> Option<&Instance> {
+        self.instance.as_ref()
+    }
+
     pub fn instance_id(&self) -> &str {
         if let Some(instance) = &self.instance {
             if let Some(id) = instance.instance_id() {
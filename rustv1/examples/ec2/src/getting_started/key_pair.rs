@@ -23,6 +23,15 @@ impl KeyPairManager {
         Self::default()
     }
 
+    /// Manage a key pair created by an earlier run.
+    pub fn restore(key_pair: KeyPairInfo, key_file_path: Option<PathBuf>) -> Self {
+        KeyPairManager {
+            key_pair,
+            key_file_path,
+            ..Default::default()
+        }
+    }
+
     pub fn key_pair(&self) -> &KeyPairInfo {
         &self.key_pair
     }
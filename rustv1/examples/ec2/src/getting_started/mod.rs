@@ -6,6 +6,7 @@ pub mod instance;
 pub mod key_pair;
 pub mod scenario;
 pub mod security_group;
+pub mod state;
 
 pub mod util;
 
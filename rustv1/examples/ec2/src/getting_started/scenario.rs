@@ -16,6 +16,9 @@
 //! * Connect to your instance with SSH, using both its public IP address and your Elastic IP
 //!   address.
 //! * Clean up all of the resources created by this example.
+//!
+//! With a state file, the scenario saves the resources it has created after each step. A run
+//! that stops partway can then be resumed, or its resources torn down, from the saved state.
 
 use std::net::Ipv4Addr;
 
@@ -24,13 +27,26 @@ use crate::{
     getting_started::{key_pair::KeyPairManager, util::Util},
     ssm::SSM,
 };
+use aws_sdk_ec2::{
+    operation::{
+        allocate_address::AllocateAddressOutput, associate_address::AssociateAddressOutput,
+    },
+    types::{Instance, KeyPairInfo, SecurityGroup},
+};
 use aws_sdk_ssm::types::Parameter;
+use sdk_examples_resource_ledger::{Ledger, Resource, ResourceKind};
 
 use super::{
-    elastic_ip::ElasticIpManager, instance::InstanceManager, security_group::SecurityGroupManager,
+    elastic_ip::ElasticIpManager,
+    instance::InstanceManager,
+    security_group::SecurityGroupManager,
+    state::{ScenarioState, StateFile, Step},
     util::ScenarioImage,
 };
 
+/// How many times to wait for the instance to start before giving up, even if asked to keep going.
+const MAX_INSTANCE_WAITS: usize = 5;
+
 pub struct Ec2InstanceScenario {
     ec2: EC2,
     ssm: SSM,
@@ -39,6 +55,9 @@ pub struct Ec2InstanceScenario {
     security_group_manager: SecurityGroupManager,
     instance_manager: InstanceManager,
     elastic_ip_manager: ElasticIpManager,
+    ledger: Ledger,
+    state: ScenarioState,
+    state_file: Option<StateFile>,
 }
 
 impl Ec2InstanceScenario {
@@ -51,19 +70,110 @@ impl Ec2InstanceScenario {
             security_group_manager: Default::default(),
             instance_manager: Default::default(),
             elastic_ip_manager: Default::default(),
+            ledger: Ledger::disabled(),
+            state: Default::default(),
+            state_file: None,
+        }
+    }
+
+    /// Record the resources the scenario creates, so an interrupted run can be cleaned up.
+    pub fn with_ledger(mut self, ledger: Ledger) -> Self {
+        self.ledger = ledger;
+        self
+    }
+
+    /// Save the scenario's progress to `state_file` after each step.
+    pub fn with_state_file(mut self, state_file: StateFile) -> Self {
+        self.state_file = Some(state_file);
+        self
+    }
+
+    /// Continue from a saved state: manage the resources it recorded, and skip the steps it
+    /// finished.
+    pub async fn resume_from(&mut self, state: ScenarioState) {
+        if let Some(key_name) = &state.key_name {
+            self.key_pair_manager = KeyPairManager::restore(
+                KeyPairInfo::builder()
+                    .key_name(key_name)
+                    .set_key_pair_id(state.key_pair_id.clone())
+                    .build(),
+                state.key_file_path.clone(),
+            );
+        }
+        if let Some(group_id) = &state.security_group_id {
+            let group_name = state.security_group_name.clone().unwrap_or_default();
+            self.security_group_manager = SecurityGroupManager::restore(
+                &group_name,
+                SecurityGroup::builder()
+                    .group_id(group_id)
+                    .group_name(&group_name)
+                    .set_vpc_id(state.vpc_id.clone())
+                    .build(),
+            );
+        }
+        if let Some(instance_id) = &state.instance_id {
+            // The public IP address changes when the instance restarts, so describe it again.
+            let instance = match self.ec2.describe_instance(instance_id).await {
+                Ok(instance) => instance,
+                Err(err) => {
+                    eprintln!("Couldn't describe instance {instance_id}: {err}");
+                    Instance::builder().instance_id(instance_id).build()
+                }
+            };
+            self.instance_manager = InstanceManager::restore(instance);
         }
+        if let Some(allocation_id) = &state.allocation_id {
+            self.elastic_ip_manager = ElasticIpManager::restore(
+                AllocateAddressOutput::builder()
+                    .allocation_id(allocation_id)
+                    .set_public_ip(state.public_ip.clone())
+                    .build(),
+                state
+                    .association_id
+                    .as_ref()
+                    .map(|id| AssociateAddressOutput::builder().association_id(id).build()),
+            );
+        }
+        self.state = state;
     }
 
     pub async fn run(&mut self) -> Result<(), EC2Error> {
-        self.create_and_list_key_pairs().await?;
-        self.create_security_group().await?;
-        self.create_instance().await?;
-        self.stop_and_start_instance().await?;
-        self.associate_elastic_ip().await?;
-        self.stop_and_start_instance().await?;
+        for step in Step::ALL {
+            if step <= self.state.completed {
+                continue;
+            }
+            let result = match step {
+                Step::NotStarted => Ok(()),
+                Step::KeyPair => self.create_and_list_key_pairs().await,
+                Step::SecurityGroup => self.create_security_group().await,
+                Step::Instance => self.create_instance().await,
+                Step::Restart | Step::RestartWithElasticIp => self.stop_and_start_instance().await,
+                Step::ElasticIp => self.associate_elastic_ip().await,
+            };
+            if result.is_ok() {
+                self.state.completed = step;
+            }
+            // Save even when the step failed, because it may have created resources first.
+            let saved = self.save_state();
+            result?;
+            saved?;
+        }
         Ok(())
     }
 
+    fn save_state(&mut self) -> Result<(), EC2Error> {
+        self.state.capture(
+            &self.key_pair_manager,
+            &self.security_group_manager,
+            &self.instance_manager,
+            &self.elastic_ip_manager,
+        );
+        match &self.state_file {
+            Some(state_file) => state_file.save(&self.state),
+            None => Ok(()),
+        }
+    }
+
     /// 1. Creates an RSA key pair and saves its private key data as a .pem file in secure
     ///    temporary storage. The private key data is deleted after the example completes.
     /// 2. Optionally, lists the first five key pairs for the current account.
@@ -75,6 +185,10 @@ impl Ec2InstanceScenario {
         self.key_pair_manager
             .create(&self.ec2, &self.util, key_name)
             .await?;
+        if let Some(key_name) = self.key_pair_manager.key_pair().key_name() {
+            self.ledger
+                .created(Resource::new(ResourceKind::Ec2KeyPair, key_name));
+        }
 
         println!(
             "Created a key pair {} and saved the private key to {:?}.",
@@ -122,6 +236,14 @@ impl Ec2InstanceScenario {
                 "Security group for example: get started with instances.",
             )
             .await?;
+        if let Some(group_id) = self
+            .security_group_manager
+            .security_group()
+            .and_then(|sg| sg.group_id())
+        {
+            self.ledger
+                .created(Resource::new(ResourceKind::Ec2SecurityGroup, group_id));
+        }
 
         println!(
             "Created security group {} in your default VPC {}.",
@@ -197,16 +319,19 @@ impl Ec2InstanceScenario {
             )
             .await
             .map_err(|e| e.add_message("Scenario failed to create instance"))?;
+        self.record_instance();
 
+        let mut waits = 1;
         while let Err(err) = self
             .ec2
             .wait_for_instance_ready(self.instance_manager.instance_id(), None)
             .await
         {
             println!("{err}");
-            if !self.util.should_continue_waiting() {
+            if waits >= MAX_INSTANCE_WAITS || !self.util.should_continue_waiting() {
                 return Err(err);
             }
+            waits += 1;
         }
 
         println!("Your instance is ready:\n{}", self.instance_manager);
@@ -217,6 +342,24 @@ impl Ec2InstanceScenario {
     }
     // snippet-end:[ec2.rust.create_instance.scenario]
 
+    fn record_instance(&self) {
+        let mut instance = Resource::new(
+            ResourceKind::Ec2Instance,
+            self.instance_manager.instance_id(),
+        );
+        if let Some(key_name) = self.key_pair_manager.key_pair().key_name() {
+            instance = instance.depends_on(ResourceKind::Ec2KeyPair, key_name);
+        }
+        if let Some(group_id) = self
+            .security_group_manager
+            .security_group()
+            .and_then(|sg| sg.group_id())
+        {
+            instance = instance.depends_on(ResourceKind::Ec2SecurityGroup, group_id);
+        }
+        self.ledger.created(instance);
+    }
+
     // snippet-start:[ec2.rust.find_image.scenario]
     async fn find_image(&mut self) -> Result<ScenarioImage, EC2Error> {
         let params: Vec<Parameter> = self
@@ -270,6 +413,10 @@ impl Ec2InstanceScenario {
     /// 2. Displays an SSH connection string that uses the Elastic IP address.
     async fn associate_elastic_ip(&mut self) -> Result<(), EC2Error> {
         self.elastic_ip_manager.allocate(&self.ec2).await?;
+        if let Some(allocation_id) = self.elastic_ip_manager.allocation_id() {
+            self.ledger
+                .created(Resource::new(ResourceKind::Ec2ElasticIp, allocation_id));
+        }
         println!(
             "Allocated static Elastic IP address: {}",
             self.elastic_ip_manager.public_ip()
@@ -320,20 +467,100 @@ impl Ec2InstanceScenario {
             self.instance_manager.instance_display_name()
         );
         if self.util.should_clean_resources() {
-            if let Err(err) = self.elastic_ip_manager.remove(&self.ec2).await {
-                eprintln!("{err}")
-            }
-            if let Err(err) = self.instance_manager.delete(&self.ec2).await {
-                eprintln!("{err}")
+            self.tear_down().await;
+        } else {
+            println!("Ok, not cleaning up any resources!");
+            if let Some(state_file) = &self.state_file {
+                println!(
+                    "Their IDs are saved in {:?}. Run with --teardown to delete them later.",
+                    state_file.path()
+                );
             }
-            if let Err(err) = self.security_group_manager.delete(&self.ec2).await {
+        }
+    }
+
+    /// Delete everything the scenario created, without asking. Each resource is removed from
+    /// the saved state once it's deleted, and the state file is removed when nothing is left.
+    pub async fn tear_down(self) {
+        let Ec2InstanceScenario {
+            ec2,
+            util,
+            key_pair_manager,
+            security_group_manager,
+            instance_manager,
+            elastic_ip_manager,
+            ledger,
+            mut state,
+            state_file,
+            ..
+        } = self;
+        let save = |state: &ScenarioState| {
+            if let Some(Err(err)) = state_file.as_ref().map(|file| file.save(state)) {
                 eprintln!("{err}");
             }
-            if let Err(err) = self.key_pair_manager.delete(&self.ec2, &self.util).await {
+        };
+
+        let allocation_id = elastic_ip_manager.allocation_id().map(String::from);
+        match elastic_ip_manager.remove(&ec2).await {
+            Ok(_) => {
+                allocation_id
+                    .into_iter()
+                    .for_each(|id| ledger.deleted(ResourceKind::Ec2ElasticIp, id));
+                state.allocation_id = None;
+                state.public_ip = None;
+                state.association_id = None;
+                save(&state);
+            }
+            Err(err) => eprintln!("{err}"),
+        }
+        let instance_id = instance_manager.instance_id().to_string();
+        match instance_manager.delete(&ec2).await {
+            Ok(_) => {
+                ledger.deleted(ResourceKind::Ec2Instance, instance_id);
+                state.instance_id = None;
+                save(&state);
+            }
+            Err(err) => eprintln!("{err}"),
+        }
+        let group_id = security_group_manager
+            .security_group()
+            .and_then(|sg| sg.group_id())
+            .map(String::from);
+        match security_group_manager.delete(&ec2).await {
+            Ok(_) => {
+                group_id
+                    .into_iter()
+                    .for_each(|id| ledger.deleted(ResourceKind::Ec2SecurityGroup, id));
+                state.security_group_name = None;
+                state.security_group_id = None;
+                state.vpc_id = None;
+                save(&state);
+            }
+            Err(err) => eprintln!("{err}"),
+        }
+        let key_name = key_pair_manager.key_pair().key_name().map(String::from);
+        match key_pair_manager.delete(&ec2, &util).await {
+            Ok(_) => {
+                key_name
+                    .into_iter()
+                    .for_each(|name| ledger.deleted(ResourceKind::Ec2KeyPair, name));
+                state.key_name = None;
+                state.key_pair_id = None;
+                state.key_file_path = None;
+                save(&state);
+            }
+            Err(err) => eprintln!("{err}"),
+        }
+
+        if let Some(state_file) = &state_file {
+            if state.has_resources() {
+                println!(
+                    "Some resources were not deleted. Their IDs are still saved in {:?}.",
+                    state_file.path()
+                );
+            } else if let Err(err) = state_file.remove() {
                 eprintln!("{err}");
             }
-        } else {
-            println!("Ok, not cleaning up any resources!");
         }
     }
 }
@@ -17,6 +17,15 @@ pub struct SecurityGroupManager {
 }
 
 impl SecurityGroupManager {
+    /// Manage a security group created by an earlier run.
+    pub fn restore(group_name: &str, security_group: SecurityGroup) -> Self {
+        SecurityGroupManager {
+            group_name: group_name.into(),
+            group_description: Default::default(),
+            security_group: Some(security_group),
+        }
+    }
+
     pub async fn create(
         &mut self,
         ec2: &EC2,
@@ -0,0 +1,166 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The scenario's progress and the resources it has created, saved to a local file after each
+//! step so an interrupted run can be resumed or torn down later.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ec2::EC2Error;
+
+use super::{
+    elastic_ip::ElasticIpManager, instance::InstanceManager, key_pair::KeyPairManager,
+    security_group::SecurityGroupManager,
+};
+
+/// Where the getting started binary saves its state, unless told otherwise.
+pub const DEFAULT_STATE_FILE: &str = "ec2-scenario-state.json";
+
+/// The steps of the scenario, in the order they run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Step {
+    #[default]
+    NotStarted,
+    KeyPair,
+    SecurityGroup,
+    Instance,
+    Restart,
+    ElasticIp,
+    RestartWithElasticIp,
+}
+
+impl Step {
+    pub const ALL: [Step; 6] = [
+        Step::KeyPair,
+        Step::SecurityGroup,
+        Step::Instance,
+        Step::Restart,
+        Step::ElasticIp,
+        Step::RestartWithElasticIp,
+    ];
+}
+
+/// Everything needed to rebuild the scenario's managers. Only IDs are kept; anything else is
+/// described again on resume.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScenarioState {
+    /// The last step that finished.
+    pub completed: Step,
+    pub key_name: Option<String>,
+    pub key_pair_id: Option<String>,
+    pub key_file_path: Option<PathBuf>,
+    pub security_group_name: Option<String>,
+    pub security_group_id: Option<String>,
+    pub vpc_id: Option<String>,
+    pub instance_id: Option<String>,
+    pub allocation_id: Option<String>,
+    pub public_ip: Option<String>,
+    pub association_id: Option<String>,
+}
+
+impl ScenarioState {
+    /// Copy the IDs of whatever the managers hold, including resources created by a step that
+    /// then failed.
+    pub fn capture(
+        &mut self,
+        key_pair_manager: &KeyPairManager,
+        security_group_manager: &SecurityGroupManager,
+        instance_manager: &InstanceManager,
+        elastic_ip_manager: &ElasticIpManager,
+    ) {
+        let key_pair = key_pair_manager.key_pair();
+        self.key_name = key_pair.key_name().map(String::from);
+        self.key_pair_id = key_pair.key_pair_id().map(String::from);
+        self.key_file_path = key_pair_manager.key_file_path().cloned();
+
+        let security_group = security_group_manager.security_group();
+        self.security_group_name =
+            security_group.map(|_| security_group_manager.group_name().into());
+        self.security_group_id = security_group
+            .and_then(|sg| sg.group_id())
+            .map(String::from);
+        self.vpc_id = security_group_manager.vpc_id().map(String::from);
+
+        self.instance_id = instance_manager
+            .instance()
+            .and_then(|instance| instance.instance_id())
+            .map(String::from);
+
+        self.allocation_id = elastic_ip_manager.allocation_id().map(String::from);
+        self.public_ip = elastic_ip_manager
+            .has_allocation()
+            .then(|| elastic_ip_manager.public_ip().to_string());
+        self.association_id = elastic_ip_manager.association_id().map(String::from);
+    }
+
+    /// Whether any resource the scenario created is still recorded.
+    pub fn has_resources(&self) -> bool {
+        self.key_name.is_some()
+            || self.security_group_id.is_some()
+            || self.instance_id.is_some()
+            || self.allocation_id.is_some()
+    }
+}
+
+/// A JSON file holding a [ScenarioState].
+#[derive(Clone, Debug)]
+pub struct StateFile {
+    path: PathBuf,
+}
+
+impl StateFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        StateFile { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The saved state, or None if nothing has been saved.
+    pub fn load(&self) -> Result<Option<ScenarioState>, EC2Error> {
+        let contents = match std::fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(EC2Error::new(format!(
+                    "Failed to read state from {:?} ({err:?})",
+                    self.path
+                )))
+            }
+        };
+        serde_json::from_str(&contents).map(Some).map_err(|err| {
+            EC2Error::new(format!(
+                "Failed to parse state in {:?} ({err:?})",
+                self.path
+            ))
+        })
+    }
+
+    /// Write the state to a temporary file first, so an interruption never leaves half a file.
+    pub fn save(&self, state: &ScenarioState) -> Result<(), EC2Error> {
+        let json = serde_json::to_string_pretty(state)
+            .map_err(|err| EC2Error::new(format!("Failed to serialize state ({err:?})")))?;
+        let temp = self.path.with_extension("json.tmp");
+        std::fs::write(&temp, json)
+            .and_then(|_| std::fs::rename(&temp, &self.path))
+            .map_err(|err| {
+                EC2Error::new(format!("Failed to save state to {:?} ({err:?})", self.path))
+            })
+    }
+
+    pub fn remove(&self) -> Result<(), EC2Error> {
+        match std::fs::remove_file(&self.path) {
+            Ok(_) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(EC2Error::new(format!(
+                "Failed to remove {:?} ({err:?})",
+                self.path
+            ))),
+        }
+    }
+}
@@ -2,4 +2,5 @@
 // SPDX-License-Identifier: Apache-2.0
 
 // List test modules here. This mod.rs is gated by #[cfg(test)] in the crate mod.rs.
+pub mod resume_with_mocks;
 pub mod scenario_with_mocks;
@@ -0,0 +1,156 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use aws_sdk_ec2::types::Instance;
+use mockall::predicate::eq;
+
+use crate::{
+    ec2::MockEC2Impl,
+    getting_started::{
+        scenario::{run, Ec2InstanceScenario},
+        state::{ScenarioState, StateFile, Step},
+        util::MockUtilImpl,
+    },
+    ssm::MockSSMImpl,
+};
+
+fn state_file(test: &str) -> StateFile {
+    StateFile::new(
+        std::env::temp_dir().join(format!("ec2-scenario-{test}-{}.json", std::process::id())),
+    )
+}
+
+fn saved_state(completed: Step) -> ScenarioState {
+    ScenarioState {
+        completed,
+        key_name: Some("test_key".into()),
+        key_pair_id: Some("kp-12345".into()),
+        key_file_path: Some(PathBuf::from("/tmp/test_key.pem")),
+        security_group_name: Some("test_group".into()),
+        security_group_id: Some("sg-0123".into()),
+        vpc_id: Some("vpc-0123".into()),
+        instance_id: Some("i-01234567".into()),
+        allocation_id: Some("eip-1234567".into()),
+        public_ip: Some("10.0.0.1".into()),
+        association_id: Some("aid-01234567".into()),
+    }
+}
+
+#[test]
+fn test_state_file_round_trip() {
+    let file = state_file("round-trip");
+    assert_eq!(file.load().unwrap(), None);
+
+    let state = saved_state(Step::Instance);
+    file.save(&state).unwrap();
+    assert_eq!(file.load().unwrap(), Some(state));
+
+    file.remove().unwrap();
+    assert_eq!(file.load().unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_resume_runs_remaining_steps() {
+    let mut mock_ec2 = MockEC2Impl::default();
+    let mock_ssm = MockSSMImpl::default();
+    let mut mock_util = MockUtilImpl::default();
+
+    // resume_from
+    mock_ec2
+        .expect_describe_instance()
+        .with(eq("i-01234567"))
+        .returning(|_| {
+            Ok(Instance::builder()
+                .instance_id("i-01234567")
+                .public_ip_address("10.0.0.1")
+                .build())
+        });
+
+    // stop_and_start_instance is the only step left, so nothing is created or prompted for.
+    mock_ec2
+        .expect_stop_instance()
+        .times(1)
+        .with(eq("i-01234567"))
+        .returning(|_| Ok(()));
+    mock_ec2
+        .expect_start_instance()
+        .times(1)
+        .with(eq("i-01234567"))
+        .returning(|_| Ok(()));
+    mock_util.expect_enter_to_continue().returning(|| Ok(()));
+
+    // clean_up
+    mock_util.expect_should_clean_resources().returning(|| true);
+    mock_ec2
+        .expect_disassociate_ip_address()
+        .with(eq("aid-01234567"))
+        .returning(|_| Ok(()));
+    mock_ec2
+        .expect_deallocate_ip_address()
+        .with(eq("eip-1234567"))
+        .returning(|_| Ok(()));
+    mock_ec2
+        .expect_delete_instance()
+        .with(eq("i-01234567"))
+        .returning(|_| Ok(()));
+    mock_ec2
+        .expect_delete_security_group()
+        .with(eq("sg-0123"))
+        .returning(|_| Ok(()));
+    mock_ec2
+        .expect_delete_key_pair()
+        .with(eq("test_key"))
+        .returning(|_| Ok(()));
+    mock_util
+        .expect_remove()
+        .withf(|p| p.ends_with("test_key.pem"))
+        .returning(|_| Ok(()));
+
+    let file = state_file("resume");
+    file.save(&saved_state(Step::ElasticIp)).unwrap();
+
+    let mut scenario =
+        Ec2InstanceScenario::new(mock_ec2, mock_ssm, mock_util).with_state_file(file.clone());
+    scenario.resume_from(file.load().unwrap().unwrap()).await;
+    run(scenario).await;
+
+    assert_eq!(file.load().unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_teardown_keeps_what_failed_to_delete() {
+    let mut mock_ec2 = MockEC2Impl::default();
+    let mock_ssm = MockSSMImpl::default();
+    let mut mock_util = MockUtilImpl::default();
+
+    mock_ec2
+        .expect_describe_instance()
+        .returning(|_| Ok(Instance::builder().instance_id("i-01234567").build()));
+    mock_ec2
+        .expect_disassociate_ip_address()
+        .returning(|_| Ok(()));
+    mock_ec2
+        .expect_deallocate_ip_address()
+        .returning(|_| Ok(()));
+    mock_ec2.expect_delete_instance().returning(|_| Ok(()));
+    mock_ec2
+        .expect_delete_security_group()
+        .returning(|_| Err(crate::ec2::EC2Error::new("DependencyViolation")));
+    mock_ec2.expect_delete_key_pair().returning(|_| Ok(()));
+    mock_util.expect_remove().returning(|_| Ok(()));
+
+    let file = state_file("teardown");
+    let mut scenario =
+        Ec2InstanceScenario::new(mock_ec2, mock_ssm, mock_util).with_state_file(file.clone());
+    scenario.resume_from(saved_state(Step::Instance)).await;
+    scenario.tear_down().await;
+
+    let remaining = file.load().unwrap().unwrap();
+    file.remove().unwrap();
+    assert_eq!(remaining.security_group_id.as_deref(), Some("sg-0123"));
+    assert_eq!(remaining.instance_id, None);
+    assert_eq!(remaining.allocation_id, None);
+    assert_eq!(remaining.key_name, None);
+}
@@ -227,9 +227,7 @@ async fn test_happy_path() {
 
         // display_ssh_info
         {
-            mock_util
-                .expect_enter_to_continue()
-                .returning(|| Ok("".into()));
+            mock_util.expect_enter_to_continue().returning(|| Ok(()));
         }
     }
 
@@ -253,9 +251,7 @@ async fn test_happy_path() {
 
         // display_ssh_info
         {
-            mock_util
-                .expect_enter_to_continue()
-                .returning(|| Ok("".into()));
+            mock_util.expect_enter_to_continue().returning(|| Ok(()));
         }
     }
 
@@ -284,9 +280,7 @@ async fn test_happy_path() {
 
         // display_ssh_info
         {
-            mock_util
-                .expect_enter_to_continue()
-                .returning(|| Ok("".into()));
+            mock_util.expect_enter_to_continue().returning(|| Ok(()));
         }
     }
 
@@ -310,9 +304,7 @@ async fn test_happy_path() {
 
         // display_ssh_info
         {
-            mock_util
-                .expect_enter_to_continue()
-                .returning(|| Ok("".into()));
+            mock_util.expect_enter_to_continue().returning(|| Ok(()));
         }
     }
 
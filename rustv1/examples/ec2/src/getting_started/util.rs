@@ -5,9 +5,8 @@
 
 use std::{fmt::Display, io::Write, path::PathBuf};
 
-use inquire::{validator::ValueRequiredValidator, InquireError};
-
-use aws_sdk_ec2::types::Image;
+use aws_sdk_ec2::types::{Image, InstanceType};
+use sdk_examples_prompter::{Prompter, TerminalPrompter};
 
 use crate::ec2::EC2Error;
 
@@ -20,82 +19,112 @@ pub use UtilImpl as Util;
 #[cfg(test)]
 pub use MockUtilImpl as Util;
 
-#[derive(Default)]
-pub struct UtilImpl;
+/// Asks its questions through a Prompter, in the terminal unless given another.
+pub struct UtilImpl {
+    prompter: Box<dyn Prompter>,
+}
+
+impl UtilImpl {
+    pub fn new(prompter: Box<dyn Prompter>) -> Self {
+        UtilImpl { prompter }
+    }
+}
+
+impl Default for UtilImpl {
+    fn default() -> Self {
+        UtilImpl::new(Box::new(TerminalPrompter))
+    }
+}
 
 #[cfg_attr(test, automock)]
 impl UtilImpl {
     pub fn prompt_key_name(&self) -> Result<String, EC2Error> {
-        inquire::Text::new("Enter a unique name for your key: ")
-            .with_validator(ValueRequiredValidator::default())
-            .with_default("my_key")
-            .prompt()
-            .map_err(|e| EC2Error::new(format!("Failed to get name for key pair. {e:?}")))
+        self.prompter
+            .required_text(
+                "key_name",
+                "Enter a unique name for your key:",
+                Some("my_key"),
+            )
+            .map_err(|e| EC2Error::new(format!("Failed to get name for key pair. {e}")))
     }
     pub fn should_clean_resources(&self) -> bool {
-        inquire::Confirm::new("Clean up resources?")
-            .with_default(true)
-            .prompt()
+        self.prompter
+            .confirm("clean_up", "Clean up resources?", true)
             .unwrap_or(false)
     }
 
-    pub fn enter_to_continue(&self) -> Result<String, InquireError> {
-        inquire::Text::new("Press Enter when you're ready to continue the demo.").prompt()
+    pub fn enter_to_continue(&self) -> Result<(), EC2Error> {
+        self.prompter
+            .pause("Press Enter when you're ready to continue the demo.")
+            .map_err(|e| EC2Error::new(format!("Failed to wait for Enter. {e}")))
     }
 
     pub fn select_scenario_image(
         &self,
         amzn2_images: Vec<ScenarioImage>,
     ) -> Result<ScenarioImage, EC2Error> {
-        inquire::Select::new(
-            "Select an Amazon Linux 2 AMI for this instance",
-            amzn2_images,
-        )
-        .prompt()
-        .map_err(|e| EC2Error::new(format!("Could not determine desired AMI ({e:?})")))
+        self.prompter
+            .choose(
+                "image_name",
+                "Select an Amazon Linux 2 AMI for this instance",
+                amzn2_images,
+            )
+            .map_err(|e| EC2Error::new(format!("Could not determine desired AMI ({e})")))
     }
 
+    /// Unattended runs give up rather than wait forever.
     pub fn should_continue_waiting(&self) -> bool {
-        inquire::Confirm::new("Continue waiting?")
-            .with_default(true)
-            .prompt()
+        self.prompter
+            .confirm("continue_waiting", "Continue waiting?", false)
             .unwrap_or(false)
     }
 
     pub fn select_instance_type(
         &self,
-        instance_types: Vec<aws_sdk_ec2::types::InstanceType>,
-    ) -> Result<aws_sdk_ec2::types::InstanceType, EC2Error> {
-        inquire::Select::new("Select an instance type for this instance:", instance_types)
-            .prompt()
+        instance_types: Vec<InstanceType>,
+    ) -> Result<InstanceType, EC2Error> {
+        self.prompter
+            .choose(
+                "instance_type",
+                "Select an instance type for this instance:",
+                instance_types,
+            )
             .map_err(|e| {
                 EC2Error::new(format!(
-                    "Could not determine the desired instance type ({e:?})"
+                    "Could not determine the desired instance type ({e})"
                 ))
             })
     }
 
     pub fn should_add_to_security_group(&self) -> bool {
-        inquire::Confirm::new("Add this rule to your security group?")
-            .with_default(true)
-            .prompt()
+        self.prompter
+            .confirm(
+                "add_ssh_rule",
+                "Add this rule to your security group?",
+                true,
+            )
             .unwrap_or(true)
     }
 
     pub fn prompt_security_group_name(&self) -> Result<String, EC2Error> {
-        inquire::Text::new("Enter a unique name for your security group: ")
-            .with_validator(ValueRequiredValidator::default())
-            .with_default("my_group")
-            .prompt()
-            .map_err(|e| EC2Error::new(format!("Failed to get name for security group! {e:?}")))
+        self.prompter
+            .required_text(
+                "security_group_name",
+                "Enter a unique name for your security group:",
+                Some("my_group"),
+            )
+            .map_err(|e| EC2Error::new(format!("Failed to get name for security group! {e}")))
     }
 
     pub fn should_list_key_pairs(&self) -> Result<bool, EC2Error> {
-        inquire::Confirm::new("Do you want to list some of your key pairs?")
-            .with_default(false)
-            .prompt()
-            .or(Ok(false))
-            .map_err(|e: InquireError| EC2Error::new(format!("Failed to ask question. {e:?}")))
+        Ok(self
+            .prompter
+            .confirm(
+                "list_key_pairs",
+                "Do you want to list some of your key pairs?",
+                false,
+            )
+            .unwrap_or(false))
     }
 
     /// Utility to perform a GET request and return the body as UTF-8, or an appropriate EC2Error.
@@ -146,7 +175,7 @@ fn open_file(path: &PathBuf) -> Result<File, EC2Error> {
         .map_err(|e| EC2Error::new(format!("Failed to create {path:?} ({e:?})")))?
 }
 
-/// Image doesn't impl Display, which is necessary for inquire to use it in a Select.
+/// Image doesn't impl Display, which is necessary to show it in a Select.
 /// This wraps Image and provides a Display impl.
 #[derive(PartialEq, Debug)]
 pub struct ScenarioImage(pub Image);
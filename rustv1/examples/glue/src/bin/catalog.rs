@@ -0,0 +1,149 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0.
+
+#![allow(clippy::result_large_err)]
+
+//! Browse the AWS Glue Data Catalog: list databases, tables, and partitions, show a table's
+//! schema, compare two versions of it, and export the catalog as JSON.
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use glue_code_examples::{
+    catalog::{self, TableSchema},
+    GlueMvpError,
+};
+use tracing::error;
+
+#[derive(Debug, Parser)]
+#[command(about)]
+struct Opt {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// List the databases in the catalog.
+    Databases,
+    /// List the tables in a database.
+    Tables {
+        #[arg(short, long)]
+        database: String,
+    },
+    /// List the partitions of a table.
+    Partitions {
+        #[arg(short, long)]
+        database: String,
+        #[arg(short, long)]
+        table: String,
+    },
+    /// Show a table's columns and partition keys.
+    Schema {
+        #[arg(short, long)]
+        database: String,
+        #[arg(short, long)]
+        table: String,
+        /// Show every version of the schema, newest first.
+        #[arg(long)]
+        versions: bool,
+    },
+    /// Compare two versions of a table's schema. Defaults to the latest two.
+    Diff {
+        #[arg(short, long)]
+        database: String,
+        #[arg(short, long)]
+        table: String,
+        #[arg(long)]
+        from: Option<String>,
+        #[arg(long)]
+        to: Option<String>,
+    },
+    /// Write the schemas of every table as JSON.
+    Export {
+        /// Only export these databases. Can be repeated.
+        #[arg(short, long)]
+        database: Vec<String>,
+        /// Write to this file instead of stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+async fn run(command: Command) -> Result<(), GlueMvpError> {
+    match command {
+        Command::Databases => {
+            for database in catalog::list_databases().await? {
+                match database.description() {
+                    Some(description) => println!("{}  {description}", database.name()),
+                    None => println!("{}", database.name()),
+                }
+            }
+        }
+        Command::Tables { database } => {
+            for table in catalog::list_tables(&database).await? {
+                let schema = TableSchema::from_table(&table, None);
+                println!(
+                    "{}  {} columns, {} partition keys",
+                    schema.name,
+                    schema.columns.len(),
+                    schema.partition_keys.len()
+                );
+            }
+        }
+        Command::Partitions { database, table } => {
+            let partitions = catalog::list_partitions(&database, &table).await?;
+            for partition in &partitions {
+                let location = partition
+                    .storage_descriptor()
+                    .and_then(|s| s.location())
+                    .unwrap_or_default();
+                println!("{}  {location}", partition.values().join("/"));
+            }
+            println!("{} partitions", partitions.len());
+        }
+        Command::Schema {
+            database,
+            table,
+            versions,
+        } => {
+            if versions {
+                for schema in catalog::table_versions(&database, &table).await? {
+                    println!("{schema}");
+                }
+            } else {
+                println!("{}", catalog::table_schema(&database, &table).await?);
+            }
+        }
+        Command::Diff {
+            database,
+            table,
+            from,
+            to,
+        } => {
+            let diff =
+                catalog::diff_table_versions(&database, &table, from.as_deref(), to.as_deref())
+                    .await?;
+            print!("{diff}");
+        }
+        Command::Export { database, output } => {
+            let json = catalog::export_catalog(&database).await?.to_json()?;
+            match output {
+                Some(path) => std::fs::write(&path, json).map_err(|err| {
+                    GlueMvpError::Unknown(format!("Failed to write {}: {err}", path.display()))
+                })?,
+                None => println!("{json}"),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let Opt { command } = Opt::parse();
+    if let Err(err) = run(command).await {
+        error!(?err, "Catalog command failed");
+    }
+}
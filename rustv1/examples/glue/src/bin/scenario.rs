@@ -4,7 +4,8 @@
 #![allow(clippy::result_large_err)]
 
 /// This scenario follows the [AWS Glue Tutorial](https://docs.aws.amazon.com/glue/latest/ug/tutorial-add-crawler.html).
-use glue_code_examples::{GlueMvpError, GlueScenario};
+use glue_code_examples::{clients::SDK_CONFIG, GlueMvpError, GlueScenario};
+use sdk_examples_resource_ledger::{Ledger, DEFAULT_JOURNAL};
 use tracing::{error, warn};
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 use tracing_subscriber::{layer::SubscriberExt, EnvFilter, Registry};
@@ -26,7 +27,10 @@ fn init_logging() -> Result<(), GlueMvpError> {
 async fn main() {
     init_logging().expect("Failed to initialize tracing");
 
-    let mut scenario = GlueScenario::parse();
+    // Record what the scenario creates, so `cleanup --from-journal` can remove it if this run is interrupted.
+    let region = SDK_CONFIG.get().await.region().map(|r| r.to_string());
+    let mut scenario =
+        GlueScenario::parse().with_ledger(Ledger::open(DEFAULT_JOURNAL).with_region(region));
 
     let prepare = scenario.prepare().await;
 
@@ -0,0 +1,129 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0.
+
+#![allow(clippy::result_large_err)]
+
+//! Run the flight data ETL script as a workflow: two copies of the job run in parallel with
+//! bookmarks enabled, and a third runs only after both succeed.
+use clap::Parser;
+use glue_code_examples::{
+    clients::SDK_CONFIG,
+    jobs::Bookmarks,
+    workflow::{Dependency, WorkflowJob, WorkflowSpec},
+    GlueMvpError, GlueScenario, GlueScenarioArgs,
+};
+use sdk_examples_resource_ledger::{Ledger, DEFAULT_JOURNAL};
+use tracing::{error, warn};
+
+#[derive(Debug, Parser)]
+struct Opt {
+    #[command(flatten)]
+    scenario: GlueScenarioArgs,
+
+    /// How many log lines to show from each failed job run.
+    #[arg(long, default_value = "50")]
+    log_lines: usize,
+}
+
+fn workflow_spec(scenario: &GlueScenario) -> Result<WorkflowSpec, GlueMvpError> {
+    let table = scenario
+        .tables()
+        .first()
+        .ok_or_else(|| GlueMvpError::Unknown("Missing crawler table".into()))?
+        .name()
+        .to_string();
+    let script = format!("s3://{}/job.py", scenario.bucket());
+    let job = |suffix: &str, output: &str| {
+        WorkflowJob::new(format!("{}_{suffix}", scenario.job()), script.as_str())
+            .argument("--input_database", scenario.database())
+            .argument("--input_table", table.as_str())
+            .argument(
+                "--output_bucket_url",
+                format!("s3://{}/{output}/", scenario.bucket()),
+            )
+            .bookmarks(Bookmarks::Enable)
+    };
+
+    let east = format!("{}_east", scenario.job());
+    let west = format!("{}_west", scenario.job());
+    Ok(WorkflowSpec::new(format!("{}_workflow", scenario.job()))
+        .job(job("east", "east"))
+        .job(job("west", "west"))
+        .job(
+            job("combined", "combined")
+                .after(Dependency::succeeded(east))
+                .after(Dependency::succeeded(west)),
+        ))
+}
+
+async fn run_workflow(
+    scenario: &GlueScenario,
+    spec: &WorkflowSpec,
+    log_lines: usize,
+) -> Result<(), GlueMvpError> {
+    scenario.create_workflow(spec).await?;
+    let run_id = scenario.start_workflow(&spec.name).await?;
+    let summary = scenario.wait_for_workflow_run(&spec.name, &run_id).await?;
+    println!("{summary}");
+
+    for logs in scenario
+        .failed_run_logs(summary.failed(), log_lines)
+        .await?
+    {
+        println!("{logs}");
+    }
+
+    if summary.is_success() {
+        Ok(())
+    } else {
+        Err(GlueMvpError::job_fail(format!(
+            "Workflow run {run_id} did not succeed"
+        )))
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let Opt {
+        scenario,
+        log_lines,
+    } = Opt::parse();
+    let should_cleanup = scenario.cleanup;
+
+    let region = SDK_CONFIG.get().await.region().map(|r| r.to_string());
+    let mut scenario = GlueScenario::from_args(scenario)
+        .with_ledger(Ledger::open(DEFAULT_JOURNAL).with_region(region));
+
+    if let Err(err) = scenario.prepare().await {
+        error!(?err, "Failed to prepare crawler");
+        return;
+    }
+
+    let spec = match workflow_spec(&scenario) {
+        Ok(spec) => spec,
+        Err(err) => {
+            error!(?err, "Failed to define workflow");
+            return;
+        }
+    };
+
+    if let Err(err) = run_workflow(&scenario, &spec, log_lines).await {
+        error!(?err, "Workflow failed");
+    }
+
+    if should_cleanup {
+        if let Err(err) = scenario.delete_workflow(&spec).await {
+            error!(?err, "Failed to delete workflow");
+        }
+        if let Err(err) = scenario.clean_scenario().await {
+            error!(?err, "Cleanup error");
+        }
+    }
+
+    warn!(
+        "Glue workflow complete! See details in {}",
+        scenario.bucket()
+    );
+}
@@ -0,0 +1,479 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0.
+
+use std::fmt::Display;
+
+use aws_sdk_glue::types::{Column, Database, Partition, Table, TableVersion};
+use aws_smithy_types::date_time::Format;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+use crate::{clients::GLUE_CLIENT, GlueMvpError};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnSchema {
+    pub name: String,
+    pub data_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl From<&Column> for ColumnSchema {
+    fn from(column: &Column) -> Self {
+        ColumnSchema {
+            name: column.name().to_string(),
+            data_type: column.r#type().unwrap_or("unknown").to_string(),
+            comment: column.comment().map(String::from),
+        }
+    }
+}
+
+/// The parts of a catalog table that describe its data: columns, partition keys, and storage.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableSchema {
+    pub database: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub classification: Option<String>,
+    pub columns: Vec<ColumnSchema>,
+    pub partition_keys: Vec<ColumnSchema>,
+}
+
+impl TableSchema {
+    pub fn from_table(table: &Table, version_id: Option<&str>) -> Self {
+        let storage = table.storage_descriptor();
+        TableSchema {
+            database: table.database_name().unwrap_or_default().to_string(),
+            name: table.name().to_string(),
+            version_id: version_id.or(table.version_id()).map(String::from),
+            updated_at: table
+                .update_time()
+                .or(table.create_time())
+                .and_then(|time| time.fmt(Format::DateTime).ok()),
+            location: storage.and_then(|s| s.location()).map(String::from),
+            classification: table
+                .parameters()
+                .and_then(|p| p.get("classification"))
+                .cloned(),
+            columns: storage
+                .map(|s| s.columns())
+                .unwrap_or_default()
+                .iter()
+                .map(ColumnSchema::from)
+                .collect(),
+            partition_keys: table
+                .partition_keys()
+                .iter()
+                .map(ColumnSchema::from)
+                .collect(),
+        }
+    }
+
+    fn all_columns(&self) -> impl Iterator<Item = &ColumnSchema> {
+        self.columns.iter().chain(self.partition_keys.iter())
+    }
+}
+
+impl Display for TableSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.database, self.name)?;
+        if let Some(version) = &self.version_id {
+            write!(f, " (version {version})")?;
+        }
+        writeln!(f)?;
+        if let Some(location) = &self.location {
+            writeln!(f, "  location: {location}")?;
+        }
+        if let Some(classification) = &self.classification {
+            writeln!(f, "  classification: {classification}")?;
+        }
+        if let Some(updated_at) = &self.updated_at {
+            writeln!(f, "  updated: {updated_at}")?;
+        }
+
+        let width = self.all_columns().map(|c| c.name.len()).max().unwrap_or(0);
+        for (heading, columns) in [
+            ("columns", &self.columns),
+            ("partition keys", &self.partition_keys),
+        ] {
+            if columns.is_empty() {
+                continue;
+            }
+            writeln!(f, "  {heading}:")?;
+            for column in columns {
+                write!(f, "    {:width$}  {}", column.name, column.data_type)?;
+                if let Some(comment) = &column.comment {
+                    write!(f, "  -- {comment}")?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "kebab-case")]
+pub enum SchemaChange {
+    Added {
+        column: String,
+        data_type: String,
+    },
+    Removed {
+        column: String,
+        data_type: String,
+    },
+    TypeChanged {
+        column: String,
+        from: String,
+        to: String,
+    },
+}
+
+impl Display for SchemaChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaChange::Added { column, data_type } => write!(f, "+ {column} {data_type}"),
+            SchemaChange::Removed { column, data_type } => write!(f, "- {column} {data_type}"),
+            SchemaChange::TypeChanged { column, from, to } => {
+                write!(f, "~ {column} {from} -> {to}")
+            }
+        }
+    }
+}
+
+/// How a table's columns changed between two versions of its schema.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaDiff {
+    pub table: String,
+    pub from_version: Option<String>,
+    pub to_version: Option<String>,
+    pub changes: Vec<SchemaChange>,
+}
+
+impl SchemaDiff {
+    /// Compare columns and partition keys by name. Columns are reported in the order the newer
+    /// schema has them, then the removed columns in the order the older schema had them.
+    pub fn between(older: &TableSchema, newer: &TableSchema) -> Self {
+        let mut changes = vec![];
+        for column in newer.all_columns() {
+            match older.all_columns().find(|c| c.name == column.name) {
+                None => changes.push(SchemaChange::Added {
+                    column: column.name.clone(),
+                    data_type: column.data_type.clone(),
+                }),
+                Some(old) if old.data_type != column.data_type => {
+                    changes.push(SchemaChange::TypeChanged {
+                        column: column.name.clone(),
+                        from: old.data_type.clone(),
+                        to: column.data_type.clone(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        for column in older.all_columns() {
+            if !newer.all_columns().any(|c| c.name == column.name) {
+                changes.push(SchemaChange::Removed {
+                    column: column.name.clone(),
+                    data_type: column.data_type.clone(),
+                });
+            }
+        }
+
+        SchemaDiff {
+            table: format!("{}.{}", newer.database, newer.name),
+            from_version: older.version_id.clone(),
+            to_version: newer.version_id.clone(),
+            changes,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl Display for SchemaDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}: version {} -> {}",
+            self.table,
+            self.from_version.as_deref().unwrap_or("?"),
+            self.to_version.as_deref().unwrap_or("?")
+        )?;
+        if self.changes.is_empty() {
+            writeln!(f, "  no column changes")?;
+        }
+        for change in &self.changes {
+            writeln!(f, "  {change}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatabaseExport {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub tables: Vec<TableSchema>,
+}
+
+/// The databases and table schemas in the catalog, for review outside the console.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogExport {
+    pub databases: Vec<DatabaseExport>,
+}
+
+impl CatalogExport {
+    pub fn to_json(&self) -> Result<String, GlueMvpError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|err| GlueMvpError::Unknown(format!("Failed to write catalog JSON: {err}")))
+    }
+}
+
+// snippet-start:[rust.glue.get_databases]
+#[instrument]
+pub async fn list_databases() -> Result<Vec<Database>, GlueMvpError> {
+    let glue = GLUE_CLIENT.get().await;
+
+    let mut databases = vec![];
+    let mut pages = glue.get_databases().into_paginator().send();
+    while let Some(page) = pages.next().await {
+        let page = page.map_err(GlueMvpError::from_glue_sdk)?;
+        databases.extend_from_slice(page.database_list());
+    }
+    Ok(databases)
+}
+// snippet-end:[rust.glue.get_databases]
+
+#[instrument]
+pub async fn list_tables(database: &str) -> Result<Vec<Table>, GlueMvpError> {
+    let glue = GLUE_CLIENT.get().await;
+
+    let mut tables = vec![];
+    let mut pages = glue
+        .get_tables()
+        .database_name(database)
+        .into_paginator()
+        .send();
+    while let Some(page) = pages.next().await {
+        let page = page.map_err(GlueMvpError::from_glue_sdk)?;
+        tables.extend_from_slice(page.table_list());
+    }
+    Ok(tables)
+}
+
+// snippet-start:[rust.glue.get_partitions]
+#[instrument]
+pub async fn list_partitions(database: &str, table: &str) -> Result<Vec<Partition>, GlueMvpError> {
+    let glue = GLUE_CLIENT.get().await;
+
+    let mut partitions = vec![];
+    let mut pages = glue
+        .get_partitions()
+        .database_name(database)
+        .table_name(table)
+        .into_paginator()
+        .send();
+    while let Some(page) = pages.next().await {
+        let page = page.map_err(GlueMvpError::from_glue_sdk)?;
+        partitions.extend_from_slice(page.partitions());
+    }
+    Ok(partitions)
+}
+// snippet-end:[rust.glue.get_partitions]
+
+#[instrument]
+pub async fn table_schema(database: &str, table: &str) -> Result<TableSchema, GlueMvpError> {
+    let glue = GLUE_CLIENT.get().await;
+
+    let output = glue
+        .get_table()
+        .database_name(database)
+        .name(table)
+        .send()
+        .await
+        .map_err(GlueMvpError::from_glue_sdk)?;
+    let table = output
+        .table()
+        .ok_or_else(|| GlueMvpError::Unknown(format!("Could not find table {table}")))?;
+    Ok(TableSchema::from_table(table, None))
+}
+
+// snippet-start:[rust.glue.get_table_versions]
+/// Every version of the table's schema, newest first. A crawler adds a version each time
+/// it finds the schema changed.
+#[instrument]
+pub async fn table_versions(database: &str, table: &str) -> Result<Vec<TableSchema>, GlueMvpError> {
+    let glue = GLUE_CLIENT.get().await;
+
+    let mut versions: Vec<TableVersion> = vec![];
+    let mut pages = glue
+        .get_table_versions()
+        .database_name(database)
+        .table_name(table)
+        .into_paginator()
+        .send();
+    while let Some(page) = pages.next().await {
+        let page = page.map_err(GlueMvpError::from_glue_sdk)?;
+        versions.extend_from_slice(page.table_versions());
+    }
+
+    let mut schemas = versions
+        .iter()
+        .filter_map(|v| Some(TableSchema::from_table(v.table()?, v.version_id())))
+        .collect::<Vec<_>>();
+    // Version IDs are increasing integers, but are returned as strings.
+    schemas.sort_by_key(|s| {
+        std::cmp::Reverse(
+            s.version_id
+                .as_deref()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(0),
+        )
+    });
+    Ok(schemas)
+}
+// snippet-end:[rust.glue.get_table_versions]
+
+/// Compare two versions of a table's schema. Without versions, compare the latest version
+/// with the one before it.
+#[instrument]
+pub async fn diff_table_versions(
+    database: &str,
+    table: &str,
+    from_version: Option<&str>,
+    to_version: Option<&str>,
+) -> Result<SchemaDiff, GlueMvpError> {
+    let versions = table_versions(database, table).await?;
+    let find = |version: &str| {
+        versions
+            .iter()
+            .find(|s| s.version_id.as_deref() == Some(version))
+            .ok_or_else(|| {
+                GlueMvpError::Unknown(format!("{database}.{table} has no version {version}"))
+            })
+    };
+
+    let newer = match to_version {
+        Some(version) => find(version)?,
+        None => versions
+            .first()
+            .ok_or_else(|| GlueMvpError::Unknown(format!("{database}.{table} has no versions")))?,
+    };
+    let older = match from_version {
+        Some(version) => find(version)?,
+        None => versions.get(1).unwrap_or(newer),
+    };
+    Ok(SchemaDiff::between(older, newer))
+}
+
+/// Export the schemas of every table in the databases, or in every database when none are named.
+#[instrument]
+pub async fn export_catalog(databases: &[String]) -> Result<CatalogExport, GlueMvpError> {
+    let mut export = CatalogExport::default();
+    for database in list_databases().await? {
+        if !databases.is_empty() && !databases.iter().any(|d| d == database.name()) {
+            continue;
+        }
+        let tables = list_tables(database.name()).await?;
+        export.databases.push(DatabaseExport {
+            name: database.name().to_string(),
+            description: database.description().map(String::from),
+            tables: tables
+                .iter()
+                .map(|table| TableSchema::from_table(table, None))
+                .collect(),
+        });
+    }
+    Ok(export)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn column(name: &str, data_type: &str) -> ColumnSchema {
+        ColumnSchema {
+            name: name.into(),
+            data_type: data_type.into(),
+            comment: None,
+        }
+    }
+
+    fn schema(version: &str, columns: Vec<ColumnSchema>) -> TableSchema {
+        TableSchema {
+            database: "flights".into(),
+            name: "csv".into(),
+            version_id: Some(version.into()),
+            columns,
+            partition_keys: vec![column("year", "string")],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_schema_diff() {
+        let older = schema(
+            "1",
+            vec![
+                column("carrier", "string"),
+                column("delay", "bigint"),
+                column("origin", "string"),
+            ],
+        );
+        let newer = schema(
+            "2",
+            vec![
+                column("carrier", "string"),
+                column("delay", "double"),
+                column("dest", "string"),
+            ],
+        );
+
+        let diff = SchemaDiff::between(&older, &newer);
+
+        assert_eq!(diff.table, "flights.csv");
+        assert_eq!(
+            diff.changes,
+            vec![
+                SchemaChange::TypeChanged {
+                    column: "delay".into(),
+                    from: "bigint".into(),
+                    to: "double".into(),
+                },
+                SchemaChange::Added {
+                    column: "dest".into(),
+                    data_type: "string".into(),
+                },
+                SchemaChange::Removed {
+                    column: "origin".into(),
+                    data_type: "string".into(),
+                },
+            ]
+        );
+        assert!(SchemaDiff::between(&newer, &newer).is_empty());
+    }
+
+    #[test]
+    fn test_render_schema() {
+        let rendered = schema(
+            "3",
+            vec![column("carrier", "string"), column("delay", "double")],
+        )
+        .to_string();
+        assert_eq!(
+            rendered,
+            "flights.csv (version 3)\n  columns:\n    carrier  string\n    delay    double\n  partition keys:\n    year     string\n"
+        );
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0.
 
 use aws_sdk_glue::types::CrawlerState;
+use sdk_examples_resource_ledger::ResourceKind;
 use tracing::{instrument, warn};
 
 use crate::{clients::GLUE_CLIENT, GlueMvpError, GlueScenario};
@@ -19,6 +20,7 @@ impl GlueScenario {
             .await
             .map_err(GlueMvpError::from_glue_sdk)?;
         // snippet-end:[rust.glue.delete_job]
+        self.ledger.deleted(ResourceKind::GlueJob, self.job());
 
         // snippet-start:[rust.glue.delete_table]
         for t in &self.tables {
@@ -77,6 +79,8 @@ impl GlueScenario {
             .await
             .map_err(GlueMvpError::from_glue_sdk)?;
         // snippet-end:[rust.glue.delete_database]
+        self.ledger
+            .deleted(ResourceKind::GlueDatabase, self.database());
 
         // DeleteCrawler
         // snippet-start:[rust.glue.delete_crawler]
@@ -86,6 +90,8 @@ impl GlueScenario {
             .await
             .map_err(GlueMvpError::from_glue_sdk)?;
         // snippet-end:[rust.glue.delete_crawler]
+        self.ledger
+            .deleted(ResourceKind::GlueCrawler, self.crawler());
 
         Ok(())
     }
@@ -16,4 +16,8 @@ lazy_static! {
         let config = SDK_CONFIG.get().await;
         aws_sdk_glue::Client::new(config)
     });
+    pub static ref LOGS_CLIENT: AsyncOnce<aws_sdk_cloudwatchlogs::Client> = AsyncOnce::new(async {
+        let config = SDK_CONFIG.get().await;
+        aws_sdk_cloudwatchlogs::Client::new(config)
+    });
 }
@@ -0,0 +1,353 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0.
+
+use std::{collections::BTreeMap, fmt::Display, time::Duration};
+
+use aws_sdk_glue::types::{JobBookmarkEntry, JobRun, JobRunState};
+use futures::future::join_all;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    clients::GLUE_CLIENT, logs::LOG_GROUP_ARGUMENT, run::wait_for_run, GlueMvpError, GlueScenario,
+};
+
+/// The job argument that controls bookmarks.
+pub const BOOKMARK_ARGUMENT: &str = "--job-bookmark-option";
+
+/// Whether a job remembers what it processed, so the next run only reads new data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Bookmarks {
+    Enable,
+    #[default]
+    Disable,
+    /// Read from the last bookmark, but don't move it.
+    Pause,
+}
+
+impl Bookmarks {
+    pub fn as_argument(&self) -> &'static str {
+        match self {
+            Bookmarks::Enable => "job-bookmark-enable",
+            Bookmarks::Disable => "job-bookmark-disable",
+            Bookmarks::Pause => "job-bookmark-pause",
+        }
+    }
+}
+
+/// One run to start, with the arguments for this run. Arguments override the job's defaults.
+#[derive(Clone, Debug, Default)]
+pub struct JobRunRequest {
+    pub job: String,
+    pub arguments: BTreeMap<String, String>,
+    pub bookmarks: Option<Bookmarks>,
+}
+
+impl JobRunRequest {
+    pub fn new(job: impl Into<String>) -> Self {
+        JobRunRequest {
+            job: job.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Add an argument. Glue argument names start with `--`, which is added when missing.
+    pub fn argument(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        let name = name.into();
+        let name = if name.starts_with("--") {
+            name
+        } else {
+            format!("--{name}")
+        };
+        self.arguments.insert(name, value.into());
+        self
+    }
+
+    pub fn bookmarks(mut self, bookmarks: Bookmarks) -> Self {
+        self.bookmarks = Some(bookmarks);
+        self
+    }
+
+    fn all_arguments(&self) -> BTreeMap<String, String> {
+        let mut arguments = self.arguments.clone();
+        if let Some(bookmarks) = self.bookmarks {
+            arguments.insert(
+                BOOKMARK_ARGUMENT.to_string(),
+                bookmarks.as_argument().to_string(),
+            );
+        }
+        arguments
+    }
+}
+
+/// How one run ended.
+#[derive(Clone, Debug)]
+pub struct JobRunOutcome {
+    pub job: String,
+    pub run_id: String,
+    pub state: Option<JobRunState>,
+    pub error_message: Option<String>,
+    /// Seconds the run used resources for.
+    pub execution_time: i32,
+    /// The log group the run was started with, when it isn't the job's.
+    pub log_group: Option<String>,
+}
+
+impl JobRunOutcome {
+    pub(crate) fn from_run(job: &str, run_id: &str, run: &JobRun) -> Self {
+        JobRunOutcome {
+            job: job.to_string(),
+            run_id: run_id.to_string(),
+            state: run.job_run_state().cloned(),
+            error_message: run.error_message().map(String::from),
+            execution_time: run.execution_time(),
+            log_group: run
+                .arguments()
+                .and_then(|arguments| arguments.get(LOG_GROUP_ARGUMENT))
+                .cloned(),
+        }
+    }
+
+    /// A run that was started, but whose end couldn't be checked.
+    fn unknown(job: &str, run_id: &str, err: GlueMvpError) -> Self {
+        JobRunOutcome {
+            job: job.to_string(),
+            run_id: run_id.to_string(),
+            state: None,
+            error_message: Some(format!("Failed to check on the run: {err}")),
+            execution_time: 0,
+            log_group: None,
+        }
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.state == Some(JobRunState::Succeeded)
+    }
+}
+
+impl Display for JobRunOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}: {} after {}s",
+            self.job,
+            self.run_id,
+            self.state.as_ref().map(|s| s.as_str()).unwrap_or("UNKNOWN"),
+            self.execution_time
+        )?;
+        if let Some(message) = &self.error_message {
+            write!(f, " ({message})")?;
+        }
+        Ok(())
+    }
+}
+
+/// A run that could not be started.
+#[derive(Clone, Debug)]
+pub struct JobStartFailure {
+    pub job: String,
+    pub error: String,
+}
+
+/// The outcomes of runs started together.
+#[derive(Clone, Debug, Default)]
+pub struct JobRunsSummary {
+    pub runs: Vec<JobRunOutcome>,
+    pub not_started: Vec<JobStartFailure>,
+}
+
+impl JobRunsSummary {
+    pub fn succeeded(&self) -> impl Iterator<Item = &JobRunOutcome> {
+        self.runs.iter().filter(|run| run.succeeded())
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &JobRunOutcome> {
+        self.runs.iter().filter(|run| !run.succeeded())
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.not_started.is_empty() && self.runs.iter().all(JobRunOutcome::succeeded)
+    }
+}
+
+impl Display for JobRunsSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} runs: {} succeeded, {} did not",
+            self.runs.len(),
+            self.succeeded().count(),
+            self.failed().count()
+        )?;
+        for run in &self.runs {
+            writeln!(f, "  {run}")?;
+        }
+        for failure in &self.not_started {
+            writeln!(f, "  {} did not start: {}", failure.job, failure.error)?;
+        }
+        Ok(())
+    }
+}
+
+impl GlueScenario {
+    #[instrument(skip(self))]
+    pub async fn start_job(&self, request: &JobRunRequest) -> Result<String, GlueMvpError> {
+        start_job(GLUE_CLIENT.get().await, request).await
+    }
+
+    /// Start every run, then wait for all of them. A run that fails, or fails to start, doesn't
+    /// stop the others; check the summary.
+    #[instrument(skip(self, requests))]
+    pub async fn run_jobs(&self, requests: &[JobRunRequest]) -> JobRunsSummary {
+        run_jobs(GLUE_CLIENT.get().await, self.wait_delay, requests).await
+    }
+
+    // snippet-start:[rust.glue.get_job_bookmark]
+    /// Where the job's bookmark is, or None if the job has never run with bookmarks enabled.
+    #[instrument(skip(self))]
+    pub async fn job_bookmark(&self, job: &str) -> Result<Option<JobBookmarkEntry>, GlueMvpError> {
+        let glue = GLUE_CLIENT.get().await;
+
+        match glue.get_job_bookmark().job_name(job).send().await {
+            Ok(output) => Ok(output.job_bookmark_entry),
+            Err(err)
+                if err
+                    .as_service_error()
+                    .map(|e| e.is_entity_not_found_exception())
+                    == Some(true) =>
+            {
+                Ok(None)
+            }
+            Err(err) => Err(GlueMvpError::from_glue_sdk(err)),
+        }
+    }
+    // snippet-end:[rust.glue.get_job_bookmark]
+
+    // snippet-start:[rust.glue.reset_job_bookmark]
+    /// Move the bookmark back, so the next run processes everything again. With a run ID, move
+    /// it back to before that run instead.
+    #[instrument(skip(self))]
+    pub async fn reset_job_bookmark(
+        &self,
+        job: &str,
+        run_id: Option<&str>,
+    ) -> Result<(), GlueMvpError> {
+        let glue = GLUE_CLIENT.get().await;
+
+        glue.reset_job_bookmark()
+            .job_name(job)
+            .set_run_id(run_id.map(String::from))
+            .send()
+            .await
+            .map_err(GlueMvpError::from_glue_sdk)?;
+
+        Ok(())
+    }
+    // snippet-end:[rust.glue.reset_job_bookmark]
+}
+
+// snippet-start:[rust.glue.start_job_run_with_arguments]
+async fn start_job(
+    glue: &aws_sdk_glue::Client,
+    request: &JobRunRequest,
+) -> Result<String, GlueMvpError> {
+    let run = glue
+        .start_job_run()
+        .job_name(request.job.as_str())
+        .set_arguments(Some(request.all_arguments().into_iter().collect()))
+        .send()
+        .await
+        .map_err(GlueMvpError::from_glue_sdk)?;
+
+    run.job_run_id
+        .ok_or_else(|| GlueMvpError::Unknown("Missing run id from just started job".into()))
+}
+// snippet-end:[rust.glue.start_job_run_with_arguments]
+
+async fn run_jobs(
+    glue: &aws_sdk_glue::Client,
+    wait_delay: Duration,
+    requests: &[JobRunRequest],
+) -> JobRunsSummary {
+    let mut summary = JobRunsSummary::default();
+    let mut started = Vec::with_capacity(requests.len());
+    for request in requests {
+        match start_job(glue, request).await {
+            Ok(run_id) => {
+                info!(job = %request.job, %run_id, "Started job run");
+                started.push((request.job.as_str(), run_id));
+            }
+            Err(err) => {
+                warn!(job = %request.job, %err, "Failed to start job run");
+                summary.not_started.push(JobStartFailure {
+                    job: request.job.clone(),
+                    error: err.to_string(),
+                });
+            }
+        }
+    }
+
+    let finished = join_all(
+        started
+            .iter()
+            .map(|(job, run_id)| wait_for_run(glue, wait_delay, job, run_id)),
+    )
+    .await;
+
+    for ((job, run_id), run) in started.iter().zip(finished) {
+        summary.runs.push(match run {
+            Ok(run) => JobRunOutcome::from_run(job, run_id, &run),
+            Err(err) => JobRunOutcome::unknown(job, run_id, err),
+        });
+    }
+    summary
+}
+
+#[cfg(test)]
+mod test {
+    use aws_smithy_runtime::client::http::test_util::StaticReplayClient;
+    use sdk_examples_test_utils::{client_config, test_event};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_jobs_waits_on_started_runs_when_one_fails_to_start() {
+        let glue = aws_sdk_glue::Client::from_conf(
+            client_config!(aws_sdk_glue)
+                .http_client(StaticReplayClient::new(vec![
+                    test_event!(r#""#, (200, r#"{"JobRunId": "jr_east"}"#)),
+                    test_event!(
+                        r#""#,
+                        (
+                            400,
+                            r#"{"__type": "ConcurrentRunsExceededException", "Message": "Too many runs"}"#
+                        )
+                    ),
+                    test_event!(
+                        r#""#,
+                        (
+                            200,
+                            r#"{"JobRun": {"Id": "jr_east", "JobRunState": "SUCCEEDED", "ExecutionTime": 42,
+                            "Arguments": {"--continuous-log-logGroup": "/custom/glue"}}}"#
+                        )
+                    ),
+                ]))
+                .build(),
+        );
+
+        let summary = run_jobs(
+            &glue,
+            Duration::from_millis(1),
+            &[JobRunRequest::new("east"), JobRunRequest::new("west")],
+        )
+        .await;
+
+        assert_eq!(summary.runs.len(), 1);
+        assert!(summary.runs[0].succeeded());
+        assert_eq!(summary.runs[0].run_id, "jr_east");
+        assert_eq!(summary.runs[0].execution_time, 42);
+        assert_eq!(summary.runs[0].log_group.as_deref(), Some("/custom/glue"));
+        assert_eq!(summary.not_started.len(), 1);
+        assert_eq!(summary.not_started[0].job, "west");
+        assert!(!summary.is_success());
+    }
+}
@@ -1,14 +1,24 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0.
 
+pub mod catalog;
 pub mod cleanup;
 pub mod clients;
+pub mod jobs;
+pub mod logs;
 pub mod prepare;
 pub mod run;
+pub mod workflow;
 
-use aws_sdk_glue::types::Table;
+use aws_sdk_glue::{
+    config::http::HttpResponse,
+    error::{ProvideErrorMetadata, SdkError},
+    types::Table,
+};
 use aws_smithy_types::error::operation::BuildError;
 use clap::Parser;
+use sdk_examples_resource_ledger::Ledger;
+use sdk_examples_scenario_error::ScenarioError;
 use secrecy::Secret;
 use std::time::Duration;
 use tracing::warn;
@@ -61,6 +71,7 @@ pub struct GlueScenario {
     wait_delay: Duration,
     tables: Vec<Table>,
     job_run_id: String,
+    ledger: Ledger,
 }
 
 impl GlueScenario {
@@ -85,9 +96,16 @@ impl GlueScenario {
             wait_delay: Duration::from_secs(5),
             tables: vec![],
             job_run_id: String::new(),
+            ledger: Ledger::disabled(),
         }
     }
 
+    /// Record the resources the scenario creates, so an interrupted run can be cleaned up.
+    pub fn with_ledger(mut self, ledger: Ledger) -> Self {
+        self.ledger = ledger;
+        self
+    }
+
     pub fn crawler(&self) -> &str {
         self.crawler.as_str()
     }
@@ -107,6 +125,11 @@ impl GlueScenario {
     pub fn job_run_id(&self) -> &str {
         self.job_run_id.as_str()
     }
+
+    /// The tables the crawler found, once `prepare` has run.
+    pub fn tables(&self) -> &[Table] {
+        &self.tables
+    }
 }
 
 impl GlueScenario {
@@ -152,13 +175,16 @@ impl GlueScenario {
 #[derive(Debug, thiserror::Error)]
 pub enum GlueMvpError {
     #[error("Glue SDK Error: {0}")]
-    GlueSdk(aws_sdk_glue::Error),
+    GlueSdk(ScenarioError),
 
     #[error("Glue Job Failure: {0}")]
     JobFail(String),
 
     #[error("S3 SDK Error: {0}")]
-    S3Sdk(aws_sdk_s3::Error),
+    S3Sdk(ScenarioError),
+
+    #[error("CloudWatch Logs SDK Error: {0}")]
+    LogsSdk(ScenarioError),
 
     #[error("Failed to clean up: {0}")]
     Cleanup(String),
@@ -171,15 +197,31 @@ pub enum GlueMvpError {
 }
 
 impl GlueMvpError {
-    pub fn from_glue_sdk(err: impl Into<aws_sdk_glue::Error>) -> Self {
-        GlueMvpError::GlueSdk(err.into())
+    /// A failed Glue call, classified by how it failed so callers can tell whether to retry.
+    pub fn from_glue_sdk<E: ProvideErrorMetadata>(err: SdkError<E, HttpResponse>) -> Self {
+        GlueMvpError::GlueSdk(ScenarioError::from_sdk("", &err))
     }
 
-    pub fn from_s3_sdk(err: impl Into<aws_sdk_s3::Error>) -> Self {
-        GlueMvpError::S3Sdk(err.into())
+    pub fn from_s3_sdk<E: ProvideErrorMetadata>(err: SdkError<E, HttpResponse>) -> Self {
+        GlueMvpError::S3Sdk(ScenarioError::from_sdk("", &err))
+    }
+
+    pub fn from_logs_sdk<E: ProvideErrorMetadata>(err: SdkError<E, HttpResponse>) -> Self {
+        GlueMvpError::LogsSdk(ScenarioError::from_sdk("", &err))
     }
 
     pub fn job_fail(err: impl Into<String>) -> Self {
         GlueMvpError::JobFail(err.into())
     }
 }
+
+impl From<GlueMvpError> for ScenarioError {
+    fn from(err: GlueMvpError) -> Self {
+        match err {
+            GlueMvpError::GlueSdk(sdk) => sdk.context("Glue SDK Error"),
+            GlueMvpError::S3Sdk(sdk) => sdk.context("S3 SDK Error"),
+            GlueMvpError::LogsSdk(sdk) => sdk.context("CloudWatch Logs SDK Error"),
+            _ => ScenarioError::with(err.to_string()),
+        }
+    }
+}
@@ -0,0 +1,240 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use tracing::{info, instrument};
+
+use crate::{
+    clients::{GLUE_CLIENT, LOGS_CLIENT},
+    jobs::JobRunOutcome,
+    GlueMvpError, GlueScenario,
+};
+
+/// Glue writes job logs under this prefix, unless the job names another log group.
+pub const DEFAULT_LOG_GROUP: &str = "/aws-glue/jobs";
+
+/// The job argument that names another log group.
+pub const LOG_GROUP_ARGUMENT: &str = "--continuous-log-logGroup";
+
+/// The lines a job run wrote to CloudWatch Logs. The driver writes to a stream named for the run
+/// ID, and each executor to a stream named for the run ID and the executor.
+#[derive(Clone, Debug, Default)]
+pub struct JobRunLogs {
+    pub run_id: String,
+    pub driver: Vec<String>,
+    pub executors: BTreeMap<String, Vec<String>>,
+}
+
+impl JobRunLogs {
+    pub fn is_empty(&self) -> bool {
+        self.driver.is_empty() && self.executors.is_empty()
+    }
+}
+
+impl Display for JobRunLogs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Logs for {}:", self.run_id)?;
+        if self.is_empty() {
+            writeln!(f, "  (no log events found)")?;
+        }
+        if !self.driver.is_empty() {
+            writeln!(f, "  driver:")?;
+            for line in &self.driver {
+                writeln!(f, "    {line}")?;
+            }
+        }
+        for (executor, lines) in &self.executors {
+            writeln!(f, "  executor {executor}:")?;
+            for line in lines {
+                writeln!(f, "    {line}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl GlueScenario {
+    /// Read up to `max_events` lines from each of the run's error and output logs.
+    /// `log_group` is the job's log group, when it isn't the default.
+    #[instrument(skip(self))]
+    pub async fn job_run_logs(
+        &self,
+        run_id: &str,
+        log_group: Option<&str>,
+        max_events: usize,
+    ) -> Result<JobRunLogs, GlueMvpError> {
+        job_run_logs(LOGS_CLIENT.get().await, run_id, log_group, max_events).await
+    }
+
+    /// Logs for each run that didn't succeed, from the log group the run or its job names.
+    pub async fn failed_run_logs<'a>(
+        &self,
+        runs: impl IntoIterator<Item = &'a JobRunOutcome>,
+        max_events: usize,
+    ) -> Result<Vec<JobRunLogs>, GlueMvpError> {
+        failed_run_logs(
+            GLUE_CLIENT.get().await,
+            LOGS_CLIENT.get().await,
+            runs,
+            max_events,
+        )
+        .await
+    }
+}
+
+// snippet-start:[rust.glue.job_run_logs]
+async fn job_run_logs(
+    logs: &aws_sdk_cloudwatchlogs::Client,
+    run_id: &str,
+    log_group: Option<&str>,
+    max_events: usize,
+) -> Result<JobRunLogs, GlueMvpError> {
+    let prefix = log_group.unwrap_or(DEFAULT_LOG_GROUP);
+    let mut run_logs = JobRunLogs {
+        run_id: run_id.to_string(),
+        ..Default::default()
+    };
+
+    // With continuous logging to a named group, the run's streams are in the group itself.
+    let mut groups = vec![format!("{prefix}/error"), format!("{prefix}/output")];
+    if log_group.is_some() {
+        groups.push(prefix.to_string());
+    }
+
+    for group in groups {
+        let mut pages = logs
+            .filter_log_events()
+            .log_group_name(group.as_str())
+            .log_stream_name_prefix(run_id)
+            .into_paginator()
+            .send();
+        let mut read = 0;
+        while read < max_events {
+            let events = match pages.next().await {
+                Some(Ok(events)) => events,
+                // A run that never started its driver has no log groups yet.
+                Some(Err(err))
+                    if err
+                        .as_service_error()
+                        .map(|e| e.is_resource_not_found_exception())
+                        == Some(true) =>
+                {
+                    info!(%group, "No log group");
+                    break;
+                }
+                Some(Err(err)) => return Err(GlueMvpError::from_logs_sdk(err)),
+                None => break,
+            };
+
+            for event in events.events().iter().take(max_events - read) {
+                read += 1;
+                let stream = event.log_stream_name().unwrap_or_default();
+                let line = event.message().unwrap_or_default().trim_end().to_string();
+                match stream.strip_prefix(run_id) {
+                    Some("") => run_logs.driver.push(line),
+                    Some(executor) => run_logs
+                        .executors
+                        .entry(executor.trim_start_matches('_').to_string())
+                        .or_default()
+                        .push(line),
+                    None => {}
+                }
+            }
+        }
+    }
+
+    Ok(run_logs)
+}
+// snippet-end:[rust.glue.job_run_logs]
+
+/// The log group a job sends its runs' logs to, when it isn't the default.
+async fn job_log_group(
+    glue: &aws_sdk_glue::Client,
+    job: &str,
+) -> Result<Option<String>, GlueMvpError> {
+    let output = glue
+        .get_job()
+        .job_name(job)
+        .send()
+        .await
+        .map_err(GlueMvpError::from_glue_sdk)?;
+    Ok(output
+        .job()
+        .and_then(|job| job.default_arguments())
+        .and_then(|arguments| arguments.get(LOG_GROUP_ARGUMENT))
+        .cloned())
+}
+
+async fn failed_run_logs<'a>(
+    glue: &aws_sdk_glue::Client,
+    logs: &aws_sdk_cloudwatchlogs::Client,
+    runs: impl IntoIterator<Item = &'a JobRunOutcome>,
+    max_events: usize,
+) -> Result<Vec<JobRunLogs>, GlueMvpError> {
+    let mut job_log_groups: BTreeMap<&str, Option<String>> = BTreeMap::new();
+    let mut run_logs = vec![];
+    for run in runs.into_iter().filter(|run| !run.succeeded()) {
+        let log_group = match &run.log_group {
+            Some(log_group) => Some(log_group.clone()),
+            None => match job_log_groups.get(run.job.as_str()) {
+                Some(log_group) => log_group.clone(),
+                None => {
+                    let log_group = job_log_group(glue, &run.job).await?;
+                    job_log_groups.insert(&run.job, log_group.clone());
+                    log_group
+                }
+            },
+        };
+        run_logs.push(job_run_logs(logs, &run.run_id, log_group.as_deref(), max_events).await?);
+    }
+    Ok(run_logs)
+}
+
+#[cfg(test)]
+mod test {
+    use aws_smithy_runtime::client::http::test_util::StaticReplayClient;
+    use sdk_examples_test_utils::{client_config, test_event};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_job_run_logs_follows_pages() {
+        let logs = aws_sdk_cloudwatchlogs::Client::from_conf(
+            client_config!(aws_sdk_cloudwatchlogs)
+                .http_client(StaticReplayClient::new(vec![
+                    test_event!(
+                        r#""#,
+                        (
+                            200,
+                            r#"{"events": [{"logStreamName": "jr_1", "message": "driver line\n"}],
+                            "nextToken": "page-2"}"#
+                        )
+                    ),
+                    test_event!(
+                        r#""#,
+                        (
+                            200,
+                            r#"{"events": [{"logStreamName": "jr_1_executor-2", "message": "executor line"}]}"#
+                        )
+                    ),
+                    test_event!(
+                        r#""#,
+                        (
+                            400,
+                            r#"{"__type": "ResourceNotFoundException", "message": "The specified log group does not exist."}"#
+                        )
+                    ),
+                ]))
+                .build(),
+        );
+
+        let run_logs = job_run_logs(&logs, "jr_1", None, 10).await.unwrap();
+
+        assert_eq!(run_logs.driver, vec!["driver line"]);
+        assert_eq!(
+            run_logs.executors,
+            BTreeMap::from([("executor-2".to_string(), vec!["executor line".to_string()])])
+        );
+    }
+}
@@ -9,6 +9,7 @@ use aws_sdk_glue::types::{
     Crawler, CrawlerState, CrawlerTargets, DatabaseInput, Job, JobCommand, S3Target, Table,
 };
 use aws_sdk_s3::primitives::ByteStream;
+use sdk_examples_resource_ledger::{Resource, ResourceKind};
 use secrecy::ExposeSecret;
 use std::future::Future;
 use tracing::{info, instrument, warn};
@@ -37,14 +38,15 @@ impl GlueScenario {
             .await;
 
         if let Err(sdk_err) = create_database {
-            let service_error = sdk_err.into_service_error();
-            match service_error {
-                err if err.is_already_exists_exception() => {
+            match sdk_err.as_service_error() {
+                Some(err) if err.is_already_exists_exception() => {
                     info!("Found existing database");
-                    Ok(())
                 }
-                _ => Err(GlueMvpError::from_glue_sdk(service_error)),
-            }?;
+                _ => return Err(GlueMvpError::from_glue_sdk(sdk_err)),
+            }
+        } else {
+            self.ledger
+                .created(Resource::new(ResourceKind::GlueDatabase, self.database()));
         }
 
         // snippet-start:[rust.glue.create_crawler]
@@ -62,17 +64,23 @@ impl GlueScenario {
             .await;
 
         match create_crawler {
-            Err(err) => {
-                let glue_err: aws_sdk_glue::Error = err.into();
-                match glue_err {
-                    aws_sdk_glue::Error::AlreadyExistsException(_) => {
-                        info!("Using existing crawler");
-                        Ok(())
-                    }
-                    _ => Err(GlueMvpError::GlueSdk(glue_err)),
-                }
+            Err(err)
+                if err
+                    .as_service_error()
+                    .map(|e| e.is_already_exists_exception())
+                    == Some(true) =>
+            {
+                info!("Using existing crawler");
+                Ok(())
+            }
+            Err(err) => Err(GlueMvpError::from_glue_sdk(err)),
+            Ok(_) => {
+                self.ledger.created(
+                    Resource::new(ResourceKind::GlueCrawler, self.crawler())
+                        .depends_on(ResourceKind::GlueDatabase, self.database()),
+                );
+                Ok(())
             }
-            Ok(_) => Ok(()),
         }?;
         // snippet-end:[rust.glue.create_crawler]
 
@@ -81,13 +89,15 @@ impl GlueScenario {
 
         match start_crawler {
             Ok(_) => Ok(()),
-            Err(err) => {
-                let glue_err: aws_sdk_glue::Error = err.into();
-                match glue_err {
-                    aws_sdk_glue::Error::CrawlerRunningException(_) => Ok(()),
-                    _ => Err(GlueMvpError::GlueSdk(glue_err)),
-                }
+            Err(err)
+                if err
+                    .as_service_error()
+                    .map(|e| e.is_crawler_running_exception())
+                    == Some(true) =>
+            {
+                Ok(())
             }
+            Err(err) => Err(GlueMvpError::from_glue_sdk(err)),
         }?;
         // snippet-end:[rust.glue.start_crawler]
 
@@ -237,7 +247,7 @@ impl GlueScenario {
             .await;
 
         if let Err(err) = put_object {
-            return Err(GlueMvpError::S3Sdk(err.into()));
+            return Err(GlueMvpError::from_s3_sdk(err));
         }
 
         Ok(())
@@ -271,6 +281,8 @@ impl GlueScenario {
             GlueMvpError::Unknown("Did not get job name after creating job".into())
         })?;
         // snippet-end:[rust.glue.create_job]
+        self.ledger
+            .created(Resource::new(ResourceKind::GlueJob, job_name));
 
         let get_job = glue
             .get_job()
@@ -1,6 +1,8 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0.
 
+use std::time::Duration;
+
 use aws_sdk_glue::types::{JobRun, JobRunState};
 use tracing::{info, instrument, warn};
 
@@ -63,41 +65,24 @@ impl GlueScenario {
     }
 
     #[instrument(skip(self))]
-    pub async fn wait_for_job_run(&self, job_run_id: &str) -> Result<(), GlueMvpError> {
-        let glue = GLUE_CLIENT.get().await;
-        let unknown_state = aws_sdk_glue::types::JobRunState::from("unknown");
-
-        // snippet-start:[rust.glue.get_job_run]
-        let get_job_run = || async {
-            Ok::<JobRun, GlueMvpError>(
-                glue.get_job_run()
-                    .job_name(self.job())
-                    .run_id(job_run_id.to_string())
-                    .send()
-                    .await
-                    .map_err(GlueMvpError::from_glue_sdk)?
-                    .job_run()
-                    .ok_or_else(|| GlueMvpError::Unknown("Failed to get job_run".into()))?
-                    .to_owned(),
-            )
-        };
-
-        let mut job_run = get_job_run().await?;
-        let mut state = job_run.job_run_state().unwrap_or(&unknown_state).to_owned();
-
-        while matches!(
-            state,
-            JobRunState::Starting | JobRunState::Stopping | JobRunState::Running
-        ) {
-            info!(?state, "Waiting for job to finish");
-            tokio::time::sleep(self.wait_delay).await;
-
-            job_run = get_job_run().await?;
-            state = job_run.job_run_state().unwrap_or(&unknown_state).to_owned();
-        }
-        // snippet-end:[rust.glue.get_job_run]
+    pub async fn wait_for_job_run(&self, job_run_id: &str) -> Result<JobRun, GlueMvpError> {
+        self.wait_for_run(self.job(), job_run_id).await
+    }
 
-        Ok(())
+    /// Poll a run of any job until it stops, and return the finished run.
+    #[instrument(skip(self))]
+    pub async fn wait_for_run(
+        &self,
+        job_name: &str,
+        job_run_id: &str,
+    ) -> Result<JobRun, GlueMvpError> {
+        wait_for_run(
+            GLUE_CLIENT.get().await,
+            self.wait_delay,
+            job_name,
+            job_run_id,
+        )
+        .await
     }
 
     // Loop and get the job run until it returns state 'SUCCEEDED', 'STOPPED', 'FAILED', or 'TIMEOUT'.
@@ -130,3 +115,44 @@ impl GlueScenario {
         Ok(())
     }
 }
+
+pub(crate) async fn wait_for_run(
+    glue: &aws_sdk_glue::Client,
+    wait_delay: Duration,
+    job_name: &str,
+    job_run_id: &str,
+) -> Result<JobRun, GlueMvpError> {
+    let unknown_state = aws_sdk_glue::types::JobRunState::from("unknown");
+
+    // snippet-start:[rust.glue.get_job_run]
+    let get_job_run = || async {
+        Ok::<JobRun, GlueMvpError>(
+            glue.get_job_run()
+                .job_name(job_name)
+                .run_id(job_run_id.to_string())
+                .send()
+                .await
+                .map_err(GlueMvpError::from_glue_sdk)?
+                .job_run()
+                .ok_or_else(|| GlueMvpError::Unknown("Failed to get job_run".into()))?
+                .to_owned(),
+        )
+    };
+
+    let mut job_run = get_job_run().await?;
+    let mut state = job_run.job_run_state().unwrap_or(&unknown_state).to_owned();
+
+    while matches!(
+        state,
+        JobRunState::Starting | JobRunState::Stopping | JobRunState::Running
+    ) {
+        info!(?state, job_name, "Waiting for job to finish");
+        tokio::time::sleep(wait_delay).await;
+
+        job_run = get_job_run().await?;
+        state = job_run.job_run_state().unwrap_or(&unknown_state).to_owned();
+    }
+    // snippet-end:[rust.glue.get_job_run]
+
+    Ok(job_run)
+}
@@ -0,0 +1,516 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+};
+
+use aws_sdk_glue::types::{
+    Action, Condition, JobCommand, JobRunState, Logical, LogicalOperator, Predicate, TriggerType,
+    WorkflowRun, WorkflowRunStatus,
+};
+use sdk_examples_resource_ledger::{Resource, ResourceKind};
+use secrecy::ExposeSecret;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    clients::GLUE_CLIENT,
+    jobs::{Bookmarks, JobRunOutcome, BOOKMARK_ARGUMENT},
+    GlueMvpError, GlueScenario,
+};
+
+/// A job that runs after another job reaches a state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dependency {
+    pub job: String,
+    pub state: JobRunState,
+}
+
+impl Dependency {
+    pub fn succeeded(job: impl Into<String>) -> Self {
+        Dependency {
+            job: job.into(),
+            state: JobRunState::Succeeded,
+        }
+    }
+
+    pub fn failed(job: impl Into<String>) -> Self {
+        Dependency {
+            job: job.into(),
+            state: JobRunState::Failed,
+        }
+    }
+}
+
+/// One job in a workflow. Jobs without dependencies start when the workflow starts.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WorkflowJob {
+    pub name: String,
+    pub script_location: String,
+    /// Default arguments for every run of the job.
+    pub arguments: BTreeMap<String, String>,
+    pub bookmarks: Bookmarks,
+    pub depends_on: Vec<Dependency>,
+    /// Start when any dependency is met, instead of when all of them are.
+    pub any: bool,
+}
+
+impl WorkflowJob {
+    pub fn new(name: impl Into<String>, script_location: impl Into<String>) -> Self {
+        WorkflowJob {
+            name: name.into(),
+            script_location: script_location.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn argument(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.arguments.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn bookmarks(mut self, bookmarks: Bookmarks) -> Self {
+        self.bookmarks = bookmarks;
+        self
+    }
+
+    pub fn after(mut self, dependency: Dependency) -> Self {
+        self.depends_on.push(dependency);
+        self
+    }
+
+    pub fn when_any(mut self) -> Self {
+        self.any = true;
+        self
+    }
+}
+
+/// Jobs, and the order they run in, as a Glue workflow with a trigger per dependent job.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WorkflowSpec {
+    pub name: String,
+    pub jobs: Vec<WorkflowJob>,
+}
+
+impl WorkflowSpec {
+    pub fn new(name: impl Into<String>) -> Self {
+        WorkflowSpec {
+            name: name.into(),
+            jobs: vec![],
+        }
+    }
+
+    pub fn job(mut self, job: WorkflowJob) -> Self {
+        self.jobs.push(job);
+        self
+    }
+
+    pub fn start_trigger(&self) -> String {
+        format!("{}_start", self.name)
+    }
+
+    pub fn job_trigger(&self, job: &str) -> String {
+        format!("{}_{job}_trigger", self.name)
+    }
+
+    /// Check the jobs form a graph Glue can run, and return them in an order where every job
+    /// comes after the jobs it depends on.
+    pub fn validate(&self) -> Result<Vec<&WorkflowJob>, GlueMvpError> {
+        let mut names = HashSet::new();
+        for job in &self.jobs {
+            if !names.insert(job.name.as_str()) {
+                return Err(GlueMvpError::Unknown(format!(
+                    "Workflow {} has more than one job named {}",
+                    self.name, job.name
+                )));
+            }
+        }
+        for job in &self.jobs {
+            if let Some(missing) = job
+                .depends_on
+                .iter()
+                .find(|d| !names.contains(d.job.as_str()))
+            {
+                return Err(GlueMvpError::Unknown(format!(
+                    "Job {} depends on {}, which isn't in workflow {}",
+                    job.name, missing.job, self.name
+                )));
+            }
+        }
+
+        let mut ordered: Vec<&WorkflowJob> = Vec::with_capacity(self.jobs.len());
+        let mut placed: HashSet<&str> = HashSet::new();
+        while ordered.len() < self.jobs.len() {
+            let ready: Vec<&WorkflowJob> = self
+                .jobs
+                .iter()
+                .filter(|job| !placed.contains(job.name.as_str()))
+                .filter(|job| {
+                    job.depends_on
+                        .iter()
+                        .all(|d| placed.contains(d.job.as_str()))
+                })
+                .collect();
+            if ready.is_empty() {
+                let stuck = self
+                    .jobs
+                    .iter()
+                    .filter(|job| !placed.contains(job.name.as_str()))
+                    .map(|job| job.name.as_str())
+                    .collect::<Vec<_>>();
+                return Err(GlueMvpError::Unknown(format!(
+                    "Workflow {} has a dependency cycle among {}",
+                    self.name,
+                    stuck.join(", ")
+                )));
+            }
+            for job in ready {
+                placed.insert(job.name.as_str());
+                ordered.push(job);
+            }
+        }
+
+        if !self.jobs.iter().any(|job| job.depends_on.is_empty()) {
+            return Err(GlueMvpError::Unknown(format!(
+                "Workflow {} has no job to start with",
+                self.name
+            )));
+        }
+        Ok(ordered)
+    }
+}
+
+/// How a workflow run ended, and how each of its job runs ended.
+#[derive(Clone, Debug)]
+pub struct WorkflowRunSummary {
+    pub workflow: String,
+    pub run_id: String,
+    pub status: Option<WorkflowRunStatus>,
+    pub error_message: Option<String>,
+    pub jobs: Vec<JobRunOutcome>,
+}
+
+impl WorkflowRunSummary {
+    fn from_run(workflow: &str, run: &WorkflowRun) -> Self {
+        let jobs = run
+            .graph()
+            .map(|graph| graph.nodes())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|node| Some((node.name()?, node.job_details()?)))
+            .flat_map(|(name, details)| {
+                details.job_runs().iter().map(move |job_run| {
+                    JobRunOutcome::from_run(name, job_run.id().unwrap_or_default(), job_run)
+                })
+            })
+            .collect();
+
+        WorkflowRunSummary {
+            workflow: workflow.to_string(),
+            run_id: run.workflow_run_id().unwrap_or_default().to_string(),
+            status: run.status().cloned(),
+            error_message: run.error_message().map(String::from),
+            jobs,
+        }
+    }
+
+    /// Whether the run completed, and every job that ran succeeded.
+    pub fn is_success(&self) -> bool {
+        self.status == Some(WorkflowRunStatus::Completed)
+            && self.jobs.iter().all(JobRunOutcome::succeeded)
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &JobRunOutcome> {
+        self.jobs.iter().filter(|job| !job.succeeded())
+    }
+}
+
+impl Display for WorkflowRunSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} {}: {}",
+            self.workflow,
+            self.run_id,
+            self.status
+                .as_ref()
+                .map(|s| s.as_str())
+                .unwrap_or("UNKNOWN")
+        )?;
+        if let Some(message) = &self.error_message {
+            writeln!(f, "  {message}")?;
+        }
+        for job in &self.jobs {
+            writeln!(f, "  {job}")?;
+        }
+        Ok(())
+    }
+}
+
+impl GlueScenario {
+    // snippet-start:[rust.glue.create_workflow]
+    /// Create the workflow, its jobs, and the triggers that start each job after its
+    /// dependencies.
+    #[instrument(skip(self, spec), fields(workflow = %spec.name))]
+    pub async fn create_workflow(&self, spec: &WorkflowSpec) -> Result<(), GlueMvpError> {
+        let glue = GLUE_CLIENT.get().await;
+        let jobs = spec.validate()?;
+
+        glue.create_workflow()
+            .name(spec.name.as_str())
+            .description("Created by the SDK for Rust Glue workflow example")
+            .send()
+            .await
+            .map_err(GlueMvpError::from_glue_sdk)?;
+        self.ledger.created(Resource::new(
+            ResourceKind::GlueWorkflow,
+            spec.name.as_str(),
+        ));
+
+        for job in &jobs {
+            let mut arguments = job.arguments.clone();
+            arguments.insert(
+                BOOKMARK_ARGUMENT.to_string(),
+                job.bookmarks.as_argument().to_string(),
+            );
+            glue.create_job()
+                .name(job.name.as_str())
+                .role(self.iam_role.expose_secret())
+                .command(
+                    JobCommand::builder()
+                        .name("glueetl")
+                        .python_version("3")
+                        .script_location(job.script_location.as_str())
+                        .build(),
+                )
+                .glue_version("3.0")
+                .set_default_arguments(Some(arguments.into_iter().collect()))
+                .send()
+                .await
+                .map_err(GlueMvpError::from_glue_sdk)?;
+            self.ledger
+                .created(Resource::new(ResourceKind::GlueJob, job.name.as_str()));
+        }
+
+        // The workflow starts every job that doesn't wait on another.
+        let start_actions = jobs
+            .iter()
+            .filter(|job| job.depends_on.is_empty())
+            .map(|job| Action::builder().job_name(job.name.as_str()).build())
+            .collect::<Vec<_>>();
+        glue.create_trigger()
+            .name(spec.start_trigger())
+            .workflow_name(spec.name.as_str())
+            .r#type(TriggerType::OnDemand)
+            .set_actions(Some(start_actions))
+            .send()
+            .await
+            .map_err(GlueMvpError::from_glue_sdk)?;
+        self.record_trigger(spec, &spec.start_trigger(), &jobs);
+
+        for job in jobs.iter().filter(|job| !job.depends_on.is_empty()) {
+            let conditions = job
+                .depends_on
+                .iter()
+                .map(|dependency| {
+                    Condition::builder()
+                        .logical_operator(LogicalOperator::Equals)
+                        .job_name(dependency.job.as_str())
+                        .state(dependency.state.clone())
+                        .build()
+                })
+                .collect::<Vec<_>>();
+            let trigger = spec.job_trigger(&job.name);
+            glue.create_trigger()
+                .name(trigger.as_str())
+                .workflow_name(spec.name.as_str())
+                .r#type(TriggerType::Conditional)
+                .predicate(
+                    Predicate::builder()
+                        .logical(if job.any { Logical::Any } else { Logical::And })
+                        .set_conditions(Some(conditions))
+                        .build(),
+                )
+                .actions(Action::builder().job_name(job.name.as_str()).build())
+                .start_on_creation(true)
+                .send()
+                .await
+                .map_err(GlueMvpError::from_glue_sdk)?;
+            self.record_trigger(spec, &trigger, &jobs);
+        }
+
+        info!(jobs = jobs.len(), "Workflow ready");
+        Ok(())
+    }
+    // snippet-end:[rust.glue.create_workflow]
+
+    fn record_trigger(&self, spec: &WorkflowSpec, trigger: &str, jobs: &[&WorkflowJob]) {
+        let resource = jobs.iter().fold(
+            Resource::new(ResourceKind::GlueTrigger, trigger)
+                .depends_on(ResourceKind::GlueWorkflow, spec.name.as_str()),
+            |resource, job| resource.depends_on(ResourceKind::GlueJob, job.name.as_str()),
+        );
+        self.ledger.created(resource);
+    }
+
+    // snippet-start:[rust.glue.start_workflow_run]
+    #[instrument(skip(self))]
+    pub async fn start_workflow(&self, workflow: &str) -> Result<String, GlueMvpError> {
+        let glue = GLUE_CLIENT.get().await;
+
+        glue.start_workflow_run()
+            .name(workflow)
+            .send()
+            .await
+            .map_err(GlueMvpError::from_glue_sdk)?
+            .run_id
+            .ok_or_else(|| GlueMvpError::Unknown("Missing run id from started workflow".into()))
+    }
+    // snippet-end:[rust.glue.start_workflow_run]
+
+    // snippet-start:[rust.glue.get_workflow_run]
+    /// Poll the workflow run until it stops, and summarize how each of its jobs ran.
+    #[instrument(skip(self))]
+    pub async fn wait_for_workflow_run(
+        &self,
+        workflow: &str,
+        run_id: &str,
+    ) -> Result<WorkflowRunSummary, GlueMvpError> {
+        let glue = GLUE_CLIENT.get().await;
+
+        loop {
+            let run = glue
+                .get_workflow_run()
+                .name(workflow)
+                .run_id(run_id)
+                .include_graph(true)
+                .send()
+                .await
+                .map_err(GlueMvpError::from_glue_sdk)?
+                .run
+                .ok_or_else(|| GlueMvpError::Unknown("Failed to get workflow run".into()))?;
+
+            match run.status() {
+                Some(WorkflowRunStatus::Running | WorkflowRunStatus::Stopping) => {
+                    let statistics = run.statistics();
+                    info!(
+                        running = statistics.map(|s| s.running_actions()),
+                        succeeded = statistics.map(|s| s.succeeded_actions()),
+                        failed = statistics.map(|s| s.failed_actions()),
+                        "Waiting for workflow to finish"
+                    );
+                    tokio::time::sleep(self.wait_delay).await;
+                }
+                _ => return Ok(WorkflowRunSummary::from_run(workflow, &run)),
+            }
+        }
+    }
+    // snippet-end:[rust.glue.get_workflow_run]
+
+    /// Delete the workflow's triggers, the workflow, and its jobs, continuing past failures.
+    #[instrument(skip(self, spec), fields(workflow = %spec.name))]
+    pub async fn delete_workflow(&self, spec: &WorkflowSpec) -> Result<(), GlueMvpError> {
+        let glue = GLUE_CLIENT.get().await;
+        let mut failed = vec![];
+
+        let triggers = std::iter::once(spec.start_trigger())
+            .chain(
+                spec.jobs
+                    .iter()
+                    .filter(|job| !job.depends_on.is_empty())
+                    .map(|job| spec.job_trigger(&job.name)),
+            )
+            .collect::<Vec<_>>();
+        for trigger in triggers {
+            match glue.delete_trigger().name(trigger.as_str()).send().await {
+                Ok(_) => self.ledger.deleted(ResourceKind::GlueTrigger, trigger),
+                Err(err) => {
+                    warn!(?err, %trigger, "Failed to delete trigger");
+                    failed.push(trigger);
+                }
+            }
+        }
+
+        match glue.delete_workflow().name(spec.name.as_str()).send().await {
+            Ok(_) => self
+                .ledger
+                .deleted(ResourceKind::GlueWorkflow, spec.name.as_str()),
+            Err(err) => {
+                warn!(?err, "Failed to delete workflow");
+                failed.push(spec.name.clone());
+            }
+        }
+
+        for job in &spec.jobs {
+            match glue.delete_job().job_name(job.name.as_str()).send().await {
+                Ok(_) => self
+                    .ledger
+                    .deleted(ResourceKind::GlueJob, job.name.as_str()),
+                Err(err) => {
+                    warn!(?err, job = %job.name, "Failed to delete job");
+                    failed.push(job.name.clone());
+                }
+            }
+        }
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(GlueMvpError::Cleanup(failed.join(", ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn job(name: &str) -> WorkflowJob {
+        WorkflowJob::new(name, "s3://bucket/job.py")
+    }
+
+    #[test]
+    fn test_validate_orders_dependencies() {
+        let spec = WorkflowSpec::new("flow")
+            .job(job("load").after(Dependency::succeeded("transform")))
+            .job(
+                job("transform")
+                    .after(Dependency::succeeded("extract_a"))
+                    .after(Dependency::succeeded("extract_b")),
+            )
+            .job(job("extract_a"))
+            .job(job("extract_b"))
+            .job(job("alert").after(Dependency::failed("load")));
+
+        let order = spec
+            .validate()
+            .unwrap()
+            .iter()
+            .map(|job| job.name.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            order,
+            vec!["extract_a", "extract_b", "transform", "load", "alert"]
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_graphs() {
+        let cycle = WorkflowSpec::new("flow")
+            .job(job("start"))
+            .job(job("a").after(Dependency::succeeded("b")))
+            .job(job("b").after(Dependency::succeeded("a")));
+        assert!(matches!(
+            cycle.validate(),
+            Err(GlueMvpError::Unknown(message)) if message.contains("cycle among a, b")
+        ));
+
+        let missing = WorkflowSpec::new("flow").job(job("a").after(Dependency::succeeded("z")));
+        assert!(missing.validate().is_err());
+
+        let duplicate = WorkflowSpec::new("flow").job(job("a")).job(job("a"));
+        assert!(duplicate.validate().is_err());
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::packaging::{LambdaPackage, TrafficShift};
 use anyhow::anyhow;
 use aws_sdk_iam::operation::{create_role::CreateRoleError, delete_role::DeleteRoleOutput};
 use aws_sdk_lambda::{
@@ -11,7 +12,10 @@ use aws_sdk_lambda::{
         update_function_configuration::UpdateFunctionConfigurationOutput,
     },
     primitives::ByteStream,
-    types::{Environment, FunctionCode, LastUpdateStatus, State},
+    types::{
+        AliasConfiguration, AliasRoutingConfiguration, Environment, FunctionCode, LastUpdateStatus,
+        Runtime, State,
+    },
 };
 use aws_sdk_s3::{
     error::ErrorMetadata,
@@ -103,6 +107,9 @@ const ROLE_POLICY_DOCUMENT: &str = r#"{
     ]
 }"#;
 
+/** How long to wait for a function to become ready before giving up. */
+const READY_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
 /**
  * A LambdaManager gathers all the resources necessary to run the Lambda example scenario.
  * This includes instantiated aws_sdk clients and details of resource names.
@@ -268,6 +275,215 @@ impl LambdaManager {
     }
     // snippet-end:[lambda.rust.scenario.create_function]
 
+    /**
+     * Upload a packaged function to this Manager's bucket.
+     */
+    async fn prepare_package(
+        &self,
+        package: &LambdaPackage,
+        key: Option<String>,
+    ) -> Result<FunctionCode, anyhow::Error> {
+        let key = key.unwrap_or_else(|| format!("{}_code", self.lambda_name));
+
+        info!(
+            code_sha256 = package.code_sha256(),
+            "Uploading function package to s3://{}/{}", self.bucket, key
+        );
+        let _ = self
+            .s3_client
+            .put_object()
+            .bucket(self.bucket.clone())
+            .key(key.clone())
+            .body(ByteStream::from(package.zip().to_vec()))
+            .send()
+            .await?;
+
+        Ok(FunctionCode::builder()
+            .s3_bucket(self.bucket.clone())
+            .s3_key(key)
+            .build())
+    }
+
+    /**
+     * Create a function on the `provided.al2023` runtime from a package built by `packaging::CargoBinary`.
+     */
+    pub async fn create_function_from_package(
+        &self,
+        package: &LambdaPackage,
+    ) -> Result<String, anyhow::Error> {
+        let code = self.prepare_package(package, None).await?;
+
+        let key = code.s3_key().unwrap().to_string();
+
+        let role = self.create_role().await.map_err(|e| anyhow!(e))?;
+
+        info!("Created iam role, waiting 15s for it to become active");
+        tokio::time::sleep(Duration::from_secs(15)).await;
+
+        info!(
+            architecture = %package.architecture(),
+            "Creating lambda function {}", self.lambda_name
+        );
+        let _ = self
+            .lambda_client
+            .create_function()
+            .function_name(self.lambda_name.clone())
+            .code(code)
+            .role(role.arn())
+            .runtime(Runtime::Providedal2023)
+            .architectures(package.architecture().into())
+            .handler("_unused")
+            .send()
+            .await
+            .map_err(anyhow::Error::from)?;
+
+        self.wait_for_function_ready().await?;
+
+        Ok(key)
+    }
+
+    /**
+     * Update the function's code to a package, unless the function is already running that exact package.
+     * Returns None when the update was skipped.
+     */
+    pub async fn deploy_package(
+        &self,
+        package: &LambdaPackage,
+        key: String,
+    ) -> Result<Option<UpdateFunctionCodeOutput>, anyhow::Error> {
+        let current = self.get_function().await?;
+        let current_sha256 = current
+            .configuration()
+            .and_then(|config| config.code_sha256());
+        if current_sha256 == Some(package.code_sha256()) {
+            info!(
+                code_sha256 = package.code_sha256(),
+                "Function code unchanged, skipping update"
+            );
+            return Ok(None);
+        }
+
+        let function_code = self.prepare_package(package, Some(key)).await?;
+
+        info!("Updating code for {}", self.lambda_name);
+        let update = self
+            .lambda_client
+            .update_function_code()
+            .function_name(self.lambda_name.clone())
+            .s3_bucket(self.bucket.clone())
+            .s3_key(function_code.s3_key().unwrap().to_string())
+            .architectures(package.architecture().into())
+            .send()
+            .await
+            .map_err(anyhow::Error::from)?;
+
+        info!("Waiting for function to run the new code");
+        self.wait_until_ready(Some(package.code_sha256())).await?;
+
+        Ok(Some(update))
+    }
+
+    /**
+     * Publish the function's current code and configuration as a new version, returning the version number.
+     * When `code_sha256` is given, Lambda refuses to publish if the function's code has changed since.
+     */
+    pub async fn publish_version(
+        &self,
+        description: Option<String>,
+        code_sha256: Option<&str>,
+    ) -> Result<String, anyhow::Error> {
+        info!("Publishing version of {}", self.lambda_name);
+        let published = self
+            .lambda_client
+            .publish_version()
+            .function_name(self.lambda_name.clone())
+            .set_description(description)
+            .set_code_sha256(code_sha256.map(str::to_string))
+            .send()
+            .await
+            .map_err(anyhow::Error::from)?;
+
+        published
+            .version
+            .ok_or_else(|| anyhow!("PublishVersion returned no version"))
+    }
+
+    /**
+     * Point an alias at a version, creating the alias if it does not exist.
+     * With a TrafficShift, the alias sends that share of invocations to the additional version instead.
+     */
+    pub async fn deploy_alias(
+        &self,
+        alias: &str,
+        version: &str,
+        shift: Option<TrafficShift>,
+    ) -> Result<AliasConfiguration, anyhow::Error> {
+        let routing = shift.map(|shift| {
+            AliasRoutingConfiguration::builder()
+                .set_additional_version_weights(Some(shift.additional_version_weights()))
+                .build()
+        });
+
+        let existing = self
+            .lambda_client
+            .get_alias()
+            .function_name(self.lambda_name.clone())
+            .name(alias)
+            .send()
+            .await;
+
+        let alias_configuration = match existing {
+            Ok(_) => {
+                info!(alias, version, ?routing, "Updating alias");
+                let updated = self
+                    .lambda_client
+                    .update_alias()
+                    .function_name(self.lambda_name.clone())
+                    .name(alias)
+                    .function_version(version)
+                    // An empty routing configuration clears any previous traffic shift.
+                    .routing_config(
+                        routing.unwrap_or_else(|| AliasRoutingConfiguration::builder().build()),
+                    )
+                    .send()
+                    .await
+                    .map_err(anyhow::Error::from)?;
+                AliasConfiguration::builder()
+                    .set_alias_arn(updated.alias_arn)
+                    .set_name(updated.name)
+                    .set_function_version(updated.function_version)
+                    .set_routing_config(updated.routing_config)
+                    .build()
+            }
+            Err(err)
+                if err
+                    .as_service_error()
+                    .is_some_and(|e| e.is_resource_not_found_exception()) =>
+            {
+                info!(alias, version, ?routing, "Creating alias");
+                let created = self
+                    .lambda_client
+                    .create_alias()
+                    .function_name(self.lambda_name.clone())
+                    .name(alias)
+                    .function_version(version)
+                    .set_routing_config(routing)
+                    .send()
+                    .await
+                    .map_err(anyhow::Error::from)?;
+                AliasConfiguration::builder()
+                    .set_alias_arn(created.alias_arn)
+                    .set_name(created.name)
+                    .set_function_version(created.function_version)
+                    .set_routing_config(created.routing_config)
+                    .build()
+            }
+            Err(err) => return Err(anyhow::Error::from(err)),
+        };
+
+        Ok(alias_configuration)
+    }
+
     /**
      * Create an IAM execution role for the managed Lambda function.
      * If the role already exists, use that instead.
@@ -308,11 +524,27 @@ impl LambdaManager {
     }
 
     /**
-     * Poll `is_function_ready` with a 1-second delay. It returns when the function is ready or when there's an error checking the function's state.
+     * Poll `is_function_ready` with a 1-second delay. It returns when the function is ready, when its update failed,
+     * when there's an error checking the function's state, or after READY_TIMEOUT.
      */
     pub async fn wait_for_function_ready(&self) -> Result<(), anyhow::Error> {
         info!("Waiting for function");
-        while !self.is_function_ready(None).await? {
+        self.wait_until_ready(None).await
+    }
+
+    async fn wait_until_ready(
+        &self,
+        expected_code_sha256: Option<&str>,
+    ) -> Result<(), anyhow::Error> {
+        let deadline = tokio::time::Instant::now() + READY_TIMEOUT;
+        while !self.is_function_ready(expected_code_sha256).await? {
+            if tokio::time::Instant::now() >= deadline {
+                return Err(anyhow!(
+                    "{} was not ready after {}s",
+                    self.lambda_name,
+                    READY_TIMEOUT.as_secs()
+                ));
+            }
             info!("Function is not ready, sleeping 1s");
             tokio::time::sleep(Duration::from_secs(1)).await;
         }
@@ -323,7 +555,7 @@ impl LambdaManager {
      * Check if a Lambda function is ready to be invoked.
      * A Lambda function is ready for this scenario when its state is active and its LastUpdateStatus is Successful.
      * Additionally, if a sha256 is provided, the function must have that as its current code hash.
-     * Any missing properties or failed requests will be reported as an Err.
+     * A failed update is reported as an Err with Lambda's reason, since waiting longer won't fix it.
      */
     async fn is_function_ready(
         &self,
@@ -345,9 +577,18 @@ impl LambdaManager {
                                 LastUpdateStatus::Successful => {
                                     // continue
                                 }
-                                LastUpdateStatus::Failed | LastUpdateStatus::InProgress => {
+                                LastUpdateStatus::InProgress => {
                                     return Ok(false);
                                 }
+                                LastUpdateStatus::Failed => {
+                                    return Err(anyhow!(
+                                        "Update of {} failed: {}",
+                                        self.lambda_name,
+                                        config
+                                            .last_update_status_reason()
+                                            .unwrap_or("no reason given")
+                                    ));
+                                }
                                 unknown => {
                                     warn!(
                                         status_variant = unknown.as_str(),
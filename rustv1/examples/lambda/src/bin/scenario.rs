@@ -74,9 +74,13 @@ use std::{collections::HashMap, path::PathBuf};
 use tracing::{debug, info, warn};
 use tracing_subscriber::EnvFilter;
 
-use lambda_code_examples::actions::{
-    InvokeArgs::{Arithmetic, Increment},
-    LambdaManager, Operation,
+use lambda_code_examples::{
+    actions::{
+        InvokeArgs::{Arithmetic, Increment},
+        LambdaManager, Operation,
+    },
+    local::LocalLambda,
+    packaging::{Architecture, CargoBinary, TrafficShift},
 };
 
 #[derive(Debug, Parser)]
@@ -109,6 +113,18 @@ pub struct Opt {
     #[structopt(short, long, default_value = "plus")]
     pub operation: Operation,
 
+    // Build the calculator handlers from source instead of using prebuilt zips.
+    #[structopt(long)]
+    pub build: bool,
+
+    // The architecture to build for and run on, x86_64 or arm64. Only used with --build.
+    #[structopt(long, default_value = "x86_64")]
+    pub architecture: Architecture,
+
+    // Run the handlers on this machine against a local Runtime API, without an AWS account.
+    #[structopt(long)]
+    pub local: bool,
+
     #[structopt(long)]
     pub cleanup: Option<bool>,
 
@@ -120,6 +136,52 @@ fn code_path(lambda: &str) -> PathBuf {
     PathBuf::from(format!("../target/lambda/{lambda}/bootstrap.zip"))
 }
 
+fn calculator(lambda: &str, architecture: Architecture) -> CargoBinary {
+    CargoBinary::new("../../lambda/calculator/Cargo.toml", lambda).architecture(architecture)
+}
+
+/**
+ * The --build variant of updating to the arithmetic handler.
+ * The new code is published as a version, and a `live` alias is shifted to it gradually.
+ */
+async fn deploy_arithmetic(
+    opt: &Opt,
+    manager: &LambdaManager,
+    code_location: String,
+) -> Result<(), anyhow::Error> {
+    let increment_version = manager.publish_version(None, None).await?;
+    manager
+        .deploy_alias("live", &increment_version, None)
+        .await?;
+
+    let package = calculator("arithmetic", opt.architecture).package()?;
+    let update_code = manager.deploy_package(&package, code_location).await?;
+    info!(
+        code_sha256 = package.code_sha256(),
+        updated = update_code.is_some(),
+        "Deployed arithmetic package"
+    );
+
+    let arithmetic_version = manager
+        .publish_version(None, Some(package.code_sha256()))
+        .await?;
+    let alias = manager
+        .deploy_alias(
+            "live",
+            &increment_version,
+            Some(TrafficShift::new(arithmetic_version.clone(), 0.1)?),
+        )
+        .await?;
+    info!(routing = ?alias.routing_config(), "Shifted 10% of live traffic to arithmetic");
+
+    let alias = manager
+        .deploy_alias("live", &arithmetic_version, None)
+        .await?;
+    info!(version = ?alias.function_version(), "Shifted all live traffic to arithmetic");
+
+    Ok(())
+}
+
 // snippet-start:[lambda.rust.scenario.log_invoke_output]
 fn log_invoke_output(invoke: &InvokeOutput, message: &str) {
     if let Some(payload) = invoke.payload().cloned() {
@@ -144,12 +206,16 @@ async fn main_block(
     let invoke = manager.invoke(Increment(opt.inc)).await?;
     log_invoke_output(&invoke, "Invoked function configured as increment");
 
-    let update_code = manager
-        .update_function_code(code_path("arithmetic"), code_location.clone())
-        .await?;
+    if opt.build {
+        deploy_arithmetic(opt, manager, code_location).await?;
+    } else {
+        let update_code = manager
+            .update_function_code(code_path("arithmetic"), code_location.clone())
+            .await?;
 
-    let code_sha256 = update_code.code_sha256().unwrap_or("Unknown SHA");
-    info!(?code_sha256, "Updated function code with arithmetic.zip");
+        let code_sha256 = update_code.code_sha256().unwrap_or("Unknown SHA");
+        info!(?code_sha256, "Updated function code with arithmetic.zip");
+    }
 
     let arithmetic_args = Arithmetic(opt.operation, opt.num_a, opt.num_b);
     let invoke = manager.invoke(arithmetic_args).await?;
@@ -187,6 +253,60 @@ async fn main_block(
     Ok::<(), anyhow::Error>(())
 }
 
+/**
+ * The --local variant of the scenario. The same steps run against LocalLambda,
+ * so there is no role, bucket, or function to create or clean up.
+ */
+async fn local_block(opt: &Opt) -> Result<(), anyhow::Error> {
+    let increment = calculator("increment", opt.architecture).build_native()?;
+    let arithmetic = calculator("arithmetic", opt.architecture).build_native()?;
+
+    let mut function = LocalLambda::start(
+        opt.lambda_name
+            .clone()
+            .unwrap_or_else(|| "rust_lambda_example".to_string()),
+        increment,
+    )
+    .await?;
+
+    let invoke = function.invoke(Increment(opt.inc)).await?;
+    log_invoke_output(&invoke, "Invoked local function configured as increment");
+
+    function.update_function_code(arithmetic).await?;
+    info!("Updated local function code to arithmetic");
+
+    let invoke = function
+        .invoke(Arithmetic(opt.operation, opt.num_a, opt.num_b))
+        .await?;
+    log_invoke_output(&invoke, "Invoked local function configured as arithmetic");
+
+    function
+        .update_function_configuration(HashMap::from([(
+            "RUST_LOG".to_string(),
+            "trace".to_string(),
+        )]))
+        .await?;
+    info!("Updated local function environment");
+
+    let invoke = function
+        .invoke(Arithmetic(opt.operation, opt.num_a, opt.num_b))
+        .await?;
+    log_invoke_output(
+        &invoke,
+        "Invoked local function configured as arithmetic with increased logging",
+    );
+
+    let invoke = function
+        .invoke(Arithmetic(Operation::DividedBy, opt.num_a, 0))
+        .await?;
+    log_invoke_output(
+        &invoke,
+        "Invoked local function configured as arithmetic with divide by zero",
+    );
+
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt()
@@ -197,9 +317,25 @@ async fn main() {
         .init();
 
     let opt = Opt::parse();
+
+    if opt.local {
+        let run_block = local_block(&opt).await;
+        info!(?run_block, "Finished running example locally");
+        return;
+    }
+
     let manager = LambdaManager::load_from_env(opt.lambda_name.clone(), opt.bucket.clone()).await;
 
-    let key = match manager.create_function(code_path("increment")).await {
+    let create = if opt.build {
+        match calculator("increment", opt.architecture).package() {
+            Ok(package) => manager.create_function_from_package(&package).await,
+            Err(err) => Err(err),
+        }
+    } else {
+        manager.create_function(code_path("increment")).await
+    };
+
+    let key = match create {
         Ok(init) => {
             info!(?init, "Created function, initially with increment.zip");
             let run_block = main_block(&opt, &manager, init.clone()).await;
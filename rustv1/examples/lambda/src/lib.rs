@@ -6,6 +6,8 @@ use aws_sdk_lambda::{config::Region, meta::PKG_VERSION, Client};
 use clap::Parser;
 
 pub mod actions;
+pub mod local;
+pub mod packaging;
 
 #[derive(Debug, Parser)]
 pub struct Opt {
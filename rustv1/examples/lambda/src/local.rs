@@ -0,0 +1,559 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+/*
+## Local invocation
+
+`LocalLambda` runs a handler binary on this machine, the same way the `provided.al2023` runtime runs `bootstrap`.
+
+The handler binary is a `lambda_runtime` client: it polls the Runtime API at `AWS_LAMBDA_RUNTIME_API` for its next event, and posts back either a response or an error.
+`RuntimeApi` serves the three endpoints the client uses:
+
+* GET /2018-06-01/runtime/invocation/next
+* POST /2018-06-01/runtime/invocation/{request_id}/response
+* POST /2018-06-01/runtime/invocation/{request_id}/error
+
+`LocalLambda::invoke` queues an event for `next`, waits for the response or error, and returns an `InvokeOutput` shaped like the Lambda `Invoke` API's.
+The handler's stdout and stderr are captured and returned as the tail log, as with `LogType=Tail`.
+ */
+
+use crate::actions::InvokeArgs;
+use anyhow::{anyhow, Context};
+use aws_sdk_lambda::{operation::invoke::InvokeOutput, primitives::Blob};
+use axum::{
+    body::Bytes,
+    extract::Path as UrlPath,
+    http::{HeaderMap, HeaderValue, StatusCode},
+    routing::{get, post},
+    Extension, Router,
+};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    net::{Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    process::{Child, Command},
+    sync::{mpsc, oneshot},
+};
+use tracing::{debug, info, warn};
+
+/** The Runtime API version the `lambda_runtime` crate speaks. */
+const RUNTIME_API_PREFIX: &str = "/2018-06-01/runtime";
+
+/** Lambda's default function timeout. */
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/** How long to wait for the handler's last log lines to make it through the pipe after it responds. */
+const LOG_SETTLE_TIME: Duration = Duration::from_millis(50);
+
+/** Lambda returns at most the last 4 KB of logs from an invocation. */
+const LOG_TAIL_BYTES: usize = 4096;
+
+/** What the handler posted back for an invocation. */
+#[derive(Debug, PartialEq)]
+enum InvocationResult {
+    Response(Vec<u8>),
+    Error(Vec<u8>),
+}
+
+/** An event waiting for the handler to ask for it. */
+struct Invocation {
+    request_id: String,
+    payload: Vec<u8>,
+    deadline: SystemTime,
+    respond: oneshot::Sender<InvocationResult>,
+}
+
+/** Shared state behind the Runtime API routes. */
+struct RuntimeState {
+    function_arn: String,
+    pending: tokio::sync::Mutex<mpsc::Receiver<Invocation>>,
+    in_flight: Mutex<HashMap<String, oneshot::Sender<InvocationResult>>>,
+    init_error: Mutex<Option<String>>,
+}
+
+/**
+ * The HTTP side of the emulator. Events are queued with `enqueue`, and handed out to the handler one at a time from `next`.
+ */
+struct RuntimeApi {
+    state: Arc<RuntimeState>,
+    queue: mpsc::Sender<Invocation>,
+}
+
+impl RuntimeApi {
+    fn new(function_name: &str) -> Self {
+        let (queue, pending) = mpsc::channel(1);
+        Self {
+            state: Arc::new(RuntimeState {
+                function_arn: format!("arn:aws:lambda:local:000000000000:function:{function_name}"),
+                pending: tokio::sync::Mutex::new(pending),
+                in_flight: Mutex::new(HashMap::new()),
+                init_error: Mutex::new(None),
+            }),
+            queue,
+        }
+    }
+
+    fn router(&self) -> Router {
+        Router::new()
+            .route(&format!("{RUNTIME_API_PREFIX}/invocation/next"), get(next))
+            .route(
+                &format!("{RUNTIME_API_PREFIX}/invocation/:request_id/response"),
+                post(response),
+            )
+            .route(
+                &format!("{RUNTIME_API_PREFIX}/invocation/:request_id/error"),
+                post(error),
+            )
+            .route(
+                &format!("{RUNTIME_API_PREFIX}/init/error"),
+                post(init_error),
+            )
+            .layer(Extension(self.state.clone()))
+    }
+
+    /** Queue an event, returning a receiver for the handler's result. */
+    async fn enqueue(
+        &self,
+        payload: Vec<u8>,
+        timeout: Duration,
+    ) -> Result<(String, oneshot::Receiver<InvocationResult>), anyhow::Error> {
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let (respond, result) = oneshot::channel();
+        self.queue
+            .send(Invocation {
+                request_id: request_id.clone(),
+                payload,
+                deadline: SystemTime::now() + timeout,
+                respond,
+            })
+            .await
+            .map_err(|_| anyhow!("Runtime API is not accepting invocations"))?;
+        Ok((request_id, result))
+    }
+
+    /** Forget an invocation the handler never answered, so a late response is rejected. */
+    fn abandon(&self, request_id: &str) {
+        self.state.in_flight.lock().unwrap().remove(request_id);
+    }
+
+    fn init_error(&self) -> Option<String> {
+        self.state.init_error.lock().unwrap().clone()
+    }
+}
+
+async fn next(Extension(state): Extension<Arc<RuntimeState>>) -> (StatusCode, HeaderMap, Vec<u8>) {
+    // Long poll, as the real Runtime API does, until there is an event to hand out.
+    let invocation = state.pending.lock().await.recv().await;
+    let Some(invocation) = invocation else {
+        return (StatusCode::GONE, HeaderMap::new(), Vec::new());
+    };
+
+    debug!(request_id = invocation.request_id, "Handing out invocation");
+    let deadline_ms = invocation
+        .deadline
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in [
+        (
+            "lambda-runtime-aws-request-id",
+            invocation.request_id.clone(),
+        ),
+        ("lambda-runtime-deadline-ms", deadline_ms.to_string()),
+        (
+            "lambda-runtime-invoked-function-arn",
+            state.function_arn.clone(),
+        ),
+        (
+            "lambda-runtime-trace-id",
+            format!("Root=1-00000000-{}", invocation.request_id.replace('-', "")),
+        ),
+    ] {
+        headers.insert(name, HeaderValue::from_str(&value).unwrap());
+    }
+
+    state
+        .in_flight
+        .lock()
+        .unwrap()
+        .insert(invocation.request_id, invocation.respond);
+
+    (StatusCode::OK, headers, invocation.payload)
+}
+
+fn complete(state: &RuntimeState, request_id: &str, result: InvocationResult) -> StatusCode {
+    match state.in_flight.lock().unwrap().remove(request_id) {
+        Some(respond) => {
+            // The invoker may have timed out and gone away; the handler doesn't need to know.
+            let _ = respond.send(result);
+            StatusCode::ACCEPTED
+        }
+        None => {
+            warn!(request_id, "Result for unknown invocation");
+            StatusCode::BAD_REQUEST
+        }
+    }
+}
+
+async fn response(
+    UrlPath(request_id): UrlPath<String>,
+    Extension(state): Extension<Arc<RuntimeState>>,
+    body: Bytes,
+) -> StatusCode {
+    complete(
+        &state,
+        &request_id,
+        InvocationResult::Response(body.to_vec()),
+    )
+}
+
+async fn error(
+    UrlPath(request_id): UrlPath<String>,
+    Extension(state): Extension<Arc<RuntimeState>>,
+    body: Bytes,
+) -> StatusCode {
+    complete(&state, &request_id, InvocationResult::Error(body.to_vec()))
+}
+
+async fn init_error(Extension(state): Extension<Arc<RuntimeState>>, body: Bytes) -> StatusCode {
+    let message = String::from_utf8_lossy(&body).to_string();
+    warn!(message, "Handler failed to initialize");
+    *state.init_error.lock().unwrap() = Some(message);
+    StatusCode::ACCEPTED
+}
+
+/** A RuntimeApi served on a free local port. The server shuts down when this is dropped. */
+struct LocalServer {
+    api: RuntimeApi,
+    addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl LocalServer {
+    fn start(function_name: &str) -> Result<Self, anyhow::Error> {
+        let api = RuntimeApi::new(function_name);
+
+        let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+        let server = axum::Server::try_bind(&SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))?
+            .serve(api.router().into_make_service());
+        let addr = server.local_addr();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            let _ = shutdown_signal.await;
+        }));
+        info!(%addr, "Local Runtime API listening");
+
+        Ok(Self {
+            api,
+            addr,
+            shutdown: Some(shutdown),
+        })
+    }
+}
+
+impl Drop for LocalServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+/**
+ * A handler binary running against a local Runtime API.
+ * The handler process is stopped when the LocalLambda is dropped.
+ */
+pub struct LocalLambda {
+    function_name: String,
+    executable: PathBuf,
+    environment: HashMap<String, String>,
+    timeout: Duration,
+    server: LocalServer,
+    child: Child,
+    logs: Arc<Mutex<Vec<String>>>,
+}
+
+impl LocalLambda {
+    /** Start the Runtime API on a free local port, and launch the handler against it. */
+    pub async fn start(
+        function_name: impl Into<String>,
+        executable: impl Into<PathBuf>,
+    ) -> Result<Self, anyhow::Error> {
+        let function_name = function_name.into();
+        let executable = executable.into();
+        let environment = HashMap::new();
+        let logs = Arc::new(Mutex::new(Vec::new()));
+
+        let server = LocalServer::start(&function_name)?;
+        let child = spawn_handler(
+            &function_name,
+            &executable,
+            &environment,
+            server.addr,
+            &logs,
+        )?;
+
+        Ok(Self {
+            function_name,
+            executable,
+            environment,
+            timeout: DEFAULT_TIMEOUT,
+            server,
+            child,
+            logs,
+        })
+    }
+
+    /** Set how long an invocation may run before it is reported as timed out. */
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /** Replace the handler binary, like UpdateFunctionCode. */
+    pub async fn update_function_code(
+        &mut self,
+        executable: impl Into<PathBuf>,
+    ) -> Result<(), anyhow::Error> {
+        self.executable = executable.into();
+        info!(executable = ?self.executable, "Updating local function code");
+        self.restart().await
+    }
+
+    /** Replace the handler's environment variables, like UpdateFunctionConfiguration. */
+    pub async fn update_function_configuration(
+        &mut self,
+        environment: HashMap<String, String>,
+    ) -> Result<(), anyhow::Error> {
+        self.environment = environment;
+        info!(environment = ?self.environment, "Updating local function environment");
+        self.restart().await
+    }
+
+    /**
+     * Replace the handler process, as Lambda does with a fresh execution environment.
+     * The Runtime API is replaced too, so a poll left over from the old process can't claim the next event.
+     */
+    async fn restart(&mut self) -> Result<(), anyhow::Error> {
+        self.child.kill().await?;
+        self.server = LocalServer::start(&self.function_name)?;
+        self.child = spawn_handler(
+            &self.function_name,
+            &self.executable,
+            &self.environment,
+            self.server.addr,
+            &self.logs,
+        )?;
+        Ok(())
+    }
+
+    /** Invoke the handler with calculator InvokeArgs. */
+    pub async fn invoke(&mut self, args: InvokeArgs) -> Result<InvokeOutput, anyhow::Error> {
+        info!(?args, "Invoking local {}", self.function_name);
+        let payload = serde_json::to_vec(&args)?;
+        self.invoke_payload(payload).await
+    }
+
+    /**
+     * Send a raw payload to the handler and wait for it to finish.
+     * Handler errors and timeouts are reported the way Lambda reports them, as a FunctionError with an error payload.
+     */
+    pub async fn invoke_payload(
+        &mut self,
+        payload: Vec<u8>,
+    ) -> Result<InvokeOutput, anyhow::Error> {
+        self.logs.lock().unwrap().clear();
+
+        let (request_id, result) = self.server.api.enqueue(payload, self.timeout).await?;
+        self.log_line(format!("START RequestId: {request_id}"));
+        let started = std::time::Instant::now();
+
+        let result = tokio::time::timeout(self.timeout, result).await;
+        let elapsed = started.elapsed();
+        tokio::time::sleep(LOG_SETTLE_TIME).await;
+
+        let (payload, function_error) = match result {
+            Ok(Ok(InvocationResult::Response(payload))) => (payload, None),
+            Ok(Ok(InvocationResult::Error(payload))) => (payload, Some("Unhandled")),
+            Ok(Err(_)) => return Err(anyhow!("Invocation {request_id} was dropped")),
+            Err(_) => {
+                self.server.api.abandon(&request_id);
+                let message = match self.server.api.init_error() {
+                    Some(init_error) => format!("Handler failed to initialize: {init_error}"),
+                    None => format!(
+                        "Task timed out after {:.2} seconds",
+                        self.timeout.as_secs_f64()
+                    ),
+                };
+                self.log_line(message.clone());
+                // Lambda discards a timed out execution environment; do the same.
+                self.restart().await?;
+                let payload = json!({"errorType": "Sandbox.Timedout", "errorMessage": message});
+                (payload.to_string().into_bytes(), Some("Unhandled"))
+            }
+        };
+
+        self.log_line(format!("END RequestId: {request_id}"));
+        self.log_line(format!(
+            "REPORT RequestId: {request_id}\tDuration: {:.2} ms",
+            elapsed.as_secs_f64() * 1000.0
+        ));
+
+        Ok(InvokeOutput::builder()
+            .status_code(200)
+            .payload(Blob::new(payload))
+            .set_function_error(function_error.map(str::to_string))
+            .log_result(base64::encode(self.log_tail()))
+            .executed_version("$LATEST")
+            .build())
+    }
+
+    fn log_line(&self, line: String) {
+        self.logs.lock().unwrap().push(line);
+    }
+
+    fn log_tail(&self) -> String {
+        let logs = self.logs.lock().unwrap().join("\n");
+        let mut start = logs.len().saturating_sub(LOG_TAIL_BYTES);
+        while !logs.is_char_boundary(start) {
+            start += 1;
+        }
+        logs[start..].to_string()
+    }
+}
+
+impl Drop for LocalLambda {
+    fn drop(&mut self) {
+        if let Err(err) = self.child.start_kill() {
+            warn!(?err, "Failed to stop local handler");
+        }
+    }
+}
+
+/** Launch the handler with the environment the Lambda service provides to custom runtimes. */
+fn spawn_handler(
+    function_name: &str,
+    executable: &Path,
+    environment: &HashMap<String, String>,
+    addr: SocketAddr,
+    logs: &Arc<Mutex<Vec<String>>>,
+) -> Result<Child, anyhow::Error> {
+    debug!(?executable, %addr, "Launching handler");
+    let mut child = Command::new(executable)
+        .envs(environment)
+        .env("AWS_LAMBDA_RUNTIME_API", addr.to_string())
+        .env("AWS_LAMBDA_FUNCTION_NAME", function_name)
+        .env("AWS_LAMBDA_FUNCTION_VERSION", "$LATEST")
+        .env("AWS_LAMBDA_FUNCTION_MEMORY_SIZE", "128")
+        .env(
+            "AWS_LAMBDA_LOG_GROUP_NAME",
+            format!("/aws/lambda/{function_name}"),
+        )
+        .env("AWS_LAMBDA_LOG_STREAM_NAME", "local")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to launch handler {executable:?}"))?;
+
+    capture_logs(child.stdout.take(), logs.clone());
+    capture_logs(child.stderr.take(), logs.clone());
+
+    Ok(child)
+}
+
+fn capture_logs(
+    stream: Option<impl AsyncRead + Unpin + Send + 'static>,
+    logs: Arc<Mutex<Vec<String>>>,
+) {
+    if let Some(stream) = stream {
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stream).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                logs.lock().unwrap().push(line);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{InvocationResult, RuntimeApi};
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    /** Walk one invocation through next and response, as the handler's runtime client would. */
+    #[tokio::test]
+    async fn test_next_then_response() {
+        let api = RuntimeApi::new("test");
+        let (request_id, result) = api
+            .enqueue(b"5".to_vec(), Duration::from_secs(3))
+            .await
+            .unwrap();
+
+        let next = api
+            .router()
+            .oneshot(
+                Request::get("/2018-06-01/runtime/invocation/next")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(next.status(), StatusCode::OK);
+        assert_eq!(
+            next.headers()["lambda-runtime-aws-request-id"],
+            request_id.as_str()
+        );
+        assert!(next.headers().contains_key("lambda-runtime-deadline-ms"));
+        assert_eq!(
+            next.headers()["lambda-runtime-invoked-function-arn"],
+            "arn:aws:lambda:local:000000000000:function:test"
+        );
+        let body = hyper::body::to_bytes(next.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"5");
+
+        let response = api
+            .router()
+            .oneshot(
+                Request::post(format!(
+                    "/2018-06-01/runtime/invocation/{request_id}/response"
+                ))
+                .body(Body::from("6"))
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert_eq!(
+            result.await.unwrap(),
+            InvocationResult::Response(b"6".to_vec())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_error_for_unknown_request() {
+        let api = RuntimeApi::new("test");
+        let response = api
+            .router()
+            .oneshot(
+                Request::post("/2018-06-01/runtime/invocation/not-a-request/error")
+                    .body(Body::from(r#"{"errorType":"x","errorMessage":"y"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,397 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+/*
+## Packaging
+
+Lambda functions written in Rust run on the `provided.al2023` runtime, which
+expects a zip with a single executable named `bootstrap` at its root.
+
+This module builds a Cargo binary target for the function's architecture,
+zips it as `bootstrap` with executable permissions, and computes the same
+base64 SHA-256 that Lambda reports as `CodeSha256`. Comparing the two lets
+`LambdaManager` skip `UpdateFunctionCode` calls that would not change anything.
+
+The zip is written with a fixed timestamp, so building the same binary twice
+yields the same `CodeSha256`.
+ */
+
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+use sha2::Digest;
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io::{Cursor, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    str::FromStr,
+};
+use tracing::{debug, info};
+use zip_next::{write::FileOptions, CompressionMethod, DateTime, ZipWriter};
+
+/** The name Lambda's custom runtimes look for at the root of the deployment package. */
+pub const BOOTSTRAP: &str = "bootstrap";
+
+/** rwxr-xr-x; Lambda must be able to execute the bootstrap binary. */
+const BOOTSTRAP_PERMISSIONS: u32 = 0o755;
+
+/** The instruction set a function runs on, and the Rust target used to build for it. */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Architecture {
+    #[default]
+    X86_64,
+    Arm64,
+}
+
+impl Architecture {
+    /** The Rust target triple that produces binaries compatible with `provided.al2023` on this architecture. */
+    pub fn target_triple(&self) -> &'static str {
+        match self {
+            Architecture::X86_64 => "x86_64-unknown-linux-gnu",
+            Architecture::Arm64 => "aarch64-unknown-linux-gnu",
+        }
+    }
+}
+
+impl From<Architecture> for aws_sdk_lambda::types::Architecture {
+    fn from(value: Architecture) -> Self {
+        match value {
+            Architecture::X86_64 => aws_sdk_lambda::types::Architecture::X8664,
+            Architecture::Arm64 => aws_sdk_lambda::types::Architecture::Arm64,
+        }
+    }
+}
+
+impl FromStr for Architecture {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "x86_64" => Ok(Architecture::X86_64),
+            "arm64" => Ok(Architecture::Arm64),
+            _ => Err(anyhow!("Unknown architecture {s}")),
+        }
+    }
+}
+
+impl Display for Architecture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Architecture::X86_64 => write!(f, "x86_64"),
+            Architecture::Arm64 => write!(f, "arm64"),
+        }
+    }
+}
+
+/**
+ * The cargo subcommand used to compile the function.
+ * `cargo build` needs a linker for the target triple when cross compiling.
+ * `cargo zigbuild` (https://github.com/rust-cross/cargo-zigbuild) brings its own.
+ */
+#[derive(Clone, Copy, Debug, Default)]
+pub enum BuildCommand {
+    #[default]
+    Build,
+    ZigBuild,
+}
+
+impl BuildCommand {
+    fn subcommand(&self) -> &'static str {
+        match self {
+            BuildCommand::Build => "build",
+            BuildCommand::ZigBuild => "zigbuild",
+        }
+    }
+}
+
+/**
+ * A binary target in a Cargo project, to be compiled and packaged as a Lambda function.
+ */
+#[derive(Clone, Debug)]
+pub struct CargoBinary {
+    manifest_path: PathBuf,
+    bin: String,
+    architecture: Architecture,
+    command: BuildCommand,
+}
+
+impl CargoBinary {
+    pub fn new(manifest_path: impl Into<PathBuf>, bin: impl Into<String>) -> Self {
+        Self {
+            manifest_path: manifest_path.into(),
+            bin: bin.into(),
+            architecture: Architecture::default(),
+            command: BuildCommand::default(),
+        }
+    }
+
+    pub fn architecture(mut self, architecture: Architecture) -> Self {
+        self.architecture = architecture;
+        self
+    }
+
+    pub fn command(mut self, command: BuildCommand) -> Self {
+        self.command = command;
+        self
+    }
+
+    /** Compile the binary in release mode for the target architecture, and return the path to the executable. */
+    pub fn build(&self) -> Result<PathBuf, anyhow::Error> {
+        info!(
+            bin = self.bin,
+            architecture = %self.architecture,
+            manifest_path = ?self.manifest_path,
+            "Building Lambda function"
+        );
+        self.cargo(Some(self.architecture.target_triple()))
+    }
+
+    /** Compile the binary for the machine running this code, such as to run it with `local::LocalLambda`. */
+    pub fn build_native(&self) -> Result<PathBuf, anyhow::Error> {
+        info!(
+            bin = self.bin,
+            manifest_path = ?self.manifest_path,
+            "Building Lambda function for this host"
+        );
+        self.cargo(None)
+    }
+
+    fn cargo(&self, target: Option<&str>) -> Result<PathBuf, anyhow::Error> {
+        let mut command = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()));
+        command
+            .arg(self.command.subcommand())
+            .arg("--release")
+            .arg("--message-format=json")
+            .arg("--manifest-path")
+            .arg(&self.manifest_path)
+            .arg("--bin")
+            .arg(&self.bin);
+        if let Some(target) = target {
+            command.arg("--target").arg(target);
+        }
+        let output = command
+            .stderr(Stdio::inherit())
+            .output()
+            .context("Failed to run cargo")?;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "cargo build for {} exited with {}",
+                self.bin,
+                output.status
+            ));
+        }
+
+        find_executable(&String::from_utf8_lossy(&output.stdout), &self.bin)
+            .ok_or_else(|| anyhow!("cargo did not report an executable for {}", self.bin))
+    }
+
+    /** Build the binary and package it for deployment. */
+    pub fn package(&self) -> Result<LambdaPackage, anyhow::Error> {
+        let executable = self.build()?;
+        LambdaPackage::from_executable(&executable, self.architecture)
+    }
+}
+
+/** The subset of cargo's `--message-format=json` output needed to locate a built binary. */
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    target: Option<CargoTarget>,
+    executable: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct CargoTarget {
+    name: String,
+}
+
+fn find_executable(messages: &str, bin: &str) -> Option<PathBuf> {
+    messages
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CargoMessage>(line).ok())
+        .filter(|message| message.reason == "compiler-artifact")
+        .filter(|message| message.target.as_ref().is_some_and(|t| t.name == bin))
+        .find_map(|message| message.executable)
+}
+
+/**
+ * A zipped `bootstrap` ready to upload, along with the metadata Lambda needs to run it.
+ */
+#[derive(Clone, Debug)]
+pub struct LambdaPackage {
+    zip: Vec<u8>,
+    code_sha256: String,
+    architecture: Architecture,
+}
+
+impl LambdaPackage {
+    /** Zip an already-built executable as `bootstrap`. */
+    pub fn from_executable(
+        executable: &Path,
+        architecture: Architecture,
+    ) -> Result<Self, anyhow::Error> {
+        let bytes = std::fs::read(executable)
+            .with_context(|| format!("Failed to read executable {executable:?}"))?;
+        Self::from_bytes(&bytes, architecture)
+    }
+
+    /** Zip the bytes of an executable as `bootstrap`. */
+    pub fn from_bytes(
+        executable: &[u8],
+        architecture: Architecture,
+    ) -> Result<Self, anyhow::Error> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .unix_permissions(BOOTSTRAP_PERMISSIONS)
+            .last_modified_time(DateTime::default());
+        zip.start_file(BOOTSTRAP, options)?;
+        zip.write_all(executable)?;
+        let zip = zip.finish()?.into_inner();
+
+        let code_sha256 = code_sha256(&zip);
+        debug!(code_sha256, len = zip.len(), "Packaged bootstrap");
+
+        Ok(Self {
+            zip,
+            code_sha256,
+            architecture,
+        })
+    }
+
+    /** Use a zip built elsewhere, such as by `cargo lambda build --output-format Zip`. */
+    pub fn from_zip(zip_file: &Path, architecture: Architecture) -> Result<Self, anyhow::Error> {
+        let zip = std::fs::read(zip_file)
+            .with_context(|| format!("Failed to read zip file {zip_file:?}"))?;
+        let code_sha256 = code_sha256(&zip);
+        Ok(Self {
+            zip,
+            code_sha256,
+            architecture,
+        })
+    }
+
+    pub fn zip(&self) -> &[u8] {
+        &self.zip
+    }
+
+    pub fn into_zip(self) -> Vec<u8> {
+        self.zip
+    }
+
+    /** The base64 encoded SHA-256 of the zip, as Lambda reports it in `CodeSha256`. */
+    pub fn code_sha256(&self) -> &str {
+        &self.code_sha256
+    }
+
+    pub fn architecture(&self) -> Architecture {
+        self.architecture
+    }
+}
+
+/** Compute the base64 encoded SHA-256 digest Lambda uses to identify deployment packages. */
+pub fn code_sha256(zip: &[u8]) -> String {
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(zip);
+    base64::encode(hasher.finalize())
+}
+
+/**
+ * Routing for an alias: the primary version receives whatever traffic is not
+ * assigned to an additional version.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TrafficShift {
+    additional_version: String,
+    weight: f64,
+}
+
+impl TrafficShift {
+    /**
+     * Send `weight` (between 0.0 and 1.0) of the alias's traffic to `additional_version`.
+     */
+    pub fn new(additional_version: impl Into<String>, weight: f64) -> Result<Self, anyhow::Error> {
+        if !(0.0..=1.0).contains(&weight) {
+            return Err(anyhow!(
+                "Traffic weight must be between 0.0 and 1.0, got {weight}"
+            ));
+        }
+        Ok(Self {
+            additional_version: additional_version.into(),
+            weight,
+        })
+    }
+
+    pub fn additional_version(&self) -> &str {
+        &self.additional_version
+    }
+
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    pub(crate) fn additional_version_weights(&self) -> HashMap<String, f64> {
+        HashMap::from([(self.additional_version.clone(), self.weight)])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{code_sha256, find_executable, Architecture, LambdaPackage, TrafficShift};
+    use std::io::{Cursor, Read};
+    use zip_next::ZipArchive;
+
+    #[test]
+    fn test_package_bootstrap() {
+        let package =
+            LambdaPackage::from_bytes(b"#!/bin/sh\necho hello", Architecture::Arm64).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(package.zip().to_vec())).unwrap();
+        assert_eq!(archive.len(), 1);
+
+        let mut bootstrap = archive.by_name("bootstrap").unwrap();
+        assert_eq!(bootstrap.unix_mode().map(|m| m & 0o777), Some(0o755));
+        let mut contents = String::new();
+        bootstrap.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "#!/bin/sh\necho hello");
+        assert_eq!(package.architecture(), Architecture::Arm64);
+    }
+
+    /** The same binary must produce the same CodeSha256, or every deploy would update the function. */
+    #[test]
+    fn test_package_is_reproducible() {
+        let a = LambdaPackage::from_bytes(b"binary", Architecture::X86_64).unwrap();
+        let b = LambdaPackage::from_bytes(b"binary", Architecture::X86_64).unwrap();
+        let c = LambdaPackage::from_bytes(b"other binary", Architecture::X86_64).unwrap();
+        assert_eq!(a.code_sha256(), b.code_sha256());
+        assert_ne!(a.code_sha256(), c.code_sha256());
+    }
+
+    #[test]
+    fn test_code_sha256() {
+        assert_eq!(
+            code_sha256(b""),
+            "47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU="
+        );
+    }
+
+    #[test]
+    fn test_find_executable() {
+        let messages = r#"{"reason":"compiler-artifact","target":{"name":"serde"},"executable":null}
+{"reason":"compiler-artifact","target":{"name":"arithmetic"},"executable":"/target/release/arithmetic"}
+{"reason":"build-finished","success":true}"#;
+        assert_eq!(
+            find_executable(messages, "arithmetic"),
+            Some("/target/release/arithmetic".into())
+        );
+        assert_eq!(find_executable(messages, "increment"), None);
+    }
+
+    #[test]
+    fn test_traffic_shift_weight() {
+        assert!(TrafficShift::new("2", 0.1).is_ok());
+        assert!(TrafficShift::new("2", 1.5).is_err());
+        assert!(TrafficShift::new("2", -0.1).is_err());
+    }
+}
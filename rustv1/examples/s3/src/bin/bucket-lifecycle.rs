@@ -0,0 +1,112 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use aws_config::meta::region::RegionProviderChain;
+use aws_sdk_s3::{config::Region, meta::PKG_VERSION, Client};
+use clap::{Parser, Subcommand};
+use s3_code_examples::{
+    error::S3ExampleError,
+    lifecycle::{self, LifecyclePolicy},
+};
+
+#[derive(Debug, Parser)]
+struct Opt {
+    /// The name of the bucket.
+    #[structopt(short, long)]
+    bucket: String,
+
+    /// The AWS Region.
+    #[structopt(short, long)]
+    region: Option<String>,
+
+    /// Whether to display additional information.
+    #[structopt(short, long)]
+    verbose: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print the bucket's lifecycle rules as YAML.
+    Show,
+    /// Check a policy file without changing the bucket.
+    Validate { policy: PathBuf },
+    /// Replace the bucket's lifecycle rules with those in a policy file.
+    Apply { policy: PathBuf },
+    /// Remove every lifecycle rule from the bucket.
+    Remove,
+}
+
+async fn run(client: &Client, bucket: &str, command: Command) -> Result<(), S3ExampleError> {
+    match command {
+        Command::Show => match lifecycle::get_lifecycle(client, bucket).await? {
+            Some(policy) => print!("{}", policy.to_yaml()?),
+            None => println!("{bucket} has no lifecycle rules."),
+        },
+        Command::Validate { policy } => {
+            let policy = LifecyclePolicy::load(&policy)?;
+            policy.validate()?;
+            println!("The policy's {} rules are valid.", policy.rules.len());
+        }
+        Command::Apply { policy } => {
+            let policy = LifecyclePolicy::load(&policy)?;
+            lifecycle::apply_lifecycle(client, bucket, &policy).await?;
+            println!(
+                "Applied {} lifecycle rules to {bucket}.",
+                policy.rules.len()
+            );
+        }
+        Command::Remove => {
+            lifecycle::remove_lifecycle(client, bucket).await?;
+            println!("Removed the lifecycle rules from {bucket}.");
+        }
+    }
+    Ok(())
+}
+
+/// Manages the lifecycle rules of an Amazon S3 bucket from a YAML or JSON policy file.
+/// # Arguments
+///
+/// * `-b BUCKET` - The name of the bucket.
+/// * `show` - Print the bucket's lifecycle rules as YAML.
+/// * `validate POLICY` - Check a policy file without changing the bucket.
+/// * `apply POLICY` - Replace the bucket's lifecycle rules with those in a policy file.
+/// * `remove` - Remove every lifecycle rule from the bucket.
+/// * `[-r REGION]` - The Region in which the client is created.
+///   If not supplied, uses the value of the **AWS_REGION** environment variable.
+///   If the environment variable is not set, defaults to **us-west-2**.
+/// * `[-v]` - Whether to display additional information.
+#[tokio::main]
+async fn main() -> Result<(), S3ExampleError> {
+    tracing_subscriber::fmt::init();
+
+    let Opt {
+        bucket,
+        region,
+        verbose,
+        command,
+    } = Opt::parse();
+
+    let region_provider = RegionProviderChain::first_try(region.map(Region::new))
+        .or_default_provider()
+        .or_else(Region::new("us-west-2"));
+
+    if verbose {
+        println!("S3 client version: {}", PKG_VERSION);
+        println!(
+            "Region:            {}",
+            region_provider.region().await.unwrap().as_ref()
+        );
+        println!("Bucket:            {}", &bucket);
+        println!();
+    }
+
+    let shared_config = aws_config::from_env().region(region_provider).load().await;
+    let client = Client::new(&shared_config);
+
+    run(&client, &bucket, command).await
+}
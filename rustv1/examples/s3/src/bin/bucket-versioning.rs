@@ -0,0 +1,132 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use aws_config::meta::region::RegionProviderChain;
+use aws_sdk_s3::{config::Region, meta::PKG_VERSION, Client};
+use clap::{Parser, Subcommand};
+use s3_code_examples::{error::S3ExampleError, versioning};
+
+#[derive(Debug, Parser)]
+struct Opt {
+    /// The name of the bucket.
+    #[structopt(short, long)]
+    bucket: String,
+
+    /// The AWS Region.
+    #[structopt(short, long)]
+    region: Option<String>,
+
+    /// Whether to display additional information.
+    #[structopt(short, long)]
+    verbose: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Show whether versioning is on.
+    Status,
+    /// Keep every version of every object from now on.
+    Enable,
+    /// Stop keeping new versions. Existing versions are kept.
+    Suspend,
+    /// List every version and delete marker.
+    List {
+        #[arg(short, long)]
+        prefix: Option<String>,
+    },
+    /// Make an earlier version of an object the current one.
+    Restore {
+        #[arg(short, long)]
+        key: String,
+        #[arg(long)]
+        version_id: String,
+    },
+    /// Permanently delete every version and delete marker, then the bucket itself.
+    DeleteBucket,
+}
+
+async fn run(client: &Client, bucket: &str, command: Command) -> Result<(), S3ExampleError> {
+    match command {
+        Command::Status => match versioning::versioning_status(client, bucket).await? {
+            Some(status) => println!("Versioning is {} for {bucket}.", status.as_str()),
+            None => println!("Versioning has never been enabled for {bucket}."),
+        },
+        Command::Enable => {
+            versioning::enable_versioning(client, bucket).await?;
+            println!("Enabled versioning for {bucket}.");
+        }
+        Command::Suspend => {
+            versioning::suspend_versioning(client, bucket).await?;
+            println!("Suspended versioning for {bucket}.");
+        }
+        Command::List { prefix } => {
+            for version in versioning::list_versions(client, bucket, prefix.as_deref()).await? {
+                let kind = if version.is_delete_marker {
+                    "delete marker".to_string()
+                } else {
+                    format!("{} bytes", version.size)
+                };
+                let latest = if version.is_latest { " (latest)" } else { "" };
+                println!("{}  {}  {kind}{latest}", version.key, version.version_id);
+            }
+        }
+        Command::Restore { key, version_id } => {
+            let output = versioning::restore_version(client, bucket, &key, &version_id).await?;
+            println!(
+                "Restored {key} from version {version_id} as version {}.",
+                output.version_id().unwrap_or("null")
+            );
+        }
+        Command::DeleteBucket => {
+            versioning::delete_versioned_bucket(client, bucket).await?;
+            println!("Deleted {bucket} and every version in it.");
+        }
+    }
+    Ok(())
+}
+
+/// Manages versioning for an Amazon S3 bucket.
+/// # Arguments
+///
+/// * `-b BUCKET` - The name of the bucket.
+/// * `status|enable|suspend` - Show, turn on, or suspend versioning.
+/// * `list [-p PREFIX]` - List every version and delete marker.
+/// * `restore -k KEY --version-id ID` - Make an earlier version the current one.
+/// * `delete-bucket` - Purge every version and delete marker, then delete the bucket.
+/// * `[-r REGION]` - The Region in which the client is created.
+///   If not supplied, uses the value of the **AWS_REGION** environment variable.
+///   If the environment variable is not set, defaults to **us-west-2**.
+/// * `[-v]` - Whether to display additional information.
+#[tokio::main]
+async fn main() -> Result<(), S3ExampleError> {
+    tracing_subscriber::fmt::init();
+
+    let Opt {
+        bucket,
+        region,
+        verbose,
+        command,
+    } = Opt::parse();
+
+    let region_provider = RegionProviderChain::first_try(region.map(Region::new))
+        .or_default_provider()
+        .or_else(Region::new("us-west-2"));
+
+    if verbose {
+        println!("S3 client version: {}", PKG_VERSION);
+        println!(
+            "Region:            {}",
+            region_provider.region().await.unwrap().as_ref()
+        );
+        println!("Bucket:            {}", &bucket);
+        println!();
+    }
+
+    let shared_config = aws_config::from_env().region(region_provider).load().await;
+    let client = Client::new(&shared_config);
+
+    run(&client, &bucket, command).await
+}
@@ -4,9 +4,10 @@
 #![allow(clippy::result_large_err)]
 
 use aws_config::meta::region::RegionProviderChain;
-use aws_sdk_s3::presigning::PresigningConfig;
 use aws_sdk_s3::{config::Region, meta::PKG_VERSION, Client};
 use clap::Parser;
+use s3_code_examples::error::S3ExampleError;
+use s3_code_examples::presign::{PresignPolicy, Presigner, DEFAULT_EXPIRY, MAX_PRESIGN_EXPIRY};
 use std::error::Error;
 use std::time::Duration;
 
@@ -36,18 +37,11 @@ struct Opt {
 // snippet-start:[s3.rust.get-object-presigned]
 /// Generate a URL for a presigned GET request.
 async fn get_object(
-    client: &Client,
-    bucket: &str,
+    presigner: &Presigner,
     object: &str,
-    expires_in: u64,
-) -> Result<(), Box<dyn Error>> {
-    let expires_in = Duration::from_secs(expires_in);
-    let presigned_request = client
-        .get_object()
-        .bucket(bucket)
-        .key(object)
-        .presigned(PresigningConfig::expires_in(expires_in)?)
-        .await?;
+    expires_in: Duration,
+) -> Result<(), S3ExampleError> {
+    let presigned_request = presigner.presign_get(object, expires_in).await?;
 
     println!("Object URI: {}", presigned_request.uri());
     let valid_until = chrono::offset::Local::now() + expires_in;
@@ -86,6 +80,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     let shared_config = aws_config::from_env().region(region_provider).load().await;
     let client = Client::new(&shared_config);
+    let expires_in = expires_in.unwrap_or(DEFAULT_EXPIRY.as_secs());
 
     println!();
 
@@ -94,9 +89,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
         println!("Region:            {}", shared_config.region().unwrap());
         println!("Bucket:            {}", &bucket);
         println!("Object:            {}", &object);
-        println!("Expires in:        {} seconds", expires_in.unwrap_or(900));
+        println!("Expires in:        {} seconds", expires_in);
         println!();
     }
 
-    get_object(&client, &bucket, &object, expires_in.unwrap_or(900)).await
+    let presigner = Presigner::new(client, bucket)
+        .with_policy(PresignPolicy::new().with_max_expiry(MAX_PRESIGN_EXPIRY));
+    get_object(&presigner, &object, Duration::from_secs(expires_in)).await?;
+    Ok(())
 }
@@ -0,0 +1,151 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{path::PathBuf, process::exit};
+
+use aws_config::meta::region::RegionProviderChain;
+use aws_sdk_s3::{config::Region, meta::PKG_VERSION, Client};
+use clap::Parser;
+use s3_code_examples::{
+    progress::{ProgressBar, ProgressTracker},
+    transfer::{
+        download::DEFAULT_RANGE_SIZE, RangedDownloader, SseCustomerKey, Verification,
+        DEFAULT_CONCURRENCY,
+    },
+};
+
+#[derive(Debug, Parser)]
+struct Opt {
+    /// The name of the bucket.
+    #[structopt(short, long)]
+    bucket: String,
+
+    /// The key of the object to download.
+    #[structopt(short, long)]
+    key: String,
+
+    /// Where to save the object.
+    #[structopt(short, long)]
+    destination: PathBuf,
+
+    /// How many ranges to download at once.
+    #[structopt(long, default_value_t = DEFAULT_CONCURRENCY)]
+    concurrency: usize,
+
+    /// The size of each range in MiB.
+    #[structopt(long, default_value_t = DEFAULT_RANGE_SIZE / 1024 / 1024)]
+    range_size_mib: u64,
+
+    /// Start over instead of finishing a partial download.
+    #[structopt(long)]
+    restart: bool,
+
+    /// Don't check the file against the object's checksum or ETag.
+    #[structopt(long)]
+    no_verify: bool,
+
+    /// A file holding the 32 byte key the object was encrypted with, for SSE-C.
+    #[structopt(long)]
+    sse_customer_key_file: Option<PathBuf>,
+
+    /// The AWS Region.
+    #[structopt(short, long)]
+    region: Option<String>,
+
+    /// Whether to display additional information.
+    #[structopt(short, long)]
+    verbose: bool,
+}
+
+/// Downloads an object as byte ranges, several at a time, and checks the result.
+/// # Arguments
+///
+/// * `-b BUCKET` - The name of the bucket.
+/// * `-k KEY` - The key of the object to download.
+/// * `-d DESTINATION` - Where to save the object.
+/// * `[--concurrency N]` - How many ranges to download at once.
+/// * `[--range-size-mib MIB]` - The size of each range.
+/// * `[--restart]` - Start over instead of finishing a partial download.
+/// * `[--no-verify]` - Don't check the file against the object's checksum or ETag.
+/// * `[--sse-customer-key-file FILE]` - The 32 byte key the object was encrypted with, for SSE-C.
+/// * `[-r REGION]` - The Region in which the client is created.
+///    If not supplied, uses the value of the **AWS_REGION** environment variable.
+///    If the environment variable is not set, defaults to **us-west-2**.
+/// * `[-v]` - Whether to display additional information.
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let Opt {
+        bucket,
+        key,
+        destination,
+        concurrency,
+        range_size_mib,
+        restart,
+        no_verify,
+        sse_customer_key_file,
+        region,
+        verbose,
+    } = Opt::parse();
+
+    let region_provider = RegionProviderChain::first_try(region.map(Region::new))
+        .or_default_provider()
+        .or_else(Region::new("us-west-2"));
+
+    if verbose {
+        println!("S3 client version: {}", PKG_VERSION);
+        println!(
+            "Region:            {}",
+            region_provider.region().await.unwrap().as_ref()
+        );
+        println!();
+    }
+
+    let shared_config = aws_config::from_env().region(region_provider).load().await;
+    let client = Client::new(&shared_config);
+
+    let mut downloader = RangedDownloader::new(client)
+        .with_concurrency(concurrency)
+        .with_range_size(range_size_mib * 1024 * 1024)
+        .with_resume(!restart)
+        .with_verify(!no_verify)
+        .with_progress(ProgressTracker::new(ProgressBar::new("Downloading")));
+    if let Some(path) = sse_customer_key_file {
+        let key = std::fs::read(&path)
+            .ok()
+            .and_then(|key| <[u8; 32]>::try_from(key).ok());
+        match key {
+            Some(key) => downloader = downloader.with_sse_customer_key(SseCustomerKey::new(&key)),
+            None => {
+                eprintln!("{} must hold a 32 byte key.", path.display());
+                exit(1);
+            }
+        }
+    }
+
+    match downloader.download(&bucket, &key, &destination).await {
+        Ok(summary) => {
+            println!(
+                "Downloaded {} bytes of {bucket}/{key} to {}",
+                summary.size,
+                destination.display()
+            );
+            if summary.resumed_ranges > 0 {
+                println!(
+                    "{} ranges were left from an earlier run.",
+                    summary.resumed_ranges
+                );
+            }
+            match summary.verification {
+                Verification::Checksum(checksum) => println!("Verified the {checksum:?} checksum."),
+                Verification::ETag => println!("Verified the ETag {}.", summary.e_tag),
+                Verification::Skipped => println!("The download was not verified."),
+            }
+        }
+        Err(err) => {
+            eprintln!("Error: {err}");
+            exit(1);
+        }
+    }
+}
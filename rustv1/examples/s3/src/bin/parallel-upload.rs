@@ -0,0 +1,130 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{path::PathBuf, process::exit};
+
+use aws_config::meta::region::RegionProviderChain;
+use aws_sdk_s3::{config::Region, meta::PKG_VERSION, Client};
+use clap::Parser;
+use s3_code_examples::{
+    progress::{ProgressBar, ProgressTracker},
+    transfer::{Checksum, MultipartUploader, DEFAULT_CONCURRENCY, MIN_PART_SIZE},
+};
+
+#[derive(Debug, Parser)]
+struct Opt {
+    /// The name of the bucket.
+    #[structopt(short, long)]
+    bucket: String,
+
+    /// The key to upload the file as.
+    #[structopt(short, long)]
+    key: String,
+
+    /// The file to upload.
+    #[structopt(short, long)]
+    source: PathBuf,
+
+    /// How many parts to upload at once.
+    #[structopt(long, default_value_t = DEFAULT_CONCURRENCY)]
+    concurrency: usize,
+
+    /// The part size in MiB. Grows if the file would need more than 10,000 parts.
+    #[structopt(long, default_value_t = MIN_PART_SIZE / 1024 / 1024)]
+    part_size_mib: u64,
+
+    /// The checksum S3 verifies each part with.
+    #[structopt(long, value_enum, default_value_t = Checksum::Crc32c)]
+    checksum: Checksum,
+
+    /// Where to save the upload's progress. Defaults to the source file with `.upload.json`
+    /// added. Running again with the same manifest resumes the upload.
+    #[structopt(long)]
+    manifest: Option<PathBuf>,
+
+    /// Keep the parts that uploaded when a part fails, so the upload can be resumed.
+    #[structopt(long)]
+    keep_on_failure: bool,
+
+    /// The AWS Region.
+    #[structopt(short, long)]
+    region: Option<String>,
+
+    /// Whether to display additional information.
+    #[structopt(short, long)]
+    verbose: bool,
+}
+
+/// Uploads a file in parts, several at a time, and resumes the upload if it was interrupted.
+/// # Arguments
+///
+/// * `-b BUCKET` - The name of the bucket.
+/// * `-k KEY` - The key to upload the file as.
+/// * `-s SOURCE` - The file to upload.
+/// * `[--concurrency N]` - How many parts to upload at once.
+/// * `[--part-size-mib MIB]` - The part size, if the file fits in 10,000 parts of this size.
+/// * `[--checksum crc32c|sha256]` - The checksum S3 verifies each part with.
+/// * `[--manifest FILE]` - Where to save the upload's progress.
+/// * `[--keep-on-failure]` - Don't abort the upload when a part fails.
+/// * `[-r REGION]` - The Region in which the client is created.
+///    If not supplied, uses the value of the **AWS_REGION** environment variable.
+///    If the environment variable is not set, defaults to **us-west-2**.
+/// * `[-v]` - Whether to display additional information.
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let Opt {
+        bucket,
+        key,
+        source,
+        concurrency,
+        part_size_mib,
+        checksum,
+        manifest,
+        keep_on_failure,
+        region,
+        verbose,
+    } = Opt::parse();
+
+    let region_provider = RegionProviderChain::first_try(region.map(Region::new))
+        .or_default_provider()
+        .or_else(Region::new("us-west-2"));
+
+    if verbose {
+        println!("S3 client version: {}", PKG_VERSION);
+        println!(
+            "Region:            {}",
+            region_provider.region().await.unwrap().as_ref()
+        );
+        println!();
+    }
+
+    let shared_config = aws_config::from_env().region(region_provider).load().await;
+    let client = Client::new(&shared_config);
+
+    let manifest = manifest.unwrap_or_else(|| {
+        let mut manifest = source.clone().into_os_string();
+        manifest.push(".upload.json");
+        manifest.into()
+    });
+    let uploader = MultipartUploader::new(client)
+        .with_concurrency(concurrency)
+        .with_part_size(part_size_mib * 1024 * 1024)
+        .with_checksum(checksum)
+        .with_manifest(manifest)
+        .with_abort_on_failure(!keep_on_failure)
+        .with_progress(ProgressTracker::new(ProgressBar::new("Uploading")));
+
+    match uploader.upload(&source, &bucket, &key).await {
+        Ok(output) => println!(
+            "Uploaded {} to {bucket}/{key} with etag {}",
+            source.display(),
+            output.e_tag().unwrap_or("missing")
+        ),
+        Err(err) => {
+            eprintln!("Error: {err}");
+            exit(1);
+        }
+    }
+}
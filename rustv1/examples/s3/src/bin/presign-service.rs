@@ -0,0 +1,321 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(clippy::result_large_err)]
+
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use aws_config::meta::region::RegionProviderChain;
+use aws_sdk_s3::{config::Region, meta::PKG_VERSION, Client};
+use axum::{
+    extract::Extension,
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    routing::post,
+    Json, Router,
+};
+use clap::Parser;
+use s3_code_examples::{
+    error::S3ExampleError,
+    presign::{
+        assume_role_client, PresignPolicy, PresignedPost, PresignedUrl, Presigner, DEFAULT_EXPIRY,
+    },
+};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+#[derive(Debug, Parser)]
+struct Opt {
+    /// The name of the bucket.
+    #[structopt(short, long)]
+    bucket: String,
+
+    /// A file with one `CALLER TOKEN` pair per line. Callers send their token as a bearer token.
+    #[structopt(short, long)]
+    tokens: PathBuf,
+
+    /// Only presign keys under this prefix. Can be repeated.
+    #[structopt(short, long)]
+    prefix: Vec<String>,
+
+    /// Only presign uploads of this content type, or of any subtype with `image/*`.
+    /// Can be repeated.
+    #[structopt(long)]
+    content_type: Vec<String>,
+
+    /// The most bytes one upload can be.
+    #[structopt(long)]
+    max_size: Option<u64>,
+
+    /// The longest, in seconds, that a presigned request can last.
+    #[structopt(long, default_value_t = DEFAULT_EXPIRY.as_secs())]
+    max_expiry: u64,
+
+    /// Sign with short-lived credentials for this role, instead of your own.
+    #[structopt(long)]
+    role_arn: Option<String>,
+
+    /// The local port to listen on.
+    #[structopt(long, default_value_t = 3000)]
+    port: u16,
+
+    /// The AWS Region.
+    #[structopt(short, long)]
+    region: Option<String>,
+
+    /// Whether to display additional information.
+    #[structopt(short, long)]
+    verbose: bool,
+}
+
+/// Someone allowed to ask for presigned requests.
+struct Caller {
+    name: String,
+    token: String,
+}
+
+struct Service {
+    presigner: Presigner,
+    callers: Vec<Caller>,
+}
+
+type Reply<T> = Result<Json<T>, (StatusCode, String)>;
+
+impl Service {
+    /// The name of the caller whose bearer token is in `headers`.
+    fn authenticate(&self, headers: &HeaderMap) -> Result<&str, (StatusCode, String)> {
+        let token = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Missing bearer token".to_string()))?;
+        self.callers
+            .iter()
+            .find(|caller| same_token(&caller.token, token))
+            .map(|caller| caller.name.as_str())
+            .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Unknown bearer token".to_string()))
+    }
+
+    fn expires_in(&self, seconds: Option<u64>) -> Duration {
+        seconds
+            .map(Duration::from_secs)
+            .unwrap_or_else(|| self.presigner.policy().max_expiry().min(DEFAULT_EXPIRY))
+    }
+}
+
+/// Compare tokens in the same time whether or not they match, so response times don't give
+/// away how much of a guessed token was right.
+fn same_token(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn forbidden(err: S3ExampleError) -> (StatusCode, String) {
+    (StatusCode::FORBIDDEN, err.to_string())
+}
+
+fn failed(err: S3ExampleError) -> (StatusCode, String) {
+    warn!("{err}");
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to presign the request".to_string(),
+    )
+}
+
+#[derive(Deserialize)]
+struct GetRequest {
+    key: String,
+    expires_in: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct UploadRequest {
+    key: String,
+    expires_in: Option<u64>,
+    content_type: Option<String>,
+    /// The exact size for a PUT, or the most bytes for a POST.
+    size: Option<u64>,
+}
+
+async fn presign_get(
+    Extension(service): Extension<Arc<Service>>,
+    headers: HeaderMap,
+    Json(request): Json<GetRequest>,
+) -> Reply<PresignedUrl> {
+    let caller = service.authenticate(&headers)?;
+    let expires_in = service.expires_in(request.expires_in);
+    let presigner = &service.presigner;
+    presigner
+        .policy()
+        .check_get(&request.key, expires_in)
+        .map_err(forbidden)?;
+    let presigned = presigner
+        .presign_get(&request.key, expires_in)
+        .await
+        .map_err(failed)?;
+    info!(caller, key = %request.key, "Presigned GET");
+    Ok(Json(PresignedUrl::new(&presigned, expires_in)))
+}
+
+async fn presign_put(
+    Extension(service): Extension<Arc<Service>>,
+    headers: HeaderMap,
+    Json(request): Json<UploadRequest>,
+) -> Reply<PresignedUrl> {
+    let caller = service.authenticate(&headers)?;
+    let expires_in = service.expires_in(request.expires_in);
+    let content_type = request.content_type.as_deref();
+    let presigner = &service.presigner;
+    presigner
+        .policy()
+        .check_put(&request.key, expires_in, content_type, request.size)
+        .map_err(forbidden)?;
+    let presigned = presigner
+        .presign_put(&request.key, expires_in, content_type, request.size)
+        .await
+        .map_err(failed)?;
+    info!(caller, key = %request.key, "Presigned PUT");
+    Ok(Json(PresignedUrl::new(&presigned, expires_in)))
+}
+
+async fn presign_post(
+    Extension(service): Extension<Arc<Service>>,
+    headers: HeaderMap,
+    Json(request): Json<UploadRequest>,
+) -> Reply<PresignedPost> {
+    let caller = service.authenticate(&headers)?;
+    let expires_in = service.expires_in(request.expires_in);
+    let content_type = request.content_type.as_deref();
+    let presigner = &service.presigner;
+    presigner
+        .policy()
+        .check_post(&request.key, expires_in, content_type, request.size)
+        .map_err(forbidden)?;
+    let presigned = presigner
+        .presign_post(&request.key, expires_in, content_type, request.size)
+        .await
+        .map_err(failed)?;
+    info!(caller, key = %request.key, "Presigned POST");
+    Ok(Json(presigned))
+}
+
+fn load_callers(path: &Path) -> Result<Vec<Caller>, S3ExampleError> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|err| S3ExampleError::new(format!("Failed to read {}: {err}", path.display())))?;
+    let callers = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.split_once(char::is_whitespace) {
+            Some((name, token)) if !token.trim().is_empty() => Ok(Caller {
+                name: name.to_string(),
+                token: token.trim().to_string(),
+            }),
+            _ => Err(S3ExampleError::new(format!(
+                "Expected `CALLER TOKEN` in {}, found `{line}`",
+                path.display()
+            ))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if callers.is_empty() {
+        return Err(S3ExampleError::new(format!(
+            "No callers in {}",
+            path.display()
+        )));
+    }
+    Ok(callers)
+}
+
+/// Runs an HTTP service that hands out presigned requests for a bucket to callers with a token.
+/// `POST /get`, `/put`, and `/post` take a JSON body with a `key`, and optionally `expires_in`
+/// seconds; `/put` and `/post` also take a `content_type` and `size`.
+/// # Arguments
+///
+/// * `-b BUCKET` - The name of the bucket.
+/// * `-t TOKENS` - A file with one `CALLER TOKEN` pair per line.
+/// * `[-p PREFIX]` - Only presign keys under this prefix.
+/// * `[--content-type TYPE]` - Only presign uploads of this content type.
+/// * `[--max-size BYTES]` - The most bytes one upload can be.
+/// * `[--max-expiry SECONDS]` - The longest a presigned request can last.
+///   The default is 900 (15 minutes).
+/// * `[--role-arn ARN]` - Sign with short-lived credentials for this role.
+///   Requests can't last longer than 12 hours with a role.
+/// * `[--port PORT]` - The local port to listen on. The default is 3000.
+/// * `[-r REGION]` - The Region in which the client is created.
+///   If not supplied, uses the value of the **AWS_REGION** environment variable.
+///   If the environment variable is not set, defaults to **us-west-2**.
+/// * `[-v]` - Whether to display additional information.
+#[tokio::main]
+async fn main() -> Result<(), S3ExampleError> {
+    tracing_subscriber::fmt::init();
+
+    let Opt {
+        bucket,
+        tokens,
+        prefix,
+        content_type,
+        max_size,
+        max_expiry,
+        role_arn,
+        port,
+        region,
+        verbose,
+    } = Opt::parse();
+
+    let region_provider = RegionProviderChain::first_try(region.map(Region::new))
+        .or_default_provider()
+        .or_else(Region::new("us-west-2"));
+    let shared_config = aws_config::from_env().region(region_provider).load().await;
+
+    if verbose {
+        println!("S3 client version: {}", PKG_VERSION);
+        println!("Region:            {}", shared_config.region().unwrap());
+        println!("Bucket:            {}", &bucket);
+        println!("Max expiry:        {} seconds", max_expiry);
+        println!();
+    }
+
+    let mut policy = PresignPolicy::new().with_max_expiry(Duration::from_secs(max_expiry));
+    for prefix in prefix {
+        policy = policy.with_allowed_prefix(prefix);
+    }
+    for content_type in content_type {
+        policy = policy.with_content_type(content_type);
+    }
+    if let Some(max_size) = max_size {
+        policy = policy.with_max_size(max_size);
+    }
+
+    let (client, policy) = match role_arn {
+        Some(role_arn) => assume_role_client(&shared_config, role_arn, policy).await,
+        None => (Client::new(&shared_config), policy),
+    };
+    let service = Service {
+        presigner: Presigner::new(client, bucket).with_policy(policy),
+        callers: load_callers(&tokens)?,
+    };
+
+    let app = Router::new()
+        .route("/get", post(presign_get))
+        .route("/put", post(presign_put))
+        .route("/post", post(presign_post))
+        .layer(Extension(Arc::new(service)));
+
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    eprintln!("Listening on {addr}");
+    axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .with_graceful_shutdown(async {
+            tokio::signal::ctrl_c().await.ok();
+        })
+        .await
+        .map_err(|err| S3ExampleError::new(format!("Server failed: {err}")))
+}
@@ -4,10 +4,11 @@
 #![allow(clippy::result_large_err)]
 
 use aws_config::meta::region::RegionProviderChain;
-use aws_sdk_s3::presigning::PresigningConfig;
 use aws_sdk_s3::{config::Region, meta::PKG_VERSION, Client};
 use clap::Parser;
 use s3_code_examples::error::S3ExampleError;
+use s3_code_examples::presign::{PresignPolicy, Presigner, DEFAULT_EXPIRY, MAX_PRESIGN_EXPIRY};
+use std::time::Duration;
 
 #[derive(Debug, Parser)]
 struct Opt {
@@ -35,23 +36,12 @@ struct Opt {
 // Adds an object to a bucket and returns a public URI.
 // snippet-start:[s3.rust.put-object-presigned]
 async fn put_object(
-    client: &Client,
-    bucket: &str,
+    presigner: &Presigner,
     object: &str,
-    expires_in: u64,
+    expires_in: Duration,
 ) -> Result<String, S3ExampleError> {
-    let expires_in: std::time::Duration = std::time::Duration::from_secs(expires_in);
-    let expires_in: aws_sdk_s3::presigning::PresigningConfig =
-        PresigningConfig::expires_in(expires_in).map_err(|err| {
-            S3ExampleError::new(format!(
-                "Failed to convert expiration to PresigningConfig: {err:?}"
-            ))
-        })?;
-    let presigned_request = client
-        .put_object()
-        .bucket(bucket)
-        .key(object)
-        .presigned(expires_in)
+    let presigned_request = presigner
+        .presign_put(object, expires_in, None, None)
         .await?;
 
     Ok(presigned_request.uri().into())
@@ -86,6 +76,7 @@ async fn main() -> Result<(), S3ExampleError> {
         .or_else(Region::new("us-west-2"));
     let shared_config = aws_config::from_env().region(region_provider).load().await;
     let client = Client::new(&shared_config);
+    let expires_in = expires_in.unwrap_or(DEFAULT_EXPIRY.as_secs());
 
     println!();
 
@@ -94,11 +85,13 @@ async fn main() -> Result<(), S3ExampleError> {
         println!("Region:            {}", shared_config.region().unwrap());
         println!("Bucket:            {}", &bucket);
         println!("Object:            {}", &object);
-        println!("Expires in:        {} seconds", expires_in.unwrap_or(900));
+        println!("Expires in:        {} seconds", expires_in);
         println!();
     }
 
-    let uri = put_object(&client, &bucket, &object, expires_in.unwrap_or(900)).await?;
+    let presigner = Presigner::new(client, bucket)
+        .with_policy(PresignPolicy::new().with_max_expiry(MAX_PRESIGN_EXPIRY));
+    let uri = put_object(&presigner, &object, Duration::from_secs(expires_in)).await?;
 
     println!("Presigned PUT URI: {}", uri);
 
@@ -0,0 +1,156 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{path::PathBuf, process::exit};
+
+use aws_config::meta::region::RegionProviderChain;
+use aws_sdk_s3::{config::Region, meta::PKG_VERSION, Client};
+use clap::Parser;
+use s3_code_examples::{
+    progress::{ProgressBar, ProgressTracker},
+    sync::{Compare, Direction, Filter, Syncer},
+    transfer::DEFAULT_CONCURRENCY,
+};
+
+#[derive(Debug, Parser)]
+struct Opt {
+    /// Which side to copy from.
+    #[structopt(value_enum)]
+    direction: Direction,
+
+    /// The local directory.
+    #[structopt(short, long)]
+    local: PathBuf,
+
+    /// The name of the bucket.
+    #[structopt(short, long)]
+    bucket: String,
+
+    /// The prefix in the bucket to sync with.
+    #[structopt(short, long, default_value = "")]
+    prefix: String,
+
+    /// Only sync paths that match this glob. Can be repeated.
+    #[structopt(long)]
+    include: Vec<String>,
+
+    /// Don't sync paths that match this glob. Can be repeated.
+    #[structopt(long)]
+    exclude: Vec<String>,
+
+    /// How to tell whether a file on both sides has changed.
+    #[structopt(long, value_enum, default_value_t = Compare::Time)]
+    compare: Compare,
+
+    /// Delete files in the destination that aren't in the source.
+    #[structopt(long)]
+    delete: bool,
+
+    /// Print what would change, without changing anything.
+    #[structopt(long)]
+    dry_run: bool,
+
+    /// How many files to transfer at once.
+    #[structopt(long, default_value_t = DEFAULT_CONCURRENCY)]
+    concurrency: usize,
+
+    /// The AWS Region.
+    #[structopt(short, long)]
+    region: Option<String>,
+
+    /// Whether to display additional information.
+    #[structopt(short, long)]
+    verbose: bool,
+}
+
+/// Syncs a local directory with a bucket prefix, copying only what changed.
+/// # Arguments
+///
+/// * `upload|download` - Make the bucket match the directory, or the directory match the bucket.
+/// * `-l LOCAL` - The local directory.
+/// * `-b BUCKET` - The name of the bucket.
+/// * `[-p PREFIX]` - The prefix in the bucket to sync with.
+/// * `[--include GLOB]` - Only sync paths that match this glob.
+/// * `[--exclude GLOB]` - Don't sync paths that match this glob.
+/// * `[--compare size|time|checksum]` - How to tell whether a file has changed.
+/// * `[--delete]` - Delete files in the destination that aren't in the source.
+/// * `[--dry-run]` - Print what would change, without changing anything.
+/// * `[--concurrency N]` - How many files to transfer at once.
+/// * `[-r REGION]` - The Region in which the client is created.
+///    If not supplied, uses the value of the **AWS_REGION** environment variable.
+///    If the environment variable is not set, defaults to **us-west-2**.
+/// * `[-v]` - Whether to display additional information.
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let Opt {
+        direction,
+        local,
+        bucket,
+        prefix,
+        include,
+        exclude,
+        compare,
+        delete,
+        dry_run,
+        concurrency,
+        region,
+        verbose,
+    } = Opt::parse();
+
+    let region_provider = RegionProviderChain::first_try(region.map(Region::new))
+        .or_default_provider()
+        .or_else(Region::new("us-west-2"));
+
+    if verbose {
+        println!("S3 client version: {}", PKG_VERSION);
+        println!(
+            "Region:            {}",
+            region_provider.region().await.unwrap().as_ref()
+        );
+        println!();
+    }
+
+    let filter = match Filter::new(&include, &exclude) {
+        Ok(filter) => filter,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            exit(1);
+        }
+    };
+
+    let shared_config = aws_config::from_env().region(region_provider).load().await;
+    let client = Client::new(&shared_config);
+    let syncer = Syncer::new(client, local, bucket, &prefix)
+        .with_filter(filter)
+        .with_compare(compare)
+        .with_delete(delete)
+        .with_concurrency(concurrency);
+
+    let plan = match syncer.plan(direction).await {
+        Ok(plan) => plan,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            exit(1);
+        }
+    };
+
+    if plan.is_empty() {
+        println!("Already in sync.");
+        return;
+    }
+    println!("{plan}");
+    if dry_run {
+        return;
+    }
+
+    let report = syncer
+        .with_progress(ProgressTracker::new(ProgressBar::new("Syncing")))
+        .apply(&plan)
+        .await;
+    print!("{report}");
+    if !report.failed.is_empty() {
+        exit(1);
+    }
+}
@@ -1,19 +1,22 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 
-#![allow(clippy::result_large_err)]
-
 use aws_config::meta::region::RegionProviderChain;
-use aws_sdk_s3::types::{
-    CompressionType, CsvInput, ExpressionType, FileHeaderInfo, InputSerialization, JsonOutput,
-    OutputSerialization, SelectObjectContentEventStream,
-};
 use aws_sdk_s3::{config::Region, meta::PKG_VERSION, Client};
 use clap::Parser;
-use s3_code_examples::error::S3ExampleError;
-use serde::de::IgnoredAny;
+use s3_code_examples::{
+    error::S3ExampleError,
+    select::{Compression, CsvFormat, Header, InputFormat, SelectEvent, SelectQuery},
+};
 use serde::Deserialize;
 
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum Format {
+    Csv,
+    JsonLines,
+    Parquet,
+}
+
 #[derive(Debug, Parser)]
 struct Opt {
     /// The AWS Region.
@@ -24,15 +27,32 @@ struct Opt {
     #[structopt(short, long)]
     bucket: String,
 
-    /// The object key to scan. This example expects the object to be an uncompressed CSV file with:
+    /// The object key to scan. This example expects the object to have these columns, as in
+    /// file.csv:
     ///
-    /// Name,PhoneNumber,City,Occupation
+    /// Name,PhoneNumber,City,Occupation,Description
     /// Person1,(nnn) nnn-nnnn,City1,Occupation1,Comment
     /// ...
     /// PersonN,(nnn) nnn-nnnn,CityN,OccupationN,Comment
     #[structopt(short, long)]
     object: String,
 
+    /// The format of the object.
+    #[structopt(long, value_enum, default_value_t = Format::Csv)]
+    format: Format,
+
+    /// How to treat the first line of a CSV object.
+    #[structopt(long, value_enum, default_value_t = Header::Use)]
+    header: Header,
+
+    /// The character between fields of a CSV object.
+    #[structopt(long, default_value_t = ',')]
+    delimiter: char,
+
+    /// How the object is compressed.
+    #[structopt(long, value_enum, default_value_t = Compression::None)]
+    compression: Compression,
+
     /// The name of the person to scan for. This used as a prefix search
     #[structopt(short, long)]
     name: String,
@@ -52,106 +72,52 @@ pub struct Record {
     pub description: String,
 }
 
-fn is_valid_json(data: &str) -> bool {
-    serde_json::from_str::<IgnoredAny>(data).is_ok()
-}
-
 // Get object content.
-// snippet-start:[s3.rust.select-object-content]
+// snippet-start:[s3.rust.select-object-content.query]
 async fn get_content(
     client: &Client,
     bucket: &str,
     object: &str,
     name: &str,
+    input: InputFormat,
+    compression: Compression,
 ) -> Result<(), S3ExampleError> {
     // To escape a single quote, use two single quotes.
     let name = name.replace('\'', "''");
     let person: String = format!("SELECT * FROM s3object s where s.Name like '{name}%'");
     tracing::info!(query = %person);
 
-    let mut output = client
-        .select_object_content()
-        .bucket(bucket)
-        .key(object)
-        .expression_type(ExpressionType::Sql)
-        .expression(person)
-        .input_serialization(
-            InputSerialization::builder()
-                .csv(
-                    CsvInput::builder()
-                        .file_header_info(FileHeaderInfo::Use)
-                        .build(),
-                )
-                .compression_type(CompressionType::None)
-                .build(),
-        )
-        .output_serialization(
-            OutputSerialization::builder()
-                // By default, the output delimiter is `\n`
-                .json(JsonOutput::builder().build())
-                .build(),
-        )
-        .send()
+    let mut results = SelectQuery::new(person)
+        .with_input(input)
+        .with_compression(compression)
+        .send::<Record>(client, bucket, object)
         .await?;
-    let mut processed_records: Vec<Record> = vec![];
-    let mut buf: String = String::new();
 
-    while let Some(event) = output.payload.recv().await? {
+    // Rows arrive whole, even when S3 splits one across Records events.
+    let mut processed_records: Vec<Record> = vec![];
+    while let Some(event) = results.next().await? {
         match event {
-            SelectObjectContentEventStream::Records(records) => {
-                let records_str = records.payload().map(|p| p.as_ref()).unwrap_or_default();
-                let records_str = std::str::from_utf8(records_str).expect("invalid utf8 from s3");
-                for line in records_str.lines() {
-                    // It's possible for one record to be split onto multiple lines
-                    if let Some(record) = parse_line_buffered(&mut buf, line)? {
-                        processed_records.push(record);
-                    }
-                }
-            }
-            SelectObjectContentEventStream::Stats(stats) => {
-                println!("Stats: {:?}", stats.details().unwrap());
-            }
-            SelectObjectContentEventStream::Progress(progress) => {
-                println!("Progress: {:?}", progress.details().unwrap());
-            }
-            SelectObjectContentEventStream::Cont(_) => {
-                println!("Continuation Event");
-            }
-            SelectObjectContentEventStream::End(_) => {
-                println!("End Event");
-            }
-            otherwise => panic!("Unknown event type: {:?}", otherwise),
+            SelectEvent::Row(record) => processed_records.push(record),
+            SelectEvent::Progress(progress) => println!("Progress: {progress:?}"),
+            SelectEvent::Stats(stats) => println!("Stats: {stats:?}"),
         }
     }
     println!("Found the following records:\n{:#?}", processed_records);
 
     Ok(())
 }
-
-/// Parse a new line &str, potentially using content from the previous line
-fn parse_line_buffered(buf: &mut String, line: &str) -> Result<Option<Record>, S3ExampleError> {
-    if buf.is_empty() && is_valid_json(line) {
-        Ok(Some(serde_json::from_str(line).map_err(|err| {
-            S3ExampleError::new(format!("Failed to parse JSON: {err:?}"))
-        })?))
-    } else {
-        buf.push_str(line);
-        if is_valid_json(buf) {
-            let result = serde_json::from_str(buf)
-                .map_err(|err| S3ExampleError::new(format!("Failed to parse JSON: {err:?}")))?;
-            buf.clear();
-            Ok(Some(result))
-        } else {
-            Ok(None)
-        }
-    }
-}
-// snippet-end:[s3.rust.select-object-content]
+// snippet-end:[s3.rust.select-object-content.query]
 
 /// Uses an SQL expression to retrieve content from an object in a bucket.
 /// # Arguments
 ///
 /// * `-b BUCKET` - The name of the bucket.
+/// * `-o OBJECT` - The object to scan.
+/// * `-n NAME` - The start of the names to look for.
+/// * `[--format csv|json-lines|parquet]` - The format of the object.
+/// * `[--header use|ignore|none]` - How to treat the first line of a CSV object.
+/// * `[--delimiter CHAR]` - The character between fields of a CSV object.
+/// * `[--compression none|gzip|bzip2]` - How the object is compressed.
 /// * `[-r REGION]` - The Region in which the client is created.
 ///   If not supplied, uses the value of the **AWS_REGION** environment variable.
 ///   If the environment variable is not set, defaults to **us-west-2**.
@@ -165,9 +131,23 @@ async fn main() -> Result<(), S3ExampleError> {
         bucket,
         object,
         name,
+        format,
+        header,
+        delimiter,
+        compression,
         verbose,
     } = Opt::parse();
 
+    let input = match format {
+        Format::Csv => InputFormat::Csv(
+            CsvFormat::default()
+                .with_header(header)
+                .with_field_delimiter(delimiter),
+        ),
+        Format::JsonLines => InputFormat::JsonLines,
+        Format::Parquet => InputFormat::Parquet,
+    };
+
     let region_provider = RegionProviderChain::first_try(region.map(Region::new))
         .or_default_provider()
         .or_else(Region::new("us-east-2"));
@@ -186,5 +166,5 @@ async fn main() -> Result<(), S3ExampleError> {
         println!();
     }
 
-    get_content(&client, &bucket, &object, &name).await
+    get_content(&client, &bucket, &object, &name, input, compression).await
 }
@@ -3,6 +3,13 @@
 
 use error::S3ExampleError;
 pub mod error;
+pub mod lifecycle;
+pub mod presign;
+pub mod progress;
+pub mod select;
+pub mod sync;
+pub mod transfer;
+pub mod versioning;
 
 // snippet-start:[s3.rust.copy_object]
 /// Copy an object from one bucket to another.
@@ -0,0 +1,656 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Manage a bucket's lifecycle configuration from a YAML or JSON file.
+//!
+//! A policy file lists rules by ID. Each rule picks objects by prefix and tags, and says when to
+//! move them to cheaper storage classes and when to delete them:
+//!
+//! ```yaml
+//! rules:
+//!   - id: archive-logs
+//!     prefix: logs/
+//!     transitions:
+//!       - days: 30
+//!         storage_class: STANDARD_IA
+//!       - days: 90
+//!         storage_class: GLACIER
+//!     expiration_days: 365
+//!     noncurrent_expiration_days: 30
+//!     abort_incomplete_multipart_days: 7
+//! ```
+//!
+//! [LifecyclePolicy::problems] catches the mistakes S3 would otherwise reject the whole
+//! configuration for, and reports all of them at once.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::Path,
+};
+
+use aws_sdk_s3::{
+    error::ProvideErrorMetadata,
+    types::{
+        AbortIncompleteMultipartUpload, BucketLifecycleConfiguration, ExpirationStatus,
+        LifecycleExpiration, LifecycleRule, LifecycleRuleAndOperator, LifecycleRuleFilter,
+        NoncurrentVersionExpiration, NoncurrentVersionTransition, Tag, Transition,
+        TransitionStorageClass,
+    },
+    Client,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::S3ExampleError;
+
+/// A bucket can have at most this many lifecycle rules.
+pub const MAX_RULES: usize = 1000;
+
+/// Storage classes that keep objects for at least 30 days, so objects can't move to them
+/// any sooner.
+const THIRTY_DAY_CLASSES: [&str; 2] = ["STANDARD_IA", "ONEZONE_IA"];
+
+/// Move objects to `storage_class` `days` after they were created, or for noncurrent
+/// transitions, after they stopped being the current version.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransitionSpec {
+    pub days: u32,
+    pub storage_class: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSpec {
+    pub id: String,
+    #[serde(default = "enabled", skip_serializing_if = "is_enabled")]
+    pub enabled: bool,
+    /// Only objects with keys that start with this. Empty for every object.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub prefix: String,
+    /// Only objects with all of these tags.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transitions: Vec<TransitionSpec>,
+    /// Delete the current version this many days after it was created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiration_days: Option<u32>,
+    /// Remove delete markers that no longer have any versions behind them.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub expired_object_delete_marker: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub noncurrent_transitions: Vec<TransitionSpec>,
+    /// Delete noncurrent versions this many days after they were replaced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub noncurrent_expiration_days: Option<u32>,
+    /// Keep this many of the newest noncurrent versions, whatever their age.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_noncurrent_versions: Option<u32>,
+    /// Abort multipart uploads that haven't finished this many days after they started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abort_incomplete_multipart_days: Option<u32>,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+fn is_enabled(enabled: &bool) -> bool {
+    *enabled
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl RuleSpec {
+    fn has_action(&self) -> bool {
+        !self.transitions.is_empty()
+            || self.expiration_days.is_some()
+            || self.expired_object_delete_marker
+            || !self.noncurrent_transitions.is_empty()
+            || self.noncurrent_expiration_days.is_some()
+            || self.abort_incomplete_multipart_days.is_some()
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let id = &self.id;
+        let mut problems = Vec::new();
+        if id.is_empty() || id.len() > 255 {
+            problems.push(format!("Rule ID {id:?} must be 1 to 255 characters"));
+        }
+        if !self.has_action() {
+            problems.push(format!("Rule {id} doesn't do anything"));
+        }
+
+        for (kind, transitions) in [
+            ("transition", &self.transitions),
+            ("noncurrent transition", &self.noncurrent_transitions),
+        ] {
+            let mut classes = HashSet::new();
+            for transition in transitions {
+                let class = transition.storage_class.as_str();
+                if !TransitionStorageClass::values().contains(&class) {
+                    problems.push(format!(
+                        "Rule {id} has a {kind} to unknown storage class {class}"
+                    ));
+                }
+                if THIRTY_DAY_CLASSES.contains(&class) && transition.days < 30 {
+                    problems.push(format!(
+                        "Rule {id} moves objects to {class} after {} days, but {class} needs at least 30",
+                        transition.days
+                    ));
+                }
+                if !classes.insert(class) {
+                    problems.push(format!("Rule {id} has two {kind}s to {class}"));
+                }
+            }
+            let days: Vec<u32> = transitions.iter().map(|t| t.days).collect();
+            if days.windows(2).any(|pair| pair[0] >= pair[1]) {
+                problems.push(format!(
+                    "Rule {id} must list its {kind}s in order of increasing days"
+                ));
+            }
+        }
+
+        if let Some(expiration) = self.expiration_days {
+            if expiration == 0 {
+                problems.push(format!(
+                    "Rule {id} must expire objects after at least 1 day"
+                ));
+            }
+            if let Some(last) = self.transitions.iter().map(|t| t.days).max() {
+                if expiration <= last {
+                    problems.push(format!(
+                        "Rule {id} expires objects after {expiration} days, before its last transition at {last} days"
+                    ));
+                }
+            }
+            if self.expired_object_delete_marker {
+                problems.push(format!(
+                    "Rule {id} can't set both expiration_days and expired_object_delete_marker"
+                ));
+            }
+        }
+        if self.expired_object_delete_marker && !self.tags.is_empty() {
+            problems.push(format!(
+                "Rule {id} can't remove expired delete markers with a tag filter"
+            ));
+        }
+
+        if self.noncurrent_expiration_days == Some(0) {
+            problems.push(format!(
+                "Rule {id} must expire noncurrent versions after at least 1 day"
+            ));
+        }
+        if let (Some(expiration), Some(last)) = (
+            self.noncurrent_expiration_days,
+            self.noncurrent_transitions.iter().map(|t| t.days).max(),
+        ) {
+            if expiration <= last {
+                problems.push(format!(
+                    "Rule {id} expires noncurrent versions after {expiration} days, before its last noncurrent transition at {last} days"
+                ));
+            }
+        }
+        if let Some(keep) = self.keep_noncurrent_versions {
+            if !(1..=100).contains(&keep) {
+                problems.push(format!(
+                    "Rule {id} must keep 1 to 100 noncurrent versions, not {keep}"
+                ));
+            }
+            if self.noncurrent_expiration_days.is_none() && self.noncurrent_transitions.is_empty() {
+                problems.push(format!(
+                    "Rule {id} sets keep_noncurrent_versions without a noncurrent action"
+                ));
+            }
+        }
+
+        match self.abort_incomplete_multipart_days {
+            Some(0) => problems.push(format!(
+                "Rule {id} must abort multipart uploads after at least 1 day"
+            )),
+            Some(_) if !self.tags.is_empty() => problems.push(format!(
+                "Rule {id} can't abort multipart uploads with a tag filter"
+            )),
+            _ => {}
+        }
+        problems
+    }
+
+    fn to_rule(&self) -> Result<LifecycleRule, S3ExampleError> {
+        let build_error = |err| {
+            S3ExampleError::new(format!(
+                "Failed to build lifecycle rule {}: {err:?}",
+                self.id
+            ))
+        };
+
+        let tags = self
+            .tags
+            .iter()
+            .map(|(key, value)| Tag::builder().key(key).value(value).build())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(build_error)?;
+        let filter = match tags.len() {
+            0 => LifecycleRuleFilter::builder().prefix(&self.prefix).build(),
+            1 if self.prefix.is_empty() => LifecycleRuleFilter::builder()
+                .tag(tags.into_iter().next().unwrap())
+                .build(),
+            _ => LifecycleRuleFilter::builder()
+                .and(
+                    LifecycleRuleAndOperator::builder()
+                        .prefix(&self.prefix)
+                        .set_tags(Some(tags))
+                        .build(),
+                )
+                .build(),
+        };
+
+        let expiration = (self.expiration_days.is_some() || self.expired_object_delete_marker)
+            .then(|| {
+                let expiration =
+                    LifecycleExpiration::builder().set_days(self.expiration_days.map(|d| d as i32));
+                if self.expired_object_delete_marker {
+                    expiration.expired_object_delete_marker(true)
+                } else {
+                    expiration
+                }
+                .build()
+            });
+
+        LifecycleRule::builder()
+            .id(&self.id)
+            .status(if self.enabled {
+                ExpirationStatus::Enabled
+            } else {
+                ExpirationStatus::Disabled
+            })
+            .filter(filter)
+            .set_transitions(Some(
+                self.transitions
+                    .iter()
+                    .map(|t| {
+                        Transition::builder()
+                            .days(t.days as i32)
+                            .storage_class(TransitionStorageClass::from(t.storage_class.as_str()))
+                            .build()
+                    })
+                    .collect(),
+            ))
+            .set_expiration(expiration)
+            .set_noncurrent_version_transitions(Some(
+                self.noncurrent_transitions
+                    .iter()
+                    .map(|t| {
+                        NoncurrentVersionTransition::builder()
+                            .noncurrent_days(t.days as i32)
+                            .storage_class(TransitionStorageClass::from(t.storage_class.as_str()))
+                            .set_newer_noncurrent_versions(
+                                self.keep_noncurrent_versions.map(|n| n as i32),
+                            )
+                            .build()
+                    })
+                    .collect(),
+            ))
+            .set_noncurrent_version_expiration(self.noncurrent_expiration_days.map(|days| {
+                NoncurrentVersionExpiration::builder()
+                    .noncurrent_days(days as i32)
+                    .set_newer_noncurrent_versions(self.keep_noncurrent_versions.map(|n| n as i32))
+                    .build()
+            }))
+            .set_abort_incomplete_multipart_upload(self.abort_incomplete_multipart_days.map(
+                |days| {
+                    AbortIncompleteMultipartUpload::builder()
+                        .days_after_initiation(days as i32)
+                        .build()
+                },
+            ))
+            .build()
+            .map_err(build_error)
+    }
+}
+
+impl From<&LifecycleRule> for RuleSpec {
+    fn from(rule: &LifecycleRule) -> Self {
+        let days = |days: Option<i32>| days.map(|d| d.max(0) as u32);
+        let (prefix, tags) = match rule.filter() {
+            Some(filter) => match (filter.prefix(), filter.tag(), filter.and()) {
+                (_, _, Some(and)) => (
+                    and.prefix().unwrap_or_default().to_string(),
+                    and.tags().to_vec(),
+                ),
+                (_, Some(tag), None) => (String::new(), vec![tag.clone()]),
+                (prefix, None, None) => (prefix.unwrap_or_default().to_string(), vec![]),
+            },
+            #[allow(deprecated)]
+            None => (rule.prefix().unwrap_or_default().to_string(), vec![]),
+        };
+        RuleSpec {
+            id: rule.id().unwrap_or_default().to_string(),
+            enabled: rule.status() == &ExpirationStatus::Enabled,
+            prefix,
+            tags: tags
+                .iter()
+                .map(|tag| (tag.key().to_string(), tag.value().to_string()))
+                .collect(),
+            transitions: rule
+                .transitions()
+                .iter()
+                .map(|t| TransitionSpec {
+                    days: days(t.days()).unwrap_or_default(),
+                    storage_class: t
+                        .storage_class()
+                        .map(|c| c.as_str().to_string())
+                        .unwrap_or_default(),
+                })
+                .collect(),
+            expiration_days: rule.expiration().and_then(|e| days(e.days())),
+            expired_object_delete_marker: rule
+                .expiration()
+                .and_then(|e| e.expired_object_delete_marker())
+                .unwrap_or_default(),
+            noncurrent_transitions: rule
+                .noncurrent_version_transitions()
+                .iter()
+                .map(|t| TransitionSpec {
+                    days: days(t.noncurrent_days()).unwrap_or_default(),
+                    storage_class: t
+                        .storage_class()
+                        .map(|c| c.as_str().to_string())
+                        .unwrap_or_default(),
+                })
+                .collect(),
+            noncurrent_expiration_days: rule
+                .noncurrent_version_expiration()
+                .and_then(|e| days(e.noncurrent_days())),
+            // The same on every noncurrent action, and a rule might only have transitions.
+            keep_noncurrent_versions: rule
+                .noncurrent_version_expiration()
+                .and_then(|e| days(e.newer_noncurrent_versions()))
+                .or_else(|| {
+                    rule.noncurrent_version_transitions()
+                        .iter()
+                        .find_map(|t| days(t.newer_noncurrent_versions()))
+                }),
+            abort_incomplete_multipart_days: rule
+                .abort_incomplete_multipart_upload()
+                .and_then(|a| days(a.days_after_initiation())),
+        }
+    }
+}
+
+/// Every lifecycle rule for a bucket.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LifecyclePolicy {
+    pub rules: Vec<RuleSpec>,
+}
+
+impl LifecyclePolicy {
+    pub fn from_yaml(yaml: &str) -> Result<Self, S3ExampleError> {
+        serde_yaml::from_str(yaml)
+            .map_err(|err| S3ExampleError::new(format!("Invalid lifecycle policy: {err}")))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, S3ExampleError> {
+        serde_json::from_str(json)
+            .map_err(|err| S3ExampleError::new(format!("Invalid lifecycle policy: {err}")))
+    }
+
+    /// Read a policy from a `.json` file, or a YAML file for any other extension.
+    pub fn load(path: &Path) -> Result<Self, S3ExampleError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| S3ExampleError::new(format!("Failed to read {path:?}: {err:?}")))?;
+        let policy = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::from_json(&contents),
+            _ => Self::from_yaml(&contents),
+        };
+        policy.map_err(|err| err.add_message(path.display().to_string()))
+    }
+
+    pub fn to_yaml(&self) -> Result<String, S3ExampleError> {
+        serde_yaml::to_string(self)
+            .map_err(|err| S3ExampleError::new(format!("Failed to write policy: {err}")))
+    }
+
+    /// Everything S3 would reject in this policy, or nothing if it is valid.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.rules.is_empty() {
+            problems.push("A policy needs at least one rule".to_string());
+        }
+        if self.rules.len() > MAX_RULES {
+            problems.push(format!(
+                "A bucket can have at most {MAX_RULES} rules, not {}",
+                self.rules.len()
+            ));
+        }
+        let mut ids = HashSet::new();
+        for rule in &self.rules {
+            if !ids.insert(rule.id.as_str()) {
+                problems.push(format!("Rule ID {} is used more than once", rule.id));
+            }
+            problems.extend(rule.problems());
+        }
+        problems
+    }
+
+    pub fn validate(&self) -> Result<(), S3ExampleError> {
+        match self.problems().as_slice() {
+            [] => Ok(()),
+            problems => Err(S3ExampleError::new(format!(
+                "Invalid lifecycle policy:\n  {}",
+                problems.join("\n  ")
+            ))),
+        }
+    }
+
+    pub fn to_configuration(&self) -> Result<BucketLifecycleConfiguration, S3ExampleError> {
+        self.validate()?;
+        let rules = self
+            .rules
+            .iter()
+            .map(RuleSpec::to_rule)
+            .collect::<Result<Vec<_>, _>>()?;
+        BucketLifecycleConfiguration::builder()
+            .set_rules(Some(rules))
+            .build()
+            .map_err(|err| {
+                S3ExampleError::new(format!("Failed to build lifecycle configuration: {err:?}"))
+            })
+    }
+}
+
+impl From<&BucketLifecycleConfiguration> for LifecyclePolicy {
+    fn from(configuration: &BucketLifecycleConfiguration) -> Self {
+        LifecyclePolicy {
+            rules: configuration.rules().iter().map(RuleSpec::from).collect(),
+        }
+    }
+}
+
+// snippet-start:[s3.rust.put_bucket_lifecycle_configuration]
+/// Replace `bucket`'s lifecycle rules with `policy`, once it passes validation.
+pub async fn apply_lifecycle(
+    client: &Client,
+    bucket: &str,
+    policy: &LifecyclePolicy,
+) -> Result<(), S3ExampleError> {
+    client
+        .put_bucket_lifecycle_configuration()
+        .bucket(bucket)
+        .lifecycle_configuration(policy.to_configuration()?)
+        .send()
+        .await?;
+    Ok(())
+}
+// snippet-end:[s3.rust.put_bucket_lifecycle_configuration]
+
+// snippet-start:[s3.rust.get_bucket_lifecycle_configuration]
+/// `bucket`'s lifecycle rules, or None if it has none.
+pub async fn get_lifecycle(
+    client: &Client,
+    bucket: &str,
+) -> Result<Option<LifecyclePolicy>, S3ExampleError> {
+    match client
+        .get_bucket_lifecycle_configuration()
+        .bucket(bucket)
+        .send()
+        .await
+    {
+        Ok(output) => Ok(Some(LifecyclePolicy {
+            rules: output.rules().iter().map(RuleSpec::from).collect(),
+        })),
+        Err(err) if err.code() == Some("NoSuchLifecycleConfiguration") => Ok(None),
+        Err(err) => Err(S3ExampleError::from(err)),
+    }
+}
+// snippet-end:[s3.rust.get_bucket_lifecycle_configuration]
+
+/// Remove every lifecycle rule from `bucket`.
+pub async fn remove_lifecycle(client: &Client, bucket: &str) -> Result<(), S3ExampleError> {
+    client
+        .delete_bucket_lifecycle()
+        .bucket(bucket)
+        .send()
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use sdk_examples_test_utils::single_shot_client;
+
+    use super::*;
+
+    const POLICY: &str = r#"
+rules:
+  - id: archive-logs
+    prefix: logs/
+    transitions:
+      - days: 30
+        storage_class: STANDARD_IA
+      - days: 90
+        storage_class: GLACIER
+    expiration_days: 365
+    noncurrent_expiration_days: 30
+    keep_noncurrent_versions: 3
+    abort_incomplete_multipart_days: 7
+  - id: scratch
+    enabled: false
+    tags:
+      team: data
+    expiration_days: 1
+"#;
+
+    #[test]
+    fn test_load_policy() {
+        let policy = LifecyclePolicy::from_yaml(POLICY).unwrap();
+        assert!(policy.problems().is_empty(), "{:?}", policy.problems());
+        assert_eq!(policy.rules[0].transitions[1].storage_class, "GLACIER");
+        assert!(!policy.rules[1].enabled);
+
+        // The same policy as JSON, and back to YAML.
+        let json = serde_json::to_string(&policy).unwrap();
+        assert_eq!(LifecyclePolicy::from_json(&json).unwrap(), policy);
+        let yaml = policy.to_yaml().unwrap();
+        assert_eq!(LifecyclePolicy::from_yaml(&yaml).unwrap(), policy);
+
+        assert!(LifecyclePolicy::from_yaml("rules:\n  - id: x\n    expire_days: 3\n").is_err());
+    }
+
+    #[test]
+    fn test_round_trip_configuration() {
+        let policy = LifecyclePolicy::from_yaml(POLICY).unwrap();
+        let configuration = policy.to_configuration().unwrap();
+        assert_eq!(configuration.rules().len(), 2);
+        let rule = &configuration.rules()[0];
+        assert_eq!(rule.transitions()[0].days(), Some(30));
+        assert_eq!(
+            rule.noncurrent_version_expiration()
+                .unwrap()
+                .newer_noncurrent_versions(),
+            Some(3)
+        );
+        assert_eq!(LifecyclePolicy::from(&configuration), policy);
+    }
+
+    #[test]
+    fn test_keep_noncurrent_versions_on_transitions() {
+        let policy = LifecyclePolicy::from_yaml(
+            r#"
+rules:
+  - id: archive-versions
+    noncurrent_transitions:
+      - days: 30
+        storage_class: GLACIER
+    keep_noncurrent_versions: 5
+"#,
+        )
+        .unwrap();
+        assert!(policy.problems().is_empty(), "{:?}", policy.problems());
+        let configuration = policy.to_configuration().unwrap();
+        let rule = &configuration.rules()[0];
+        assert_eq!(
+            rule.noncurrent_version_transitions()[0].newer_noncurrent_versions(),
+            Some(5)
+        );
+        assert!(rule.noncurrent_version_expiration().is_none());
+        assert_eq!(LifecyclePolicy::from(&configuration), policy);
+    }
+
+    #[test]
+    fn test_validation() {
+        let policy = LifecyclePolicy::from_yaml(
+            r#"
+rules:
+  - id: too-soon
+    transitions:
+      - days: 10
+        storage_class: STANDARD_IA
+      - days: 5
+        storage_class: TAPE
+    expiration_days: 7
+  - id: too-soon
+  - id: tagged
+    tags:
+      env: test
+    abort_incomplete_multipart_days: 3
+    keep_noncurrent_versions: 500
+"#,
+        )
+        .unwrap();
+        let problems = policy.problems();
+        for expected in [
+            "needs at least 30",
+            "unknown storage class TAPE",
+            "increasing days",
+            "before its last transition",
+            "used more than once",
+            "doesn't do anything",
+            "with a tag filter",
+            "1 to 100 noncurrent versions",
+            "without a noncurrent action",
+        ] {
+            assert!(
+                problems.iter().any(|p| p.contains(expected)),
+                "{expected:?} not in {problems:#?}"
+            );
+        }
+        assert!(policy.to_configuration().is_err());
+        assert!(LifecyclePolicy::default().validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_missing_lifecycle() {
+        let client = single_shot_client!(
+            sdk: aws_sdk_s3,
+            status: 404,
+            response: r#"<Error><Code>NoSuchLifecycleConfiguration</Code><Message>The lifecycle configuration does not exist</Message><BucketName>bucket</BucketName></Error>"#
+        );
+
+        let resp = get_lifecycle(&client, "bucket").await;
+        assert_eq!(resp.unwrap(), None);
+    }
+}
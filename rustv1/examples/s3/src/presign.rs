@@ -0,0 +1,802 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hand out presigned GET, PUT, and POST requests for one bucket, within limits.
+//!
+//! A presigned request lets whoever holds it read or write one object without credentials of
+//! their own, until it expires. A [PresignPolicy] limits what can be signed: how long requests
+//! last, which key prefixes they can touch, which content types can be uploaded, and how big an
+//! upload can be. [Presigner] checks every request against its policy before signing it.
+//!
+//! A presigned request stops working when the credentials that signed it expire, and anyone who
+//! holds one acts with all the permissions of those credentials. [assume_role_client] signs with
+//! a role that only has the permissions presigned requests should have.
+
+use std::{
+    collections::BTreeMap,
+    time::{Duration, SystemTime},
+};
+
+use aws_config::{sts::AssumeRoleProvider, SdkConfig};
+use aws_credential_types::{provider::ProvideCredentials, Credentials};
+use aws_sdk_s3::{
+    presigning::{PresignedRequest, PresigningConfig},
+    Client,
+};
+use aws_sigv4::sign::v4::{calculate_signature, generate_signing_key};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::error::S3ExampleError;
+
+/// S3 won't accept a SigV4 presigned request that lasts longer than a week.
+pub const MAX_PRESIGN_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// How long presigned requests last unless a policy says otherwise.
+pub const DEFAULT_EXPIRY: Duration = Duration::from_secs(15 * 60);
+
+/// STS won't issue a session shorter than this.
+const MIN_SESSION_LENGTH: Duration = Duration::from_secs(15 * 60);
+
+/// STS won't issue a role session longer than this, so requests signed with an assumed role
+/// can't last any longer.
+pub const MAX_SESSION_LENGTH: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// What a [Presigner] is allowed to sign.
+///
+/// The default policy allows any key and any content type, for up to [DEFAULT_EXPIRY].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PresignPolicy {
+    max_expiry: Duration,
+    allowed_prefixes: Vec<String>,
+    content_types: Vec<String>,
+    max_size: Option<u64>,
+}
+
+impl Default for PresignPolicy {
+    fn default() -> Self {
+        PresignPolicy {
+            max_expiry: DEFAULT_EXPIRY,
+            allowed_prefixes: vec![],
+            content_types: vec![],
+            max_size: None,
+        }
+    }
+}
+
+impl PresignPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The longest a presigned request can last. Capped at [MAX_PRESIGN_EXPIRY].
+    pub fn with_max_expiry(mut self, max_expiry: Duration) -> Self {
+        self.max_expiry = max_expiry.min(MAX_PRESIGN_EXPIRY);
+        self
+    }
+
+    /// Allow keys that start with `prefix`. Can be called more than once; with no prefixes,
+    /// any key is allowed.
+    pub fn with_allowed_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.allowed_prefixes.push(prefix.into());
+        self
+    }
+
+    /// Allow uploads of `content_type`, or of any subtype with `image/*`. Can be called more
+    /// than once. Once any content type is set, every upload must say which one it is.
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_types.push(content_type.into());
+        self
+    }
+
+    /// The most bytes one upload can be. Once set, every PUT must say how big it is.
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    pub fn max_expiry(&self) -> Duration {
+        self.max_expiry
+    }
+
+    /// This policy with its max expiry capped at the longest session STS will issue.
+    fn for_session(mut self) -> Self {
+        self.max_expiry = self.max_expiry.min(MAX_SESSION_LENGTH);
+        self
+    }
+
+    /// Whether a GET for `key` that lasts `expires_in` is allowed.
+    pub fn check_get(&self, key: &str, expires_in: Duration) -> Result<(), S3ExampleError> {
+        self.check_expiry(expires_in)?;
+        self.check_key(key)
+    }
+
+    /// Whether a PUT of `content_length` bytes of `content_type` to `key` is allowed.
+    pub fn check_put(
+        &self,
+        key: &str,
+        expires_in: Duration,
+        content_type: Option<&str>,
+        content_length: Option<u64>,
+    ) -> Result<(), S3ExampleError> {
+        self.check_get(key, expires_in)?;
+        self.check_content_type(content_type)?;
+        match (self.max_size, content_length) {
+            (Some(max), None) => Err(S3ExampleError::new(format!(
+                "Uploads must give their content length, up to {max} bytes"
+            ))),
+            (Some(max), Some(length)) if length > max => Err(S3ExampleError::new(format!(
+                "{length} bytes is more than the {max} bytes allowed"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether a POST of up to `max_size` bytes of `content_type` to `key` is allowed.
+    /// Returns the size limit the POST policy should enforce, if any.
+    pub fn check_post(
+        &self,
+        key: &str,
+        expires_in: Duration,
+        content_type: Option<&str>,
+        max_size: Option<u64>,
+    ) -> Result<Option<u64>, S3ExampleError> {
+        self.check_get(key, expires_in)?;
+        self.check_content_type(content_type)?;
+        match (self.max_size, max_size) {
+            (Some(max), Some(size)) if size > max => Err(S3ExampleError::new(format!(
+                "{size} bytes is more than the {max} bytes allowed"
+            ))),
+            (policy, requested) => Ok(requested.or(policy)),
+        }
+    }
+
+    fn check_expiry(&self, expires_in: Duration) -> Result<(), S3ExampleError> {
+        if expires_in.is_zero() {
+            return Err(S3ExampleError::new(
+                "Presigned requests must last at least a second",
+            ));
+        }
+        if expires_in > self.max_expiry {
+            return Err(S3ExampleError::new(format!(
+                "{}s is longer than the {}s allowed",
+                expires_in.as_secs(),
+                self.max_expiry.as_secs()
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_key(&self, key: &str) -> Result<(), S3ExampleError> {
+        if key.is_empty() {
+            return Err(S3ExampleError::new("Object keys can't be empty"));
+        }
+        if !self.allowed_prefixes.is_empty()
+            && !self
+                .allowed_prefixes
+                .iter()
+                .any(|prefix| key.starts_with(prefix.as_str()))
+        {
+            return Err(S3ExampleError::new(format!(
+                "{key} isn't under any of the allowed prefixes: {}",
+                self.allowed_prefixes.join(", ")
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_content_type(&self, content_type: Option<&str>) -> Result<(), S3ExampleError> {
+        if self.content_types.is_empty() {
+            return Ok(());
+        }
+        let allowed = self.content_types.join(", ");
+        let Some(content_type) = content_type else {
+            return Err(S3ExampleError::new(format!(
+                "Uploads must give their content type, one of: {allowed}"
+            )));
+        };
+        let matches = self
+            .content_types
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => content_type
+                    .get(..prefix.len())
+                    .is_some_and(|start| start.eq_ignore_ascii_case(prefix)),
+                None => content_type.eq_ignore_ascii_case(pattern),
+            });
+        if !matches {
+            return Err(S3ExampleError::new(format!(
+                "{content_type} isn't one of the allowed content types: {allowed}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A presigned GET or PUT, as handed to a caller that doesn't use the SDK.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PresignedUrl {
+    pub method: String,
+    pub url: String,
+    /// Headers the request must be sent with, or the signature won't match.
+    pub headers: BTreeMap<String, String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl PresignedUrl {
+    /// `request` was presigned to last `expires_in` from now.
+    pub fn new(request: &PresignedRequest, expires_in: Duration) -> Self {
+        PresignedUrl {
+            method: request.method().to_string(),
+            url: request.uri().to_string(),
+            headers: request
+                .headers()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            expires_at: Utc::now() + expires_in,
+        }
+    }
+}
+
+/// A presigned POST. Browsers upload with a `multipart/form-data` form sent to `url`, with
+/// every one of `fields` as a form field, and the object's contents in a last field named `file`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PresignedPost {
+    pub url: String,
+    pub fields: BTreeMap<String, String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Presigns requests for objects in one bucket, within a [PresignPolicy].
+#[derive(Clone, Debug)]
+pub struct Presigner {
+    client: Client,
+    bucket: String,
+    policy: PresignPolicy,
+}
+
+impl Presigner {
+    pub fn new(client: Client, bucket: impl Into<String>) -> Self {
+        Presigner {
+            client,
+            bucket: bucket.into(),
+            policy: PresignPolicy::default(),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: PresignPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    pub fn policy(&self) -> &PresignPolicy {
+        &self.policy
+    }
+
+    // snippet-start:[s3.rust.presign-get-object]
+    /// Presign a GET for `key` that lasts `expires_in`.
+    pub async fn presign_get(
+        &self,
+        key: &str,
+        expires_in: Duration,
+    ) -> Result<PresignedRequest, S3ExampleError> {
+        self.policy.check_get(key, expires_in)?;
+        self.credentials(expires_in).await?;
+        let request = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .presigned(presigning_config(expires_in)?)
+            .await?;
+        Ok(request)
+    }
+    // snippet-end:[s3.rust.presign-get-object]
+
+    // snippet-start:[s3.rust.presign-put-object]
+    /// Presign a PUT to `key` that lasts `expires_in`. A content type or length given here is
+    /// signed, so the upload has to send the same `Content-Type` or `Content-Length` header.
+    pub async fn presign_put(
+        &self,
+        key: &str,
+        expires_in: Duration,
+        content_type: Option<&str>,
+        content_length: Option<u64>,
+    ) -> Result<PresignedRequest, S3ExampleError> {
+        self.policy
+            .check_put(key, expires_in, content_type, content_length)?;
+        self.credentials(expires_in).await?;
+        let content_length = content_length
+            .map(i64::try_from)
+            .transpose()
+            .map_err(|_| S3ExampleError::new("Content length is too large"))?;
+        let request = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .set_content_type(content_type.map(String::from))
+            .set_content_length(content_length)
+            .presigned(presigning_config(expires_in)?)
+            .await?;
+        Ok(request)
+    }
+    // snippet-end:[s3.rust.presign-put-object]
+
+    /// Presign a browser POST upload to `key` that lasts `expires_in`. Unlike a PUT, S3 checks
+    /// the size against a range in the signed policy, so the form doesn't need to know the
+    /// exact size when it's signed.
+    pub async fn presign_post(
+        &self,
+        key: &str,
+        expires_in: Duration,
+        content_type: Option<&str>,
+        max_size: Option<u64>,
+    ) -> Result<PresignedPost, S3ExampleError> {
+        let max_size = self
+            .policy
+            .check_post(key, expires_in, content_type, max_size)?;
+        let config = self.client.config();
+        let region = config
+            .region()
+            .ok_or_else(|| S3ExampleError::new("The client has no Region to sign for"))?;
+        let credentials = self.credentials(expires_in).await?;
+        let url = self.post_url().await?;
+
+        Ok(PostPolicy {
+            url: &url,
+            bucket: &self.bucket,
+            key,
+            content_type,
+            max_size,
+        }
+        .sign(&credentials, region.as_ref(), SystemTime::now(), expires_in))
+    }
+
+    /// The credentials requests are signed with. A presigned request stops working when these
+    /// expire, so a request that would outlive them is refused rather than handed out broken.
+    async fn credentials(&self, expires_in: Duration) -> Result<Credentials, S3ExampleError> {
+        let credentials = self
+            .client
+            .config()
+            .credentials_provider()
+            .ok_or_else(|| S3ExampleError::new("The client has no credentials to sign with"))?
+            .provide_credentials()
+            .await
+            .map_err(|err| S3ExampleError::new(format!("Failed to load credentials: {err}")))?;
+        check_credentials(&credentials, SystemTime::now(), expires_in)?;
+        Ok(credentials)
+    }
+
+    /// Where to send a POST for this bucket. This is where the client sends its own requests,
+    /// so a custom endpoint or path-style addressing in its config is used here too. A bucket
+    /// name with dots doesn't match S3's certificate as a host name, so it always goes in the
+    /// path.
+    async fn post_url(&self) -> Result<String, S3ExampleError> {
+        // Presigning doesn't send anything; it's only a way to ask the client for its endpoint.
+        const KEY: &str = "key";
+        let request = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(KEY)
+            .presigned(presigning_config(Duration::from_secs(60))?)
+            .await?;
+        let uri = request.uri();
+        let url = uri
+            .split('?')
+            .next()
+            .and_then(|url| url.strip_suffix(KEY))
+            .ok_or_else(|| S3ExampleError::new(format!("Unexpected presigned URL {uri}")))?;
+        let virtual_host = format!("https://{}.", self.bucket);
+        match url.strip_prefix(&virtual_host) {
+            Some(rest) if self.bucket.contains('.') => {
+                Ok(format!("https://{rest}{}/", self.bucket))
+            }
+            _ => Ok(url.to_string()),
+        }
+    }
+}
+
+/// Whether `credentials` are still good `expires_in` after `now`.
+fn check_credentials(
+    credentials: &Credentials,
+    now: SystemTime,
+    expires_in: Duration,
+) -> Result<(), S3ExampleError> {
+    match credentials.expiry() {
+        Some(expiry) if now + expires_in > expiry => {
+            let left = expiry.duration_since(now).unwrap_or_default();
+            Err(S3ExampleError::new(format!(
+                "The signing credentials expire in {}s, before the {}s requested",
+                left.as_secs(),
+                expires_in.as_secs()
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn presigning_config(expires_in: Duration) -> Result<PresigningConfig, S3ExampleError> {
+    PresigningConfig::expires_in(expires_in).map_err(|err| {
+        S3ExampleError::new(format!(
+            "Failed to convert expiration to PresigningConfig: {err:?}"
+        ))
+    })
+}
+
+/// The conditions a presigned POST is checked against.
+struct PostPolicy<'a> {
+    url: &'a str,
+    bucket: &'a str,
+    key: &'a str,
+    content_type: Option<&'a str>,
+    max_size: Option<u64>,
+}
+
+/// The policy document is signed as JSON, so field order matters.
+#[derive(Serialize)]
+struct PostPolicyDocument {
+    expiration: String,
+    conditions: Vec<Value>,
+}
+
+// snippet-start:[s3.rust.presign-post-policy]
+impl PostPolicy<'_> {
+    /// Sign this policy with SigV4. Every form field except the signature and the policy
+    /// itself has to match a condition, so each one is added to the policy as it's set.
+    fn sign(
+        &self,
+        credentials: &Credentials,
+        region: &str,
+        now: SystemTime,
+        expires_in: Duration,
+    ) -> PresignedPost {
+        let signed_at: DateTime<Utc> = now.into();
+        let expires_at = signed_at + expires_in;
+        let date = signed_at.format("%Y%m%d");
+
+        let mut fields = BTreeMap::new();
+        fields.insert("key".to_string(), self.key.to_string());
+        fields.insert(
+            "x-amz-algorithm".to_string(),
+            "AWS4-HMAC-SHA256".to_string(),
+        );
+        fields.insert(
+            "x-amz-credential".to_string(),
+            format!(
+                "{}/{date}/{region}/s3/aws4_request",
+                credentials.access_key_id()
+            ),
+        );
+        fields.insert(
+            "x-amz-date".to_string(),
+            signed_at.format("%Y%m%dT%H%M%SZ").to_string(),
+        );
+        if let Some(token) = credentials.session_token() {
+            fields.insert("x-amz-security-token".to_string(), token.to_string());
+        }
+        if let Some(content_type) = self.content_type {
+            fields.insert("Content-Type".to_string(), content_type.to_string());
+        }
+
+        let mut conditions = vec![exact("bucket", self.bucket)];
+        conditions.extend(fields.iter().map(|(name, value)| exact(name, value)));
+        if let Some(max_size) = self.max_size {
+            conditions.push(serde_json::json!(["content-length-range", 0, max_size]));
+        }
+        let document = PostPolicyDocument {
+            expiration: expires_at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+            conditions,
+        };
+        let policy = aws_smithy_types::base64::encode(
+            serde_json::to_string(&document).expect("policy documents are valid JSON"),
+        );
+
+        let signing_key = generate_signing_key(credentials.secret_access_key(), now, region, "s3");
+        let signature = calculate_signature(signing_key, policy.as_bytes());
+        fields.insert("policy".to_string(), policy);
+        fields.insert("x-amz-signature".to_string(), signature);
+
+        PresignedPost {
+            url: self.url.to_string(),
+            fields,
+            expires_at,
+        }
+    }
+}
+
+/// A condition that `name` must equal `value`.
+fn exact(name: &str, value: &str) -> Value {
+    let mut condition = Map::new();
+    condition.insert(name.to_string(), Value::from(value));
+    Value::Object(condition)
+}
+// snippet-end:[s3.rust.presign-post-policy]
+
+// snippet-start:[s3.rust.presign-assume-role]
+/// An S3 client that signs with short-lived credentials for `role_arn`, rather than with the
+/// credentials in `config`, and `policy` with its max expiry capped at [MAX_SESSION_LENGTH].
+/// The sessions last long enough for any request the returned policy allows, so the role's
+/// maximum session duration must be at least that long.
+pub async fn assume_role_client(
+    config: &SdkConfig,
+    role_arn: impl Into<String>,
+    policy: PresignPolicy,
+) -> (Client, PresignPolicy) {
+    let policy = policy.for_session();
+    let provider = AssumeRoleProvider::builder(role_arn)
+        .session_name("s3-presigner")
+        .session_length(policy.max_expiry().max(MIN_SESSION_LENGTH))
+        .configure(config)
+        .build()
+        .await;
+
+    let s3_config = aws_sdk_s3::config::Builder::from(config)
+        .credentials_provider(provider)
+        .build();
+    (Client::from_conf(s3_config), policy)
+}
+// snippet-end:[s3.rust.presign-assume-role]
+
+#[cfg(test)]
+mod test {
+    use aws_smithy_runtime::client::http::test_util::StaticReplayClient;
+    use sdk_examples_test_utils::client_config;
+
+    use super::*;
+
+    fn presigner(policy: PresignPolicy) -> Presigner {
+        let client = Client::from_conf(
+            client_config!(aws_sdk_s3)
+                .http_client(StaticReplayClient::new(vec![]))
+                .build(),
+        );
+        Presigner::new(client, "amzn-s3-demo-bucket").with_policy(policy)
+    }
+
+    fn uploads_policy() -> PresignPolicy {
+        PresignPolicy::new()
+            .with_max_expiry(Duration::from_secs(600))
+            .with_allowed_prefix("uploads/")
+            .with_content_type("image/*")
+            .with_max_size(1024 * 1024)
+    }
+
+    #[test]
+    fn test_policy_checks() {
+        let policy = uploads_policy();
+        let ten_minutes = Duration::from_secs(600);
+
+        assert!(policy.check_get("uploads/a.jpg", ten_minutes).is_ok());
+        assert!(policy
+            .check_get("uploads/a.jpg", Duration::from_secs(601))
+            .is_err());
+        assert!(policy.check_get("uploads/a.jpg", Duration::ZERO).is_err());
+        assert!(policy.check_get("private/a.jpg", ten_minutes).is_err());
+        assert!(policy.check_get("", ten_minutes).is_err());
+
+        assert!(policy
+            .check_put("uploads/a.jpg", ten_minutes, Some("image/png"), Some(10))
+            .is_ok());
+        assert!(policy
+            .check_put("uploads/a.jpg", ten_minutes, Some("Image/PNG"), Some(10))
+            .is_ok());
+        assert!(policy
+            .check_put("uploads/a.jpg", ten_minutes, Some("text/html"), Some(10))
+            .is_err());
+        assert!(policy
+            .check_put("uploads/a.jpg", ten_minutes, None, Some(10))
+            .is_err());
+        assert!(policy
+            .check_put("uploads/a.jpg", ten_minutes, Some("image/png"), None)
+            .is_err());
+        assert!(policy
+            .check_put(
+                "uploads/a.jpg",
+                ten_minutes,
+                Some("image/png"),
+                Some(1024 * 1024 + 1)
+            )
+            .is_err());
+
+        assert_eq!(
+            policy
+                .check_post("uploads/a.jpg", ten_minutes, Some("image/png"), None)
+                .unwrap(),
+            Some(1024 * 1024)
+        );
+        assert_eq!(
+            policy
+                .check_post("uploads/a.jpg", ten_minutes, Some("image/png"), Some(10))
+                .unwrap(),
+            Some(10)
+        );
+        assert!(policy
+            .check_post(
+                "uploads/a.jpg",
+                ten_minutes,
+                Some("image/png"),
+                Some(1024 * 1024 + 1)
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_max_expiry_is_capped() {
+        let policy = PresignPolicy::new().with_max_expiry(Duration::from_secs(30 * 24 * 60 * 60));
+        assert_eq!(policy.max_expiry(), MAX_PRESIGN_EXPIRY);
+    }
+
+    #[test]
+    fn test_session_caps_max_expiry() {
+        let policy = PresignPolicy::new().with_max_expiry(MAX_PRESIGN_EXPIRY);
+        assert_eq!(policy.for_session().max_expiry(), MAX_SESSION_LENGTH);
+        let policy = uploads_policy();
+        assert_eq!(policy.clone().for_session(), policy);
+    }
+
+    #[test]
+    fn test_check_credentials() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let ten_minutes = Duration::from_secs(600);
+        let expiring =
+            |expiry| Credentials::new("ATESTCLIENT", "atestsecretkey", None, expiry, "test");
+
+        assert!(check_credentials(&expiring(None), now, ten_minutes).is_ok());
+        assert!(check_credentials(&expiring(Some(now + ten_minutes)), now, ten_minutes).is_ok());
+        assert!(check_credentials(
+            &expiring(Some(now + Duration::from_secs(599))),
+            now,
+            ten_minutes
+        )
+        .is_err());
+        assert!(check_credentials(&expiring(Some(now)), now, ten_minutes).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_presign_refuses_past_credential_expiry() {
+        let client = Client::from_conf(
+            client_config!(aws_sdk_s3)
+                .credentials_provider(Credentials::new(
+                    "ATESTCLIENT",
+                    "atestsecretkey",
+                    Some("atestsessiontoken".to_string()),
+                    Some(SystemTime::now() + Duration::from_secs(60)),
+                    "test",
+                ))
+                .http_client(StaticReplayClient::new(vec![]))
+                .build(),
+        );
+        let presigner = Presigner::new(client, "amzn-s3-demo-bucket").with_policy(uploads_policy());
+
+        assert!(presigner
+            .presign_get("uploads/a.jpg", Duration::from_secs(600))
+            .await
+            .is_err());
+        assert!(presigner
+            .presign_get("uploads/a.jpg", Duration::from_secs(30))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_presign_get() {
+        let presigner = presigner(uploads_policy());
+
+        let request = presigner
+            .presign_get("uploads/a.jpg", Duration::from_secs(300))
+            .await
+            .unwrap();
+        assert_eq!(request.method(), "GET");
+        assert!(request.uri().starts_with("https://amzn-s3-demo-bucket.s3."));
+        assert!(request.uri().contains("/uploads/a.jpg?"));
+        assert!(request.uri().contains("X-Amz-Expires=300"));
+
+        assert!(presigner
+            .presign_get("private/a.jpg", Duration::from_secs(300))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_presign_put() {
+        let presigner = presigner(uploads_policy());
+
+        let request = presigner
+            .presign_put(
+                "uploads/a.jpg",
+                Duration::from_secs(300),
+                Some("image/jpeg"),
+                Some(2048),
+            )
+            .await
+            .unwrap();
+        assert_eq!(request.method(), "PUT");
+        let url = PresignedUrl::new(&request, Duration::from_secs(300));
+        assert_eq!(
+            url.headers.get("content-type").map(String::as_str),
+            Some("image/jpeg")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_post_url() {
+        let virtual_hosted = presigner(uploads_policy());
+        assert_eq!(
+            virtual_hosted.post_url().await.unwrap(),
+            "https://amzn-s3-demo-bucket.s3.us-east-1.amazonaws.com/"
+        );
+
+        let dotted = Presigner::new(virtual_hosted.client.clone(), "amzn.s3.demo.bucket");
+        assert_eq!(
+            dotted.post_url().await.unwrap(),
+            "https://s3.us-east-1.amazonaws.com/amzn.s3.demo.bucket/"
+        );
+
+        let custom = Client::from_conf(
+            client_config!(aws_sdk_s3)
+                .endpoint_url("http://localhost:9000")
+                .force_path_style(true)
+                .http_client(StaticReplayClient::new(vec![]))
+                .build(),
+        );
+        assert_eq!(
+            Presigner::new(custom, "amzn-s3-demo-bucket")
+                .post_url()
+                .await
+                .unwrap(),
+            "http://localhost:9000/amzn-s3-demo-bucket/"
+        );
+    }
+
+    #[test]
+    fn test_sign_post_policy() {
+        let credentials = Credentials::new(
+            "ATESTCLIENT",
+            "atestsecretkey",
+            Some("atestsessiontoken".to_string()),
+            None,
+            "",
+        );
+        let post = PostPolicy {
+            url: "https://amzn-s3-demo-bucket.s3.us-east-1.amazonaws.com/",
+            bucket: "amzn-s3-demo-bucket",
+            key: "uploads/photo.jpg",
+            content_type: Some("image/jpeg"),
+            max_size: Some(1024 * 1024),
+        }
+        .sign(
+            &credentials,
+            "us-east-1",
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            Duration::from_secs(600),
+        );
+
+        assert_eq!(
+            post.url,
+            "https://amzn-s3-demo-bucket.s3.us-east-1.amazonaws.com/"
+        );
+        assert_eq!(post.expires_at.to_rfc3339(), "2023-11-14T22:23:20+00:00");
+        assert_eq!(
+            post.fields["x-amz-credential"],
+            "ATESTCLIENT/20231114/us-east-1/s3/aws4_request"
+        );
+        assert_eq!(post.fields["x-amz-date"], "20231114T221320Z");
+
+        let policy = aws_smithy_types::base64::decode(&post.fields["policy"]).unwrap();
+        assert_eq!(
+            String::from_utf8(policy).unwrap(),
+            r#"{"expiration":"2023-11-14T22:23:20.000Z","conditions":[{"bucket":"amzn-s3-demo-bucket"},{"Content-Type":"image/jpeg"},{"key":"uploads/photo.jpg"},{"x-amz-algorithm":"AWS4-HMAC-SHA256"},{"x-amz-credential":"ATESTCLIENT/20231114/us-east-1/s3/aws4_request"},{"x-amz-date":"20231114T221320Z"},{"x-amz-security-token":"atestsessiontoken"},["content-length-range",0,1048576]]}"#
+        );
+        assert_eq!(
+            post.fields["x-amz-signature"],
+            "62da32021d0057940d42b61b4c49e24ea8f6f31ce438e7a54b569a06191c817f"
+        );
+    }
+}
@@ -0,0 +1,545 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Report how far uploads and downloads have got. A [ProgressTracker] counts the bytes of one
+//! transfer and passes each change to a [ProgressObserver]. Trackers made with
+//! [ProgressTracker::transfer] also count toward their parent, so one observer can follow many
+//! transfers running at once.
+
+use std::{
+    convert::Infallible,
+    fmt::Debug,
+    io::Write,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use aws_sdk_s3::primitives::SdkBody;
+use aws_smithy_runtime_api::http::Request;
+use bytes::Bytes;
+use http_body::{Body, SizeHint};
+
+/// A snapshot of a transfer, or of every transfer under a parent tracker.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Progress {
+    /// Bytes transferred so far, including any done by an earlier run.
+    pub bytes: u64,
+    /// Bytes to transfer in all, once known.
+    pub total: Option<u64>,
+    /// How many transfers have been started under this tracker.
+    pub transfers: usize,
+    /// How many of those transfers have finished.
+    pub finished: usize,
+    pub elapsed: Duration,
+    /// Bytes per second in this run. Bytes done by an earlier run don't count.
+    pub rate: f64,
+}
+
+impl Progress {
+    /// How much of the total is done, from 0 to 1.
+    pub fn fraction(&self) -> Option<f64> {
+        match self.total {
+            Some(0) => Some(1.0),
+            Some(total) => Some((self.bytes as f64 / total as f64).min(1.0)),
+            None => None,
+        }
+    }
+
+    /// How long the rest should take at the current rate.
+    pub fn eta(&self) -> Option<Duration> {
+        let remaining = self.total?.saturating_sub(self.bytes);
+        if remaining == 0 {
+            Some(Duration::ZERO)
+        } else if self.rate > 0.0 {
+            Some(Duration::from_secs_f64(remaining as f64 / self.rate))
+        } else {
+            None
+        }
+    }
+}
+
+/// Receives progress as a transfer goes. Observers are called from whichever task moved the
+/// bytes, so they should be quick.
+pub trait ProgressObserver: Send + Sync {
+    fn on_progress(&self, progress: &Progress);
+
+    /// Called once, when the transfer is done.
+    fn on_finish(&self, progress: &Progress) {
+        self.on_progress(progress);
+    }
+}
+
+impl<F> ProgressObserver for F
+where
+    F: Fn(&Progress) + Send + Sync,
+{
+    fn on_progress(&self, progress: &Progress) {
+        self(progress)
+    }
+}
+
+#[derive(Default)]
+struct State {
+    bytes: u64,
+    resumed: u64,
+    total: Option<u64>,
+    transfers: usize,
+    finished: usize,
+}
+
+/// A change to apply to a tracker and every tracker above it.
+#[derive(Clone, Copy, Default)]
+struct Delta {
+    bytes: i64,
+    resumed: i64,
+    total: Option<i64>,
+    transfers: usize,
+    finished: usize,
+}
+
+struct Shared {
+    started: Instant,
+    state: Mutex<State>,
+    observer: Option<Box<dyn ProgressObserver>>,
+    parent: Option<ProgressTracker>,
+}
+
+// snippet-start:[s3.rust.ProgressTracker]
+/// Counts the bytes of a transfer. Clones share the same count, so the tracker can be handed to
+/// every part of a transfer running at once.
+#[derive(Clone)]
+pub struct ProgressTracker {
+    shared: Arc<Shared>,
+}
+
+impl ProgressTracker {
+    /// A tracker that reports to `observer`.
+    pub fn new(observer: impl ProgressObserver + 'static) -> Self {
+        Self::build(Some(Box::new(observer)), None)
+    }
+
+    /// A tracker that doesn't report to anything.
+    pub fn silent() -> Self {
+        Self::build(None, None)
+    }
+
+    fn build(observer: Option<Box<dyn ProgressObserver>>, parent: Option<ProgressTracker>) -> Self {
+        ProgressTracker {
+            shared: Arc::new(Shared {
+                started: Instant::now(),
+                state: Mutex::new(State::default()),
+                observer,
+                parent,
+            }),
+        }
+    }
+
+    /// Start tracking one transfer of several. Its bytes and total are added to this tracker's,
+    /// and finishing it counts toward this tracker's finished transfers.
+    pub fn transfer(&self) -> ProgressTracker {
+        self.apply(Delta {
+            transfers: 1,
+            ..Delta::default()
+        });
+        Self::build(None, Some(self.clone()))
+    }
+
+    /// Set how many bytes this transfer has in all.
+    pub fn set_total(&self, total: u64) {
+        let old = self.shared.state.lock().unwrap().total.unwrap_or(0);
+        self.apply(Delta {
+            total: Some(total as i64 - old as i64),
+            ..Delta::default()
+        });
+    }
+
+    /// Count `len` more bytes as transferred.
+    pub fn track(&self, len: u64) {
+        self.apply(Delta {
+            bytes: len as i64,
+            ..Delta::default()
+        });
+    }
+
+    /// Take back `len` bytes counted for a piece of the transfer that failed, and so will be
+    /// transferred again.
+    pub fn untrack(&self, len: u64) {
+        self.apply(Delta {
+            bytes: -(len as i64),
+            ..Delta::default()
+        });
+    }
+
+    /// Count `len` bytes that an earlier run already transferred. They count toward the total,
+    /// but not toward the rate.
+    pub fn resume(&self, len: u64) {
+        self.apply(Delta {
+            bytes: len as i64,
+            resumed: len as i64,
+            ..Delta::default()
+        });
+    }
+
+    /// Forget the bytes tracked in this run, for when a transfer starts over.
+    pub fn rewind(&self) {
+        let tracked = {
+            let state = self.shared.state.lock().unwrap();
+            state.bytes.saturating_sub(state.resumed)
+        };
+        self.apply(Delta {
+            bytes: -(tracked as i64),
+            ..Delta::default()
+        });
+    }
+
+    /// Mark the transfer done. A transfer's parent counts it as finished; a tracker made with
+    /// [ProgressTracker::new] tells its observer.
+    pub fn finish(&self) {
+        if let Some(parent) = &self.shared.parent {
+            parent.apply(Delta {
+                finished: 1,
+                ..Delta::default()
+            });
+        }
+        if let Some(observer) = &self.shared.observer {
+            observer.on_finish(&self.progress());
+        }
+    }
+
+    pub fn progress(&self) -> Progress {
+        let state = self.shared.state.lock().unwrap();
+        let elapsed = self.shared.started.elapsed();
+        let rate = if elapsed.is_zero() {
+            0.0
+        } else {
+            state.bytes.saturating_sub(state.resumed) as f64 / elapsed.as_secs_f64()
+        };
+        Progress {
+            bytes: state.bytes,
+            total: state.total,
+            transfers: state.transfers,
+            finished: state.finished,
+            elapsed,
+            rate,
+        }
+    }
+
+    fn apply(&self, delta: Delta) {
+        {
+            let mut state = self.shared.state.lock().unwrap();
+            state.bytes = state.bytes.saturating_add_signed(delta.bytes);
+            state.resumed = state.resumed.saturating_add_signed(delta.resumed);
+            if let Some(total) = delta.total {
+                state.total = Some(state.total.unwrap_or(0).saturating_add_signed(total));
+            }
+            state.transfers += delta.transfers;
+            state.finished += delta.finished;
+        }
+        if let Some(observer) = &self.shared.observer {
+            observer.on_progress(&self.progress());
+        }
+        if let Some(parent) = &self.shared.parent {
+            parent.apply(delta);
+        }
+    }
+}
+// snippet-end:[s3.rust.ProgressTracker]
+
+impl Default for ProgressTracker {
+    fn default() -> Self {
+        Self::silent()
+    }
+}
+
+impl Debug for ProgressTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressTracker")
+            .field("progress", &self.progress())
+            .finish()
+    }
+}
+
+/// Draws a progress bar on one line of the terminal, redrawn in place.
+pub struct ProgressBar {
+    label: String,
+    width: usize,
+    interval: Duration,
+    last_draw: Mutex<Option<Instant>>,
+}
+
+impl ProgressBar {
+    pub fn new(label: impl Into<String>) -> Self {
+        ProgressBar {
+            label: label.into(),
+            width: 30,
+            interval: Duration::from_millis(100),
+            last_draw: Mutex::new(None),
+        }
+    }
+
+    /// How many characters the bar itself takes up.
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width.max(1);
+        self
+    }
+
+    /// The least time between redraws, so fast transfers don't flood the terminal.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// The line drawn for `progress`.
+    pub fn render(&self, progress: &Progress) -> String {
+        let mut line = String::new();
+        if !self.label.is_empty() {
+            line.push_str(&self.label);
+            line.push(' ');
+        }
+        match progress.fraction() {
+            Some(fraction) => {
+                let filled = (fraction * self.width as f64) as usize;
+                let bar: String = (0..self.width)
+                    .map(|i| match i.cmp(&filled) {
+                        std::cmp::Ordering::Less => '=',
+                        std::cmp::Ordering::Equal => '>',
+                        std::cmp::Ordering::Greater => ' ',
+                    })
+                    .collect();
+                line.push_str(&format!(
+                    "[{bar}] {:>3.0}% {} / {}",
+                    fraction * 100.0,
+                    format_bytes(progress.bytes),
+                    format_bytes(progress.total.unwrap_or_default())
+                ));
+            }
+            None => line.push_str(&format_bytes(progress.bytes)),
+        }
+        line.push_str(&format!("  {}/s", format_bytes(progress.rate as u64)));
+        if let Some(eta) = progress.eta() {
+            line.push_str(&format!("  ETA {}", format_duration(eta)));
+        }
+        if progress.transfers > 0 {
+            line.push_str(&format!(
+                "  ({}/{} files)",
+                progress.finished, progress.transfers
+            ));
+        }
+        line
+    }
+
+    fn draw(&self, progress: &Progress, end: &str) {
+        let mut stderr = std::io::stderr().lock();
+        // Clear to the end of the line, in case the last line was longer.
+        let _ = write!(stderr, "\r{}\x1b[K{end}", self.render(progress));
+        let _ = stderr.flush();
+    }
+}
+
+impl ProgressObserver for ProgressBar {
+    fn on_progress(&self, progress: &Progress) {
+        let mut last_draw = self.last_draw.lock().unwrap();
+        if last_draw.is_some_and(|last| last.elapsed() < self.interval) {
+            return;
+        }
+        *last_draw = Some(Instant::now());
+        self.draw(progress, "");
+    }
+
+    fn on_finish(&self, progress: &Progress) {
+        let _guard = self.last_draw.lock().unwrap();
+        self.draw(progress, "\n");
+    }
+}
+
+/// `bytes` in the largest binary unit that keeps it at least 1.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// `duration` as `h:mm:ss`, or `m:ss` when under an hour.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+// snippet-start:[s3.rust.put-object-progress-body]
+// A ProgressBody to wrap any http::Body with upload progress information.
+#[pin_project::pin_project]
+pub struct ProgressBody<InnerBody> {
+    #[pin]
+    inner: InnerBody,
+    progress: ProgressTracker,
+}
+
+// For an SdkBody specifically, the ProgressBody can swap itself in-place while customizing the SDK operation.
+impl ProgressBody<SdkBody> {
+    // Returns a function that wraps a Request's SdkBody with a new ProgressBody, to pass to
+    // `customize().map_request()`. This is specialized for SdkBody specifically, as SdkBody
+    // provides ::from_body_0_4() to get an SdkBody back from the ProgressBody it created.
+    // http::Body does not have this "change the wheels on the fly" utility.
+    pub fn replace(
+        progress: ProgressTracker,
+    ) -> impl Fn(Request<SdkBody>) -> Result<Request<SdkBody>, Infallible> + Send + Sync + 'static
+    {
+        move |value| {
+            // A retried request sends the whole body again.
+            progress.rewind();
+            Ok(value.map(|body| {
+                if let Some(len) = body.content_length() {
+                    progress.set_total(len);
+                }
+                SdkBody::from_body_0_4(ProgressBody::new(body, progress.clone()))
+            }))
+        }
+    }
+}
+
+impl<InnerBody> ProgressBody<InnerBody>
+where
+    InnerBody: Body<Data = Bytes, Error = aws_smithy_types::body::Error>,
+{
+    pub fn new(body: InnerBody, progress: ProgressTracker) -> Self {
+        Self {
+            inner: body,
+            progress,
+        }
+    }
+}
+
+impl<InnerBody> Body for ProgressBody<InnerBody>
+where
+    InnerBody: Body<Data = Bytes, Error = aws_smithy_types::body::Error>,
+{
+    type Data = Bytes;
+
+    type Error = aws_smithy_types::body::Error;
+
+    // Our poll_data delegates to the inner poll_data, but needs a project() to
+    // get there. When the poll has data, it updates the progress tracker.
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        match this.inner.poll_data(cx) {
+            Poll::Ready(Some(Ok(data))) => {
+                this.progress.track(data.len() as u64);
+                Poll::Ready(Some(Ok(data)))
+            }
+            Poll::Ready(None) => {
+                tracing::debug!("done");
+                Poll::Ready(None)
+            }
+            Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(e))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    // Delegate utilities to inner.
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        self.project().inner.poll_trailers(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+// snippet-end:[s3.rust.put-object-progress-body]
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_aggregate_progress() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let root = {
+            let seen = seen.clone();
+            ProgressTracker::new(move |progress: &Progress| {
+                seen.lock().unwrap().push(*progress);
+            })
+        };
+
+        let first = root.transfer();
+        let second = root.transfer();
+        first.set_total(100);
+        second.set_total(300);
+        first.resume(50);
+        first.track(50);
+        second.track(100);
+        first.finish();
+
+        let progress = root.progress();
+        assert_eq!(progress.bytes, 200);
+        assert_eq!(progress.total, Some(400));
+        assert_eq!((progress.finished, progress.transfers), (1, 2));
+        assert_eq!(progress.fraction(), Some(0.5));
+        assert_eq!(first.progress().fraction(), Some(1.0));
+        assert_eq!(seen.lock().unwrap().last().unwrap().finished, 1);
+
+        // Starting over takes back the bytes sent in this run, but not the resumed ones.
+        second.rewind();
+        first.rewind();
+        assert_eq!(root.progress().bytes, 50);
+        // Correcting a total replaces it rather than adding to it.
+        second.set_total(200);
+        assert_eq!(root.progress().total, Some(300));
+    }
+
+    #[test]
+    fn test_eta() {
+        let progress = Progress {
+            bytes: 25,
+            total: Some(100),
+            rate: 5.0,
+            ..Progress::default()
+        };
+        assert_eq!(progress.eta(), Some(Duration::from_secs(15)));
+        assert_eq!(Progress::default().eta(), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let bar = ProgressBar::new("upload").with_width(10);
+        let progress = Progress {
+            bytes: 5 * 1024 * 1024,
+            total: Some(10 * 1024 * 1024),
+            transfers: 3,
+            finished: 1,
+            elapsed: Duration::from_secs(5),
+            rate: 1024.0 * 1024.0,
+        };
+        assert_eq!(
+            bar.render(&progress),
+            "upload [=====>    ]  50% 5.0 MiB / 10.0 MiB  1.0 MiB/s  ETA 0:05  (1/3 files)"
+        );
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_duration(Duration::from_secs(3725)), "1:02:05");
+    }
+}
@@ -0,0 +1,584 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Query CSV, JSON Lines, and Parquet objects with S3 Select, and read the matching rows as
+//! `serde` types.
+//!
+//! S3 Select returns rows as JSON Lines in `Records` events, but an event can end in the middle
+//! of a row, or even in the middle of a UTF-8 character. `RecordDecoder` holds on to the
+//! unfinished line until the rest arrives. `SelectStream` drives a decoder from any
+//! `EventSource`, either the SDK's event receiver or a `recorded::RecordedStream`, and passes
+//! along the `Progress` and `Stats` events S3 sends as it scans.
+
+use std::{collections::VecDeque, marker::PhantomData};
+
+use async_trait::async_trait;
+use aws_sdk_s3::{
+    primitives::event_stream::EventReceiver,
+    types::{
+        error::SelectObjectContentEventStreamError, CompressionType, CsvInput, ExpressionType,
+        FileHeaderInfo, InputSerialization, JsonInput, JsonOutput, JsonType, OutputSerialization,
+        ParquetInput, SelectObjectContentEventStream,
+    },
+    Client,
+};
+use serde::de::DeserializeOwned;
+use tracing::{debug, warn};
+
+use crate::error::S3ExampleError;
+
+pub mod recorded;
+
+/// How the first line of a CSV object is treated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Header {
+    /// The first line names the columns. Rows come back keyed by column name, and the query
+    /// can refer to columns by name.
+    #[default]
+    Use,
+    /// The first line is a header, but is skipped. Rows come back keyed `_1`, `_2`, and so on.
+    Ignore,
+    /// There is no header. Rows come back keyed `_1`, `_2`, and so on.
+    None,
+}
+
+impl From<Header> for FileHeaderInfo {
+    fn from(header: Header) -> Self {
+        match header {
+            Header::Use => FileHeaderInfo::Use,
+            Header::Ignore => FileHeaderInfo::Ignore,
+            Header::None => FileHeaderInfo::None,
+        }
+    }
+}
+
+/// The layout of a CSV object.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CsvFormat {
+    header: Header,
+    field_delimiter: char,
+    record_delimiter: char,
+    quote: char,
+    comments: Option<char>,
+    quoted_record_delimiter: bool,
+}
+
+impl Default for CsvFormat {
+    fn default() -> Self {
+        CsvFormat {
+            header: Header::Use,
+            field_delimiter: ',',
+            record_delimiter: '\n',
+            quote: '"',
+            comments: None,
+            quoted_record_delimiter: false,
+        }
+    }
+}
+
+impl CsvFormat {
+    pub fn with_header(mut self, header: Header) -> Self {
+        self.header = header;
+        self
+    }
+
+    pub fn with_field_delimiter(mut self, delimiter: char) -> Self {
+        self.field_delimiter = delimiter;
+        self
+    }
+
+    pub fn with_record_delimiter(mut self, delimiter: char) -> Self {
+        self.record_delimiter = delimiter;
+        self
+    }
+
+    pub fn with_quote(mut self, quote: char) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Skip lines that start with `comments`.
+    pub fn with_comments(mut self, comments: char) -> Self {
+        self.comments = Some(comments);
+        self
+    }
+
+    /// Whether quoted fields can contain the record delimiter. This makes S3 Select slower, so
+    /// it is off by default.
+    pub fn with_quoted_record_delimiter(mut self, allow: bool) -> Self {
+        self.quoted_record_delimiter = allow;
+        self
+    }
+
+    fn csv_input(&self) -> CsvInput {
+        CsvInput::builder()
+            .file_header_info(self.header.into())
+            .field_delimiter(self.field_delimiter.to_string())
+            .record_delimiter(self.record_delimiter.to_string())
+            .quote_character(self.quote.to_string())
+            .set_comments(self.comments.map(String::from))
+            .allow_quoted_record_delimiter(self.quoted_record_delimiter)
+            .build()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InputFormat {
+    Csv(CsvFormat),
+    /// One JSON object per line.
+    JsonLines,
+    /// A single JSON document, or several JSON objects not split by lines.
+    JsonDocument,
+    Parquet,
+}
+
+impl Default for InputFormat {
+    fn default() -> Self {
+        InputFormat::Csv(CsvFormat::default())
+    }
+}
+
+/// How the whole object is compressed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Bzip2,
+}
+
+impl From<Compression> for CompressionType {
+    fn from(compression: Compression) -> Self {
+        match compression {
+            Compression::None => CompressionType::None,
+            Compression::Gzip => CompressionType::Gzip,
+            Compression::Bzip2 => CompressionType::Bzip2,
+        }
+    }
+}
+
+/// An SQL expression and the format of the object it runs against.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelectQuery {
+    expression: String,
+    input: InputFormat,
+    compression: Compression,
+}
+
+impl SelectQuery {
+    /// A query of an uncompressed CSV object with a header line.
+    pub fn new(expression: impl Into<String>) -> Self {
+        SelectQuery {
+            expression: expression.into(),
+            input: InputFormat::default(),
+            compression: Compression::default(),
+        }
+    }
+
+    pub fn with_input(mut self, input: InputFormat) -> Self {
+        self.input = input;
+        self
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn input_serialization(&self) -> Result<InputSerialization, S3ExampleError> {
+        let builder = InputSerialization::builder().compression_type(self.compression.into());
+        Ok(match &self.input {
+            InputFormat::Csv(csv) => builder.csv(csv.csv_input()),
+            InputFormat::JsonLines => {
+                builder.json(JsonInput::builder().r#type(JsonType::Lines).build())
+            }
+            InputFormat::JsonDocument => {
+                builder.json(JsonInput::builder().r#type(JsonType::Document).build())
+            }
+            // Parquet compresses its own columns, and S3 Select rejects whole-object compression.
+            InputFormat::Parquet if self.compression != Compression::None => {
+                return Err(S3ExampleError::new(
+                    "Parquet objects can't use whole-object compression",
+                ))
+            }
+            InputFormat::Parquet => builder.parquet(ParquetInput::builder().build()),
+        }
+        .build())
+    }
+
+    // snippet-start:[s3.rust.select-object-content]
+    /// Run the query against `bucket`/`key`. Rows come back as JSON Lines, and are read into `T`
+    /// as the stream is consumed.
+    pub async fn send<T: DeserializeOwned>(
+        &self,
+        client: &Client,
+        bucket: &str,
+        key: &str,
+    ) -> Result<SdkSelectStream<T>, S3ExampleError> {
+        debug!(query = %self.expression, "Selecting from {bucket}/{key}");
+        let output = client
+            .select_object_content()
+            .bucket(bucket)
+            .key(key)
+            .expression_type(ExpressionType::Sql)
+            .expression(&self.expression)
+            .input_serialization(self.input_serialization()?)
+            .output_serialization(
+                OutputSerialization::builder()
+                    .json(JsonOutput::builder().record_delimiter("\n").build())
+                    .build(),
+            )
+            .send()
+            .await?;
+        Ok(SelectStream::new(output.payload))
+    }
+    // snippet-end:[s3.rust.select-object-content]
+}
+
+/// A [SelectStream] reading from the SDK.
+pub type SdkSelectStream<T> = SelectStream<
+    EventReceiver<SelectObjectContentEventStream, SelectObjectContentEventStreamError>,
+    T,
+>;
+
+/// Where S3 Select events come from.
+#[async_trait]
+pub trait EventSource: Send {
+    /// The next event, or None when the stream has ended.
+    async fn recv(&mut self) -> Result<Option<SelectObjectContentEventStream>, S3ExampleError>;
+}
+
+#[async_trait]
+impl EventSource
+    for EventReceiver<SelectObjectContentEventStream, SelectObjectContentEventStreamError>
+{
+    async fn recv(&mut self) -> Result<Option<SelectObjectContentEventStream>, S3ExampleError> {
+        EventReceiver::recv(self)
+            .await
+            .map_err(S3ExampleError::from)
+    }
+}
+
+/// How many bytes S3 Select has scanned, processed after decompression, and returned.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScanStats {
+    pub bytes_scanned: i64,
+    pub bytes_processed: i64,
+    pub bytes_returned: i64,
+}
+
+impl From<&aws_sdk_s3::types::Stats> for ScanStats {
+    fn from(stats: &aws_sdk_s3::types::Stats) -> Self {
+        ScanStats {
+            bytes_scanned: stats.bytes_scanned().unwrap_or_default(),
+            bytes_processed: stats.bytes_processed().unwrap_or_default(),
+            bytes_returned: stats.bytes_returned().unwrap_or_default(),
+        }
+    }
+}
+
+impl From<&aws_sdk_s3::types::Progress> for ScanStats {
+    fn from(progress: &aws_sdk_s3::types::Progress) -> Self {
+        ScanStats {
+            bytes_scanned: progress.bytes_scanned().unwrap_or_default(),
+            bytes_processed: progress.bytes_processed().unwrap_or_default(),
+            bytes_returned: progress.bytes_returned().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SelectEvent<T> {
+    Row(T),
+    /// Sent from time to time while a long scan runs.
+    Progress(ScanStats),
+    /// Sent once, when the scan is done.
+    Stats(ScanStats),
+}
+
+/// Splits `Records` payloads into lines and reads each whole line into a `T`.
+pub struct RecordDecoder<T> {
+    buffer: Vec<u8>,
+    row: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Default for RecordDecoder<T> {
+    fn default() -> Self {
+        RecordDecoder {
+            buffer: Vec::new(),
+            row: PhantomData,
+        }
+    }
+}
+
+impl<T: DeserializeOwned> RecordDecoder<T> {
+    /// The rows completed by `chunk`. A row left unfinished at the end of `chunk` is kept for
+    /// the next one.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<T>, S3ExampleError> {
+        self.buffer.extend_from_slice(chunk);
+        let Some(end) = self.buffer.iter().rposition(|b| *b == b'\n') else {
+            return Ok(Vec::new());
+        };
+        let complete: Vec<u8> = self.buffer.drain(..=end).collect();
+        complete
+            .split(|b| *b == b'\n')
+            .filter(|line| !is_blank(line))
+            .map(parse_row)
+            .collect()
+    }
+
+    /// The last row, if the stream didn't end with a line break.
+    pub fn finish(&mut self) -> Result<Option<T>, S3ExampleError> {
+        let rest = std::mem::take(&mut self.buffer);
+        if is_blank(&rest) {
+            Ok(None)
+        } else {
+            parse_row(&rest).map(Some)
+        }
+    }
+}
+
+fn is_blank(line: &[u8]) -> bool {
+    line.iter().all(u8::is_ascii_whitespace)
+}
+
+fn parse_row<T: DeserializeOwned>(line: &[u8]) -> Result<T, S3ExampleError> {
+    serde_json::from_slice(line).map_err(|err| {
+        S3ExampleError::new(format!(
+            "Failed to read row {}: {err}",
+            String::from_utf8_lossy(line)
+        ))
+    })
+}
+
+/// Every row of a finished query, and the final stats.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectOutput<T> {
+    pub rows: Vec<T>,
+    pub stats: Option<ScanStats>,
+}
+
+/// The rows and scan events of a query, in the order S3 sent them.
+pub struct SelectStream<S, T> {
+    source: S,
+    decoder: RecordDecoder<T>,
+    rows: VecDeque<T>,
+    ended: bool,
+    done: bool,
+}
+
+impl<S: EventSource, T: DeserializeOwned> SelectStream<S, T> {
+    pub fn new(source: S) -> Self {
+        SelectStream {
+            source,
+            decoder: RecordDecoder::default(),
+            rows: VecDeque::new(),
+            ended: false,
+            done: false,
+        }
+    }
+
+    /// The next row or scan event, or None once S3 has sent everything.
+    pub async fn next(&mut self) -> Result<Option<SelectEvent<T>>, S3ExampleError> {
+        loop {
+            if let Some(row) = self.rows.pop_front() {
+                return Ok(Some(SelectEvent::Row(row)));
+            }
+            if self.done {
+                return Ok(None);
+            }
+            match self.source.recv().await? {
+                Some(SelectObjectContentEventStream::Records(records)) => {
+                    let payload = records.payload().map(|p| p.as_ref()).unwrap_or_default();
+                    self.rows.extend(self.decoder.push(payload)?);
+                }
+                Some(SelectObjectContentEventStream::Progress(progress)) => {
+                    if let Some(details) = progress.details() {
+                        return Ok(Some(SelectEvent::Progress(details.into())));
+                    }
+                }
+                Some(SelectObjectContentEventStream::Stats(stats)) => {
+                    if let Some(details) = stats.details() {
+                        return Ok(Some(SelectEvent::Stats(details.into())));
+                    }
+                }
+                // Keep-alives during a long scan with no matches.
+                Some(SelectObjectContentEventStream::Cont(_)) => {}
+                Some(SelectObjectContentEventStream::End(_)) => {
+                    self.ended = true;
+                    self.rows.extend(self.decoder.finish()?);
+                }
+                Some(other) => warn!("Skipping unknown select event {other:?}"),
+                None => {
+                    // Without an End event, the results may be missing rows.
+                    if !self.ended {
+                        return Err(S3ExampleError::new(
+                            "The select stream stopped before the End event",
+                        ));
+                    }
+                    self.done = true;
+                }
+            }
+        }
+    }
+
+    /// Read the whole stream, keeping the rows and the final stats.
+    pub async fn collect(mut self) -> Result<SelectOutput<T>, S3ExampleError> {
+        let mut output = SelectOutput {
+            rows: Vec::new(),
+            stats: None,
+        };
+        while let Some(event) = self.next().await? {
+            match event {
+                SelectEvent::Row(row) => output.rows.push(row),
+                SelectEvent::Progress(_) => {}
+                SelectEvent::Stats(stats) => output.stats = Some(stats),
+            }
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use super::{
+        recorded::RecordedStream, Compression, CsvFormat, Header, InputFormat, RecordDecoder,
+        ScanStats, SelectEvent, SelectQuery, SelectStream,
+    };
+
+    const CSV_PEOPLE: &str = include_str!("./fixtures/csv-people.jsonl");
+    const TRUNCATED: &str = include_str!("./fixtures/truncated.jsonl");
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "PascalCase")]
+    struct Person {
+        name: String,
+        city: String,
+        occupation: String,
+    }
+
+    fn person(name: &str, city: &str, occupation: &str) -> Person {
+        Person {
+            name: name.into(),
+            city: city.into(),
+            occupation: occupation.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rows_across_records() {
+        let mut stream = SelectStream::<_, Person>::new(RecordedStream::parse(CSV_PEOPLE).unwrap());
+        let mut events = Vec::new();
+        while let Some(event) = stream.next().await.unwrap() {
+            events.push(event);
+        }
+
+        assert_eq!(
+            events,
+            vec![
+                SelectEvent::Row(person("Name_369", "City_7", "Occupation_2")),
+                SelectEvent::Progress(ScanStats {
+                    bytes_scanned: 4096,
+                    bytes_processed: 4096,
+                    bytes_returned: 60,
+                }),
+                SelectEvent::Row(person("Name_370", "São Paulo", "Occupation_12")),
+                SelectEvent::Row(person("Name_371", "City_81", "Occupation_7")),
+                SelectEvent::Stats(ScanStats {
+                    bytes_scanned: 8192,
+                    bytes_processed: 8192,
+                    bytes_returned: 181,
+                }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_collect() {
+        let output = SelectStream::<_, Person>::new(RecordedStream::parse(CSV_PEOPLE).unwrap())
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(output.rows.len(), 3);
+        assert_eq!(output.stats.unwrap().bytes_returned, 181);
+    }
+
+    #[tokio::test]
+    async fn test_missing_end() {
+        let result = SelectStream::<_, Person>::new(RecordedStream::parse(TRUNCATED).unwrap())
+            .collect()
+            .await;
+        assert!(result.is_err(), "{result:?}");
+    }
+
+    #[tokio::test]
+    async fn test_stream_error() {
+        let source = RecordedStream::parse(CSV_PEOPLE)
+            .unwrap()
+            .fail_after(1, "InternalError");
+        let result = SelectStream::<_, Person>::new(source).collect().await;
+        assert!(result.unwrap_err().to_string().contains("InternalError"));
+    }
+
+    #[test]
+    fn test_decoder_splits_characters() {
+        let line = "{\"Name\":\"Zoë\",\"City\":\"Ørsta\",\"Occupation\":\"Chef\"}\n".as_bytes();
+        // Split inside the two-byte ë.
+        let split = line.iter().position(|b| *b == 0xC3).unwrap() + 1;
+
+        let mut decoder = RecordDecoder::<Person>::default();
+        assert!(decoder.push(&line[..split]).unwrap().is_empty());
+        assert_eq!(
+            decoder.push(&line[split..]).unwrap(),
+            vec![person("Zoë", "Ørsta", "Chef")]
+        );
+        assert_eq!(decoder.finish().unwrap(), None);
+
+        // A last row without a line break is read at the end.
+        assert!(decoder.push(&line[..line.len() - 1]).unwrap().is_empty());
+        assert!(decoder.finish().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_input_serialization() {
+        let csv = SelectQuery::new("SELECT * FROM s3object")
+            .with_input(InputFormat::Csv(
+                CsvFormat::default()
+                    .with_header(Header::Ignore)
+                    .with_field_delimiter('\t')
+                    .with_comments('#'),
+            ))
+            .with_compression(Compression::Gzip)
+            .input_serialization()
+            .unwrap();
+        let input = csv.csv().unwrap();
+        assert_eq!(input.field_delimiter(), Some("\t"));
+        assert_eq!(input.comments(), Some("#"));
+        assert_eq!(
+            input.file_header_info(),
+            Some(&aws_sdk_s3::types::FileHeaderInfo::Ignore)
+        );
+        assert_eq!(
+            csv.compression_type(),
+            Some(&aws_sdk_s3::types::CompressionType::Gzip)
+        );
+
+        let lines = SelectQuery::new("SELECT * FROM s3object")
+            .with_input(InputFormat::JsonLines)
+            .input_serialization()
+            .unwrap();
+        assert_eq!(
+            lines.json().unwrap().r#type(),
+            Some(&aws_sdk_s3::types::JsonType::Lines)
+        );
+
+        let parquet = SelectQuery::new("SELECT * FROM s3object").with_input(InputFormat::Parquet);
+        assert!(parquet.input_serialization().unwrap().parquet().is_some());
+        assert!(parquet
+            .with_compression(Compression::Bzip2)
+            .input_serialization()
+            .is_err());
+    }
+}
@@ -0,0 +1,128 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replay a SelectObjectContent event stream from a recorded fixture, for testing offline.
+//!
+//! A fixture has one event per line: `{"records":"..."}` with the payload as a string,
+//! `{"progress":{...}}` and `{"stats":{...}}` with `bytesScanned`, `bytesProcessed`, and
+//! `bytesReturned`, and `{"cont":{}}` and `{"end":{}}`.
+
+use std::collections::VecDeque;
+
+use async_trait::async_trait;
+use aws_sdk_s3::{
+    primitives::Blob,
+    types::{
+        ContinuationEvent, EndEvent, Progress, ProgressEvent, RecordsEvent,
+        SelectObjectContentEventStream, Stats, StatsEvent,
+    },
+};
+use serde::Deserialize;
+
+use crate::error::S3ExampleError;
+
+use super::EventSource;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum RecordedEvent {
+    Records(String),
+    Progress(RecordedStats),
+    Stats(RecordedStats),
+    Cont {},
+    End {},
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordedStats {
+    bytes_scanned: i64,
+    bytes_processed: i64,
+    bytes_returned: i64,
+}
+
+impl From<RecordedEvent> for SelectObjectContentEventStream {
+    fn from(event: RecordedEvent) -> Self {
+        match event {
+            RecordedEvent::Records(payload) => SelectObjectContentEventStream::Records(
+                RecordsEvent::builder()
+                    .payload(Blob::new(payload.into_bytes()))
+                    .build(),
+            ),
+            RecordedEvent::Progress(stats) => SelectObjectContentEventStream::Progress(
+                ProgressEvent::builder()
+                    .details(
+                        Progress::builder()
+                            .bytes_scanned(stats.bytes_scanned)
+                            .bytes_processed(stats.bytes_processed)
+                            .bytes_returned(stats.bytes_returned)
+                            .build(),
+                    )
+                    .build(),
+            ),
+            RecordedEvent::Stats(stats) => SelectObjectContentEventStream::Stats(
+                StatsEvent::builder()
+                    .details(
+                        Stats::builder()
+                            .bytes_scanned(stats.bytes_scanned)
+                            .bytes_processed(stats.bytes_processed)
+                            .bytes_returned(stats.bytes_returned)
+                            .build(),
+                    )
+                    .build(),
+            ),
+            RecordedEvent::Cont {} => {
+                SelectObjectContentEventStream::Cont(ContinuationEvent::builder().build())
+            }
+            RecordedEvent::End {} => {
+                SelectObjectContentEventStream::End(EndEvent::builder().build())
+            }
+        }
+    }
+}
+
+/// An EventSource that replays recorded events.
+pub struct RecordedStream {
+    events: VecDeque<SelectObjectContentEventStream>,
+    /// When set, replaying fails with this error after the recorded events run out.
+    error: Option<String>,
+}
+
+impl RecordedStream {
+    /// Parse a fixture with one JSON event per line. Blank lines are skipped.
+    pub fn parse(fixture: &str) -> Result<Self, S3ExampleError> {
+        let events = fixture
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str::<RecordedEvent>(line)
+                    .map(SelectObjectContentEventStream::from)
+                    .map_err(|e| S3ExampleError::new(format!("Bad fixture line {line}: {e}")))
+            })
+            .collect::<Result<VecDeque<_>, _>>()?;
+        Ok(Self {
+            events,
+            error: None,
+        })
+    }
+
+    /// Keep only the first `count` events, then fail, as if the connection dropped.
+    pub fn fail_after(mut self, count: usize, error: &str) -> Self {
+        self.events.truncate(count);
+        self.error = Some(error.into());
+        self
+    }
+}
+
+#[async_trait]
+impl EventSource for RecordedStream {
+    async fn recv(&mut self) -> Result<Option<SelectObjectContentEventStream>, S3ExampleError> {
+        match self.events.pop_front() {
+            Some(event) => Ok(Some(event)),
+            None => match self.error.take() {
+                Some(error) => Err(S3ExampleError::new(error)),
+                None => Ok(None),
+            },
+        }
+    }
+}
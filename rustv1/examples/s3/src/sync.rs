@@ -0,0 +1,856 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Sync a local directory with a bucket prefix, in either direction. Only files that are
+//! missing or changed on the other side are copied, and files that only exist on the other side
+//! can be deleted.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use aws_sdk_s3::Client;
+use futures_util::StreamExt;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
+
+use crate::{
+    delete_objects, download_object,
+    error::S3ExampleError,
+    progress::ProgressTracker,
+    transfer::{
+        download::{file_md5s, with_suffix},
+        MultipartUploader, RangedDownloader, DEFAULT_CONCURRENCY,
+    },
+    upload_object,
+};
+
+/// Files at least this large are transferred in parts.
+pub const DEFAULT_MULTIPART_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+/// DeleteObjects takes at most this many keys.
+const MAX_DELETE_KEYS: usize = 1000;
+
+/// Files a download is written to before it's complete. They're never synced themselves.
+const WORKING_FILE_SUFFIXES: [&str; 3] = [".part", ".download.json", ".download.tmp"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Direction {
+    /// Make the bucket prefix match the local directory.
+    Upload,
+    /// Make the local directory match the bucket prefix.
+    Download,
+}
+
+/// How to tell whether a file that exists on both sides has changed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Compare {
+    /// Only a different size counts as a change.
+    Size,
+    /// A different size, or a newer modification time on the source side.
+    #[default]
+    Time,
+    /// A different size, or a different MD5. Objects uploaded in parts don't have an MD5 ETag,
+    /// and fall back to comparing times.
+    Checksum,
+}
+
+/// Include and exclude globs, matched against paths relative to the directory or prefix, with
+/// `/` separators. `*` matches across directories, so `*.log` matches `logs/app.log`.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl Filter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, S3ExampleError> {
+        Ok(Filter {
+            include: glob_set(include)?,
+            exclude: glob_set(exclude)?,
+        })
+    }
+
+    /// A path is synced if it matches an include glob, or there are none, and matches no
+    /// exclude glob.
+    pub fn matches(&self, relative: &str) -> bool {
+        let included = match &self.include {
+            Some(include) => include.is_match(relative),
+            None => true,
+        };
+        included && !self.exclude.as_ref().is_some_and(|g| g.is_match(relative))
+    }
+}
+
+fn glob_set(globs: &[String]) -> Result<Option<GlobSet>, S3ExampleError> {
+    if globs.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(
+            Glob::new(glob)
+                .map_err(|err| S3ExampleError::new(format!("Invalid glob {glob}: {err}")))?,
+        );
+    }
+    builder
+        .build()
+        .map(Some)
+        .map_err(|err| S3ExampleError::new(format!("Invalid globs: {err}")))
+}
+
+/// A file in the local directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocalFile {
+    pub relative: String,
+    pub size: u64,
+    /// Seconds since the epoch.
+    pub modified: i64,
+    /// Only filled in when comparing checksums, for files the bucket has at the same size.
+    pub md5: Option<String>,
+}
+
+/// An object under the bucket prefix.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteObject {
+    pub relative: String,
+    pub size: u64,
+    /// Seconds since the epoch.
+    pub modified: i64,
+    pub e_tag: String,
+}
+
+impl RemoteObject {
+    /// The object's MD5, if its ETag is one. Objects uploaded in parts have ETags ending in
+    /// `-N`, which aren't.
+    fn md5(&self) -> Option<&str> {
+        Some(self.e_tag.trim_matches('"')).filter(|e_tag| !e_tag.contains('-'))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActionKind {
+    Upload,
+    Download,
+    DeleteRemote,
+    DeleteLocal,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reason {
+    Missing,
+    SizeChanged,
+    Newer,
+    ContentChanged,
+    Extraneous,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyncAction {
+    pub kind: ActionKind,
+    pub relative: String,
+    pub size: u64,
+    pub reason: Reason,
+}
+
+impl Display for SyncAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            ActionKind::Upload => "upload",
+            ActionKind::Download => "download",
+            ActionKind::DeleteRemote => "delete (bucket)",
+            ActionKind::DeleteLocal => "delete (local)",
+        };
+        let reason = match self.reason {
+            Reason::Missing => "missing",
+            Reason::SizeChanged => "size changed",
+            Reason::Newer => "newer",
+            Reason::ContentChanged => "content changed",
+            Reason::Extraneous => "not in source",
+        };
+        write!(f, "{kind:<16} {} ({reason})", self.relative)
+    }
+}
+
+/// The actions that make the destination match the source.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyncPlan {
+    pub actions: Vec<SyncAction>,
+}
+
+impl SyncPlan {
+    /// Compare the source and destination listings. Both are filtered already.
+    pub fn new(
+        direction: Direction,
+        local: &[LocalFile],
+        remote: &[RemoteObject],
+        compare: Compare,
+        delete: bool,
+    ) -> Self {
+        let local: BTreeMap<&str, &LocalFile> =
+            local.iter().map(|f| (f.relative.as_str(), f)).collect();
+        let remote: BTreeMap<&str, &RemoteObject> =
+            remote.iter().map(|o| (o.relative.as_str(), o)).collect();
+        let mut actions = Vec::new();
+
+        match direction {
+            Direction::Upload => {
+                for (relative, file) in &local {
+                    let reason = match remote.get(relative) {
+                        None => Some(Reason::Missing),
+                        Some(object) => {
+                            changed(compare, file, object, file.modified, object.modified)
+                        }
+                    };
+                    if let Some(reason) = reason {
+                        actions.push(SyncAction {
+                            kind: ActionKind::Upload,
+                            relative: relative.to_string(),
+                            size: file.size,
+                            reason,
+                        });
+                    }
+                }
+                if delete {
+                    for (relative, object) in &remote {
+                        if !local.contains_key(relative) {
+                            actions.push(SyncAction {
+                                kind: ActionKind::DeleteRemote,
+                                relative: relative.to_string(),
+                                size: object.size,
+                                reason: Reason::Extraneous,
+                            });
+                        }
+                    }
+                }
+            }
+            Direction::Download => {
+                for (relative, object) in &remote {
+                    let reason = match local.get(relative) {
+                        None => Some(Reason::Missing),
+                        Some(file) => {
+                            changed(compare, file, object, object.modified, file.modified)
+                        }
+                    };
+                    if let Some(reason) = reason {
+                        actions.push(SyncAction {
+                            kind: ActionKind::Download,
+                            relative: relative.to_string(),
+                            size: object.size,
+                            reason,
+                        });
+                    }
+                }
+                if delete {
+                    for (relative, file) in &local {
+                        if !remote.contains_key(relative) {
+                            actions.push(SyncAction {
+                                kind: ActionKind::DeleteLocal,
+                                relative: relative.to_string(),
+                                size: file.size,
+                                reason: Reason::Extraneous,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        SyncPlan { actions }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+}
+
+/// Why a file on both sides needs copying from the source, if it does.
+fn changed(
+    compare: Compare,
+    file: &LocalFile,
+    object: &RemoteObject,
+    source_modified: i64,
+    destination_modified: i64,
+) -> Option<Reason> {
+    if file.size != object.size {
+        return Some(Reason::SizeChanged);
+    }
+    let newer = (source_modified > destination_modified).then_some(Reason::Newer);
+    match compare {
+        Compare::Size => None,
+        Compare::Time => newer,
+        Compare::Checksum => match (file.md5.as_deref(), object.md5()) {
+            (Some(local), Some(remote)) => (local != remote).then_some(Reason::ContentChanged),
+            _ => newer,
+        },
+    }
+}
+
+impl Display for SyncPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for action in &self.actions {
+            writeln!(f, "{action}")?;
+        }
+        let bytes: u64 = self
+            .actions
+            .iter()
+            .filter(|a| matches!(a.kind, ActionKind::Upload | ActionKind::Download))
+            .map(|a| a.size)
+            .sum();
+        write!(
+            f,
+            "{} actions, {bytes} bytes to transfer",
+            self.actions.len()
+        )
+    }
+}
+
+/// What applying a plan did.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub transferred: Vec<String>,
+    pub deleted: Vec<String>,
+    /// Paths whose action failed, with the error.
+    pub failed: Vec<(String, String)>,
+}
+
+impl Display for SyncReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Transferred {}, deleted {}, failed {}",
+            self.transferred.len(),
+            self.deleted.len(),
+            self.failed.len()
+        )?;
+        for (relative, err) in &self.failed {
+            writeln!(f, "  {relative}: {err}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Syncs one local directory with one bucket prefix.
+#[derive(Clone, Debug)]
+pub struct Syncer {
+    client: Client,
+    local: PathBuf,
+    bucket: String,
+    prefix: String,
+    filter: Filter,
+    compare: Compare,
+    delete: bool,
+    concurrency: usize,
+    multipart_threshold: u64,
+    progress: ProgressTracker,
+}
+
+impl Syncer {
+    pub fn new(
+        client: Client,
+        local: impl Into<PathBuf>,
+        bucket: impl Into<String>,
+        prefix: &str,
+    ) -> Self {
+        // Treat the prefix as a folder, so `photos` doesn't also sync `photos-old/`.
+        let prefix = match prefix.trim_start_matches('/') {
+            "" => String::new(),
+            prefix => format!("{}/", prefix.trim_end_matches('/')),
+        };
+        Syncer {
+            client,
+            local: local.into(),
+            bucket: bucket.into(),
+            prefix,
+            filter: Filter::default(),
+            compare: Compare::default(),
+            delete: false,
+            concurrency: DEFAULT_CONCURRENCY,
+            multipart_threshold: DEFAULT_MULTIPART_THRESHOLD,
+            progress: ProgressTracker::silent(),
+        }
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_compare(mut self, compare: Compare) -> Self {
+        self.compare = compare;
+        self
+    }
+
+    /// Delete files in the destination that aren't in the source.
+    pub fn with_delete(mut self, delete: bool) -> Self {
+        self.delete = delete;
+        self
+    }
+
+    /// How many files to transfer at once.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Files at least this large are transferred in parts with the transfer module.
+    pub fn with_multipart_threshold(mut self, multipart_threshold: u64) -> Self {
+        self.multipart_threshold = multipart_threshold;
+        self
+    }
+
+    /// Report the bytes and files transferred by [Syncer::apply] to `progress`, as one
+    /// transfer per file.
+    pub fn with_progress(mut self, progress: ProgressTracker) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// List both sides and work out what to do, without changing anything.
+    pub async fn plan(&self, direction: Direction) -> Result<SyncPlan, S3ExampleError> {
+        let remote = self.list_remote().await?;
+        let mut local = self.list_local()?;
+        if self.compare == Compare::Checksum {
+            let sizes: BTreeMap<&str, &RemoteObject> = remote
+                .iter()
+                .filter(|o| o.md5().is_some())
+                .map(|o| (o.relative.as_str(), o))
+                .collect();
+            for file in local.iter_mut() {
+                if sizes.get(file.relative.as_str()).map(|o| o.size) == Some(file.size) {
+                    file.md5 = Some(file_md5(&self.local_path(&file.relative)).await?);
+                }
+            }
+        }
+        Ok(SyncPlan::new(
+            direction,
+            &local,
+            &remote,
+            self.compare,
+            self.delete,
+        ))
+    }
+
+    /// Carry out a plan. A failed action doesn't stop the others; it is recorded in the report.
+    pub async fn apply(&self, plan: &SyncPlan) -> SyncReport {
+        let mut report = SyncReport::default();
+
+        // Every transfer is counted up front, so the total is known from the start.
+        let transfers: Vec<(&SyncAction, ProgressTracker)> = plan
+            .actions
+            .iter()
+            .filter(|a| matches!(a.kind, ActionKind::Upload | ActionKind::Download))
+            .map(|action| {
+                let progress = self.progress.transfer();
+                progress.set_total(action.size);
+                (action, progress)
+            })
+            .collect();
+        let mut results = futures_util::stream::iter(transfers)
+            .map(
+                |(action, progress)| async move { (action, self.transfer(action, progress).await) },
+            )
+            .buffer_unordered(self.concurrency);
+        while let Some((action, result)) = results.next().await {
+            match result {
+                Ok(()) => report.transferred.push(action.relative.clone()),
+                Err(err) => report
+                    .failed
+                    .push((action.relative.clone(), err.to_string())),
+            }
+        }
+
+        let remote_deletes: Vec<&SyncAction> = plan
+            .actions
+            .iter()
+            .filter(|a| a.kind == ActionKind::DeleteRemote)
+            .collect();
+        for chunk in remote_deletes.chunks(MAX_DELETE_KEYS) {
+            let keys = chunk.iter().map(|a| self.key(&a.relative)).collect();
+            match delete_objects(&self.client, &self.bucket, keys).await {
+                Ok(()) => report
+                    .deleted
+                    .extend(chunk.iter().map(|a| a.relative.clone())),
+                Err(err) => report
+                    .failed
+                    .extend(chunk.iter().map(|a| (a.relative.clone(), err.to_string()))),
+            }
+        }
+
+        for action in plan
+            .actions
+            .iter()
+            .filter(|a| a.kind == ActionKind::DeleteLocal)
+        {
+            match tokio::fs::remove_file(self.local_path(&action.relative)).await {
+                Ok(()) => report.deleted.push(action.relative.clone()),
+                Err(err) => report
+                    .failed
+                    .push((action.relative.clone(), format!("{err:?}"))),
+            }
+        }
+
+        self.progress.finish();
+        report
+    }
+
+    async fn transfer(
+        &self,
+        action: &SyncAction,
+        progress: ProgressTracker,
+    ) -> Result<(), S3ExampleError> {
+        let path = self.local_path(&action.relative);
+        let key = self.key(&action.relative);
+        debug!("{action}");
+        match action.kind {
+            ActionKind::Upload if action.size >= self.multipart_threshold => {
+                MultipartUploader::new(self.client.clone())
+                    .with_progress(progress.clone())
+                    .upload(&path, &self.bucket, &key)
+                    .await?;
+            }
+            ActionKind::Upload => {
+                upload_object(&self.client, &self.bucket, &path.to_string_lossy(), &key).await?;
+                progress.track(action.size);
+                progress.finish();
+            }
+            ActionKind::Download => {
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent)
+                        .await
+                        .map_err(|err| write_error(parent, err))?;
+                }
+                let modified = if action.size >= self.multipart_threshold {
+                    RangedDownloader::new(self.client.clone())
+                        .with_progress(progress.clone())
+                        .download(&self.bucket, &key, &path)
+                        .await?;
+                    None
+                } else {
+                    let modified = self.download_small(&key, &path, &progress).await?;
+                    progress.finish();
+                    modified
+                };
+                // Give the file the object's time, so the next sync sees them as the same.
+                if let Some(modified) = modified {
+                    set_modified(&path, modified).map_err(|err| write_error(&path, err))?;
+                }
+            }
+            ActionKind::DeleteRemote | ActionKind::DeleteLocal => {}
+        }
+        Ok(())
+    }
+
+    /// Download into `path.part`, and only replace `path` once the whole object is there, so a
+    /// failed download never leaves a truncated file where the old one was.
+    async fn download_small(
+        &self,
+        key: &str,
+        path: &Path,
+        progress: &ProgressTracker,
+    ) -> Result<Option<SystemTime>, S3ExampleError> {
+        let mut object = download_object(&self.client, &self.bucket, key).await?;
+        let partial = with_suffix(path, ".part");
+        let mut written = 0;
+        let result = async {
+            let mut file = tokio::fs::File::create(&partial)
+                .await
+                .map_err(|err| write_error(&partial, err))?;
+            while let Some(bytes) = object.body.try_next().await.map_err(|err| {
+                S3ExampleError::new(format!("Failed to read from S3 download stream: {err:?}"))
+            })? {
+                file.write_all(&bytes)
+                    .await
+                    .map_err(|err| write_error(&partial, err))?;
+                written += bytes.len() as u64;
+                progress.track(bytes.len() as u64);
+            }
+            file.sync_data()
+                .await
+                .map_err(|err| write_error(&partial, err))?;
+            tokio::fs::rename(&partial, path)
+                .await
+                .map_err(|err| write_error(path, err))
+        }
+        .await;
+        if let Err(err) = result {
+            progress.untrack(written);
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(err);
+        }
+        Ok(object
+            .last_modified()
+            .and_then(|t| SystemTime::try_from(*t).ok()))
+    }
+
+    fn key(&self, relative: &str) -> String {
+        format!("{}{relative}", self.prefix)
+    }
+
+    fn local_path(&self, relative: &str) -> PathBuf {
+        relative
+            .split('/')
+            .fold(self.local.clone(), |path, part| path.join(part))
+    }
+
+    async fn list_remote(&self) -> Result<Vec<RemoteObject>, S3ExampleError> {
+        let mut objects = Vec::new();
+        let mut pages = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.bucket)
+            .prefix(&self.prefix)
+            .into_paginator()
+            .send();
+        while let Some(page) = pages.next().await {
+            for object in page?.contents() {
+                let Some(relative) = object.key().and_then(|k| k.strip_prefix(&self.prefix)) else {
+                    continue;
+                };
+                // Skip folder markers.
+                if relative.is_empty() || relative.ends_with('/') || !self.filter.matches(relative)
+                {
+                    continue;
+                }
+                if !is_safe_relative(relative) {
+                    warn!("Skipping {relative}, which would be written outside the directory");
+                    continue;
+                }
+                objects.push(RemoteObject {
+                    relative: relative.to_string(),
+                    size: object.size().unwrap_or_default() as u64,
+                    modified: object.last_modified().map(|t| t.secs()).unwrap_or_default(),
+                    e_tag: object.e_tag().unwrap_or_default().to_string(),
+                });
+            }
+        }
+        Ok(objects)
+    }
+
+    /// Symbolic links are followed, but a directory that's already been listed, by way of a link
+    /// back up the tree or another link to it, isn't listed again.
+    fn list_local(&self) -> Result<Vec<LocalFile>, S3ExampleError> {
+        let mut files = Vec::new();
+        if !self.local.exists() {
+            return Ok(files);
+        }
+        let read_error = |path: &Path, err| {
+            S3ExampleError::new(format!("Failed to read {}: {err:?}", path.display()))
+        };
+        let mut visited = HashSet::new();
+        let mut directories = vec![self.local.clone()];
+        while let Some(directory) = directories.pop() {
+            let canonical =
+                std::fs::canonicalize(&directory).map_err(|err| read_error(&directory, err))?;
+            if !visited.insert(canonical) {
+                debug!("Skipping {}, already listed", directory.display());
+                continue;
+            }
+            for entry in std::fs::read_dir(&directory).map_err(|err| read_error(&directory, err))? {
+                let entry = entry.map_err(|err| read_error(&directory, err))?;
+                let path = entry.path();
+                let metadata = std::fs::metadata(&path).map_err(|err| read_error(&path, err))?;
+                if metadata.is_dir() {
+                    directories.push(path);
+                    continue;
+                }
+                let relative = path
+                    .strip_prefix(&self.local)
+                    .unwrap_or(&path)
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                if !self.filter.matches(&relative)
+                    || WORKING_FILE_SUFFIXES
+                        .iter()
+                        .any(|suffix| relative.ends_with(suffix))
+                {
+                    continue;
+                }
+                files.push(LocalFile {
+                    relative,
+                    size: metadata.len(),
+                    modified: metadata
+                        .modified()
+                        .ok()
+                        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                        .map(|d| d.as_secs() as i64)
+                        .unwrap_or_default(),
+                    md5: None,
+                });
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// Whether a key, relative to the prefix, stays inside the local directory when its parts are
+/// joined onto it. Keys can hold `..`, `.`, empty parts, and backslashes that a path would
+/// treat as moving up or out of the directory.
+fn is_safe_relative(relative: &str) -> bool {
+    !relative.contains('\\')
+        && relative
+            .split('/')
+            .all(|part| !matches!(part, "" | "." | "..") && !Path::new(part).has_root())
+}
+
+fn write_error(path: &Path, err: std::io::Error) -> S3ExampleError {
+    S3ExampleError::new(format!("Failed to write {}: {err:?}", path.display()))
+}
+
+fn set_modified(path: &Path, modified: SystemTime) -> std::io::Result<()> {
+    std::fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(modified)
+}
+
+async fn file_md5(path: &Path) -> Result<String, S3ExampleError> {
+    Ok(format!("{:x}", file_md5s(path, u64::MAX).await?.remove(0)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn local(relative: &str, size: u64, modified: i64) -> LocalFile {
+        LocalFile {
+            relative: relative.into(),
+            size,
+            modified,
+            md5: None,
+        }
+    }
+
+    fn remote(relative: &str, size: u64, modified: i64, e_tag: &str) -> RemoteObject {
+        RemoteObject {
+            relative: relative.into(),
+            size,
+            modified,
+            e_tag: e_tag.into(),
+        }
+    }
+
+    #[test]
+    fn test_unsafe_keys() {
+        assert!(is_safe_relative("photos/2024/cat.jpg"));
+        assert!(is_safe_relative("photos/..cat.jpg"));
+        assert!(!is_safe_relative("photos/../../.ssh/authorized_keys"));
+        assert!(!is_safe_relative("../escape.txt"));
+        assert!(!is_safe_relative("photos/./cat.jpg"));
+        assert!(!is_safe_relative("photos//cat.jpg"));
+        assert!(!is_safe_relative("/etc/passwd"));
+        assert!(!is_safe_relative("photos\\..\\..\\escape.txt"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_list_local_stops_at_symlink_loops() {
+        let local = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir_all(local.join("photos")).unwrap();
+        std::fs::write(local.join("photos/cat.jpg"), "meow").unwrap();
+        std::fs::write(local.join("photos/dog.jpg.part"), "wo").unwrap();
+        std::fs::write(local.join("photos/dog.jpg.download.json"), "{}").unwrap();
+        std::os::unix::fs::symlink(&local, local.join("photos/up")).unwrap();
+
+        let client = Client::from_conf(sdk_examples_test_utils::client_config!(aws_sdk_s3).build());
+        let files = Syncer::new(client, &local, "bucket", "")
+            .list_local()
+            .unwrap();
+
+        let mut relative = files
+            .iter()
+            .map(|f| f.relative.as_str())
+            .collect::<Vec<_>>();
+        relative.sort();
+        assert_eq!(relative, vec!["photos/cat.jpg"]);
+        std::fs::remove_dir_all(&local).unwrap();
+    }
+
+    #[test]
+    fn test_filter() {
+        let filter = Filter::new(&["photos/**".into()], &["*.tmp".into()]).unwrap();
+        assert!(filter.matches("photos/2024/cat.jpg"));
+        assert!(!filter.matches("photos/2024/cat.tmp"));
+        assert!(!filter.matches("notes.txt"));
+        assert!(Filter::default().matches("anything"));
+        assert!(Filter::new(&["[".into()], &[]).is_err());
+    }
+
+    #[test]
+    fn test_upload_plan() {
+        let files = vec![
+            local("new.txt", 1, 100),
+            local("same.txt", 2, 100),
+            local("grown.txt", 3, 100),
+            local("touched.txt", 4, 200),
+        ];
+        let objects = vec![
+            remote("same.txt", 2, 150, "\"a\""),
+            remote("grown.txt", 2, 150, "\"b\""),
+            remote("touched.txt", 4, 150, "\"c\""),
+            remote("old.txt", 5, 150, "\"d\""),
+        ];
+
+        let plan = SyncPlan::new(Direction::Upload, &files, &objects, Compare::Time, true);
+        let actions: Vec<_> = plan
+            .actions
+            .iter()
+            .map(|a| (a.kind, a.relative.as_str(), a.reason))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                (ActionKind::Upload, "grown.txt", Reason::SizeChanged),
+                (ActionKind::Upload, "new.txt", Reason::Missing),
+                (ActionKind::Upload, "touched.txt", Reason::Newer),
+                (ActionKind::DeleteRemote, "old.txt", Reason::Extraneous),
+            ]
+        );
+
+        // Comparing sizes only ignores the newer time, and nothing is deleted without asking.
+        let plan = SyncPlan::new(Direction::Upload, &files, &objects, Compare::Size, false);
+        assert_eq!(plan.actions.len(), 2);
+    }
+
+    #[test]
+    fn test_download_plan_with_checksums() {
+        let mut same = local("same.txt", 2, 100);
+        same.md5 = Some("aaaa".into());
+        let mut edited = local("edited.txt", 2, 100);
+        edited.md5 = Some("bbbb".into());
+        let files = vec![same, edited, local("extra.txt", 1, 100)];
+        let objects = vec![
+            remote("same.txt", 2, 150, "\"aaaa\""),
+            remote("edited.txt", 2, 50, "\"cccc\""),
+            // Uploaded in parts, so compared by time.
+            remote("big.bin", 9, 150, "\"dddd-2\""),
+        ];
+
+        let plan = SyncPlan::new(
+            Direction::Download,
+            &files,
+            &objects,
+            Compare::Checksum,
+            true,
+        );
+        let actions: Vec<_> = plan
+            .actions
+            .iter()
+            .map(|a| (a.kind, a.relative.as_str(), a.reason))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                (ActionKind::Download, "big.bin", Reason::Missing),
+                (ActionKind::Download, "edited.txt", Reason::ContentChanged),
+                (ActionKind::DeleteLocal, "extra.txt", Reason::Extraneous),
+            ]
+        );
+    }
+}
@@ -0,0 +1,759 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Download an object as byte ranges, several at a time, into a partial file that a later run
+//! can finish if this one is interrupted. The finished file is checked against the object's
+//! checksum or ETag.
+
+use std::{
+    collections::BTreeSet,
+    io::SeekFrom,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use aws_sdk_s3::{
+    operation::head_object::HeadObjectOutput,
+    types::{ChecksumMode, ServerSideEncryption},
+    Client,
+};
+use futures_util::{StreamExt, TryStreamExt};
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tracing::{debug, info, warn};
+
+use crate::{error::S3ExampleError, progress::ProgressTracker};
+
+use super::{Checksum, DEFAULT_CONCURRENCY};
+
+/// Ranges are this large unless told otherwise.
+pub const DEFAULT_RANGE_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Which object a partial download is for, and which of its ranges are already on disk.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadManifest {
+    pub bucket: String,
+    pub key: String,
+    pub e_tag: String,
+    pub size: u64,
+    pub range_size: u64,
+    pub completed: BTreeSet<u64>,
+}
+
+impl DownloadManifest {
+    pub fn range_count(&self) -> u64 {
+        self.size.div_ceil(self.range_size)
+    }
+
+    /// The first and last byte of a range, as an HTTP Range header counts them.
+    pub fn range(&self, index: u64) -> (u64, u64) {
+        let start = index * self.range_size;
+        (start, (start + self.range_size).min(self.size) - 1)
+    }
+
+    fn completed_bytes(&self) -> u64 {
+        self.completed
+            .iter()
+            .map(|index| {
+                let (start, end) = self.range(*index);
+                end - start + 1
+            })
+            .sum()
+    }
+
+    pub fn load(path: &Path) -> Result<Option<Self>, S3ExampleError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(S3ExampleError::new(format!(
+                    "Failed to read download manifest {path:?}: {err:?}"
+                )))
+            }
+        };
+        serde_json::from_str(&contents).map(Some).map_err(|err| {
+            S3ExampleError::new(format!(
+                "Failed to parse download manifest {path:?}: {err:?}"
+            ))
+        })
+    }
+
+    /// Write to a temporary file first, so a crash never leaves half a manifest.
+    pub fn save(&self, path: &Path) -> Result<(), S3ExampleError> {
+        let json = serde_json::to_string(self).map_err(|err| {
+            S3ExampleError::new(format!("Failed to serialize download manifest: {err:?}"))
+        })?;
+        let temp = path.with_extension("tmp");
+        std::fs::write(&temp, json)
+            .and_then(|_| std::fs::rename(&temp, path))
+            .map_err(|err| {
+                S3ExampleError::new(format!(
+                    "Failed to save download manifest {path:?}: {err:?}"
+                ))
+            })
+    }
+}
+
+/// How the finished file was checked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verification {
+    /// Against the object's full CRC32C or SHA-256 checksum.
+    Checksum(Checksum),
+    /// Against the object's ETag, which is the MD5 of the object, or of its parts' MD5s for an
+    /// object uploaded in parts.
+    ETag,
+    /// The object has no checksum, and its ETag isn't an MD5 because of SSE-KMS or SSE-C
+    /// encryption, or verification was turned off.
+    Skipped,
+}
+
+/// A key the object was encrypted with using SSE-C. S3 doesn't keep the key, so every request
+/// for the object, including HEADs, has to send it.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SseCustomerKey {
+    key: String,
+    key_md5: String,
+}
+
+impl SseCustomerKey {
+    /// S3 only accepts AES-256 keys for SSE-C.
+    const ALGORITHM: &'static str = "AES256";
+
+    pub fn new(key: &[u8; 32]) -> Self {
+        SseCustomerKey {
+            key: aws_smithy_types::base64::encode(key),
+            key_md5: aws_smithy_types::base64::encode(Md5::digest(key)),
+        }
+    }
+
+    fn algorithm(&self) -> String {
+        Self::ALGORITHM.to_string()
+    }
+
+    fn key(&self) -> String {
+        self.key.clone()
+    }
+
+    fn key_md5(&self) -> String {
+        self.key_md5.clone()
+    }
+}
+
+/// Keep the key out of logs.
+impl std::fmt::Debug for SseCustomerKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SseCustomerKey")
+            .field("key_md5", &self.key_md5)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DownloadSummary {
+    pub size: u64,
+    pub e_tag: String,
+    /// How many ranges were already on disk from an earlier, interrupted run.
+    pub resumed_ranges: u64,
+    pub verification: Verification,
+}
+
+/// Downloads objects as byte ranges, `concurrency` ranges at a time.
+#[derive(Clone, Debug)]
+pub struct RangedDownloader {
+    client: Client,
+    concurrency: usize,
+    range_size: u64,
+    resume: bool,
+    verify: bool,
+    sse_customer_key: Option<SseCustomerKey>,
+    progress: ProgressTracker,
+}
+
+impl RangedDownloader {
+    pub fn new(client: Client) -> Self {
+        RangedDownloader {
+            client,
+            concurrency: DEFAULT_CONCURRENCY,
+            range_size: DEFAULT_RANGE_SIZE,
+            resume: true,
+            verify: true,
+            sse_customer_key: None,
+            progress: ProgressTracker::silent(),
+        }
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn with_range_size(mut self, range_size: u64) -> Self {
+        self.range_size = range_size.max(1);
+        self
+    }
+
+    /// Whether to finish a partial download of the same object left by an earlier run.
+    /// Defaults to true.
+    pub fn with_resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Whether to check the finished file against the object's checksum or ETag. Defaults to
+    /// true.
+    pub fn with_verify(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// Download an object encrypted with SSE-C, using the key it was encrypted with.
+    pub fn with_sse_customer_key(mut self, key: SseCustomerKey) -> Self {
+        self.sse_customer_key = Some(key);
+        self
+    }
+
+    /// Report the download's progress to `progress`. Use a separate tracker, from
+    /// [ProgressTracker::transfer], for each download.
+    pub fn with_progress(mut self, progress: ProgressTracker) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Download `key` from `bucket` to `destination`. The ranges are written to
+    /// `destination.part`, which is only renamed to `destination` once the whole object is
+    /// there and verified. If the download fails, the partial file and its manifest are left
+    /// for the next run to resume.
+    pub async fn download(
+        &self,
+        bucket: &str,
+        key: &str,
+        destination: &Path,
+    ) -> Result<DownloadSummary, S3ExampleError> {
+        let sse = self.sse_customer_key.as_ref();
+        let head = self
+            .client
+            .head_object()
+            .bucket(bucket)
+            .key(key)
+            .checksum_mode(ChecksumMode::Enabled)
+            .set_sse_customer_algorithm(sse.map(SseCustomerKey::algorithm))
+            .set_sse_customer_key(sse.map(SseCustomerKey::key))
+            .set_sse_customer_key_md5(sse.map(SseCustomerKey::key_md5))
+            .send()
+            .await?;
+        let size = head.content_length().unwrap_or_default() as u64;
+        let e_tag = head
+            .e_tag()
+            .ok_or_else(|| S3ExampleError::new(format!("{bucket}/{key} has no ETag")))?
+            .to_string();
+
+        let partial = with_suffix(destination, ".part");
+        let manifest_path = with_suffix(destination, ".download.json");
+        let manifest = match self.resume(&manifest_path, &partial, bucket, key, &e_tag, size)? {
+            Some(manifest) => manifest,
+            None => {
+                let manifest = DownloadManifest {
+                    bucket: bucket.to_string(),
+                    key: key.to_string(),
+                    e_tag: e_tag.clone(),
+                    size,
+                    range_size: self.range_size,
+                    completed: BTreeSet::new(),
+                };
+                let file = tokio::fs::File::create(&partial)
+                    .await
+                    .map_err(|err| file_error(&partial, err))?;
+                file.set_len(size)
+                    .await
+                    .map_err(|err| file_error(&partial, err))?;
+                manifest.save(&manifest_path)?;
+                manifest
+            }
+        };
+
+        let resumed_ranges = manifest.completed.len() as u64;
+        let remaining: Vec<u64> = (0..manifest.range_count())
+            .filter(|index| !manifest.completed.contains(index))
+            .collect();
+        self.progress.set_total(size);
+        self.progress.resume(manifest.completed_bytes());
+        let manifest = Mutex::new(manifest);
+
+        futures_util::stream::iter(remaining)
+            .map(|index| self.download_range(&partial, &manifest, &manifest_path, index))
+            .buffer_unordered(self.concurrency)
+            .try_collect::<Vec<()>>()
+            .await?;
+
+        let verification = if self.verify {
+            self.verify(&head, bucket, key, &partial).await?
+        } else {
+            Verification::Skipped
+        };
+
+        tokio::fs::rename(&partial, destination)
+            .await
+            .map_err(|err| file_error(destination, err))?;
+        if let Err(err) = std::fs::remove_file(&manifest_path) {
+            warn!("Failed to remove download manifest {manifest_path:?}: {err:?}");
+        }
+
+        self.progress.finish();
+
+        Ok(DownloadSummary {
+            size,
+            e_tag,
+            resumed_ranges,
+            verification,
+        })
+    }
+
+    /// The manifest of an earlier download of the same version of the object, if its partial
+    /// file is still there.
+    fn resume(
+        &self,
+        manifest_path: &Path,
+        partial: &Path,
+        bucket: &str,
+        key: &str,
+        e_tag: &str,
+        size: u64,
+    ) -> Result<Option<DownloadManifest>, S3ExampleError> {
+        if !self.resume || !partial.exists() {
+            return Ok(None);
+        }
+        match DownloadManifest::load(manifest_path)? {
+            Some(manifest)
+                if manifest.bucket == bucket
+                    && manifest.key == key
+                    && manifest.e_tag == e_tag
+                    && manifest.size == size =>
+            {
+                info!(
+                    "Resuming download with {} of {} ranges done",
+                    manifest.completed.len(),
+                    manifest.range_count()
+                );
+                Ok(Some(manifest))
+            }
+            Some(_) => {
+                info!("{bucket}/{key} changed since the last download, starting over");
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Fetch one range, write it at its offset in the partial file, and record it as done. The
+    /// If-Match header makes the GET fail if the object changes part way through the download.
+    async fn download_range(
+        &self,
+        partial: &Path,
+        manifest: &Mutex<DownloadManifest>,
+        manifest_path: &Path,
+        index: u64,
+    ) -> Result<(), S3ExampleError> {
+        let (request, start, end) = {
+            let manifest = manifest.lock().unwrap();
+            let (start, end) = manifest.range(index);
+            let sse = self.sse_customer_key.as_ref();
+            let request = self
+                .client
+                .get_object()
+                .bucket(&manifest.bucket)
+                .key(&manifest.key)
+                .range(format!("bytes={start}-{end}"))
+                .if_match(&manifest.e_tag)
+                .set_sse_customer_algorithm(sse.map(SseCustomerKey::algorithm))
+                .set_sse_customer_key(sse.map(SseCustomerKey::key))
+                .set_sse_customer_key_md5(sse.map(SseCustomerKey::key_md5));
+            (request, start, end)
+        };
+        let mut object = request.send().await.map_err(|err| {
+            S3ExampleError::from(err).add_message(format!("Failed to get bytes {start}-{end}"))
+        })?;
+
+        let mut written = 0;
+        let result = async {
+            let mut file = tokio::fs::OpenOptions::new()
+                .write(true)
+                .open(partial)
+                .await
+                .map_err(|err| file_error(partial, err))?;
+            file.seek(SeekFrom::Start(start))
+                .await
+                .map_err(|err| file_error(partial, err))?;
+
+            while let Some(bytes) = object.body.try_next().await.map_err(|err| {
+                S3ExampleError::new(format!("Failed to read from S3 download stream: {err:?}"))
+            })? {
+                file.write_all(&bytes)
+                    .await
+                    .map_err(|err| file_error(partial, err))?;
+                written += bytes.len() as u64;
+                self.progress.track(bytes.len() as u64);
+            }
+            if written != end - start + 1 {
+                return Err(S3ExampleError::new(format!(
+                    "Expected {} bytes at {start}, got {written}",
+                    end - start + 1
+                )));
+            }
+            // The range is only recorded once it is safely on disk.
+            file.sync_data()
+                .await
+                .map_err(|err| file_error(partial, err))
+        }
+        .await;
+        if let Err(err) = result {
+            // The whole range is fetched again next time, so none of it counts as done.
+            self.progress.untrack(written);
+            return Err(err);
+        }
+
+        let mut manifest = manifest.lock().unwrap();
+        manifest.completed.insert(index);
+        manifest.save(manifest_path)?;
+        debug!("Downloaded bytes {start}-{end}");
+        Ok(())
+    }
+
+    /// Check the file against the object's full checksum if it has one, or else its ETag.
+    async fn verify(
+        &self,
+        head: &HeadObjectOutput,
+        bucket: &str,
+        key: &str,
+        path: &Path,
+    ) -> Result<Verification, S3ExampleError> {
+        // Checksums of objects uploaded in parts end in -N, and are checksums of the parts'
+        // checksums. Only full object checksums are compared.
+        let full = |checksum: Option<&str>| checksum.filter(|c| !c.contains('-')).map(String::from);
+        let checksums = [
+            (Checksum::Crc32c, full(head.checksum_crc32_c())),
+            (Checksum::Sha256, full(head.checksum_sha256())),
+        ];
+        for (kind, expected) in checksums {
+            if let Some(expected) = expected {
+                let actual = file_checksum(path, kind).await?;
+                return if actual == expected {
+                    Ok(Verification::Checksum(kind))
+                } else {
+                    Err(S3ExampleError::new(format!(
+                        "{kind:?} checksum {actual} doesn't match {expected} for {bucket}/{key}"
+                    )))
+                };
+            }
+        }
+
+        // With SSE-KMS or SSE-C, the ETag isn't an MD5 of the object.
+        if matches!(
+            head.server_side_encryption(),
+            Some(ServerSideEncryption::AwsKms) | Some(ServerSideEncryption::AwsKmsDsse)
+        ) || head.sse_customer_algorithm().is_some()
+        {
+            return Ok(Verification::Skipped);
+        }
+
+        let e_tag = head.e_tag().unwrap_or_default().trim_matches('"');
+        let actual = match e_tag.split_once('-') {
+            None => format!("{:x}", file_md5s(path, u64::MAX).await?.remove(0)),
+            Some((_, parts)) => {
+                // Assume every part but the last was the size of the first, as uploaders do.
+                let sse = self.sse_customer_key.as_ref();
+                let first = self
+                    .client
+                    .head_object()
+                    .bucket(bucket)
+                    .key(key)
+                    .part_number(1)
+                    .set_sse_customer_algorithm(sse.map(SseCustomerKey::algorithm))
+                    .set_sse_customer_key(sse.map(SseCustomerKey::key))
+                    .set_sse_customer_key_md5(sse.map(SseCustomerKey::key_md5))
+                    .send()
+                    .await?;
+                let part_size = first.content_length().unwrap_or_default().max(1) as u64;
+                let mut md5 = Md5::new();
+                for digest in file_md5s(path, part_size).await? {
+                    md5.update(digest);
+                }
+                format!("{:x}-{parts}", md5.finalize())
+            }
+        };
+        if actual == e_tag {
+            Ok(Verification::ETag)
+        } else {
+            Err(S3ExampleError::new(format!(
+                "ETag {actual} doesn't match {e_tag} for {bucket}/{key}"
+            )))
+        }
+    }
+}
+
+pub(crate) fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    path.into()
+}
+
+fn file_error(path: &Path, err: std::io::Error) -> S3ExampleError {
+    S3ExampleError::new(format!("Failed to write {path:?}: {err:?}"))
+}
+
+/// Read a file a MiB at a time.
+async fn read_file(path: &Path, mut f: impl FnMut(&[u8])) -> Result<(), S3ExampleError> {
+    let read_error = |err| S3ExampleError::new(format!("Failed to read {path:?}: {err:?}"));
+    let mut file = tokio::fs::File::open(path).await.map_err(read_error)?;
+    let mut buffer = vec![0; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await.map_err(read_error)?;
+        if read == 0 {
+            return Ok(());
+        }
+        f(&buffer[..read]);
+    }
+}
+
+/// The MD5 of each `part_size` long piece of a file.
+pub(crate) async fn file_md5s(
+    path: &Path,
+    part_size: u64,
+) -> Result<Vec<md5::digest::Output<Md5>>, S3ExampleError> {
+    let mut digests = Vec::new();
+    let mut md5 = Md5::new();
+    let mut in_part = 0;
+    read_file(path, |mut data| {
+        while !data.is_empty() {
+            let take = (data.len() as u64).min(part_size - in_part) as usize;
+            md5.update(&data[..take]);
+            in_part += take as u64;
+            data = &data[take..];
+            if in_part == part_size {
+                digests.push(md5.finalize_reset());
+                in_part = 0;
+            }
+        }
+    })
+    .await?;
+    if in_part > 0 || digests.is_empty() {
+        digests.push(md5.finalize());
+    }
+    Ok(digests)
+}
+
+async fn file_checksum(path: &Path, checksum: Checksum) -> Result<String, S3ExampleError> {
+    match checksum {
+        Checksum::Crc32c => {
+            let mut crc = 0;
+            read_file(path, |data| crc = crc32c::crc32c_append(crc, data)).await?;
+            Ok(aws_smithy_types::base64::encode(crc.to_be_bytes()))
+        }
+        Checksum::Sha256 => {
+            let mut sha = sha2::Sha256::new();
+            read_file(path, |data| sha.update(data)).await?;
+            Ok(aws_smithy_types::base64::encode(sha.finalize()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env::temp_dir;
+
+    use aws_smithy_runtime::client::http::test_util::StaticReplayClient;
+    use sdk_examples_test_utils::{client_config, test_event};
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn test_ranges() {
+        let manifest = DownloadManifest {
+            bucket: "bucket".into(),
+            key: "key".into(),
+            e_tag: "\"etag\"".into(),
+            size: 25,
+            range_size: 10,
+            completed: BTreeSet::from([0, 2]),
+        };
+        assert_eq!(manifest.range_count(), 3);
+        assert_eq!(manifest.range(0), (0, 9));
+        assert_eq!(manifest.range(2), (20, 24));
+        assert_eq!(manifest.completed_bytes(), 15);
+    }
+
+    #[tokio::test]
+    async fn test_file_hashes() {
+        let path = temp_dir().join(format!("{}.txt", Uuid::new_v4()));
+        std::fs::write(&path, "hello world").unwrap();
+
+        assert_eq!(
+            file_checksum(&path, Checksum::Crc32c).await.unwrap(),
+            Checksum::Crc32c.compute(b"hello world")
+        );
+        assert_eq!(
+            file_checksum(&path, Checksum::Sha256).await.unwrap(),
+            Checksum::Sha256.compute(b"hello world")
+        );
+        let md5s = file_md5s(&path, 6).await.unwrap();
+        assert_eq!(md5s.len(), 2);
+        assert_eq!(md5s[1], Md5::digest(b"world"));
+    }
+
+    #[tokio::test]
+    async fn test_download_resumes_and_verifies() {
+        let client = Client::from_conf(
+            client_config!(aws_sdk_s3)
+                .http_client(StaticReplayClient::new(vec![
+                    // client.head_object()
+                    test_event!(
+                        r#""#,
+                        (
+                            200,
+                            vec![
+                                ("Content-Length", "11"),
+                                // The MD5 of "hello world".
+                                ("ETag", "\"5eb63bbbe01eeed093cb22bb8f5acdc3\""),
+                            ],
+                            r#""#
+                        )
+                    ),
+                    // client.get_object() for the last range; the first is already on disk.
+                    test_event!(
+                        r#""#,
+                        (206, vec![("Content-Range", "bytes 6-10/11")], r#"world"#)
+                    ),
+                ]))
+                .build(),
+        );
+
+        let destination = temp_dir().join(format!("{}.txt", Uuid::new_v4()));
+        std::fs::write(with_suffix(&destination, ".part"), "hello XXXXX").unwrap();
+        DownloadManifest {
+            bucket: "bucket".into(),
+            key: "key".into(),
+            e_tag: "\"5eb63bbbe01eeed093cb22bb8f5acdc3\"".into(),
+            size: 11,
+            range_size: 6,
+            completed: BTreeSet::from([0]),
+        }
+        .save(&with_suffix(&destination, ".download.json"))
+        .unwrap();
+
+        let progress = ProgressTracker::silent();
+        let summary = RangedDownloader::new(client)
+            .with_progress(progress.clone())
+            .download("bucket", "key", &destination)
+            .await;
+
+        assert!(summary.is_ok(), "{summary:?}");
+        let summary = summary.unwrap();
+        assert_eq!(summary.resumed_ranges, 1);
+        assert_eq!(summary.verification, Verification::ETag);
+        // The first range counts as done, but only the second was downloaded in this run.
+        assert_eq!(progress.progress().bytes, 11);
+        assert_eq!(progress.progress().total, Some(11));
+        assert_eq!(
+            std::fs::read_to_string(&destination).unwrap(),
+            "hello world"
+        );
+        assert!(!with_suffix(&destination, ".download.json").exists());
+    }
+
+    #[tokio::test]
+    async fn test_download_skips_etag_with_sse_c() {
+        let http_client = StaticReplayClient::new(vec![
+            test_event!(
+                r#""#,
+                (
+                    200,
+                    vec![
+                        ("Content-Length", "11"),
+                        ("ETag", "\"0123456789abcdef0123456789abcdef\""),
+                        ("x-amz-server-side-encryption-customer-algorithm", "AES256"),
+                    ],
+                    r#""#
+                )
+            ),
+            test_event!(
+                r#""#,
+                (
+                    206,
+                    vec![("Content-Range", "bytes 0-10/11")],
+                    r#"hello world"#
+                )
+            ),
+        ]);
+        let client = Client::from_conf(
+            client_config!(aws_sdk_s3)
+                .http_client(http_client.clone())
+                .build(),
+        );
+        let key = SseCustomerKey::new(&[7; 32]);
+
+        let destination = temp_dir().join(format!("{}.txt", Uuid::new_v4()));
+        let summary = RangedDownloader::new(client)
+            .with_sse_customer_key(key.clone())
+            .download("bucket", "key", &destination)
+            .await;
+
+        assert!(summary.is_ok(), "{summary:?}");
+        assert_eq!(summary.unwrap().verification, Verification::Skipped);
+        // The HEAD and the ranged GET both carry the key.
+        let requests: Vec<_> = http_client.actual_requests().collect();
+        assert_eq!(requests.len(), 2);
+        for request in requests {
+            let header = |name| request.headers().get(name);
+            assert_eq!(
+                header("x-amz-server-side-encryption-customer-algorithm"),
+                Some("AES256")
+            );
+            assert_eq!(
+                header("x-amz-server-side-encryption-customer-key"),
+                Some(key.key.as_str())
+            );
+            assert_eq!(
+                header("x-amz-server-side-encryption-customer-key-MD5"),
+                Some(key.key_md5.as_str())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_range_is_not_progress() {
+        let client = Client::from_conf(
+            client_config!(aws_sdk_s3)
+                .http_client(StaticReplayClient::new(vec![
+                    test_event!(
+                        r#""#,
+                        (
+                            200,
+                            vec![
+                                ("Content-Length", "11"),
+                                ("ETag", "\"5eb63bbbe01eeed093cb22bb8f5acdc3\""),
+                            ],
+                            r#""#
+                        )
+                    ),
+                    // The stream ends early.
+                    test_event!(
+                        r#""#,
+                        (206, vec![("Content-Range", "bytes 0-10/11")], r#"hello"#)
+                    ),
+                ]))
+                .build(),
+        );
+
+        let destination = temp_dir().join(format!("{}.txt", Uuid::new_v4()));
+        let progress = ProgressTracker::silent();
+        let summary = RangedDownloader::new(client)
+            .with_progress(progress.clone())
+            .download("bucket", "key", &destination)
+            .await;
+
+        assert!(summary.is_err());
+        assert_eq!(progress.progress().bytes, 0);
+        assert!(with_suffix(&destination, ".download.json").exists());
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Transfers of large objects in parts, several parts at a time.
+
+use aws_sdk_s3::types::{ChecksumAlgorithm, CompletedPart, Part};
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+
+use crate::error::S3ExampleError;
+
+pub mod download;
+pub mod upload;
+
+pub use download::{DownloadSummary, RangedDownloader, SseCustomerKey, Verification};
+pub use upload::{MultipartUploader, UploadManifest};
+
+const MIB: u64 = 1024 * 1024;
+
+/// Every part but the last must be at least this large.
+pub const MIN_PART_SIZE: u64 = 5 * MIB;
+/// No part can be larger than this.
+pub const MAX_PART_SIZE: u64 = 5 * 1024 * MIB;
+/// A multipart upload can have at most this many parts.
+pub const MAX_PARTS: u64 = 10_000;
+/// The largest object S3 stores.
+pub const MAX_OBJECT_SIZE: u64 = 5 * 1024 * 1024 * MIB;
+/// How many parts to transfer at once, unless told otherwise.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// The part size to use for an object of `object_size` bytes. This is `preferred`, unless that
+/// would take more than [MAX_PARTS] parts, in which case it grows to the smallest whole MiB that
+/// fits.
+pub fn part_size(object_size: u64, preferred: u64) -> Result<u64, S3ExampleError> {
+    if object_size > MAX_OBJECT_SIZE {
+        return Err(S3ExampleError::new(format!(
+            "{object_size} bytes is larger than the largest S3 object"
+        )));
+    }
+    let smallest = object_size.div_ceil(MAX_PARTS);
+    let size = preferred.max(smallest).max(MIN_PART_SIZE);
+    Ok((size.div_ceil(MIB) * MIB).min(MAX_PART_SIZE))
+}
+
+/// The checksum sent with each part, which S3 checks before accepting the part.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Checksum {
+    #[default]
+    Crc32c,
+    Sha256,
+}
+
+impl Checksum {
+    pub fn algorithm(self) -> ChecksumAlgorithm {
+        match self {
+            Checksum::Crc32c => ChecksumAlgorithm::Crc32C,
+            Checksum::Sha256 => ChecksumAlgorithm::Sha256,
+        }
+    }
+
+    /// The base64 encoded checksum of `data`, as S3 expects it.
+    pub fn compute(self, data: &[u8]) -> String {
+        match self {
+            Checksum::Crc32c => {
+                aws_smithy_types::base64::encode(crc32c::crc32c(data).to_be_bytes())
+            }
+            Checksum::Sha256 => aws_smithy_types::base64::encode(sha2::Sha256::digest(data)),
+        }
+    }
+
+    /// The checksum of this kind that S3 has for an uploaded part.
+    pub fn of_part(self, part: &Part) -> Option<&str> {
+        match self {
+            Checksum::Crc32c => part.checksum_crc32_c(),
+            Checksum::Sha256 => part.checksum_sha256(),
+        }
+    }
+
+    pub fn completed_part(
+        self,
+        part_number: i32,
+        e_tag: Option<&str>,
+        checksum: String,
+    ) -> CompletedPart {
+        let part = CompletedPart::builder()
+            .part_number(part_number)
+            .set_e_tag(e_tag.map(String::from));
+        match self {
+            Checksum::Crc32c => part.checksum_crc32_c(checksum),
+            Checksum::Sha256 => part.checksum_sha256(checksum),
+        }
+        .build()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_part_size() {
+        // Small files use the smallest part size.
+        assert_eq!(part_size(1024, MIN_PART_SIZE).unwrap(), MIN_PART_SIZE);
+        assert_eq!(part_size(1024, 8 * MIB).unwrap(), 8 * MIB);
+
+        // 100 GiB in 5 MiB parts would be 20,480 parts, so the parts grow.
+        let size = part_size(100 * 1024 * MIB, MIN_PART_SIZE).unwrap();
+        assert_eq!(size, 11 * MIB);
+        assert!((100 * 1024 * MIB).div_ceil(size) <= MAX_PARTS);
+
+        assert!(part_size(MAX_OBJECT_SIZE + 1, MIN_PART_SIZE).is_err());
+    }
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(Checksum::Crc32c.compute(b"hello world"), "yZRlqg==");
+        assert_eq!(
+            Checksum::Sha256.compute(b"hello world"),
+            "uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek="
+        );
+    }
+}
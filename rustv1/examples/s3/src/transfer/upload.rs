@@ -0,0 +1,682 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Upload a file as a multipart upload, several parts at a time. A manifest saved next to the
+//! upload lets a later run pick up where a crashed one stopped.
+
+use std::{
+    collections::HashMap,
+    io::SeekFrom,
+    path::{Path, PathBuf},
+};
+
+use aws_sdk_s3::{
+    operation::complete_multipart_upload::CompleteMultipartUploadOutput,
+    primitives::ByteStream,
+    types::{CompletedMultipartUpload, CompletedPart, Part},
+    Client,
+};
+use futures_util::{StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tracing::{debug, info, warn};
+
+use crate::{error::S3ExampleError, progress::ProgressTracker};
+
+use super::{part_size, Checksum, DEFAULT_CONCURRENCY, MIN_PART_SIZE};
+
+/// What a resumed upload needs to know: which multipart upload to continue, and how the file
+/// was split into parts.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadManifest {
+    pub bucket: String,
+    pub key: String,
+    pub upload_id: String,
+    pub file_size: u64,
+    pub part_size: u64,
+    pub checksum: Checksum,
+}
+
+impl UploadManifest {
+    pub fn part_count(&self) -> u64 {
+        self.file_size.div_ceil(self.part_size)
+    }
+
+    /// The offset and length in the file of a part. Part numbers start at 1.
+    pub fn part_range(&self, part_number: i32) -> (u64, u64) {
+        let offset = (part_number as u64 - 1) * self.part_size;
+        (offset, self.part_size.min(self.file_size - offset))
+    }
+
+    /// The saved manifest, or None if there isn't one.
+    pub fn load(path: &Path) -> Result<Option<Self>, S3ExampleError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(S3ExampleError::new(format!(
+                    "Failed to read upload manifest {path:?}: {err:?}"
+                )))
+            }
+        };
+        serde_json::from_str(&contents).map(Some).map_err(|err| {
+            S3ExampleError::new(format!("Failed to parse upload manifest {path:?}: {err:?}"))
+        })
+    }
+
+    /// Write to a temporary file first, so a crash never leaves half a manifest.
+    pub fn save(&self, path: &Path) -> Result<(), S3ExampleError> {
+        let json = serde_json::to_string_pretty(self).map_err(|err| {
+            S3ExampleError::new(format!("Failed to serialize upload manifest: {err:?}"))
+        })?;
+        let temp = path.with_extension("tmp");
+        std::fs::write(&temp, json)
+            .and_then(|_| std::fs::rename(&temp, path))
+            .map_err(|err| {
+                S3ExampleError::new(format!("Failed to save upload manifest {path:?}: {err:?}"))
+            })
+    }
+
+    fn remove(path: &Path) -> Result<(), S3ExampleError> {
+        match std::fs::remove_file(path) {
+            Ok(_) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(S3ExampleError::new(format!(
+                "Failed to remove upload manifest {path:?}: {err:?}"
+            ))),
+        }
+    }
+}
+
+/// Uploads files in parts, `concurrency` parts at a time. Each part is read into memory while
+/// it uploads, so at most `concurrency * part_size` bytes are buffered.
+#[derive(Clone, Debug)]
+pub struct MultipartUploader {
+    client: Client,
+    concurrency: usize,
+    part_size: u64,
+    checksum: Checksum,
+    manifest: Option<PathBuf>,
+    abort_on_failure: bool,
+    progress: ProgressTracker,
+}
+
+impl MultipartUploader {
+    pub fn new(client: Client) -> Self {
+        MultipartUploader {
+            client,
+            concurrency: DEFAULT_CONCURRENCY,
+            part_size: MIN_PART_SIZE,
+            checksum: Checksum::default(),
+            manifest: None,
+            abort_on_failure: true,
+            progress: ProgressTracker::silent(),
+        }
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// The part size to use when the file fits in [super::MAX_PARTS] parts of this size.
+    pub fn with_part_size(mut self, part_size: u64) -> Self {
+        self.part_size = part_size;
+        self
+    }
+
+    pub fn with_checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Save the upload's progress to `manifest`, and resume the upload saved there if it is for
+    /// the same file, bucket and key.
+    pub fn with_manifest(mut self, manifest: impl Into<PathBuf>) -> Self {
+        self.manifest = Some(manifest.into());
+        self
+    }
+
+    /// Whether to abort the multipart upload when a part fails, so S3 doesn't keep the parts
+    /// that did upload. Defaults to true. Without aborting, the upload can be resumed from its
+    /// manifest.
+    pub fn with_abort_on_failure(mut self, abort_on_failure: bool) -> Self {
+        self.abort_on_failure = abort_on_failure;
+        self
+    }
+
+    /// Report the upload's progress to `progress`, a part at a time. Use a separate tracker,
+    /// from [ProgressTracker::transfer], for each upload.
+    pub fn with_progress(mut self, progress: ProgressTracker) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Upload `source` to `bucket` as `key`.
+    pub async fn upload(
+        &self,
+        source: &Path,
+        bucket: &str,
+        key: &str,
+    ) -> Result<CompleteMultipartUploadOutput, S3ExampleError> {
+        let file_size = tokio::fs::metadata(source)
+            .await
+            .map_err(|err| S3ExampleError::new(format!("Failed to read {source:?}: {err:?}")))?
+            .len();
+        if file_size == 0 {
+            return Err(S3ExampleError::new(format!(
+                "{source:?} is empty, and can't be uploaded in parts"
+            )));
+        }
+
+        let (manifest, uploaded) = match self.resume(bucket, key, file_size).await? {
+            Some(resumed) => resumed,
+            None => (self.start(bucket, key, file_size).await?, HashMap::new()),
+        };
+        self.progress.set_total(file_size);
+
+        let result = match self.upload_parts(source, &manifest, &uploaded).await {
+            Ok(parts) => self.complete(&manifest, parts).await,
+            Err(err) => Err(err),
+        };
+
+        match result {
+            Ok(output) => {
+                self.remove_manifest()?;
+                self.progress.finish();
+                Ok(output)
+            }
+            Err(err) => {
+                if self.abort_on_failure {
+                    self.abort(&manifest).await;
+                    self.remove_manifest()?;
+                } else if let Some(path) = &self.manifest {
+                    warn!("Upload {} can be resumed from {path:?}", manifest.upload_id);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// The saved upload and the parts S3 already has, if the manifest is for this upload.
+    async fn resume(
+        &self,
+        bucket: &str,
+        key: &str,
+        file_size: u64,
+    ) -> Result<Option<(UploadManifest, HashMap<i32, Part>)>, S3ExampleError> {
+        let Some(path) = &self.manifest else {
+            return Ok(None);
+        };
+        let Some(manifest) = UploadManifest::load(path)? else {
+            return Ok(None);
+        };
+        if manifest.bucket != bucket || manifest.key != key || manifest.file_size != file_size {
+            warn!(
+                "{path:?} is for another upload, aborting upload {}",
+                manifest.upload_id
+            );
+            self.abort(&manifest).await;
+            return Ok(None);
+        }
+
+        match self.list_parts(&manifest).await {
+            Ok(parts) => {
+                info!(
+                    "Resuming upload {} with {} of {} parts uploaded",
+                    manifest.upload_id,
+                    parts.len(),
+                    manifest.part_count()
+                );
+                Ok(Some((manifest, parts)))
+            }
+            Err(err) => {
+                warn!(
+                    "Can't resume upload {}, starting over: {err}",
+                    manifest.upload_id
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    async fn list_parts(
+        &self,
+        manifest: &UploadManifest,
+    ) -> Result<HashMap<i32, Part>, S3ExampleError> {
+        let mut parts = HashMap::new();
+        let mut marker = None;
+        loop {
+            let output = self
+                .client
+                .list_parts()
+                .bucket(&manifest.bucket)
+                .key(&manifest.key)
+                .upload_id(&manifest.upload_id)
+                .set_part_number_marker(marker)
+                .send()
+                .await?;
+            for part in output.parts() {
+                if let Some(part_number) = part.part_number() {
+                    parts.insert(part_number, part.clone());
+                }
+            }
+            marker = output.next_part_number_marker().map(String::from);
+            if !output.is_truncated().unwrap_or_default() || marker.is_none() {
+                break;
+            }
+        }
+        Ok(parts)
+    }
+
+    async fn start(
+        &self,
+        bucket: &str,
+        key: &str,
+        file_size: u64,
+    ) -> Result<UploadManifest, S3ExampleError> {
+        let output = self
+            .client
+            .create_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .checksum_algorithm(self.checksum.algorithm())
+            .send()
+            .await?;
+        let manifest = UploadManifest {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            upload_id: output
+                .upload_id()
+                .ok_or_else(|| {
+                    S3ExampleError::new("Missing upload_id after CreateMultipartUpload")
+                })?
+                .to_string(),
+            file_size,
+            part_size: part_size(file_size, self.part_size)?,
+            checksum: self.checksum,
+        };
+        if let Some(path) = &self.manifest {
+            manifest.save(path)?;
+        }
+        Ok(manifest)
+    }
+
+    /// Upload every part S3 doesn't already have. On the first part that fails, the parts still
+    /// uploading are dropped.
+    async fn upload_parts(
+        &self,
+        source: &Path,
+        manifest: &UploadManifest,
+        uploaded: &HashMap<i32, Part>,
+    ) -> Result<Vec<CompletedPart>, S3ExampleError> {
+        let mut parts: Vec<CompletedPart> =
+            futures_util::stream::iter(1..=manifest.part_count() as i32)
+                .map(|part_number| {
+                    self.upload_part(source, manifest, part_number, uploaded.get(&part_number))
+                })
+                .buffer_unordered(self.concurrency)
+                .try_collect()
+                .await?;
+        parts.sort_by_key(|part| part.part_number());
+        Ok(parts)
+    }
+
+    async fn upload_part(
+        &self,
+        source: &Path,
+        manifest: &UploadManifest,
+        part_number: i32,
+        uploaded: Option<&Part>,
+    ) -> Result<CompletedPart, S3ExampleError> {
+        let (offset, length) = manifest.part_range(part_number);
+        let data = read_part(source, offset, length).await?;
+        let checksum = manifest.checksum.compute(&data);
+
+        // A part from before a crash is only kept if it matches the file as it is now.
+        if let Some(part) = uploaded {
+            if manifest.checksum.of_part(part) == Some(checksum.as_str()) {
+                debug!("Part {part_number} is already uploaded");
+                self.progress.resume(length);
+                return Ok(manifest
+                    .checksum
+                    .completed_part(part_number, part.e_tag(), checksum));
+            }
+        }
+
+        let request = self
+            .client
+            .upload_part()
+            .bucket(&manifest.bucket)
+            .key(&manifest.key)
+            .upload_id(&manifest.upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(data));
+        let request = match manifest.checksum {
+            Checksum::Crc32c => request.checksum_crc32_c(&checksum),
+            Checksum::Sha256 => request.checksum_sha256(&checksum),
+        };
+        let output = request.send().await.map_err(|err| {
+            S3ExampleError::from(err).add_message(format!("Failed to upload part {part_number}"))
+        })?;
+        debug!("Uploaded part {part_number} of {}", manifest.part_count());
+        self.progress.track(length);
+
+        Ok(manifest
+            .checksum
+            .completed_part(part_number, output.e_tag(), checksum))
+    }
+
+    async fn complete(
+        &self,
+        manifest: &UploadManifest,
+        parts: Vec<CompletedPart>,
+    ) -> Result<CompleteMultipartUploadOutput, S3ExampleError> {
+        self.client
+            .complete_multipart_upload()
+            .bucket(&manifest.bucket)
+            .key(&manifest.key)
+            .upload_id(&manifest.upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(S3ExampleError::from)
+    }
+
+    /// Abort an upload. A failure here is only logged, as there is already an error to return.
+    async fn abort(&self, manifest: &UploadManifest) {
+        if let Err(err) = self
+            .client
+            .abort_multipart_upload()
+            .bucket(&manifest.bucket)
+            .key(&manifest.key)
+            .upload_id(&manifest.upload_id)
+            .send()
+            .await
+        {
+            warn!(
+                "Failed to abort upload {}: {}",
+                manifest.upload_id,
+                S3ExampleError::from(err)
+            );
+        }
+    }
+
+    fn remove_manifest(&self) -> Result<(), S3ExampleError> {
+        match &self.manifest {
+            Some(path) => UploadManifest::remove(path),
+            None => Ok(()),
+        }
+    }
+}
+
+async fn read_part(source: &Path, offset: u64, length: u64) -> Result<Vec<u8>, S3ExampleError> {
+    let read = async {
+        let mut file = tokio::fs::File::open(source).await?;
+        file.seek(SeekFrom::Start(offset)).await?;
+        let mut data = vec![0; length as usize];
+        file.read_exact(&mut data).await?;
+        Ok::<_, std::io::Error>(data)
+    };
+    read.await.map_err(|err| {
+        S3ExampleError::new(format!(
+            "Failed to read {length} bytes at {offset} from {source:?}: {err:?}"
+        ))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::env::temp_dir;
+
+    use aws_smithy_runtime::client::http::test_util::StaticReplayClient;
+    use sdk_examples_test_utils::{client_config, test_event};
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn test_part_ranges() {
+        let manifest = UploadManifest {
+            bucket: "bucket".into(),
+            key: "key".into(),
+            upload_id: "upload".into(),
+            file_size: 12 * 1024 * 1024,
+            part_size: MIN_PART_SIZE,
+            checksum: Checksum::Crc32c,
+        };
+        assert_eq!(manifest.part_count(), 3);
+        assert_eq!(manifest.part_range(1), (0, MIN_PART_SIZE));
+        assert_eq!(manifest.part_range(3), (2 * MIN_PART_SIZE, 2 * 1024 * 1024));
+
+        let path = temp_dir().join(format!("{}.json", Uuid::new_v4()));
+        manifest.save(&path).unwrap();
+        assert_eq!(UploadManifest::load(&path).unwrap(), Some(manifest));
+        UploadManifest::remove(&path).unwrap();
+        assert_eq!(UploadManifest::load(&path).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_upload() {
+        let client = Client::from_conf(
+            client_config!(aws_sdk_s3)
+                .http_client(StaticReplayClient::new(vec![
+                    // client.create_multipart_upload()
+                    test_event!(
+                        r#""#,
+                        (
+                            200,
+                            r#"<?xml version="1.0" encoding="UTF-8"?><InitiateMultipartUploadResult>
+                            <Bucket>bucket</Bucket><Key>key</Key><UploadId>upload</UploadId>
+                            </InitiateMultipartUploadResult>"#
+                        )
+                    ),
+                    // client.upload_part()
+                    test_event!(r#""#, (200, vec![("ETag", "\"part-1\"")], r#""#)),
+                    // client.complete_multipart_upload()
+                    test_event!(
+                        r#""#,
+                        (
+                            200,
+                            r#"<?xml version="1.0" encoding="UTF-8"?><CompleteMultipartUploadResult>
+                            <Bucket>bucket</Bucket><Key>key</Key><ETag>"object-1"</ETag>
+                            </CompleteMultipartUploadResult>"#
+                        )
+                    ),
+                ]))
+                .build(),
+        );
+
+        let source = temp_dir().join(format!("{}.txt", Uuid::new_v4()));
+        std::fs::write(&source, "test file").unwrap();
+        let manifest = source.with_extension("upload.json");
+
+        let resp = MultipartUploader::new(client)
+            .with_manifest(&manifest)
+            .upload(&source, "bucket", "key")
+            .await;
+
+        assert!(resp.is_ok(), "{resp:?}");
+        assert_eq!(resp.unwrap().e_tag(), Some("\"object-1\""));
+        // The manifest is only kept for unfinished uploads.
+        assert!(!manifest.exists());
+    }
+
+    /// A file split into two parts, "hello " and "world", with a saved manifest for upload
+    /// `upload`, as if an earlier run crashed part way through.
+    fn interrupted_upload() -> (PathBuf, PathBuf) {
+        let source = temp_dir().join(format!("{}.txt", Uuid::new_v4()));
+        std::fs::write(&source, "hello world").unwrap();
+        let manifest = source.with_extension("upload.json");
+        UploadManifest {
+            bucket: "bucket".into(),
+            key: "key".into(),
+            upload_id: "upload".into(),
+            file_size: 11,
+            part_size: 6,
+            checksum: Checksum::Crc32c,
+        }
+        .save(&manifest)
+        .unwrap();
+        (source, manifest)
+    }
+
+    /// A ListParts response with a part for each CRC32C checksum.
+    fn list_parts(checksums: &[&str]) -> String {
+        let parts: String = checksums
+            .iter()
+            .enumerate()
+            .map(|(index, checksum)| {
+                format!(
+                    r#"<Part><PartNumber>{}</PartNumber><ETag>"part-{}"</ETag><Size>6</Size><ChecksumCRC32C>{checksum}</ChecksumCRC32C></Part>"#,
+                    index + 1,
+                    index + 1
+                )
+            })
+            .collect();
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?><ListPartsResult>
+            <Bucket>bucket</Bucket><Key>key</Key><UploadId>upload</UploadId>
+            <IsTruncated>false</IsTruncated>{parts}</ListPartsResult>"#
+        )
+    }
+
+    const COMPLETE: &str = r#"<?xml version="1.0" encoding="UTF-8"?><CompleteMultipartUploadResult>
+        <Bucket>bucket</Bucket><Key>key</Key><ETag>"object-1"</ETag>
+        </CompleteMultipartUploadResult>"#;
+
+    #[tokio::test]
+    async fn test_resume_skips_uploaded_parts() {
+        let (source, manifest) = interrupted_upload();
+        let http_client = StaticReplayClient::new(vec![
+            // client.list_parts()
+            test_event!(
+                r#""#,
+                (
+                    200,
+                    list_parts(&[
+                        Checksum::Crc32c.compute(b"hello ").as_str(),
+                        Checksum::Crc32c.compute(b"world").as_str(),
+                    ])
+                )
+            ),
+            // client.complete_multipart_upload()
+            test_event!(r#""#, (200, COMPLETE)),
+        ]);
+        let client = Client::from_conf(
+            client_config!(aws_sdk_s3)
+                .http_client(http_client.clone())
+                .build(),
+        );
+        let progress = ProgressTracker::silent();
+
+        let resp = MultipartUploader::new(client)
+            .with_manifest(&manifest)
+            .with_progress(progress.clone())
+            .upload(&source, "bucket", "key")
+            .await;
+
+        assert!(resp.is_ok(), "{resp:?}");
+        assert_eq!(http_client.actual_requests().count(), 2);
+        assert_eq!(progress.progress().bytes, 11);
+        assert!(!manifest.exists());
+    }
+
+    #[tokio::test]
+    async fn test_resume_uploads_changed_parts_again() {
+        let (source, manifest) = interrupted_upload();
+        let http_client = StaticReplayClient::new(vec![
+            // client.list_parts(), where part 2 was uploaded before the file changed.
+            test_event!(
+                r#""#,
+                (
+                    200,
+                    list_parts(&[
+                        Checksum::Crc32c.compute(b"hello ").as_str(),
+                        Checksum::Crc32c.compute(b"earth").as_str(),
+                    ])
+                )
+            ),
+            // client.upload_part() for part 2
+            test_event!(r#""#, (200, vec![("ETag", "\"part-2b\"")], r#""#)),
+            // client.complete_multipart_upload()
+            test_event!(r#""#, (200, COMPLETE)),
+        ]);
+        let client = Client::from_conf(
+            client_config!(aws_sdk_s3)
+                .http_client(http_client.clone())
+                .build(),
+        );
+
+        let resp = MultipartUploader::new(client)
+            .with_manifest(&manifest)
+            .upload(&source, "bucket", "key")
+            .await;
+
+        assert!(resp.is_ok(), "{resp:?}");
+        let requests: Vec<_> = http_client.actual_requests().collect();
+        assert_eq!(requests.len(), 3);
+        assert!(
+            requests[1].uri().contains("partNumber=2"),
+            "{}",
+            requests[1].uri()
+        );
+        assert_eq!(
+            requests[1].headers().get("x-amz-checksum-crc32c"),
+            Some(Checksum::Crc32c.compute(b"world").as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_upload_is_aborted() {
+        let http_client = StaticReplayClient::new(vec![
+            // client.create_multipart_upload()
+            test_event!(
+                r#""#,
+                (
+                    200,
+                    r#"<?xml version="1.0" encoding="UTF-8"?><InitiateMultipartUploadResult>
+                    <Bucket>bucket</Bucket><Key>key</Key><UploadId>upload</UploadId>
+                    </InitiateMultipartUploadResult>"#
+                )
+            ),
+            // client.upload_part()
+            test_event!(
+                r#""#,
+                (
+                    400,
+                    r#"<?xml version="1.0" encoding="UTF-8"?><Error><Code>BadDigest</Code>
+                    <Message>The checksum didn't match</Message></Error>"#
+                )
+            ),
+            // client.abort_multipart_upload()
+            test_event!(r#""#, (204, r#""#)),
+        ]);
+        let client = Client::from_conf(
+            client_config!(aws_sdk_s3)
+                .http_client(http_client.clone())
+                .build(),
+        );
+
+        let source = temp_dir().join(format!("{}.txt", Uuid::new_v4()));
+        std::fs::write(&source, "test file").unwrap();
+        let manifest = source.with_extension("upload.json");
+
+        let resp = MultipartUploader::new(client)
+            .with_manifest(&manifest)
+            .upload(&source, "bucket", "key")
+            .await;
+
+        assert!(resp.is_err());
+        let requests: Vec<_> = http_client.actual_requests().collect();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[2].method(), "DELETE");
+        assert!(requests[2].uri().contains("uploadId=upload"));
+        // An aborted upload can't be resumed, so its manifest is gone too.
+        assert!(!manifest.exists());
+    }
+}
@@ -0,0 +1,366 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Turn bucket versioning on and off, restore earlier versions of objects, and empty a
+//! versioned bucket so it can be deleted.
+//!
+//! Deleting an object in a versioned bucket only adds a delete marker, and the earlier versions
+//! stay. S3 won't delete a bucket until every version and delete marker is gone, which is why
+//! [crate::delete_bucket] fails on a bucket that has ever had versioning turned on.
+
+use aws_sdk_s3::{
+    operation::copy_object::CopyObjectOutput,
+    primitives::DateTime,
+    types::{BucketVersioningStatus, Delete, ObjectIdentifier, VersioningConfiguration},
+    Client,
+};
+use tracing::debug;
+
+use crate::{delete_bucket, error::S3ExampleError};
+
+/// DeleteObjects takes at most this many keys.
+const MAX_DELETE_KEYS: usize = 1000;
+
+// snippet-start:[s3.rust.put_bucket_versioning]
+/// Keep every version of every object written to `bucket` from now on.
+pub async fn enable_versioning(client: &Client, bucket: &str) -> Result<(), S3ExampleError> {
+    set_versioning(client, bucket, BucketVersioningStatus::Enabled).await
+}
+
+/// Stop keeping new versions in `bucket`. Versions that already exist are kept.
+pub async fn suspend_versioning(client: &Client, bucket: &str) -> Result<(), S3ExampleError> {
+    set_versioning(client, bucket, BucketVersioningStatus::Suspended).await
+}
+
+async fn set_versioning(
+    client: &Client,
+    bucket: &str,
+    status: BucketVersioningStatus,
+) -> Result<(), S3ExampleError> {
+    client
+        .put_bucket_versioning()
+        .bucket(bucket)
+        .versioning_configuration(VersioningConfiguration::builder().status(status).build())
+        .send()
+        .await?;
+    Ok(())
+}
+// snippet-end:[s3.rust.put_bucket_versioning]
+
+/// Whether versioning is enabled or suspended, or None if it has never been turned on.
+pub async fn versioning_status(
+    client: &Client,
+    bucket: &str,
+) -> Result<Option<BucketVersioningStatus>, S3ExampleError> {
+    let output = client.get_bucket_versioning().bucket(bucket).send().await?;
+    Ok(output.status().cloned())
+}
+
+/// One version of an object, or a delete marker.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectVersion {
+    pub key: String,
+    /// `null` for objects written while versioning was off.
+    pub version_id: String,
+    pub is_latest: bool,
+    pub is_delete_marker: bool,
+    pub last_modified: Option<DateTime>,
+    pub size: i64,
+}
+
+impl ObjectVersion {
+    fn identifier(&self) -> Result<ObjectIdentifier, S3ExampleError> {
+        ObjectIdentifier::builder()
+            .key(&self.key)
+            .version_id(&self.version_id)
+            .build()
+            .map_err(|err| {
+                S3ExampleError::new(format!("Failed to build key for delete_object: {err:?}"))
+            })
+    }
+}
+
+// snippet-start:[s3.rust.list_all_object_versions]
+/// Every version and delete marker under `prefix`, in key order, newest first for each key.
+pub async fn list_versions(
+    client: &Client,
+    bucket: &str,
+    prefix: Option<&str>,
+) -> Result<Vec<ObjectVersion>, S3ExampleError> {
+    let mut versions = Vec::new();
+    let mut key_marker = None;
+    let mut version_id_marker = None;
+    loop {
+        let output = client
+            .list_object_versions()
+            .bucket(bucket)
+            .set_prefix(prefix.map(String::from))
+            .set_key_marker(key_marker)
+            .set_version_id_marker(version_id_marker)
+            .send()
+            .await?;
+
+        let mut page: Vec<ObjectVersion> = output
+            .versions()
+            .iter()
+            .map(|version| ObjectVersion {
+                key: version.key().unwrap_or_default().to_string(),
+                version_id: version.version_id().unwrap_or("null").to_string(),
+                is_latest: version.is_latest().unwrap_or_default(),
+                is_delete_marker: false,
+                last_modified: version.last_modified().cloned(),
+                size: version.size().unwrap_or_default(),
+            })
+            .chain(output.delete_markers().iter().map(|marker| ObjectVersion {
+                key: marker.key().unwrap_or_default().to_string(),
+                version_id: marker.version_id().unwrap_or("null").to_string(),
+                is_latest: marker.is_latest().unwrap_or_default(),
+                is_delete_marker: true,
+                last_modified: marker.last_modified().cloned(),
+                size: 0,
+            }))
+            .collect();
+        // Versions and delete markers come back in separate lists; put them back in order.
+        page.sort_by(|a, b| {
+            a.key
+                .cmp(&b.key)
+                .then_with(|| b.last_modified.cmp(&a.last_modified))
+        });
+        versions.extend(page);
+
+        if !output.is_truncated().unwrap_or_default() {
+            break;
+        }
+        key_marker = output.next_key_marker().map(String::from);
+        version_id_marker = output.next_version_id_marker().map(String::from);
+        if key_marker.is_none() {
+            break;
+        }
+    }
+    Ok(versions)
+}
+// snippet-end:[s3.rust.list_all_object_versions]
+
+// snippet-start:[s3.rust.purge_object_versions]
+/// Permanently delete every version and delete marker in `bucket`, and return how many were
+/// deleted. Turn versioning off first if anything is still writing to the bucket.
+pub async fn purge_versions(client: &Client, bucket: &str) -> Result<usize, S3ExampleError> {
+    let mut deleted = 0;
+    loop {
+        let versions = list_versions(client, bucket, None).await?;
+        if versions.is_empty() {
+            return Ok(deleted);
+        }
+        for chunk in versions.chunks(MAX_DELETE_KEYS) {
+            let objects = chunk
+                .iter()
+                .map(ObjectVersion::identifier)
+                .collect::<Result<Vec<_>, _>>()?;
+            let output = client
+                .delete_objects()
+                .bucket(bucket)
+                .delete(
+                    Delete::builder()
+                        .set_objects(Some(objects))
+                        .quiet(true)
+                        .build()
+                        .map_err(|err| {
+                            S3ExampleError::new(format!(
+                                "Failed to build delete_object input {err:?}"
+                            ))
+                        })?,
+                )
+                .send()
+                .await?;
+            // DeleteObjects succeeds even when some keys fail, so check each one.
+            if let Some(error) = output.errors().first() {
+                return Err(S3ExampleError::new(format!(
+                    "Failed to delete {} version {}: {}",
+                    error.key().unwrap_or_default(),
+                    error.version_id().unwrap_or_default(),
+                    error.message().unwrap_or_default()
+                )));
+            }
+            deleted += chunk.len();
+            debug!("Deleted {deleted} versions from {bucket}");
+        }
+    }
+}
+
+/// Delete `bucket` even if it is versioned, by purging every version first.
+pub async fn delete_versioned_bucket(client: &Client, bucket: &str) -> Result<(), S3ExampleError> {
+    purge_versions(client, bucket).await?;
+    delete_bucket(client, bucket).await
+}
+// snippet-end:[s3.rust.purge_object_versions]
+
+// snippet-start:[s3.rust.restore_object_version]
+/// Make `version_id` of `key` the current version again, by copying it over the current one.
+/// Nothing is deleted: the version being replaced stays as a noncurrent version. Objects over
+/// 5 GB can't be copied in one request.
+pub async fn restore_version(
+    client: &Client,
+    bucket: &str,
+    key: &str,
+    version_id: &str,
+) -> Result<CopyObjectOutput, S3ExampleError> {
+    let source = format!(
+        "{bucket}/{}?versionId={}",
+        encode_key(key),
+        encode_key(version_id)
+    );
+    client
+        .copy_object()
+        .copy_source(source)
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await
+        .map_err(|err| {
+            S3ExampleError::from(err).add_message(format!("Failed to restore {key} {version_id}"))
+        })
+}
+// snippet-end:[s3.rust.restore_object_version]
+
+/// Percent-encode `key` for the CopySource header, keeping `/` between path segments.
+fn encode_key(key: &str) -> String {
+    key.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use aws_smithy_runtime::client::http::test_util::StaticReplayClient;
+    use sdk_examples_test_utils::{client_config, single_shot_client, test_event};
+
+    use super::*;
+
+    #[test]
+    fn test_encode_key() {
+        assert_eq!(
+            encode_key("photos/2024/a b+c.jpg"),
+            "photos/2024/a%20b%2Bc.jpg"
+        );
+        assert_eq!(encode_key("café"), "caf%C3%A9");
+    }
+
+    #[tokio::test]
+    async fn test_list_versions() {
+        let client = single_shot_client!(
+            sdk: aws_sdk_s3,
+            status: 200,
+            response: r#"<?xml version="1.0" encoding="UTF-8"?><ListVersionsResult>
+            <Name>bucket</Name><IsTruncated>false</IsTruncated>
+            <Version><Key>a.txt</Key><VersionId>v2</VersionId><IsLatest>false</IsLatest>
+                <LastModified>2024-05-02T00:00:00.000Z</LastModified><Size>4</Size></Version>
+            <Version><Key>a.txt</Key><VersionId>v1</VersionId><IsLatest>false</IsLatest>
+                <LastModified>2024-05-01T00:00:00.000Z</LastModified><Size>3</Size></Version>
+            <DeleteMarker><Key>a.txt</Key><VersionId>v3</VersionId><IsLatest>true</IsLatest>
+                <LastModified>2024-05-03T00:00:00.000Z</LastModified></DeleteMarker>
+            </ListVersionsResult>"#
+        );
+
+        let versions = list_versions(&client, "bucket", None).await.unwrap();
+        let ids: Vec<&str> = versions.iter().map(|v| v.version_id.as_str()).collect();
+        assert_eq!(ids, vec!["v3", "v2", "v1"]);
+        assert!(versions[0].is_delete_marker && versions[0].is_latest);
+        assert_eq!(versions[1].size, 4);
+    }
+
+    #[tokio::test]
+    async fn test_delete_versioned_bucket() {
+        let client = Client::from_conf(
+            client_config!(aws_sdk_s3)
+                .http_client(StaticReplayClient::new(vec![
+                    // client.list_object_versions()
+                    test_event!(
+                        r#""#,
+                        (
+                            200,
+                            r#"<?xml version="1.0" encoding="UTF-8"?><ListVersionsResult>
+                            <Name>bucket</Name><IsTruncated>false</IsTruncated>
+                            <Version><Key>a.txt</Key><VersionId>v1</VersionId></Version>
+                            <DeleteMarker><Key>a.txt</Key><VersionId>v3</VersionId></DeleteMarker>
+                            </ListVersionsResult>"#
+                        )
+                    ),
+                    // client.delete_objects()
+                    test_event!(
+                        r#""#,
+                        (
+                            200,
+                            r#"<?xml version="1.0" encoding="UTF-8"?><DeleteResult></DeleteResult>"#
+                        )
+                    ),
+                    // client.list_object_versions() finds nothing left.
+                    test_event!(
+                        r#""#,
+                        (
+                            200,
+                            r#"<?xml version="1.0" encoding="UTF-8"?><ListVersionsResult>
+                            <Name>bucket</Name><IsTruncated>false</IsTruncated>
+                            </ListVersionsResult>"#
+                        )
+                    ),
+                    // client.delete_bucket()
+                    test_event!(r#""#, (204, r#""#)),
+                ]))
+                .build(),
+        );
+
+        let resp = delete_versioned_bucket(&client, "bucket").await;
+        assert!(resp.is_ok(), "{resp:?}");
+    }
+
+    #[tokio::test]
+    async fn test_purge_reports_failed_keys() {
+        let client = Client::from_conf(
+            client_config!(aws_sdk_s3)
+                .http_client(StaticReplayClient::new(vec![
+                    test_event!(
+                        r#""#,
+                        (
+                            200,
+                            r#"<?xml version="1.0" encoding="UTF-8"?><ListVersionsResult>
+                            <Name>bucket</Name><IsTruncated>false</IsTruncated>
+                            <Version><Key>locked.txt</Key><VersionId>v1</VersionId></Version>
+                            </ListVersionsResult>"#
+                        )
+                    ),
+                    test_event!(
+                        r#""#,
+                        (
+                            200,
+                            r#"<?xml version="1.0" encoding="UTF-8"?><DeleteResult>
+                            <Error><Key>locked.txt</Key><VersionId>v1</VersionId>
+                            <Code>AccessDenied</Code><Message>Access Denied because object protected by object lock.</Message></Error>
+                            </DeleteResult>"#
+                        )
+                    ),
+                ]))
+                .build(),
+        );
+
+        let resp = purge_versions(&client, "bucket").await;
+        assert!(resp.unwrap_err().to_string().contains("locked.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_restore_version() {
+        let client = single_shot_client!(
+            sdk: aws_sdk_s3,
+            status: 200,
+            response: r#"<?xml version="1.0" encoding="UTF-8"?><CopyObjectResult>
+            <ETag>"restored"</ETag></CopyObjectResult>"#
+        );
+
+        let resp = restore_version(&client, "bucket", "a.txt", "v1").await;
+        assert!(resp.is_ok(), "{resp:?}");
+    }
+}
@@ -4,11 +4,12 @@
 #![allow(clippy::result_large_err)]
 
 use aws_config::meta::region::RegionProviderChain;
-use aws_sdk_s3::presigning::{PresignedRequest, PresigningConfig};
+use aws_sdk_s3::presigning::PresignedRequest;
 use aws_sdk_s3::{config::Region, meta::PKG_VERSION, Client};
 use clap::Parser;
 use hyper::http::{HeaderName, HeaderValue};
 use hyper_util::rt::TokioExecutor;
+use s3_code_examples::presign::{PresignPolicy, Presigner, MAX_PRESIGN_EXPIRY};
 use std::error::Error;
 use std::time::Duration;
 
@@ -83,11 +84,10 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     let expires_in = Duration::from_secs(expires_in);
 
-    let presigned_request = client
-        .put_object()
-        .bucket(bucket)
-        .key(object)
-        .presigned(PresigningConfig::expires_in(expires_in)?)
+    let presigner = Presigner::new(client, bucket)
+        .with_policy(PresignPolicy::new().with_max_expiry(MAX_PRESIGN_EXPIRY));
+    let presigned_request = presigner
+        .presign_put(&object, expires_in, None, None)
         .await?;
 
     println!("Object URI: {}", presigned_request.uri());
@@ -3,7 +3,31 @@
 
 use anyhow::{anyhow, Result};
 use aws_sdk_sesv2::Client;
-use ses_code_examples::newsletter::SESWorkflow;
+use clap::Parser;
+use sdk_examples_prompter::PrompterArgs;
+use ses_code_examples::{bulk::Personalization, monitor::MonitorArgs, newsletter::SESWorkflow};
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+struct Opt {
+    #[command(flatten)]
+    prompts: PrompterArgs,
+
+    /// A CSV file of template data for each subscriber, with an `email` column.
+    #[structopt(long)]
+    personalization: Option<PathBuf>,
+
+    /// The page subscribers unsubscribe on. `{email}` in it is replaced by their address.
+    #[structopt(long)]
+    unsubscribe_url: Option<String>,
+
+    #[command(flatten)]
+    monitor: MonitorArgs,
+
+    /// Also save the sending statistics to this file as JSON.
+    #[structopt(long)]
+    stats_json: Option<PathBuf>,
+}
 
 const INTRO: &str = "
 Welcome to the Amazon SES v2 Coupon Newsletter Workflow!
@@ -12,7 +36,7 @@ This workflow will help you:
 1. Prepare a verified email identity and contact list for your newsletter.
 2. Gather subscriber email addresses and send them a welcome email.
 3. Send a weekly coupon newsletter to your subscribers using email templates.
-4. Monitor your sending activity and metrics in the AWS console.
+4. Review your sending quota, and your bounce and complaint rates.
 
 Let's get started!
 ";
@@ -21,10 +45,28 @@ Let's get started!
 ///
 /// # Arguments
 ///
-/// None
+/// * `[--answers FILE]` - Answer the workflow's prompts from this JSON file.
+/// * `[--non-interactive]` - Take every prompt's default without asking.
+/// * `[--personalization CSV]` - Per-subscriber template data for the newsletter.
+/// * `[--unsubscribe-url URL]` - The newsletter's unsubscribe link, with `{email}` for the
+///    subscriber's address. Without it, the link emails the sender.
+/// * `[--event-namespace [NAMESPACE]]` - Also report event counts from this CloudWatch namespace.
+/// * `[--stats-json FILE]` - Save the sending statistics as JSON.
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
+    let Opt {
+        prompts,
+        personalization,
+        unsubscribe_url,
+        monitor,
+        stats_json,
+    } = Opt::parse();
+    let prompter = prompts.prompter()?;
+    let personalization = match personalization {
+        Some(path) => Personalization::load(&path)?,
+        None => Personalization::default(),
+    };
 
     println!("{}", INTRO);
 
@@ -33,9 +75,16 @@ async fn main() -> Result<()> {
     let client = Client::new(&config);
 
     // Initialize the SESWorkflow struct
-    let mut stdin = std::io::stdin().lock();
     let mut stdout = std::io::stdout().lock();
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, prompter.as_ref(), &mut stdout);
+    workflow.set_personalization(personalization);
+    if let Some(unsubscribe_url) = unsubscribe_url {
+        workflow.set_unsubscribe_url(unsubscribe_url);
+    }
+    workflow.set_sending_monitor(monitor.monitor(&config));
+    if let Some(stats_json) = stats_json {
+        workflow.set_stats_json(stats_json);
+    }
 
     // Execute the workflow steps
     let run = workflow.run().await;
@@ -0,0 +1,86 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use aws_config::meta::region::RegionProviderChain;
+use aws_sdk_sesv2::{config::Region, meta::PKG_VERSION};
+use clap::Parser;
+use ses_code_examples::monitor::MonitorArgs;
+
+#[derive(Debug, Parser)]
+struct Opt {
+    #[command(flatten)]
+    monitor: MonitorArgs,
+
+    /// Print the report as JSON, for dashboards.
+    #[structopt(long)]
+    json: bool,
+
+    /// Exit with an error when any rate is over its threshold.
+    #[structopt(long)]
+    fail_on_alert: bool,
+
+    /// The AWS Region.
+    #[structopt(short, long)]
+    region: Option<String>,
+
+    /// Whether to display additional information.
+    #[structopt(short, long)]
+    verbose: bool,
+}
+
+/// Shows the account's sending quota and its bounce and complaint rates over the last two weeks.
+/// # Arguments
+///
+/// * `[--event-namespace [NAMESPACE]]` - Also report the events an event destination publishes
+///    to this CloudWatch namespace. Defaults to **AWS/SES** when given without a value.
+/// * `[--configuration-set NAME]` - Only count events for this configuration set.
+/// * `[--bounce-threshold RATE]` - Alert at this bounce rate. Defaults to 0.05.
+/// * `[--complaint-threshold RATE]` - Alert at this complaint rate. Defaults to 0.001.
+/// * `[--interval-hours HOURS]` - How many hours each period of the report covers. At least 1;
+///   defaults to 24.
+/// * `[--json]` - Print the report as JSON.
+/// * `[--fail-on-alert]` - Exit with an error when any rate is over its threshold.
+/// * `[-r REGION]` - The Region in which the client is created.
+///    If not supplied, uses the value of the **AWS_REGION** environment variable.
+///    If the environment variable is not set, defaults to **us-west-2**.
+/// * `[-v]` - Whether to display additional information.
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let Opt {
+        monitor,
+        json,
+        fail_on_alert,
+        region,
+        verbose,
+    } = Opt::parse();
+
+    let region_provider = RegionProviderChain::first_try(region.map(Region::new))
+        .or_default_provider()
+        .or_else(Region::new("us-west-2"));
+
+    if verbose {
+        println!("SES client version: {}", PKG_VERSION);
+        println!(
+            "Region:             {}",
+            region_provider.region().await.unwrap().as_ref()
+        );
+        println!();
+    }
+
+    let shared_config = aws_config::from_env().region(region_provider).load().await;
+    let report = monitor.monitor(&shared_config).report().await?;
+
+    if json {
+        println!("{}", report.to_json()?);
+    } else {
+        print!("{report}");
+    }
+
+    if fail_on_alert && !report.alerts.is_empty() {
+        anyhow::bail!("{} sending alerts", report.alerts.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,124 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Pieces of the bulk newsletter send: per-recipient template data, and the report of who
+//! the newsletter reached.
+
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+use std::{collections::HashMap, fmt::Display, io::Read, path::Path};
+
+/// SendBulkEmail accepts at most this many destinations in one call.
+pub const MAX_BULK_ENTRIES: usize = 50;
+
+/// How many times to try a recipient whose send was throttled, before giving up on it.
+pub const MAX_SEND_ATTEMPTS: u32 = 3;
+
+/// Template data for each recipient, read from a CSV file with an `email` column. Every other
+/// column becomes a template variable of the same name, for that recipient's email.
+#[derive(Clone, Debug, Default)]
+pub struct Personalization {
+    recipients: HashMap<String, Map<String, Value>>,
+}
+
+impl Personalization {
+    pub fn from_csv(reader: impl Read) -> Result<Self> {
+        let mut csv = csv::Reader::from_reader(reader);
+        let headers = csv.headers()?.clone();
+        let email_column = headers
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case("email"))
+            .ok_or_else(|| anyhow!("Personalization CSV has no email column"))?;
+
+        let mut recipients = HashMap::new();
+        for record in csv.records() {
+            let record = record?;
+            let email = record
+                .get(email_column)
+                .map(|email| email.trim().to_lowercase())
+                .filter(|email| !email.is_empty())
+                .ok_or_else(|| anyhow!("Personalization CSV row without an email: {record:?}"))?;
+            let data = headers
+                .iter()
+                .zip(record.iter())
+                .enumerate()
+                .filter(|(column, (_, value))| *column != email_column && !value.is_empty())
+                .map(|(_, (header, value))| (header.trim().to_string(), value.into()))
+                .collect();
+            recipients.insert(email, data);
+        }
+        Ok(Personalization { recipients })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)
+            .map_err(|e| anyhow!("Error opening personalization CSV {path:?}: {e}"))?;
+        Self::from_csv(file)
+    }
+
+    pub fn len(&self) -> usize {
+        self.recipients.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recipients.is_empty()
+    }
+
+    /// The template data for one recipient: the default data, with their CSV columns on top.
+    pub fn template_data(&self, email: &str, default_data: &str) -> Result<String> {
+        let mut data = match serde_json::from_str(default_data)? {
+            Value::Object(data) => data,
+            _ => Map::new(),
+        };
+        if let Some(fields) = self.recipients.get(&email.to_lowercase()) {
+            data.extend(fields.clone());
+        }
+        Ok(Value::Object(data).to_string())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Delivery {
+    pub email: String,
+    pub message_id: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Failure {
+    pub email: String,
+    pub reason: String,
+}
+
+/// What happened to each contact in the list.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeliveryReport {
+    pub sent: Vec<Delivery>,
+    pub failed: Vec<Failure>,
+    /// Addresses on the account suppression list, which were not sent to.
+    pub suppressed: Vec<String>,
+}
+
+impl DeliveryReport {
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+impl Display for DeliveryReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Newsletter delivery: {} sent, {} failed, {} suppressed",
+            self.sent.len(),
+            self.failed.len(),
+            self.suppressed.len()
+        )?;
+        for failure in &self.failed {
+            writeln!(f, "  failed: {} ({})", failure.email, failure.reason)?;
+        }
+        for email in &self.suppressed {
+            writeln!(f, "  suppressed: {email}")?;
+        }
+        Ok(())
+    }
+}
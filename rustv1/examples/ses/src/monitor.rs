@@ -0,0 +1,478 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Sending statistics for the account: quota, bounce and complaint rates over time, and the
+//! event counts an event destination publishes to CloudWatch.
+
+use anyhow::{anyhow, Result};
+use aws_sdk_cloudwatch::types::{Dimension, Statistic};
+use aws_sdk_ses::primitives::{DateTime, DateTimeFormat};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    time::{Duration, SystemTime},
+};
+
+/// The namespace SES event destinations publish to, unless they were set up with another one.
+pub const DEFAULT_NAMESPACE: &str = "AWS/SES";
+
+/// The dimension SES adds to event metrics for the configuration set that sent the email.
+const CONFIGURATION_SET_DIMENSION: &str = "ses:configuration-set";
+
+/// The event metrics an event destination can publish.
+const EVENT_METRICS: [&str; 9] = [
+    "Send",
+    "Delivery",
+    "Bounce",
+    "Complaint",
+    "Reject",
+    "Open",
+    "Click",
+    "RenderingFailure",
+    "DeliveryDelay",
+];
+
+/// Send statistics only go back two weeks.
+const STATISTICS_WINDOW: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+/// The most datapoints one GetMetricStatistics call returns.
+const MAX_DATAPOINTS: u64 = 1440;
+
+/// The rates to alert at. The defaults are the rates at which SES puts an account under review.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Thresholds {
+    pub bounce_rate: f64,
+    pub complaint_rate: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            bounce_rate: 0.05,
+            complaint_rate: 0.001,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct SendingQuota {
+    pub max_24_hour_send: f64,
+    pub max_send_rate: f64,
+    pub sent_last_24_hours: f64,
+    /// HEALTHY, PROBATION, or SHUTDOWN.
+    pub enforcement_status: Option<String>,
+}
+
+/// Sending activity in one interval.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct SendingPeriod {
+    /// The start of the interval, in RFC 3339.
+    pub start: String,
+    pub delivery_attempts: i64,
+    pub bounces: i64,
+    pub complaints: i64,
+    pub rejects: i64,
+}
+
+impl SendingPeriod {
+    pub fn bounce_rate(&self) -> f64 {
+        rate(self.bounces, self.delivery_attempts)
+    }
+
+    pub fn complaint_rate(&self) -> f64 {
+        rate(self.complaints, self.delivery_attempts)
+    }
+
+    fn add(&mut self, other: &SendingPeriod) {
+        self.delivery_attempts += other.delivery_attempts;
+        self.bounces += other.bounces;
+        self.complaints += other.complaints;
+        self.rejects += other.rejects;
+    }
+}
+
+fn rate(count: i64, attempts: i64) -> f64 {
+    if attempts == 0 {
+        0.0
+    } else {
+        count as f64 / attempts as f64
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    BounceRate,
+    ComplaintRate,
+}
+
+/// A rate at or over its threshold, either in one interval or over the whole window.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Alert {
+    pub kind: AlertKind,
+    /// The start of the interval, or None for the whole window.
+    pub period: Option<String>,
+    pub rate: f64,
+    pub threshold: f64,
+}
+
+impl Display for Alert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self.kind {
+            AlertKind::BounceRate => "Bounce rate",
+            AlertKind::ComplaintRate => "Complaint rate",
+        };
+        write!(
+            f,
+            "{name} {:.2}% is at or over the {:.2}% threshold",
+            self.rate * 100.0,
+            self.threshold * 100.0
+        )?;
+        match &self.period {
+            Some(period) => write!(f, " in the interval starting {period}"),
+            None => write!(f, " over the last two weeks"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct SendingReport {
+    pub quota: SendingQuota,
+    pub thresholds: Thresholds,
+    /// Oldest first.
+    pub periods: Vec<SendingPeriod>,
+    pub total: SendingPeriod,
+    pub alerts: Vec<Alert>,
+    /// Event counts from CloudWatch, by metric name. Empty when no namespace is configured.
+    pub events: BTreeMap<String, f64>,
+}
+
+impl SendingReport {
+    /// Group 15 minute send statistics into `interval` long periods, and check every period and
+    /// the whole window against the thresholds.
+    pub fn new(
+        quota: SendingQuota,
+        thresholds: Thresholds,
+        points: &[aws_sdk_ses::types::SendDataPoint],
+        interval: Duration,
+    ) -> Result<Self> {
+        let interval = interval.as_secs().max(1) as i64;
+        let mut periods: BTreeMap<i64, SendingPeriod> = BTreeMap::new();
+        for point in points {
+            let Some(timestamp) = point.timestamp() else {
+                continue;
+            };
+            let start = timestamp.secs() - timestamp.secs().rem_euclid(interval);
+            periods.entry(start).or_default().add(&SendingPeriod {
+                start: String::new(),
+                delivery_attempts: point.delivery_attempts(),
+                bounces: point.bounces(),
+                complaints: point.complaints(),
+                rejects: point.rejects(),
+            });
+        }
+
+        let mut report = SendingReport {
+            quota,
+            thresholds,
+            total: SendingPeriod {
+                start: periods
+                    .keys()
+                    .next()
+                    .map(|start| format_time(*start))
+                    .transpose()?
+                    .unwrap_or_default(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        for (start, mut period) in periods {
+            period.start = format_time(start)?;
+            report.total.add(&period);
+            report.check(&period, Some(period.start.clone()));
+            report.periods.push(period);
+        }
+        let total = report.total.clone();
+        report.check(&total, None);
+        Ok(report)
+    }
+
+    fn check(&mut self, period: &SendingPeriod, label: Option<String>) {
+        if period.delivery_attempts == 0 {
+            return;
+        }
+        if period.bounce_rate() >= self.thresholds.bounce_rate {
+            self.alerts.push(Alert {
+                kind: AlertKind::BounceRate,
+                period: label.clone(),
+                rate: period.bounce_rate(),
+                threshold: self.thresholds.bounce_rate,
+            });
+        }
+        if period.complaint_rate() >= self.thresholds.complaint_rate {
+            self.alerts.push(Alert {
+                kind: AlertKind::ComplaintRate,
+                period: label,
+                rate: period.complaint_rate(),
+                threshold: self.thresholds.complaint_rate,
+            });
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+fn format_time(secs: i64) -> Result<String> {
+    DateTime::from_secs(secs)
+        .fmt(DateTimeFormat::DateTime)
+        .map_err(|e| anyhow!("Error formatting time {secs}: {e}"))
+}
+
+fn write_row(
+    f: &mut std::fmt::Formatter<'_>,
+    label: &str,
+    period: &SendingPeriod,
+) -> std::fmt::Result {
+    writeln!(
+        f,
+        "{:<22} {:>9} {:>8} {:>7.2}% {:>8} {:>7.3}%",
+        label,
+        period.delivery_attempts,
+        period.bounces,
+        period.bounce_rate() * 100.0,
+        period.complaints,
+        period.complaint_rate() * 100.0
+    )
+}
+
+impl Display for SendingReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Sending quota: {} of {} emails sent in the last 24 hours, at most {} per second",
+            self.quota.sent_last_24_hours, self.quota.max_24_hour_send, self.quota.max_send_rate
+        )?;
+        if let Some(status) = &self.quota.enforcement_status {
+            writeln!(f, "Account status: {status}")?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<22} {:>9} {:>8} {:>8} {:>8} {:>8}",
+            "Interval", "Attempts", "Bounces", "Bounce%", "Compl.", "Compl.%"
+        )?;
+        for period in &self.periods {
+            write_row(f, &period.start, period)?;
+        }
+        write_row(f, "Total", &self.total)?;
+        if !self.events.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Events:")?;
+            for (name, count) in &self.events {
+                writeln!(f, "  {name:<17} {count}")?;
+            }
+        }
+        writeln!(f)?;
+        if self.alerts.is_empty() {
+            writeln!(f, "No rates are over their thresholds.")?;
+        }
+        for alert in &self.alerts {
+            writeln!(f, "ALERT: {alert}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Command line options for a [SendingMonitor].
+#[derive(Clone, Debug, clap::Args)]
+pub struct MonitorArgs {
+    /// Report the events an event destination publishes to this CloudWatch namespace.
+    #[structopt(long, value_name = "NAMESPACE", num_args = 0..=1, default_missing_value = DEFAULT_NAMESPACE)]
+    pub event_namespace: Option<String>,
+
+    /// Only count events for this configuration set.
+    #[structopt(long, requires = "event_namespace")]
+    pub configuration_set: Option<String>,
+
+    /// Alert when the bounce rate reaches this fraction of delivery attempts.
+    #[structopt(long, default_value_t = Thresholds::default().bounce_rate)]
+    pub bounce_threshold: f64,
+
+    /// Alert when the complaint rate reaches this fraction of delivery attempts.
+    #[structopt(long, default_value_t = Thresholds::default().complaint_rate)]
+    pub complaint_threshold: f64,
+
+    /// How many hours each period of the report covers.
+    #[structopt(long, default_value_t = 24, value_parser = clap::value_parser!(u64).range(1..))]
+    pub interval_hours: u64,
+}
+
+impl MonitorArgs {
+    pub fn monitor(&self, config: &aws_config::SdkConfig) -> SendingMonitor {
+        let monitor = SendingMonitor::new(
+            aws_sdk_sesv2::Client::new(config),
+            aws_sdk_ses::Client::new(config),
+        )
+        .with_thresholds(Thresholds {
+            bounce_rate: self.bounce_threshold,
+            complaint_rate: self.complaint_threshold,
+        })
+        .with_interval(Duration::from_secs(self.interval_hours * 60 * 60));
+        match &self.event_namespace {
+            Some(namespace) => monitor.with_event_metrics(
+                aws_sdk_cloudwatch::Client::new(config),
+                namespace,
+                self.configuration_set.clone(),
+            ),
+            None => monitor,
+        }
+    }
+}
+
+/// Where an event destination publishes its metrics.
+#[derive(Clone, Debug)]
+struct EventSource {
+    client: aws_sdk_cloudwatch::Client,
+    namespace: String,
+    configuration_set: Option<String>,
+}
+
+/// Builds a [SendingReport] from SES, and CloudWatch when an event destination is set up.
+#[derive(Clone, Debug)]
+pub struct SendingMonitor {
+    sesv2: aws_sdk_sesv2::Client,
+    ses: aws_sdk_ses::Client,
+    events: Option<EventSource>,
+    thresholds: Thresholds,
+    interval: Duration,
+}
+
+impl SendingMonitor {
+    /// Send statistics are only available from the SES v1 API, so the monitor needs both clients.
+    pub fn new(sesv2: aws_sdk_sesv2::Client, ses: aws_sdk_ses::Client) -> Self {
+        SendingMonitor {
+            sesv2,
+            ses,
+            events: None,
+            thresholds: Thresholds::default(),
+            interval: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+
+    /// Also report the event counts an event destination publishes to `namespace`, for one
+    /// configuration set or for all of them.
+    pub fn with_event_metrics(
+        mut self,
+        client: aws_sdk_cloudwatch::Client,
+        namespace: impl Into<String>,
+        configuration_set: Option<String>,
+    ) -> Self {
+        self.events = Some(EventSource {
+            client,
+            namespace: namespace.into(),
+            configuration_set,
+        });
+        self
+    }
+
+    pub fn with_thresholds(mut self, thresholds: Thresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// How long each period in the report is. Defaults to a day.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub async fn report(&self) -> Result<SendingReport> {
+        let account = self
+            .sesv2
+            .get_account()
+            .send()
+            .await
+            .map_err(|e| anyhow!("Error getting account details: {}", e))?;
+        let quota = SendingQuota {
+            max_24_hour_send: account
+                .send_quota()
+                .map(|q| q.max24_hour_send())
+                .unwrap_or_default(),
+            max_send_rate: account
+                .send_quota()
+                .map(|q| q.max_send_rate())
+                .unwrap_or_default(),
+            sent_last_24_hours: account
+                .send_quota()
+                .map(|q| q.sent_last24_hours())
+                .unwrap_or_default(),
+            enforcement_status: account.enforcement_status().map(String::from),
+        };
+
+        let statistics = self
+            .ses
+            .get_send_statistics()
+            .send()
+            .await
+            .map_err(|e| anyhow!("Error getting send statistics: {}", e))?;
+
+        let mut report = SendingReport::new(
+            quota,
+            self.thresholds,
+            statistics.send_data_points(),
+            self.interval,
+        )?;
+        if let Some(events) = &self.events {
+            report.events = self.event_counts(events).await?;
+        }
+        Ok(report)
+    }
+
+    /// Sum each event metric over the statistics window. Metrics with no data are left out.
+    async fn event_counts(&self, events: &EventSource) -> Result<BTreeMap<String, f64>> {
+        let end = SystemTime::now();
+        let start = end - STATISTICS_WINDOW;
+        // Periods must be a multiple of 60 seconds, and long enough that the whole window fits
+        // in one call's datapoints.
+        let shortest = STATISTICS_WINDOW.as_secs().div_ceil(MAX_DATAPOINTS);
+        let period = self.interval.as_secs().max(shortest).div_ceil(60) * 60;
+        let mut counts = BTreeMap::new();
+        for metric in EVENT_METRICS {
+            let output = events
+                .client
+                .get_metric_statistics()
+                .namespace(&events.namespace)
+                .metric_name(metric)
+                .set_dimensions(events.configuration_set.as_ref().map(|name| {
+                    vec![Dimension::builder()
+                        .name(CONFIGURATION_SET_DIMENSION)
+                        .value(name)
+                        .build()]
+                }))
+                .start_time(DateTime::from(start))
+                .end_time(DateTime::from(end))
+                .period(period as i32)
+                .statistics(Statistic::Sum)
+                .send()
+                .await
+                .map_err(|e| {
+                    anyhow!(
+                        "Error getting {metric} from CloudWatch namespace {}: {}",
+                        events.namespace,
+                        e
+                    )
+                })?;
+            let datapoints = output.datapoints();
+            if !datapoints.is_empty() {
+                counts.insert(
+                    metric.to_string(),
+                    datapoints.iter().filter_map(|d| d.sum()).sum(),
+                );
+            }
+        }
+        Ok(counts)
+    }
+}
@@ -6,26 +6,50 @@ use aws_sdk_sesv2::{
     operation::{
         create_contact::CreateContactError, create_contact_list::CreateContactListError,
         create_email_identity::CreateEmailIdentityError,
-        create_email_template::CreateEmailTemplateError,
+        create_email_template::CreateEmailTemplateError, send_bulk_email::SendBulkEmailError,
     },
     types::{
-        Body, Contact, Content, Destination, EmailContent, EmailTemplateContent,
-        ListManagementOptions, Message, Template,
+        Body, BulkEmailContent, BulkEmailEntry, BulkEmailStatus, Contact, Content, Destination,
+        EmailContent, EmailTemplateContent, Message, ReplacementEmailContent, ReplacementTemplate,
+        SubscriptionStatus, Template, Topic,
     },
     Client,
 };
-use std::io::{BufRead, Write};
+use sdk_examples_prompter::Prompter;
+use serde_json::{Map, Value};
+use std::{
+    collections::{HashSet, VecDeque},
+    io::Write,
+    path::PathBuf,
+    time::Duration,
+};
 use tracing::info;
 
+use crate::{
+    bulk::{
+        Delivery, DeliveryReport, Failure, Personalization, MAX_BULK_ENTRIES, MAX_SEND_ATTEMPTS,
+    },
+    monitor::SendingMonitor,
+};
+
 const CONTACT_LIST_NAME: &str = "weekly-coupons-newsletter";
 const TEMPLATE_NAME: &str = "weekly-coupons";
+const TOPIC_NAME: &str = "coupons";
+/// The template variable SES fills with its unsubscribe link when it manages the contact list.
+/// SendBulkEmail doesn't, so the workflow fills it in each recipient's template data instead.
+const UNSUBSCRIBE_URL_VARIABLE: &str = "amazonSESUnsubscribeUrl";
 
 /// The SESWorkflow struct encapsulates the entire SES v2 Coupon Newsletter Workflow.
 pub struct SESWorkflow<'a> {
     client: Client,
-    stdin: &'a mut dyn BufRead,
+    prompter: &'a dyn Prompter,
     stdout: &'a mut dyn Write,
     verified_email: String,
+    personalization: Personalization,
+    retry_delay: Duration,
+    sending_monitor: Option<SendingMonitor>,
+    stats_json: Option<PathBuf>,
+    unsubscribe_url: Option<String>,
 }
 
 impl<'a> SESWorkflow<'a> {
@@ -34,24 +58,30 @@ impl<'a> SESWorkflow<'a> {
     /// # Arguments
     ///
     /// * `client` - The AWS SDK for Rust SES v2 client.
-    /// * `stdin` - A mutable reference to the standard input stream.
+    /// * `prompter` - Asks the user for the workflow's inputs.
     /// * `stdout` - A mutable reference to the standard output stream.
-    pub fn new(client: Client, stdin: &'a mut dyn BufRead, stdout: &'a mut dyn Write) -> Self {
+    pub fn new(client: Client, prompter: &'a dyn Prompter, stdout: &'a mut dyn Write) -> Self {
         Self {
             client,
-            stdin,
+            prompter,
             stdout,
             verified_email: "".into(),
+            personalization: Personalization::default(),
+            retry_delay: Duration::from_secs(1),
+            sending_monitor: None,
+            stats_json: None,
+            unsubscribe_url: None,
         }
     }
 
     /// Prepares the application by creating a verified email identity and a contact list.
     pub async fn prepare_application(&mut self) -> Result<()> {
         // Prompt the user for a verified email address
-        writeln!(self.stdout, "Enter the verified email address to use: ")?;
-        self.stdout.flush().unwrap();
-        let mut verified_email = String::new();
-        self.stdin.read_line(&mut verified_email).unwrap();
+        let verified_email = self.prompter.text(
+            "verified_email",
+            "Enter the verified email address to use:",
+            None,
+        )?;
         self.verified_email = verified_email.trim().to_string();
 
         // snippet-start:[sesv2.rust.create-email-identity]
@@ -81,6 +111,13 @@ impl<'a> SESWorkflow<'a> {
             .client
             .create_contact_list()
             .contact_list_name(CONTACT_LIST_NAME)
+            .topics(
+                Topic::builder()
+                    .topic_name(TOPIC_NAME)
+                    .display_name("Weekly coupons")
+                    .default_subscription_status(SubscriptionStatus::OptIn)
+                    .build()?,
+            )
             .send()
             .await
         {
@@ -139,13 +176,11 @@ impl<'a> SESWorkflow<'a> {
     /// Gathers subscriber email addresses and sends welcome emails.
     pub async fn gather_subscriber_emails(&mut self) -> Result<()> {
         // Prompt the user for a base email address
-        writeln!(
-            self.stdout,
-            "Enter a base email address for subscribing (e.g., user@example.com): "
+        let base_email = self.prompter.text(
+            "base_email",
+            "Enter a base email address for subscribing (e.g., user@example.com):",
+            None,
         )?;
-        self.stdout.flush().unwrap();
-        let mut base_email = String::new();
-        self.stdin.read_line(&mut base_email).unwrap();
         let base_email = base_email.trim().to_string();
 
         // Create 3 variants of the email address as {user email}+ses-weekly-newsletter-{i}@{user domain}
@@ -232,8 +267,15 @@ impl<'a> SESWorkflow<'a> {
         Ok(())
     }
 
-    /// Sends the coupon newsletter to the subscribers.
-    pub async fn send_coupon_newsletter(&mut self) -> Result<()> {
+    /// Sends the coupon newsletter to the subscribers, in bulk.
+    ///
+    /// Addresses on the account suppression list, and contacts who have unsubscribed from the
+    /// contact list or from its coupons topic, are skipped. SendBulkEmail has no
+    /// ListManagementOptions, so SES won't filter unsubscribed contacts or add an unsubscribe
+    /// link on its own; each recipient's template data carries their unsubscribe link instead.
+    /// Recipients whose send was throttled are retried with a backoff, and every other failure
+    /// is recorded in the returned report instead of stopping the send.
+    pub async fn send_coupon_newsletter(&mut self) -> Result<DeliveryReport> {
         // Retrieve the list of contacts
         // snippet-start:[sesv2.rust.list-contacts]
         let contacts: Vec<Contact> = match self
@@ -256,86 +298,238 @@ impl<'a> SESWorkflow<'a> {
         };
         // snippet-end:[sesv2.rust.list-contacts]
 
-        // Send the newsletter to each contact
-        for email in contacts {
-            let email = email.email_address.unwrap();
+        let mut report = DeliveryReport::default();
+        let suppressed = self.suppressed_destinations().await?;
+        let mut queue: VecDeque<(String, u32)> = VecDeque::new();
+        for contact in contacts {
+            let unsubscribed = unsubscribed(&contact);
+            let Some(email) = contact.email_address else {
+                continue;
+            };
+            if unsubscribed {
+                writeln!(self.stdout, "Skipping unsubscribed contact {}", email)?;
+                report.suppressed.push(email);
+            } else if suppressed.contains(&email.to_lowercase()) {
+                writeln!(self.stdout, "Skipping suppressed address {}", email)?;
+                report.suppressed.push(email);
+            } else {
+                queue.push_back((email, 0));
+            }
+        }
 
-            // snippet-start:[sesv2.rust.send-email.template]
-            let coupons = std::fs::read_to_string("../resources/newsletter/sample_coupons.json")
-                .unwrap_or_else(|_| r#"{"coupons":[]}"#.to_string());
-            let email_content = EmailContent::builder()
-                .template(
-                    Template::builder()
-                        .template_name(TEMPLATE_NAME)
-                        .template_data(coupons)
-                        .build(),
-                )
-                .build();
+        let coupons = std::fs::read_to_string("../resources/newsletter/sample_coupons.json")
+            .unwrap_or_else(|_| r#"{"coupons":[]}"#.to_string());
 
-            match self
+        while !queue.is_empty() {
+            let chunk: Vec<(String, u32)> =
+                queue.drain(..queue.len().min(MAX_BULK_ENTRIES)).collect();
+
+            // Back off before sending recipients that were throttled last time.
+            if let Some(attempts) = chunk.iter().map(|(_, attempts)| *attempts).max() {
+                if attempts > 0 {
+                    tokio::time::sleep(self.retry_delay * 2u32.pow(attempts - 1)).await;
+                }
+            }
+
+            // snippet-start:[sesv2.rust.send-bulk-email.template]
+            let mut entries = Vec::with_capacity(chunk.len());
+            for (email, _) in &chunk {
+                let mut data: Map<String, Value> =
+                    serde_json::from_str(&self.personalization.template_data(email, &coupons)?)?;
+                data.insert(
+                    UNSUBSCRIBE_URL_VARIABLE.into(),
+                    self.unsubscribe_url(email).into(),
+                );
+                entries.push(
+                    BulkEmailEntry::builder()
+                        .destination(Destination::builder().to_addresses(email.clone()).build())
+                        .replacement_email_content(
+                            ReplacementEmailContent::builder()
+                                .replacement_template(
+                                    ReplacementTemplate::builder()
+                                        .replacement_template_data(Value::Object(data).to_string())
+                                        .build(),
+                                )
+                                .build(),
+                        )
+                        .build()?,
+                );
+            }
+
+            let result = self
                 .client
-                .send_email()
+                .send_bulk_email()
                 .from_email_address(self.verified_email.clone())
-                .destination(Destination::builder().to_addresses(email.clone()).build())
-                .content(email_content)
-                .list_management_options(
-                    ListManagementOptions::builder()
-                        .contact_list_name(CONTACT_LIST_NAME)
-                        .build()?,
+                .default_content(
+                    BulkEmailContent::builder()
+                        .template(
+                            Template::builder()
+                                .template_name(TEMPLATE_NAME)
+                                .template_data(coupons.clone())
+                                .build(),
+                        )
+                        .build(),
                 )
+                .set_bulk_email_entries(Some(entries))
                 .send()
-                .await
-            {
+                .await;
+            // snippet-end:[sesv2.rust.send-bulk-email.template]
+
+            match result {
                 Ok(output) => {
-                    if let Some(message_id) = output.message_id {
-                        writeln!(
-                            self.stdout,
-                            "Newsletter sent to {} with message ID {}",
-                            email, message_id
-                        )?;
-                    } else {
-                        writeln!(self.stdout, "Newsletter sent to {}", email)?;
+                    let results = output.bulk_email_entry_results();
+                    for (i, (email, attempts)) in chunk.into_iter().enumerate() {
+                        // Results come back in the order of the entries. A recipient without
+                        // one can't be known to have been sent to.
+                        let Some(result) = results.get(i) else {
+                            self.record_failure(&mut report, email, "no result returned".into())?;
+                            continue;
+                        };
+                        match result.status() {
+                            Some(BulkEmailStatus::Success) => {
+                                let message_id = result.message_id().map(String::from);
+                                match &message_id {
+                                    Some(message_id) => writeln!(
+                                        self.stdout,
+                                        "Newsletter sent to {} with message ID {}",
+                                        email, message_id
+                                    )?,
+                                    None => writeln!(self.stdout, "Newsletter sent to {}", email)?,
+                                }
+                                report.sent.push(Delivery { email, message_id });
+                            }
+                            Some(BulkEmailStatus::AccountThrottled)
+                            | Some(BulkEmailStatus::TransientFailure)
+                                if attempts + 1 < MAX_SEND_ATTEMPTS =>
+                            {
+                                queue.push_back((email, attempts + 1));
+                            }
+                            status => {
+                                let reason = result
+                                    .error()
+                                    .map(String::from)
+                                    .or_else(|| status.map(|s| s.as_str().to_string()))
+                                    .unwrap_or_else(|| "no status returned".to_string());
+                                self.record_failure(&mut report, email, reason)?;
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    let e = e.into_service_error();
+                    match &e {
+                        SendBulkEmailError::TooManyRequestsException(_) => {
+                            for (email, attempts) in chunk {
+                                if attempts + 1 < MAX_SEND_ATTEMPTS {
+                                    queue.push_back((email, attempts + 1));
+                                } else {
+                                    self.record_failure(&mut report, email, e.to_string())?;
+                                }
+                            }
+                        }
+                        // Nothing else will get through either, so stop here.
+                        SendBulkEmailError::AccountSuspendedException(_)
+                        | SendBulkEmailError::SendingPausedException(_)
+                        | SendBulkEmailError::MailFromDomainNotVerifiedException(_) => {
+                            let remaining: Vec<_> = queue.drain(..).collect();
+                            for (email, _) in chunk.into_iter().chain(remaining) {
+                                self.record_failure(&mut report, email, e.to_string())?;
+                            }
+                        }
+                        _ => {
+                            for (email, _) in chunk {
+                                self.record_failure(&mut report, email, e.to_string())?;
+                            }
+                        }
                     }
                 }
-                Err(e) => return Err(anyhow!("Error sending newsletter to {}: {}", email, e)),
             }
-            // snippet-end:[sesv2.rust.send-email.template]
         }
 
-        Ok(())
+        write!(self.stdout, "{report}")?;
+        Ok(report)
     }
 
-    /// Monitors the sending activity and provides insights.
-    pub async fn monitor(&mut self) -> Result<()> {
-        // Check if the user wants to review the monitoring dashboard
+    /// Where a recipient unsubscribes: the configured unsubscribe URL, or an email to the
+    /// sender when there isn't one.
+    fn unsubscribe_url(&self, email: &str) -> String {
+        match &self.unsubscribe_url {
+            Some(url) => url.replace("{email}", &percent_encode(email)),
+            None => format!(
+                "mailto:{}?subject=Unsubscribe%20{}",
+                self.verified_email,
+                percent_encode(email)
+            ),
+        }
+    }
+
+    fn record_failure(
+        &mut self,
+        report: &mut DeliveryReport,
+        email: String,
+        reason: String,
+    ) -> Result<()> {
         writeln!(
             self.stdout,
-            "Do you want to review the monitoring dashboard? (y/n): "
+            "Failed to send newsletter to {}: {}",
+            email, reason
         )?;
-        self.stdout.flush().unwrap();
-        let mut response = String::new();
-        self.stdin.read_line(&mut response).unwrap();
-
-        if response.trim().eq_ignore_ascii_case("y") {
-            // Open the SES monitoring dashboard in the default browser
-            open::that("https://console.aws.amazon.com/ses/home#/account")?;
-
-            writeln!(
-                self.stdout,
-                "The SES monitoring dashboard has been opened in your default browser."
-            )?;
-            writeln!(
-                self.stdout,
-                "Review the sending activity, open and click rates, bounces, complaints, and more."
-            )?;
+        report.failed.push(Failure { email, reason });
+        Ok(())
+    }
+
+    /// The addresses on the account suppression list, in lowercase.
+    async fn suppressed_destinations(&self) -> Result<HashSet<String>> {
+        let mut suppressed = HashSet::new();
+        let mut next_token = None;
+        loop {
+            let output = self
+                .client
+                .list_suppressed_destinations()
+                .set_next_token(next_token)
+                .send()
+                .await
+                .map_err(|e| anyhow!("Error listing suppressed destinations: {}", e))?;
+            suppressed.extend(
+                output
+                    .suppressed_destination_summaries()
+                    .iter()
+                    .map(|summary| summary.email_address().to_lowercase()),
+            );
+            next_token = output.next_token;
+            if next_token.is_none() {
+                break;
+            }
+        }
+        Ok(suppressed)
+    }
+
+    /// Monitors the sending activity and provides insights.
+    pub async fn monitor(&mut self) -> Result<()> {
+        // Check if the user wants to review the sending statistics
+        if self.prompter.confirm(
+            "review_dashboard",
+            "Do you want to review your sending statistics?",
+            false,
+        )? {
+            match &self.sending_monitor {
+                Some(monitor) => {
+                    let report = monitor.report().await?;
+                    write!(self.stdout, "{report}")?;
+                    if let Some(path) = &self.stats_json {
+                        std::fs::write(path, report.to_json()?).map_err(|e| {
+                            anyhow!("Error writing sending statistics to {path:?}: {e}")
+                        })?;
+                        writeln!(self.stdout, "Sending statistics saved to {path:?}.")?;
+                    }
+                }
+                None => writeln!(self.stdout, "No sending monitor is configured.")?,
+            }
         } else {
-            writeln!(self.stdout, "Skipping the monitoring dashboard review.")?;
+            writeln!(self.stdout, "Skipping the sending statistics review.")?;
         }
 
-        writeln!(self.stdout, "Press any key to continue.")?;
-        self.stdout.flush().unwrap();
-        let mut response = String::new();
-        self.stdin.read_line(&mut response).unwrap();
+        self.prompter.pause("Press Enter to continue.")?;
 
         Ok(())
     }
@@ -373,15 +567,11 @@ impl<'a> SESWorkflow<'a> {
         // snippet-end:[sesv2.rust.delete-email-template]
 
         // Delete the email identity
-        writeln!(
-            self.stdout,
-            "Do you want to delete the verified email identity? (y/n): "
-        )?;
-        self.stdout.flush().unwrap();
-        let mut response = String::new();
-        self.stdin.read_line(&mut response).unwrap();
-
-        if response.trim().eq_ignore_ascii_case("y") {
+        if self.prompter.confirm(
+            "delete_identity",
+            "Do you want to delete the verified email identity?",
+            false,
+        )? {
             // snippet-start:[sesv2.rust.delete-email-identity]
             match self
                 .client
@@ -416,4 +606,56 @@ impl<'a> SESWorkflow<'a> {
     pub fn set_verified_email(&mut self, verified_email: String) {
         self.verified_email = verified_email;
     }
+
+    pub fn set_sending_monitor(&mut self, sending_monitor: SendingMonitor) {
+        self.sending_monitor = Some(sending_monitor);
+    }
+
+    /// Also save the sending statistics as JSON, for dashboards.
+    pub fn set_stats_json(&mut self, stats_json: PathBuf) {
+        self.stats_json = Some(stats_json);
+    }
+
+    /// The page subscribers unsubscribe on, linked from the newsletter. `{email}` in it is
+    /// replaced by the subscriber's address. Without one, the link emails the sender.
+    pub fn set_unsubscribe_url(&mut self, unsubscribe_url: String) {
+        self.unsubscribe_url = Some(unsubscribe_url);
+    }
+
+    pub fn set_personalization(&mut self, personalization: Personalization) {
+        self.personalization = personalization;
+    }
+
+    /// How long to wait before the first retry of a throttled send. Each retry waits twice as
+    /// long as the one before.
+    pub fn set_retry_delay(&mut self, retry_delay: Duration) {
+        self.retry_delay = retry_delay;
+    }
+}
+
+/// Whether a contact has unsubscribed from the whole list, or opted out of the coupons topic,
+/// either themselves or by the topic's default.
+fn unsubscribed(contact: &Contact) -> bool {
+    contact.unsubscribe_all()
+        || contact
+            .topic_preferences()
+            .iter()
+            .chain(contact.topic_default_preferences())
+            .find(|preference| preference.topic_name() == TOPIC_NAME)
+            .is_some_and(|preference| {
+                preference.subscription_status() == &SubscriptionStatus::OptOut
+            })
+}
+
+/// Percent-encodes everything but unreserved characters, for a URL query or path.
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
 }
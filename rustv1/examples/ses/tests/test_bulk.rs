@@ -0,0 +1,37 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use ses_code_examples::bulk::Personalization;
+
+#[test]
+fn test_personalization_overrides_default_data() -> Result<()> {
+    let personalization = Personalization::from_csv(
+        "email,first_name,discount\nAna@Example.com,Ana,30%\nbo@example.com,Bo,\n".as_bytes(),
+    )?;
+    assert_eq!(personalization.len(), 2);
+
+    let default_data = r#"{"coupons":[],"discount":"20%"}"#;
+    let ana: Value =
+        serde_json::from_str(&personalization.template_data("ana@example.com", default_data)?)?;
+    assert_eq!(ana["first_name"], "Ana");
+    assert_eq!(ana["discount"], "30%");
+    assert_eq!(ana["coupons"], json!([]));
+
+    // Empty cells keep the default.
+    let bo: Value =
+        serde_json::from_str(&personalization.template_data("bo@example.com", default_data)?)?;
+    assert_eq!(bo["discount"], "20%");
+
+    let unknown: Value =
+        serde_json::from_str(&personalization.template_data("cy@example.com", default_data)?)?;
+    assert_eq!(unknown, json!({"coupons": [], "discount": "20%"}));
+
+    Ok(())
+}
+
+#[test]
+fn test_personalization_requires_email_column() {
+    assert!(Personalization::from_csv("name\nAna\n".as_bytes()).is_err());
+}
@@ -0,0 +1,114 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use aws_sdk_ses::operation::get_send_statistics::GetSendStatisticsOutput;
+use aws_sdk_ses::primitives::DateTime;
+use aws_sdk_ses::types::SendDataPoint;
+use aws_sdk_sesv2::operation::get_account::GetAccountOutput;
+use aws_sdk_sesv2::types::SendQuota;
+use aws_smithy_mocks_experimental::{mock, mock_client, RuleMode};
+use ses_code_examples::monitor::{
+    AlertKind, SendingMonitor, SendingQuota, SendingReport, Thresholds,
+};
+use std::time::Duration;
+
+const DAY: i64 = 24 * 60 * 60;
+
+fn data_point(secs: i64, attempts: i64, bounces: i64, complaints: i64) -> SendDataPoint {
+    SendDataPoint::builder()
+        .timestamp(DateTime::from_secs(secs))
+        .delivery_attempts(attempts)
+        .bounces(bounces)
+        .complaints(complaints)
+        .rejects(0)
+        .build()
+}
+
+#[test]
+fn test_report_groups_periods_and_alerts() -> Result<()> {
+    // Two points on the first day, one on the second, out of order.
+    let points = vec![
+        data_point(DAY + 900, 100, 20, 0),
+        data_point(900, 100, 1, 0),
+        data_point(1800, 100, 1, 1),
+    ];
+
+    let report = SendingReport::new(
+        SendingQuota::default(),
+        Thresholds::default(),
+        &points,
+        Duration::from_secs(DAY as u64),
+    )?;
+
+    assert_eq!(report.periods.len(), 2);
+    assert_eq!(report.periods[0].start, "1970-01-01T00:00:00Z");
+    assert_eq!(report.periods[0].delivery_attempts, 200);
+    assert_eq!(report.periods[1].bounce_rate(), 0.2);
+    assert_eq!(report.total.delivery_attempts, 300);
+
+    // Day one is over the complaint threshold, day two over the bounce threshold, and the
+    // whole window over both.
+    let alerts: Vec<_> = report
+        .alerts
+        .iter()
+        .map(|alert| (alert.kind, alert.period.is_some()))
+        .collect();
+    assert_eq!(
+        alerts,
+        vec![
+            (AlertKind::ComplaintRate, true),
+            (AlertKind::BounceRate, true),
+            (AlertKind::BounceRate, false),
+            (AlertKind::ComplaintRate, false),
+        ]
+    );
+
+    let json: serde_json::Value = serde_json::from_str(&report.to_json()?)?;
+    assert_eq!(json["alerts"][0]["kind"], "complaint_rate");
+    assert_eq!(json["total"]["bounces"], 22);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_monitor_report() -> Result<()> {
+    let mock_get_account = mock!(aws_sdk_sesv2::Client::get_account).then_output(|| {
+        GetAccountOutput::builder()
+            .send_quota(
+                SendQuota::builder()
+                    .max24_hour_send(50000.0)
+                    .max_send_rate(14.0)
+                    .sent_last24_hours(120.0)
+                    .build(),
+            )
+            .enforcement_status("HEALTHY")
+            .build()
+    });
+    let mock_get_send_statistics =
+        mock!(aws_sdk_ses::Client::get_send_statistics).then_output(|| {
+            GetSendStatisticsOutput::builder()
+                .send_data_points(data_point(900, 120, 0, 0))
+                .build()
+        });
+
+    let sesv2 = mock_client!(aws_sdk_sesv2, RuleMode::Sequential, &[&mock_get_account]);
+    let ses = mock_client!(
+        aws_sdk_ses,
+        RuleMode::Sequential,
+        &[&mock_get_send_statistics]
+    );
+
+    let report = SendingMonitor::new(sesv2, ses).report().await?;
+
+    assert_eq!(report.quota.sent_last_24_hours, 120.0);
+    assert_eq!(report.quota.enforcement_status.as_deref(), Some("HEALTHY"));
+    assert_eq!(report.total.delivery_attempts, 120);
+    assert!(report.alerts.is_empty());
+    assert!(report.events.is_empty());
+    let output = report.to_string();
+    assert!(output.contains("Sending quota: 120 of 50000 emails sent in the last 24 hours"));
+    assert!(output.contains("No rates are over their thresholds."));
+
+    Ok(())
+}
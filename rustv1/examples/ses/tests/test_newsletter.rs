@@ -1,8 +1,6 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::io::Cursor;
-
 use anyhow::Result;
 use aws_sdk_sesv2::operation::create_contact::{CreateContactError, CreateContactOutput};
 use aws_sdk_sesv2::operation::create_contact_list::{
@@ -20,20 +18,28 @@ use aws_sdk_sesv2::operation::delete_email_template::{
     DeleteEmailTemplateError, DeleteEmailTemplateOutput,
 };
 use aws_sdk_sesv2::operation::list_contacts::{ListContactsError, ListContactsOutput};
+use aws_sdk_sesv2::operation::list_suppressed_destinations::ListSuppressedDestinationsOutput;
+use aws_sdk_sesv2::operation::send_bulk_email::{SendBulkEmailError, SendBulkEmailOutput};
 use aws_sdk_sesv2::operation::send_email::{SendEmailError, SendEmailOutput};
+use aws_sdk_sesv2::primitives::DateTime;
 use aws_sdk_sesv2::types::error::{
     AccountSuspendedException, AlreadyExistsException, LimitExceededException,
     MailFromDomainNotVerifiedException, MessageRejected, NotFoundException, SendingPausedException,
 };
-use aws_sdk_sesv2::types::{Contact, IdentityType};
+use aws_sdk_sesv2::types::{
+    BulkEmailEntryResult, BulkEmailStatus, Contact, IdentityType, SubscriptionStatus,
+    SuppressedDestinationSummary, SuppressionListReason, TopicPreference,
+};
 use aws_sdk_sesv2::Client;
 use aws_smithy_mocks_experimental::{mock, mock_client, RuleMode};
+use sdk_examples_prompter::ScriptedPrompter;
 use ses_code_examples::newsletter::SESWorkflow;
+use std::time::Duration;
 
 // Test prepare_application method
 #[tokio::test]
 async fn test_prepare_application_success() -> Result<()> {
-    let mut stdin = Cursor::new("test@example.com\n".as_bytes());
+    let prompter = ScriptedPrompter::new().answer("verified_email", "test@example.com");
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
@@ -64,7 +70,7 @@ async fn test_prepare_application_success() -> Result<()> {
         ]
     );
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
 
     // Run the method
     workflow.prepare_application().await?;
@@ -80,7 +86,7 @@ async fn test_prepare_application_success() -> Result<()> {
 
 #[tokio::test]
 async fn test_prepare_application_error_identity_already_exists() -> Result<()> {
-    let mut stdin = Cursor::new("test@example.com\n".as_bytes());
+    let prompter = ScriptedPrompter::new().answer("verified_email", "test@example.com");
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
@@ -110,7 +116,7 @@ async fn test_prepare_application_error_identity_already_exists() -> Result<()>
         ]
     );
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
 
     // Run the method
     workflow.prepare_application().await?;
@@ -126,7 +132,7 @@ async fn test_prepare_application_error_identity_already_exists() -> Result<()>
 
 #[tokio::test]
 async fn test_prepare_application_error_identity_not_found() -> Result<()> {
-    let mut stdin = Cursor::new("test@example.com\n".as_bytes());
+    let prompter = ScriptedPrompter::new().answer("verified_email", "test@example.com");
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
@@ -138,7 +144,7 @@ async fn test_prepare_application_error_identity_not_found() -> Result<()> {
 
     let client = mock_client!(aws_sdk_sesv2, RuleMode::Sequential, &[&mock_email_identity]);
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
 
     // Run the method
     let result = workflow.prepare_application().await;
@@ -152,7 +158,7 @@ async fn test_prepare_application_error_identity_not_found() -> Result<()> {
 
 #[tokio::test]
 async fn test_prepare_application_error_identity_limit_exceeded() -> Result<()> {
-    let mut stdin = Cursor::new("test@example.com\n".as_bytes());
+    let prompter = ScriptedPrompter::new().answer("verified_email", "test@example.com");
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
@@ -166,7 +172,7 @@ async fn test_prepare_application_error_identity_limit_exceeded() -> Result<()>
 
     let client = mock_client!(aws_sdk_sesv2, RuleMode::Sequential, &[&mock_email_identity]);
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
 
     // Run the method
     let result = workflow.prepare_application().await;
@@ -180,7 +186,7 @@ async fn test_prepare_application_error_identity_limit_exceeded() -> Result<()>
 
 #[tokio::test]
 async fn test_prepare_application_error_contact_list_limit_exceeded() -> Result<()> {
-    let mut stdin = Cursor::new("test@example.com\n".as_bytes());
+    let prompter = ScriptedPrompter::new().answer("verified_email", "test@example.com");
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
@@ -207,7 +213,7 @@ async fn test_prepare_application_error_contact_list_limit_exceeded() -> Result<
         &[&mock_email_identity, &mock_contact_list]
     );
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
 
     // Run the method
     let result = workflow.prepare_application().await;
@@ -225,7 +231,7 @@ async fn test_prepare_application_error_contact_list_limit_exceeded() -> Result<
 
 #[tokio::test]
 async fn test_gather_subscriber_emails_success() -> Result<()> {
-    let mut stdin = Cursor::new("user@example.com\n".as_bytes());
+    let prompter = ScriptedPrompter::new().answer("base_email", "user@example.com");
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
@@ -287,7 +293,7 @@ async fn test_gather_subscriber_emails_success() -> Result<()> {
         ]
     );
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
 
     // Run the method
     workflow.gather_subscriber_emails().await?;
@@ -312,7 +318,7 @@ async fn test_gather_subscriber_emails_success() -> Result<()> {
 
 #[tokio::test]
 async fn test_gather_subscriber_emails_error_contact_already_exists() -> Result<()> {
-    let mut stdin = Cursor::new("user@example.com\n".as_bytes());
+    let prompter = ScriptedPrompter::new().answer("base_email", "user@example.com");
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
@@ -376,7 +382,7 @@ async fn test_gather_subscriber_emails_error_contact_already_exists() -> Result<
         ]
     );
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
 
     // Run the method
     workflow.gather_subscriber_emails().await?;
@@ -394,7 +400,7 @@ async fn test_gather_subscriber_emails_error_contact_already_exists() -> Result<
 
 #[tokio::test]
 async fn test_gather_subscriber_emails_error_send_email() -> Result<()> {
-    let mut stdin = Cursor::new("user@example.com\n".as_bytes());
+    let prompter = ScriptedPrompter::new().answer("base_email", "user@example.com");
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
@@ -421,7 +427,7 @@ async fn test_gather_subscriber_emails_error_send_email() -> Result<()> {
         &[&mock_create_contact_1, &mock_send_email_1,]
     );
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
 
     // Run the method
     let result = workflow.gather_subscriber_emails().await;
@@ -434,55 +440,102 @@ async fn test_gather_subscriber_emails_error_send_email() -> Result<()> {
     Ok(())
 }
 
+fn mock_list_contacts(emails: &'static [&'static str]) -> aws_smithy_mocks_experimental::Rule {
+    mock!(Client::list_contacts)
+        .match_requests(|req| req.contact_list_name() == Some("weekly-coupons-newsletter"))
+        .then_output(|| {
+            ListContactsOutput::builder()
+                .set_contacts(Some(
+                    emails
+                        .iter()
+                        .map(|email| Contact::builder().email_address(*email).build())
+                        .collect(),
+                ))
+                .build()
+        })
+}
+
+fn mock_suppressed_destinations(
+    emails: &'static [&'static str],
+) -> aws_smithy_mocks_experimental::Rule {
+    mock!(Client::list_suppressed_destinations).then_output(|| {
+        ListSuppressedDestinationsOutput::builder()
+            .set_suppressed_destination_summaries(Some(
+                emails
+                    .iter()
+                    .map(|email| {
+                        SuppressedDestinationSummary::builder()
+                            .email_address(*email)
+                            .reason(SuppressionListReason::Bounce)
+                            .last_update_time(DateTime::from_secs(0))
+                            .build()
+                            .unwrap()
+                    })
+                    .collect(),
+            ))
+            .build()
+    })
+}
+
+fn bulk_result(status: BulkEmailStatus, message_id: Option<&str>) -> BulkEmailEntryResult {
+    BulkEmailEntryResult::builder()
+        .status(status)
+        .set_message_id(message_id.map(String::from))
+        .build()
+}
+
 #[tokio::test]
 async fn test_send_coupon_newsletter_success() -> Result<()> {
-    let mut stdin = Cursor::new("".as_bytes());
+    let prompter = ScriptedPrompter::new();
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
-    let mock_list_contacts = mock!(Client::list_contacts)
-        .match_requests(|req| req.contact_list_name() == Some("weekly-coupons-newsletter"))
-        .then_output(|| {
-            ListContactsOutput::builder()
-                .contacts(Contact::builder().email_address("user@example.com").build())
-                .build()
-        });
+    let mock_list_contacts = mock_list_contacts(&["user@example.com"]);
+    let mock_suppressed = mock_suppressed_destinations(&[]);
 
-    let mock_send_email = mock!(Client::send_email)
+    let mock_send_bulk_email = mock!(Client::send_bulk_email)
         .match_requests(|req| {
-            req.destination()
+            req.bulk_email_entries()[0]
+                .destination()
                 .unwrap()
                 .to_addresses()
                 .contains(&"user@example.com".into())
         })
         .then_output(|| {
-            SendEmailOutput::builder()
-                .message_id("newsletter-email")
+            SendBulkEmailOutput::builder()
+                .bulk_email_entry_results(bulk_result(
+                    BulkEmailStatus::Success,
+                    Some("newsletter-email"),
+                ))
                 .build()
+                .unwrap()
         });
 
     let client = mock_client!(
         aws_sdk_sesv2,
         RuleMode::Sequential,
-        &[&mock_list_contacts, &mock_send_email,]
+        &[&mock_list_contacts, &mock_suppressed, &mock_send_bulk_email,]
     );
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
     workflow.set_verified_email("sender@example.com".into());
 
     // Run the method
-    workflow.send_coupon_newsletter().await?;
+    let report = workflow.send_coupon_newsletter().await?;
 
     // Assert the output
+    assert!(report.is_complete());
+    assert_eq!(report.sent.len(), 1);
     let output = String::from_utf8(stdout)?;
     assert!(output.contains("Newsletter sent to user@example.com with message ID newsletter-email"));
+    assert!(output.contains("Newsletter delivery: 1 sent, 0 failed, 0 suppressed"));
 
     Ok(())
 }
 
 #[tokio::test]
 async fn test_send_coupon_newsletter_error_template_already_exists() -> Result<()> {
-    let mut stdin = Cursor::new("".as_bytes());
+    let prompter = ScriptedPrompter::new();
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
@@ -492,7 +545,7 @@ async fn test_send_coupon_newsletter_error_template_already_exists() -> Result<(
 
     let client = mock_client!(aws_sdk_sesv2, RuleMode::Sequential, &[&mock_list_contacts]);
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
     workflow.set_verified_email("sender@example.com".into());
 
     // Run the method
@@ -509,51 +562,206 @@ async fn test_send_coupon_newsletter_error_template_already_exists() -> Result<(
 
 #[tokio::test]
 async fn test_send_coupon_newsletter_error_account_suspended() -> Result<()> {
-    let mut stdin = Cursor::new("".as_bytes());
+    let prompter = ScriptedPrompter::new();
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
-    let mock_list_contacts = mock!(Client::list_contacts)
-        .match_requests(|req| req.contact_list_name() == Some("weekly-coupons-newsletter"))
-        .then_output(|| {
-            ListContactsOutput::builder()
-                .contacts(Contact::builder().email_address("user@example.com").build())
-                .build()
+    let mock_list_contacts = mock_list_contacts(&["user@example.com"]);
+    let mock_suppressed = mock_suppressed_destinations(&[]);
+
+    let mock_send_bulk_email = mock!(Client::send_bulk_email)
+        .match_requests(|req| req.bulk_email_entries().len() == 1)
+        .then_error(|| {
+            SendBulkEmailError::AccountSuspendedException(
+                AccountSuspendedException::builder().build(),
+            )
+        });
+
+    let client = mock_client!(
+        aws_sdk_sesv2,
+        RuleMode::Sequential,
+        &[&mock_list_contacts, &mock_suppressed, &mock_send_bulk_email,]
+    );
+
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
+    workflow.set_verified_email("sender@example.com".into());
+
+    // Run the method
+    let report = workflow.send_coupon_newsletter().await?;
+
+    // Check that the failure is reported instead of propagated
+    assert!(report.sent.is_empty());
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].email, "user@example.com");
+    let output = String::from_utf8(stdout)?;
+    assert!(output.contains("Failed to send newsletter to user@example.com:"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_coupon_newsletter_error_mail_from_domain_not_verified() -> Result<()> {
+    let prompter = ScriptedPrompter::new();
+    let mut stdout = Vec::<u8>::new();
+
+    // Mock AWS SDK calls
+    let mock_list_contacts = mock_list_contacts(&["user@example.com"]);
+    let mock_suppressed = mock_suppressed_destinations(&[]);
+
+    let mock_send_bulk_email = mock!(Client::send_bulk_email)
+        .match_requests(|req| req.bulk_email_entries().len() == 1)
+        .then_error(|| {
+            SendBulkEmailError::MailFromDomainNotVerifiedException(
+                MailFromDomainNotVerifiedException::builder().build(),
+            )
         });
 
-    let mock_send_email = mock!(Client::send_email)
+    let client = mock_client!(
+        aws_sdk_sesv2,
+        RuleMode::Sequential,
+        &[&mock_list_contacts, &mock_suppressed, &mock_send_bulk_email,]
+    );
+
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
+    workflow.set_verified_email("sender@example.com".into());
+
+    // Run the method
+    let report = workflow.send_coupon_newsletter().await?;
+
+    // Check that the failure is reported instead of propagated
+    assert!(report.sent.is_empty());
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].email, "user@example.com");
+    let output = String::from_utf8(stdout)?;
+    assert!(output.contains("Failed to send newsletter to user@example.com:"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_coupon_newsletter_error_message_rejected() -> Result<()> {
+    let prompter = ScriptedPrompter::new();
+    let mut stdout = Vec::<u8>::new();
+
+    // Mock AWS SDK calls
+    let mock_list_contacts = mock_list_contacts(&["user@example.com"]);
+    let mock_suppressed = mock_suppressed_destinations(&[]);
+
+    let mock_send_bulk_email = mock!(Client::send_bulk_email)
+        .match_requests(|req| req.bulk_email_entries().len() == 1)
+        .then_error(|| SendBulkEmailError::MessageRejected(MessageRejected::builder().build()));
+
+    let client = mock_client!(
+        aws_sdk_sesv2,
+        RuleMode::Sequential,
+        &[&mock_list_contacts, &mock_suppressed, &mock_send_bulk_email,]
+    );
+
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
+    workflow.set_verified_email("sender@example.com".into());
+
+    // Run the method
+    let report = workflow.send_coupon_newsletter().await?;
+
+    // Check that the failure is reported instead of propagated
+    assert!(report.sent.is_empty());
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].email, "user@example.com");
+    let output = String::from_utf8(stdout)?;
+    assert!(output.contains("Failed to send newsletter to user@example.com:"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_coupon_newsletter_error_sending_paused() -> Result<()> {
+    let prompter = ScriptedPrompter::new();
+    let mut stdout = Vec::<u8>::new();
+
+    // Mock AWS SDK calls
+    let mock_list_contacts = mock_list_contacts(&["user@example.com"]);
+    let mock_suppressed = mock_suppressed_destinations(&[]);
+
+    let mock_send_bulk_email = mock!(Client::send_bulk_email)
+        .match_requests(|req| req.bulk_email_entries().len() == 1)
+        .then_error(|| {
+            SendBulkEmailError::SendingPausedException(SendingPausedException::builder().build())
+        });
+
+    let client = mock_client!(
+        aws_sdk_sesv2,
+        RuleMode::Sequential,
+        &[&mock_list_contacts, &mock_suppressed, &mock_send_bulk_email,]
+    );
+
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
+    workflow.set_verified_email("sender@example.com".into());
+
+    // Run the method
+    let report = workflow.send_coupon_newsletter().await?;
+
+    // Check that the failure is reported instead of propagated
+    assert!(report.sent.is_empty());
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].email, "user@example.com");
+    let output = String::from_utf8(stdout)?;
+    assert!(output.contains("Failed to send newsletter to user@example.com:"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_coupon_newsletter_skips_suppressed() -> Result<()> {
+    let prompter = ScriptedPrompter::new();
+    let mut stdout = Vec::<u8>::new();
+
+    // Mock AWS SDK calls
+    let mock_list_contacts = mock_list_contacts(&["user@example.com", "bounced@example.com"]);
+    let mock_suppressed = mock_suppressed_destinations(&["Bounced@example.com"]);
+
+    let mock_send_bulk_email = mock!(Client::send_bulk_email)
         .match_requests(|req| {
-            req.destination()
-                .unwrap()
-                .to_addresses()
-                .contains(&"user@example.com".into())
+            req.bulk_email_entries().len() == 1
+                && req.bulk_email_entries()[0]
+                    .destination()
+                    .unwrap()
+                    .to_addresses()
+                    .contains(&"user@example.com".into())
         })
-        .then_error(|| {
-            SendEmailError::AccountSuspendedException(AccountSuspendedException::builder().build())
+        .then_output(|| {
+            SendBulkEmailOutput::builder()
+                .bulk_email_entry_results(bulk_result(
+                    BulkEmailStatus::Success,
+                    Some("newsletter-email"),
+                ))
+                .build()
+                .unwrap()
         });
 
     let client = mock_client!(
         aws_sdk_sesv2,
         RuleMode::Sequential,
-        &[&mock_list_contacts, &mock_send_email,]
+        &[&mock_list_contacts, &mock_suppressed, &mock_send_bulk_email,]
     );
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
     workflow.set_verified_email("sender@example.com".into());
 
     // Run the method
-    let result = workflow.send_coupon_newsletter().await;
+    let report = workflow.send_coupon_newsletter().await?;
 
-    // Check that the error is propagated
-    assert!(result.is_err());
-    assert!(format!("{result:?}").contains("Error sending newsletter to user@example.com:"));
+    // Assert the output
+    assert_eq!(report.sent.len(), 1);
+    assert_eq!(report.suppressed, vec!["bounced@example.com".to_string()]);
+    let output = String::from_utf8(stdout)?;
+    assert!(output.contains("Skipping suppressed address bounced@example.com"));
 
     Ok(())
 }
 
 #[tokio::test]
-async fn test_send_coupon_newsletter_error_mail_from_domain_not_verified() -> Result<()> {
-    let mut stdin = Cursor::new("".as_bytes());
+async fn test_send_coupon_newsletter_skips_unsubscribed() -> Result<()> {
+    let prompter = ScriptedPrompter::new();
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
@@ -562,129 +770,281 @@ async fn test_send_coupon_newsletter_error_mail_from_domain_not_verified() -> Re
         .then_output(|| {
             ListContactsOutput::builder()
                 .contacts(Contact::builder().email_address("user@example.com").build())
+                .contacts(
+                    Contact::builder()
+                        .email_address("gone@example.com")
+                        .unsubscribe_all(true)
+                        .build(),
+                )
                 .build()
         });
+    let mock_suppressed = mock_suppressed_destinations(&[]);
 
-    let mock_send_email = mock!(Client::send_email)
-        .match_requests(|req| {
-            req.destination()
+    let mock_send_bulk_email = mock!(Client::send_bulk_email)
+        .match_requests(|req| req.bulk_email_entries().len() == 1)
+        .then_output(|| {
+            SendBulkEmailOutput::builder()
+                .bulk_email_entry_results(bulk_result(
+                    BulkEmailStatus::Success,
+                    Some("newsletter-email"),
+                ))
+                .build()
                 .unwrap()
-                .to_addresses()
-                .contains(&"user@example.com".into())
-        })
-        .then_error(|| {
-            SendEmailError::MailFromDomainNotVerifiedException(
-                MailFromDomainNotVerifiedException::builder().build(),
-            )
         });
 
     let client = mock_client!(
         aws_sdk_sesv2,
         RuleMode::Sequential,
-        &[&mock_list_contacts, &mock_send_email,]
+        &[&mock_list_contacts, &mock_suppressed, &mock_send_bulk_email,]
     );
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
     workflow.set_verified_email("sender@example.com".into());
 
     // Run the method
-    let result = workflow.send_coupon_newsletter().await;
+    let report = workflow.send_coupon_newsletter().await?;
 
-    // Check that the error is propagated
-    assert!(result.is_err());
-    assert!(format!("{result:?}").contains("Error sending newsletter to user@example.com:"));
+    // Assert the output
+    assert_eq!(report.sent.len(), 1);
+    assert_eq!(report.suppressed, vec!["gone@example.com".to_string()]);
+    let output = String::from_utf8(stdout)?;
+    assert!(output.contains("Skipping unsubscribed contact gone@example.com"));
 
     Ok(())
 }
 
 #[tokio::test]
-async fn test_send_coupon_newsletter_error_message_rejected() -> Result<()> {
-    let mut stdin = Cursor::new("".as_bytes());
+async fn test_send_coupon_newsletter_skips_topic_opt_out() -> Result<()> {
+    let prompter = ScriptedPrompter::new();
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
+    let topic_preference = |status| {
+        TopicPreference::builder()
+            .topic_name("coupons")
+            .subscription_status(status)
+            .build()
+            .unwrap()
+    };
     let mock_list_contacts = mock!(Client::list_contacts)
         .match_requests(|req| req.contact_list_name() == Some("weekly-coupons-newsletter"))
-        .then_output(|| {
+        .then_output(move || {
             ListContactsOutput::builder()
-                .contacts(Contact::builder().email_address("user@example.com").build())
+                .contacts(
+                    Contact::builder()
+                        .email_address("user@example.com")
+                        .topic_default_preferences(topic_preference(SubscriptionStatus::OptIn))
+                        .build(),
+                )
+                .contacts(
+                    Contact::builder()
+                        .email_address("opted-out@example.com")
+                        .topic_preferences(topic_preference(SubscriptionStatus::OptOut))
+                        .topic_default_preferences(topic_preference(SubscriptionStatus::OptIn))
+                        .build(),
+                )
                 .build()
         });
+    let mock_suppressed = mock_suppressed_destinations(&[]);
 
-    let mock_send_email = mock!(Client::send_email)
-        .match_requests(|req| {
-            req.destination()
+    let mock_send_bulk_email = mock!(Client::send_bulk_email)
+        .match_requests(|req| req.bulk_email_entries().len() == 1)
+        .then_output(|| {
+            SendBulkEmailOutput::builder()
+                .bulk_email_entry_results(bulk_result(
+                    BulkEmailStatus::Success,
+                    Some("newsletter-email"),
+                ))
+                .build()
                 .unwrap()
-                .to_addresses()
-                .contains(&"user@example.com".into())
+        });
+
+    let client = mock_client!(
+        aws_sdk_sesv2,
+        RuleMode::Sequential,
+        &[&mock_list_contacts, &mock_suppressed, &mock_send_bulk_email,]
+    );
+
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
+    workflow.set_verified_email("sender@example.com".into());
+
+    // Run the method
+    let report = workflow.send_coupon_newsletter().await?;
+
+    // Assert the output
+    assert_eq!(report.sent.len(), 1);
+    assert_eq!(report.suppressed, vec!["opted-out@example.com".to_string()]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_coupon_newsletter_unsubscribe_url() -> Result<()> {
+    let prompter = ScriptedPrompter::new();
+    let mut stdout = Vec::<u8>::new();
+
+    // Mock AWS SDK calls
+    let mock_list_contacts = mock_list_contacts(&["user+news@example.com"]);
+    let mock_suppressed = mock_suppressed_destinations(&[]);
+
+    let mock_send_bulk_email = mock!(Client::send_bulk_email)
+        .match_requests(|req| {
+            let data: serde_json::Value = serde_json::from_str(
+                req.bulk_email_entries()[0]
+                    .replacement_email_content()
+                    .and_then(|content| content.replacement_template())
+                    .and_then(|template| template.replacement_template_data())
+                    .unwrap(),
+            )
+            .unwrap();
+            data["amazonSESUnsubscribeUrl"]
+                == "https://example.com/unsubscribe?email=user%2Bnews%40example.com"
         })
-        .then_error(|| SendEmailError::MessageRejected(MessageRejected::builder().build()));
+        .then_output(|| {
+            SendBulkEmailOutput::builder()
+                .bulk_email_entry_results(bulk_result(
+                    BulkEmailStatus::Success,
+                    Some("newsletter-email"),
+                ))
+                .build()
+                .unwrap()
+        });
 
     let client = mock_client!(
         aws_sdk_sesv2,
         RuleMode::Sequential,
-        &[&mock_list_contacts, &mock_send_email,]
+        &[&mock_list_contacts, &mock_suppressed, &mock_send_bulk_email,]
     );
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
     workflow.set_verified_email("sender@example.com".into());
+    workflow.set_unsubscribe_url("https://example.com/unsubscribe?email={email}".into());
 
     // Run the method
-    let result = workflow.send_coupon_newsletter().await;
+    let report = workflow.send_coupon_newsletter().await?;
 
-    // Check that the error is propagated
-    assert!(result.is_err());
-    assert!(format!("{result:?}").contains("Error sending newsletter to user@example.com:"));
+    // Assert the output
+    assert_eq!(report.sent.len(), 1);
 
     Ok(())
 }
 
 #[tokio::test]
-async fn test_send_coupon_newsletter_error_sending_paused() -> Result<()> {
-    let mut stdin = Cursor::new("".as_bytes());
+async fn test_send_coupon_newsletter_missing_results_fail() -> Result<()> {
+    let prompter = ScriptedPrompter::new();
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
-    let mock_list_contacts = mock!(Client::list_contacts)
-        .match_requests(|req| req.contact_list_name() == Some("weekly-coupons-newsletter"))
+    let mock_list_contacts = mock_list_contacts(&["user@example.com", "other@example.com"]);
+    let mock_suppressed = mock_suppressed_destinations(&[]);
+
+    let mock_send_bulk_email = mock!(Client::send_bulk_email)
+        .match_requests(|req| req.bulk_email_entries().len() == 2)
         .then_output(|| {
-            ListContactsOutput::builder()
-                .contacts(Contact::builder().email_address("user@example.com").build())
+            SendBulkEmailOutput::builder()
+                .bulk_email_entry_results(bulk_result(
+                    BulkEmailStatus::Success,
+                    Some("newsletter-email"),
+                ))
                 .build()
+                .unwrap()
         });
 
-    let mock_send_email = mock!(Client::send_email)
-        .match_requests(|req| {
-            req.destination()
+    let client = mock_client!(
+        aws_sdk_sesv2,
+        RuleMode::Sequential,
+        &[&mock_list_contacts, &mock_suppressed, &mock_send_bulk_email,]
+    );
+
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
+    workflow.set_verified_email("sender@example.com".into());
+
+    // Run the method
+    let report = workflow.send_coupon_newsletter().await?;
+
+    // Assert the output
+    assert!(!report.is_complete());
+    assert_eq!(report.sent.len(), 1);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].email, "other@example.com");
+    assert_eq!(report.failed[0].reason, "no result returned");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_coupon_newsletter_retries_throttled() -> Result<()> {
+    let prompter = ScriptedPrompter::new();
+    let mut stdout = Vec::<u8>::new();
+
+    // Mock AWS SDK calls
+    let mock_list_contacts = mock_list_contacts(&["user@example.com", "other@example.com"]);
+    let mock_suppressed = mock_suppressed_destinations(&[]);
+
+    let mock_send_bulk_email_1 = mock!(Client::send_bulk_email)
+        .match_requests(|req| req.bulk_email_entries().len() == 2)
+        .then_output(|| {
+            SendBulkEmailOutput::builder()
+                .bulk_email_entry_results(bulk_result(
+                    BulkEmailStatus::Success,
+                    Some("newsletter-email-1"),
+                ))
+                .bulk_email_entry_results(bulk_result(BulkEmailStatus::AccountThrottled, None))
+                .build()
                 .unwrap()
-                .to_addresses()
-                .contains(&"user@example.com".into())
+        });
+
+    let mock_send_bulk_email_2 = mock!(Client::send_bulk_email)
+        .match_requests(|req| {
+            req.bulk_email_entries().len() == 1
+                && req.bulk_email_entries()[0]
+                    .destination()
+                    .unwrap()
+                    .to_addresses()
+                    .contains(&"other@example.com".into())
         })
-        .then_error(|| {
-            SendEmailError::SendingPausedException(SendingPausedException::builder().build())
+        .then_output(|| {
+            SendBulkEmailOutput::builder()
+                .bulk_email_entry_results(bulk_result(
+                    BulkEmailStatus::Success,
+                    Some("newsletter-email-2"),
+                ))
+                .build()
+                .unwrap()
         });
 
     let client = mock_client!(
         aws_sdk_sesv2,
         RuleMode::Sequential,
-        &[&mock_list_contacts, &mock_send_email,]
+        &[
+            &mock_list_contacts,
+            &mock_suppressed,
+            &mock_send_bulk_email_1,
+            &mock_send_bulk_email_2,
+        ]
     );
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
     workflow.set_verified_email("sender@example.com".into());
+    workflow.set_retry_delay(Duration::ZERO);
 
     // Run the method
-    let result = workflow.send_coupon_newsletter().await;
+    let report = workflow.send_coupon_newsletter().await?;
 
-    // Check that the error is propagated
-    assert!(result.is_err());
-    assert!(format!("{result:?}").contains("Error sending newsletter to user@example.com:"));
+    // Assert the output
+    assert!(report.is_complete());
+    assert_eq!(report.sent.len(), 2);
+    let output = String::from_utf8(stdout)?;
+    assert!(
+        output.contains("Newsletter sent to other@example.com with message ID newsletter-email-2")
+    );
 
     Ok(())
 }
+
 #[tokio::test]
 async fn test_cleanup_success() -> Result<()> {
-    let mut stdin = Cursor::new("n\n".as_bytes());
+    let prompter = ScriptedPrompter::new().answer("delete_identity", false);
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
@@ -702,7 +1062,7 @@ async fn test_cleanup_success() -> Result<()> {
         &[&mock_delete_contact_list, &mock_delete_email_template,]
     );
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
 
     // Run the method
     workflow.cleanup().await?;
@@ -718,7 +1078,7 @@ async fn test_cleanup_success() -> Result<()> {
 
 #[tokio::test]
 async fn test_cleanup_error_contact_list_not_found() -> Result<()> {
-    let mut stdin = Cursor::new("n\n".as_bytes());
+    let prompter = ScriptedPrompter::new().answer("delete_identity", false);
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
@@ -738,7 +1098,7 @@ async fn test_cleanup_error_contact_list_not_found() -> Result<()> {
         &[&mock_delete_contact_list, &mock_delete_email_template,]
     );
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
 
     // Run the method
     let result = workflow.cleanup().await;
@@ -752,7 +1112,7 @@ async fn test_cleanup_error_contact_list_not_found() -> Result<()> {
 
 #[tokio::test]
 async fn test_cleanup_error_template_not_found() -> Result<()> {
-    let mut stdin = Cursor::new("n\n".as_bytes());
+    let prompter = ScriptedPrompter::new().answer("delete_identity", false);
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
@@ -772,7 +1132,7 @@ async fn test_cleanup_error_template_not_found() -> Result<()> {
         &[&mock_delete_contact_list, &mock_delete_email_template,]
     );
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
 
     // Run the method
     let result = workflow.cleanup().await;
@@ -789,7 +1149,7 @@ async fn test_cleanup_error_template_not_found() -> Result<()> {
 
 #[tokio::test]
 async fn test_cleanup_error_identity_not_found() -> Result<()> {
-    let mut stdin = Cursor::new("y\n".as_bytes());
+    let prompter = ScriptedPrompter::new().answer("delete_identity", true);
     let mut stdout = Vec::<u8>::new();
 
     // Mock AWS SDK calls
@@ -817,7 +1177,7 @@ async fn test_cleanup_error_identity_not_found() -> Result<()> {
         ]
     );
 
-    let mut workflow = SESWorkflow::new(client, &mut stdin, &mut stdout);
+    let mut workflow = SESWorkflow::new(client, &prompter, &mut stdout);
     workflow.set_verified_email("test@example.com".into());
 
     // Run the method
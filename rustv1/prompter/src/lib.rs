@@ -0,0 +1,153 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Questions a scenario asks its user, behind one interface.
+//!
+//! Each prompt has a key, which names the answer, and a message, which is shown to a person.
+//! [TerminalPrompter] asks in the terminal. [ScriptedPrompter] answers from a JSON file or a
+//! list built in a test, by key. [DefaultPrompter] takes each prompt's default, so the
+//! scenario can run unattended.
+
+use std::{fmt::Display, path::PathBuf};
+
+mod scripted;
+mod terminal;
+
+pub use scripted::ScriptedPrompter;
+pub use terminal::TerminalPrompter;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PromptError {
+    /// Nothing answered the prompt with this key, and it has no default.
+    Unanswered(String),
+    /// The answer doesn't fit the prompt, like "maybe" to a yes or no question.
+    Invalid { key: String, answer: String },
+    /// The user cancelled the prompt.
+    Cancelled,
+    /// The prompt couldn't be shown or read.
+    Failed(String),
+}
+
+impl Display for PromptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PromptError::Unanswered(key) => write!(f, "No answer for {key}, and it has no default"),
+            PromptError::Invalid { key, answer } => write!(f, "{answer:?} doesn't answer {key}"),
+            PromptError::Cancelled => write!(f, "Prompt cancelled"),
+            PromptError::Failed(message) => write!(f, "Prompt failed: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for PromptError {}
+
+/// Asks questions. `key` identifies the question to scripted answers, and `message` is shown
+/// to a person.
+pub trait Prompter: Send + Sync {
+    fn confirm(&self, key: &str, message: &str, default: bool) -> Result<bool, PromptError>;
+
+    /// The index of the chosen option. The first option is the default.
+    fn select(&self, key: &str, message: &str, options: &[String]) -> Result<usize, PromptError>;
+
+    fn text(&self, key: &str, message: &str, default: Option<&str>) -> Result<String, PromptError>;
+
+    /// Like text, but the answer can't be blank. The terminal asks again; other prompters
+    /// return [PromptError::Invalid].
+    fn required_text(
+        &self,
+        key: &str,
+        message: &str,
+        default: Option<&str>,
+    ) -> Result<String, PromptError> {
+        let answer = self.text(key, message, default)?;
+        if answer.trim().is_empty() {
+            Err(PromptError::Invalid {
+                key: key.to_string(),
+                answer: format!("{answer:?}"),
+            })
+        } else {
+            Ok(answer)
+        }
+    }
+
+    /// Like text, but not echoed. Secrets have no default.
+    fn secret(&self, key: &str, message: &str) -> Result<String, PromptError>;
+
+    /// Wait for the user before going on.
+    fn pause(&self, message: &str) -> Result<(), PromptError> {
+        self.text("continue", message, Some("")).map(|_| ())
+    }
+}
+
+impl dyn Prompter + '_ {
+    /// Select one of `items`, showing each with its Display impl.
+    pub fn choose<T: Display>(
+        &self,
+        key: &str,
+        message: &str,
+        items: Vec<T>,
+    ) -> Result<T, PromptError> {
+        let options: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+        let index = self.select(key, message, &options)?;
+        items
+            .into_iter()
+            .nth(index)
+            .ok_or_else(|| PromptError::Unanswered(key.to_string()))
+    }
+}
+
+/// Takes every prompt's default without asking.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultPrompter;
+
+impl Prompter for DefaultPrompter {
+    fn confirm(&self, _key: &str, _message: &str, default: bool) -> Result<bool, PromptError> {
+        Ok(default)
+    }
+
+    fn select(&self, key: &str, _message: &str, options: &[String]) -> Result<usize, PromptError> {
+        if options.is_empty() {
+            Err(PromptError::Unanswered(key.to_string()))
+        } else {
+            Ok(0)
+        }
+    }
+
+    fn text(
+        &self,
+        key: &str,
+        _message: &str,
+        default: Option<&str>,
+    ) -> Result<String, PromptError> {
+        default
+            .map(String::from)
+            .ok_or_else(|| PromptError::Unanswered(key.to_string()))
+    }
+
+    fn secret(&self, key: &str, _message: &str) -> Result<String, PromptError> {
+        Err(PromptError::Unanswered(key.to_string()))
+    }
+}
+
+/// Command line flags to pick a prompter. Flatten into a scenario's options.
+#[derive(Clone, Debug, Default, clap::Args)]
+pub struct PrompterArgs {
+    /// Answer prompts from this JSON file, instead of asking. Prompts it doesn't answer take
+    /// their defaults.
+    #[arg(long)]
+    pub answers: Option<PathBuf>,
+
+    /// Don't ask; take every prompt's default.
+    #[arg(long, conflicts_with = "answers")]
+    pub non_interactive: bool,
+}
+
+impl PrompterArgs {
+    pub fn prompter(&self) -> Result<Box<dyn Prompter>, PromptError> {
+        match (&self.answers, self.non_interactive) {
+            (Some(path), _) => Ok(Box::new(ScriptedPrompter::load(path)?)),
+            (None, true) => Ok(Box::new(DefaultPrompter)),
+            (None, false) => Ok(Box::new(TerminalPrompter)),
+        }
+    }
+}
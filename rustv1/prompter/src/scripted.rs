@@ -0,0 +1,216 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::{HashMap, VecDeque},
+    path::Path,
+    sync::Mutex,
+};
+
+use serde_json::Value;
+
+use crate::{PromptError, Prompter};
+
+/// Answers prompts by key, from a JSON object or from answers added in a test.
+///
+/// ```json
+/// { "key_name": "ci_key", "clean_up": true, "instance_type": "t3.micro", "continue": ["", ""] }
+/// ```
+///
+/// A key with a list answers its prompt with each value in turn, then keeps giving the last
+/// one. Prompts without an answer take their defaults. A select is answered with the option's
+/// index, the option's text, or text the option contains. A confirm takes true or false, or
+/// "y", "yes", "n", or "no".
+#[derive(Debug, Default)]
+pub struct ScriptedPrompter {
+    answers: Mutex<HashMap<String, VecDeque<Value>>>,
+}
+
+impl ScriptedPrompter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an answer for `key`. Adding several answers to one key answers in order.
+    pub fn answer(self, key: impl Into<String>, answer: impl Into<Value>) -> Self {
+        self.answers
+            .lock()
+            .unwrap()
+            .entry(key.into())
+            .or_default()
+            .push_back(answer.into());
+        self
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, PromptError> {
+        let answers: HashMap<String, Value> = serde_json::from_str(json)
+            .map_err(|err| PromptError::Failed(format!("Failed to parse answers ({err})")))?;
+        Ok(answers.into_iter().fold(
+            ScriptedPrompter::new(),
+            |prompter, (key, answer)| match answer {
+                Value::Array(answers) => answers
+                    .into_iter()
+                    .fold(prompter, |prompter, answer| prompter.answer(&key, answer)),
+                answer => prompter.answer(key, answer),
+            },
+        ))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, PromptError> {
+        let json = std::fs::read_to_string(path).map_err(|err| {
+            PromptError::Failed(format!("Failed to read answers from {path:?} ({err})"))
+        })?;
+        Self::from_json(&json)
+    }
+
+    fn next(&self, key: &str) -> Option<Value> {
+        let mut answers = self.answers.lock().unwrap();
+        let queue = answers.get_mut(key)?;
+        if queue.len() > 1 {
+            queue.pop_front()
+        } else {
+            queue.front().cloned()
+        }
+    }
+}
+
+fn invalid(key: &str, answer: &Value) -> PromptError {
+    PromptError::Invalid {
+        key: key.to_string(),
+        answer: answer.to_string(),
+    }
+}
+
+fn as_text(answer: Value) -> String {
+    match answer {
+        Value::String(text) => text,
+        answer => answer.to_string(),
+    }
+}
+
+impl Prompter for ScriptedPrompter {
+    fn confirm(&self, key: &str, _message: &str, default: bool) -> Result<bool, PromptError> {
+        let Some(answer) = self.next(key) else {
+            return Ok(default);
+        };
+        match &answer {
+            Value::Bool(yes) => Ok(*yes),
+            Value::String(text) => match text.to_ascii_lowercase().as_str() {
+                "y" | "yes" | "true" => Ok(true),
+                "n" | "no" | "false" => Ok(false),
+                _ => Err(invalid(key, &answer)),
+            },
+            _ => Err(invalid(key, &answer)),
+        }
+    }
+
+    fn select(&self, key: &str, _message: &str, options: &[String]) -> Result<usize, PromptError> {
+        if options.is_empty() {
+            return Err(PromptError::Unanswered(key.to_string()));
+        }
+        let Some(answer) = self.next(key) else {
+            return Ok(0);
+        };
+        let index = match &answer {
+            Value::Number(n) => n
+                .as_u64()
+                .map(|n| n as usize)
+                .filter(|n| *n < options.len()),
+            Value::String(text) => options
+                .iter()
+                .position(|option| option == text)
+                .or_else(|| options.iter().position(|option| option.contains(text))),
+            _ => None,
+        };
+        index.ok_or_else(|| invalid(key, &answer))
+    }
+
+    fn text(
+        &self,
+        key: &str,
+        _message: &str,
+        default: Option<&str>,
+    ) -> Result<String, PromptError> {
+        match self.next(key) {
+            Some(answer) => Ok(as_text(answer)),
+            None => default
+                .map(String::from)
+                .ok_or_else(|| PromptError::Unanswered(key.to_string())),
+        }
+    }
+
+    fn secret(&self, key: &str, _message: &str) -> Result<String, PromptError> {
+        self.next(key)
+            .map(as_text)
+            .ok_or_else(|| PromptError::Unanswered(key.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_scripted_answers() {
+        let prompter = ScriptedPrompter::from_json(
+            r#"{"name": "ci", "delete": "y", "size": "t3.micro", "step": ["one", "two"]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(prompter.text("name", "Name?", None).unwrap(), "ci");
+        assert!(prompter.confirm("delete", "Delete?", false).unwrap());
+        let sizes = vec!["t3.small".to_string(), "t3.micro".to_string()];
+        assert_eq!(prompter.select("size", "Size?", &sizes).unwrap(), 1);
+
+        // A list answers in order, then repeats its last answer.
+        assert_eq!(prompter.text("step", "Step?", None).unwrap(), "one");
+        assert_eq!(prompter.text("step", "Step?", None).unwrap(), "two");
+        assert_eq!(prompter.text("step", "Step?", None).unwrap(), "two");
+    }
+
+    #[test]
+    fn test_unanswered_prompts_take_defaults() {
+        let prompter = ScriptedPrompter::new().answer("delete", "maybe");
+        let options = vec!["a".to_string(), "b".to_string()];
+
+        assert!(prompter.confirm("keep", "Keep?", true).unwrap());
+        assert_eq!(prompter.select("option", "Option?", &options).unwrap(), 0);
+        assert_eq!(
+            prompter.text("name", "Name?", Some("default")).unwrap(),
+            "default"
+        );
+        assert_eq!(
+            prompter.text("email", "Email?", None),
+            Err(PromptError::Unanswered("email".into()))
+        );
+        assert_eq!(
+            prompter.confirm("delete", "Delete?", false),
+            Err(PromptError::Invalid {
+                key: "delete".into(),
+                answer: "\"maybe\"".into()
+            })
+        );
+    }
+
+    #[test]
+    fn test_required_text() {
+        let prompter = ScriptedPrompter::new().answer("name", " ");
+        assert_eq!(
+            prompter.required_text("name", "Name?", Some("default")),
+            Err(PromptError::Invalid {
+                key: "name".into(),
+                answer: "\" \"".into()
+            })
+        );
+        assert_eq!(
+            prompter.required_text("group", "Group?", Some("default")),
+            Ok("default".into())
+        );
+    }
+
+    #[test]
+    fn test_choose() {
+        let prompter: Box<dyn Prompter> = Box::new(ScriptedPrompter::new().answer("port", 2));
+        assert_eq!(prompter.choose("port", "Port?", vec![22, 80, 443]), Ok(443));
+    }
+}
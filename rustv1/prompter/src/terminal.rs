@@ -0,0 +1,71 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use inquire::{validator::ValueRequiredValidator, InquireError};
+
+use crate::{PromptError, Prompter};
+
+/// Asks in the terminal, with `inquire`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TerminalPrompter;
+
+impl From<InquireError> for PromptError {
+    fn from(err: InquireError) -> Self {
+        match err {
+            InquireError::OperationCanceled | InquireError::OperationInterrupted => {
+                PromptError::Cancelled
+            }
+            err => PromptError::Failed(err.to_string()),
+        }
+    }
+}
+
+impl Prompter for TerminalPrompter {
+    fn confirm(&self, _key: &str, message: &str, default: bool) -> Result<bool, PromptError> {
+        Ok(inquire::Confirm::new(message)
+            .with_default(default)
+            .prompt()?)
+    }
+
+    fn select(&self, key: &str, message: &str, options: &[String]) -> Result<usize, PromptError> {
+        if options.is_empty() {
+            return Err(PromptError::Unanswered(key.to_string()));
+        }
+        Ok(inquire::Select::new(message, options.to_vec())
+            .raw_prompt()?
+            .index)
+    }
+
+    fn text(
+        &self,
+        _key: &str,
+        message: &str,
+        default: Option<&str>,
+    ) -> Result<String, PromptError> {
+        let mut text = inquire::Text::new(message);
+        if let Some(default) = default {
+            text = text.with_default(default);
+        }
+        Ok(text.prompt()?)
+    }
+
+    fn required_text(
+        &self,
+        _key: &str,
+        message: &str,
+        default: Option<&str>,
+    ) -> Result<String, PromptError> {
+        let mut text =
+            inquire::Text::new(message).with_validator(ValueRequiredValidator::default());
+        if let Some(default) = default {
+            text = text.with_default(default);
+        }
+        Ok(text.prompt()?)
+    }
+
+    fn secret(&self, _key: &str, message: &str) -> Result<String, PromptError> {
+        Ok(inquire::Password::new(message)
+            .without_confirmation()
+            .prompt()?)
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Delete the resources an aborted scenario run left behind.
+//!
+//! The scenarios record each resource in a journal as they create it. This reads the
+//! journal, and deletes whatever was never cleaned up, newest and most dependent first.
+
+use std::{collections::BTreeMap, process::ExitCode, time::Duration};
+
+use aws_config::{meta::region::RegionProviderChain, Region};
+use clap::Parser;
+use sdk_examples_resource_ledger::{reapers, Ledger, Resource, Teardown, DEFAULT_JOURNAL};
+
+#[derive(Debug, Parser)]
+struct Opt {
+    /// The journal written by the scenario run to clean up.
+    #[structopt(long, default_value = DEFAULT_JOURNAL)]
+    from_journal: String,
+
+    /// List what would be deleted without deleting it.
+    #[structopt(long)]
+    dry_run: bool,
+
+    /// How many minutes to keep retrying each resource before giving up on it.
+    #[structopt(long, default_value = "30")]
+    max_wait_minutes: u64,
+
+    /// Whether to display additional information.
+    #[structopt(short, long)]
+    verbose: bool,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let Opt {
+        from_journal,
+        dry_run,
+        max_wait_minutes,
+        verbose,
+    } = Opt::parse();
+
+    if verbose {
+        tracing_subscriber::fmt::init();
+    }
+
+    let ledger = Ledger::open(&from_journal);
+    let outstanding = match ledger.outstanding() {
+        Ok(outstanding) => outstanding,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if outstanding.is_empty() {
+        println!("Nothing to clean up in {from_journal}.");
+        return ExitCode::SUCCESS;
+    }
+
+    let mut by_region: BTreeMap<Option<String>, Vec<Resource>> = BTreeMap::new();
+    for resource in outstanding {
+        by_region
+            .entry(resource.region.clone())
+            .or_default()
+            .push(resource);
+    }
+
+    let mut clean = true;
+    for (region, resources) in by_region {
+        if dry_run {
+            for resource in Teardown::plan(resources) {
+                println!("Would delete {resource}");
+            }
+            continue;
+        }
+
+        let region_provider = RegionProviderChain::first_try(region.map(Region::new))
+            .or_default_provider()
+            .or_else(Region::new("us-west-2"));
+        let sdk_config = aws_config::from_env().region(region_provider).load().await;
+
+        let report = reapers::teardown(&sdk_config)
+            .max_wait(Duration::from_secs(max_wait_minutes * 60))
+            .run_for(&ledger, resources)
+            .await;
+
+        for resource in &report.deleted {
+            println!("Deleted {resource}");
+        }
+        for (resource, reason) in &report.failed {
+            println!("Failed to delete {resource}: {reason}");
+        }
+        for (resource, reason) in &report.skipped {
+            println!("Skipped {resource}: {reason}");
+        }
+        clean &= report.is_clean();
+    }
+
+    if clean {
+        ExitCode::SUCCESS
+    } else {
+        println!("Some resources remain. They are still in {from_journal} for the next run.");
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,329 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An append-only journal of the resources a scenario has created and deleted.
+//!
+//! Each line is one JSON entry, written and synced before the call returns, so the
+//! journal survives a panic or Ctrl-C at any point after the resource was created.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::LedgerError;
+
+/// The kinds of resources the scenarios create, and the teardown engine knows how to delete.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResourceKind {
+    RdsDbClusterParameterGroup,
+    RdsDbCluster,
+    RdsDbInstance,
+    RdsDbClusterSnapshot,
+    AutoScalingGroup,
+    Ec2LaunchTemplate,
+    Ec2Instance,
+    Ec2SecurityGroup,
+    Ec2KeyPair,
+    Ec2ElasticIp,
+    GlueDatabase,
+    GlueCrawler,
+    GlueJob,
+    GlueWorkflow,
+    GlueTrigger,
+}
+
+impl Display for ResourceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ResourceKind::RdsDbClusterParameterGroup => "rds-db-cluster-parameter-group",
+            ResourceKind::RdsDbCluster => "rds-db-cluster",
+            ResourceKind::RdsDbInstance => "rds-db-instance",
+            ResourceKind::RdsDbClusterSnapshot => "rds-db-cluster-snapshot",
+            ResourceKind::AutoScalingGroup => "auto-scaling-group",
+            ResourceKind::Ec2LaunchTemplate => "ec2-launch-template",
+            ResourceKind::Ec2Instance => "ec2-instance",
+            ResourceKind::Ec2SecurityGroup => "ec2-security-group",
+            ResourceKind::Ec2KeyPair => "ec2-key-pair",
+            ResourceKind::Ec2ElasticIp => "ec2-elastic-ip",
+            ResourceKind::GlueDatabase => "glue-database",
+            ResourceKind::GlueCrawler => "glue-crawler",
+            ResourceKind::GlueJob => "glue-job",
+            ResourceKind::GlueWorkflow => "glue-workflow",
+            ResourceKind::GlueTrigger => "glue-trigger",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Identifies one resource: its kind, and the id or ARN its service deletes it by.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ResourceRef {
+    pub kind: ResourceKind,
+    pub id: String,
+}
+
+impl Display for ResourceRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.kind, self.id)
+    }
+}
+
+/// A created resource, with the resources that must outlive it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Resource {
+    pub kind: ResourceKind,
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Resources this one uses. They are deleted after this one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<ResourceRef>,
+}
+
+impl Resource {
+    pub fn new(kind: ResourceKind, id: impl Into<String>) -> Self {
+        Resource {
+            kind,
+            id: id.into(),
+            region: None,
+            depends_on: vec![],
+        }
+    }
+
+    pub fn region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    pub fn depends_on(mut self, kind: ResourceKind, id: impl Into<String>) -> Self {
+        self.depends_on.push(ResourceRef {
+            kind,
+            id: id.into(),
+        });
+        self
+    }
+
+    pub fn as_ref(&self) -> ResourceRef {
+        ResourceRef {
+            kind: self.kind,
+            id: self.id.clone(),
+        }
+    }
+}
+
+impl Display for Resource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.region {
+            Some(region) => write!(f, "{} {} ({region})", self.kind, self.id),
+            None => write!(f, "{} {}", self.kind, self.id),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+enum Entry {
+    Created {
+        #[serde(flatten)]
+        resource: Resource,
+        at: u64,
+    },
+    Deleted {
+        #[serde(flatten)]
+        resource: ResourceRef,
+        at: u64,
+    },
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Whether the file is not empty and its last byte isn't a newline.
+fn ends_mid_line(file: &mut std::fs::File) -> std::io::Result<bool> {
+    if file.metadata()?.len() == 0 {
+        return Ok(false);
+    }
+    let mut last = [0u8];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] != b'\n')
+}
+
+/// A journal file on disk.
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    /// Use the journal at `path`. The file is created on the first write.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Journal { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn created(&self, resource: &Resource) -> Result<(), LedgerError> {
+        self.append(&Entry::Created {
+            resource: resource.clone(),
+            at: now(),
+        })
+    }
+
+    pub fn deleted(&self, resource: &ResourceRef) -> Result<(), LedgerError> {
+        self.append(&Entry::Deleted {
+            resource: resource.clone(),
+            at: now(),
+        })
+    }
+
+    fn append(&self, entry: &Entry) -> Result<(), LedgerError> {
+        let mut line = serde_json::to_string(entry)
+            .map_err(|e| LedgerError::journal(&self.path, e.to_string()))?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| LedgerError::journal(&self.path, e.to_string()))?;
+        // A crash in the middle of a write leaves the last line without its newline. Start
+        // a new line, so this entry isn't joined to the torn one and lost with it.
+        if ends_mid_line(&mut file).map_err(|e| LedgerError::journal(&self.path, e.to_string()))? {
+            line.insert(0, '\n');
+        }
+        file.write_all(line.as_bytes())
+            .and_then(|_| file.sync_data())
+            .map_err(|e| LedgerError::journal(&self.path, e.to_string()))
+    }
+
+    /// Replay the journal, returning the resources that were created and not yet deleted,
+    /// in the order they were created. A missing journal has no outstanding resources.
+    pub fn outstanding(&self) -> Result<Vec<Resource>, LedgerError> {
+        let file = match std::fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(LedgerError::journal(&self.path, e.to_string())),
+        };
+
+        let mut order: Vec<ResourceRef> = vec![];
+        let mut live: HashMap<ResourceRef, Resource> = HashMap::new();
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| LedgerError::journal(&self.path, e.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // A crash in the middle of a write leaves a partial last line. Skip it, and
+            // anything else unreadable, rather than refusing to clean up the rest.
+            match serde_json::from_str::<Entry>(&line) {
+                Ok(Entry::Created { resource, .. }) => {
+                    let key = resource.as_ref();
+                    order.retain(|r| r != &key);
+                    order.push(key.clone());
+                    live.insert(key, resource);
+                }
+                Ok(Entry::Deleted { resource, .. }) => {
+                    order.retain(|r| r != &resource);
+                    live.remove(&resource);
+                }
+                Err(err) => {
+                    warn!(path = ?self.path, line = number + 1, %err, "Skipping unreadable journal entry")
+                }
+            }
+        }
+
+        Ok(order
+            .into_iter()
+            .filter_map(|key| live.remove(&key))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn test_outstanding_replays_created_and_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("journal.jsonl"));
+
+        assert!(journal.outstanding().unwrap().is_empty());
+
+        let template = Resource::new(ResourceKind::Ec2LaunchTemplate, "lt-1").region("us-west-2");
+        let group = Resource::new(ResourceKind::AutoScalingGroup, "group")
+            .region("us-west-2")
+            .depends_on(ResourceKind::Ec2LaunchTemplate, "lt-1");
+        journal.created(&template).unwrap();
+        journal.created(&group).unwrap();
+        assert_eq!(
+            journal.outstanding().unwrap(),
+            vec![template.clone(), group.clone()]
+        );
+
+        journal.deleted(&group.as_ref()).unwrap();
+        assert_eq!(journal.outstanding().unwrap(), vec![template]);
+    }
+
+    #[test]
+    fn test_outstanding_skips_torn_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("journal.jsonl"));
+        let key_pair = Resource::new(ResourceKind::Ec2KeyPair, "key");
+        journal.created(&key_pair).unwrap();
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(journal.path())
+            .unwrap();
+        write!(file, r#"{{"event":"created","kind":"ec2-inst"#).unwrap();
+
+        assert_eq!(journal.outstanding().unwrap(), vec![key_pair]);
+    }
+
+    #[test]
+    fn test_append_after_torn_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("journal.jsonl"));
+        let key_pair = Resource::new(ResourceKind::Ec2KeyPair, "key");
+        journal.created(&key_pair).unwrap();
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(journal.path())
+            .unwrap();
+        write!(file, r#"{{"event":"created","kind":"ec2-inst"#).unwrap();
+
+        let group = Resource::new(ResourceKind::Ec2SecurityGroup, "sg-1");
+        journal.created(&group).unwrap();
+        assert_eq!(
+            journal.outstanding().unwrap(),
+            vec![key_pair.clone(), group]
+        );
+
+        journal
+            .deleted(&ResourceRef {
+                kind: ResourceKind::Ec2SecurityGroup,
+                id: "sg-1".into(),
+            })
+            .unwrap();
+        assert_eq!(journal.outstanding().unwrap(), vec![key_pair]);
+    }
+}
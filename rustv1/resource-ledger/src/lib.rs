@@ -0,0 +1,131 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Record the resources a scenario creates as it creates them, so that a run that
+//! panics or is interrupted can still be cleaned up with `cleanup --from-journal`.
+
+use std::{fmt::Display, path::Path, sync::Arc};
+
+use tracing::warn;
+
+pub mod journal;
+pub mod reapers;
+pub mod teardown;
+
+pub use journal::{Journal, Resource, ResourceKind, ResourceRef};
+pub use teardown::{ReapError, Reaper, Teardown, TeardownReport};
+
+/// The journal scenarios write to when no other path is given.
+pub const DEFAULT_JOURNAL: &str = "scenario-journal.jsonl";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum LedgerError {
+    Journal { path: String, message: String },
+    Teardown(String),
+}
+
+impl LedgerError {
+    fn journal(path: &Path, message: impl Into<String>) -> Self {
+        LedgerError::Journal {
+            path: path.display().to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::error::Error for LedgerError {}
+impl Display for LedgerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LedgerError::Journal { path, message } => {
+                write!(f, "Resource journal {path}: {message}")
+            }
+            LedgerError::Teardown(message) => write!(f, "Teardown failed: {message}"),
+        }
+    }
+}
+
+/// A cheap, cloneable handle scenarios use to record what they create and delete.
+///
+/// Recording is best effort: a journal write failure is logged, and never fails the
+/// scenario. A disabled ledger records nothing, for tests and callers that opt out.
+#[derive(Clone, Debug, Default)]
+pub struct Ledger {
+    journal: Option<Arc<Journal>>,
+    region: Option<String>,
+}
+
+impl Ledger {
+    pub fn open(path: impl AsRef<Path>) -> Self {
+        Ledger {
+            journal: Some(Arc::new(Journal::new(path.as_ref()))),
+            region: None,
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Ledger::default()
+    }
+
+    /// Record resources in this region, unless the resource names its own.
+    pub fn with_region(mut self, region: Option<impl Into<String>>) -> Self {
+        self.region = region.map(Into::into);
+        self
+    }
+
+    pub fn journal(&self) -> Option<&Journal> {
+        self.journal.as_deref()
+    }
+
+    pub fn created(&self, resource: Resource) {
+        if let Some(journal) = &self.journal {
+            let resource = match (&resource.region, &self.region) {
+                (None, Some(region)) => resource.region(region.clone()),
+                _ => resource,
+            };
+            if let Err(err) = journal.created(&resource) {
+                warn!(%err, %resource, "Failed to record created resource");
+            }
+        }
+    }
+
+    pub fn deleted(&self, kind: ResourceKind, id: impl Into<String>) {
+        if let Some(journal) = &self.journal {
+            let resource = ResourceRef {
+                kind,
+                id: id.into(),
+            };
+            if let Err(err) = journal.deleted(&resource) {
+                warn!(%err, %resource, "Failed to record deleted resource");
+            }
+        }
+    }
+
+    pub fn outstanding(&self) -> Result<Vec<Resource>, LedgerError> {
+        match &self.journal {
+            Some(journal) => journal.outstanding(),
+            None => Ok(vec![]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ledger_fills_in_region() {
+        let dir = tempfile::tempdir().unwrap();
+        let ledger = Ledger::open(dir.path().join("journal.jsonl")).with_region(Some("eu-west-1"));
+
+        ledger.created(Resource::new(ResourceKind::GlueJob, "job"));
+        ledger.created(Resource::new(ResourceKind::GlueCrawler, "crawler").region("us-east-1"));
+        ledger.deleted(ResourceKind::GlueJob, "job");
+
+        assert_eq!(
+            ledger.outstanding().unwrap(),
+            vec![Resource::new(ResourceKind::GlueCrawler, "crawler").region("us-east-1")]
+        );
+        assert!(Ledger::disabled().outstanding().unwrap().is_empty());
+    }
+}
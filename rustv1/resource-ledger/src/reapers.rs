@@ -0,0 +1,463 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reapers that delete the scenario resources with the AWS SDK.
+//!
+//! Each service's reaper handles every kind of resource in that service. Errors are
+//! sorted by their error code: "not found" means the resource is gone, "in use" and
+//! throttling mean try again later, and anything else is fatal.
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use aws_config::SdkConfig;
+use aws_sdk_ec2::{
+    error::{DisplayErrorContext, ProvideErrorMetadata},
+    types::InstanceStateName,
+};
+
+use crate::{ReapError, Reaper, Resource, ResourceKind, Teardown};
+
+const THROTTLING: &[&str] = &[
+    "Throttling",
+    "ThrottlingException",
+    "RequestLimitExceeded",
+    "TooManyRequestsException",
+];
+
+/// Sort an SDK error into gone (`Ok`), retry, or fatal.
+fn classify<E>(err: &E, gone: &[&str], retry: &[&str]) -> Result<(), ReapError>
+where
+    E: ProvideErrorMetadata + std::error::Error + Debug,
+{
+    let message = format!("{}", DisplayErrorContext(err));
+    match err.code() {
+        Some(code) if gone.contains(&code) => Ok(()),
+        Some(code) if retry.contains(&code) || THROTTLING.contains(&code) => {
+            Err(ReapError::Retry(message))
+        }
+        Some(_) => Err(ReapError::Fatal(message)),
+        // No error code means the request didn't get a response, for example a timeout.
+        None => Err(ReapError::Retry(message)),
+    }
+}
+
+/// Existence from a describe call: a "not found" error means it doesn't exist.
+fn exists_from<T, E>(
+    result: Result<T, E>,
+    gone: &[&str],
+    exists: impl FnOnce(T) -> bool,
+) -> Result<bool, ReapError>
+where
+    E: ProvideErrorMetadata + std::error::Error + Debug,
+{
+    match result {
+        Ok(output) => Ok(exists(output)),
+        Err(err) => classify(&err, gone, &[]).map(|_| false),
+    }
+}
+
+/// A Teardown that can delete every kind of resource in the SDK config's region.
+pub fn teardown(sdk_config: &SdkConfig) -> Teardown {
+    let rds = RdsReaper(aws_sdk_rds::Client::new(sdk_config));
+    let ec2 = Ec2Reaper(aws_sdk_ec2::Client::new(sdk_config));
+    let glue = GlueReaper(aws_sdk_glue::Client::new(sdk_config));
+    Teardown::new()
+        .reaper(ResourceKind::RdsDbClusterParameterGroup, rds.clone())
+        .reaper(ResourceKind::RdsDbCluster, rds.clone())
+        .reaper(ResourceKind::RdsDbInstance, rds.clone())
+        .reaper(ResourceKind::RdsDbClusterSnapshot, rds)
+        .reaper(
+            ResourceKind::AutoScalingGroup,
+            AutoScalingReaper(aws_sdk_autoscaling::Client::new(sdk_config)),
+        )
+        .reaper(ResourceKind::Ec2LaunchTemplate, ec2.clone())
+        .reaper(ResourceKind::Ec2Instance, ec2.clone())
+        .reaper(ResourceKind::Ec2SecurityGroup, ec2.clone())
+        .reaper(ResourceKind::Ec2KeyPair, ec2.clone())
+        .reaper(ResourceKind::Ec2ElasticIp, ec2)
+        .reaper(ResourceKind::GlueDatabase, glue.clone())
+        .reaper(ResourceKind::GlueCrawler, glue.clone())
+        .reaper(ResourceKind::GlueJob, glue.clone())
+        .reaper(ResourceKind::GlueWorkflow, glue.clone())
+        .reaper(ResourceKind::GlueTrigger, glue)
+}
+
+fn unsupported(resource: &Resource) -> ReapError {
+    ReapError::Fatal(format!("Can't delete {resource} with this reaper"))
+}
+
+#[derive(Clone)]
+pub struct RdsReaper(pub aws_sdk_rds::Client);
+
+#[async_trait]
+impl Reaper for RdsReaper {
+    async fn delete(&self, resource: &Resource) -> Result<(), ReapError> {
+        let id = resource.id.as_str();
+        match resource.kind {
+            ResourceKind::RdsDbInstance => {
+                match self
+                    .0
+                    .delete_db_instance()
+                    .db_instance_identifier(id)
+                    .skip_final_snapshot(true)
+                    .send()
+                    .await
+                {
+                    Ok(_) => Ok(()),
+                    Err(err) => classify(
+                        &err,
+                        &["DBInstanceNotFound"],
+                        &["InvalidDBInstanceState", "InvalidDBClusterStateFault"],
+                    ),
+                }
+            }
+            ResourceKind::RdsDbCluster => {
+                match self
+                    .0
+                    .delete_db_cluster()
+                    .db_cluster_identifier(id)
+                    .skip_final_snapshot(true)
+                    .send()
+                    .await
+                {
+                    Ok(_) => Ok(()),
+                    Err(err) => classify(
+                        &err,
+                        &["DBClusterNotFoundFault"],
+                        &["InvalidDBClusterStateFault"],
+                    ),
+                }
+            }
+            ResourceKind::RdsDbClusterParameterGroup => {
+                match self
+                    .0
+                    .delete_db_cluster_parameter_group()
+                    .db_cluster_parameter_group_name(id)
+                    .send()
+                    .await
+                {
+                    Ok(_) => Ok(()),
+                    Err(err) => classify(
+                        &err,
+                        &["DBParameterGroupNotFound"],
+                        &["InvalidDBParameterGroupState"],
+                    ),
+                }
+            }
+            ResourceKind::RdsDbClusterSnapshot => {
+                match self
+                    .0
+                    .delete_db_cluster_snapshot()
+                    .db_cluster_snapshot_identifier(id)
+                    .send()
+                    .await
+                {
+                    Ok(_) => Ok(()),
+                    Err(err) => classify(
+                        &err,
+                        &["DBClusterSnapshotNotFoundFault"],
+                        &["InvalidDBClusterSnapshotStateFault"],
+                    ),
+                }
+            }
+            _ => Err(unsupported(resource)),
+        }
+    }
+
+    async fn exists(&self, resource: &Resource) -> Result<bool, ReapError> {
+        let id = resource.id.as_str();
+        match resource.kind {
+            ResourceKind::RdsDbInstance => exists_from(
+                self.0
+                    .describe_db_instances()
+                    .db_instance_identifier(id)
+                    .send()
+                    .await,
+                &["DBInstanceNotFound"],
+                |output| !output.db_instances().is_empty(),
+            ),
+            ResourceKind::RdsDbCluster => exists_from(
+                self.0
+                    .describe_db_clusters()
+                    .db_cluster_identifier(id)
+                    .send()
+                    .await,
+                &["DBClusterNotFoundFault"],
+                |output| !output.db_clusters().is_empty(),
+            ),
+            ResourceKind::RdsDbClusterParameterGroup => exists_from(
+                self.0
+                    .describe_db_cluster_parameter_groups()
+                    .db_cluster_parameter_group_name(id)
+                    .send()
+                    .await,
+                &["DBParameterGroupNotFound"],
+                |output| !output.db_cluster_parameter_groups().is_empty(),
+            ),
+            ResourceKind::RdsDbClusterSnapshot => exists_from(
+                self.0
+                    .describe_db_cluster_snapshots()
+                    .db_cluster_snapshot_identifier(id)
+                    .send()
+                    .await,
+                &["DBClusterSnapshotNotFoundFault"],
+                |output| !output.db_cluster_snapshots().is_empty(),
+            ),
+            _ => Err(unsupported(resource)),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AutoScalingReaper(pub aws_sdk_autoscaling::Client);
+
+#[async_trait]
+impl Reaper for AutoScalingReaper {
+    async fn delete(&self, resource: &Resource) -> Result<(), ReapError> {
+        if resource.kind != ResourceKind::AutoScalingGroup {
+            return Err(unsupported(resource));
+        }
+        // Force delete terminates the group's instances along with it.
+        match self
+            .0
+            .delete_auto_scaling_group()
+            .auto_scaling_group_name(resource.id.as_str())
+            .force_delete(true)
+            .send()
+            .await
+        {
+            Ok(_) => Ok(()),
+            // Deleting a group that doesn't exist is a ValidationError, which is also used
+            // for malformed requests, so check whether the group is still there.
+            Err(err) if err.code() == Some("ValidationError") => {
+                match self.exists(resource).await? {
+                    false => Ok(()),
+                    true => Err(ReapError::Fatal(format!("{}", DisplayErrorContext(&err)))),
+                }
+            }
+            Err(err) => classify(&err, &[], &["ResourceInUse", "ScalingActivityInProgress"]),
+        }
+    }
+
+    async fn exists(&self, resource: &Resource) -> Result<bool, ReapError> {
+        exists_from(
+            self.0
+                .describe_auto_scaling_groups()
+                .auto_scaling_group_names(resource.id.as_str())
+                .send()
+                .await,
+            &[],
+            |output| !output.auto_scaling_groups().is_empty(),
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct Ec2Reaper(pub aws_sdk_ec2::Client);
+
+const LAUNCH_TEMPLATE_NOT_FOUND: &[&str] = &[
+    "InvalidLaunchTemplateId.NotFound",
+    "InvalidLaunchTemplateId.Malformed",
+    "InvalidLaunchTemplateName.NotFoundException",
+];
+
+#[async_trait]
+impl Reaper for Ec2Reaper {
+    async fn delete(&self, resource: &Resource) -> Result<(), ReapError> {
+        let id = resource.id.as_str();
+        match resource.kind {
+            ResourceKind::Ec2LaunchTemplate => {
+                match self
+                    .0
+                    .delete_launch_template()
+                    .launch_template_id(id)
+                    .send()
+                    .await
+                {
+                    Ok(_) => Ok(()),
+                    Err(err) => classify(&err, LAUNCH_TEMPLATE_NOT_FOUND, &[]),
+                }
+            }
+            ResourceKind::Ec2Instance => {
+                match self.0.terminate_instances().instance_ids(id).send().await {
+                    Ok(_) => Ok(()),
+                    Err(err) => classify(&err, &["InvalidInstanceID.NotFound"], &[]),
+                }
+            }
+            ResourceKind::Ec2SecurityGroup => {
+                match self.0.delete_security_group().group_id(id).send().await {
+                    Ok(_) => Ok(()),
+                    // Instances keep using the group until they finish terminating.
+                    Err(err) => {
+                        classify(&err, &["InvalidGroup.NotFound"], &["DependencyViolation"])
+                    }
+                }
+            }
+            ResourceKind::Ec2KeyPair => match self.0.delete_key_pair().key_name(id).send().await {
+                Ok(_) => Ok(()),
+                Err(err) => classify(&err, &["InvalidKeyPair.NotFound"], &[]),
+            },
+            ResourceKind::Ec2ElasticIp => {
+                let addresses = self.0.describe_addresses().allocation_ids(id).send().await;
+                let association_id = match addresses {
+                    Ok(output) => output
+                        .addresses()
+                        .first()
+                        .and_then(|address| address.association_id())
+                        .map(String::from),
+                    Err(err) => {
+                        return classify(&err, &["InvalidAllocationID.NotFound"], &[]);
+                    }
+                };
+                if let Some(association_id) = association_id {
+                    if let Err(err) = self
+                        .0
+                        .disassociate_address()
+                        .association_id(association_id)
+                        .send()
+                        .await
+                    {
+                        classify(&err, &["InvalidAssociationID.NotFound"], &[])?;
+                    }
+                }
+                match self.0.release_address().allocation_id(id).send().await {
+                    Ok(_) => Ok(()),
+                    Err(err) => classify(
+                        &err,
+                        &["InvalidAllocationID.NotFound"],
+                        &["InvalidIPAddress.InUse"],
+                    ),
+                }
+            }
+            _ => Err(unsupported(resource)),
+        }
+    }
+
+    async fn exists(&self, resource: &Resource) -> Result<bool, ReapError> {
+        let id = resource.id.as_str();
+        match resource.kind {
+            ResourceKind::Ec2LaunchTemplate => exists_from(
+                self.0
+                    .describe_launch_templates()
+                    .launch_template_ids(id)
+                    .send()
+                    .await,
+                LAUNCH_TEMPLATE_NOT_FOUND,
+                |output| !output.launch_templates().is_empty(),
+            ),
+            ResourceKind::Ec2Instance => exists_from(
+                self.0.describe_instances().instance_ids(id).send().await,
+                &["InvalidInstanceID.NotFound"],
+                |output| {
+                    output
+                        .reservations()
+                        .iter()
+                        .flat_map(|reservation| reservation.instances())
+                        .any(|instance| {
+                            instance.state().and_then(|state| state.name())
+                                != Some(&InstanceStateName::Terminated)
+                        })
+                },
+            ),
+            ResourceKind::Ec2SecurityGroup => exists_from(
+                self.0.describe_security_groups().group_ids(id).send().await,
+                &["InvalidGroup.NotFound"],
+                |output| !output.security_groups().is_empty(),
+            ),
+            ResourceKind::Ec2KeyPair => exists_from(
+                self.0.describe_key_pairs().key_names(id).send().await,
+                &["InvalidKeyPair.NotFound"],
+                |output| !output.key_pairs().is_empty(),
+            ),
+            ResourceKind::Ec2ElasticIp => exists_from(
+                self.0.describe_addresses().allocation_ids(id).send().await,
+                &["InvalidAllocationID.NotFound"],
+                |output| !output.addresses().is_empty(),
+            ),
+            _ => Err(unsupported(resource)),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct GlueReaper(pub aws_sdk_glue::Client);
+
+const GLUE_NOT_FOUND: &[&str] = &["EntityNotFoundException"];
+
+#[async_trait]
+impl Reaper for GlueReaper {
+    async fn delete(&self, resource: &Resource) -> Result<(), ReapError> {
+        let id = resource.id.as_str();
+        match resource.kind {
+            // Deleting the database also deletes the tables the crawler made in it.
+            ResourceKind::GlueDatabase => match self.0.delete_database().name(id).send().await {
+                Ok(_) => Ok(()),
+                Err(err) => classify(&err, GLUE_NOT_FOUND, &["ConcurrentModificationException"]),
+            },
+            ResourceKind::GlueCrawler => {
+                // A running crawler can't be deleted, so ask it to stop first.
+                if let Err(err) = self.0.stop_crawler().name(id).send().await {
+                    classify(
+                        &err,
+                        &["EntityNotFoundException", "CrawlerNotRunningException"],
+                        &["CrawlerStoppingException"],
+                    )?;
+                }
+                match self.0.delete_crawler().name(id).send().await {
+                    Ok(_) => Ok(()),
+                    Err(err) => classify(
+                        &err,
+                        GLUE_NOT_FOUND,
+                        &["CrawlerRunningException", "SchedulerTransitioningException"],
+                    ),
+                }
+            }
+            ResourceKind::GlueJob => match self.0.delete_job().job_name(id).send().await {
+                Ok(_) => Ok(()),
+                Err(err) => classify(&err, GLUE_NOT_FOUND, &[]),
+            },
+            ResourceKind::GlueWorkflow => match self.0.delete_workflow().name(id).send().await {
+                Ok(_) => Ok(()),
+                Err(err) => classify(&err, GLUE_NOT_FOUND, &["ConcurrentModificationException"]),
+            },
+            ResourceKind::GlueTrigger => match self.0.delete_trigger().name(id).send().await {
+                Ok(_) => Ok(()),
+                Err(err) => classify(&err, GLUE_NOT_FOUND, &["ConcurrentModificationException"]),
+            },
+            _ => Err(unsupported(resource)),
+        }
+    }
+
+    async fn exists(&self, resource: &Resource) -> Result<bool, ReapError> {
+        let id = resource.id.as_str();
+        match resource.kind {
+            ResourceKind::GlueDatabase => exists_from(
+                self.0.get_database().name(id).send().await,
+                GLUE_NOT_FOUND,
+                |output| output.database().is_some(),
+            ),
+            ResourceKind::GlueCrawler => exists_from(
+                self.0.get_crawler().name(id).send().await,
+                GLUE_NOT_FOUND,
+                |output| output.crawler().is_some(),
+            ),
+            ResourceKind::GlueJob => exists_from(
+                self.0.get_job().job_name(id).send().await,
+                GLUE_NOT_FOUND,
+                |output| output.job().is_some(),
+            ),
+            ResourceKind::GlueWorkflow => exists_from(
+                self.0.get_workflow().name(id).send().await,
+                GLUE_NOT_FOUND,
+                |output| output.workflow().is_some(),
+            ),
+            ResourceKind::GlueTrigger => exists_from(
+                self.0.get_trigger().name(id).send().await,
+                GLUE_NOT_FOUND,
+                |output| output.trigger().is_some(),
+            ),
+            _ => Err(unsupported(resource)),
+        }
+    }
+}
@@ -0,0 +1,313 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Delete outstanding resources in dependency order, retrying each until it is gone.
+
+use std::{collections::HashMap, time::Duration};
+
+use async_trait::async_trait;
+use sdk_examples_test_utils::waiter::Waiter;
+use tracing::{info, warn};
+
+use crate::{Ledger, LedgerError, Resource, ResourceKind, ResourceRef};
+
+// Some resources, like DB clusters, can take more than 15 minutes to delete.
+const MAX_WAIT: Duration = Duration::from_secs(30 * 60);
+const POLL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ReapError {
+    /// The resource can't be deleted yet, for example because it is still in use. Try again.
+    Retry(String),
+    /// The resource can't be deleted.
+    Fatal(String),
+}
+
+impl std::error::Error for ReapError {}
+impl std::fmt::Display for ReapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReapError::Retry(message) | ReapError::Fatal(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+/// Deletes one kind of resource.
+#[async_trait]
+pub trait Reaper: Send + Sync {
+    /// Start deleting the resource. Succeeds if the resource is already gone.
+    async fn delete(&self, resource: &Resource) -> Result<(), ReapError>;
+
+    /// Whether the resource still exists, including while it is being deleted.
+    async fn exists(&self, resource: &Resource) -> Result<bool, ReapError>;
+}
+
+/// What happened to each resource in a teardown.
+#[derive(Debug, Default)]
+pub struct TeardownReport {
+    pub deleted: Vec<Resource>,
+    pub failed: Vec<(Resource, String)>,
+    /// Resources not attempted, because nothing can delete them or a resource that
+    /// depends on them could not be deleted.
+    pub skipped: Vec<(Resource, String)>,
+}
+
+impl TeardownReport {
+    pub fn is_clean(&self) -> bool {
+        self.failed.is_empty() && self.skipped.is_empty()
+    }
+}
+
+pub struct Teardown {
+    reapers: HashMap<ResourceKind, Box<dyn Reaper>>,
+    max_wait: Duration,
+    poll: Duration,
+}
+
+impl Default for Teardown {
+    fn default() -> Self {
+        Teardown::new()
+    }
+}
+
+impl Teardown {
+    pub fn new() -> Self {
+        Teardown {
+            reapers: HashMap::new(),
+            max_wait: MAX_WAIT,
+            poll: POLL,
+        }
+    }
+
+    pub fn reaper(mut self, kind: ResourceKind, reaper: impl Reaper + 'static) -> Self {
+        self.reapers.insert(kind, Box::new(reaper));
+        self
+    }
+
+    /// How long to keep retrying each resource before giving up on it.
+    pub fn max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = max_wait;
+        self
+    }
+
+    pub fn poll(mut self, poll: Duration) -> Self {
+        self.poll = poll;
+        self
+    }
+
+    /// Order resources so that each is deleted before anything it depends on. Without
+    /// recorded dependencies, the newest resource is deleted first.
+    pub fn plan(resources: Vec<Resource>) -> Vec<Resource> {
+        let mut remaining: Vec<Resource> = resources.into_iter().rev().collect();
+        let mut plan = Vec::with_capacity(remaining.len());
+        while !remaining.is_empty() {
+            // The first resource that nothing left depends on. If there's a cycle, there is
+            // no such resource, so break it at the newest.
+            let next = remaining
+                .iter()
+                .position(|candidate| {
+                    let key = candidate.as_ref();
+                    !remaining
+                        .iter()
+                        .any(|other| other.depends_on.contains(&key))
+                })
+                .unwrap_or(0);
+            plan.push(remaining.remove(next));
+        }
+        plan
+    }
+
+    /// Delete everything outstanding in the ledger, recording each deletion as it completes.
+    pub async fn run(&self, ledger: &Ledger) -> Result<TeardownReport, LedgerError> {
+        Ok(self.run_for(ledger, ledger.outstanding()?).await)
+    }
+
+    /// Delete some of the ledger's outstanding resources, such as those in one region.
+    pub async fn run_for(&self, ledger: &Ledger, resources: Vec<Resource>) -> TeardownReport {
+        let mut report = TeardownReport::default();
+        let mut blocked: Vec<ResourceRef> = vec![];
+
+        for resource in Teardown::plan(resources) {
+            if blocked.contains(&resource.as_ref()) {
+                blocked.extend(resource.depends_on.iter().cloned());
+                report
+                    .skipped
+                    .push((resource, "a resource that uses it was not deleted".into()));
+                continue;
+            }
+            let Some(reaper) = self.reapers.get(&resource.kind) else {
+                blocked.extend(resource.depends_on.iter().cloned());
+                report
+                    .skipped
+                    .push((resource, "no reaper for this kind of resource".into()));
+                continue;
+            };
+
+            info!(%resource, "Deleting");
+            match self.reap(reaper.as_ref(), &resource).await {
+                Ok(()) => {
+                    ledger.deleted(resource.kind, resource.id.clone());
+                    report.deleted.push(resource);
+                }
+                Err(err) => {
+                    warn!(%resource, %err, "Failed to delete");
+                    blocked.extend(resource.depends_on.iter().cloned());
+                    report.failed.push((resource, err.to_string()));
+                }
+            }
+        }
+
+        report
+    }
+
+    async fn reap(&self, reaper: &dyn Reaper, resource: &Resource) -> Result<(), ReapError> {
+        let waiter = Waiter::builder().max(self.max_wait).poll(self.poll).build();
+
+        loop {
+            match reaper.delete(resource).await {
+                Ok(()) => break,
+                Err(ReapError::Retry(message)) => {
+                    info!(%resource, reason = %message, "Not ready to delete, retrying");
+                    waiter
+                        .sleep()
+                        .await
+                        .map_err(|_| ReapError::Fatal(message))?;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        loop {
+            match reaper.exists(resource).await {
+                Ok(false) => return Ok(()),
+                Ok(true) => {}
+                Err(ReapError::Retry(message)) => {
+                    info!(%resource, reason = %message, "Failed to check deletion, retrying")
+                }
+                Err(err) => return Err(err),
+            }
+            waiter
+                .sleep()
+                .await
+                .map_err(|e| ReapError::Fatal(format!("Still exists: {e}")))?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    /// Deletes resources after refusing a set number of times, and remembers the order.
+    #[derive(Clone, Default)]
+    struct FakeReaper {
+        refusals: Arc<Mutex<HashMap<String, usize>>>,
+        fatal: Vec<String>,
+        deleted: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Reaper for FakeReaper {
+        async fn delete(&self, resource: &Resource) -> Result<(), ReapError> {
+            if self.fatal.contains(&resource.id) {
+                return Err(ReapError::Fatal("access denied".into()));
+            }
+            let mut refusals = self.refusals.lock().unwrap();
+            match refusals.get_mut(&resource.id) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    Err(ReapError::Retry("in use".into()))
+                }
+                _ => {
+                    self.deleted.lock().unwrap().push(resource.id.clone());
+                    Ok(())
+                }
+            }
+        }
+
+        async fn exists(&self, resource: &Resource) -> Result<bool, ReapError> {
+            Ok(!self.deleted.lock().unwrap().contains(&resource.id))
+        }
+    }
+
+    fn resources() -> Vec<Resource> {
+        vec![
+            Resource::new(ResourceKind::Ec2KeyPair, "key"),
+            Resource::new(ResourceKind::Ec2SecurityGroup, "sg"),
+            Resource::new(ResourceKind::Ec2Instance, "i-1")
+                .depends_on(ResourceKind::Ec2KeyPair, "key")
+                .depends_on(ResourceKind::Ec2SecurityGroup, "sg"),
+            // Recorded out of order, but must still go before the instance.
+            Resource::new(ResourceKind::Ec2ElasticIp, "eip")
+                .depends_on(ResourceKind::Ec2Instance, "i-1"),
+        ]
+    }
+
+    #[test]
+    fn test_plan_deletes_dependents_first() {
+        let mut shuffled = resources();
+        shuffled.swap(2, 3);
+        let plan: Vec<String> = Teardown::plan(shuffled).into_iter().map(|r| r.id).collect();
+        assert_eq!(plan, vec!["eip", "i-1", "sg", "key"]);
+    }
+
+    #[tokio::test]
+    async fn test_run_retries_and_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let ledger = Ledger::open(dir.path().join("journal.jsonl"));
+        resources().into_iter().for_each(|r| ledger.created(r));
+
+        let reaper = FakeReaper {
+            refusals: Arc::new(Mutex::new(HashMap::from([("sg".to_string(), 2)]))),
+            ..Default::default()
+        };
+        let teardown = [
+            ResourceKind::Ec2KeyPair,
+            ResourceKind::Ec2SecurityGroup,
+            ResourceKind::Ec2Instance,
+            ResourceKind::Ec2ElasticIp,
+        ]
+        .into_iter()
+        .fold(Teardown::new().poll(Duration::from_millis(1)), |t, kind| {
+            t.reaper(kind, reaper.clone())
+        });
+
+        let report = teardown.run(&ledger).await.unwrap();
+
+        assert!(report.is_clean(), "{report:?}");
+        assert_eq!(
+            *reaper.deleted.lock().unwrap(),
+            vec!["eip", "i-1", "sg", "key"]
+        );
+        assert!(ledger.outstanding().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_skips_dependencies_of_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let ledger = Ledger::open(dir.path().join("journal.jsonl"));
+        resources().into_iter().for_each(|r| ledger.created(r));
+
+        let reaper = FakeReaper {
+            fatal: vec!["i-1".into()],
+            ..Default::default()
+        };
+        let teardown = Teardown::new()
+            .poll(Duration::from_millis(1))
+            .reaper(ResourceKind::Ec2ElasticIp, reaper.clone())
+            .reaper(ResourceKind::Ec2Instance, reaper.clone())
+            .reaper(ResourceKind::Ec2SecurityGroup, reaper.clone());
+
+        let report = teardown.run(&ledger).await.unwrap();
+
+        let ids = |list: &Vec<(Resource, String)>| -> Vec<String> {
+            list.iter().map(|(r, _)| r.id.clone()).collect()
+        };
+        assert_eq!(ids(&report.failed), vec!["i-1"]);
+        // The instance still uses the security group and key pair, so they're left alone.
+        assert_eq!(ids(&report.skipped), vec!["sg", "key"]);
+        assert_eq!(ledger.outstanding().unwrap().len(), 3);
+    }
+}
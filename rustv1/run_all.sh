@@ -8,6 +8,10 @@ DIRS=(
   cross_service
   examples
   lambda
+  prompter
+  resource-ledger
+  scenario-error
+  snapshot-retention
   webassembly
 )
 
@@ -0,0 +1,356 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The error type shared by the scenario examples.
+//!
+//! A ScenarioError keeps the service's error code, message, and request ID, the operation
+//! that failed, and whether the failure is worth retrying. Callers wrap it in context as it
+//! moves up the stack, and it renders the same way in every scenario.
+
+use std::fmt::Display;
+
+use aws_smithy_runtime_api::client::{orchestrator::HttpResponse, result::SdkError};
+use aws_smithy_types::error::metadata::ProvideErrorMetadata;
+
+/// Error codes services use when the caller is sending requests too quickly.
+const THROTTLING_CODES: &[&str] = &[
+    "Throttling",
+    "ThrottlingException",
+    "ThrottledException",
+    "RequestThrottledException",
+    "TooManyRequestsException",
+    "ProvisionedThroughputExceededException",
+    "RequestLimitExceeded",
+    "BandwidthLimitExceeded",
+    "RequestThrottled",
+    "SlowDown",
+    "PriorRequestNotComplete",
+    "EC2ThrottledException",
+];
+
+/// Error codes for failures that usually go away on their own.
+const TRANSIENT_CODES: &[&str] = &[
+    "RequestTimeout",
+    "RequestTimeoutException",
+    "InternalError",
+    "InternalFailure",
+    "InternalServerError",
+    "InternalServerException",
+    "InternalServiceError",
+    "ServiceUnavailable",
+    "ServiceUnavailableException",
+];
+
+/// What kind of failure an error is, and so whether to try again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The service is limiting the request rate. Retry with backoff.
+    Throttling,
+    /// The request timed out or couldn't be sent. Retry.
+    Transient,
+    /// The service failed. Retrying may help.
+    Server,
+    /// The request was invalid, or not allowed. Retrying won't help.
+    Client,
+    /// Not an SDK error, or not enough information to tell.
+    Unknown,
+}
+
+impl ErrorKind {
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            ErrorKind::Throttling | ErrorKind::Transient | ErrorKind::Server
+        )
+    }
+
+    /// Classify by error code, and HTTP status when there is one.
+    pub fn classify(code: Option<&str>, status: Option<u16>) -> Self {
+        match (code, status) {
+            (Some(code), _) if THROTTLING_CODES.contains(&code) => ErrorKind::Throttling,
+            (Some(code), _) if TRANSIENT_CODES.contains(&code) => ErrorKind::Transient,
+            (_, Some(429)) => ErrorKind::Throttling,
+            (_, Some(status)) if status >= 500 => ErrorKind::Server,
+            (_, Some(status)) if status >= 400 => ErrorKind::Client,
+            // Services only send codes for errors they report, which without a status are
+            // most often the caller's mistake.
+            (Some(_), None) => ErrorKind::Client,
+            _ => ErrorKind::Unknown,
+        }
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            ErrorKind::Throttling => "throttling",
+            ErrorKind::Transient => "transient",
+            ErrorKind::Server => "server",
+            ErrorKind::Client => "client",
+            ErrorKind::Unknown => "unknown",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+/// What the service said about a failed request.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ErrorMetadata {
+    pub code: Option<String>,
+    pub message: Option<String>,
+    pub request_id: Option<String>,
+}
+
+impl ErrorMetadata {
+    pub fn from_error(err: &dyn ProvideErrorMetadata) -> Self {
+        ErrorMetadata {
+            code: err.code().map(String::from),
+            message: err.message().map(String::from),
+            request_id: err.meta().extra("aws_request_id").map(String::from),
+        }
+    }
+}
+
+impl Display for ErrorMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let display = match (&self.message, &self.code) {
+            (None, None) => "Unknown".to_string(),
+            (None, Some(code)) => format!("({code})"),
+            (Some(message), None) => message.to_string(),
+            (Some(message), Some(code)) => format!("{message} ({code})"),
+        };
+        write!(f, "{display}")
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ScenarioError {
+    message: String,
+    operation: Option<String>,
+    /// Boxed so `Result<T, ScenarioError>` stays small.
+    metadata: Option<Box<ErrorMetadata>>,
+    kind: ErrorKind,
+    /// Context added by callers, innermost first.
+    context: Vec<String>,
+}
+
+impl ScenarioError {
+    /// An error that didn't come from the SDK.
+    pub fn with(message: impl Into<String>) -> Self {
+        ScenarioError {
+            message: message.into(),
+            operation: None,
+            metadata: None,
+            kind: ErrorKind::Unknown,
+            context: vec![],
+        }
+    }
+
+    /// An error from the SDK, classified by its error code.
+    pub fn new(message: impl Into<String>, err: &dyn ProvideErrorMetadata) -> Self {
+        let metadata = ErrorMetadata::from_error(err);
+        ScenarioError {
+            message: message.into(),
+            kind: ErrorKind::classify(metadata.code.as_deref(), None),
+            metadata: Some(Box::new(metadata)),
+            ..ScenarioError::with("")
+        }
+    }
+
+    /// An error from the SDK, described only by its metadata.
+    pub fn from_metadata(err: &dyn ProvideErrorMetadata) -> Self {
+        ScenarioError::new("", err)
+    }
+
+    /// A failed SDK call, classified by how it failed: whether the request was sent at all,
+    /// and the error code and HTTP status the service responded with.
+    pub fn from_sdk<E>(message: impl Into<String>, err: &SdkError<E, HttpResponse>) -> Self
+    where
+        E: ProvideErrorMetadata,
+    {
+        let mut metadata = ErrorMetadata::from_error(err);
+        let kind = match err {
+            SdkError::ConstructionFailure(_) => ErrorKind::Client,
+            SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) => ErrorKind::Transient,
+            SdkError::ResponseError(_) => ErrorKind::Transient,
+            SdkError::ServiceError(context) => ErrorKind::classify(
+                metadata.code.as_deref(),
+                Some(context.raw().status().as_u16()),
+            ),
+            _ => ErrorKind::classify(metadata.code.as_deref(), None),
+        };
+        if metadata.message.is_none() && metadata.code.is_none() {
+            // The request never got a service response, so describe what went wrong instead.
+            metadata.message = Some(err.to_string());
+        }
+        ScenarioError {
+            message: message.into(),
+            metadata: Some(Box::new(metadata)),
+            kind,
+            ..ScenarioError::with("")
+        }
+    }
+
+    /// Name the operation that failed.
+    pub fn operation(mut self, operation: impl Into<String>) -> Self {
+        self.operation = Some(operation.into());
+        self
+    }
+
+    /// Override the classification, for errors the caller knows more about.
+    pub fn kind(mut self, kind: ErrorKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Wrap the error in a description of what the caller was doing.
+    pub fn context(mut self, context: impl Into<String>) -> Self {
+        self.context.push(context.into());
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn operation_name(&self) -> Option<&str> {
+        self.operation.as_deref()
+    }
+
+    pub fn metadata(&self) -> Option<&ErrorMetadata> {
+        self.metadata.as_deref()
+    }
+
+    pub fn code(&self) -> Option<&str> {
+        self.metadata.as_ref().and_then(|m| m.code.as_deref())
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.metadata.as_ref().and_then(|m| m.request_id.as_deref())
+    }
+
+    pub fn error_kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.kind.is_retryable()
+    }
+
+    pub fn is_throttling(&self) -> bool {
+        self.kind == ErrorKind::Throttling
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+/// `outer context: inner context: message: service message (Code) [Operation, request id]`
+impl Display for ScenarioError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts: Vec<String> = self.context.iter().rev().cloned().collect();
+        if !self.message.is_empty() {
+            parts.push(self.message.clone());
+        }
+        if let Some(metadata) = &self.metadata {
+            parts.push(metadata.to_string());
+        }
+        write!(f, "{}", parts.join(": "))?;
+
+        let details: Vec<&str> = [self.operation.as_deref(), self.request_id()]
+            .into_iter()
+            .flatten()
+            .collect();
+        if !details.is_empty() {
+            write!(f, " [{}]", details.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Add context to any error that converts to a ScenarioError.
+pub trait ResultExt<T> {
+    fn context(self, context: impl Into<String>) -> Result<T, ScenarioError>;
+}
+
+impl<T, E: Into<ScenarioError>> ResultExt<T> for Result<T, E> {
+    fn context(self, context: impl Into<String>) -> Result<T, ScenarioError> {
+        self.map_err(|err| err.into().context(context))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aws_smithy_runtime_api::http::StatusCode;
+    use aws_smithy_types::{body::SdkBody, error::ErrorMetadata as SdkErrorMetadata};
+
+    use super::*;
+
+    fn service_error(code: &str, status: u16) -> SdkError<SdkErrorMetadata, HttpResponse> {
+        SdkError::service_error(
+            SdkErrorMetadata::builder()
+                .code(code)
+                .message("Something went wrong")
+                .custom("aws_request_id", "req-1234")
+                .build(),
+            HttpResponse::new(StatusCode::try_from(status).unwrap(), SdkBody::empty()),
+        )
+    }
+
+    #[test]
+    fn test_from_sdk_classifies() {
+        let throttled = ScenarioError::from_sdk("", &service_error("Throttling", 400));
+        assert_eq!(throttled.error_kind(), ErrorKind::Throttling);
+        assert!(throttled.is_retryable());
+
+        let server = ScenarioError::from_sdk("", &service_error("Oops", 503));
+        assert_eq!(server.error_kind(), ErrorKind::Server);
+
+        let client = ScenarioError::from_sdk(
+            "Failed to describe instances",
+            &service_error("Invalid", 400),
+        )
+        .operation("DescribeInstances");
+        assert_eq!(client.error_kind(), ErrorKind::Client);
+        assert!(!client.is_retryable());
+        assert_eq!(client.code(), Some("Invalid"));
+        assert_eq!(client.request_id(), Some("req-1234"));
+        assert_eq!(client.operation_name(), Some("DescribeInstances"));
+        assert_eq!(
+            client.to_string(),
+            "Failed to describe instances: Something went wrong (Invalid) [DescribeInstances, req-1234]"
+        );
+
+        let construction: SdkError<SdkErrorMetadata, HttpResponse> =
+            SdkError::construction_failure("missing field");
+        assert_eq!(
+            ScenarioError::from_sdk("", &construction).error_kind(),
+            ErrorKind::Client
+        );
+
+        let timeout: SdkError<SdkErrorMetadata, HttpResponse> =
+            SdkError::timeout_error("took too long");
+        let timeout = ScenarioError::from_sdk("", &timeout);
+        assert_eq!(timeout.error_kind(), ErrorKind::Transient);
+        assert!(timeout.is_retryable());
+    }
+
+    #[test]
+    fn test_display_chains_context() {
+        let err: Result<(), ScenarioError> = Err(ScenarioError::new(
+            "Failed to create cluster",
+            &service_error("DBClusterAlreadyExistsFault", 400),
+        )
+        .operation("CreateDBCluster"));
+        let err = err.context("Failed to start scenario").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to start scenario: Failed to create cluster: Something went wrong (DBClusterAlreadyExistsFault) [CreateDBCluster, req-1234]"
+        );
+        assert_eq!(err.message(), "Failed to create cluster");
+
+        assert_eq!(
+            ScenarioError::with("Missing Snapshot").to_string(),
+            "Missing Snapshot"
+        );
+    }
+}
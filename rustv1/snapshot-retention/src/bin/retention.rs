@@ -0,0 +1,243 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Apply a retention policy to the snapshots of an Aurora DB cluster or an EBS volume.
+//!
+//! Without `--apply`, this only reports what it would delete. With a copy destination, the
+//! newest snapshot the policy keeps is copied there before anything is deleted.
+
+use std::{process::ExitCode, time::Duration};
+
+use aws_config::{meta::region::RegionProviderChain, sts::AssumeRoleProvider, Region, SdkConfig};
+use clap::{Parser, ValueEnum};
+use sdk_examples_snapshot_retention::{
+    copy_snapshot, CopyRequest, EbsSnapshots, RdsClusterSnapshots, Retention, RetentionPolicy,
+    SnapshotStore, TagRule,
+};
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Service {
+    /// Aurora DB cluster snapshots. The source is the cluster identifier.
+    Aurora,
+    /// EBS snapshots. The source is the volume ID.
+    Ebs,
+}
+
+#[derive(Debug, Parser)]
+struct Opt {
+    /// The AWS Region.
+    #[structopt(short, long)]
+    region: Option<String>,
+
+    /// Which kind of snapshots to manage.
+    #[structopt(long, value_enum)]
+    service: Service,
+
+    /// The DB cluster identifier or volume ID whose snapshots to manage.
+    #[structopt(short, long)]
+    source: String,
+
+    /// Keep this many of the newest snapshots.
+    #[structopt(long, default_value = "0")]
+    keep_latest: usize,
+
+    /// Keep the newest snapshot of each of this many days.
+    #[structopt(long, default_value = "0")]
+    keep_daily: usize,
+
+    /// Keep the newest snapshot of each of this many weeks.
+    #[structopt(long, default_value = "0")]
+    keep_weekly: usize,
+
+    /// Keep snapshots with this tag, as KEY or KEY=VALUE. Can be repeated.
+    #[structopt(long)]
+    keep_tag: Vec<TagRule>,
+
+    /// Never delete snapshots younger than this many days.
+    #[structopt(long, default_value = "0")]
+    min_age_days: u64,
+
+    /// Copy the newest kept snapshot to this region.
+    #[structopt(long)]
+    copy_to_region: Option<String>,
+
+    /// Copy the newest kept snapshot to this account. Needs --copy-role-arn.
+    #[structopt(long)]
+    copy_to_account: Option<String>,
+
+    /// A role in the destination account to make the copy with.
+    #[structopt(long)]
+    copy_role_arn: Option<String>,
+
+    /// The KMS key, in the destination, to encrypt the copy with.
+    #[structopt(long)]
+    copy_kms_key_id: Option<String>,
+
+    /// Delete the expired snapshots. Without this, only report what would be deleted.
+    #[structopt(long)]
+    apply: bool,
+
+    /// Whether to display additional information.
+    #[structopt(short, long)]
+    verbose: bool,
+}
+
+impl Opt {
+    fn policy(&self) -> RetentionPolicy {
+        self.keep_tag.iter().cloned().fold(
+            RetentionPolicy::new()
+                .keep_latest(self.keep_latest)
+                .keep_daily(self.keep_daily)
+                .keep_weekly(self.keep_weekly)
+                .min_age(Duration::from_secs(self.min_age_days * 24 * 60 * 60)),
+            RetentionPolicy::keep_tagged,
+        )
+    }
+
+    fn wants_copy(&self) -> bool {
+        self.copy_to_region.is_some() || self.copy_to_account.is_some()
+    }
+}
+
+async fn destination_config(opt: &Opt, source_region: &str) -> SdkConfig {
+    let region = Region::new(
+        opt.copy_to_region
+            .clone()
+            .unwrap_or_else(|| source_region.to_string()),
+    );
+    let config = aws_config::from_env().region(region.clone()).load().await;
+    match &opt.copy_role_arn {
+        Some(role_arn) => {
+            let provider = AssumeRoleProvider::builder(role_arn)
+                .session_name("rust_sdk_example_snapshot_retention")
+                .region(region.clone())
+                .configure(&config)
+                .build()
+                .await;
+            aws_config::from_env()
+                .region(region)
+                .credentials_provider(provider)
+                .load()
+                .await
+        }
+        None => config,
+    }
+}
+
+async fn run<S: SnapshotStore>(
+    opt: &Opt,
+    source_region: &str,
+    store: S,
+    destination: Option<S>,
+) -> ExitCode {
+    let retention = Retention::new(store, opt.policy());
+    let plan = match retention.plan(&opt.source).await {
+        Ok(plan) => plan,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    print!("{plan}");
+
+    if let Some(destination) = destination {
+        let Some(newest) = plan.newest_kept() else {
+            eprintln!("{} has no finished snapshot to copy.", opt.source);
+            if !opt.apply {
+                // Still show what the dry run would have deleted.
+                print!("{}", retention.apply(&plan, true).await);
+            }
+            return ExitCode::FAILURE;
+        };
+        let request = CopyRequest {
+            source_region: source_region.to_string(),
+            copy_id: format!("{}-copy", newest.id),
+            kms_key_id: opt.copy_kms_key_id.clone(),
+            destination_account: opt.copy_to_account.clone(),
+        };
+        if opt.apply {
+            match copy_snapshot(retention.store(), &destination, newest, &request).await {
+                Ok(copy) => println!("Copied {} to {copy}", newest.id),
+                Err(err) => {
+                    // Don't delete anything when the backup copy failed.
+                    eprintln!("{err}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        } else {
+            println!("Would copy {} as {}", newest.id, request.copy_id);
+        }
+    }
+
+    let report = retention.apply(&plan, !opt.apply).await;
+    print!("{report}");
+    if report.is_success() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Keeps the snapshots a retention policy names, and deletes the rest.
+/// # Arguments
+///
+/// * `--service aurora|ebs` - Which kind of snapshots to manage.
+/// * `-s SOURCE` - The DB cluster identifier or volume ID.
+/// * `[--keep-latest N] [--keep-daily N] [--keep-weekly N]` - How many snapshots to keep.
+/// * `[--keep-tag KEY[=VALUE]]` - Keep snapshots with this tag. Can be repeated.
+/// * `[--min-age-days N]` - Never delete snapshots younger than this.
+/// * `[--copy-to-region REGION] [--copy-to-account ACCOUNT --copy-role-arn ARN]` - Copy the
+///    newest kept snapshot before deleting.
+/// * `[--copy-kms-key-id KEY]` - The key to encrypt the copy with.
+/// * `[--apply]` - Delete, and copy. Without it, only report.
+/// * `[-r REGION]` - The Region in which the client is created.
+///    If not supplied, uses the value of the **AWS_REGION** environment variable.
+///    If the environment variable is not set, defaults to **us-west-2**.
+/// * `[-v]` - Whether to display additional information.
+#[tokio::main]
+async fn main() -> ExitCode {
+    let opt = Opt::parse();
+    if opt.verbose {
+        tracing_subscriber::fmt::init();
+    }
+    if opt.copy_to_account.is_some() && opt.copy_role_arn.is_none() {
+        eprintln!("--copy-to-account needs --copy-role-arn to make the copy in that account.");
+        return ExitCode::FAILURE;
+    }
+
+    let region_provider = RegionProviderChain::first_try(opt.region.clone().map(Region::new))
+        .or_default_provider()
+        .or_else(Region::new("us-west-2"));
+    let sdk_config = aws_config::from_env().region(region_provider).load().await;
+    let source_region = sdk_config
+        .region()
+        .map(|r| r.to_string())
+        .unwrap_or_default();
+    let destination_config = if opt.wants_copy() {
+        Some(destination_config(&opt, &source_region).await)
+    } else {
+        None
+    };
+
+    match opt.service {
+        Service::Aurora => {
+            run(
+                &opt,
+                &source_region,
+                RdsClusterSnapshots(aws_sdk_rds::Client::new(&sdk_config)),
+                destination_config
+                    .map(|config| RdsClusterSnapshots(aws_sdk_rds::Client::new(&config))),
+            )
+            .await
+        }
+        Service::Ebs => {
+            run(
+                &opt,
+                &source_region,
+                EbsSnapshots(aws_sdk_ec2::Client::new(&sdk_config)),
+                destination_config.map(|config| EbsSnapshots(aws_sdk_ec2::Client::new(&config))),
+            )
+            .await
+        }
+    }
+}
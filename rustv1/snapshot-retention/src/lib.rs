@@ -0,0 +1,348 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Keep a cluster's or a volume's snapshots under a retention policy: list them, decide which
+//! to keep, copy the ones worth keeping to another region or account, and delete the rest.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    time::{Duration, SystemTime},
+};
+
+use async_trait::async_trait;
+use sdk_examples_scenario_error::ScenarioError;
+use tracing::info;
+
+pub mod policy;
+pub mod stores;
+
+pub use policy::{KeepReason, Kept, RetentionPlan, RetentionPolicy, TagRule};
+pub use stores::{EbsSnapshots, RdsClusterSnapshots};
+
+/// A snapshot of an Aurora DB cluster or an EBS volume, with what retention needs to know.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    pub id: String,
+    /// Cross-region copies of cluster snapshots name their source by ARN.
+    pub arn: Option<String>,
+    /// The cluster or volume the snapshot is of.
+    pub source_id: String,
+    pub created_at: SystemTime,
+    pub state: String,
+    pub encrypted: bool,
+    pub kms_key_id: Option<String>,
+    pub tags: BTreeMap<String, String>,
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Snapshot {
+            id: String::new(),
+            arn: None,
+            source_id: String::new(),
+            created_at: SystemTime::UNIX_EPOCH,
+            state: String::new(),
+            encrypted: false,
+            kms_key_id: None,
+            tags: BTreeMap::new(),
+        }
+    }
+}
+
+impl Snapshot {
+    /// Cluster snapshots are `available` when finished, and EBS snapshots `completed`.
+    pub fn is_available(&self) -> bool {
+        self.state == "available" || self.state == "completed"
+    }
+}
+
+/// The snapshots of one service, in one region and account.
+#[async_trait]
+pub trait SnapshotStore: Send + Sync {
+    /// The manual snapshots of the cluster or volume `source`.
+    async fn list(&self, source: &str) -> Result<Vec<Snapshot>, ScenarioError>;
+
+    async fn delete(&self, snapshot: &Snapshot) -> Result<(), ScenarioError>;
+
+    /// Copy `snapshot` from `source_region` into this store, encrypting the copy with
+    /// `kms_key_id` when it's set. Returns the copy's ID.
+    async fn copy_from(
+        &self,
+        snapshot: &Snapshot,
+        source_region: &str,
+        copy_id: &str,
+        kms_key_id: Option<&str>,
+    ) -> Result<String, ScenarioError>;
+
+    /// Let `account` copy or restore the snapshot.
+    async fn share(&self, snapshot: &Snapshot, account: &str) -> Result<(), ScenarioError>;
+}
+
+/// Where to copy a snapshot.
+#[derive(Clone, Debug, Default)]
+pub struct CopyRequest {
+    /// The region of the snapshot being copied.
+    pub source_region: String,
+    /// The ID, or for EBS the description, to give the copy.
+    pub copy_id: String,
+    /// The key to encrypt the copy with, in the destination's region and account.
+    pub kms_key_id: Option<String>,
+    /// When the destination is another account, the source shares the snapshot with it first.
+    /// An encrypted snapshot can only be shared if its key is a customer managed key that the
+    /// other account is allowed to use.
+    pub destination_account: Option<String>,
+}
+
+/// Copy `snapshot` from the `source` store to the `destination` store, which can be in another
+/// region, another account, or both. Returns the copy's ID.
+pub async fn copy_snapshot(
+    source: &dyn SnapshotStore,
+    destination: &dyn SnapshotStore,
+    snapshot: &Snapshot,
+    request: &CopyRequest,
+) -> Result<String, ScenarioError> {
+    if !snapshot.is_available() {
+        return Err(ScenarioError::with(format!(
+            "Snapshot {} is {}, and can't be copied until it's finished",
+            snapshot.id, snapshot.state
+        )));
+    }
+    if let Some(account) = &request.destination_account {
+        source.share(snapshot, account).await?;
+    }
+    let copy = destination
+        .copy_from(
+            snapshot,
+            &request.source_region,
+            &request.copy_id,
+            request.kms_key_id.as_deref(),
+        )
+        .await?;
+    info!(snapshot = %snapshot.id, %copy, "Copied snapshot");
+    Ok(copy)
+}
+
+/// What applying a plan did, or in a dry run, would do.
+#[derive(Debug, Default)]
+pub struct RetentionReport {
+    pub source: String,
+    pub dry_run: bool,
+    pub kept: usize,
+    pub deleted: Vec<String>,
+    pub failed: Vec<(String, ScenarioError)>,
+}
+
+impl RetentionReport {
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+impl Display for RetentionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let verb = if self.dry_run {
+            "would delete"
+        } else {
+            "deleted"
+        };
+        writeln!(
+            f,
+            "{}: kept {}, {verb} {}, failed {}",
+            self.source,
+            self.kept,
+            self.deleted.len(),
+            self.failed.len()
+        )?;
+        for id in &self.deleted {
+            writeln!(f, "  {verb} {id}")?;
+        }
+        for (id, err) in &self.failed {
+            writeln!(f, "  failed {id}: {err}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Applies a retention policy to the snapshots in a store.
+pub struct Retention<S> {
+    store: S,
+    policy: RetentionPolicy,
+}
+
+impl<S: SnapshotStore> Retention<S> {
+    pub fn new(store: S, policy: RetentionPolicy) -> Self {
+        Retention { store, policy }
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    pub async fn plan(&self, source: &str) -> Result<RetentionPlan, ScenarioError> {
+        let snapshots = self.store.list(source).await?;
+        Ok(self.policy.plan(source, snapshots, SystemTime::now()))
+    }
+
+    /// Delete the plan's expired snapshots, continuing past failures. A dry run deletes nothing,
+    /// and reports what it would have deleted.
+    pub async fn apply(&self, plan: &RetentionPlan, dry_run: bool) -> RetentionReport {
+        let mut report = RetentionReport {
+            source: plan.source.clone(),
+            dry_run,
+            kept: plan.keep.len(),
+            ..Default::default()
+        };
+        for snapshot in &plan.expire {
+            if dry_run {
+                report.deleted.push(snapshot.id.clone());
+                continue;
+            }
+            match self.store.delete(snapshot).await {
+                Ok(()) => {
+                    info!(snapshot = %snapshot.id, "Deleted expired snapshot");
+                    report.deleted.push(snapshot.id.clone());
+                }
+                Err(err) => report.failed.push((snapshot.id.clone(), err)),
+            }
+        }
+        report
+    }
+}
+
+/// Convert an SDK timestamp, which is missing for snapshots still starting.
+pub(crate) fn system_time(secs: Option<i64>) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(secs.unwrap_or(0).max(0) as u64)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    /// Holds snapshots in memory, refusing to delete the IDs in `in_use`.
+    #[derive(Clone, Default)]
+    struct FakeStore {
+        snapshots: Arc<Mutex<Vec<Snapshot>>>,
+        in_use: Vec<String>,
+        shared: Arc<Mutex<Vec<(String, String)>>>,
+    }
+
+    #[async_trait]
+    impl SnapshotStore for FakeStore {
+        async fn list(&self, source: &str) -> Result<Vec<Snapshot>, ScenarioError> {
+            Ok(self
+                .snapshots
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|s| s.source_id == source)
+                .cloned()
+                .collect())
+        }
+
+        async fn delete(&self, snapshot: &Snapshot) -> Result<(), ScenarioError> {
+            if self.in_use.contains(&snapshot.id) {
+                return Err(ScenarioError::with("snapshot in use"));
+            }
+            self.snapshots
+                .lock()
+                .unwrap()
+                .retain(|s| s.id != snapshot.id);
+            Ok(())
+        }
+
+        async fn copy_from(
+            &self,
+            snapshot: &Snapshot,
+            _source_region: &str,
+            copy_id: &str,
+            kms_key_id: Option<&str>,
+        ) -> Result<String, ScenarioError> {
+            self.snapshots.lock().unwrap().push(Snapshot {
+                id: copy_id.into(),
+                encrypted: kms_key_id.is_some() || snapshot.encrypted,
+                kms_key_id: kms_key_id.map(String::from),
+                ..snapshot.clone()
+            });
+            Ok(copy_id.into())
+        }
+
+        async fn share(&self, snapshot: &Snapshot, account: &str) -> Result<(), ScenarioError> {
+            self.shared
+                .lock()
+                .unwrap()
+                .push((snapshot.id.clone(), account.into()));
+            Ok(())
+        }
+    }
+
+    fn snapshot(id: &str, days_ago: u64) -> Snapshot {
+        Snapshot {
+            id: id.into(),
+            source_id: "cluster".into(),
+            created_at: SystemTime::now() - Duration::from_secs(days_ago * 24 * 60 * 60),
+            state: "available".into(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_apply_deletes_expired_and_reports_failures() {
+        let store = FakeStore {
+            snapshots: Arc::new(Mutex::new(vec![
+                snapshot("a", 1),
+                snapshot("b", 2),
+                snapshot("c", 3),
+                snapshot("d", 4),
+            ])),
+            in_use: vec!["d".into()],
+            ..Default::default()
+        };
+        let retention = Retention::new(store.clone(), RetentionPolicy::new().keep_latest(2));
+
+        let plan = retention.plan("cluster").await.unwrap();
+        let dry_run = retention.apply(&plan, true).await;
+        assert_eq!(dry_run.deleted, vec!["c", "d"]);
+        assert_eq!(store.snapshots.lock().unwrap().len(), 4);
+
+        let report = retention.apply(&plan, false).await;
+        assert_eq!(report.kept, 2);
+        assert_eq!(report.deleted, vec!["c"]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, "d");
+        assert!(!report.is_success());
+    }
+
+    #[tokio::test]
+    async fn test_copy_shares_then_copies() {
+        let source = FakeStore::default();
+        let destination = FakeStore::default();
+        let request = CopyRequest {
+            source_region: "us-west-2".into(),
+            copy_id: "a-copy".into(),
+            kms_key_id: Some("alias/backup".into()),
+            destination_account: Some("111122223333".into()),
+        };
+
+        let copy = copy_snapshot(&source, &destination, &snapshot("a", 0), &request)
+            .await
+            .unwrap();
+
+        assert_eq!(copy, "a-copy");
+        assert_eq!(
+            *source.shared.lock().unwrap(),
+            vec![("a".to_string(), "111122223333".to_string())]
+        );
+        let copies = destination.snapshots.lock().unwrap();
+        assert_eq!(copies[0].kms_key_id.as_deref(), Some("alias/backup"));
+        assert!(copies[0].encrypted);
+
+        let mut pending = snapshot("b", 0);
+        pending.state = "creating".into();
+        assert!(copy_snapshot(&source, &destination, &pending, &request)
+            .await
+            .is_err());
+    }
+}
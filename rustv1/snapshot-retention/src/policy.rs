@@ -0,0 +1,404 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Retention rules, and the plan of which snapshots they keep and which have expired.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    time::{Duration, SystemTime},
+};
+
+use crate::Snapshot;
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// Why a snapshot is kept. A snapshot can be kept for more than one reason.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum KeepReason {
+    /// One of the newest snapshots.
+    Latest,
+    /// The newest snapshot of a day, with the day's UTC date.
+    Daily(String),
+    /// The newest snapshot of a week, with the UTC date of the week's Monday.
+    Weekly(String),
+    /// Has a tag the policy protects.
+    Tagged(String),
+    /// Younger than the policy's minimum age.
+    TooNew,
+    /// Not finished, so it can't be deleted yet.
+    NotAvailable(String),
+}
+
+impl Display for KeepReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeepReason::Latest => write!(f, "latest"),
+            KeepReason::Daily(day) => write!(f, "daily {day}"),
+            KeepReason::Weekly(week) => write!(f, "weekly {week}"),
+            KeepReason::Tagged(tag) => write!(f, "tagged {tag}"),
+            KeepReason::TooNew => write!(f, "too new"),
+            KeepReason::NotAvailable(state) => write!(f, "{state}"),
+        }
+    }
+}
+
+/// A tag that protects snapshots. Without a value, any value of the key protects them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TagRule {
+    pub key: String,
+    pub value: Option<String>,
+}
+
+impl TagRule {
+    fn matches(&self, snapshot: &Snapshot) -> bool {
+        match (snapshot.tags.get(&self.key), &self.value) {
+            (Some(_), None) => true,
+            (Some(actual), Some(wanted)) => actual == wanted,
+            (None, _) => false,
+        }
+    }
+}
+
+impl Display for TagRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{}={value}", self.key),
+            None => write!(f, "{}", self.key),
+        }
+    }
+}
+
+impl std::str::FromStr for TagRule {
+    type Err = String;
+
+    /// Parse `key` or `key=value`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = match s.split_once('=') {
+            Some((key, value)) => (key, Some(value.to_string())),
+            None => (s, None),
+        };
+        if key.is_empty() {
+            return Err(format!("Tag rule {s:?} has no key"));
+        }
+        Ok(TagRule {
+            key: key.to_string(),
+            value,
+        })
+    }
+}
+
+/// Which snapshots to keep. Every snapshot no rule keeps has expired.
+///
+/// A policy with no rules would expire every snapshot, so `plan` keeps them all instead.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub keep_latest: usize,
+    pub keep_daily: usize,
+    pub keep_weekly: usize,
+    pub keep_tagged: Vec<TagRule>,
+    pub min_age: Duration,
+}
+
+impl RetentionPolicy {
+    pub fn new() -> Self {
+        RetentionPolicy::default()
+    }
+
+    pub fn keep_latest(mut self, count: usize) -> Self {
+        self.keep_latest = count;
+        self
+    }
+
+    /// Keep the newest snapshot of each of the `days` most recent days that have a snapshot.
+    pub fn keep_daily(mut self, days: usize) -> Self {
+        self.keep_daily = days;
+        self
+    }
+
+    /// Keep the newest snapshot of each of the `weeks` most recent weeks that have a snapshot.
+    pub fn keep_weekly(mut self, weeks: usize) -> Self {
+        self.keep_weekly = weeks;
+        self
+    }
+
+    pub fn keep_tagged(mut self, rule: TagRule) -> Self {
+        self.keep_tagged.push(rule);
+        self
+    }
+
+    /// Never expire a snapshot younger than this.
+    pub fn min_age(mut self, age: Duration) -> Self {
+        self.min_age = age;
+        self
+    }
+
+    fn is_empty(&self) -> bool {
+        self.keep_latest == 0
+            && self.keep_daily == 0
+            && self.keep_weekly == 0
+            && self.keep_tagged.is_empty()
+            && self.min_age.is_zero()
+    }
+
+    /// Sort snapshots into kept and expired, as of `now`.
+    pub fn plan(&self, source: &str, snapshots: Vec<Snapshot>, now: SystemTime) -> RetentionPlan {
+        let mut snapshots = snapshots;
+        // Newest first, so the first snapshot seen in a day or week is the one to keep.
+        snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+        let mut reasons: Vec<BTreeSet<KeepReason>> = vec![BTreeSet::new(); snapshots.len()];
+        let mut days: BTreeMap<u64, usize> = BTreeMap::new();
+        let mut weeks: BTreeMap<u64, usize> = BTreeMap::new();
+
+        for (index, snapshot) in snapshots.iter().enumerate() {
+            let reasons = &mut reasons[index];
+            if !snapshot.is_available() {
+                reasons.insert(KeepReason::NotAvailable(snapshot.state.clone()));
+            }
+            if index < self.keep_latest {
+                reasons.insert(KeepReason::Latest);
+            }
+            if now
+                .duration_since(snapshot.created_at)
+                .unwrap_or(Duration::ZERO)
+                < self.min_age
+            {
+                reasons.insert(KeepReason::TooNew);
+            }
+            for rule in &self.keep_tagged {
+                if rule.matches(snapshot) {
+                    reasons.insert(KeepReason::Tagged(rule.to_string()));
+                }
+            }
+
+            let day = epoch_day(snapshot.created_at);
+            if days.len() < self.keep_daily && !days.contains_key(&day) {
+                days.insert(day, index);
+                reasons.insert(KeepReason::Daily(date(day)));
+            }
+            let week = week_start(day);
+            if weeks.len() < self.keep_weekly && !weeks.contains_key(&week) {
+                weeks.insert(week, index);
+                reasons.insert(KeepReason::Weekly(date(week)));
+            }
+        }
+
+        let mut plan = RetentionPlan {
+            source: source.to_string(),
+            keep: vec![],
+            expire: vec![],
+        };
+        for (snapshot, reasons) in snapshots.into_iter().zip(reasons) {
+            if reasons.is_empty() && !self.is_empty() {
+                plan.expire.push(snapshot);
+            } else {
+                plan.keep.push(Kept {
+                    snapshot,
+                    reasons: reasons.into_iter().collect(),
+                });
+            }
+        }
+        plan
+    }
+}
+
+/// Days since the Unix epoch, in UTC.
+fn epoch_day(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs()
+        / DAY
+}
+
+/// The Monday on or before `day`. The epoch was a Thursday.
+fn week_start(day: u64) -> u64 {
+    day - (day + 3) % 7
+}
+
+/// The UTC date of a day since the epoch, as YYYY-MM-DD.
+fn date(day: u64) -> String {
+    // Howard Hinnant's days-to-civil algorithm.
+    let z = day as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Kept {
+    pub snapshot: Snapshot,
+    pub reasons: Vec<KeepReason>,
+}
+
+/// What a policy keeps and expires for one cluster or volume.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetentionPlan {
+    pub source: String,
+    pub keep: Vec<Kept>,
+    pub expire: Vec<Snapshot>,
+}
+
+impl RetentionPlan {
+    /// The newest finished snapshot the plan keeps, which is the one worth copying.
+    pub fn newest_kept(&self) -> Option<&Snapshot> {
+        self.keep
+            .iter()
+            .map(|kept| &kept.snapshot)
+            .find(|snapshot| snapshot.is_available())
+    }
+}
+
+impl Display for RetentionPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}: keep {}, expire {}",
+            self.source,
+            self.keep.len(),
+            self.expire.len()
+        )?;
+        for kept in &self.keep {
+            let reasons = kept
+                .reasons
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            writeln!(
+                f,
+                "  keep   {} {} ({})",
+                kept.snapshot.id,
+                date(epoch_day(kept.snapshot.created_at)),
+                if reasons.is_empty() {
+                    "no rules".to_string()
+                } else {
+                    reasons.join(", ")
+                }
+            )?;
+        }
+        for snapshot in &self.expire {
+            writeln!(
+                f,
+                "  expire {} {}",
+                snapshot.id,
+                date(epoch_day(snapshot.created_at))
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn at(day: u64, hour: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(day * DAY + hour * 60 * 60)
+    }
+
+    fn snapshot(id: &str, created_at: SystemTime) -> Snapshot {
+        Snapshot {
+            id: id.into(),
+            source_id: "vol-1".into(),
+            created_at,
+            state: "completed".into(),
+            ..Default::default()
+        }
+    }
+
+    fn kept_ids(plan: &RetentionPlan) -> Vec<&str> {
+        plan.keep.iter().map(|k| k.snapshot.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_date() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(19_723), "2024-01-01");
+        assert_eq!(date(week_start(19_723)), "2024-01-01");
+        assert_eq!(date(week_start(19_729)), "2024-01-01");
+        assert_eq!(date(week_start(19_730)), "2024-01-08");
+    }
+
+    #[test]
+    fn test_daily_and_weekly() {
+        // Two snapshots a day for three weeks, starting on Monday 2024-01-01.
+        let snapshots = (19_723..19_744)
+            .flat_map(|day| {
+                [
+                    snapshot(&format!("snap-{day}-am"), at(day, 6)),
+                    snapshot(&format!("snap-{day}-pm"), at(day, 18)),
+                ]
+            })
+            .collect();
+
+        let plan = RetentionPolicy::new().keep_daily(3).keep_weekly(3).plan(
+            "vol-1",
+            snapshots,
+            at(19_744, 0),
+        );
+
+        assert_eq!(
+            kept_ids(&plan),
+            vec![
+                "snap-19743-pm",
+                "snap-19742-pm",
+                "snap-19741-pm",
+                "snap-19736-pm",
+                "snap-19729-pm",
+            ]
+        );
+        assert_eq!(
+            plan.keep[0].reasons,
+            vec![
+                KeepReason::Daily("2024-01-21".into()),
+                KeepReason::Weekly("2024-01-15".into()),
+            ]
+        );
+        assert_eq!(plan.expire.len(), 42 - 5);
+    }
+
+    #[test]
+    fn test_tags_age_and_state() {
+        let mut tagged = snapshot("tagged", at(1, 0));
+        tagged.tags.insert("retain".into(), "forever".into());
+        let mut pending = snapshot("pending", at(2, 0));
+        pending.state = "pending".into();
+        let snapshots = vec![
+            tagged,
+            pending,
+            snapshot("old", at(3, 0)),
+            snapshot("new", at(9, 0)),
+        ];
+
+        let plan = RetentionPolicy::new()
+            .keep_tagged("retain=forever".parse().unwrap())
+            .min_age(Duration::from_secs(2 * DAY))
+            .plan("vol-1", snapshots, at(10, 0));
+
+        assert_eq!(kept_ids(&plan), vec!["new", "pending", "tagged"]);
+        assert_eq!(
+            plan.expire
+                .iter()
+                .map(|s| s.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["old"]
+        );
+    }
+
+    #[test]
+    fn test_empty_policy_expires_nothing() {
+        let plan = RetentionPolicy::new().plan(
+            "vol-1",
+            vec![snapshot("a", at(1, 0)), snapshot("b", at(2, 0))],
+            at(3, 0),
+        );
+        assert_eq!(plan.keep.len(), 2);
+        assert!(plan.expire.is_empty());
+    }
+}
@@ -0,0 +1,383 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Snapshot stores for Aurora DB cluster snapshots and EBS volume snapshots.
+//!
+//! Each store works in the region and account of the client it's made with. To copy across
+//! regions or accounts, make a second store with a client for the destination.
+
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use aws_credential_types::{provider::ProvideCredentials, Credentials};
+use aws_sdk_ec2::types::{Filter, OperationType, SnapshotAttributeName};
+use aws_sigv4::{
+    http_request::{sign, SignableBody, SignableRequest, SignatureLocation, SigningSettings},
+    sign::v4,
+};
+use sdk_examples_scenario_error::ScenarioError;
+
+use crate::{system_time, Snapshot, SnapshotStore};
+
+#[derive(Clone)]
+pub struct RdsClusterSnapshots(pub aws_sdk_rds::Client);
+
+#[async_trait]
+impl SnapshotStore for RdsClusterSnapshots {
+    // snippet-start:[rust.snapshot_retention.rds.list]
+    async fn list(&self, source: &str) -> Result<Vec<Snapshot>, ScenarioError> {
+        let pages: Vec<_> = self
+            .0
+            .describe_db_cluster_snapshots()
+            .db_cluster_identifier(source)
+            // Automated snapshots expire with the cluster's backup retention period.
+            .snapshot_type("manual")
+            .into_paginator()
+            .send()
+            .try_collect()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to list snapshots of {source}"), &err)
+                    .operation("DescribeDBClusterSnapshots")
+            })?;
+
+        Ok(pages
+            .iter()
+            .flat_map(|page| page.db_cluster_snapshots())
+            .map(|snapshot| Snapshot {
+                id: snapshot
+                    .db_cluster_snapshot_identifier()
+                    .unwrap_or_default()
+                    .to_string(),
+                arn: snapshot.db_cluster_snapshot_arn().map(String::from),
+                source_id: snapshot
+                    .db_cluster_identifier()
+                    .unwrap_or(source)
+                    .to_string(),
+                created_at: system_time(snapshot.snapshot_create_time().map(|t| t.secs())),
+                state: snapshot.status().unwrap_or_default().to_string(),
+                encrypted: snapshot.storage_encrypted().unwrap_or(false),
+                kms_key_id: snapshot.kms_key_id().map(String::from),
+                tags: snapshot
+                    .tag_list()
+                    .iter()
+                    .filter_map(|tag| Some((tag.key()?.to_string(), tag.value()?.to_string())))
+                    .collect(),
+            })
+            .collect())
+    }
+    // snippet-end:[rust.snapshot_retention.rds.list]
+
+    async fn delete(&self, snapshot: &Snapshot) -> Result<(), ScenarioError> {
+        self.0
+            .delete_db_cluster_snapshot()
+            .db_cluster_snapshot_identifier(&snapshot.id)
+            .send()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to delete {}", snapshot.id), &err)
+                    .operation("DeleteDBClusterSnapshot")
+            })?;
+        Ok(())
+    }
+
+    // snippet-start:[rust.snapshot_retention.rds.copy]
+    async fn copy_from(
+        &self,
+        snapshot: &Snapshot,
+        source_region: &str,
+        copy_id: &str,
+        kms_key_id: Option<&str>,
+    ) -> Result<String, ScenarioError> {
+        // Copies from another region or account must name the source by ARN.
+        let source = snapshot.arn.as_deref().unwrap_or(&snapshot.id);
+        let pre_signed_url = match self.0.config().region() {
+            Some(region) if snapshot.encrypted && region.as_ref() != source_region => {
+                let kms_key_id = kms_key_id.ok_or_else(|| {
+                    ScenarioError::with(format!(
+                        "{} is encrypted, so copying it to {region} needs a key there",
+                        snapshot.id
+                    ))
+                })?;
+                let credentials = self.credentials().await?;
+                Some(presign_cluster_copy(
+                    &credentials,
+                    &ClusterCopy {
+                        source_region,
+                        destination_region: region.as_ref(),
+                        source,
+                        kms_key_id,
+                    },
+                    SystemTime::now(),
+                )?)
+            }
+            _ => None,
+        };
+        let copy = self
+            .0
+            .copy_db_cluster_snapshot()
+            .source_db_cluster_snapshot_identifier(source)
+            .target_db_cluster_snapshot_identifier(copy_id)
+            .set_kms_key_id(kms_key_id.map(String::from))
+            .set_pre_signed_url(pre_signed_url)
+            .copy_tags(true)
+            .send()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to copy {}", snapshot.id), &err)
+                    .operation("CopyDBClusterSnapshot")
+            })?;
+
+        Ok(copy
+            .db_cluster_snapshot()
+            .and_then(|s| s.db_cluster_snapshot_identifier())
+            .unwrap_or(copy_id)
+            .to_string())
+    }
+    // snippet-end:[rust.snapshot_retention.rds.copy]
+
+    async fn share(&self, snapshot: &Snapshot, account: &str) -> Result<(), ScenarioError> {
+        self.0
+            .modify_db_cluster_snapshot_attribute()
+            .db_cluster_snapshot_identifier(&snapshot.id)
+            .attribute_name("restore")
+            .values_to_add(account)
+            .send()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(
+                    format!("Failed to share {} with {account}", snapshot.id),
+                    &err,
+                )
+                .operation("ModifyDBClusterSnapshotAttribute")
+            })?;
+        Ok(())
+    }
+}
+
+impl RdsClusterSnapshots {
+    async fn credentials(&self) -> Result<Credentials, ScenarioError> {
+        self.0
+            .config()
+            .credentials_provider()
+            .ok_or_else(|| ScenarioError::with("The RDS client has no credentials to sign with"))?
+            .provide_credentials()
+            .await
+            .map_err(|err| ScenarioError::with(format!("Failed to load credentials: {err}")))
+    }
+}
+
+/// How long RDS has to start reading a snapshot with a presigned copy request.
+const PRESIGNED_COPY_EXPIRY: Duration = Duration::from_secs(60 * 60);
+
+/// A copy of an encrypted cluster snapshot into another region.
+struct ClusterCopy<'a> {
+    source_region: &'a str,
+    destination_region: &'a str,
+    /// The ARN of the snapshot in the source region.
+    source: &'a str,
+    /// The key to encrypt the copy with, in the destination region.
+    kms_key_id: &'a str,
+}
+
+/// RDS in the destination region reads an encrypted snapshot from the source region with a
+/// CopyDBClusterSnapshot request signed for the source region, passed as `PreSignedUrl`.
+fn presign_cluster_copy(
+    credentials: &Credentials,
+    copy: &ClusterCopy,
+    now: SystemTime,
+) -> Result<String, ScenarioError> {
+    let query = form_urlencoded::Serializer::new(String::new())
+        .append_pair("Action", "CopyDBClusterSnapshot")
+        .append_pair("Version", "2014-10-31")
+        .append_pair("SourceDBClusterSnapshotIdentifier", copy.source)
+        .append_pair("KmsKeyId", copy.kms_key_id)
+        .append_pair("DestinationRegion", copy.destination_region)
+        .finish();
+    let url = format!("https://rds.{}.amazonaws.com/?{query}", copy.source_region);
+
+    let mut settings = SigningSettings::default();
+    settings.signature_location = SignatureLocation::QueryParams;
+    settings.expires_in = Some(PRESIGNED_COPY_EXPIRY);
+    let identity = credentials.clone().into();
+    let params = v4::SigningParams::builder()
+        .identity(&identity)
+        .region(copy.source_region)
+        .name("rds")
+        .time(now)
+        .settings(settings)
+        .build()
+        .map_err(|err| ScenarioError::with(format!("Invalid signing parameters: {err}")))?
+        .into();
+    let request = SignableRequest::new("GET", &url, std::iter::empty(), SignableBody::Bytes(&[]))
+        .map_err(|err| ScenarioError::with(format!("Failed to presign {url}: {err}")))?;
+    let (instructions, _signature) = sign(request, &params)
+        .map_err(|err| ScenarioError::with(format!("Failed to presign {url}: {err}")))?
+        .into_parts();
+
+    let (_headers, signed) = instructions.into_parts();
+    let signed = form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(signed.iter().map(|(name, value)| (*name, value.as_ref())))
+        .finish();
+    Ok(format!("{url}&{signed}"))
+}
+
+#[derive(Clone)]
+pub struct EbsSnapshots(pub aws_sdk_ec2::Client);
+
+#[async_trait]
+impl SnapshotStore for EbsSnapshots {
+    // snippet-start:[rust.snapshot_retention.ebs.list]
+    async fn list(&self, source: &str) -> Result<Vec<Snapshot>, ScenarioError> {
+        let snapshots: Vec<_> = self
+            .0
+            .describe_snapshots()
+            .owner_ids("self")
+            .filters(Filter::builder().name("volume-id").values(source).build())
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to list snapshots of {source}"), &err)
+                    .operation("DescribeSnapshots")
+            })?;
+
+        Ok(snapshots
+            .iter()
+            .map(|snapshot| Snapshot {
+                id: snapshot.snapshot_id().unwrap_or_default().to_string(),
+                arn: None,
+                source_id: snapshot.volume_id().unwrap_or(source).to_string(),
+                created_at: system_time(snapshot.start_time().map(|t| t.secs())),
+                state: snapshot
+                    .state()
+                    .map(|s| s.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                encrypted: snapshot.encrypted().unwrap_or(false),
+                kms_key_id: snapshot.kms_key_id().map(String::from),
+                tags: snapshot
+                    .tags()
+                    .iter()
+                    .filter_map(|tag| Some((tag.key()?.to_string(), tag.value()?.to_string())))
+                    .collect(),
+            })
+            .collect())
+    }
+    // snippet-end:[rust.snapshot_retention.ebs.list]
+
+    async fn delete(&self, snapshot: &Snapshot) -> Result<(), ScenarioError> {
+        self.0
+            .delete_snapshot()
+            .snapshot_id(&snapshot.id)
+            .send()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to delete {}", snapshot.id), &err)
+                    .operation("DeleteSnapshot")
+            })?;
+        Ok(())
+    }
+
+    // snippet-start:[rust.snapshot_retention.ebs.copy]
+    async fn copy_from(
+        &self,
+        snapshot: &Snapshot,
+        source_region: &str,
+        copy_id: &str,
+        kms_key_id: Option<&str>,
+    ) -> Result<String, ScenarioError> {
+        // A copy of an encrypted snapshot must stay encrypted; a key re-encrypts it.
+        let copy = self
+            .0
+            .copy_snapshot()
+            .source_region(source_region)
+            .source_snapshot_id(&snapshot.id)
+            .description(copy_id)
+            .encrypted(snapshot.encrypted || kms_key_id.is_some())
+            .set_kms_key_id(kms_key_id.map(String::from))
+            .send()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(format!("Failed to copy {}", snapshot.id), &err)
+                    .operation("CopySnapshot")
+            })?;
+
+        copy.snapshot_id
+            .ok_or_else(|| ScenarioError::with(format!("Copy of {} has no ID", snapshot.id)))
+    }
+    // snippet-end:[rust.snapshot_retention.ebs.copy]
+
+    async fn share(&self, snapshot: &Snapshot, account: &str) -> Result<(), ScenarioError> {
+        self.0
+            .modify_snapshot_attribute()
+            .snapshot_id(&snapshot.id)
+            .attribute(SnapshotAttributeName::CreateVolumePermission)
+            .operation_type(OperationType::Add)
+            .user_ids(account)
+            .send()
+            .await
+            .map_err(|err| {
+                ScenarioError::from_sdk(
+                    format!("Failed to share {} with {account}", snapshot.id),
+                    &err,
+                )
+                .operation("ModifySnapshotAttribute")
+            })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_presign_cluster_copy() {
+        let credentials = Credentials::new(
+            "ATESTCLIENT",
+            "atestsecretkey",
+            Some("atestsessiontoken".to_string()),
+            None,
+            "test",
+        );
+        let copy = ClusterCopy {
+            source_region: "us-west-2",
+            destination_region: "us-east-1",
+            source: "arn:aws:rds:us-west-2:111122223333:cluster-snapshot:nightly",
+            kms_key_id: "alias/backup",
+        };
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        let url = presign_cluster_copy(&credentials, &copy, now).unwrap();
+        let (endpoint, query) = url.split_once('?').unwrap();
+        assert_eq!(endpoint, "https://rds.us-west-2.amazonaws.com/");
+        let params: Vec<(String, String)> = form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect();
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(param("Action"), Some("CopyDBClusterSnapshot"));
+        assert_eq!(
+            param("SourceDBClusterSnapshotIdentifier"),
+            Some(copy.source)
+        );
+        assert_eq!(param("KmsKeyId"), Some("alias/backup"));
+        assert_eq!(param("DestinationRegion"), Some("us-east-1"));
+        assert_eq!(
+            param("X-Amz-Credential"),
+            Some("ATESTCLIENT/20231114/us-west-2/rds/aws4_request")
+        );
+        assert_eq!(param("X-Amz-Date"), Some("20231114T221320Z"));
+        assert_eq!(param("X-Amz-Expires"), Some("3600"));
+        assert_eq!(param("X-Amz-Security-Token"), Some("atestsessiontoken"));
+        assert!(param("X-Amz-Signature").is_some_and(|sig| sig.len() == 64));
+        assert_eq!(presign_cluster_copy(&credentials, &copy, now).unwrap(), url);
+    }
+}